@@ -1,10 +1,13 @@
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 use gfahandlegraph::{
-    gfa::orientation::Orientation,
-    handle::{Edge, Handle},
-    hashgraph::HashGraph,
+    gfa::{gfa1::GFA, orientation::Orientation},
+    handle::{Edge, Handle, NodeId},
+    handlegraph::AllEdges,
+    hashgraph::{graph::FileType, HashGraph, Node, SequenceRef},
     mutablehandlegraph::*,
     parser::*,
+    pathgraph::PathHandleGraph,
+    util::{dna, ThreadConfig},
 };
 
 /*
@@ -40,18 +43,16 @@ fn mod_graph_from_medium_gfa2() -> bool {
     let mut graph = create_graph_from_medium_gfa2();
 
     let random_node = 15_usize;
-    match graph.remove_handle(random_node) {
-        Err(why) => println!("Error: {}", why),
-        _ => (),
+    if let Err(why) = graph.remove_handle(random_node) {
+        println!("Error: {}", why)
     };
 
     let edge = Edge(
         Handle::new(4046, Orientation::Forward),
         Handle::new(2346, Orientation::Forward),
     );
-    match graph.remove_edge(edge) {
-        Err(why) => println!("Error: {}", why),
-        _ => (),
+    if let Err(why) = graph.remove_edge(edge) {
+        println!("Error: {}", why)
     };
     true
 }
@@ -65,6 +66,274 @@ fn create_graph_from_medium_gfa1() -> HashGraph {
     graph
 }
 
+// a single thread, to measure the line parsing without the noise of the pool
+// with the field regexes:    GFA 10.5 ms, GFA2 18.0 ms
+// with the byte validators:  GFA  6.3 ms, GFA2  8.5 ms
+fn parse_medium_gfa1() -> usize {
+    let mut builder = parser_gfa1::ParserBuilder::all();
+    builder.threads(ThreadConfig::sequential());
+    let gfa = builder
+        .build()
+        .parse_file("./tests/big_files/test.gfa")
+        .unwrap();
+    gfa.segments.len()
+}
+
+fn parse_medium_gfa2() -> usize {
+    let mut builder = parser_gfa2::ParserBuilder::all();
+    builder.threads(ThreadConfig::sequential());
+    let gfa2 = builder
+        .build()
+        .parse_file("./tests/big_files/test.gfa2")
+        .unwrap();
+    gfa2.segments.len()
+}
+
+/// The graph of a parsed file built as before, an edge at a time
+fn build_one_at_a_time(gfa: GFA) -> HashGraph {
+    let mut graph = HashGraph::new();
+    for s in gfa.segments.iter() {
+        graph.create_handle(s.name, &s.sequence).unwrap();
+    }
+    for l in gfa.links.iter() {
+        let left = Handle::new(l.from_segment, l.from_orient);
+        let right = Handle::new(l.to_segment, l.to_orient);
+        graph.create_edge(Edge(left, right)).unwrap();
+    }
+    for p in gfa.paths.iter() {
+        let path = graph.create_path_handle(&p.path_name, false);
+        for (id, orient) in p.iter() {
+            graph.append_step(&path, Handle::new(id, orient)).unwrap();
+        }
+    }
+    graph
+}
+
+/// The graph of a parsed file built in a single batch
+fn build_bulk_edit(gfa: GFA) -> HashGraph {
+    let mut graph = HashGraph::new();
+    let mut edit = graph.bulk_edit();
+    for s in gfa.segments.iter() {
+        edit.add_node(s.name, &s.sequence);
+    }
+    for l in gfa.links.iter() {
+        let left = Handle::new(l.from_segment, l.from_orient);
+        let right = Handle::new(l.to_segment, l.to_orient);
+        edit.add_edge(Edge(left, right));
+    }
+    for p in gfa.paths.iter() {
+        let steps: Vec<Handle> = p
+            .iter()
+            .map(|(id, orient)| Handle::new(id, orient))
+            .collect();
+        edit.add_path(&p.path_name, &steps);
+    }
+    edit.commit().unwrap();
+    graph
+}
+
+/// The nodes of a parsed file, with every sequence in its own
+/// allocation as before the short ones were kept in the node
+fn nodes_on_the_heap(gfa: &GFA) -> HashGraph {
+    let mut graph = HashGraph::with_capacity(gfa.segments.len());
+    for s in gfa.segments.iter() {
+        let sequence = SequenceRef::Inline(s.sequence.clone());
+        graph
+            .graph
+            .insert(s.name.into(), Node::with_sequence(sequence));
+    }
+    graph
+}
+
+/// The nodes of a parsed file, as they're added now
+fn nodes_in_place(gfa: &GFA) -> HashGraph {
+    let mut graph = HashGraph::with_capacity(gfa.segments.len());
+    for s in gfa.segments.iter() {
+        graph.graph.insert(s.name.into(), Node::new(&s.sequence));
+    }
+    graph
+}
+
+fn construction_benchmark(c: &mut Criterion) {
+    /*
+    BUILD MID GFA ONE AT A TIME     time:   [7.3834 ms 7.6145 ms 7.8731 ms] (1 core)
+    BUILD MID GFA PARALLEL          time:   [9.1571 ms 9.3831 ms 9.6257 ms] (1 core)
+    on a single core the edges are added one at a time by both, the
+    parallel build also keeps the names and the tags of the lines
+
+    BUILD MID GFA ONE AT A TIME     time:   [18.395 ms 18.585 ms 18.778 ms] (1 core)
+    BUILD MID GFA BULK EDIT         time:   [15.946 ms 16.151 ms 16.419 ms] (1 core)
+    measured in the same run, on a slower machine than the ones above:
+    the batch skips the checks of each step and the id of each new path
+    */
+    let gfa = parser_gfa1::ParserBuilder::all()
+        .build()
+        .parse_file("./tests/big_files/test.gfa")
+        .unwrap();
+    c.bench_function("BUILD MID GFA ONE AT A TIME", |b| {
+        b.iter(|| build_one_at_a_time(gfa.clone()))
+    });
+    c.bench_function("BUILD MID GFA PARALLEL", |b| {
+        b.iter(|| {
+            HashGraph::new()
+                .create_graph(FileType::GFA(gfa.clone()))
+                .unwrap()
+        })
+    });
+    c.bench_function("BUILD MID GFA BULK EDIT", |b| {
+        b.iter(|| build_bulk_edit(gfa.clone()))
+    });
+    /*
+    BUILD MID GFA NODES HEAP        time:   [394.07 us 417.61 us 440.05 us]
+    BUILD MID GFA NODES IN PLACE    time:   [146.40 us 149.55 us 153.18 us]
+    4036 of the 4058 sequences have at most 15 bases, and aren't
+    allocated anymore: see short_sequences_are_kept_in_the_node in
+    tests/memory.rs for the bytes
+    */
+    c.bench_function("BUILD MID GFA NODES HEAP", |b| {
+        b.iter(|| nodes_on_the_heap(&gfa))
+    });
+    c.bench_function("BUILD MID GFA NODES IN PLACE", |b| {
+        b.iter(|| nodes_in_place(&gfa))
+    });
+}
+
+/// A sequence of bases of the given length, always the same, with an N
+/// every 10 kb
+fn dna_sequence(len: usize) -> Vec<u8> {
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    (0..len)
+        .map(|ix| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            if ix % 10_000 == 9_999 {
+                b'N'
+            } else {
+                b"ACGTacgt"[(state % 8) as usize]
+            }
+        })
+        .collect()
+}
+
+/// The base counting done before the chunked one
+fn naive_gc(seq: &[u8]) -> usize {
+    seq.iter()
+        .filter(|b| matches!(b, b'G' | b'C' | b'g' | b'c'))
+        .count()
+}
+
+fn dna_benchmark(c: &mut Criterion) {
+    /*
+                     1 KB        100 KB      10 MB
+    REV COMP OLD     290.85 ns   27.735 us   3.1307 ms
+    REV COMP NEW     294.09 ns   24.648 us   2.8450 ms
+    COMPLEMENT NEW   205.47 ns   13.375 us   1.6462 ms
+    GC OLD           929.52 ns   85.043 us   8.8410 ms
+    BASE COUNTS NEW  153.16 ns   14.538 us   1.5671 ms
+
+    on the default x86-64 target (SSE2 only) reversing the bytes costs
+    as much as the vectorized complement saves
+    */
+    for (name, len) in [("1KB", 1_000), ("100KB", 100_000), ("10MB", 10_000_000)].iter() {
+        let seq = dna_sequence(*len);
+        c.bench_function(&format!("REV COMP OLD {}", name), |b| {
+            b.iter(|| dna::rev_comp(&seq))
+        });
+        c.bench_function(&format!("REV COMP NEW {}", name), |b| {
+            b.iter(|| dna::rev_comp_slice(&seq))
+        });
+        c.bench_function(&format!("COMPLEMENT NEW {}", name), |b| {
+            b.iter(|| dna::complement(&seq))
+        });
+        c.bench_function(&format!("GC OLD {}", name), |b| b.iter(|| naive_gc(&seq)));
+        c.bench_function(&format!("BASE COUNTS NEW {}", name), |b| {
+            b.iter(|| dna::base_counts(&seq))
+        });
+    }
+}
+
+/// A graph of 1000 nodes and a path with a step on each of them
+fn graph_with_a_path() -> (HashGraph, i64) {
+    let mut graph = HashGraph::new();
+    for id in 1..=1_000u64 {
+        graph.create_handle(id, b"ACGT").unwrap();
+    }
+    let path = graph.create_path_handle(b"x", false);
+    for id in 1..=1_000u64 {
+        graph.append_step(&path, Handle::pack(id, false)).unwrap();
+    }
+    (graph, path)
+}
+
+/// Prepend 100k steps to the path, going around the nodes
+fn prepend_steps(mut graph: HashGraph, path: i64) -> usize {
+    for ix in 0..100_000u64 {
+        graph
+            .prepend_step(&path, Handle::pack(ix % 1_000 + 1, false))
+            .unwrap();
+    }
+    graph.step_count(&path)
+}
+
+fn path_benchmark(c: &mut Criterion) {
+    /*
+    PREPEND 100K STEPS   6.6940 ms
+
+    moving the index of every step for each prepend took 50.5 s (one
+    run, too slow to sample)
+    */
+    let (graph, path) = graph_with_a_path();
+    c.bench_function("PREPEND 100K STEPS", |b| {
+        b.iter(|| prepend_steps(graph.clone(), path))
+    });
+}
+
+/// Whether an edge touches node 15 of the medium graph
+fn touches_node(Edge(l, r): &Edge) -> bool {
+    let node = NodeId::from(15);
+    l.id() == node || r.id() == node
+}
+
+/// Remove the edges of node 15 calling remove_edge on each of them
+fn remove_edges_one_at_a_time(mut graph: HashGraph) -> usize {
+    let to_remove: Vec<Edge> = graph.edges().filter(touches_node).collect();
+    for edge in to_remove.iter() {
+        if let Err(why) = graph.remove_edge(*edge) {
+            println!("Error: {}", why)
+        }
+    }
+    to_remove.len()
+}
+
+fn edge_removal_benchmark(c: &mut Criterion) {
+    /*
+    REMOVE EDGES OF NODE ONE AT A TIME
+                            time:   [446.74 us 466.73 us 489.33 us]
+
+    REMOVE EDGES OF NODE IN A SINGLE PASS
+                            time:   [480.14 us 506.78 us 533.07 us]
+
+    for the few edges of a single node the pass over every edge costs
+    about as much as the removals it saves
+    */
+    let graph = create_graph_from_medium_gfa2();
+    c.bench_function("REMOVE EDGES OF NODE ONE AT A TIME", |b| {
+        b.iter_batched(
+            || graph.clone(),
+            remove_edges_one_at_a_time,
+            BatchSize::LargeInput,
+        )
+    });
+    c.bench_function("REMOVE EDGES OF NODE IN A SINGLE PASS", |b| {
+        b.iter_batched(
+            || graph.clone(),
+            |mut graph| graph.remove_edges_where(touches_node),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
 /*
 fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("big GFA", |b| b.iter(|| create_graph_from_big_gfa1()));
@@ -98,15 +367,24 @@ fn criterion_benchmark(c: &mut Criterion) {
       2 (2.00%) high mild
     */
     c.bench_function("CREATE GRAPH FROM MID GFA", |b| {
-        b.iter(|| create_graph_from_medium_gfa1())
+        b.iter(create_graph_from_medium_gfa1)
     });
     c.bench_function("CREATE GRAPH FROM MID GFA2", |b| {
-        b.iter(|| create_graph_from_medium_gfa2())
+        b.iter(create_graph_from_medium_gfa2)
     });
     c.bench_function("MODIFY GRAPH FROM MID GFA2", |b| {
-        b.iter(|| mod_graph_from_medium_gfa2())
+        b.iter(mod_graph_from_medium_gfa2)
     });
+    c.bench_function("PARSE MID GFA", |b| b.iter(parse_medium_gfa1));
+    c.bench_function("PARSE MID GFA2", |b| b.iter(parse_medium_gfa2));
 }
 
-criterion_group!(benches, criterion_benchmark);
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    construction_benchmark,
+    dna_benchmark,
+    path_benchmark,
+    edge_removal_benchmark
+);
 criterion_main!(benches);
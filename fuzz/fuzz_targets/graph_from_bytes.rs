@@ -0,0 +1,7 @@
+// seeded with the inputs of tests/adversarial
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    gfahandlegraph::fuzzing::graph_from_bytes(data);
+});
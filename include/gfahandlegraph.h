@@ -0,0 +1,67 @@
+/*
+ * C ABI of gfahandlegraph, built with the `ffi` cargo feature
+ * (cargo build --release --features ffi).
+ *
+ * A handle is the node id shifted left by one, with the lowest bit set
+ * for the reverse strand; a path is its position, from 0 to
+ * gfa_path_count() - 1, among the paths sorted by id.
+ *
+ * Every buffer returned is a new allocation owned by the caller, to
+ * release with the matching gfa_*_free function. No function unwinds
+ * into the caller: a failure returns NULL (or 0, or false) and leaves its
+ * message to gfa_last_error().
+ */
+#ifndef GFAHANDLEGRAPH_H
+#define GFAHANDLEGRAPH_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct GraphHandle GraphHandle;
+
+#define GFA_LEFT 0
+#define GFA_RIGHT 1
+
+/* Parse a GFA or GFA2 file, by its extension. On failure return NULL and,
+ * if err isn't NULL, set *err to the message (free it with
+ * gfa_string_free); on success *err is set to NULL. */
+GraphHandle *gfa_graph_load(const char *path, char **err);
+void gfa_graph_free(GraphHandle *graph);
+
+size_t gfa_node_count(const GraphHandle *graph);
+bool gfa_has_node(const GraphHandle *graph, uint64_t node_id);
+
+/* The sequence of a handle, reverse complemented on the reverse strand,
+ * not NUL terminated. Free it with gfa_bytes_free(seq, *out_len). It's
+ * NULL when out_len is NULL, as the lists below without out_count. */
+const uint8_t *gfa_sequence(const GraphHandle *graph, uint64_t handle, size_t *out_len);
+
+/* The neighbors of a handle, dir is GFA_LEFT or GFA_RIGHT. Free them with
+ * gfa_handles_free(handles, *out_count). */
+uint64_t *gfa_neighbors(const GraphHandle *graph, uint64_t handle, uint8_t dir,
+                        size_t *out_count);
+
+size_t gfa_path_count(const GraphHandle *graph);
+/* Free the name with gfa_string_free. */
+char *gfa_path_name(const GraphHandle *graph, size_t idx);
+/* Free the steps with gfa_handles_free(steps, *out_count). */
+uint64_t *gfa_path_steps(const GraphHandle *graph, size_t idx, size_t *out_count);
+
+void gfa_bytes_free(const uint8_t *bytes, size_t len);
+void gfa_handles_free(uint64_t *handles, size_t count);
+void gfa_string_free(char *s);
+
+/* The message of the last failed call on this thread (free it with
+ * gfa_string_free), or NULL; it's returned only once. */
+char *gfa_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GFAHANDLEGRAPH_H */
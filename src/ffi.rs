@@ -0,0 +1,342 @@
+//! A C ABI for the read-only queries on an HashGraph, built with the
+//! ```ffi``` feature and declared in ```include/gfahandlegraph.h```.\
+//! A handle crosses the boundary as the integer of
+//! [`Handle::as_integer`](../handle/struct.Handle.html#method.as_integer)
+//! (the node id shifted left by one, with the lowest bit set for the
+//! reverse strand), a path as its position among the paths sorted by id.\
+//! Every buffer returned to the caller is a new allocation, owned by the
+//! caller and released with the matching ```gfa_*_free``` function, so
+//! nothing returned points inside the graph. No function unwinds into
+//! the caller: a panic, as a failed query, returns a null pointer (or 0,
+//! or false) and leaves its message to
+//! [`gfa_last_error`](fn.gfa_last_error.html)
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use crate::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    hashgraph::{HashGraph, Path, PathId},
+    parser::parse_file_to_graph,
+};
+
+/// A graph loaded by [`gfa_graph_load`](fn.gfa_graph_load.html), opaque
+/// to the caller
+pub struct GraphHandle {
+    graph: HashGraph,
+    /// The path ids, sorted, so a path is found by its position
+    paths: Vec<PathId>,
+}
+
+impl GraphHandle {
+    fn path(&self, idx: usize) -> Result<&Path, String> {
+        self.paths
+            .get(idx)
+            .and_then(|id| self.graph.paths.get(id))
+            .ok_or_else(|| {
+                format!(
+                    "path {} doesn't exist, the graph has {} paths",
+                    idx,
+                    self.paths.len()
+                )
+            })
+    }
+
+    fn handle(&self, handle: u64) -> Result<Handle, String> {
+        let handle = Handle::from_integer(handle);
+        if self.graph.has_node(handle.id()) {
+            Ok(handle)
+        } else {
+            Err(format!("node {} doesn't exist", handle.id()))
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn to_c_string(message: String) -> CString {
+    CString::new(message.replace('\0', " ")).unwrap()
+}
+
+fn set_last_error(message: String) {
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(to_c_string(message)));
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+    {
+        Some(message) => format!("panic: {}", message),
+        None => "panic".to_string(),
+    }
+}
+
+/// Run ```f``` at the boundary: an error or a panic becomes the last
+/// error, and ```failed``` is returned
+fn guard<T>(failed: T, f: impl FnOnce() -> Result<T, String>) -> T {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(message)) => {
+            set_last_error(message);
+            failed
+        }
+        Err(payload) => {
+            set_last_error(panic_message(payload));
+            failed
+        }
+    }
+}
+
+unsafe fn graph_ref<'a>(graph: *const GraphHandle) -> Result<&'a GraphHandle, String> {
+    graph
+        .as_ref()
+        .ok_or_else(|| "the graph is null".to_string())
+}
+
+/// The length of a buffer can't be returned through a null pointer, and
+/// without it the buffer couldn't be released, so the query fails first
+unsafe fn out_ref<'a>(out_len: *mut usize, name: &str) -> Result<&'a mut usize, String> {
+    out_len.as_mut().ok_or_else(|| format!("{} is null", name))
+}
+
+/// Hand a buffer to the caller, writing its length to ```out_len```
+fn into_raw<T>(items: Vec<T>, out_len: &mut usize) -> *mut T {
+    let items = items.into_boxed_slice();
+    *out_len = items.len();
+    Box::into_raw(items) as *mut T
+}
+
+unsafe fn free_raw<T>(items: *mut T, len: usize) {
+    if !items.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(items, len)));
+    }
+}
+
+/// Function that parses a GFA or GFA2 file (from its extension) into a
+/// new graph, to release with [`gfa_graph_free`](fn.gfa_graph_free.html).\
+/// If it fails it returns null and, when ```err``` isn't null, writes to
+/// it the message of the error, to release with
+/// [`gfa_string_free`](fn.gfa_string_free.html); on success ```*err```
+/// is set to null
+/// # Safety
+/// ```path``` must be null or a NUL terminated string, ```err``` null or
+/// valid for a write
+#[no_mangle]
+pub unsafe extern "C" fn gfa_graph_load(
+    path: *const c_char,
+    err: *mut *mut c_char,
+) -> *mut GraphHandle {
+    let loaded = catch_unwind(|| {
+        if path.is_null() {
+            return Err("the path is null".to_string());
+        }
+        let path = CStr::from_ptr(path)
+            .to_str()
+            .map_err(|e| format!("the path isn't valid UTF-8: {}", e))?;
+        let graph = parse_file_to_graph(path).map_err(|e| e.to_string())?;
+        let mut paths: Vec<PathId> = graph.paths.keys().copied().collect();
+        paths.sort();
+        Ok(Box::new(GraphHandle { graph, paths }))
+    })
+    .unwrap_or_else(|payload| Err(panic_message(payload)));
+
+    match loaded {
+        Ok(graph) => {
+            if !err.is_null() {
+                *err = ptr::null_mut();
+            }
+            Box::into_raw(graph)
+        }
+        Err(message) => {
+            if !err.is_null() {
+                *err = to_c_string(message).into_raw();
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Function that releases a graph, doing nothing with null
+/// # Safety
+/// ```graph``` must be null or returned by
+/// [`gfa_graph_load`](fn.gfa_graph_load.html), and not released yet
+#[no_mangle]
+pub unsafe extern "C" fn gfa_graph_free(graph: *mut GraphHandle) {
+    if !graph.is_null() {
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(graph))));
+    }
+}
+
+/// Function that returns the number of nodes, or 0 for a null graph
+/// # Safety
+/// ```graph``` must be null or a graph not released yet
+#[no_mangle]
+pub unsafe extern "C" fn gfa_node_count(graph: *const GraphHandle) -> usize {
+    guard(0, || Ok(graph_ref(graph)?.graph.node_count()))
+}
+
+/// Function that checks if the node with id ```node_id``` exists
+/// # Safety
+/// ```graph``` must be null or a graph not released yet
+#[no_mangle]
+pub unsafe extern "C" fn gfa_has_node(graph: *const GraphHandle, node_id: u64) -> bool {
+    guard(false, || {
+        Ok(graph_ref(graph)?.graph.has_node(NodeId::from(node_id)))
+    })
+}
+
+/// Function that returns the sequence of a handle, reverse complemented
+/// for the reverse strand, writing its length to ```out_len```.\
+/// The buffer isn't NUL terminated, and it's released with
+/// [`gfa_bytes_free`](fn.gfa_bytes_free.html); it's null if the node
+/// doesn't exist or ```out_len``` is null
+/// # Safety
+/// ```graph``` must be null or a graph not released yet, ```out_len```
+/// null or valid for a write
+#[no_mangle]
+pub unsafe extern "C" fn gfa_sequence(
+    graph: *const GraphHandle,
+    handle: u64,
+    out_len: *mut usize,
+) -> *const u8 {
+    guard(ptr::null(), || {
+        let out_len = out_ref(out_len, "out_len")?;
+        let graph = graph_ref(graph)?;
+        let sequence = graph.graph.sequence(graph.handle(handle)?);
+        Ok(into_raw(sequence, out_len) as *const u8)
+    })
+}
+
+/// Function that returns the neighbors of a handle on its left
+/// (```dir``` 0) or on its right (```dir``` 1), writing their number to
+/// ```out_count```.\
+/// The handles are released with
+/// [`gfa_handles_free`](fn.gfa_handles_free.html); they're null if the
+/// node doesn't exist, the direction isn't valid or ```out_count``` is
+/// null
+/// # Safety
+/// ```graph``` must be null or a graph not released yet, ```out_count```
+/// null or valid for a write
+#[no_mangle]
+pub unsafe extern "C" fn gfa_neighbors(
+    graph: *const GraphHandle,
+    handle: u64,
+    dir: u8,
+    out_count: *mut usize,
+) -> *mut u64 {
+    guard(ptr::null_mut(), || {
+        let out_count = out_ref(out_count, "out_count")?;
+        let graph = graph_ref(graph)?;
+        let dir = match dir {
+            0 => Direction::Left,
+            1 => Direction::Right,
+            _ => return Err(format!("direction {} isn't 0 (left) or 1 (right)", dir)),
+        };
+        let neighbors: Vec<u64> = graph
+            .graph
+            .neighbors(graph.handle(handle)?, dir)
+            .map(Handle::as_integer)
+            .collect();
+        Ok(into_raw(neighbors, out_count))
+    })
+}
+
+/// Function that returns the number of paths, or 0 for a null graph
+/// # Safety
+/// ```graph``` must be null or a graph not released yet
+#[no_mangle]
+pub unsafe extern "C" fn gfa_path_count(graph: *const GraphHandle) -> usize {
+    guard(0, || Ok(graph_ref(graph)?.paths.len()))
+}
+
+/// Function that returns the name of the path at position ```idx```, as
+/// a NUL terminated string released with
+/// [`gfa_string_free`](fn.gfa_string_free.html), or null if there isn't
+/// such a path
+/// # Safety
+/// ```graph``` must be null or a graph not released yet
+#[no_mangle]
+pub unsafe extern "C" fn gfa_path_name(graph: *const GraphHandle, idx: usize) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let path = graph_ref(graph)?.path(idx)?;
+        CString::new(path.name.to_vec())
+            .map(CString::into_raw)
+            .map_err(|_| format!("the name of path {} contains a NUL", idx))
+    })
+}
+
+/// Function that returns the steps of the path at position ```idx```, as
+/// handles, writing their number to ```out_count```.\
+/// The handles are released with
+/// [`gfa_handles_free`](fn.gfa_handles_free.html); they're null if
+/// there isn't such a path or ```out_count``` is null
+/// # Safety
+/// ```graph``` must be null or a graph not released yet, ```out_count```
+/// null or valid for a write
+#[no_mangle]
+pub unsafe extern "C" fn gfa_path_steps(
+    graph: *const GraphHandle,
+    idx: usize,
+    out_count: *mut usize,
+) -> *mut u64 {
+    guard(ptr::null_mut(), || {
+        let out_count = out_ref(out_count, "out_count")?;
+        let path = graph_ref(graph)?.path(idx)?;
+        let steps: Vec<u64> = path.nodes.iter().map(|h| h.as_integer()).collect();
+        Ok(into_raw(steps, out_count))
+    })
+}
+
+/// Function that releases a sequence returned by
+/// [`gfa_sequence`](fn.gfa_sequence.html), doing nothing with null
+/// # Safety
+/// ```bytes``` must be null or returned by ```gfa_sequence``` with the
+/// length ```len```, and not released yet
+#[no_mangle]
+pub unsafe extern "C" fn gfa_bytes_free(bytes: *const u8, len: usize) {
+    free_raw(bytes as *mut u8, len)
+}
+
+/// Function that releases the handles returned by
+/// [`gfa_neighbors`](fn.gfa_neighbors.html) or
+/// [`gfa_path_steps`](fn.gfa_path_steps.html), doing nothing with null
+/// # Safety
+/// ```handles``` must be null or returned with the number ```count```,
+/// and not released yet
+#[no_mangle]
+pub unsafe extern "C" fn gfa_handles_free(handles: *mut u64, count: usize) {
+    free_raw(handles, count)
+}
+
+/// Function that releases a string returned by this module, doing
+/// nothing with null
+/// # Safety
+/// ```s``` must be null or a string returned by this module, and not
+/// released yet
+#[no_mangle]
+pub unsafe extern "C" fn gfa_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Function that takes the message of the last failed call on this
+/// thread, to release with [`gfa_string_free`](fn.gfa_string_free.html),
+/// or null if there isn't one; the next call returns null until another
+/// call fails
+#[no_mangle]
+pub extern "C" fn gfa_last_error() -> *mut c_char {
+    LAST_ERROR
+        .try_with(|last| last.borrow_mut().take())
+        .ok()
+        .flatten()
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
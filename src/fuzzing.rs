@@ -0,0 +1,63 @@
+//! The entry points of the fuzz targets in ```fuzz/```, built only by
+//! ```cargo fuzz``` (that sets ```--cfg fuzzing```).\
+//! Each one runs a parser on arbitrary bytes and uses what it returns
+//! the way the graph does: it can fail, it never panics; a crash found is
+//! minimized and added to ```tests/adversarial```
+use std::path::PathBuf;
+
+use crate::gfa::{gfa1::Line as Line1, gfa2::Line as Line2};
+use crate::parser::{parse_file_to_graph, GFA2Parser, GFAParser};
+
+/// Parse ```bytes``` as a line of a GFA file
+pub fn gfa1_line(bytes: &[u8]) {
+    match GFAParser::new().parse_gfa_line(bytes) {
+        Ok(Line1::Path(p)) => {
+            p.iter().count();
+            p.to_string();
+        }
+        Ok(Line1::Segment(s)) => {
+            s.to_string();
+        }
+        Ok(Line1::Link(l)) => {
+            l.to_string();
+        }
+        _ => (),
+    }
+}
+
+/// Parse ```bytes``` as a line of a GFA2 file
+pub fn gfa2_line(bytes: &[u8]) {
+    match GFA2Parser::new().parse_gfa_line(bytes) {
+        Ok(Line2::Edge(e)) => {
+            e.references();
+            e.to_string();
+        }
+        Ok(Line2::GroupO(o)) => {
+            o.iter().count();
+            o.to_string();
+        }
+        Ok(Line2::Segment(s)) => {
+            s.to_string();
+        }
+        _ => (),
+    }
+}
+
+/// Parse ```bytes``` as a GFA, then as a GFA2 file, into a graph
+pub fn graph_from_bytes(bytes: &[u8]) {
+    for extension in ["gfa", "gfa2"] {
+        // the parser reads a file, one for each fuzzing process
+        let path: PathBuf = std::env::temp_dir().join(format!(
+            "gfahandlegraph-fuzz-{}.{}",
+            std::process::id(),
+            extension
+        ));
+        if std::fs::write(&path, bytes).is_err() {
+            return;
+        }
+        if let Ok(graph) = parse_file_to_graph(&path) {
+            graph.to_string();
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,271 @@
+//! Minimal support for the
+//! [GAF](https://github.com/lh3/gfatools/blob/master/doc/rGFA.md#the-graph-alignment-format-gaf)
+//! (Graph Alignment Format), used to resolve alignments against a
+//! [`HashGraph`](../hashgraph/graph/struct.HashGraph.html)
+use bstr::{BString, ByteSlice};
+use std::io::BufRead;
+
+use crate::{
+    gfa::orientation::Orientation,
+    handle::Handle,
+    handlegraph::{GraphError, HandleSequences},
+    hashgraph::HashGraph,
+    parser::{ParseError, ParseFieldError, ParserFieldResult},
+};
+
+/// A single line of a GAF file
+/// ```ignore
+/// pub struct GafRecord {
+///     pub query_name: BString,
+///     pub query_len: usize,
+///     pub query_start: usize,
+///     pub query_end: usize,
+///     pub strand: Orientation,
+///     pub path: BString,
+///     pub path_len: usize,
+///     pub path_start: usize,
+///     pub path_end: usize,
+///     pub matches: usize,
+///     pub block_len: usize,
+///     pub mapping_quality: u8,
+///     pub tags: Vec<BString>,
+/// }
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct GafRecord {
+    pub query_name: BString,
+    pub query_len: usize,
+    pub query_start: usize,
+    pub query_end: usize,
+    pub strand: Orientation,
+    pub path: BString,
+    pub path_len: usize,
+    pub path_start: usize,
+    pub path_end: usize,
+    pub matches: usize,
+    pub block_len: usize,
+    /// 255 when the mapping quality is missing
+    pub mapping_quality: u8,
+    pub tags: Vec<BString>,
+}
+
+#[inline]
+fn next_field<'a, I>(input: &mut I, field: &'static str) -> ParserFieldResult<&'a [u8]>
+where
+    I: Iterator<Item = &'a [u8]>,
+{
+    match input.next() {
+        Some(f) if !f.is_empty() => Ok(f),
+        Some(_) => Err(ParseFieldError::InvalidField(field)),
+        None => Err(ParseFieldError::MissingFields),
+    }
+}
+
+#[inline]
+fn parse_usize<'a, I>(input: &mut I, field: &'static str) -> ParserFieldResult<usize>
+where
+    I: Iterator<Item = &'a [u8]>,
+{
+    next_field(input, field)?
+        .to_str()?
+        .parse::<usize>()
+        .map_err(|_| ParseFieldError::InvalidField(field))
+}
+
+impl GafRecord {
+    #[inline]
+    pub fn parse_line<'a, I>(mut input: I) -> ParserFieldResult<Self>
+    where
+        I: Iterator<Item = &'a [u8]>,
+    {
+        let query_name = next_field(&mut input, "Query name")?.into();
+        let query_len = parse_usize(&mut input, "Query length")?;
+        let query_start = parse_usize(&mut input, "Query start")?;
+        let query_end = parse_usize(&mut input, "Query end")?;
+        let strand = Orientation::parse_error(Orientation::from_bytes_plus_minus(next_field(
+            &mut input, "Strand",
+        )?))?;
+        let path = next_field(&mut input, "Path")?.into();
+        let path_len = parse_usize(&mut input, "Path length")?;
+        let path_start = parse_usize(&mut input, "Path start")?;
+        let path_end = parse_usize(&mut input, "Path end")?;
+        let matches = parse_usize(&mut input, "Residue matches")?;
+        let block_len = parse_usize(&mut input, "Block length")?;
+        let mapping_quality = next_field(&mut input, "Mapping quality")?
+            .to_str()?
+            .parse::<u8>()
+            .map_err(|_| ParseFieldError::InvalidField("Mapping quality"))?;
+        let tags = input.map(BString::from).collect();
+        Ok(GafRecord {
+            query_name,
+            query_len,
+            query_start,
+            query_end,
+            strand,
+            path,
+            path_len,
+            path_start,
+            path_end,
+            matches,
+            block_len,
+            mapping_quality,
+            tags,
+        })
+    }
+
+    /// Split the path field in its segments, each paired with its orientation.\
+    /// A path without any ```>``` or ```<``` marker is a single stable-name
+    /// segment, traversed forward
+    /// # Example
+    /// ```ignore
+    /// // path: ">12<13>14"
+    /// // [("12", Forward), ("13", Backward), ("14", Forward)]
+    /// ```
+    pub fn path_segments(&self) -> Vec<(&[u8], Orientation)> {
+        let path: &[u8] = self.path.as_ref();
+        if !path.starts_with(b">") && !path.starts_with(b"<") {
+            return vec![(path, Orientation::Forward)];
+        }
+        let mut segments = vec![];
+        let mut start = 0;
+        for i in 1..=path.len() {
+            if i == path.len() || path[i] == b'>' || path[i] == b'<' {
+                let orient = Orientation::from_bytes_gt_ln(&path[start..start + 1]).unwrap();
+                segments.push((&path[start + 1..i], orient));
+                start = i;
+            }
+        }
+        segments
+    }
+}
+
+/// Function that parses a single tab-separated GAF line
+/// # Example
+/// ```ignore
+/// let record = parse_gaf_line(b"read1\t10\t0\t10\t+\t>11<12\t11\t0\t10\t10\t10\t60").unwrap();
+/// ```
+pub fn parse_gaf_line(line: &[u8]) -> Result<GafRecord, ParseError> {
+    if line.is_empty() {
+        return Err(ParseError::EmptyLine);
+    }
+    GafRecord::parse_line(line.split_str(b"\t")).map_err(|err| ParseError::invalid_line(err, line))
+}
+
+/// Function that lazily parses every non-empty line of a GAF reader
+/// # Example
+/// ```ignore
+/// let file = BufReader::new(File::open("./alignments.gaf").unwrap());
+/// for record in read_gaf(file) {
+///     println!("{:?}", record);
+/// }
+/// ```
+pub fn read_gaf<R: BufRead>(reader: R) -> impl Iterator<Item = Result<GafRecord, ParseError>> {
+    use bstr::io::BufReadExt;
+
+    crate::parser::until_read_error(reader.byte_lines())
+        .filter(|line| !matches!(line, Ok(l) if l.is_empty()))
+        .map(|line| parse_gaf_line(&line?))
+}
+
+/// Function that resolves the path of a GAF record to the handles of the graph,
+/// with the segment names of the file the graph was parsed from (or the
+/// node ids of the nodes without one), as
+/// [`node_id_of_name`](../hashgraph/graph/struct.HashGraph.html#method.node_id_of_name)
+/// # Example
+/// ```ignore
+/// // path: ">11<12"
+/// let handles = resolve_gaf_path(&graph, &record).unwrap();
+/// // [Handle::new(11, Orientation::Forward), Handle::new(12, Orientation::Backward)]
+/// ```
+pub fn resolve_gaf_path(graph: &HashGraph, record: &GafRecord) -> Result<Vec<Handle>, GraphError> {
+    record
+        .path_segments()
+        .into_iter()
+        .map(|(name, orient)| {
+            let node = graph
+                .node_id_of_name(name)
+                .ok_or_else(|| GraphError::NodeNotExist(name.to_str_lossy().into_owned()))?;
+            Ok(Handle::pack(node, orient == Orientation::Backward))
+        })
+        .collect()
+}
+
+/// Function that returns the sequence the record aligns to, the bases
+/// from ```path_start``` to ```path_end``` of the sequence spelled by its
+/// path, reverse complementing the segments traversed backward.\
+/// Fails with a ```PositionNotFound``` if the range isn't on the path
+/// # Example
+/// ```ignore
+/// // path: ">11<12", from 2 to 8
+/// let seq = gaf_path_sequence(&graph, &record).unwrap();
+/// // CTT + CCT of ACCTT + CCTTGA
+/// ```
+pub fn gaf_path_sequence(graph: &HashGraph, record: &GafRecord) -> Result<Vec<u8>, GraphError> {
+    let handles = resolve_gaf_path(graph, record)?;
+    let sequence: Vec<u8> = handles
+        .into_iter()
+        .flat_map(|h| graph.sequence_iter(h))
+        .collect();
+    match sequence.get(record.path_start..record.path_end) {
+        Some(aligned) => Ok(aligned.to_vec()),
+        None => Err(GraphError::PositionNotFound(
+            format!("{}-{}", record.path_start, record.path_end),
+            record.path.to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gaf_record() {
+        let line = b"read1\t18\t0\t18\t+\t>11<12>13\t18\t0\t18\t18\t18\t60\tNM:i:0\tcg:Z:18M";
+        let record = parse_gaf_line(line).unwrap();
+        assert_eq!(record.query_name, "read1");
+        assert_eq!(record.query_len, 18);
+        assert_eq!(record.strand, Orientation::Forward);
+        assert_eq!(record.path, ">11<12>13");
+        assert_eq!(record.mapping_quality, 60);
+        assert_eq!(
+            record.tags,
+            vec![BString::from("NM:i:0"), "cg:Z:18M".into()]
+        );
+        assert_eq!(
+            record.path_segments(),
+            vec![
+                (&b"11"[..], Orientation::Forward),
+                (&b"12"[..], Orientation::Backward),
+                (&b"13"[..], Orientation::Forward)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_gaf_errors() {
+        assert!(matches!(parse_gaf_line(b""), Err(ParseError::EmptyLine)));
+        assert!(matches!(
+            parse_gaf_line(b"read1\t18\t0"),
+            Err(ParseError::InvalidLine(ParseFieldError::MissingFields, _))
+        ));
+        assert!(matches!(
+            parse_gaf_line(b"read1\t18\t0\t18\t*\t>11\t5\t0\t5\t5\t5\t60"),
+            Err(ParseError::InvalidLine(
+                ParseFieldError::OrientationError,
+                _
+            ))
+        ));
+    }
+
+    #[test]
+    fn read_gaf_lines() {
+        let data =
+            b"r1\t5\t0\t5\t+\t>11\t5\t0\t5\t5\t5\t60\n\nr2\t5\t0\t5\t-\tx\t5\t0\t5\t5\t5\t255\n";
+        let records: Vec<_> = read_gaf(&data[..]).collect();
+        assert_eq!(records.len(), 2);
+        let r2 = records[1].as_ref().unwrap();
+        assert_eq!(r2.strand, Orientation::Backward);
+        assert_eq!(r2.path_segments(), vec![(&b"x"[..], Orientation::Forward)]);
+    }
+}
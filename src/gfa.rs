@@ -1,9 +1,17 @@
+pub mod cigar;
+pub mod extension;
 pub mod gfa1;
 pub mod gfa2;
+pub mod optional;
 pub mod orientation;
 pub mod segment_id;
 
-pub use self::gfa1::*;
-pub use self::gfa2::*;
+pub use self::cigar::{CigarOp, CIGAR};
+pub use self::extension::{CustomRecord, UnknownRecord};
+// the records with the same name in both formats (Line, LineRef, Header
+// and Segment) are only in their own module
+pub use self::gfa1::{Containment, Link, Path, GFA};
+pub use self::gfa2::{Edge, Fragment, Gap, GroupO, GroupU, GFA2};
+pub use self::optional::OptField;
 pub use self::orientation::*;
 pub use self::segment_id::*;
@@ -0,0 +1,201 @@
+/// This file provides the CIGAR strings of the overlaps, as the ones of
+/// the links and of the paths of GFA1 and the alignments of GFA2
+use crate::parser::ParseFieldError;
+use std::fmt;
+
+/// The operations of a CIGAR, the reference being the first segment and
+/// the query the second one
+/// ```ignore
+/// pub enum CigarOp {
+///     Match,    // M
+///     Ins,      // I
+///     Del,      // D
+///     Skip,     // N
+///     SoftClip, // S
+///     HardClip, // H
+///     Pad,      // P
+///     Equal,    // =
+///     Diff,     // X
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CigarOp {
+    Match,
+    Ins,
+    Del,
+    Skip,
+    SoftClip,
+    HardClip,
+    Pad,
+    Equal,
+    Diff,
+}
+
+impl CigarOp {
+    /// Parse an operation from its character
+    #[inline]
+    pub fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            b'M' => Some(CigarOp::Match),
+            b'I' => Some(CigarOp::Ins),
+            b'D' => Some(CigarOp::Del),
+            b'N' => Some(CigarOp::Skip),
+            b'S' => Some(CigarOp::SoftClip),
+            b'H' => Some(CigarOp::HardClip),
+            b'P' => Some(CigarOp::Pad),
+            b'=' => Some(CigarOp::Equal),
+            b'X' => Some(CigarOp::Diff),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn as_byte(&self) -> u8 {
+        match self {
+            CigarOp::Match => b'M',
+            CigarOp::Ins => b'I',
+            CigarOp::Del => b'D',
+            CigarOp::Skip => b'N',
+            CigarOp::SoftClip => b'S',
+            CigarOp::HardClip => b'H',
+            CigarOp::Pad => b'P',
+            CigarOp::Equal => b'=',
+            CigarOp::Diff => b'X',
+        }
+    }
+
+    /// If the operation covers bases of the reference
+    #[inline]
+    pub fn consumes_ref(&self) -> bool {
+        matches!(
+            self,
+            CigarOp::Match | CigarOp::Del | CigarOp::Skip | CigarOp::Equal | CigarOp::Diff
+        )
+    }
+
+    /// If the operation covers bases of the query
+    #[inline]
+    pub fn consumes_query(&self) -> bool {
+        matches!(
+            self,
+            CigarOp::Match | CigarOp::Ins | CigarOp::SoftClip | CigarOp::Equal | CigarOp::Diff
+        )
+    }
+}
+
+impl fmt::Display for CigarOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_byte() as char)
+    }
+}
+
+/// A CIGAR, as the lengths and the operations it's made of, empty for
+/// the unknown overlap ```*```
+/// ```ignore
+/// pub struct CIGAR(pub Vec<(u32, CigarOp)>);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CIGAR(pub Vec<(u32, CigarOp)>);
+
+impl CIGAR {
+    /// Parse a CIGAR as ```([0-9]+[MIDNSHPX=])+``` or ```*```, failing
+    /// with ```InvalidField("Overlap")``` on anything else, as an
+    /// operation without its length or a length too big for an u32
+    /// # Example
+    /// ```ignore
+    /// let cigar = CIGAR::from_bytes(b"3M1I2M")?;
+    /// assert_eq!(cigar.query_len(), 6);
+    /// ```
+    pub fn from_bytes<T: AsRef<[u8]>>(bytes: T) -> Result<Self, ParseFieldError> {
+        let bytes = bytes.as_ref();
+        let invalid = ParseFieldError::InvalidField("Overlap");
+        if bytes == b"*" {
+            return Ok(CIGAR::default());
+        }
+        if bytes.is_empty() {
+            return Err(invalid);
+        }
+        let mut ops = vec![];
+        let mut count: Option<u32> = None;
+        for b in bytes {
+            if b.is_ascii_digit() {
+                let digit = u32::from(b - b'0');
+                count = Some(
+                    count
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|c| c.checked_add(digit))
+                        .ok_or(invalid.clone())?,
+                );
+                continue;
+            }
+            match (count.take(), CigarOp::from_byte(*b)) {
+                (Some(len), Some(op)) => ops.push((len, op)),
+                _ => return Err(invalid),
+            }
+        }
+        if count.is_some() {
+            return Err(invalid);
+        }
+        Ok(CIGAR(ops))
+    }
+
+    /// If the overlap is unknown (```*```)
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The number of bases of the reference (the first segment) covered
+    /// by the overlap
+    pub fn ref_len(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|(_, op)| op.consumes_ref())
+            .map(|(len, _)| *len as usize)
+            .sum()
+    }
+
+    /// The number of bases of the query (the second segment) covered by
+    /// the overlap
+    pub fn query_len(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|(_, op)| op.consumes_query())
+            .map(|(len, _)| *len as usize)
+            .sum()
+    }
+
+    /// If the overlap is made only of matches (```M``` or ```=```), so
+    /// the 2 segments share the same bases
+    pub fn is_perfect_match(&self) -> bool {
+        !self.is_empty()
+            && self
+                .0
+                .iter()
+                .all(|(_, op)| matches!(op, CigarOp::Match | CigarOp::Equal))
+    }
+}
+
+impl std::str::FromStr for CIGAR {
+    type Err = ParseFieldError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CIGAR::from_bytes(s.as_bytes())
+    }
+}
+
+/// Display writes the CIGAR as it's found in a file, ```*``` if it's
+/// empty
+impl fmt::Display for CIGAR {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "*");
+        }
+        for (len, op) in self.0.iter() {
+            write!(f, "{}{}", len, op)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+/// This file provides the records used to keep the nonstandard lines
+/// (vendor extensions) of a GFA or GFA2 file
+use bstr::BString;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A line with an unknown record type, kept verbatim when the parser
+/// is built with ```preserve_unknown_lines(true)```
+/// ```ignore
+/// pub struct UnknownRecord {
+///     pub prefix: u8,
+///     pub raw: BString,
+///     pub line_number: usize,
+/// }
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
+pub struct UnknownRecord {
+    /// The first byte of the line (the record type)
+    pub prefix: u8,
+    /// The whole line, as found in the file
+    pub raw: BString,
+    /// Line number in the file, starting from 1
+    pub line_number: usize,
+}
+
+impl fmt::Display for UnknownRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+/// A line of a record type registered with ```custom_line```, with the
+/// fields returned by its parser
+/// ```ignore
+/// pub struct CustomRecord {
+///     pub prefix: u8,
+///     pub fields: Vec<BString>,
+///     pub raw: BString,
+///     pub line_number: usize,
+/// }
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
+pub struct CustomRecord {
+    /// The record type it was registered with
+    pub prefix: u8,
+    /// The payload, as returned by the parser
+    pub fields: Vec<BString>,
+    /// The whole line, as found in the file
+    pub raw: BString,
+    /// Line number in the file, starting from 1
+    pub line_number: usize,
+}
+
+impl CustomRecord {
+    /// Create the record returned by a custom line parser, the prefix,
+    /// the line and its number are filled in by the file parser
+    #[inline]
+    pub fn new(fields: Vec<BString>) -> Self {
+        CustomRecord {
+            fields,
+            ..Default::default()
+        }
+    }
+}
+
+impl fmt::Display for CustomRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.raw)
+    }
+}
+
+/// The unknown and custom lines, one for each row, in the order
+/// they were found in the file
+pub(crate) fn extension_lines(unknown: &[UnknownRecord], custom: &[CustomRecord]) -> String {
+    let mut lines: Vec<(usize, &BString)> = unknown
+        .iter()
+        .map(|u| (u.line_number, &u.raw))
+        .chain(custom.iter().map(|c| (c.line_number, &c.raw)))
+        .collect();
+    lines.sort();
+    lines
+        .iter()
+        .fold(String::new(), |acc, (_, raw)| acc + &raw.to_string() + "\n")
+}
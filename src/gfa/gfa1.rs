@@ -1,10 +1,14 @@
 /// This file provides the structure to create a GFA Object
+use crate::gfa::extension::*;
+use crate::gfa::optional::*;
 use crate::gfa::orientation::*;
 use crate::gfa::segment_id::*;
 
 use bstr::{BString, ByteSlice};
+use fnv::FnvHasher;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 /// return a GFA object
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
@@ -14,13 +18,27 @@ pub struct GFA {
     pub links: Vec<Link>,
     pub containments: Vec<Containment>,
     pub paths: Vec<Path>,
+    /// Lines with an unknown record type, see
+    /// [`preserve_unknown_lines`](../../parser/parser_gfa1/struct.ParserBuilder.html#method.preserve_unknown_lines)
+    #[serde(default)]
+    pub unknown: Vec<UnknownRecord>,
+    /// Lines of the record types registered with
+    /// [`custom_line`](../../parser/parser_gfa1/struct.ParserBuilder.html#method.custom_line)
+    #[serde(default)]
+    pub custom: Vec<CustomRecord>,
+    /// Number of lines not parsed, as their record type was left out by
+    /// the [`ParserBuilder`](../../parser/parser_gfa1/struct.ParserBuilder.html) of the parser
+    #[serde(default)]
+    pub skipped_lines: usize,
 }
 
+/// The unknown and custom lines are written verbatim after all the
+/// other lines, in the order they were found in the file
 impl fmt::Display for GFA {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{}{}{}{}",
+            "{}{}{}{}{}",
             self.headers
                 .iter()
                 .fold(String::new(), |acc, str| acc + &str.to_string() + "\n"),
@@ -33,6 +51,7 @@ impl fmt::Display for GFA {
             self.paths
                 .iter()
                 .fold(String::new(), |acc, str| acc + &str.to_string() + "\n"),
+            extension_lines(&self.unknown, &self.custom),
         )
     }
 }
@@ -99,13 +118,17 @@ pub enum LineRef<'a> {
 impl GFA {
     /// Insert a GFA line (wrapped in the Line enum) into an existing
     /// GFA. Simply pushes it into the corresponding Vec in the GFA,
-    /// or replaces the header, so there's no deduplication or sorting
-    /// taking place.
+    /// or merges the header into the first one (see
+    /// [`Header::merge`](struct.Header.html#method.merge)), so there's
+    /// no deduplication or sorting taking place.
     #[inline]
     pub fn insert_line(&mut self, line: Line) {
         use Line::*;
         match line {
-            Header(h) => self.headers.push(h),
+            Header(h) => match self.headers.first_mut() {
+                Some(first) => first.merge(h),
+                None => self.headers.push(h),
+            },
             Segment(s) => self.segments.push(s),
             Link(s) => self.links.push(s),
             Containment(s) => self.containments.push(s),
@@ -145,64 +168,121 @@ impl GFA {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Return the 64 bits FNV-1a hash of the object, the same on every
+    /// run and on every machine for the same lines in the same order
+    pub fn to_hash(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Return an iterator over the custom records with the given prefix
+    pub fn custom_records(&self, prefix: u8) -> impl Iterator<Item = &CustomRecord> {
+        self.custom.iter().filter(move |c| c.prefix == prefix)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
 pub struct Header {
-    pub version: BString,
+    /// The value of the ```VN:Z:``` tag, as ```1.0```
+    pub version: Option<BString>,
+    /// The other tags of the line, in the order they were found
+    #[serde(default)]
+    pub tags: Vec<OptField>,
 }
 
 impl Header {
     #[inline]
     pub fn new(version: &[u8]) -> Self {
         Header {
-            version: version.into(),
+            version: Some(version.into()),
+            tags: vec![],
+        }
+    }
+
+    /// Add the version and the tags of another header line: the version
+    /// and the tags already found are kept, the others are added after
+    /// them
+    pub fn merge(&mut self, other: Header) {
+        if self.version.is_none() {
+            self.version = other.version;
+        }
+        for tag in other.tags {
+            if self.tags.iter().all(|t| t.tag != tag.tag) {
+                self.tags.push(tag);
+            }
         }
     }
 }
 
 impl fmt::Display for Header {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "H\t{}", self.version)
+        write!(f, "H")?;
+        if let Some(version) = &self.version {
+            write!(f, "\tVN:Z:{}", version)?;
+        }
+        write!(f, "{}", optional_fields(&self.tags))
     }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
 pub struct Segment {
-    pub name: usize,
+    pub name: u64,
+    /// The name written in the file, that the id can't be turned back
+    /// into, empty if the name is a number
+    #[serde(default)]
+    pub original_name: BString,
     pub sequence: BString,
+    /// The tags of the line, see
+    /// [`keep_tags`](../../parser/parser_gfa1/struct.ParserBuilder.html#method.keep_tags)
+    #[serde(default)]
+    pub optional: Vec<OptField>,
 }
 
 impl Segment {
     #[inline]
-    pub fn new(name: usize, sequence: &[u8]) -> Self {
+    pub fn new(name: u64, sequence: &[u8]) -> Self {
         Segment {
             name,
+            original_name: BString::from(""),
             sequence: BString::from(sequence),
+            optional: vec![],
         }
     }
 }
 
 impl fmt::Display for Segment {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "S\t{}\t{}", self.name, self.sequence)
+        write!(
+            f,
+            "S\t{}\t{}{}",
+            self.name,
+            self.sequence,
+            optional_fields(&self.optional)
+        )
     }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
 pub struct Link {
-    pub from_segment: usize,
+    pub from_segment: u64,
     pub from_orient: Orientation,
-    pub to_segment: usize,
+    pub to_segment: u64,
     pub to_orient: Orientation,
+    /// The overlap (a CIGAR or ```*```) as written in the file
+    #[serde(default)]
+    pub overlap: BString,
+    #[serde(default)]
+    pub optional: Vec<OptField>,
 }
 
 impl Link {
     #[inline]
     pub fn new(
-        from_segment: usize,
+        from_segment: u64,
         from_orient: Orientation,
-        to_segment: usize,
+        to_segment: u64,
         to_orient: Orientation,
     ) -> Link {
         Link {
@@ -210,27 +290,107 @@ impl Link {
             from_orient,
             to_segment,
             to_orient,
+            overlap: BString::from("*"),
+            optional: vec![],
         }
     }
+
+    /// The same link with the given overlap
+    #[inline]
+    pub fn with_overlap(mut self, overlap: &[u8]) -> Link {
+        self.overlap = overlap.into();
+        self
+    }
+}
+
+/// The field written for an overlap, ```*``` if it was never set
+#[inline]
+fn overlap_field(overlap: &BString) -> &[u8] {
+    if overlap.is_empty() {
+        b"*"
+    } else {
+        overlap.as_slice()
+    }
 }
 
 impl fmt::Display for Link {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "L\t{}\t{}\t{}\t{}\t*",
-            self.from_segment, self.from_orient, self.to_segment, self.to_orient,
+            "L\t{}\t{}\t{}\t{}\t{}{}",
+            self.from_segment,
+            self.from_orient,
+            self.to_segment,
+            self.to_orient,
+            overlap_field(&self.overlap).as_bstr(),
+            optional_fields(&self.optional)
         )
     }
 }
 
+/// A segment contained in another one, from the base ```pos``` of the
+/// container, with the overlap (a CIGAR or ```*```) as written in the
+/// file
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
-pub struct Containment {}
+pub struct Containment {
+    pub container_name: u64,
+    pub container_orient: Orientation,
+    pub contained_name: u64,
+    pub contained_orient: Orientation,
+    pub pos: usize,
+    #[serde(default)]
+    pub overlap: BString,
+    #[serde(default)]
+    pub optional: Vec<OptField>,
+}
+
+impl Containment {
+    #[inline]
+    pub fn new(
+        container_name: u64,
+        container_orient: Orientation,
+        contained_name: u64,
+        contained_orient: Orientation,
+        pos: usize,
+    ) -> Containment {
+        Containment {
+            container_name,
+            container_orient,
+            contained_name,
+            contained_orient,
+            pos,
+            overlap: BString::from("*"),
+            optional: vec![],
+        }
+    }
+}
+
+impl fmt::Display for Containment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "C\t{}\t{}\t{}\t{}\t{}\t{}{}",
+            self.container_name,
+            self.container_orient,
+            self.contained_name,
+            self.contained_orient,
+            self.pos,
+            overlap_field(&self.overlap).as_bstr(),
+            optional_fields(&self.optional)
+        )
+    }
+}
 
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
 pub struct Path {
     pub path_name: BString,
     pub segment_names: BString,
+    /// The overlaps between the steps (CIGARs separated by commas, or
+    /// ```*```) as written in the file
+    #[serde(default)]
+    pub overlaps: BString,
+    #[serde(default)]
+    pub optional: Vec<OptField>,
 }
 
 impl Path {
@@ -239,36 +399,60 @@ impl Path {
         Path {
             path_name,
             segment_names,
+            overlaps: BString::from("*"),
+            optional: vec![],
         }
     }
 
-    /// Parses (and copies!) a segment ID in the path segment list
+    /// The same path with the given overlaps
+    #[inline]
+    pub fn with_overlaps(mut self, overlaps: &[u8]) -> Self {
+        self.overlaps = overlaps.into();
+        self
+    }
+
+    /// Produces an iterator over the overlaps between the steps of the
+    /// path, empty if they're ```*```
     #[inline]
-    fn parse_segment_id(input: &[u8]) -> Option<(usize, Orientation)> {
-        use Orientation::*;
-        let last = input.len() - 1;
-        let orient = match input[last] {
-            b'+' => Forward,
-            b'-' => Backward,
-            _ => panic!("Path segment did not include orientation"),
-        };
-        let seg = &input[..last];
-        let id = usize::parse_id(IdType::ID(), seg)?;
-        Some((id, orient))
+    pub fn overlaps_iter(&self) -> impl Iterator<Item = &[u8]> {
+        let overlaps = overlap_field(&self.overlaps);
+        let known = overlaps != b"*";
+        overlaps.split_str(b",").filter(move |_| known)
     }
 
-    /// Produces an iterator over the usize segments of the given
-    /// path.
+    /// Produces an iterator over the u64 segments of the given
+    /// path, leaving out the steps that can't be parsed, see
+    /// [`iter_checked`](#method.iter_checked)
     #[inline]
-    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (usize, Orientation)> + 'a {
-        self.segment_names
-            .split_str(b",")
-            .filter_map(Self::parse_segment_id)
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (u64, Orientation)> + 'a {
+        self.iter_checked().filter_map(Result::ok)
+    }
+
+    /// Produces an iterator over the steps of the path, each with its
+    /// segment and orientation, or the error of a step that isn't a
+    /// segment name followed by '+' or '-'
+    /// ## Example
+    /// ```ignore
+    /// let path = Path::new("x".into(), "1+,2,3-".into());
+    /// // Ok((1, +)), Err(MissingOrientation("2")), Ok((3, -))
+    /// ```
+    #[inline]
+    pub fn iter_checked<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = Result<(u64, Orientation), PathParseError>> + 'a {
+        self.segment_names.split_str(b",").map(parse_step)
     }
 }
 
 impl fmt::Display for Path {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "P\t{}\t{}\t*", self.path_name, self.segment_names)
+        write!(
+            f,
+            "P\t{}\t{}\t{}{}",
+            self.path_name,
+            self.segment_names,
+            overlap_field(&self.overlaps).as_bstr(),
+            optional_fields(&self.optional)
+        )
     }
 }
@@ -1,9 +1,13 @@
 /// This file provides the structure to create a GFA2 Object
+use crate::gfa::extension::*;
+use crate::gfa::optional::*;
 use crate::gfa::orientation::*;
 use crate::gfa::segment_id::*;
-use bstr::{BString, ByteSlice};
+use bstr::{BStr, BString, ByteSlice};
+use fnv::FnvHasher;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 /// return a GFA2 object
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
@@ -15,25 +19,54 @@ pub struct GFA2 {
     pub gaps: Vec<Gap>,
     pub groups_o: Vec<GroupO>,
     pub groups_u: Vec<GroupU>,
+    /// Lines with an unknown record type, see
+    /// [`preserve_unknown_lines`](../../parser/parser_gfa2/struct.ParserBuilder.html#method.preserve_unknown_lines)
+    #[serde(default)]
+    pub unknown: Vec<UnknownRecord>,
+    /// Lines of the record types registered with
+    /// [`custom_line`](../../parser/parser_gfa2/struct.ParserBuilder.html#method.custom_line)
+    #[serde(default)]
+    pub custom: Vec<CustomRecord>,
+    /// Number of lines not parsed, as their record type was left out by
+    /// the [`ParserBuilder`](../../parser/parser_gfa2/struct.ParserBuilder.html) of the parser
+    #[serde(default)]
+    pub skipped_lines: usize,
+    /// The segments whose length isn't the length of their sequence,
+    /// found by the parser with the ```Safe``` tolerance, see
+    /// [`validate`](#method.validate)
+    #[serde(default)]
+    pub warnings: Vec<ValidationError>,
 }
 
+/// The unknown and custom lines are written verbatim after all the
+/// other lines, in the order they were found in the file
 impl fmt::Display for GFA2 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "{}{}{}{}",
+            "{}{}{}{}{}{}{}{}",
             self.headers
                 .iter()
                 .fold(String::new(), |acc, str| acc + &str.to_string() + "\n"),
             self.segments
                 .iter()
                 .fold(String::new(), |acc, str| acc + &str.to_string() + "\n"),
+            self.fragments
+                .iter()
+                .fold(String::new(), |acc, str| acc + &str.to_string() + "\n"),
             self.edges
                 .iter()
                 .fold(String::new(), |acc, str| acc + &str.to_string() + "\n"),
+            self.gaps
+                .iter()
+                .fold(String::new(), |acc, str| acc + &str.to_string() + "\n"),
             self.groups_o
                 .iter()
                 .fold(String::new(), |acc, str| acc + &str.to_string() + "\n"),
+            self.groups_u
+                .iter()
+                .fold(String::new(), |acc, str| acc + &str.to_string() + "\n"),
+            extension_lines(&self.unknown, &self.custom),
         )
     }
 }
@@ -107,18 +140,22 @@ some_line_ref_fn!(some_ugroup, GroupU, LineRef::GroupU);
 
 /// Insert a GFA line (wrapped in the Line enum) into an existing
 /// GFA. Simply pushes it into the corresponding Vec in the GFA,
-/// or replaces the header, so there's no deduplication or sorting
-/// taking place.
+/// or merges the header into the first one, so there's no
+/// deduplication or sorting taking place.
 impl GFA2 {
     /// Insert a GFA line (wrapped in the Line enum) into an existing
     /// GFA. Simply pushes it into the corresponding Vec in the GFA,
-    /// or replaces the header, so there's no deduplication or sorting
-    /// taking place.
+    /// or merges the header into the first one (see
+    /// [`Header::merge`](struct.Header.html#method.merge)), so there's
+    /// no deduplication or sorting taking place.
     #[inline]
     pub fn insert_line(&mut self, line: Line) {
         use Line::*;
         match line {
-            Header(h) => self.headers.push(h),
+            Header(h) => match self.headers.first_mut() {
+                Some(first) => first.merge(h),
+                None => self.headers.push(h),
+            },
             Segment(s) => self.segments.push(s),
             Fragment(f) => self.fragments.push(f),
             Edge(e) => self.edges.push(e),
@@ -176,95 +213,404 @@ impl GFA2 {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Return the 64 bits FNV-1a hash of the object, the same on every
+    /// run and on every machine for the same lines in the same order
+    pub fn to_hash(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Check the lines of the object against the spec: a segment with
+    /// a sequence must have its length as ```slen```, while the length of
+    /// a ```*``` segment is only known by ```slen```
+    /// # Example
+    /// ```ignore
+    /// // S 1 21 ACGT
+    /// let errors = gfa2.validate().unwrap_err();
+    /// println!("{}", errors[0]);
+    /// ```
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let errors: Vec<ValidationError> = self
+            .segments
+            .iter()
+            .filter_map(Segment::length_mismatch)
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Return an iterator over the custom records with the given prefix
+    pub fn custom_records(&self, prefix: u8) -> impl Iterator<Item = &CustomRecord> {
+        self.custom.iter().filter(move |c| c.prefix == prefix)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
+#[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
 pub struct Header {
-    pub version: BString,
+    /// The value of the ```VN:Z:``` tag, as ```2.0```
+    pub version: Option<BString>,
+    /// The other tags of the line, in the order they were found
+    #[serde(default)]
+    pub tags: Vec<OptField>,
 }
 
 impl Header {
     #[inline]
     pub fn new(version: &[u8]) -> Self {
         Header {
-            version: version.into(),
+            version: Some(version.into()),
+            tags: vec![],
+        }
+    }
+
+    /// Add the version and the tags of another header line: the version
+    /// and the tags already found are kept, the others are added after
+    /// them
+    pub fn merge(&mut self, other: Header) {
+        if self.version.is_none() {
+            self.version = other.version;
+        }
+        for tag in other.tags {
+            if self.tags.iter().all(|t| t.tag != tag.tag) {
+                self.tags.push(tag);
+            }
         }
     }
 }
 
 impl fmt::Display for Header {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "H\t{}", self.version)
+        write!(f, "H")?;
+        if let Some(version) = &self.version {
+            write!(f, "\tVN:Z:{}", version)?;
+        }
+        write!(f, "{}", optional_fields(&self.tags))
     }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
 pub struct Segment {
-    pub id: usize,
+    pub id: u64,
+    /// The name written in the file, that the id can't be turned back
+    /// into, empty if the name is a number
+    #[serde(default)]
+    pub original_name: BString,
     pub sequence: BString,
+    /// The length declared by the line, the length of the sequence
+    /// unless it's ```*```
+    #[serde(default)]
+    pub slen: u64,
+    /// The tags of the line, see
+    /// [`keep_tags`](../../parser/parser_gfa2/struct.ParserBuilder.html#method.keep_tags)
+    #[serde(default)]
+    pub optional: Vec<OptField>,
 }
 
 impl Segment {
     #[inline]
-    pub fn new(id: usize, sequence: &[u8]) -> Self {
+    pub fn new(id: u64, sequence: &[u8]) -> Self {
         Segment {
             id,
+            original_name: BString::from(""),
             sequence: BString::from(sequence),
+            slen: if sequence == b"*" {
+                0
+            } else {
+                sequence.len() as u64
+            },
+            optional: vec![],
+        }
+    }
+
+    /// The length of the segment: the length of the sequence, or
+    /// ```slen``` for a ```*``` sequence
+    #[inline]
+    pub fn length(&self) -> u64 {
+        if self.sequence == "*" {
+            self.slen
+        } else {
+            self.sequence.len() as u64
+        }
+    }
+
+    /// The ```SegmentLength``` error of a segment whose sequence isn't
+    /// ```slen``` bases long
+    pub fn length_mismatch(&self) -> Option<ValidationError> {
+        if self.length() == self.slen {
+            None
+        } else {
+            Some(ValidationError::SegmentLength {
+                id: self.id,
+                slen: self.slen,
+                sequence_len: self.sequence.len(),
+            })
+        }
+    }
+
+    /// The sequence of the node of the segment, with a ```N``` for each
+    /// base of a ```*``` sequence with a length, as the node can't be
+    /// empty
+    pub(crate) fn node_sequence(sequence: BString, slen: u64) -> BString {
+        if sequence == "*" && slen > 0 {
+            BString::from(vec![b'N'; slen as usize])
+        } else {
+            sequence
         }
     }
 }
 
+/// The length written is the length of the sequence, so a line read
+/// with a wrong ```slen``` is written with the right one
 impl fmt::Display for Segment {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let len = self.sequence.len();
-        write!(f, "S\t{}\t{}\t{}", self.id, len, self.sequence)
+        write!(
+            f,
+            "S\t{}\t{}\t{}{}",
+            self.id,
+            self.length(),
+            self.sequence,
+            optional_fields(&self.optional)
+        )
+    }
+}
+
+/// A line of a GFA2 object that doesn't follow the spec, see
+/// [`validate`](struct.GFA2.html#method.validate)
+/// ```ignore
+/// pub enum ValidationError {
+///     SegmentLength { id: u64, slen: u64, sequence_len: usize },
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
+pub enum ValidationError {
+    /// The segment ```id``` declares ```slen``` bases, but its sequence
+    /// has ```sequence_len```
+    SegmentLength {
+        id: u64,
+        slen: u64,
+        sequence_len: usize,
+    },
+}
+
+impl ValidationError {
+    /// The stable code of the error, see the
+    /// [`report`](../../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationError::SegmentLength { .. } => "V012",
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::SegmentLength {
+                id,
+                slen,
+                sequence_len,
+            } => write!(
+                f,
+                "The segment {} has length {}, but its sequence has {} bases",
+                id, slen, sequence_len
+            ),
+        }
     }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
-pub struct Fragment {}
+pub struct Fragment {
+    pub sid: u64,
+    /// The external sequence (a read, not a segment) as written in the
+    /// file, with its orientation
+    pub external: BString,
+    pub external_orient: Orientation,
+    /// The positions of the fragment on the segment and on the external
+    /// sequence, and the alignment, as written in the file
+    pub sbeg: BString,
+    pub send: BString,
+    pub fbeg: BString,
+    pub fend: BString,
+    pub alignment: BString,
+    #[serde(default)]
+    pub optional: Vec<OptField>,
+}
+
+impl Fragment {
+    #[inline]
+    pub fn new(sid: u64, external: &[u8], external_orient: Orientation) -> Self {
+        Fragment {
+            sid,
+            external: external.into(),
+            external_orient,
+            sbeg: BString::from("0"),
+            send: BString::from("0"),
+            fbeg: BString::from("0"),
+            fend: BString::from("0"),
+            alignment: BString::from("*"),
+            optional: vec![],
+        }
+    }
+}
+
+impl fmt::Display for Fragment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "F\t{}\t{}{}\t{}\t{}\t{}\t{}\t{}{}",
+            self.sid,
+            self.external,
+            self.external_orient,
+            self.sbeg,
+            self.send,
+            self.fbeg,
+            self.fend,
+            self.alignment,
+            optional_fields(&self.optional)
+        )
+    }
+}
 
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
 pub struct Edge {
-    pub sid1: usize, // orientation as final char (+-)
-    pub sid2: usize, // orientation as final char (+-)
+    pub sid1: u64,
+    pub sid1_orient: Orientation,
+    pub sid2: u64,
+    pub sid2_orient: Orientation,
+    /// The positions of the alignment on the 2 segments, with the final
+    /// ```$``` of a position at the end of a segment, and the alignment
+    /// (a CIGAR, a trace or ```*```), as written in the file
+    #[serde(default)]
+    pub beg1: BString,
+    #[serde(default)]
+    pub end1: BString,
+    #[serde(default)]
+    pub beg2: BString,
+    #[serde(default)]
+    pub end2: BString,
+    #[serde(default)]
+    pub alignment: BString,
+    #[serde(default)]
+    pub optional: Vec<OptField>,
 }
 
 impl Edge {
     #[inline]
-    pub fn new(sid1: usize, sid2: usize) -> Self {
-        Edge { sid1, sid2 }
+    pub fn new(sid1: u64, sid1_orient: Orientation, sid2: u64, sid2_orient: Orientation) -> Self {
+        Edge {
+            sid1,
+            sid1_orient,
+            sid2,
+            sid2_orient,
+            beg1: BString::from("0"),
+            end1: BString::from("0$"),
+            beg2: BString::from("0"),
+            end2: BString::from("0$"),
+            alignment: BString::from("*"),
+            optional: vec![],
+        }
+    }
+
+    /// The same edge with the given positions and alignment
+    #[inline]
+    pub fn with_alignment(mut self, positions: [&[u8]; 4], alignment: &[u8]) -> Self {
+        let [beg1, end1, beg2, end2] = positions;
+        self.beg1 = beg1.into();
+        self.end1 = end1.into();
+        self.beg2 = beg2.into();
+        self.end2 = end2.into();
+        self.alignment = alignment.into();
+        self
+    }
+
+    /// The two segment references, each one with its orientation
+    #[inline]
+    pub fn references(&self) -> ((u64, Orientation), (u64, Orientation)) {
+        ((self.sid1, self.sid1_orient), (self.sid2, self.sid2_orient))
+    }
+}
+
+/// A field of an edge, as written by ```Edge::new``` if it was never set
+#[inline]
+fn edge_field<'a>(value: &'a BString, default: &'static str) -> &'a BStr {
+    if value.is_empty() {
+        default.as_bytes().as_bstr()
+    } else {
+        value.as_bstr()
     }
 }
 
 impl fmt::Display for Edge {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let len = self.sid1.to_string().len() - 2;
-        let sid1 = self.sid1.to_string()[..len].to_string();
-        let sgn1 = match self.sid1.to_string()[len..].to_string().as_str() {
-            "43" => "+",
-            "45" => "-",
-            _ => panic!("Orientation not found!"),
-        };
-
-        let len = self.sid2.to_string().len() - 2;
-        let sid2 = self.sid2.to_string()[..len].to_string();
-        let sgn2 = match self.sid2.to_string()[len..].to_string().as_str() {
-            "43" => "+",
-            "45" => "-",
-            _ => panic!("Orientation not found!"),
-        };
-
         write!(
             f,
-            "E\t*\t{}{}\t{}{}\t0\t0$\t0\t0$\t*",
-            sid1, sgn1, sid2, sgn2
+            "E\t*\t{}{}\t{}{}\t{}\t{}\t{}\t{}\t{}{}",
+            self.sid1,
+            self.sid1_orient,
+            self.sid2,
+            self.sid2_orient,
+            edge_field(&self.beg1, "0"),
+            edge_field(&self.end1, "0$"),
+            edge_field(&self.beg2, "0"),
+            edge_field(&self.end2, "0$"),
+            edge_field(&self.alignment, "*"),
+            optional_fields(&self.optional)
         )
     }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
-pub struct Gap {}
+pub struct Gap {
+    pub id: BString, // optional id, can be either * or id tag
+    pub sid1: u64,
+    pub sid1_orient: Orientation,
+    pub sid2: u64,
+    pub sid2_orient: Orientation,
+    /// The estimated distance between the 2 segments and its variance
+    /// (or ```*```), as written in the file
+    pub dist: BString,
+    pub var: BString,
+    #[serde(default)]
+    pub optional: Vec<OptField>,
+}
+
+impl Gap {
+    #[inline]
+    pub fn new(sid1: u64, sid1_orient: Orientation, sid2: u64, sid2_orient: Orientation) -> Self {
+        Gap {
+            id: BString::from("*"),
+            sid1,
+            sid1_orient,
+            sid2,
+            sid2_orient,
+            dist: BString::from("0"),
+            var: BString::from("*"),
+            optional: vec![],
+        }
+    }
+}
+
+impl fmt::Display for Gap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "G\t{}\t{}{}\t{}{}\t{}\t{}{}",
+            self.id,
+            self.sid1,
+            self.sid1_orient,
+            self.sid2,
+            self.sid2_orient,
+            self.dist,
+            self.var,
+            optional_fields(&self.optional)
+        )
+    }
+}
 
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
 pub struct GroupO {
@@ -272,46 +618,91 @@ pub struct GroupO {
     // this field can implment or not an optional tag (using * char)
     pub id: BString,        // optional id, can be either * or id tag
     pub var_field: BString, // "array" of ref (from 1 to n)
+    #[serde(default)]
+    pub optional: Vec<OptField>,
 }
 
 impl GroupO {
     #[inline]
     pub fn new(id: BString, var_field: BString) -> Self {
-        GroupO { id, var_field }
+        GroupO {
+            id,
+            var_field,
+            optional: vec![],
+        }
     }
 
-    /// parses (and copies) a segment ID in the group segment list
+    /// Produces an iterator over the u64 segments of the given group,
+    /// leaving out the references that can't be parsed, see
+    /// [`iter_checked`](#method.iter_checked)
     #[inline]
-    fn parse_segment_id(input: &[u8]) -> Option<(usize, Orientation)> {
-        use Orientation::*;
-        let last = input.len() - 1;
-        let orient = match input[last] {
-            b'+' => Forward,
-            b'-' => Backward,
-            _ => panic!("Group O segment did not include orientation"),
-        };
-        let seg = &input[..last];
-        let id = usize::parse_id(IdType::ID(), seg)?;
-        Some((id, orient))
-    }
-
-    /// Produces an iterator over the usize segments of the given group
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (u64, Orientation)> + 'a {
+        self.iter_checked().filter_map(Result::ok)
+    }
+
+    /// Produces an iterator over the references of the group, each with
+    /// its segment and orientation, or the error of a reference that
+    /// isn't a segment name followed by '+' or '-'
     #[inline]
-    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (usize, Orientation)> + 'a {
-        self.var_field
-            .split_str(b" ")
-            .filter_map(Self::parse_segment_id)
+    pub fn iter_checked<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = Result<(u64, Orientation), PathParseError>> + 'a {
+        self.var_field.split_str(b" ").map(parse_step)
     }
 }
 
 impl fmt::Display for GroupO {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "O\t{}\t{}", self.id, self.var_field)
+        write!(
+            f,
+            "O\t{}\t{}{}",
+            self.id,
+            self.var_field,
+            optional_fields(&self.optional)
+        )
     }
 }
 
 #[derive(Default, Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize, Hash)]
-pub struct GroupU {}
+pub struct GroupU {
+    pub id: BString,        // optional id, can be either * or id tag
+    pub var_field: BString, // "array" of ids, without orientation (from 1 to n)
+    #[serde(default)]
+    pub optional: Vec<OptField>,
+}
+
+impl GroupU {
+    #[inline]
+    pub fn new(id: BString, var_field: BString) -> Self {
+        GroupU {
+            id,
+            var_field,
+            optional: vec![],
+        }
+    }
+
+    /// Produces an iterator over the references of the group as segment
+    /// ids: the references to edges and to other groups get an id too,
+    /// that isn't the one of a segment
+    #[inline]
+    pub fn iter_ids<'a>(&'a self) -> impl Iterator<Item = u64> + 'a {
+        self.var_field
+            .split_str(b" ")
+            .filter_map(|name| u64::parse_id(IdType::ID(), name))
+    }
+}
+
+impl fmt::Display for GroupU {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "U\t{}\t{}{}",
+            self.id,
+            self.var_field,
+            optional_fields(&self.optional)
+        )
+    }
+}
 
 #[cfg(test)]
 mod test {
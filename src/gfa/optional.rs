@@ -0,0 +1,81 @@
+/// This file provides the optional fields (the tags) of the GFA and GFA2
+/// lines, kept as they are written in the file
+use bstr::{BString, ByteSlice};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// An optional field ```<tag>:<type>:<value>``` of a line, as ```LN:i:12```.\
+/// The value isn't interpreted, so it's written back as it was read
+/// ```ignore
+/// pub struct OptField {
+///     pub tag: [u8; 2],
+///     pub kind: u8,
+///     pub value: BString,
+/// }
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
+pub struct OptField {
+    /// The 2 letters (or digits) of the tag
+    pub tag: [u8; 2],
+    /// The type of the value, one of ```ABHJZif```
+    pub kind: u8,
+    /// The value, as found in the file
+    pub value: BString,
+}
+
+impl OptField {
+    #[inline]
+    pub fn new(tag: &[u8; 2], kind: u8, value: &[u8]) -> Self {
+        OptField {
+            tag: *tag,
+            kind,
+            value: value.into(),
+        }
+    }
+
+    /// Parses a field as
+    /// ```<tag> <- [A-Za-z0-9][A-Za-z0-9]:[ABHJZif]:[ -~]*```,
+    /// None if it isn't an optional field
+    pub fn parse(input: &[u8]) -> Option<Self> {
+        match input {
+            [t1, t2, b':', kind, b':', value @ ..]
+                if t1.is_ascii_alphanumeric()
+                    && t2.is_ascii_alphanumeric()
+                    && b"ABHJZif".contains(kind)
+                    && value.iter().all(|b| (b' '..=b'~').contains(b)) =>
+            {
+                Some(OptField::new(&[*t1, *t2], *kind, value))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for OptField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            self.tag.as_bstr(),
+            self.kind as char,
+            self.value
+        )
+    }
+}
+
+/// The optional fields among the remaining fields of a line, the ones
+/// that aren't tags are skipped
+#[inline]
+pub fn parse_optional<I>(input: I) -> Vec<OptField>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    input.filter_map(|f| OptField::parse(f.as_ref())).collect()
+}
+
+/// The optional fields written at the end of a line, each one after a
+/// tab, so nothing is written if there aren't any
+pub fn optional_fields(optional: &[OptField]) -> String {
+    optional.iter().map(|o| format!("\t{}", o)).collect()
+}
@@ -1,10 +1,14 @@
 /// file that is used to define all the common types that can be
 /// parsed and used as SegmentId
+use crate::gfa::orientation::Orientation;
 use crate::parser::ParseFieldError;
 
+use crate::parser::validators;
+
 use bstr::{BString, ByteSlice};
-use lazy_static::lazy_static;
-use regex::bytes::Regex;
+use fnv::FnvHasher;
+use std::convert::TryFrom;
+use std::hash::Hasher;
 
 /// enum representing the type of ID that is going to be parsed
 /// ```ignore
@@ -23,20 +27,21 @@ pub enum IdType {
     REFERENCEID(),
 }
 
-lazy_static! {
-    static ref RE_ID: Regex = Regex::new(r"(?-u)[!-~]+").unwrap();
-    static ref RE_OPTIONAL_ID: Regex = Regex::new(r"(?-u)[!-~]+|\*").unwrap();
-    static ref RE_REFERENCE_ID: Regex = Regex::new(r"(?-u)[!-~]+[+-]").unwrap();
-}
-
 /// Trait for the types that can be parsed and used as segment IDs;
-/// will probably only be usize and BString.
+/// will probably only be u64, usize and BString.
 pub trait SegmentId: std::fmt::Display + Sized + Default {
     const ERROR: ParseFieldError;
 
     // define the functions
     fn parse_id(id: IdType, input: &[u8]) -> Option<Self>;
 
+    /// Like ```parse_id```, but with the reason why the ID couldn't
+    /// be parsed
+    #[inline]
+    fn try_parse_id(id: IdType, input: &[u8]) -> Result<Self, ParseFieldError> {
+        Self::parse_id(id, input).ok_or(Self::ERROR)
+    }
+
     #[inline]
     fn parse_next<I>(mut input: I, id: IdType) -> Result<Self, ParseFieldError>
     where
@@ -44,61 +49,81 @@ pub trait SegmentId: std::fmt::Display + Sized + Default {
         I::Item: AsRef<[u8]>,
     {
         let next = input.next().ok_or(ParseFieldError::MissingFields)?;
-        Self::parse_id(id, next.as_ref()).ok_or(Self::ERROR)
+        Self::try_parse_id(id, next.as_ref())
     }
 }
 
-impl SegmentId for usize {
+/// The biggest segment ID that can be used as a node id, as the
+/// [`Handle`](../../handle/struct.Handle.html) keeps the orientation
+/// in the lowest bit
+pub const MAX_SEGMENT_ID: u64 = crate::handle::MAX_NODE_ID;
+
+/// The bound of the ids given to the names that aren't numbers
+pub const NAMED_ID_BOUND: u64 = 100_000_000_000_000_000;
+
+impl SegmentId for u64 {
     const ERROR: ParseFieldError = ParseFieldError::UintIdError;
 
     #[inline]
     fn parse_id(id: IdType, input: &[u8]) -> Option<Self> {
+        Self::try_parse_id(id, input).ok()
+    }
+
+    /// The id of a segment name as in [`name_to_id`](fn.name_to_id.html),
+    /// where a GFA2 reference is the id of the segment it references,
+    /// see [`parse_reference`](fn.parse_reference.html) for its orientation
+    #[inline]
+    fn try_parse_id(id: IdType, input: &[u8]) -> Result<Self, ParseFieldError> {
         match id {
-            IdType::ID() => {
-                if RE_ID.is_match(input) {
-                    convert_to_usize(input)
-                } else {
-                    panic!("Error! the ID tag it's not correct")
-                }
-            }
-            IdType::OPTIONALID() => {
-                if RE_OPTIONAL_ID.is_match(input) {
-                    convert_to_usize(input)
-                } else {
-                    panic!("Error! the Optional ID tag it's not correct")
-                }
-            }
-            IdType::REFERENCEID() => {
-                if RE_REFERENCE_ID.is_match(input) {
-                    convert_to_usize(input)
-                } else {
-                    panic!("Error! the reference ID tag it's not correct")
-                }
+            IdType::ID() | IdType::OPTIONALID() if validators::has_printable(input) => {
+                name_to_id(input)
             }
+            IdType::REFERENCEID() => parse_reference(input).map(|(id, _)| id),
+            _ => Err(Self::ERROR),
         }
     }
 }
 
+impl SegmentId for usize {
+    const ERROR: ParseFieldError = ParseFieldError::UintIdError;
+
+    /// Parse the ID as an u64, failing if it doesn't fit in an usize
+    /// (on 32-bit targets)
+    #[inline]
+    fn parse_id(id: IdType, input: &[u8]) -> Option<Self> {
+        Self::try_parse_id(id, input).ok()
+    }
+
+    #[inline]
+    fn try_parse_id(id: IdType, input: &[u8]) -> Result<Self, ParseFieldError> {
+        let id = u64::try_parse_id(id, input)?;
+        usize::try_from(id)
+            .map_err(|_| ParseFieldError::IdOverflow(input.to_str_lossy().into_owned()))
+    }
+}
+
 impl SegmentId for BString {
     const ERROR: ParseFieldError = ParseFieldError::Utf8Error;
 
     #[inline]
     fn parse_id(id: IdType, input: &[u8]) -> Option<Self> {
         match id {
-            IdType::ID() => RE_ID.find(input).map(|s| BString::from(s.as_bytes())),
-            IdType::OPTIONALID() => RE_OPTIONAL_ID
-                .find(input)
-                .map(|s| BString::from(s.as_bytes())),
-            IdType::REFERENCEID() => RE_REFERENCE_ID
-                .find(input)
-                .map(|s| BString::from(s.as_bytes())),
+            IdType::ID() | IdType::OPTIONALID() => validators::find_printable(input),
+            IdType::REFERENCEID() => validators::find_reference_id(input),
         }
+        .map(BString::from)
     }
 }
 
 /// This function will convert any [printable]
 /// input into the corresponding [ASCII CODE].\
-/// Remember! if the input it's already a `Number` it will be returned as it is.
+/// Remember! if the input it's already a `Number` it will be returned as it is.\
+/// Returns ```None``` if the result doesn't fit in an usize,
+/// see [`convert_to_u64`](fn.convert_to_u64.html)
+///
+/// The parsers don't use it to get the segment ids anymore, as two names
+/// can be converted into the same number ("s1" and "1151"), see
+/// [`name_to_id`](fn.name_to_id.html)
 ///
 /// [printable]: https://flaviocopes.com/printable-ascii-characters/
 /// [ASCII CODE]: https://www.ascii-code.com/
@@ -115,21 +140,40 @@ impl SegmentId for BString {
 /// ```
 #[inline]
 pub fn convert_to_usize(input: &[u8]) -> Option<usize> {
-    let my_vec: Vec<char> = input.to_str().unwrap().chars().collect();
-    let mut res: String = "".to_string();
-    my_vec.iter().for_each(|c| {
-        res = format!("{}{}", res, &get_code_from_char(c).to_string());
-    });
-    match res.len() {
-        1..=20 => Some(res.parse::<usize>().unwrap()),
-        _ => panic!(
-            "Error! the conversion of the string: {} (length: {}) into usize: {} \
-            (length {}) exceeds the maximum length (20 digits) ",
-            input.to_str().unwrap(),
-            input.len(),
-            res,
-            res.len(),
-        ),
+    convert_to_u64(input)
+        .ok()
+        .and_then(|id| usize::try_from(id).ok())
+}
+
+/// Like [`convert_to_usize`](fn.convert_to_usize.html), but
+/// converting to an u64 (whatever the target) and failing with
+/// ```IdOverflow``` when the result exceeds
+/// [`MAX_SEGMENT_ID`](constant.MAX_SEGMENT_ID.html)
+/// ## Example
+/// ```ignore
+/// assert_eq!(convert_to_u64(b"4294967296"), Ok(4294967296));
+/// ```
+#[inline]
+pub fn convert_to_u64(input: &[u8]) -> Result<u64, ParseFieldError> {
+    convert_to_id_bounded(input, MAX_SEGMENT_ID)
+}
+
+/// Convert the input as in [`convert_to_u64`](fn.convert_to_u64.html),
+/// failing with ```IdOverflow``` when the result exceeds ```max```
+#[inline]
+pub fn convert_to_id_bounded(input: &[u8], max: u64) -> Result<u64, ParseFieldError> {
+    let input = input.to_str()?;
+    let mut res = String::new();
+    for c in input.chars() {
+        let code = get_code_from_char(&c).ok_or(ParseFieldError::InvalidField("id"))?;
+        res.push_str(&code.to_string());
+    }
+    match res.parse::<u64>() {
+        Ok(id) if id <= max => Ok(id),
+        Ok(_) => Err(ParseFieldError::IdOverflow(input.to_string())),
+        // the only digits are the ones pushed, so it can only be too big
+        Err(_) if !res.is_empty() => Err(ParseFieldError::IdOverflow(input.to_string())),
+        Err(_) => Err(ParseFieldError::MissingFields),
     }
 }
 
@@ -149,6 +193,135 @@ const CHARS: [&str; 128] = [
     "DEL",
 ];
 
+/// True if the segment name is a number written without leading zeros,
+/// so it's the node id itself and it can be written back from the id
+#[inline]
+pub fn is_numeric_id(input: &[u8]) -> bool {
+    match input {
+        [b'0'] => true,
+        [first, ..] => *first != b'0' && input.iter().all(u8::is_ascii_digit),
+        [] => false,
+    }
+}
+
+/// The name of a segment to keep beside its id, empty if the name is
+/// a number, as it can be written back from the id
+#[inline]
+pub fn segment_name(input: &[u8]) -> BString {
+    if is_numeric_id(input) {
+        BString::from("")
+    } else {
+        BString::from(input)
+    }
+}
+
+/// The id of a segment name: a number is the id itself, failing with
+/// ```IdOverflow``` above [`MAX_SEGMENT_ID`](constant.MAX_SEGMENT_ID.html),
+/// any other name is hashed as in [`hash_name`](fn.hash_name.html)
+/// ## Example
+/// ```ignore
+/// assert_eq!(name_to_id(b"12"), Ok(12));
+/// assert_eq!(name_to_id(b"contig_12"), Ok(hash_name(b"contig_12")));
+/// ```
+#[inline]
+pub fn name_to_id(name: &[u8]) -> Result<u64, ParseFieldError> {
+    if !is_numeric_id(name) {
+        return Ok(hash_name(name));
+    }
+    match name.to_str().ok().and_then(|n| n.parse::<u64>().ok()) {
+        Some(id) if id <= MAX_SEGMENT_ID => Ok(id),
+        _ => Err(ParseFieldError::IdOverflow(
+            name.to_str_lossy().into_owned(),
+        )),
+    }
+}
+
+/// The id and the orientation of a GFA2 segment reference, a segment
+/// name followed by '+' or '-'
+/// ## Example
+/// ```ignore
+/// assert_eq!(parse_reference(b"12-"), Ok((12, Orientation::Backward)));
+/// ```
+#[inline]
+pub fn parse_reference(input: &[u8]) -> Result<(u64, Orientation), ParseFieldError> {
+    if !validators::has_reference_id(input) {
+        return Err(ParseFieldError::UintIdError);
+    }
+    let (orient, name) = match input.split_last() {
+        Some((b'+', name)) => (Orientation::Forward, name),
+        Some((b'-', name)) => (Orientation::Backward, name),
+        _ => return Err(ParseFieldError::UintIdError),
+    };
+    Ok((name_to_id(name)?, orient))
+}
+
+/// A step of a path (or a reference of an O-group) that isn't a
+/// segment name followed by its orientation
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathParseError {
+    /// The step doesn't end with '+' or '-'
+    MissingOrientation(BString),
+    /// The name of the step isn't a valid segment id (as an empty
+    /// name, or a number too big to be a node id)
+    InvalidId(BString),
+}
+
+impl PathParseError {
+    /// The step as it's written in the record
+    pub fn token(&self) -> &BString {
+        match self {
+            PathParseError::MissingOrientation(step) | PathParseError::InvalidId(step) => step,
+        }
+    }
+}
+
+impl std::fmt::Display for PathParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathParseError::MissingOrientation(step) => {
+                write!(f, "The step {} has no orientation", step)
+            }
+            PathParseError::InvalidId(step) => {
+                write!(f, "The step {} isn't a valid segment id", step)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathParseError {}
+
+/// The id and the orientation of a step of a path or an O-group, a
+/// segment name followed by '+' or '-'
+/// ## Example
+/// ```ignore
+/// assert_eq!(parse_step(b"12-"), Ok((12, Orientation::Backward)));
+/// assert!(parse_step(b"12").is_err());
+/// ```
+#[inline]
+pub fn parse_step(input: &[u8]) -> Result<(u64, Orientation), PathParseError> {
+    let (orient, name) = match input.split_last() {
+        Some((b'+', name)) => (Orientation::Forward, name),
+        Some((b'-', name)) => (Orientation::Backward, name),
+        _ => return Err(PathParseError::MissingOrientation(BString::from(input))),
+    };
+    match u64::parse_id(IdType::ID(), name) {
+        Some(id) => Ok((id, orient)),
+        None => Err(PathParseError::InvalidId(BString::from(input))),
+    }
+}
+
+/// The id of a name that isn't a number, its FNV hash below
+/// [`NAMED_ID_BOUND`](constant.NAMED_ID_BOUND.html), so it's the same
+/// in every line referencing it.\
+/// Two names may get the same id, even if it's unlikely: the graph
+/// reports them as a ```NameCollision``` when it's built
+#[inline]
+pub fn hash_name(name: &[u8]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(name);
+    hasher.finish() % NAMED_ID_BOUND
+}
+
 /// function that performs the conversion from a symbol to the associated ascii code
 /// # Example
 /// ```ignore
@@ -157,11 +330,10 @@ const CHARS: [&str; 128] = [
 /// assert_eq!(a_, get_code_from_char(a));
 /// ```
 #[inline]
-fn get_code_from_char(c: &char) -> usize {
-    if c.is_numeric() {
-        c.to_digit(10).unwrap() as usize
-    } else {
-        CHARS.iter().position(|&x| x == c.to_string()).unwrap()
+fn get_code_from_char(c: &char) -> Option<usize> {
+    match c.to_digit(10) {
+        Some(digit) => Some(digit as usize),
+        None => CHARS.iter().position(|&x| x == c.to_string()),
     }
 }
 
@@ -190,4 +362,72 @@ mod tests {
         let res = BString::parse_next(edge_, IdType::REFERENCEID());
         println!("BString: {}", res.unwrap());
     }
+
+    #[test]
+    fn ids_beyond_u32() {
+        let big = u64::from(u32::MAX) + 1;
+        let id = u64::parse_id(IdType::ID(), big.to_string().as_bytes());
+        assert_eq!(id, Some(big));
+        assert_eq!(convert_to_u64(b"4294967296"), Ok(big));
+    }
+
+    #[test]
+    fn id_overflow() {
+        assert_eq!(convert_to_id_bounded(b"99", 99), Ok(99));
+        assert_eq!(
+            convert_to_id_bounded(b"100", 99),
+            Err(ParseFieldError::IdOverflow("100".to_string()))
+        );
+        // each letter takes 2 or 3 digits, more than 20 digits overflow u64
+        let res = u64::try_parse_id(IdType::ID(), b"99999999999999999999");
+        // the error has the id as it's written, not its digits
+        assert_eq!(
+            res,
+            Err(ParseFieldError::IdOverflow(
+                "99999999999999999999".to_string()
+            ))
+        );
+        // the same bound applies where usize is only 32 bits
+        let res = convert_to_id_bounded(b"4294967296", u64::from(u32::MAX));
+        assert!(matches!(res, Err(ParseFieldError::IdOverflow(_))));
+    }
+
+    #[test]
+    fn names_are_hashed() {
+        // as letters the name would take more than 20 digits
+        let id = u64::try_parse_id(IdType::ID(), b"alphabeticalname").unwrap();
+        assert_eq!(id, hash_name(b"alphabeticalname"));
+        assert!(id < NAMED_ID_BOUND);
+        assert_ne!(
+            id,
+            u64::parse_id(IdType::ID(), b"alphabeticalnamf").unwrap()
+        );
+        // a reference keeps its orientation beside the id
+        assert_eq!(
+            parse_reference(b"contig_12-"),
+            Ok((hash_name(b"contig_12"), Orientation::Backward))
+        );
+        assert_eq!(
+            parse_reference(b"12343+"),
+            Ok((12343, Orientation::Forward))
+        );
+        assert_eq!(u64::try_parse_id(IdType::REFERENCEID(), b"12+"), Ok(12));
+        assert_eq!(parse_reference(b"12"), Err(ParseFieldError::UintIdError));
+        assert_eq!(parse_reference(b"1+2"), Err(ParseFieldError::UintIdError));
+        // the numbers are the ids, unless they have leading zeros
+        assert_eq!(u64::try_parse_id(IdType::ID(), b"12"), Ok(12));
+        assert_eq!(u64::try_parse_id(IdType::ID(), b"0"), Ok(0));
+        assert_eq!(
+            u64::try_parse_id(IdType::ID(), b"012"),
+            Ok(hash_name(b"012"))
+        );
+        assert_eq!(segment_name(b"s1"), "s1");
+        assert_eq!(segment_name(b"012"), "012");
+        assert_eq!(segment_name(b"12"), "");
+        // a name without printable characters is still rejected
+        assert_eq!(
+            u64::try_parse_id(IdType::ID(), b""),
+            Err(ParseFieldError::UintIdError)
+        );
+    }
 }
@@ -1,10 +1,14 @@
 use crate::gfa::Orientation;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::ops::Add;
+use std::str::FromStr;
 
 /// Newtype that represents a node in the graph, no matter the
 /// graph implementation
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 #[repr(transparent)]
 pub struct NodeId(u64);
 
@@ -14,6 +18,16 @@ impl std::fmt::Display for NodeId {
     }
 }
 
+/// Parse a NodeId from its plain numeric representation,
+/// so negative or non-numeric input is rejected
+impl FromStr for NodeId {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim().parse::<u64>().map(NodeId)
+    }
+}
+
 impl From<u64> for NodeId {
     #[inline]
     fn from(num: u64) -> Self {
@@ -35,6 +49,8 @@ impl From<NodeId> for u64 {
     }
 }
 
+/// On 32-bit targets the ids bigger than ```u32::MAX``` are truncated,
+/// use ```u64::from``` to keep the whole id
 impl From<NodeId> for usize {
     #[inline]
     fn from(id: NodeId) -> Self {
@@ -58,8 +74,26 @@ impl Add<u64> for NodeId {
     }
 }
 
-/// A Handle is a node ID with an orientation, packed as a single u64
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
+/// The bit of a packed [`Handle`](struct.Handle.html) that holds the
+/// orientation, set for the reverse strand
+pub const ORIENTATION_BIT: u64 = 1;
+
+/// The biggest node ID that can be packed in a
+/// [`Handle`](struct.Handle.html), as the lowest bit is taken by the
+/// orientation
+pub const MAX_NODE_ID: u64 = u64::MAX >> 1;
+
+/// A Handle is a node ID with an orientation, packed as a single u64.\
+/// The layout is the ```number_bool_packing``` of libhandlegraph, and
+/// it's part of the stable API (changing it would be a breaking change):
+/// ```text
+/// packed = (id << 1) | is_reverse
+/// ```
+/// so the forward handle of a node is even and the reverse one is the
+/// next odd number. Every u64 is a valid packed handle, there are no
+/// reserved patterns; an id above [`MAX_NODE_ID`](constant.MAX_NODE_ID.html)
+/// can't be packed
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord, Serialize, Deserialize)]
 #[repr(transparent)]
 pub struct Handle(pub u64);
 
@@ -79,7 +113,106 @@ impl From<Handle> for NodeId {
     }
 }
 
+/// Error returned when a handle, or an edge, can't be parsed from its
+/// GFA textual form
+/// ```ignore
+/// pub enum HandleParseError {
+///     Empty,
+///     MissingId(String),
+///     InvalidId(String),
+///     InvalidOrientation(char),
+///     InvalidEdge(String),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandleParseError {
+    /// Nothing to parse
+    Empty,
+    /// An orientation without the id before it
+    MissingId(String),
+    /// An id that isn't a number, or one too big for a handle
+    InvalidId(String),
+    /// A character other than '+' or '-' after the id
+    InvalidOrientation(char),
+    /// An edge that isn't two handles separated by a comma
+    InvalidEdge(String),
+}
+
+impl std::fmt::Display for HandleParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use HandleParseError as HPE;
+        match self {
+            HPE::Empty => write!(f, "The handle is empty"),
+            HPE::MissingId(handle) => write!(f, "The handle {} has no id", handle),
+            HPE::InvalidId(id) => write!(f, "The id {} isn't a valid node id", id),
+            HPE::InvalidOrientation(c) => {
+                write!(f, "The orientation {} isn't + or -", c)
+            }
+            HPE::InvalidEdge(edge) => {
+                write!(
+                    f,
+                    "The edge {} isn't two handles separated by a comma",
+                    edge
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for HandleParseError {}
+
+/// The handle in the GFA textual form, the id followed by its
+/// orientation, as ```12+``` or ```7-```
+impl std::fmt::Display for Handle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let orient = if self.is_reverse() { '-' } else { '+' };
+        write!(f, "{}{}", self.id(), orient)
+    }
+}
+
+/// Parse a handle from its textual form, see
+/// [`from_name_orient`](struct.Handle.html#method.from_name_orient)
+impl FromStr for Handle {
+    type Err = HandleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Handle::from_name_orient(s.as_bytes())
+    }
+}
+
+// the layout is checked at compile time, see the documentation of Handle
+const _: () = assert!(std::mem::size_of::<Handle>() == std::mem::size_of::<u64>());
+const _: () = assert!(Handle::from_packed(85).packed() == 85);
+const _: () = assert!(Handle::from_packed((42 << 1) | ORIENTATION_BIT).unpack_number() == 42);
+const _: () = assert!(Handle::from_packed((42 << 1) | ORIENTATION_BIT).unpack_bit());
+const _: () = assert!(Handle::from_packed(MAX_NODE_ID << 1).unpack_number() == MAX_NODE_ID);
+
 impl Handle {
+    /// The handle with the given packed value, as used by
+    /// libhandlegraph; every value is a valid handle
+    #[inline]
+    pub const fn from_packed(packed: u64) -> Handle {
+        Handle(packed)
+    }
+
+    /// The packed value of the handle, ```(id << 1) | is_reverse```
+    #[inline]
+    pub const fn packed(&self) -> u64 {
+        self.0
+    }
+
+    /// The node ID and the orientation of a packed handle
+    #[inline]
+    pub fn unpack(packed: u64) -> (NodeId, Orientation) {
+        let handle = Handle::from_packed(packed);
+        let orient = if handle.is_reverse() {
+            Orientation::Backward
+        } else {
+            Orientation::Forward
+        };
+        (handle.id(), orient)
+    }
+
     #[inline]
     pub fn as_integer(self) -> u64 {
         self.0
@@ -91,13 +224,13 @@ impl Handle {
     }
 
     #[inline]
-    pub fn unpack_number(self) -> u64 {
-        self.as_integer() >> 1
+    pub const fn unpack_number(self) -> u64 {
+        self.0 >> 1
     }
 
     #[inline]
-    pub fn unpack_bit(self) -> bool {
-        self.as_integer() & 1 != 0
+    pub const fn unpack_bit(self) -> bool {
+        self.0 & ORIENTATION_BIT != 0
     }
 
     #[inline]
@@ -105,7 +238,7 @@ impl Handle {
         let id: NodeId = id.into();
         let uint: u64 = id.into();
         let is_reverse = orient != Orientation::Forward;
-        if uint < (0x1 << 63) {
+        if uint <= MAX_NODE_ID {
             Handle::from_integer((uint << 1) | is_reverse as u64)
         } else {
             panic!("Tried to create a handle with a node ID that filled 64 bits")
@@ -116,13 +249,49 @@ impl Handle {
     pub fn pack<T: Into<NodeId>>(id: T, is_reverse: bool) -> Handle {
         let id: NodeId = id.into();
         let uint: u64 = id.into();
-        if uint < (0x1 << 63) {
+        if uint <= MAX_NODE_ID {
             Handle::from_integer((uint << 1) | is_reverse as u64)
         } else {
             panic!("Tried to create a handle with a node ID that filled 64 bits")
         }
     }
 
+    /// The handle written as its numeric id followed by its orientation,
+    /// as in the GFA files (```123+``` or ```123-```); an id without
+    /// orientation is forward
+    /// # Example
+    /// ```ignore
+    /// assert_eq!(Handle::from_name_orient(b"12-"), Ok(Handle::pack(12, true)));
+    /// assert_eq!(Handle::from_name_orient(b"12"), Ok(Handle::pack(12, false)));
+    /// ```
+    pub fn from_name_orient(bytes: &[u8]) -> Result<Handle, HandleParseError> {
+        let text = || String::from_utf8_lossy(bytes).into_owned();
+        let (id, is_reverse) = match bytes.split_last() {
+            None => return Err(HandleParseError::Empty),
+            Some((b'+', id)) => (id, false),
+            Some((b'-', id)) => (id, true),
+            Some((last, _)) if last.is_ascii_digit() => (bytes, false),
+            Some(_) => {
+                let last = text().chars().last().unwrap_or('?');
+                return Err(HandleParseError::InvalidOrientation(last));
+            }
+        };
+        if id.is_empty() {
+            return Err(HandleParseError::MissingId(text()));
+        }
+        let invalid = || HandleParseError::InvalidId(String::from_utf8_lossy(id).into_owned());
+        if !id.iter().all(u8::is_ascii_digit) {
+            return Err(invalid());
+        }
+        match std::str::from_utf8(id)
+            .ok()
+            .and_then(|id| id.parse::<u64>().ok())
+        {
+            Some(id) if id <= MAX_NODE_ID => Ok(Handle::pack(id, is_reverse)),
+            _ => Err(invalid()),
+        }
+    }
+
     #[inline]
     pub fn id(self) -> NodeId {
         NodeId(self.unpack_number())
@@ -135,7 +304,7 @@ impl Handle {
 
     #[inline]
     pub fn flip(self) -> Self {
-        Handle(self.as_integer() ^ 1)
+        Handle(self.as_integer() ^ ORIENTATION_BIT)
     }
 
     #[inline]
@@ -148,10 +317,39 @@ impl Handle {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Hash, Eq, Ord, Serialize, Deserialize)]
 pub struct Edge(pub Handle, pub Handle);
 
 impl Edge {
+    /// The edge between two packed handles, see
+    /// [`Handle::from_packed`](struct.Handle.html#method.from_packed)
+    #[inline]
+    pub const fn from_packed(left: u64, right: u64) -> Edge {
+        Edge(Handle::from_packed(left), Handle::from_packed(right))
+    }
+
+    /// The packed values of the two handles of the edge
+    #[inline]
+    pub const fn packed(&self) -> (u64, u64) {
+        (self.0.packed(), self.1.packed())
+    }
+
+    /// The edge in its canonical orientation, as
+    /// [`edge_handle`](#method.edge_handle) builds it.\
+    /// An edge of a bidirected graph can be written from both its ends:
+    /// ```Edge(l, r)``` and ```Edge(r.flip(), l.flip())``` are the same
+    /// adjacency, as ```11-,13+``` and ```13-,11+```, and have the same
+    /// canonical form
+    /// # Example
+    /// ```ignore
+    /// let edge = Edge(Handle::pack(13, true), Handle::pack(11, false));
+    /// assert_eq!(edge.canonical(), Edge(Handle::pack(11, true), Handle::pack(13, false)));
+    /// ```
+    #[inline]
+    pub fn canonical(self) -> Edge {
+        Edge::edge_handle(self.0, self.1)
+    }
+
     /// Construct an edge, taking the orientation of the handles into account
     #[inline]
     pub fn edge_handle(left: Handle, right: Handle) -> Edge {
@@ -172,6 +370,28 @@ impl Edge {
     }
 }
 
+/// The edge in the textual form of its handles separated by a comma, as
+/// ```11+,13-```
+impl std::fmt::Display for Edge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.0, self.1)
+    }
+}
+
+/// Parse an edge from two handles separated by a comma, each as in
+/// [`Handle::from_name_orient`](struct.Handle.html#method.from_name_orient)
+impl FromStr for Edge {
+    type Err = HandleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut handles = s.split(',');
+        match (handles.next(), handles.next(), handles.next()) {
+            (Some(left), Some(right), None) => Ok(Edge(left.parse()?, right.parse()?)),
+            _ => Err(HandleParseError::InvalidEdge(s.to_string())),
+        }
+    }
+}
+
 /// Enum for handle orientation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Direction {
@@ -189,7 +409,7 @@ mod tests {
         let u: u64 = 597283742;
         let h = Handle::pack(NodeId(u), true);
         assert_eq!(h.unpack_number(), u);
-        assert_eq!(h.unpack_bit(), true);
+        assert!(h.unpack_bit());
     }
 
     // Handle::pack should panic when the provided NodeId is invalid
@@ -197,7 +417,7 @@ mod tests {
     #[test]
     #[should_panic]
     fn handle_pack_panic() {
-        Handle::pack(NodeId(std::u64::MAX), true);
+        Handle::pack(NodeId(u64::MAX), true);
     }
 
     #[test]
@@ -212,7 +432,123 @@ mod tests {
         println!("{:?}, {}, {}", h3, h3.unpack_bit(), h3.is_reverse());
 
         assert_eq!(h1.unpack_number(), h2.unpack_number());
-        assert_eq!(h1.unpack_bit(), true);
-        assert_eq!(h2.unpack_bit(), false);
+        assert!(h1.unpack_bit());
+        assert!(!h2.unpack_bit());
+    }
+
+    #[test]
+    fn packed_layout() {
+        // (id, reverse) -> packed, as in libhandlegraph
+        let known: &[(u64, bool, u64)] = &[
+            (0, false, 0),
+            (0, true, 1),
+            (1, false, 2),
+            (1, true, 3),
+            (42, true, 85),
+            (MAX_NODE_ID, false, u64::MAX - 1),
+            (MAX_NODE_ID, true, u64::MAX),
+        ];
+        for &(id, reverse, packed) in known {
+            let h = Handle::pack(id, reverse);
+            assert_eq!(h.packed(), packed);
+            assert_eq!(Handle::from_packed(packed), h);
+            let orient = if reverse {
+                Orientation::Backward
+            } else {
+                Orientation::Forward
+            };
+            assert_eq!(Handle::unpack(packed), (NodeId(id), orient));
+            assert_eq!(Handle::new(id, orient), h);
+        }
+        assert_eq!(ORIENTATION_BIT, 1);
+        assert_eq!(MAX_NODE_ID, (1 << 63) - 1);
+    }
+
+    #[test]
+    fn packed_round_trip() {
+        for &packed in &[
+            0,
+            1,
+            2,
+            3,
+            MAX_NODE_ID - 1,
+            MAX_NODE_ID,
+            u64::MAX - 1,
+            u64::MAX,
+        ] {
+            let (id, orient) = Handle::unpack(packed);
+            assert_eq!(Handle::new(id, orient).packed(), packed);
+            assert_eq!(Handle::from_packed(packed).flip().flip().packed(), packed);
+        }
+        for &id in &[0, 1, MAX_NODE_ID] {
+            for &reverse in &[false, true] {
+                let h = Handle::pack(id, reverse);
+                assert_eq!(Handle::from_packed(h.packed()), h);
+                assert_eq!(h.id(), NodeId(id));
+                assert_eq!(h.is_reverse(), reverse);
+            }
+        }
+
+        let edge = Edge(Handle::pack(1, false), Handle::pack(MAX_NODE_ID, true));
+        assert_eq!(edge.packed(), (2, u64::MAX));
+        assert_eq!(Edge::from_packed(2, u64::MAX), edge);
+    }
+
+    #[test]
+    fn handle_text_round_trip() {
+        for &(text, id, reverse) in &[("123+", 123, false), ("0-", 0, true), ("7+", 7, false)] {
+            let handle: Handle = text.parse().unwrap();
+            assert_eq!(handle, Handle::pack(id, reverse));
+            assert_eq!(handle.to_string(), text);
+        }
+        let max = format!("{}-", MAX_NODE_ID);
+        assert_eq!(max.parse::<Handle>(), Ok(Handle::pack(MAX_NODE_ID, true)));
+        assert_eq!(Handle::from_name_orient(b"42"), Ok(Handle::pack(42, false)));
+
+        let edge: Edge = "11+,13-".parse().unwrap();
+        assert_eq!(edge, Edge(Handle::pack(11, false), Handle::pack(13, true)));
+        assert_eq!(edge.to_string(), "11+,13-");
+        assert_eq!(edge.to_string().parse::<Edge>(), Ok(edge));
+    }
+
+    #[test]
+    fn handle_from_str_errors() {
+        use HandleParseError as HPE;
+        assert_eq!("".parse::<Handle>(), Err(HPE::Empty));
+        assert_eq!("+".parse::<Handle>(), Err(HPE::MissingId("+".into())));
+        assert_eq!("12*".parse::<Handle>(), Err(HPE::InvalidOrientation('*')));
+        assert_eq!("a1+".parse::<Handle>(), Err(HPE::InvalidId("a1".into())));
+        let too_big = format!("{}+", MAX_NODE_ID as u128 + 1);
+        assert!(matches!(too_big.parse::<Handle>(), Err(HPE::InvalidId(_))));
+        assert_eq!("11+".parse::<Edge>(), Err(HPE::InvalidEdge("11+".into())));
+        assert_eq!(
+            "1+,2+,3+".parse::<Edge>(),
+            Err(HPE::InvalidEdge("1+,2+,3+".into()))
+        );
+        assert_eq!("11+,x-".parse::<Edge>(), Err(HPE::InvalidId("x".into())));
+    }
+
+    #[test]
+    fn edge_canonical_is_the_same_for_both_writings() {
+        for (l, r) in [(false, false), (false, true), (true, false), (true, true)].iter() {
+            let l = Handle::pack(NodeId(11), *l);
+            let r = Handle::pack(NodeId(13), *r);
+            let (edge, reverse) = (Edge(l, r), Edge(r.flip(), l.flip()));
+            assert_eq!(edge.canonical(), reverse.canonical());
+            assert!(edge.canonical() == edge || edge.canonical() == reverse);
+            assert_eq!(edge.canonical().canonical(), edge.canonical());
+        }
+        // a reversing self loop is its own reverse
+        let h = Handle::pack(NodeId(11), false);
+        assert_eq!(Edge(h, h.flip()).canonical(), Edge(h, h.flip()));
+    }
+
+    #[test]
+    fn node_id_from_str() {
+        assert_eq!("42".parse::<NodeId>(), Ok(NodeId(42)));
+        assert_eq!(" 7 ".parse::<NodeId>(), Ok(NodeId(7)));
+        assert!("-1".parse::<NodeId>().is_err());
+        assert!("contig_7".parse::<NodeId>().is_err());
+        assert!("".parse::<NodeId>().is_err());
     }
 }
@@ -1,15 +1,25 @@
 use crate::handle::{Direction, Edge, Handle, NodeId};
 
+pub mod algorithms;
+pub mod analysis;
 pub mod error;
 pub mod iter;
+pub mod namer;
+pub mod traversal;
 
+pub use self::algorithms::*;
+pub use self::analysis::*;
 pub use self::error::*;
 pub use self::iter::*;
+pub use self::namer::*;
+pub use self::traversal::*;
 
 use rayon::prelude::*;
 
 /// Access all the handles in the graph as an iterator, and related
 /// methods.
+/// The iteration order is not specified, graphs that need a stable
+/// order (e.g. for writing files) should provide a sorted variant.
 pub trait AllHandles: Sized {
     type Handles: Iterator<Item = Handle>;
     fn handles(self) -> Self::Handles;
@@ -34,6 +44,8 @@ pub trait AllHandlesPar {
 
 /// Access all the edges in the graph as an iterator, and related
 /// methods.
+/// As for [`AllHandles`](trait.AllHandles.html), the iteration order
+/// is not specified.
 pub trait AllEdges: Sized {
     type Edges: Iterator<Item = Edge>;
 
@@ -64,8 +76,12 @@ pub trait HandleNeighbors: Sized {
         self.neighbors(handle, dir).count()
     }
 
+    /// True if the edge from ```left``` to ```right``` exists, written
+    /// from either of its ends, see
+    /// [`Edge::canonical`](../handle/struct.Edge.html#method.canonical)
     #[inline]
     fn has_edge(self, left: Handle, right: Handle) -> bool {
+        let Edge(left, right) = Edge(left, right).canonical();
         self.neighbors(left, Direction::Right).any(|h| h == right)
     }
 }
@@ -115,7 +131,7 @@ pub trait HandleSequencesPar {
 /// Trait denoting that implementors have access to all the immutable
 /// parts of the HandleGraph interface, and that implementors are
 /// copyable references (i.e. immutable, shared references).
-
+///
 /// Collects all the HandleGraph iterator traits in a single bound.
 /// The `impl` on `&T`, which has the additional bound that `T:
 /// HandleGraph`, makes it possible to use this as the only bound in
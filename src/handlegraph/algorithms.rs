@@ -0,0 +1,116 @@
+/// This file provides the topological order of the oriented handles of
+/// a graph, computed without recursion so it works on graphs of any size
+use std::{error, fmt};
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::handle::{Direction, Edge, Handle, NodeId};
+
+use super::HandleGraphRef;
+
+/// Error returned when a graph has no topological order, with one of
+/// the edges of a cycle
+#[derive(Debug, Clone, PartialEq)]
+pub struct CycleError {
+    pub edge: Edge,
+}
+
+impl fmt::Display for CycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Edge(l, r) = self.edge;
+        write!(f, "The graph has a cycle through the edge {} -> {}", l, r)
+    }
+}
+
+impl error::Error for CycleError {}
+
+/// Order every oriented handle (both the orientations of each node) so
+/// each edge goes from left to right, following the edges with
+/// Kahn's algorithm, or the handles left out by a cycle
+fn oriented_order<G: HandleGraphRef>(graph: G) -> (Vec<Handle>, FnvHashMap<Handle, usize>) {
+    let mut ids: Vec<NodeId> = graph.handles().map(|h| h.id()).collect();
+    ids.sort_unstable();
+    let mut in_degree: FnvHashMap<Handle, usize> = FnvHashMap::default();
+    let mut stack = vec![];
+    for id in ids.iter() {
+        for handle in [Handle::pack(*id, false), Handle::pack(*id, true)].iter() {
+            let degree = graph.degree(*handle, Direction::Left);
+            if degree == 0 {
+                stack.push(*handle);
+            } else {
+                in_degree.insert(*handle, degree);
+            }
+        }
+    }
+    // the smallest id is taken first
+    stack.reverse();
+
+    let mut order = Vec::with_capacity(ids.len() * 2);
+    while let Some(handle) = stack.pop() {
+        order.push(handle);
+        for next in graph.neighbors(handle, Direction::Right) {
+            if let Some(degree) = in_degree.get_mut(&next) {
+                *degree -= 1;
+                if *degree == 0 {
+                    in_degree.remove(&next);
+                    stack.push(next);
+                }
+            }
+        }
+    }
+    (order, in_degree)
+}
+
+/// An edge of a cycle among the handles left out of the order: each of
+/// them has an edge from another one, so walking back along them
+/// reaches a handle twice
+fn cycle_edge<G: HandleGraphRef>(graph: G, left: &FnvHashMap<Handle, usize>) -> Edge {
+    let mut handle = *left.keys().min().unwrap();
+    let mut seen = FnvHashSet::default();
+    seen.insert(handle);
+    loop {
+        let previous = graph
+            .neighbors(handle, Direction::Left)
+            .find(|h| left.contains_key(h))
+            .unwrap();
+        if !seen.insert(previous) {
+            return Edge::edge_handle(previous, handle);
+        }
+        handle = previous;
+    }
+}
+
+/// Function that returns the nodes of the graph, each once with an
+/// orientation, so every edge between two handles of the order goes
+/// from left to right: the order of the oriented handles found with
+/// Kahn's algorithm (from the smallest ids), keeping the first
+/// orientation of each node.\
+/// An edge that changes strand (from a handle of the order to the flip
+/// of another, as a reversing self-loop ```1+ -> 1-```) doesn't make a
+/// cycle, but can't go from left to right; a cycle of oriented handles
+/// is a ```CycleError``` with one of its edges
+/// # Example
+/// ```ignore
+/// // 1+ -> 2+, 1+ -> 3+, 3+ -> 2-
+/// let order = topological_order(&graph).unwrap();
+/// // 1+ 3+ 2-
+/// ```
+pub fn topological_order<G: HandleGraphRef>(graph: G) -> Result<Vec<Handle>, CycleError> {
+    let (order, left) = oriented_order(graph);
+    if !left.is_empty() {
+        return Err(CycleError {
+            edge: cycle_edge(graph, &left),
+        });
+    }
+    let mut placed: FnvHashSet<NodeId> = FnvHashSet::default();
+    Ok(order
+        .into_iter()
+        .filter(|h| placed.insert(h.id()))
+        .collect())
+}
+
+/// Function that checks if the graph has a cycle of oriented handles,
+/// as [`topological_order`](fn.topological_order.html) does
+pub fn has_cycle<G: HandleGraphRef>(graph: G) -> bool {
+    !oriented_order(graph).1.is_empty()
+}
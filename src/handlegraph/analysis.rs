@@ -0,0 +1,81 @@
+/// This file provides the queries on the degree of the nodes of a graph
+/// used to clean an assembly graph, computed in parallel over the nodes
+use std::collections::BTreeMap;
+
+use rayon::prelude::*;
+
+use crate::handle::{Direction, Handle, NodeId};
+
+use super::{AllHandlesPar, HandleGraphRef};
+
+/// Function that returns the tips of the graph, the oriented handles
+/// without neighbors on their right, sorted: a node with no edges on a
+/// side is a tip with the orientation that has that side on the right,
+/// and an isolated node is a tip with both its orientations
+/// # Example
+/// ```ignore
+/// // 1+ -> 2+, 1+ -> 3+
+/// let tips = tips(&graph);
+/// // 1- 2+ 3+
+/// ```
+pub fn tips<G>(graph: G) -> Vec<Handle>
+where
+    G: HandleGraphRef + AllHandlesPar + Send + Sync,
+{
+    let mut tips: Vec<Handle> = graph
+        .handles_par()
+        .flat_map_iter(|h| vec![h.forward(), h.forward().flip()])
+        .filter(|h| graph.degree(*h, Direction::Right) == 0)
+        .collect();
+    tips.par_sort_unstable();
+    tips
+}
+
+/// Function that returns the ids of the nodes without edges, sorted
+/// # Example
+/// ```ignore
+/// for id in isolated_nodes(&graph) {
+///     println!("{} has no edges", id);
+/// }
+/// ```
+pub fn isolated_nodes<G>(graph: G) -> Vec<NodeId>
+where
+    G: HandleGraphRef + AllHandlesPar + Send + Sync,
+{
+    let mut ids: Vec<NodeId> = graph
+        .handles_par()
+        .filter(|h| total_degree(graph, *h) == 0)
+        .map(|h| h.id())
+        .collect();
+    ids.par_sort_unstable();
+    ids
+}
+
+/// Function that returns how many nodes have each degree, the number
+/// of neighbors on both the sides of a node (a neighbor on both sides
+/// is counted twice), from the smallest degree
+/// # Example
+/// ```ignore
+/// for (degree, nodes) in degree_histogram(&graph) {
+///     println!("{} nodes with degree {}", nodes, degree);
+/// }
+/// ```
+pub fn degree_histogram<G>(graph: G) -> BTreeMap<usize, usize>
+where
+    G: HandleGraphRef + AllHandlesPar + Send + Sync,
+{
+    let degrees: Vec<usize> = graph
+        .handles_par()
+        .map(|h| total_degree(graph, h))
+        .collect();
+    let mut histogram = BTreeMap::new();
+    for degree in degrees {
+        *histogram.entry(degree).or_insert(0) += 1;
+    }
+    histogram
+}
+
+fn total_degree<G: HandleGraphRef>(graph: G, handle: Handle) -> usize {
+    let handle = handle.forward();
+    graph.degree(handle, Direction::Left) + graph.degree(handle, Direction::Right)
+}
@@ -12,9 +12,20 @@ pub type GraphResult<T> = Result<T, GraphError>;
 ///     EdgeNotExist(String, String),
 ///     EdgeAlreadyExist(String, String),
 ///     PathNotExist(String),
+///     PathAlreadyExist(String),
 ///     OrientationNotExists(String),
 ///     PositionNotFound(String, String),
 ///     Unknown,
+///     RepeatedVisit(String, String),
+///     SequenceUnavailable(String),
+///     MissingStepNode(String, String, usize),
+///     NameCollision(String, String),
+///     ChainNotSimple(String, String),
+///     InvalidOffset(String, usize),
+///     InvalidPathStep(String, String),
+///     IdOverflow(String, u64),
+///     SequenceConflict(Vec<String>),
+///     InvalidBatchRecord(String, Box<GraphError>),
 /// }
 /// ```
 #[derive(Debug)]
@@ -25,9 +36,36 @@ pub enum GraphError {
     EdgeNotExist(String, String),
     EdgeAlreadyExist(String, String),
     PathNotExist(String),
+    PathAlreadyExist(String),
     OrientationNotExists(String),
     PositionNotFound(String, String),
     Unknown,
+    RepeatedVisit(String, String),
+    SequenceUnavailable(String),
+    /// A step of a path (its name, the node and the index of the step)
+    /// references a node that doesn't exist
+    MissingStepNode(String, String, usize),
+    /// A segment name (the first one) with the same node id of another
+    /// segment (the second one), a number if the name is the id
+    NameCollision(String, String),
+    /// The handles to combine (at the node, the first one) aren't a
+    /// simple chain, for the reason (the second one)
+    ChainNotSimple(String, String),
+    /// An offset (the second one) that doesn't divide the node (the
+    /// first one) in 2 pieces with a sequence
+    InvalidOffset(String, usize),
+    /// A step of a path (its name, the step as it's written) that
+    /// isn't a segment name followed by its orientation
+    InvalidPathStep(String, String),
+    /// A node (the first one) whose id moved by an offset (the second
+    /// one) would be above the biggest node id
+    IdOverflow(String, u64),
+    /// The nodes with the same id and different sequences in two
+    /// graphs merged with shared ids
+    SequenceConflict(Vec<String>),
+    /// A record of a batch (where it is, as "edge 3" or "path 1, step
+    /// 4") rejected for an error (the second one)
+    InvalidBatchRecord(String, Box<GraphError>),
 }
 
 impl fmt::Display for GraphError {
@@ -50,6 +88,9 @@ impl fmt::Display for GraphError {
             GE::PathNotExist(path) => {
                 write!(f, "The Path ({}) did not exist", path)
             }
+            GE::PathAlreadyExist(path) => {
+                write!(f, "The Path ({}) already exists", path)
+            }
             GE::PositionNotFound(pos_list, lr) => {
                 write!(f, "Not found node {} in {} list", pos_list, lr)
             }
@@ -61,8 +102,80 @@ impl fmt::Display for GraphError {
             GE::Unknown => {
                 write!(f, "Unknown error while operating on the graph")
             }
+            GE::RepeatedVisit(node, path) => {
+                write!(
+                    f,
+                    "The Path ({}) visits the node {} more than once",
+                    path, node
+                )
+            }
+            GE::SequenceUnavailable(why) => {
+                write!(f, "The sequence of the node is not available: {}", why)
+            }
+            GE::MissingStepNode(path, node, index) => write!(
+                f,
+                "The Path ({}) references the missing node {} at step {}",
+                path, node, index
+            ),
+            GE::NameCollision(name, node) => write!(
+                f,
+                "The segment {} has the same id of the segment {}",
+                name, node
+            ),
+            GE::ChainNotSimple(node, why) => {
+                write!(
+                    f,
+                    "The nodes can't be combined at the node {}: {}",
+                    node, why
+                )
+            }
+            GE::InvalidOffset(node, offset) => {
+                write!(f, "The offset {} doesn't divide the node {}", offset, node)
+            }
+            GE::InvalidPathStep(path, step) => {
+                write!(f, "The Path ({}) has the invalid step {}", path, step)
+            }
+            GE::IdOverflow(node, offset) => {
+                write!(f, "The node {} can't be moved by {}", node, offset)
+            }
+            GE::SequenceConflict(nodes) => write!(
+                f,
+                "The nodes {} have different sequences in the merged graphs",
+                nodes.join(", ")
+            ),
+            GE::InvalidBatchRecord(record, why) => write!(f, "{} ({})", why, record),
         }
     }
 }
 
 impl error::Error for GraphError {}
+
+impl GraphError {
+    /// The stable code of the error, see the
+    /// [`report`](../../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        use GraphError as GE;
+        match self {
+            GE::IdAlreadyExist(_) => "G001",
+            GE::EmptySequence => "G002",
+            GE::NodeNotExist(_) => "G003",
+            GE::EdgeNotExist(_, _) => "G004",
+            GE::EdgeAlreadyExist(_, _) => "G005",
+            GE::PathNotExist(_) => "G006",
+            GE::PathAlreadyExist(_) => "G007",
+            GE::OrientationNotExists(_) => "G008",
+            GE::PositionNotFound(_, _) => "G009",
+            GE::Unknown => "G010",
+            GE::RepeatedVisit(_, _) => "G011",
+            GE::SequenceUnavailable(_) => "G012",
+            GE::MissingStepNode(_, _, _) => "G013",
+            GE::NameCollision(_, _) => "G014",
+            GE::ChainNotSimple(_, _) => "G015",
+            GE::InvalidOffset(_, _) => "G016",
+            GE::InvalidPathStep(_, _) => "G017",
+            GE::IdOverflow(_, _) => "G018",
+            GE::SequenceConflict(_) => "G019",
+            GE::InvalidBatchRecord(_, _) => "G020",
+        }
+    }
+}
@@ -68,7 +68,7 @@ where
 
 /// Utility struct for iterating through the edges of a single handle,
 /// for use with EdgesIter
-struct HandleEdgesIter<I>
+pub(crate) struct HandleEdgesIter<I>
 where
     I: Iterator<Item = Handle>,
 {
@@ -81,7 +81,7 @@ impl<I> HandleEdgesIter<I>
 where
     I: Iterator<Item = Handle>,
 {
-    fn new(handle: Handle, left: I, right: I) -> Self {
+    pub(crate) fn new(handle: Handle, left: I, right: I) -> Self {
         Self {
             handle,
             left_neighbors: Some(left),
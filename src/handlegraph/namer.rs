@@ -0,0 +1,76 @@
+use crate::handle::NodeId;
+
+use super::GraphError;
+
+/// Trait denoting that implementors can render a NodeId in a form
+/// meaningful to the user (e.g. the segment name found in the file)
+pub trait NodeNamer {
+    fn display_node(&self, id: NodeId) -> String;
+}
+
+/// Trivial namer that renders every NodeId as its number
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NumericNamer;
+
+impl NodeNamer for NumericNamer {
+    fn display_node(&self, id: NodeId) -> String {
+        id.to_string()
+    }
+}
+
+impl<T: NodeNamer + ?Sized> NodeNamer for &T {
+    fn display_node(&self, id: NodeId) -> String {
+        (**self).display_node(id)
+    }
+}
+
+impl GraphError {
+    /// Render the error like its
+    /// [`Display`](https://doc.rust-lang.org/std/fmt/trait.Display.html)
+    /// implementation, but with every node replaced by the name
+    /// provided by the namer
+    /// # Example
+    /// ```ignore
+    /// let error = GraphError::NodeNotExist("12".to_string());
+    /// println!("{}", error.explain(&NumericNamer));
+    /// // Cannot find the node: 12
+    /// ```
+    pub fn explain<N: NodeNamer>(&self, namer: &N) -> String {
+        self.named(namer).to_string()
+    }
+
+    /// The same error with every node replaced by its name
+    fn named<N: NodeNamer>(&self, namer: &N) -> GraphError {
+        let name = |node: &String| match node.parse::<NodeId>() {
+            Ok(id) => namer.display_node(id),
+            Err(_) => node.clone(),
+        };
+        use GraphError as GE;
+        match self {
+            GE::IdAlreadyExist(id) => GE::IdAlreadyExist(name(id)),
+            GE::NodeNotExist(node) => GE::NodeNotExist(name(node)),
+            GE::EdgeNotExist(l, r) => GE::EdgeNotExist(name(l), name(r)),
+            GE::EdgeAlreadyExist(l, r) => GE::EdgeAlreadyExist(name(l), name(r)),
+            GE::PositionNotFound(node, lr) => GE::PositionNotFound(name(node), lr.clone()),
+            GE::EmptySequence => GE::EmptySequence,
+            GE::PathNotExist(path) => GE::PathNotExist(path.clone()),
+            GE::PathAlreadyExist(path) => GE::PathAlreadyExist(path.clone()),
+            GE::OrientationNotExists(o) => GE::OrientationNotExists(o.clone()),
+            GE::Unknown => GE::Unknown,
+            GE::RepeatedVisit(node, path) => GE::RepeatedVisit(name(node), path.clone()),
+            GE::SequenceUnavailable(why) => GE::SequenceUnavailable(why.clone()),
+            GE::MissingStepNode(path, node, index) => {
+                GE::MissingStepNode(path.clone(), name(node), *index)
+            }
+            GE::NameCollision(segment, node) => GE::NameCollision(segment.clone(), name(node)),
+            GE::ChainNotSimple(node, why) => GE::ChainNotSimple(name(node), why.clone()),
+            GE::InvalidOffset(node, offset) => GE::InvalidOffset(name(node), *offset),
+            GE::InvalidPathStep(path, step) => GE::InvalidPathStep(path.clone(), step.clone()),
+            GE::IdOverflow(node, offset) => GE::IdOverflow(name(node), *offset),
+            GE::SequenceConflict(nodes) => GE::SequenceConflict(nodes.iter().map(name).collect()),
+            GE::InvalidBatchRecord(record, why) => {
+                GE::InvalidBatchRecord(record.clone(), Box::new(why.named(namer)))
+            }
+        }
+    }
+}
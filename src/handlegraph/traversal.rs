@@ -0,0 +1,150 @@
+/// This file provides the breadth-first and depth-first walks of a graph
+/// and its connected components, without recursion, so they work on
+/// graphs of any size
+use std::collections::VecDeque;
+
+use fnv::FnvHashSet;
+
+use crate::handle::{Direction, Handle, NodeId};
+
+use super::HandleGraphRef;
+
+/// Iterator over the handles reached from a handle breadth-first, see
+/// [`bfs`](fn.bfs.html)
+pub struct Bfs<G: HandleGraphRef> {
+    graph: G,
+    queue: VecDeque<Handle>,
+    visited: FnvHashSet<NodeId>,
+}
+
+impl<G: HandleGraphRef> Iterator for Bfs<G> {
+    type Item = Handle;
+
+    fn next(&mut self) -> Option<Handle> {
+        let handle = self.queue.pop_front()?;
+        for next in self.graph.neighbors(handle, Direction::Right) {
+            if self.visited.insert(next.id()) {
+                self.queue.push_back(next);
+            }
+        }
+        Some(handle)
+    }
+}
+
+/// Iterator over the handles reached from a handle depth-first, see
+/// [`dfs`](fn.dfs.html)
+pub struct Dfs<G: HandleGraphRef> {
+    graph: G,
+    stack: Vec<Handle>,
+    visited: FnvHashSet<NodeId>,
+}
+
+impl<G: HandleGraphRef> Iterator for Dfs<G> {
+    type Item = Handle;
+
+    fn next(&mut self) -> Option<Handle> {
+        // a node can be on the stack more than once, reached first by
+        // the nodes visited earlier
+        let handle = loop {
+            let handle = self.stack.pop()?;
+            if self.visited.insert(handle.id()) {
+                break handle;
+            }
+        };
+        let (len, visited) = (self.stack.len(), &self.visited);
+        self.stack.extend(
+            self.graph
+                .neighbors(handle, Direction::Right)
+                .filter(|h| !visited.contains(&h.id())),
+        );
+        // the first neighbor is visited first
+        self.stack[len..].reverse();
+        Some(handle)
+    }
+}
+
+/// Function that returns the handles reached from ```start``` following
+/// the edges to the right, breadth-first, each node once: a node reached
+/// with both its orientations is returned with the first one found.\
+/// The handles are found while the iterator is consumed, there aren't
+/// any if ```start``` isn't a node of the graph
+/// # Example
+/// ```ignore
+/// // 1+ -> 2+, 1+ -> 3+, 2+ -> 4+
+/// let order: Vec<Handle> = bfs(&graph, Handle::pack(1, false)).collect();
+/// // 1+ 2+ 3+ 4+
+/// ```
+pub fn bfs<G: HandleGraphRef>(graph: G, start: Handle) -> Bfs<G> {
+    let mut queue = VecDeque::new();
+    let mut visited = FnvHashSet::default();
+    if graph.has_node(start.id()) {
+        queue.push_back(start);
+        visited.insert(start.id());
+    }
+    Bfs {
+        graph,
+        queue,
+        visited,
+    }
+}
+
+/// Function that returns the handles reached from ```start``` following
+/// the edges to the right, depth-first (in preorder, the neighbors in
+/// the order of the graph), each node once as in [`bfs`](fn.bfs.html)
+/// # Example
+/// ```ignore
+/// // 1+ -> 2+, 1+ -> 3+, 2+ -> 4+
+/// let order: Vec<Handle> = dfs(&graph, Handle::pack(1, false)).collect();
+/// // 1+ 2+ 4+ 3+
+/// ```
+pub fn dfs<G: HandleGraphRef>(graph: G, start: Handle) -> Dfs<G> {
+    let stack = if graph.has_node(start.id()) {
+        vec![start]
+    } else {
+        vec![]
+    };
+    Dfs {
+        graph,
+        stack,
+        visited: FnvHashSet::default(),
+    }
+}
+
+/// Function that returns the connected components of the graph,
+/// following the edges in both directions and with both the
+/// orientations: the ids of each component are sorted, and the
+/// components are sorted by their smallest id
+/// # Example
+/// ```ignore
+/// for component in connected_components(&graph) {
+///     println!("{} nodes", component.len());
+/// }
+/// ```
+pub fn connected_components<G: HandleGraphRef>(graph: G) -> Vec<Vec<NodeId>> {
+    let mut ids: Vec<NodeId> = graph.handles().map(|h| h.id()).collect();
+    ids.sort_unstable();
+    let mut visited: FnvHashSet<NodeId> = FnvHashSet::default();
+    let mut components = vec![];
+    for id in ids {
+        if !visited.insert(id) {
+            continue;
+        }
+        let mut component = vec![];
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            component.push(id);
+            let handle = Handle::pack(id, false);
+            let near = graph
+                .neighbors(handle, Direction::Left)
+                .chain(graph.neighbors(handle, Direction::Right));
+            for h in near {
+                if visited.insert(h.id()) {
+                    stack.push(h.id());
+                }
+            }
+        }
+        component.sort_unstable();
+        components.push(component);
+    }
+    components
+}
@@ -1,13 +1,65 @@
+mod adjacency;
+pub(crate) mod attach;
+mod bounds;
+pub mod builder;
+mod bulk;
+mod combine;
+pub mod diff;
+mod duplicate;
+mod edges;
+mod fasta;
+pub mod features;
+pub(crate) mod fidelity;
+mod generation;
 pub mod graph;
+mod groups;
+mod interner;
+pub mod lazy;
+pub mod matrix;
+mod merge;
+mod names;
 pub mod node;
 pub mod path;
-
-pub use self::graph::HashGraph;
-pub use self::node::Node;
+mod records;
+mod removal;
+mod rename;
+mod reverse;
+pub mod sampling;
+mod serialize;
+mod shared;
+mod similarity;
+mod sorted;
+mod subgraph;
+pub mod subpath;
+mod tags;
+mod unitigs;
+
+pub use self::adjacency::{BrokenAdjacency, PathOrientationAnomaly, RepairMode};
+pub use self::attach::{AttachError, AttachReport, LengthMismatch};
+pub use self::bounds::IdBoundsMismatch;
+pub use self::builder::{GraphBuilder, UnresolvedReference};
+pub use self::bulk::{BulkEditor, EditReport};
+pub use self::diff::{GraphDiff, LocalDiffReport, LocalDiffSummary, NodeChange, NodeDiff};
+pub use self::features::NodeFeatures;
+pub use self::graph::{BuildReport, ConsistencyError, HashGraph, HashGraphConfig, DISPLAY_LIMIT};
+pub use self::groups::ContainmentRecord;
+pub use self::lazy::{LazySequence, LazySequenceStore};
+pub use self::matrix::{AdjacencyMode, CsrAdjacency};
+pub use self::merge::{MergePolicy, MergeReport, DEFAULT_MERGE_SUFFIX};
+pub use self::node::{Node, SequenceRef};
 pub use self::path::{Path, PathId, PathStep};
+pub use self::records::NodeRecord;
+pub use self::removal::PathRepair;
+pub use self::sampling::WalkEnd;
+pub use self::shared::SharedHashGraph;
+pub use self::subpath::PathSnapshot;
+
+use self::generation::Changes;
+use self::interner::{resolve_sequence, store_sequence};
 
 use bstr::{BString, ByteSlice};
 use rayon::iter::*;
+use std::collections::VecDeque;
 
 use crate::{
     handle::{Direction, Edge, Handle, NodeId},
@@ -55,6 +107,11 @@ impl<'a> AllEdges for &'a HashGraph {
     fn edges(self) -> Self::Edges {
         EdgesIter::new(self)
     }
+
+    #[inline]
+    fn edge_count(self) -> usize {
+        self.edge_count
+    }
 }
 
 /// parallel version for AllEdges Iter
@@ -85,9 +142,10 @@ impl<'a> HandleNeighbors for &'a HashGraph {
     #[inline]
     fn degree(self, handle: Handle, dir: Direction) -> usize {
         let n = self.get_node_unchecked(&handle.id());
-        match dir {
-            Direction::Right => n.right_edges.len(),
-            Direction::Left => n.left_edges.len(),
+        // as in neighbors, the sides of a reverse handle are swapped
+        match (dir, handle.is_reverse()) {
+            (Direction::Left, true) | (Direction::Right, false) => n.right_edges.len(),
+            (Direction::Left, false) | (Direction::Right, true) => n.left_edges.len(),
         }
     }
 }
@@ -106,14 +164,14 @@ impl<'a> HandleSequences for &'a HashGraph {
 
     #[inline]
     fn sequence_iter(self, handle: Handle) -> Self::Sequence {
-        let seq: &[u8] = &self.get_node_unchecked(&handle.id()).sequence.as_ref();
+        let seq: &[u8] = self.node_sequence(self.get_node_unchecked(&handle.id()));
         SequenceIter::new(seq.iter().copied(), handle.is_reverse())
     }
 
     fn sequence(self, handle: Handle) -> Vec<u8> {
-        let seq: &[u8] = &self.get_node_unchecked(&handle.id()).sequence.as_ref();
+        let seq: &[u8] = self.node_sequence(self.get_node_unchecked(&handle.id()));
         if handle.is_reverse() {
-            dna::rev_comp(seq)
+            dna::rev_comp_slice(seq)
         } else {
             seq.into()
         }
@@ -121,7 +179,7 @@ impl<'a> HandleSequences for &'a HashGraph {
 
     #[inline]
     fn node_len(self, handle: Handle) -> usize {
-        self.get_node_unchecked(&handle.id()).sequence.len()
+        self.node_sequence_len(self.get_node_unchecked(&handle.id()))
     }
 }
 
@@ -147,9 +205,9 @@ impl HandleGraph for HashGraph {
     }
 }
 
-impl<'a> HandleGraphRef for &'a HashGraph {
+impl HandleGraphRef for &HashGraph {
     fn total_length(self) -> usize {
-        self.graph.values().map(|n| n.sequence.len()).sum()
+        self.graph.values().map(|n| self.node_sequence_len(n)).sum()
     }
 }
 
@@ -160,21 +218,23 @@ impl ModdableHandleGraph for HashGraph {
         seq: &[u8],
     ) -> Result<bool, GraphError> {
         let node_id: NodeId = node_id.into();
-        let seq: BString = BString::from(seq);
-        if let Some(n) = self.graph.get_mut(&node_id) {
-            if seq.trim().is_empty() {
-                // error if the sequence is empty or blank
-                Err(GraphError::EmptySequence)
-            } else if n.sequence == seq {
-                // no need to update
-                Ok(true)
-            } else {
-                // update the sequence value of node
-                *self.graph.get_mut(&node_id).unwrap().sequence = seq.to_vec();
-                Ok(true)
-            }
+        let n = match self.graph.get_mut(&node_id) {
+            Some(n) => n,
+            None => return Err(GraphError::NodeNotExist(node_id.to_string())),
+        };
+        if seq.trim().is_empty() {
+            // error if the sequence is empty or blank
+            Err(GraphError::EmptySequence)
+        } else if resolve_sequence(&self.interner, &n.sequence) == seq {
+            // no need to update
+            Ok(true)
         } else {
-            Err(GraphError::NodeNotExist(node_id.to_string()))
+            // update the sequence value of node, interning it again
+            // if the graph interns the sequences
+            n.sequence = store_sequence(&mut self.interner, seq);
+            self.placeholders.remove(&node_id);
+            self.record(Changes::SEQUENCES);
+            Ok(true)
         }
     }
 
@@ -185,145 +245,143 @@ impl ModdableHandleGraph for HashGraph {
         right_node: Option<Handle>,
     ) -> Result<bool, GraphError> {
         let Edge(left, right) = old_edge;
+        if !self.graph.contains_key(&left.id())
+            || !self.graph.contains_key(&right.id())
+            || !self.has_edge(left, right)
+        {
+            return Err(GraphError::EdgeNotExist(
+                left.id().to_string(),
+                right.id().to_string(),
+            ));
+        }
         let left_node = left_node.unwrap_or(left);
         let right_node = right_node.unwrap_or(right);
+        // the new ends are checked before removing the old edge
+        for end in [left_node, right_node].iter() {
+            if !self.graph.contains_key(&end.id()) {
+                return Err(GraphError::NodeNotExist(end.id().to_string()));
+            }
+        }
         if old_edge == Edge(left_node, right_node) {
             // no need to update
             Ok(true)
         } else {
             // update Edge
-            self.remove_edge(old_edge)?;
-            self.create_edge(Edge(left_node, right_node))?;
-            Ok(true)
+            let overlap = self.edge_overlap(old_edge);
+            let tags = self.edge_tags(old_edge).to_vec();
+            self.as_one_change(|graph| {
+                graph.remove_edge(old_edge)?;
+                if let Err(error) = graph.create_edge(Edge(left_node, right_node)) {
+                    // the old edge is put back
+                    graph.create_edge(old_edge)?;
+                    if overlap > 0 {
+                        graph.set_edge_overlap(old_edge, overlap)?;
+                    }
+                    graph.set_edge_tags(old_edge, tags);
+                    return Err(error);
+                }
+                Ok(true)
+            })
         }
     }
 }
 
 impl SubtractiveHandleGraph for HashGraph {
     fn remove_handle<T: Into<NodeId>>(&mut self, node: T) -> Result<bool, GraphError> {
-        let node_id: NodeId = node.into();
-        if let Some(node) = self.graph.remove(&node_id) {
-            // delete all the occurrencies in the edge list of node.id()
-            let l = node.left_edges;
-            let r = node.right_edges;
-            l.iter().for_each(|i| {
-                let left = self.graph.get_mut(&i.id()).unwrap();
-                if i.is_reverse() {
-                    if let Some(ll) = left
-                        .left_edges
-                        .par_iter()
-                        .position_any(|x| x.id() == node_id)
-                    {
-                        left.left_edges.swap_remove(ll);
-                    }
-                } else if let Some(lr) = left
-                    .right_edges
-                    .par_iter()
-                    .position_any(|x| x.id() == node_id)
-                {
-                    left.right_edges.swap_remove(lr);
-                }
-            });
-            r.iter().for_each(|i| {
-                let right = self.graph.get_mut(&i.id()).unwrap();
-                if i.is_reverse() {
-                    if let Some(rr) = right
-                        .right_edges
-                        .par_iter()
-                        .position_any(|x| x.id() == node_id)
-                    {
-                        right.right_edges.swap_remove(rr);
-                    }
-                } else if let Some(rl) = right
-                    .left_edges
-                    .par_iter()
-                    .position_any(|x| x.id() == node_id)
-                {
-                    right.left_edges.swap_remove(rl);
-                }
-            });
-            self.clone().paths().for_each(|path| {
-                let nodes = &self.paths.get_mut(&path).unwrap().nodes;
-                if nodes.par_iter().any(|x| x.id() == node_id) {
-                    self.paths.remove(&path);
-                }
-            });
-            Ok(true)
-        } else {
-            Err(GraphError::NodeNotExist(node_id.to_string()))
-        }
+        self.remove_handle_with(node.into(), PathRepair::DropPath)
     }
 
-    fn remove_edge(&mut self, Edge(l, r): Edge) -> Result<bool, GraphError> {
-        // delete all the occurrencies of edge found in graph
-        if let Some(left) = self.graph.get_mut(&l.id()) {
-            if l.is_reverse() {
-                if let Some(ll) = left
-                    .left_edges
-                    .par_iter()
-                    .position_any(|x| x.id() == r.id())
-                {
-                    left.left_edges.swap_remove(ll);
-                }
-            } else if let Some(lr) = left
-                .right_edges
-                .par_iter()
-                .position_any(|x| x.id() == r.id())
-            {
-                left.right_edges.swap_remove(lr);
-            }
-        } else {
+    fn remove_edge(&mut self, edge: Edge) -> Result<bool, GraphError> {
+        let Edge(l, r) = edge;
+        if !self.graph.contains_key(&l.id()) || !self.graph.contains_key(&r.id()) {
             return Err(GraphError::EdgeNotExist(
                 l.id().to_string(),
                 r.id().to_string(),
             ));
         }
-        if let Some(right) = self.graph.get_mut(&r.id()) {
-            if r.is_reverse() {
-                if let Some(rr) = right
-                    .right_edges
-                    .par_iter()
-                    .position_any(|x| x.id() == l.id())
-                {
-                    right.right_edges.swap_remove(rr);
-                }
-            } else if let Some(rl) = right
-                .left_edges
-                .par_iter()
-                .position_any(|x| x.id() == l.id())
-            {
-                right.left_edges.swap_remove(rl);
+        // the edge is removed whatever the end it's written from
+        let Edge(l, r) = edge.canonical();
+        // the edges found on the 2 nodes before and after removing it, as
+        // only the ends with the same id are removed
+        let ends = [l.id(), r.id()];
+        let found_before = self.edges_found_on(&ends);
+        let edge = Edge(l, r);
+        let mut changes = Changes::NONE;
+        if self.forget_edge(&edge) {
+            changes = Changes::TOPOLOGY;
+        }
+        // only this oriented pair is removed, from both its ends: the edge
+        // is stored on the side of l as r, and on the side of r as the
+        // flip of l, once for a reversing self loop
+        let mut unlink = |node: NodeId, right_side: bool, neighbor: Handle| {
+            let node = self.graph.get_mut(&node).unwrap();
+            let edges = if right_side {
+                &mut node.right_edges
+            } else {
+                &mut node.left_edges
+            };
+            if let Some(ix) = edges.iter().position(|h| *h == neighbor) {
+                edges.swap_remove(ix);
+                changes = Changes::TOPOLOGY;
             }
-        } else {
-            return Err(GraphError::EdgeNotExist(
-                l.id().to_string(),
-                r.id().to_string(),
-            ));
+        };
+        unlink(l.id(), !l.is_reverse(), r);
+        if l != r.flip() {
+            unlink(r.id(), r.is_reverse(), l.flip());
+        }
+        if changes == Changes::NONE {
+            return Ok(true);
         }
-        self.clone().paths().for_each(|path| {
-            let nodes = &self.paths.get_mut(&path).unwrap().nodes;
-            if let Some(l) = nodes.par_iter().position_any(|x| x.id() == l.id()) {
-                if let Some(r) = nodes.par_iter().position_any(|x| x.id() == r.id()) {
-                    if r == l + 1 {
-                        self.paths.remove(&path);
+        self.edge_count =
+            (self.edge_count + self.edges_found_on(&ends)).saturating_sub(found_before);
+        let walked: Vec<PathId> = self
+            .paths
+            .iter()
+            .filter(|(_, path)| {
+                path.nodes
+                    .iter()
+                    .zip(path.nodes.iter().skip(1))
+                    .any(|(from, to)| Edge::edge_handle(*from, *to) == edge)
+            })
+            .map(|(path_id, _)| *path_id)
+            .collect();
+        // the paths walking through the edge are removed with their
+        // occurrences and their names
+        for path_id in walked {
+            if let Some(path) = self.paths.remove(&path_id) {
+                for handle in path.nodes.iter() {
+                    if let Some(node) = self.graph.get_mut(&handle.id()) {
+                        node.occurrences.remove(&path_id);
                     }
                 }
+                self.path_id.remove(path.name.as_slice());
+                changes = changes | Changes::PATHS;
             }
-        });
+        }
+        self.record(changes);
         Ok(true)
     }
 
     fn clear_graph(&mut self) {
+        self.record(Changes::ALL);
         self.max_id = NodeId::from(0);
-        self.min_id = NodeId::from(std::u64::MAX);
+        self.min_id = NodeId::from(u64::MAX);
         // clears the map, removing all key-value pairs
         self.graph.clear();
         self.path_id.clear();
         self.paths.clear();
+        self.overlaps.clear();
+        self.edge_tags.clear();
+        self.placeholders.clear();
+        self.edge_count = 0;
+        if let Some(interner) = self.interner.as_mut() {
+            *interner = Default::default();
+        }
 
         self.graph.shrink_to_fit();
         self.path_id.shrink_to_fit();
         self.paths.shrink_to_fit();
+        self.check_bounds();
     }
 }
 
@@ -345,67 +403,185 @@ impl AdditiveHandleGraph for HashGraph {
         if self.get_node(&id).is_some() {
             Err(GraphError::IdAlreadyExist(id.to_string()))
         } else {
-            self.graph.insert(id, Node::new(seq));
-            self.max_id = std::cmp::max(self.max_id, id);
-            self.min_id = std::cmp::min(self.min_id, id);
-            Ok(Handle::pack(id, false))
+            let sequence = self.make_sequence(seq);
+            self.insert_node(id, sequence)
         }
     }
 
-    fn create_edge(&mut self, Edge(left, right): Edge) -> Result<bool, GraphError> {
-        let add_edge = {
-            let left_node = self
-                .graph
-                .get(&left.id())
-                .expect("Node doesn't exist for the given handle");
+    fn create_edge(&mut self, edge: Edge) -> Result<bool, GraphError> {
+        let Edge(left, right) = edge;
+        // both the nodes are checked before changing any of them
+        if !self.graph.contains_key(&left.id()) {
+            return Err(GraphError::NodeNotExist(left.id().to_string()));
+        }
+        if !self.graph.contains_key(&right.id()) {
+            return Err(GraphError::NodeNotExist(right.id().to_string()));
+        }
 
-            None == left_node.right_edges.iter().find(|&&h| h == right)
+        // an edge and its reverse are the same edge, added once
+        let Edge(left, right) = edge.canonical();
+        let left_node = &self.graph[&left.id()];
+
+        // the edges leaving the reverse handle are on the left side
+        let edges = if left.is_reverse() {
+            &left_node.left_edges
+        } else {
+            &left_node.right_edges
         };
+        if edges.contains(&right) {
+            return Ok(false);
+        }
 
-        if add_edge {
-            let left_node = self
-                .graph
-                .get_mut(&left.id())
-                .expect("Node doesn't exist for the given handle");
-            if left.is_reverse() {
-                left_node.left_edges.push(right);
+        let left_node = self.graph.get_mut(&left.id()).unwrap();
+        if left.is_reverse() {
+            left_node.left_edges.push(right);
+        } else {
+            left_node.right_edges.push(right);
+        }
+        if left != right.flip() {
+            let right_node = self.graph.get_mut(&right.id()).unwrap();
+            if right.is_reverse() {
+                right_node.right_edges.push(left.flip());
             } else {
-                left_node.right_edges.push(right);
-            }
-            if left != right.flip() {
-                let right_node = self
-                    .graph
-                    .get_mut(&right.id())
-                    .expect("Node doesn't exist for the given handle");
-                if right.is_reverse() {
-                    right_node.right_edges.push(left.flip());
-                } else {
-                    right_node.left_edges.push(left.flip());
-                }
+                right_node.left_edges.push(left.flip());
             }
         }
+        self.edge_count += 1;
+        self.record(Changes::TOPOLOGY);
         Ok(true)
     }
 }
 
+impl HashGraph {
+    /// Add a node with a sequence already stored, as ```create_handle```
+    pub(crate) fn insert_node(
+        &mut self,
+        id: NodeId,
+        sequence: SequenceRef,
+    ) -> Result<Handle, GraphError> {
+        if self.graph.contains_key(&id) {
+            return Err(GraphError::IdAlreadyExist(id.to_string()));
+        }
+        self.graph.insert(id, Node::with_sequence(sequence));
+        self.record(Changes::TOPOLOGY);
+        self.max_id = std::cmp::max(self.max_id, id);
+        self.min_id = std::cmp::min(self.min_id, id);
+        self.check_bounds();
+        Ok(Handle::pack(id, false))
+    }
+
+    /// The index of a new step of a path (```index```, or the end of the
+    /// path), checked before the path is changed: both the path and the
+    /// node of the step have to exist
+    fn check_step(
+        &self,
+        path_id: &PathId,
+        handle: Handle,
+        index: Option<usize>,
+    ) -> Result<usize, GraphError> {
+        let path = self
+            .paths
+            .get(path_id)
+            .ok_or_else(|| GraphError::PathNotExist(path_id.to_string()))?;
+        let index = index.unwrap_or(path.nodes.len());
+        if self.graph.contains_key(&handle.id()) {
+            Ok(index)
+        } else {
+            Err(GraphError::MissingStepNode(
+                path.name.to_string(),
+                handle.id().to_string(),
+                index,
+            ))
+        }
+    }
+}
+
 impl MutableHandleGraph for HashGraph {
-    fn divide_handle(&mut self, handle: Handle, mut offsets: Vec<usize>) -> Vec<Handle> {
-        let mut result = vec![handle];
-        let node_len = self.node_len(handle);
-        let sequence = self.sequence(handle);
+    fn divide_handle(
+        &mut self,
+        handle: Handle,
+        offsets: Vec<usize>,
+    ) -> Result<Vec<Handle>, GraphError> {
+        self.as_one_change(|graph| graph.divide_node(handle, offsets))
+    }
 
-        let fwd_handle = handle.forward();
+    fn combine_handles(&mut self, handles: &[Handle]) -> Result<Handle, GraphError> {
+        self.as_one_change(|graph| graph.combine_nodes(handles))
+    }
 
-        // Push the node length as a last offset to make constructing
-        // the ranges nicer
-        offsets.push(node_len);
+    fn apply_orientation(&mut self, handle: Handle) -> Result<Handle, GraphError> {
+        let node = self
+            .get_node(&handle.id())
+            .ok_or_else(|| GraphError::NodeNotExist(handle.id().to_string()))?;
+        if !handle.is_reverse() {
+            return Ok(handle);
+        }
+        // everything is checked before changing the graph
+        self.try_node_sequence(node)?;
+        for (path_id, keys) in node.occurrences.iter() {
+            let path = self.paths.get(path_id);
+            let on_node = |key: &usize| {
+                path.and_then(|p| p.nodes.get(p.step_index(*key)))
+                    .map(|h| h.id())
+                    == Some(handle.id())
+            };
+            if !keys.iter().all(on_node) {
+                let path = path.map_or_else(|| path_id.to_string(), |p| p.name.to_string());
+                return Err(GraphError::PositionNotFound(handle.id().to_string(), path));
+            }
+        }
+        let mut changes = Changes::TOPOLOGY | Changes::SEQUENCES;
+        if !node.occurrences.is_empty() {
+            changes = changes | Changes::PATHS;
+        }
+        self.record(changes);
+        Ok(self.orient_forward(handle))
+    }
+}
 
-        let fwd_offsets: Vec<usize> = if handle.is_reverse() {
-            offsets.iter().map(|o| node_len - o).collect()
+impl HashGraph {
+    /// The body of ```divide_handle```, that counts it as a single change
+    fn divide_node(
+        &mut self,
+        handle: Handle,
+        mut offsets: Vec<usize>,
+    ) -> Result<Vec<Handle>, GraphError> {
+        if !self.graph.contains_key(&handle.id()) {
+            return Err(GraphError::NodeNotExist(handle.id().to_string()));
+        }
+        let node_len = self.node_len(handle);
+        // every piece needs a sequence, so the offsets are checked before
+        // changing the graph
+        let mut previous = 0;
+        for offset in offsets.iter() {
+            if *offset <= previous || *offset >= node_len {
+                return Err(GraphError::InvalidOffset(handle.id().to_string(), *offset));
+            }
+            previous = *offset;
+        }
+        if offsets.is_empty() {
+            return Ok(vec![handle]);
+        }
+        self.record(Changes::TOPOLOGY | Changes::SEQUENCES);
+        // the pieces aren't the declared segment anymore
+        self.placeholders.remove(&handle.id());
+        let fwd_handle = handle.forward();
+        // the pieces are made on the forward strand, the first one on the
+        // original node
+        let mut result = vec![fwd_handle];
+        let sequence = self.sequence(fwd_handle);
+
+        // the offsets of a reverse handle are counted from the other end
+        let mut fwd_offsets: Vec<usize> = if handle.is_reverse() {
+            offsets.drain(..).rev().map(|o| node_len - o).collect()
         } else {
             offsets
         };
 
+        // Push the node length as a last offset to make constructing
+        // the ranges nicer
+        fwd_offsets.push(node_len);
+
         // staggered zip of the offsets with themselves to make the ranges
         let ranges: Vec<_> = fwd_offsets
             .iter()
@@ -416,30 +592,51 @@ impl MutableHandleGraph for HashGraph {
         // TODO it should be possible to do this without creating new
         // strings and collecting into a vec
 
-        let subseqs: Vec<BString> = ranges.into_iter().map(|r| sequence[r].into()).collect();
+        let subseqs: Vec<BString> = ranges.iter().map(|r| sequence[r.clone()].into()).collect();
 
         for seq in subseqs {
-            match self.append_handle(&seq) {
-                Ok(h) => result.push(h),
-                Err(why) => println!("Error: {}", why),
-            };
+            result.push(self.append_handle(&seq)?);
         }
 
+        // the containments of the node move to the piece they're on
+        let pieces: Vec<(NodeId, std::ops::Range<usize>)> = std::iter::once(0..fwd_offsets[0])
+            .chain(ranges)
+            .zip(result.iter())
+            .map(|(r, h)| (h.id(), r))
+            .collect();
+        self.divide_grouped_node(fwd_handle.id(), &pieces);
+
+        // the edges found on the node and on its neighbors on the right,
+        // the only ones whose edges move
+        let last = *result.last().unwrap();
+        let mut ends: Vec<NodeId> = vec![fwd_handle.id(), last.id()];
+        ends.extend(
+            self.get_node_unchecked(&fwd_handle.id())
+                .right_edges
+                .iter()
+                .map(|h| h.id()),
+        );
+        let found_before = self.edges_found_on(&ends);
+
         // move the outgoing edges to the last new segment
         // empty the existing right edges of the original node
         let mut orig_rights =
-            std::mem::take(&mut self.get_node_mut(&handle.id()).unwrap().right_edges);
+            std::mem::take(&mut self.get_node_mut(&fwd_handle.id()).unwrap().right_edges);
+        // a self loop from the end of the node back to its end now goes
+        // from the end of the last segment to itself, and it's stored once
+        for h in orig_rights.iter_mut() {
+            if *h == fwd_handle.flip() {
+                *h = last.flip();
+            }
+        }
 
-        let new_rights = &mut self
-            .get_node_mut(&result.last().unwrap().id())
-            .unwrap()
-            .right_edges;
+        let new_rights = &mut self.get_node_mut(&last.id()).unwrap().right_edges;
         // and swap with the new right edges
         std::mem::swap(&mut orig_rights, new_rights);
 
         // shrink the sequence of the starting handle
-        let orig_node = &mut self.get_node_mut(&handle.id()).unwrap();
-        orig_node.sequence = orig_node.sequence[0..fwd_offsets[0]].into();
+        let orig_sequence = self.make_sequence(&sequence[0..fwd_offsets[0]]);
+        self.get_node_mut(&fwd_handle.id()).unwrap().sequence = orig_sequence;
 
         // update backwards references
         // first collect all the handles whose nodes we need to update
@@ -463,40 +660,78 @@ impl MutableHandleGraph for HashGraph {
             }
         }
 
+        self.edge_count =
+            (self.edge_count + self.edges_found_on(&ends)).saturating_sub(found_before);
+
         // create edges between the new segments
         for (this, next) in result.iter().zip(result.iter().skip(1)) {
-            match self.create_edge(Edge(*this, *next)) {
-                Ok(_) => (),
-                Err(why) => println!("Error: {}", why),
-            }
+            self.create_edge(Edge(*this, *next))?;
         }
 
-        // update paths and path occurrences
-
-        // TODO this is probably not correct, and it's silly to clone
-        // the results all the time
-        let affected_paths: Vec<(i64, usize)> = self
+        // update paths and path occurrences: every visit to the node
+        // goes through all the pieces, backwards if the node is reversed
+        let pieces = result.clone();
+        let affected_paths: Vec<i64> = self
             .get_node_unchecked(&handle.id())
             .occurrences
-            .iter()
-            .map(|(k, v)| (*k, *v))
+            .keys()
+            .copied()
             .collect();
 
-        for (path_id, ix) in affected_paths.into_iter() {
-            let step = PathStep::Step(path_id, ix);
-            self.rewrite_segment(&step, &step, result.clone());
+        if !affected_paths.is_empty() {
+            self.record(Changes::PATHS);
+        }
+        for path_id in affected_paths {
+            let path = self.paths.get_mut(&path_id).unwrap();
+            let mut nodes = VecDeque::with_capacity(path.nodes.len() + pieces.len());
+            for step in path.nodes.iter() {
+                if step.id() != handle.id() {
+                    nodes.push_back(*step);
+                } else if step.is_reverse() {
+                    nodes.extend(pieces.iter().rev().map(|h| h.flip()));
+                } else {
+                    nodes.extend(pieces.iter().copied());
+                }
+            }
+            path.nodes = nodes;
+            self.index_steps(&path_id);
         }
 
-        result
+        // the pieces in the order of the handle
+        if handle.is_reverse() {
+            Ok(result.into_iter().rev().map(|h| h.flip()).collect())
+        } else {
+            Ok(result)
+        }
     }
 
-    fn apply_orientation(&mut self, handle: Handle) -> Handle {
-        if !handle.is_reverse() {
-            return handle;
+    /// Index again the steps of a path in the occurrences of their
+    /// nodes, after the steps changed, with the keys starting again from
+    /// 0; the nodes that left the path are left to the caller
+    pub(crate) fn index_steps(&mut self, path_id: &PathId) {
+        let path = match self.paths.get_mut(path_id) {
+            Some(path) => path,
+            None => return,
+        };
+        path.first_key = 0;
+        for step in path.nodes.iter() {
+            if let Some(node) = self.graph.get_mut(&step.id()) {
+                node.occurrences.remove(path_id);
+            }
+        }
+        for (ix, step) in path.nodes.iter().enumerate() {
+            if let Some(node) = self.graph.get_mut(&step.id()) {
+                node.occurrences.entry(*path_id).or_default().push(ix);
+            }
         }
+    }
 
-        let node = self.get_node_mut(&handle.id()).unwrap();
-        node.sequence = dna::rev_comp(node.sequence.as_slice()).into();
+    /// The body of ```apply_orientation``` for a reverse handle
+    fn orient_forward(&mut self, handle: Handle) -> Handle {
+        self.flip_grouped_nodes(|id| id == handle.id());
+        let sequence = self.sequence(handle);
+        let sequence = self.make_sequence(&sequence);
+        self.get_node_mut(&handle.id()).unwrap().sequence = sequence;
 
         let edges = {
             let node = self.get_node(&handle.id()).unwrap();
@@ -529,10 +764,13 @@ impl MutableHandleGraph for HashGraph {
         let occurrences = &self.graph.get(&handle.id()).unwrap().occurrences;
         let paths = &mut self.paths;
 
-        for (path_id, index) in occurrences.iter() {
-            let path = paths.get_mut(&path_id).unwrap();
-            let step = path.nodes.get_mut(*index).unwrap();
-            *step = step.flip();
+        for (path_id, keys) in occurrences.iter() {
+            let path = paths.get_mut(path_id).unwrap();
+            for key in keys.iter() {
+                let index = path.step_index(*key);
+                let step = path.nodes.get_mut(index).unwrap();
+                *step = step.flip();
+            }
         }
 
         handle.flip()
@@ -602,12 +840,12 @@ impl PathHandleGraph for HashGraph {
 
     fn path_bases_len(&self, path_handle: &Self::PathHandle) -> Option<usize> {
         let path = self.paths.get(path_handle)?;
-        Some(path.bases_len(&self.graph))
+        Some(path.bases_len(self))
     }
 
     fn position_of_step(&self, step: &Self::StepHandle) -> Option<usize> {
         let path = self.paths.get(&step.path_id())?;
-        path.position_of_step(&self.graph, step)
+        path.position_of_step(self, step)
     }
 
     fn step_at_position(
@@ -616,16 +854,37 @@ impl PathHandleGraph for HashGraph {
         pos: usize,
     ) -> Option<Self::StepHandle> {
         let path = self.paths.get(path_handle)?;
-        Some(path.step_at_position(&self.graph, pos))
+        Some(path.step_at_position(self, pos))
+    }
+
+    fn path_position_to_handle(
+        &self,
+        path_handle: &Self::PathHandle,
+        pos: usize,
+    ) -> Option<(Handle, usize)> {
+        self.paths.get(path_handle)?.position_to_handle(self, pos)
+    }
+
+    fn path_range_sequence(
+        &self,
+        path_handle: &Self::PathHandle,
+        start: usize,
+        end: usize,
+    ) -> Option<Vec<u8>> {
+        self.paths
+            .get(path_handle)?
+            .range_sequence(self, start, end)
     }
 
     fn destroy_path(&mut self, path: &Self::PathHandle) {
-        if let Some(p) = self.paths.get(&path) {
+        if let Some(p) = self.paths.get(path) {
             for handle in p.nodes.iter() {
                 let node: &mut Node = self.graph.get_mut(&handle.id()).unwrap();
                 node.occurrences.remove(path);
             }
-            self.paths.remove(&path);
+            self.path_id.remove(p.name.as_slice());
+            self.paths.remove(path);
+            self.record(Changes::PATHS);
         }
     }
 
@@ -658,10 +917,12 @@ impl PathHandleGraph for HashGraph {
     }
 
     fn create_path_handle(&mut self, name: &[u8], is_circular: bool) -> Self::PathHandle {
-        let path_id = self.paths.len() as i64;
+        // after a path is destroyed the number of paths can be a used id
+        let path_id = self.paths.keys().max().map_or(0, |max| max + 1);
         let path = Path::new(name, path_id, is_circular);
         self.path_id.insert(name.into(), path_id);
         self.paths.insert(path_id, path);
+        self.record(Changes::PATHS);
         path_id
     }
 
@@ -670,28 +931,32 @@ impl PathHandleGraph for HashGraph {
         path_id: &Self::PathHandle,
         to_append: Handle,
     ) -> Result<Self::StepHandle, GraphError> {
-        let path: &mut Path = match self.paths.get_mut(path_id) {
-            Some(path) => path,
-            None => return Err(GraphError::PathNotExist(path_id.to_string())),
-        };
-        path.nodes.push(to_append);
-        let step = (*path_id, path.nodes.len() - 1);
-        let node: &mut Node = self.graph.get_mut(&to_append.id()).unwrap();
-        node.occurrences.insert(step.0, step.1);
-        Ok(PathStep::Step(*path_id, path.nodes.len() - 1))
+        let index = self.check_step(path_id, to_append, None)?;
+        let path: &mut Path = self.paths.get_mut(path_id).unwrap();
+        path.nodes.push_back(to_append);
+        let key = path.step_key(index);
+        let node: &mut Node = self.get_node_mut(&to_append.id()).unwrap();
+        node.occurrences.entry(*path_id).or_default().push(key);
+        self.record(Changes::PATHS);
+        Ok(PathStep::Step(*path_id, index))
     }
 
-    fn prepend_step(&mut self, path_id: &Self::PathHandle, to_prepend: Handle) -> Self::StepHandle {
+    fn prepend_step(
+        &mut self,
+        path_id: &Self::PathHandle,
+        to_prepend: Handle,
+    ) -> Result<Self::StepHandle, GraphError> {
+        self.check_step(path_id, to_prepend, Some(0))?;
         let path: &mut Path = self.paths.get_mut(path_id).unwrap();
-        // update occurrences in nodes already in the graph
-        for h in path.nodes.iter() {
-            let node: &mut Node = self.graph.get_mut(&h.id()).unwrap();
-            *node.occurrences.get_mut(path_id).unwrap() += 1;
-        }
-        path.nodes.insert(0, to_prepend);
-        let node: &mut Node = self.graph.get_mut(&to_prepend.id()).unwrap();
-        node.occurrences.insert(*path_id, 0);
-        PathStep::Step(*path_id, 0)
+        // the steps already on the path keep their keys, the new one
+        // takes the key before the first
+        path.nodes.push_front(to_prepend);
+        path.first_key = path.first_key.wrapping_sub(1);
+        let key = path.first_key;
+        let node: &mut Node = self.get_node_mut(&to_prepend.id()).unwrap();
+        node.occurrences.entry(*path_id).or_default().insert(0, key);
+        self.record(Changes::PATHS);
+        Ok(PathStep::Step(*path_id, 0))
     }
 
     fn rewrite_segment(
@@ -718,35 +983,91 @@ impl PathHandleGraph for HashGraph {
         let l = step_index(begin);
         let r = step_index(end);
 
-        let range = l..=r;
-
-        // first delete the occurrences of the nodes in the range
-        for handle in self
-            .paths
-            .get(&path_id)
-            .unwrap()
+        // the steps after the range move by the change of its length:
+        // their keys change if they're fewer than the steps before it,
+        // otherwise the keys of the steps before it change with the
+        // first key, so they keep their index
+        let path = &self.paths[&path_id];
+        let (removed, added) = (r - l + 1, new_segment.len());
+        let before = removed != added && l < path_len - r - 1;
+        let moved = match (removed == added, before) {
+            (true, _) => 0..0,
+            (false, true) => 0..l,
+            (false, false) => r + 1..path_len,
+        };
+        let old_first = path.first_key;
+        let first_key = if before {
+            old_first.wrapping_add(removed).wrapping_sub(added)
+        } else {
+            old_first
+        };
+        let touched: fnv::FnvHashSet<NodeId> = path
             .nodes
-            .iter()
-            .skip(l)
-            .take(r - l + 1)
-        {
-            let node: &mut Node = self.graph.get_mut(&handle.id()).unwrap();
-            node.occurrences.remove(&path_id);
+            .range(l..=r)
+            .chain(path.nodes.range(moved))
+            .chain(new_segment.iter())
+            .map(|h| h.id())
+            .collect();
+        let rekey = |key: usize| {
+            let ix = key.wrapping_sub(old_first);
+            let ix = match ix {
+                ix if ix < l => ix,
+                ix if ix <= r => return None,
+                ix => ix - removed + added,
+            };
+            Some(ix.wrapping_add(first_key))
+        };
+        for id in touched.iter() {
+            if let Some(keys) = self
+                .graph
+                .get_mut(id)
+                .and_then(|node| node.occurrences.get_mut(&path_id))
+            {
+                *keys = keys.iter().filter_map(|key| rekey(*key)).collect();
+            }
         }
 
-        // get a &mut to the path's vector of handles
-        let handles: &mut Vec<Handle> = &mut self.paths.get_mut(&path_id).unwrap().nodes;
-
-        let r = l + new_segment.len();
-        // replace the range of the path's handle vector with the new segment
-        handles.splice(range, new_segment);
+        // replace the range of the path's handles with the new segment
+        let path: &mut Path = self.paths.get_mut(&path_id).unwrap();
+        path.first_key = first_key;
+        let nodes = &mut path.nodes;
+        if removed == added {
+            for (step, handle) in nodes.range_mut(l..=r).zip(new_segment.iter()) {
+                *step = *handle;
+            }
+        } else if before {
+            let head: Vec<Handle> = nodes.drain(..l).collect();
+            nodes.drain(..removed);
+            for handle in head.iter().chain(new_segment.iter()).rev() {
+                nodes.push_front(*handle);
+            }
+        } else {
+            let tail: Vec<Handle> = nodes.drain(r + 1..).collect();
+            nodes.truncate(l);
+            nodes.extend(new_segment.iter().copied().chain(tail));
+        }
 
-        // update occurrences
-        for (ix, handle) in self.paths.get(&path_id).unwrap().nodes.iter().enumerate() {
-            let node: &mut Node = self.graph.get_mut(&handle.id()).unwrap();
-            node.occurrences.insert(path_id, ix);
+        for (ix, handle) in new_segment.iter().enumerate() {
+            if let Some(node) = self.graph.get_mut(&handle.id()) {
+                let key = (l + ix).wrapping_add(first_key);
+                node.occurrences.entry(path_id).or_default().push(key);
+            }
         }
+        // the visits of a node in the order of the path
+        for id in touched.iter() {
+            if let Some(node) = self.graph.get_mut(id) {
+                match node.occurrences.get_mut(&path_id) {
+                    Some(keys) if keys.is_empty() => {
+                        node.occurrences.remove(&path_id);
+                    }
+                    Some(keys) => keys.sort_unstable_by_key(|key| key.wrapping_sub(first_key)),
+                    None => (),
+                }
+            }
+        }
+        self.record(Changes::PATHS);
 
+        let r = l + added;
         // return the new beginning and end step handles: even if the
         // input steps were Front and/or End, the output steps exist
         // on the path
@@ -762,7 +1083,11 @@ impl PathHandleGraph for HashGraph {
         handle: Handle,
     ) -> Box<dyn Iterator<Item = Self::StepHandle> + 'a> {
         let node: &Node = self.get_node_unchecked(&handle.id());
-        Box::new(node.occurrences.iter().map(|(k, v)| PathStep::Step(*k, *v)))
+        Box::new(node.occurrences.iter().flat_map(move |(k, v)| {
+            let path = &self.paths[k];
+            v.iter()
+                .map(move |key| PathStep::Step(*k, path.step_index(*key)))
+        }))
     }
 
     fn steps<'a>(
@@ -778,17 +1103,49 @@ impl PathHandleGraph for HashGraph {
         )
     }
 
+    fn steps_with_positions<'a>(
+        &'a self,
+        path_handle: &'a Self::PathHandle,
+    ) -> Box<dyn Iterator<Item = (Self::StepHandle, Handle, usize)> + 'a> {
+        let path = self.get_path_unchecked(path_handle);
+        // the position of the next step, the length of a node is the same
+        // on both its strands; as position_of_step, there's no position
+        // after a step on a missing node
+        let mut next = Some(0);
+        Box::new(
+            path.nodes
+                .iter()
+                .enumerate()
+                .map_while(move |(ix, handle)| {
+                    let position = next?;
+                    next = self
+                        .get_node(&handle.id())
+                        .map(|node| position + self.node_sequence_len(node));
+                    Some((PathStep::Step(*path_handle, ix), *handle, position))
+                }),
+        )
+    }
+
     fn remove_step<T: Into<NodeId>>(&mut self, name: &[u8], node: T) -> Result<bool, GraphError> {
-        if self.has_path(name) {
-            let path_handle = self.name_to_path_handle(name).unwrap();
-            let node = node.into();
-            if let Some(p) = self.paths.get_mut(&path_handle) {
-                p.nodes.retain(|x| x.id() != node);
-            }
-            Ok(true)
-        } else {
-            Err(GraphError::PathNotExist(name.to_str().unwrap().to_string()))
+        let path_handle = match self.name_to_path_handle(name) {
+            Some(path_handle) => path_handle,
+            None => return Err(GraphError::PathNotExist(name.to_str_lossy().into_owned())),
+        };
+        let node = node.into();
+        if !self.paths[&path_handle]
+            .nodes
+            .iter()
+            .any(|h| h.id() == node)
+        {
+            return Err(GraphError::NodeNotExist(node.to_string()));
+        }
+        let removed = std::iter::once(node).collect();
+        self.remove_steps_of(&path_handle, &removed);
+        if let Some(n) = self.graph.get_mut(&node) {
+            n.occurrences.remove(&path_handle);
         }
+        self.record(Changes::PATHS);
+        Ok(true)
     }
 
     fn modify_step<T: Into<NodeId>>(
@@ -797,22 +1154,48 @@ impl PathHandleGraph for HashGraph {
         old_node: T,
         new_node: Handle,
     ) -> Result<bool, GraphError> {
-        if self.has_path(name) {
-            let path_handle = self.name_to_path_handle(name).unwrap();
-            let old_node = old_node.into();
-            if let Some(p) = self.paths.get_mut(&path_handle) {
-                let path = p.nodes.clone();
-                for (id, &handle) in path.iter().enumerate() {
-                    if handle.id() == old_node {
-                        p.nodes.remove(id);
-                        p.nodes.insert(id, new_node);
-                    }
-                }
+        let path_handle = match self.name_to_path_handle(name) {
+            Some(path_handle) => path_handle,
+            None => return Err(GraphError::PathNotExist(name.to_str_lossy().into_owned())),
+        };
+        let old_node = old_node.into();
+        let first = match self.paths[&path_handle]
+            .nodes
+            .iter()
+            .position(|h| h.id() == old_node)
+        {
+            Some(first) => first,
+            None => return Err(GraphError::NodeNotExist(old_node.to_string())),
+        };
+        if !self.graph.contains_key(&new_node.id()) {
+            return Err(GraphError::NodeNotExist(new_node.id().to_string()));
+        }
+        let p = self.paths.get_mut(&path_handle).unwrap();
+        let mut changed = false;
+        for handle in p.nodes.iter_mut().skip(first) {
+            if handle.id() == old_node && *handle != new_node {
+                *handle = new_node;
+                changed = true;
             }
-            Ok(true)
-        } else {
-            Err(GraphError::PathNotExist(name.to_str().unwrap().to_string()))
         }
+        if changed {
+            // the steps keep their index: the old node leaves the path,
+            // and the new node gets its steps among the ones it had
+            if new_node.id() != old_node {
+                let first_key = p.first_key;
+                let keys = self
+                    .graph
+                    .get_mut(&old_node)
+                    .and_then(|node| node.occurrences.remove(&path_handle))
+                    .unwrap_or_default();
+                let node = self.graph.get_mut(&new_node.id()).unwrap();
+                let steps = node.occurrences.entry(path_handle).or_default();
+                steps.extend(keys);
+                steps.sort_unstable_by_key(|key| key.wrapping_sub(first_key));
+            }
+            self.record(Changes::PATHS);
+        }
+        Ok(true)
     }
 
     fn rewrite_path(
@@ -822,18 +1205,20 @@ impl PathHandleGraph for HashGraph {
     ) -> Result<bool, GraphError> {
         // update occurrencies in path
         if let Some(path_handle) = self.name_to_path_handle(path_name) {
-            self.destroy_path(&path_handle);
-            let len: usize = sequence_of_id.len();
-            let mut x: usize = 0;
-            let path = self.create_path_handle(path_name, false);
-            while x < len {
-                match self.append_step(&path, sequence_of_id[x]) {
-                    Ok(_) => (),
-                    Err(why) => return Err(why),
-                };
-                x += 1;
-            }
-            Ok(true)
+            self.as_one_change(|graph| {
+                graph.destroy_path(&path_handle);
+                let len: usize = sequence_of_id.len();
+                let mut x: usize = 0;
+                let path = graph.create_path_handle(path_name, false);
+                while x < len {
+                    match graph.append_step(&path, sequence_of_id[x]) {
+                        Ok(_) => (),
+                        Err(why) => return Err(why),
+                    };
+                    x += 1;
+                }
+                Ok(true)
+            })
         } else {
             Err(GraphError::PathNotExist(
                 path_name.to_str().unwrap().to_string(),
@@ -0,0 +1,270 @@
+use bstr::BString;
+use fnv::FnvHashSet;
+
+use crate::{
+    handle::{Edge, Handle},
+    handlegraph::*,
+    mutablehandlegraph::*,
+    pathgraph::PathHandleGraph,
+};
+
+use super::{HashGraph, PathId};
+
+/// Two consecutive steps of a path that aren't connected by an edge
+/// ```ignore
+/// pub struct BrokenAdjacency {
+///     pub path_id: PathId,
+///     pub path_name: BString,
+///     pub step: usize,
+///     pub left: Handle,
+///     pub right: Handle,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrokenAdjacency {
+    pub path_id: PathId,
+    pub path_name: BString,
+    /// Index of the step of ```left```, ```right``` is the next one (or
+    /// the first one, for the last step of a circular path)
+    pub step: usize,
+    pub left: Handle,
+    pub right: Handle,
+}
+
+/// A step of a path that isn't connected to the steps next to it, while
+/// it would be if it was visited with the other orientation: the classic
+/// strand error of a P (or O) line
+/// ```ignore
+/// pub struct PathOrientationAnomaly {
+///     pub path: BString,
+///     pub index: usize,
+///     pub suggested_fix: Handle,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathOrientationAnomaly {
+    /// The name of the path
+    pub path: BString,
+    /// Index of the step
+    pub index: usize,
+    /// The step with the orientation that connects it
+    pub suggested_fix: Handle,
+}
+
+impl BrokenAdjacency {
+    /// The stable code of the diagnostic, see the
+    /// [`report`](../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        "V001"
+    }
+}
+
+impl PathOrientationAnomaly {
+    /// The stable code of the diagnostic, see the
+    /// [`report`](../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        "V002"
+    }
+}
+
+impl std::fmt::Display for PathOrientationAnomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fix = self.suggested_fix;
+        write!(
+            f,
+            "Path {} step {}: no edge connects {}{}, but {}{} would be connected",
+            self.path,
+            self.index,
+            fix.id(),
+            if fix.is_reverse() { "+" } else { "-" },
+            fix.id(),
+            if fix.is_reverse() { "-" } else { "+" },
+        )
+    }
+}
+
+/// How [`repair_path_adjacencies`](../graph/struct.HashGraph.html#method.repair_path_adjacencies)
+/// fixes the broken adjacencies
+/// ```ignore
+/// pub enum RepairMode {
+///     CreateMissingEdges,
+///     SplitPaths,
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairMode {
+    /// Add the edges missing between the steps
+    CreateMissingEdges,
+    /// Break the paths at each missing edge
+    SplitPaths,
+}
+
+impl HashGraph {
+    /// Function that returns the consecutive steps of the paths that
+    /// aren't connected by an edge, sorted by path id and step, checking
+    /// also the last and the first step of the circular paths.\
+    /// The steps are compared as canonical edges, so an edge stored
+    /// from the other strand is still found
+    /// # Example
+    /// ```ignore
+    /// for broken in graph.broken_path_adjacencies() {
+    ///     println!("{} {}: {} {}", broken.path_name, broken.step, broken.left, broken.right);
+    /// }
+    /// ```
+    pub fn broken_path_adjacencies(&self) -> Vec<BrokenAdjacency> {
+        let edges: FnvHashSet<Edge> = self.edges().collect();
+        let mut path_ids: Vec<PathId> = self.paths.keys().copied().collect();
+        path_ids.sort();
+
+        let mut broken = vec![];
+        for path_id in path_ids {
+            let path = self.get_path_unchecked(&path_id);
+            let steps = &path.nodes;
+            let pairs = if path.is_circular {
+                steps.len()
+            } else {
+                steps.len().saturating_sub(1)
+            };
+            for step in 0..pairs {
+                let (left, right) = (steps[step], steps[(step + 1) % steps.len()]);
+                if !edges.contains(&Edge::edge_handle(left, right)) {
+                    broken.push(BrokenAdjacency {
+                        path_id,
+                        path_name: path.name.clone(),
+                        step,
+                        left,
+                        right,
+                    });
+                }
+            }
+        }
+        broken
+    }
+
+    /// Function that returns the steps of the paths that aren't connected
+    /// by an edge to the step before or after them, but would be connected
+    /// to both visiting the node with the other orientation, sorted by
+    /// path id and step.\
+    /// The steps not connected with either orientation are left to
+    /// [`broken_path_adjacencies`](#method.broken_path_adjacencies)
+    /// # Example
+    /// ```ignore
+    /// for anomaly in graph.path_orientation_anomalies() {
+    ///     println!("{}", anomaly);
+    /// }
+    /// ```
+    pub fn path_orientation_anomalies(&self) -> Vec<PathOrientationAnomaly> {
+        let edges: FnvHashSet<Edge> = self.edges().collect();
+        let connected =
+            |left: Handle, right: Handle| edges.contains(&Edge::edge_handle(left, right));
+        let mut path_ids: Vec<PathId> = self.paths.keys().copied().collect();
+        path_ids.sort();
+
+        let mut anomalies = vec![];
+        for path_id in path_ids {
+            let path = self.get_path_unchecked(&path_id);
+            let steps = &path.nodes;
+            let len = steps.len();
+            for (index, step) in steps.iter().enumerate() {
+                // the steps before and after, if any
+                let before = if index > 0 {
+                    Some(steps[index - 1])
+                } else if path.is_circular && len > 1 {
+                    Some(steps[len - 1])
+                } else {
+                    None
+                };
+                let after = if index + 1 < len {
+                    Some(steps[index + 1])
+                } else if path.is_circular && len > 1 {
+                    Some(steps[0])
+                } else {
+                    None
+                };
+                let joined = |h: Handle| {
+                    (
+                        before.is_none_or(|b| connected(b, h)),
+                        after.is_none_or(|a| connected(h, a)),
+                    )
+                };
+                if joined(*step) != (true, true) && joined(step.flip()) == (true, true) {
+                    anomalies.push(PathOrientationAnomaly {
+                        path: path.name.clone(),
+                        index,
+                        suggested_fix: step.flip(),
+                    });
+                }
+            }
+        }
+        anomalies
+    }
+
+    /// Function that fixes the
+    /// [`broken_path_adjacencies`](#method.broken_path_adjacencies) and
+    /// returns how many they were:
+    /// * ```CreateMissingEdges``` adds the missing edges
+    /// * ```SplitPaths``` replaces every path with a missing edge with
+    ///   its fragments, named ```name:0```, ```name:1```, ... in the order
+    ///   of the path; a circular path is opened after a missing edge, so
+    ///   its fragments are all linear
+    /// # Example
+    /// ```ignore
+    /// graph.repair_path_adjacencies(RepairMode::SplitPaths);
+    /// assert!(graph.broken_path_adjacencies().is_empty());
+    /// ```
+    pub fn repair_path_adjacencies(&mut self, mode: RepairMode) -> usize {
+        self.as_one_change(|graph| graph.repair_adjacencies(mode))
+    }
+
+    fn repair_adjacencies(&mut self, mode: RepairMode) -> usize {
+        let broken = self.broken_path_adjacencies();
+        match mode {
+            RepairMode::CreateMissingEdges => {
+                for b in broken.iter() {
+                    let _ = self.create_edge(Edge::edge_handle(b.left, b.right));
+                }
+            }
+            RepairMode::SplitPaths => {
+                let mut ix = 0;
+                while ix < broken.len() {
+                    let path_id = broken[ix].path_id;
+                    let gaps: Vec<usize> = broken[ix..]
+                        .iter()
+                        .take_while(|b| b.path_id == path_id)
+                        .map(|b| b.step)
+                        .collect();
+                    ix += gaps.len();
+                    self.split_path_at(&path_id, &gaps);
+                }
+            }
+        }
+        broken.len()
+    }
+
+    /// Replace a path with its fragments, breaking it after each of the
+    /// (sorted) steps in ```gaps```
+    fn split_path_at(&mut self, path_id: &PathId, gaps: &[usize]) {
+        let path = self.get_path_unchecked(path_id);
+        let (name, mut steps) = (path.name.clone(), path.nodes.clone());
+        let mut ends: Vec<usize> = gaps.iter().map(|g| g + 1).collect();
+        if path.is_circular {
+            // start right after the last gap, the last fragment ends there
+            let start = ends.pop().unwrap() % steps.len();
+            steps.rotate_left(start);
+            let len = steps.len();
+            ends = ends.iter().map(|e| (e + len - start) % len).collect();
+        }
+        ends.push(steps.len());
+
+        self.destroy_path(path_id);
+        let mut begin = 0;
+        for (fragment, end) in ends.into_iter().enumerate() {
+            let fragment_name = format!("{}:{}", name, fragment);
+            let fragment_id = self.create_path_handle(fragment_name.as_bytes(), false);
+            for step in steps.range(begin..end) {
+                let _ = self.append_step(&fragment_id, *step);
+            }
+            begin = end;
+        }
+    }
+}
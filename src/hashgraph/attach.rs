@@ -0,0 +1,283 @@
+/// This file provides the sequences of the segments written without
+/// them (as ```*```), read from a FASTA file after the graph is built
+use bstr::{BString, ByteSlice};
+use fnv::{FnvHashMap, FnvHashSet};
+use std::{error, fmt, path::Path};
+
+use crate::{gfa::optional::OptField, handle::NodeId, util::gzip::gunzip};
+
+use super::{generation::Changes, HashGraph};
+
+/// The declared length of a GFA1 segment whose sequence is ```*```,
+/// from its ```LN``` tag (0 without it), None if it has a sequence
+pub(crate) fn gfa1_placeholder(sequence: &[u8], optional: &[OptField]) -> Option<usize> {
+    if sequence != b"*" {
+        return None;
+    }
+    let length = optional
+        .iter()
+        .find(|t| &t.tag == b"LN")
+        .and_then(|t| t.value.to_str().ok()?.parse().ok());
+    Some(length.unwrap_or(0))
+}
+
+/// The declared length of a GFA2 segment whose sequence is ```*```, None
+/// if it has a sequence
+pub(crate) fn gfa2_placeholder(sequence: &[u8], slen: u64) -> Option<usize> {
+    if sequence == b"*" {
+        Some(slen as usize)
+    } else {
+        None
+    }
+}
+
+/// A FASTA record whose length isn't the declared length of its node,
+/// that is left without its sequence
+/// ```ignore
+/// pub struct LengthMismatch {
+///     pub id: NodeId,
+///     pub declared: usize,
+///     pub found: usize,
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthMismatch {
+    pub id: NodeId,
+    pub declared: usize,
+    pub found: usize,
+}
+
+impl LengthMismatch {
+    /// The stable code of the diagnostic, see the
+    /// [`report`](../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        "V013"
+    }
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The segment {} has length {}, but its FASTA record has {} bases",
+            self.id, self.declared, self.found
+        )
+    }
+}
+
+/// What [`attach_sequences_from_fasta`](graph/struct.HashGraph.html#method.attach_sequences_from_fasta)
+/// did with the records of the file
+/// ```ignore
+/// pub struct AttachReport {
+///     pub matched: Vec<NodeId>,
+///     pub missing: Vec<NodeId>,
+///     pub length_mismatches: Vec<LengthMismatch>,
+///     pub ignored_records: Vec<BString>,
+/// }
+/// ```
+/// * ```matched``` are the nodes that got their sequence, sorted
+/// * ```missing``` are the nodes without a sequence and without a
+///   record, sorted
+/// * ```length_mismatches``` are the records of a node without a
+///   sequence that don't have its declared length, sorted by node
+/// * ```ignored_records``` are the names of the records that aren't a
+///   node without a sequence, in the order of the file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttachReport {
+    pub matched: Vec<NodeId>,
+    pub missing: Vec<NodeId>,
+    pub length_mismatches: Vec<LengthMismatch>,
+    pub ignored_records: Vec<BString>,
+}
+
+/// Type encapsulating the errors of
+/// [`attach_sequences_from_fasta`](graph/struct.HashGraph.html#method.attach_sequences_from_fasta)
+/// ```ignore
+/// pub enum AttachError {
+///     Io(std::io::Error),
+///     InvalidFasta(usize),
+///     DuplicateRecord(BString),
+/// }
+/// ```
+#[derive(Debug)]
+pub enum AttachError {
+    Io(std::io::Error),
+    /// The (1-based) line that isn't part of a record: bases before the
+    /// first header, a header without a name or a record without bases
+    InvalidFasta(usize),
+    /// The name of two records
+    DuplicateRecord(BString),
+}
+
+impl AttachError {
+    /// The stable code of the error, see the
+    /// [`report`](../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        match self {
+            AttachError::Io(_) => "A001",
+            AttachError::InvalidFasta(_) => "A002",
+            AttachError::DuplicateRecord(_) => "A003",
+        }
+    }
+}
+
+impl fmt::Display for AttachError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttachError::Io(why) => write!(f, "{}", why),
+            AttachError::InvalidFasta(line) => {
+                write!(f, "The line {} isn't part of a FASTA record", line)
+            }
+            AttachError::DuplicateRecord(name) => {
+                write!(f, "The FASTA record {} is found more times", name)
+            }
+        }
+    }
+}
+
+impl error::Error for AttachError {}
+
+impl From<std::io::Error> for AttachError {
+    fn from(why: std::io::Error) -> Self {
+        AttachError::Io(why)
+    }
+}
+
+/// The records of a FASTA file, as the first word of their header and
+/// their bases
+fn fasta_records(text: &[u8]) -> Result<Vec<(BString, Vec<u8>)>, AttachError> {
+    let mut records: Vec<(BString, Vec<u8>, usize)> = vec![];
+    for (ix, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if let Some(header) = line.strip_prefix(b">") {
+            match header.fields().next() {
+                Some(name) => records.push((BString::from(name), vec![], ix + 1)),
+                None => return Err(AttachError::InvalidFasta(ix + 1)),
+            }
+        } else if !line.is_empty() {
+            match records.last_mut() {
+                Some((_, bases, _)) => bases.extend_from_slice(line),
+                None => return Err(AttachError::InvalidFasta(ix + 1)),
+            }
+        }
+    }
+    let mut names: FnvHashSet<&BString> = FnvHashSet::default();
+    for (name, bases, line) in records.iter() {
+        if bases.is_empty() {
+            return Err(AttachError::InvalidFasta(*line));
+        }
+        if !names.insert(name) {
+            return Err(AttachError::DuplicateRecord(name.clone()));
+        }
+    }
+    Ok(records
+        .into_iter()
+        .map(|(name, bases, _)| (name, bases))
+        .collect())
+}
+
+impl HashGraph {
+    /// Function that returns the nodes of the segments written without
+    /// their sequence (```*```), that are still without it, sorted.\
+    /// Such a node has a sequence of ```N```s as long as its declared
+    /// length (the ```slen``` of GFA2, the ```LN``` tag of GFA1), or
+    /// ```*``` if the length isn't known; it gets its sequence from
+    /// [`attach_sequences_from_fasta`](#method.attach_sequences_from_fasta),
+    /// or from ```modify_handle```
+    pub fn nodes_missing_sequence(&self) -> Vec<NodeId> {
+        let mut ids: Vec<NodeId> = self.placeholders.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Function that reads the sequences of the nodes without one from
+    /// a FASTA file, compressed with gzip or not.\
+    /// A record belongs to the node whose segment name is the first
+    /// word of its header, as
+    /// [`node_id_of_name`](#method.node_id_of_name) finds it, and it
+    /// replaces the placeholder of the node only if it has the declared
+    /// length of the segment (when it was declared); the other records
+    /// are listed in the [`AttachReport`](../struct.AttachReport.html).\
+    /// The file is read whole before changing the graph, so nothing
+    /// changes if it isn't a valid FASTA file
+    /// # Example
+    /// ```ignore
+    /// let mut graph = parse_file_to_graph("assembly.gfa2")?;
+    /// let report = graph.attach_sequences_from_fasta("contigs.fa.gz")?;
+    /// assert_eq!(graph.nodes_missing_sequence(), report.missing);
+    /// ```
+    pub fn attach_sequences_from_fasta<P: AsRef<Path>>(
+        &mut self,
+        fasta: P,
+    ) -> Result<AttachReport, AttachError> {
+        let mut bytes = std::fs::read(fasta.as_ref())?;
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            bytes = gunzip(&bytes)?;
+        }
+        let records = fasta_records(&bytes)?;
+
+        let mut report = AttachReport::default();
+        for (name, bases) in records {
+            let waiting = self
+                .node_id_of_name(&name)
+                .and_then(|id| Some((id, *self.placeholders.get(&id)?)));
+            let (id, declared) = match waiting {
+                Some(waiting) => waiting,
+                None => {
+                    report.ignored_records.push(name);
+                    continue;
+                }
+            };
+            if declared > 0 && declared != bases.len() {
+                report.length_mismatches.push(LengthMismatch {
+                    id,
+                    declared,
+                    found: bases.len(),
+                });
+                continue;
+            }
+            let sequence = self.make_sequence(&bases);
+            self.graph.get_mut(&id).unwrap().sequence = sequence;
+            self.placeholders.remove(&id);
+            report.matched.push(id);
+        }
+        if !report.matched.is_empty() {
+            self.record(Changes::SEQUENCES);
+        }
+        report.matched.sort_unstable();
+        report.length_mismatches.sort_unstable_by_key(|m| m.id);
+        let mismatched: FnvHashSet<NodeId> =
+            report.length_mismatches.iter().map(|m| m.id).collect();
+        report.missing = self
+            .nodes_missing_sequence()
+            .into_iter()
+            .filter(|id| !mismatched.contains(id))
+            .collect();
+        Ok(report)
+    }
+
+    /// Keep the declared length of the segments without a sequence,
+    /// whose node exists
+    pub(crate) fn mark_placeholders<I>(&mut self, placeholders: I)
+    where
+        I: IntoIterator<Item = (NodeId, usize)>,
+    {
+        for (id, declared) in placeholders {
+            if self.graph.contains_key(&id) {
+                self.placeholders.insert(id, declared);
+            }
+        }
+    }
+
+    /// Move the placeholders of the renamed nodes to their new ids
+    pub(crate) fn move_placeholders(&mut self, mapping: &FnvHashMap<NodeId, NodeId>) {
+        if self.placeholders.is_empty() {
+            return;
+        }
+        let moved: Vec<(NodeId, usize)> = mapping
+            .iter()
+            .filter_map(|(old, new)| Some((*new, self.placeholders.remove(old)?)))
+            .collect();
+        self.placeholders.extend(moved);
+    }
+}
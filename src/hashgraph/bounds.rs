@@ -0,0 +1,147 @@
+use std::fmt;
+
+use crate::{
+    handle::NodeId,
+    util::report::{ReportItem, Severity},
+};
+
+use super::HashGraph;
+
+/// The ```min_id``` and ```max_id``` of a graph that don't match its
+/// nodes, as after changing the public fields directly
+/// ```ignore
+/// pub struct IdBoundsMismatch {
+///     pub min_id: NodeId,
+///     pub max_id: NodeId,
+///     pub expected: Option<(NodeId, NodeId)>,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdBoundsMismatch {
+    pub min_id: NodeId,
+    pub max_id: NodeId,
+    /// The smallest and the largest id of the nodes, None if there
+    /// aren't nodes
+    pub expected: Option<(NodeId, NodeId)>,
+}
+
+impl IdBoundsMismatch {
+    /// The stable code of the diagnostic, see the
+    /// [`report`](../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        "V008"
+    }
+}
+
+impl fmt::Display for IdBoundsMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The id bounds are min {} and max {}, but ",
+            self.min_id, self.max_id
+        )?;
+        match self.expected {
+            Some((min, max)) => write!(f, "the nodes go from {} to {}", min, max),
+            None => write!(f, "the graph has no nodes"),
+        }
+    }
+}
+
+impl From<&IdBoundsMismatch> for ReportItem {
+    fn from(mismatch: &IdBoundsMismatch) -> Self {
+        ReportItem::new(mismatch.code(), Severity::Error, mismatch.to_string())
+    }
+}
+
+impl HashGraph {
+    /// Function that returns the smallest and the largest node id, or
+    /// None if the graph has no nodes (when ```min_id``` and ```max_id```
+    /// are ```u64::MAX``` and 0)
+    /// # Example
+    /// ```ignore
+    /// if let Some((min, max)) = graph.id_bounds() {
+    ///     println!("ids from {} to {}", min, max);
+    /// }
+    /// ```
+    #[inline]
+    pub fn id_bounds(&self) -> Option<(NodeId, NodeId)> {
+        if self.graph.is_empty() {
+            None
+        } else {
+            Some((self.min_id, self.max_id))
+        }
+    }
+
+    /// Function that sets ```min_id``` and ```max_id``` from the ids of
+    /// the nodes, in O(n).\
+    /// The methods of the graph keep the bounds exact, so it's needed
+    /// only after changing the public fields directly
+    pub fn recompute_bounds(&mut self) {
+        let (min, max) = self
+            .bounds_of_nodes()
+            .unwrap_or((NodeId::from(u64::MAX), NodeId::from(0)));
+        self.min_id = min;
+        self.max_id = max;
+    }
+
+    /// Function that checks ```min_id``` and ```max_id``` against the ids
+    /// of the nodes, in O(n), see also
+    /// [`validate`](#method.validate)
+    pub fn id_bounds_mismatch(&self) -> Option<IdBoundsMismatch> {
+        let expected = self.bounds_of_nodes();
+        let found = match expected {
+            Some(_) => Some((self.min_id, self.max_id)),
+            None if self.min_id == NodeId::from(u64::MAX) && self.max_id == NodeId::from(0) => None,
+            None => Some((self.min_id, self.max_id)),
+        };
+        if found == expected {
+            None
+        } else {
+            Some(IdBoundsMismatch {
+                min_id: self.min_id,
+                max_id: self.max_id,
+                expected,
+            })
+        }
+    }
+
+    /// Function that returns the diagnostics of the graph, ready for a
+    /// [`to_json_report`](../util/report/fn.to_json_report.html): the
+    /// [`id bounds`](#method.id_bounds_mismatch) that don't match the
+    /// nodes, the [`broken adjacencies`](#method.broken_path_adjacencies)
+    /// and the
+    /// [`orientation anomalies`](#method.path_orientation_anomalies) of
+    /// the paths
+    /// # Example
+    /// ```ignore
+    /// let items = graph.validate();
+    /// print!("{}", to_json_report(&items));
+    /// ```
+    pub fn validate(&self) -> Vec<ReportItem> {
+        let mut items: Vec<ReportItem> = vec![];
+        items.extend(self.id_bounds_mismatch().iter().map(ReportItem::from));
+        items.extend(self.broken_path_adjacencies().iter().map(ReportItem::from));
+        items.extend(
+            self.path_orientation_anomalies()
+                .iter()
+                .map(ReportItem::from),
+        );
+        items
+    }
+
+    fn bounds_of_nodes(&self) -> Option<(NodeId, NodeId)> {
+        let mut ids = self.graph.keys().copied();
+        let first = ids.next()?;
+        Some(ids.fold((first, first), |(min, max), id| {
+            (std::cmp::min(min, id), std::cmp::max(max, id))
+        }))
+    }
+
+    /// Check that the bounds are exact after a method that adds, removes
+    /// or renames nodes; only in the tests of the crate, as it's O(n)
+    #[inline]
+    pub(crate) fn check_bounds(&self) {
+        #[cfg(test)]
+        debug_assert_eq!(self.id_bounds_mismatch(), None);
+    }
+}
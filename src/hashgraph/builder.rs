@@ -0,0 +1,355 @@
+/// This file provides the incremental construction of an HashGraph, where
+/// edges and paths can be declared before the segments they reference
+use bstr::BString;
+use fnv::{FnvHashMap, FnvHashSet};
+use std::fmt;
+
+use crate::{
+    gfa::{gfa1::Header, optional::OptField},
+    handle::{Edge, Handle, NodeId},
+    handlegraph::*,
+    mutablehandlegraph::*,
+    pathgraph::PathHandleGraph,
+};
+
+use super::{groups::ContainmentRecord, lazy::LazySequence, HashGraph, PathId, SequenceRef};
+
+/// An edge or a path that references segments that were never declared
+/// ```ignore
+/// pub enum UnresolvedReference {
+///     Edge(Edge, Vec<NodeId>),
+///     Path(BString, Vec<NodeId>),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnresolvedReference {
+    /// The edge, with the missing segments
+    Edge(Edge, Vec<NodeId>),
+    /// The name of the path, with the missing segments
+    Path(BString, Vec<NodeId>),
+}
+
+impl UnresolvedReference {
+    /// The stable code of the diagnostic, see the
+    /// [`report`](../../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        match self {
+            UnresolvedReference::Edge(_, _) => "V003",
+            UnresolvedReference::Path(_, _) => "V004",
+        }
+    }
+
+    /// The segments referenced but never declared, sorted
+    pub fn missing(&self) -> &[NodeId] {
+        match self {
+            UnresolvedReference::Edge(_, missing) => missing,
+            UnresolvedReference::Path(_, missing) => missing,
+        }
+    }
+}
+
+impl fmt::Display for UnresolvedReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let missing: Vec<String> = self.missing().iter().map(|id| id.to_string()).collect();
+        match self {
+            UnresolvedReference::Edge(Edge(l, r), _) => write!(
+                f,
+                "Edge {}{} -> {}{} references the missing segments {}",
+                l.id(),
+                if l.is_reverse() { "-" } else { "+" },
+                r.id(),
+                if r.is_reverse() { "-" } else { "+" },
+                missing.join(", ")
+            ),
+            UnresolvedReference::Path(name, _) => write!(
+                f,
+                "Path {} references the missing segments {}",
+                name,
+                missing.join(", ")
+            ),
+        }
+    }
+}
+
+enum Record {
+    /// An edge with the overlap and the tags of its link
+    Edge(Edge, usize, Vec<OptField>),
+    Path(PathId, Vec<Handle>),
+}
+
+/// Record waiting for some segments, with the number of (distinct)
+/// segments still missing
+struct Pending {
+    record: Record,
+    missing: usize,
+}
+
+/// Builder of an HashGraph that receives the records one at a time, in
+/// any order.\
+/// An edge or a path referencing a segment not declared yet is kept in
+/// a queue and it's added to the graph as soon as all its segments are
+/// declared, so the graph built doesn't depend on the order of the lines
+/// of the file.\
+/// The paths keep the order in which they were declared, even when
+/// their steps are resolved later, and the subsets and the containments
+/// are added when the graph is finished, with the nodes it has.
+/// # Example
+/// ```ignore
+/// let mut builder = GraphBuilder::new();
+/// builder.add_edge(Edge(Handle::new(1, Orientation::Forward), Handle::new(2, Orientation::Forward)));
+/// builder.add_segment(1, b"A").unwrap();
+/// builder.add_segment(2, b"C").unwrap();
+/// let graph = builder.finish_strict().unwrap();
+/// ```
+#[derive(Default)]
+pub struct GraphBuilder {
+    graph: HashGraph,
+    pending: Vec<Option<Pending>>,
+    waiting: FnvHashMap<NodeId, Vec<usize>>,
+    subsets: Vec<(BString, Vec<u64>)>,
+    containments: Vec<ContainmentRecord>,
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Function that adds a segment, and every queued edge or path that
+    /// was waiting only for it
+    pub fn add_segment<T: Into<NodeId>>(
+        &mut self,
+        id: T,
+        sequence: &[u8],
+    ) -> Result<(), GraphError> {
+        let id: NodeId = id.into();
+        self.graph.create_handle(id, sequence)?;
+        self.declared(id);
+        Ok(())
+    }
+
+    /// Like [`add_segment`](#method.add_segment), keeping the name of
+    /// the segment if it isn't a number, see
+    /// [`node_name`](../graph/struct.HashGraph.html#method.node_name)
+    pub(crate) fn add_named_segment(
+        &mut self,
+        id: NodeId,
+        name: BString,
+        sequence: &[u8],
+    ) -> Result<(), GraphError> {
+        self.graph.create_named_handle(id, name, sequence)?;
+        self.declared(id);
+        Ok(())
+    }
+
+    /// Like [`add_named_segment`](#method.add_named_segment), for a
+    /// segment whose sequence is left in its file
+    pub(crate) fn add_lazy_segment(
+        &mut self,
+        id: NodeId,
+        name: BString,
+        sequence: LazySequence,
+    ) -> Result<(), GraphError> {
+        self.graph.check_segment_name(id, &name)?;
+        self.graph
+            .insert_node(id, SequenceRef::Lazy(Box::new(sequence)))?;
+        self.graph.set_node_name(id, name);
+        self.declared(id);
+        Ok(())
+    }
+
+    /// Keep the optional fields of a segment already added
+    pub(crate) fn tag_segment(&mut self, id: NodeId, optional: Vec<OptField>) {
+        self.graph.set_node_tags(id, optional);
+    }
+
+    /// Keep the declared length of a segment already added, if it's
+    /// written without a sequence
+    pub(crate) fn placeholder_segment(&mut self, id: NodeId, placeholder: Option<usize>) {
+        self.graph
+            .mark_placeholders(placeholder.map(|len| (id, len)));
+    }
+
+    /// Keep a header line, merged in the ones already found as
+    /// [`Header::merge`](../../gfa/gfa1/struct.Header.html#method.merge)
+    /// does
+    pub(crate) fn add_header(&mut self, header: Header) {
+        match self.graph.header.as_mut() {
+            Some(first) => first.merge(header),
+            None => self.graph.header = Some(header),
+        }
+    }
+
+    /// Keep a subset (an U-group of GFA2), added when the graph is
+    /// finished
+    pub(crate) fn add_subset(&mut self, name: BString, ids: Vec<u64>) {
+        self.subsets.push((name, ids));
+    }
+
+    /// Keep a containment, added when the graph is finished
+    pub(crate) fn add_containment(&mut self, containment: ContainmentRecord) {
+        self.containments.push(containment);
+    }
+
+    /// Keep the optional fields of a path already added, even if its
+    /// steps are still queued
+    pub(crate) fn tag_path(&mut self, name: &[u8], optional: Vec<OptField>) {
+        self.graph.set_path_tags(name, optional);
+    }
+
+    /// Add the queued edges and paths that were waiting only for the
+    /// segment ```id```
+    fn declared(&mut self, id: NodeId) {
+        for ix in self.waiting.remove(&id).unwrap_or_default() {
+            let ready = match self.pending[ix].as_mut() {
+                Some(pending) => {
+                    pending.missing -= 1;
+                    pending.missing == 0
+                }
+                None => false,
+            };
+            if ready {
+                let pending = self.pending[ix].take().unwrap();
+                self.apply(pending.record);
+            }
+        }
+    }
+
+    /// The sequence of a segment already added
+    pub(crate) fn segment_sequence(&self, id: NodeId) -> Option<&[u8]> {
+        self.graph
+            .get_node(&id)
+            .map(|n| self.graph.node_sequence(n))
+    }
+
+    /// True if a path with this name was already added
+    pub(crate) fn has_path(&self, name: &[u8]) -> bool {
+        self.graph.has_path(name)
+    }
+
+    /// Function that adds an edge, or queues it if one of its segments
+    /// doesn't exist yet
+    pub fn add_edge(&mut self, edge: Edge) {
+        self.add_tagged_edge(edge, 0, vec![]);
+    }
+
+    /// Like [`add_edge`](#method.add_edge), keeping the overlap and the
+    /// optional fields of its link once it's added
+    pub(crate) fn add_tagged_edge(&mut self, edge: Edge, overlap: usize, optional: Vec<OptField>) {
+        let Edge(l, r) = edge;
+        self.add_record(Record::Edge(edge, overlap, optional), &[l, r]);
+    }
+
+    /// Function that adds a path, or queues its steps if one of its
+    /// segments doesn't exist yet
+    pub fn add_path(&mut self, name: &[u8], steps: Vec<Handle>) {
+        let path_id = self.graph.create_path_handle(name, false);
+        let handles = steps.clone();
+        self.add_record(Record::Path(path_id, steps), &handles);
+    }
+
+    fn add_record(&mut self, record: Record, handles: &[Handle]) {
+        let missing: FnvHashSet<NodeId> = handles
+            .iter()
+            .map(|h| h.id())
+            .filter(|id| !self.graph.has_node(*id))
+            .collect();
+        if missing.is_empty() {
+            self.apply(record);
+            return;
+        }
+        let ix = self.pending.len();
+        for id in missing.iter() {
+            self.waiting.entry(*id).or_default().push(ix);
+        }
+        self.pending.push(Some(Pending {
+            record,
+            missing: missing.len(),
+        }));
+    }
+
+    /// Add a record whose segments all exist
+    fn apply(&mut self, record: Record) {
+        match record {
+            Record::Edge(edge, overlap, optional) => {
+                if self.graph.create_edge(edge).is_ok() {
+                    if overlap > 0 {
+                        let _ = self.graph.set_edge_overlap(edge, overlap);
+                    }
+                    self.graph.set_edge_tags(edge, optional);
+                }
+            }
+            Record::Path(path_id, steps) => {
+                for handle in steps {
+                    let _ = self.graph.append_step(&path_id, handle);
+                }
+            }
+        }
+    }
+
+    /// Function that returns the edges and paths still waiting for a
+    /// segment, in the order they were added
+    pub fn unresolved(&self) -> Vec<UnresolvedReference> {
+        self.pending
+            .iter()
+            .flatten()
+            .map(|pending| {
+                let handles = match &pending.record {
+                    Record::Edge(Edge(l, r), _, _) => vec![*l, *r],
+                    Record::Path(_, steps) => steps.clone(),
+                };
+                let mut missing: Vec<NodeId> = handles
+                    .iter()
+                    .map(|h| h.id())
+                    .filter(|id| !self.graph.has_node(*id))
+                    .collect();
+                missing.sort();
+                missing.dedup();
+                match &pending.record {
+                    Record::Edge(edge, _, _) => UnresolvedReference::Edge(*edge, missing),
+                    Record::Path(path_id, _) => UnresolvedReference::Path(
+                        self.graph.get_path_unchecked(path_id).name.clone(),
+                        missing,
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    /// Function that returns the graph built, failing if some edge or
+    /// path references a segment that was never declared
+    pub fn finish_strict(mut self) -> Result<HashGraph, Vec<UnresolvedReference>> {
+        let unresolved = self.unresolved();
+        if unresolved.is_empty() {
+            self.add_groups();
+            Ok(self.graph)
+        } else {
+            Err(unresolved)
+        }
+    }
+
+    /// Add the subsets and the containments kept, on the nodes of the
+    /// graph
+    fn add_groups(&mut self) {
+        let subsets = std::mem::take(&mut self.subsets);
+        let containments = std::mem::take(&mut self.containments);
+        self.graph.add_groups(subsets, containments);
+    }
+
+    /// Function that returns the graph built, together with the edges
+    /// and paths that reference a segment that was never declared.\
+    /// Those edges are dropped, while those paths are kept without the
+    /// steps on the missing segments, as in
+    /// [`create_graph_recover`](../graph/struct.HashGraph.html#method.create_graph_recover)
+    pub fn finish(mut self) -> (HashGraph, Vec<UnresolvedReference>) {
+        let unresolved = self.unresolved();
+        for pending in std::mem::take(&mut self.pending).into_iter().flatten() {
+            if let Record::Path(path_id, steps) = pending.record {
+                let steps = steps.into_iter().filter(|h| self.graph.has_node(h.id()));
+                self.apply(Record::Path(path_id, steps.collect()));
+            }
+        }
+        self.add_groups();
+        (self.graph, unresolved)
+    }
+}
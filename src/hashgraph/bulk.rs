@@ -0,0 +1,490 @@
+use bstr::BString;
+use fnv::FnvHashSet;
+use rayon::prelude::*;
+use std::convert::TryFrom;
+
+use crate::gfa::{
+    gfa1::GFA,
+    gfa2::{Segment as Segment2, GFA2},
+    optional::OptField,
+    orientation::Orientation,
+    segment_id::PathParseError,
+};
+use crate::{
+    handle::{Edge, Handle, NodeId},
+    handlegraph::*,
+    mutablehandlegraph::*,
+};
+
+use super::{
+    attach::{gfa1_placeholder, gfa2_placeholder},
+    generation::Changes,
+    graph::{containment_record, gfa2_edge, gfa2_header, link_edge, link_overlap, merged_header},
+    HashGraph, Node, Path,
+};
+
+impl HashGraph {
+    /// Function that creates an empty HashGraph with room for ```nodes```
+    /// nodes, so adding them doesn't grow its map again and again
+    /// # Example
+    /// ```ignore
+    /// let mut graph = HashGraph::with_capacity(gfa.segments.len());
+    /// ```
+    pub fn with_capacity(nodes: usize) -> HashGraph {
+        let mut graph = HashGraph::new();
+        graph.graph.reserve(nodes);
+        graph
+    }
+
+    /// Add the edges as ```create_edge``` one at a time would, returning
+    /// the errors of the edges with a missing node in their order: the
+    /// ends of the edges are checked in parallel, and then each node gets
+    /// its ends in parallel with the other nodes, in the order of the
+    /// edges, so its neighbors are in the same order.\
+    /// A graph that has edges already gets them one at a time, as does
+    /// a graph built on a single thread, where the parallel steps only
+    /// add their overhead
+    pub(crate) fn create_edges_par(&mut self, edges: Vec<Edge>) -> Vec<GraphError> {
+        if self.edge_count > 0 || rayon::current_num_threads() == 1 {
+            return edges
+                .into_iter()
+                .filter_map(|edge| self.create_edge(edge).err())
+                .collect();
+        }
+        let graph = &self.graph;
+        let checked: Vec<Result<Edge, GraphError>> = edges
+            .into_par_iter()
+            .map(
+                |Edge(l, r)| match [l, r].iter().find(|h| !graph.contains_key(&h.id())) {
+                    Some(missing) => Err(GraphError::NodeNotExist(missing.id().to_string())),
+                    None => Ok(Edge(l, r)),
+                },
+            )
+            .collect();
+
+        let mut errors = vec![];
+        // the node, the side (true for the right one) and the neighbor of
+        // each end of the edges, and if it's the first end of its edge
+        let mut ends: Vec<(NodeId, bool, Handle, bool)> = Vec::with_capacity(checked.len() * 2);
+        for edge in checked {
+            match edge {
+                Ok(Edge(l, r)) => {
+                    ends.push((l.id(), !l.is_reverse(), r, true));
+                    if l != r.flip() {
+                        ends.push((r.id(), r.is_reverse(), l.flip(), false));
+                    }
+                }
+                Err(why) => errors.push(why),
+            }
+        }
+        // the sort is stable, so the ends of a node keep their order
+        ends.par_sort_by_key(|end| end.0);
+        let ends = &ends;
+        let added: usize = self
+            .graph
+            .par_iter_mut()
+            .map(|(id, node)| {
+                let start = ends.partition_point(|end| end.0 < *id);
+                let mut added = 0;
+                for (_, right, handle, first) in ends[start..].iter().take_while(|end| end.0 == *id)
+                {
+                    let edges = if *right {
+                        &mut node.right_edges
+                    } else {
+                        &mut node.left_edges
+                    };
+                    // an end found again is of an edge already added, as it
+                    // is or from its other end, and so is its other end
+                    if !edges.contains(handle) {
+                        edges.push(*handle);
+                        added += usize::from(*first);
+                    }
+                }
+                added
+            })
+            .sum();
+        self.edge_count += added;
+        if added > 0 {
+            self.record(Changes::TOPOLOGY);
+        }
+        errors
+    }
+
+    /// Function that builds an HashGraph from nodes, edges and paths
+    /// already in memory, without going through the GFA objects.\
+    /// Each path is given as its name, its steps and whether it's
+    /// circular, and the paths get their ids in the order they are given.\
+    /// Everything is validated before building the graph, and the first
+    /// problem found is returned, as an [`InvalidBatchRecord`] with its
+    /// position in the respective iterator (except the
+    /// [`EmptySequence`]):
+    /// * [`EmptySequence`] for a node without sequence
+    /// * [`IdAlreadyExist`] for a node id given more times, at "node 5"
+    /// * [`NodeNotExist`] for an edge or a path step referencing a missing
+    ///   node, at "edge 3" or "path 1, step 4"
+    /// * [`PathAlreadyExist`] for a path name given more times, at "path 2"
+    ///
+    /// [`InvalidBatchRecord`]: ../../handlegraph/error/enum.GraphError.html
+    /// [`EmptySequence`]: ../../handlegraph/error/enum.GraphError.html
+    /// [`IdAlreadyExist`]: ../../handlegraph/error/enum.GraphError.html
+    /// [`NodeNotExist`]: ../../handlegraph/error/enum.GraphError.html
+    /// [`PathAlreadyExist`]: ../../handlegraph/error/enum.GraphError.html
+    /// # Example
+    /// ```ignore
+    /// let graph = HashGraph::from_parts(
+    ///     vec![(NodeId::from(1), b"ACG".to_vec()), (NodeId::from(2), b"T".to_vec())],
+    ///     vec![Edge(Handle::pack(1, false), Handle::pack(2, false))],
+    ///     vec![(b"x".to_vec(), vec![Handle::pack(1, false), Handle::pack(2, false)], false)],
+    /// )?;
+    /// ```
+    pub fn from_parts<N, E, P>(nodes: N, edges: E, paths: P) -> Result<HashGraph, GraphError>
+    where
+        N: IntoIterator<Item = (NodeId, Vec<u8>)>,
+        E: IntoIterator<Item = Edge>,
+        P: IntoIterator<Item = (Vec<u8>, Vec<Handle>, bool)>,
+    {
+        let batch = Batch {
+            nodes: nodes.into_iter().collect(),
+            edges: edges.into_iter().collect(),
+            paths: paths.into_iter().collect(),
+        };
+        let mut graph = HashGraph::new();
+        graph.check_batch(&batch)?;
+        graph.apply_batch(batch);
+        graph.mark_modified();
+        Ok(graph)
+    }
+
+    /// Function that starts a batch of nodes, edges and paths to add to
+    /// the graph together, see [`BulkEditor`](../struct.BulkEditor.html)
+    /// # Example
+    /// ```ignore
+    /// let mut edit = graph.bulk_edit();
+    /// edit.add_node(1, b"ACG");
+    /// edit.add_node(2, b"T");
+    /// edit.add_edge(Edge(Handle::pack(1, false), Handle::pack(2, false)));
+    /// edit.add_path(b"x", &[Handle::pack(1, false), Handle::pack(2, false)]);
+    /// let report = edit.commit()?;
+    /// ```
+    pub fn bulk_edit(&mut self) -> BulkEditor<'_> {
+        BulkEditor {
+            graph: self,
+            batch: Batch::default(),
+        }
+    }
+
+    /// Check the records of a batch against themselves and the graph, as
+    /// [`from_parts`](#method.from_parts) says, returning the first
+    /// problem found
+    fn check_batch(&self, batch: &Batch) -> Result<(), GraphError> {
+        let mut ids: FnvHashSet<NodeId> = FnvHashSet::default();
+        ids.reserve(batch.nodes.len());
+        for (ix, (id, sequence)) in batch.nodes.iter().enumerate() {
+            if sequence.is_empty() {
+                return Err(GraphError::EmptySequence);
+            }
+            if !ids.insert(*id) || self.graph.contains_key(id) {
+                return Err(in_batch(
+                    format!("node {}", ix),
+                    GraphError::IdAlreadyExist(id.to_string()),
+                ));
+            }
+        }
+        let exists = |h: &Handle| ids.contains(&h.id()) || self.graph.contains_key(&h.id());
+        for (ix, Edge(l, r)) in batch.edges.iter().enumerate() {
+            if let Some(missing) = [l, r].iter().find(|h| !exists(h)) {
+                return Err(in_batch(
+                    format!("edge {}", ix),
+                    GraphError::NodeNotExist(missing.id().to_string()),
+                ));
+            }
+        }
+        let mut names: FnvHashSet<&[u8]> = FnvHashSet::default();
+        for (ix, (name, steps, _)) in batch.paths.iter().enumerate() {
+            if !names.insert(name) || self.path_id.contains_key(name) {
+                return Err(in_batch(
+                    format!("path {}", ix),
+                    GraphError::PathAlreadyExist(BString::from(name.as_slice()).to_string()),
+                ));
+            }
+            if let Some(step) = steps.iter().position(|h| !exists(h)) {
+                return Err(in_batch(
+                    format!("path {}, step {}", ix, step),
+                    GraphError::NodeNotExist(steps[step].id().to_string()),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the records of a batch already checked: the id bounds are
+    /// widened once, the edges added as ```create_edges_par``` does, and
+    /// the occurrences of the steps built while the paths are added
+    fn apply_batch(&mut self, batch: Batch) -> EditReport {
+        let mut report = EditReport {
+            nodes_added: batch.nodes.len(),
+            paths_added: batch.paths.len(),
+            ..Default::default()
+        };
+        self.graph.reserve(batch.nodes.len());
+        let (mut min_id, mut max_id) = (self.min_id, self.max_id);
+        for (id, sequence) in batch.nodes {
+            max_id = std::cmp::max(max_id, id);
+            min_id = std::cmp::min(min_id, id);
+            let sequence = self.make_sequence(&sequence);
+            self.graph.insert(id, Node::with_sequence(sequence));
+        }
+        self.min_id = min_id;
+        self.max_id = max_id;
+
+        let edges_before = self.edge_count;
+        self.create_edges_par(batch.edges);
+        report.edges_added = self.edge_count - edges_before;
+
+        self.paths.reserve(batch.paths.len());
+        self.path_id.reserve(batch.paths.len());
+        // after a path is destroyed the number of paths can be a used id
+        let first_id = self.paths.keys().max().map_or(0, |max| max + 1);
+        for (ix, (name, steps, is_circular)) in batch.paths.into_iter().enumerate() {
+            let path_id = first_id + ix as i64;
+            for (step, handle) in steps.iter().enumerate() {
+                self.graph
+                    .get_mut(&handle.id())
+                    .unwrap()
+                    .occurrences
+                    .entry(path_id)
+                    .or_default()
+                    .push(step);
+            }
+            let mut path = Path::new(name.as_slice(), path_id, is_circular);
+            path.nodes = steps.into();
+            self.path_id.insert(name, path_id);
+            self.paths.insert(path_id, path);
+        }
+
+        let mut changes = Changes::NONE;
+        if report.nodes_added + report.edges_added > 0 {
+            changes = changes | Changes::TOPOLOGY;
+        }
+        if report.paths_added > 0 {
+            changes = changes | Changes::PATHS;
+        }
+        self.record(changes);
+        self.check_bounds();
+        report
+    }
+}
+
+/// The nodes, the edges and the paths (with whether they're circular)
+/// of a batch, in the order they were given
+#[derive(Default)]
+struct Batch {
+    nodes: Vec<(NodeId, Vec<u8>)>,
+    edges: Vec<Edge>,
+    paths: Vec<(Vec<u8>, Vec<Handle>, bool)>,
+}
+
+/// What a [`BulkEditor`](struct.BulkEditor.html) added to the graph
+/// ```ignore
+/// pub struct EditReport {
+///     pub nodes_added: usize,
+///     pub edges_added: usize,
+///     pub paths_added: usize,
+/// }
+/// ```
+/// * ```edges_added``` doesn't count the edges already in the graph, or
+///   given more times
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditReport {
+    pub nodes_added: usize,
+    pub edges_added: usize,
+    pub paths_added: usize,
+}
+
+/// A batch of nodes, edges and paths to add to an HashGraph, made by
+/// [`bulk_edit`](graph/struct.HashGraph.html#method.bulk_edit).\
+/// The records are only kept by the editor until
+/// [`commit`](#method.commit), that checks them all against each other
+/// and the graph before adding any: an edge or a path can reference the
+/// nodes of the same batch, in any order, and the bookkeeping of the
+/// graph (the id bounds, the occurrences of the steps, the duplicated
+/// edges) is done once for the whole batch instead of at each record.\
+/// An editor dropped without a commit leaves the graph as it was
+pub struct BulkEditor<'a> {
+    graph: &'a mut HashGraph,
+    batch: Batch,
+}
+
+impl BulkEditor<'_> {
+    pub fn add_node<T: Into<NodeId>>(&mut self, id: T, sequence: &[u8]) {
+        self.batch.nodes.push((id.into(), sequence.to_vec()));
+    }
+
+    pub fn add_edge(&mut self, edge: Edge) {
+        self.batch.edges.push(edge);
+    }
+
+    pub fn add_path(&mut self, name: &[u8], steps: &[Handle]) {
+        self.batch
+            .paths
+            .push((name.to_vec(), steps.to_vec(), false));
+    }
+
+    /// Function that adds the records of the batch to the graph, or
+    /// none of them if one can't be added, failing with the first
+    /// problem found as [`from_parts`](graph/struct.HashGraph.html#method.from_parts)
+    /// does, where an id or a path name of the graph is taken as well
+    /// # Example
+    /// ```ignore
+    /// let mut edit = graph.bulk_edit();
+    /// edit.add_node(1, b"A");
+    /// edit.add_node(1, b"C");
+    /// // IdAlreadyExist("1") of "node 1", and the graph didn't change
+    /// assert!(edit.commit().is_err());
+    /// ```
+    pub fn commit(self) -> Result<EditReport, GraphError> {
+        self.graph.check_batch(&self.batch)?;
+        Ok(self.graph.apply_batch(self.batch))
+    }
+}
+
+/// The error of a record of a batch, with where the record is
+fn in_batch(record: String, why: GraphError) -> GraphError {
+    GraphError::InvalidBatchRecord(record, Box::new(why))
+}
+
+/// The steps of a path, or the first one that can't be parsed
+fn checked_steps<I>(name: &BString, steps: I) -> Result<Vec<Handle>, GraphError>
+where
+    I: Iterator<Item = Result<(u64, Orientation), PathParseError>>,
+{
+    steps
+        .map(|step| match step {
+            Ok((id, orient)) => Ok(Handle::new(id, orient)),
+            Err(why) => Err(GraphError::InvalidPathStep(
+                name.to_string(),
+                why.token().to_string(),
+            )),
+        })
+        .collect()
+}
+
+/// The tags of the segments that have them, taken from the segments
+fn take_segment_tags<'a, I>(segments: I) -> Vec<(NodeId, Vec<OptField>)>
+where
+    I: Iterator<Item = (u64, &'a mut Vec<OptField>)>,
+{
+    segments
+        .filter(|(_, optional)| !optional.is_empty())
+        .map(|(id, optional)| (NodeId::from(id), std::mem::take(optional)))
+        .collect()
+}
+
+impl TryFrom<GFA> for HashGraph {
+    type Error = GraphError;
+
+    /// Build an HashGraph from a GFA object, validating it as
+    /// [`from_parts`](struct.HashGraph.html#method.from_parts), and
+    /// failing with ```InvalidPathStep``` on a path step that can't be
+    /// parsed. The nodes keep the tags of the segments, the edges the
+    /// overlaps and the tags of the links, and the graph the header and
+    /// the containments of its nodes
+    fn try_from(mut gfa: GFA) -> Result<Self, Self::Error> {
+        let header = merged_header(gfa.headers.iter().cloned());
+        let placeholders: Vec<(NodeId, usize)> = gfa
+            .segments
+            .iter()
+            .filter_map(|s| Some((s.name.into(), gfa1_placeholder(&s.sequence, &s.optional)?)))
+            .collect();
+        let node_tags =
+            take_segment_tags(gfa.segments.iter_mut().map(|s| (s.name, &mut s.optional)));
+        let paths = gfa
+            .paths
+            .iter()
+            .map(|p| {
+                let steps = checked_steps(&p.path_name, p.iter_checked())?;
+                Ok((p.path_name.to_vec(), steps, false))
+            })
+            .collect::<Result<Vec<_>, GraphError>>()?;
+        let mut graph = HashGraph::from_parts(
+            gfa.segments
+                .into_iter()
+                .map(|s| (NodeId::from(s.name), s.sequence.into())),
+            gfa.links.iter().map(link_edge),
+            paths,
+        )?;
+        for link in gfa.links.into_iter() {
+            let overlap = link_overlap(&link);
+            if overlap > 0 {
+                graph.set_edge_overlap(link_edge(&link), overlap)?;
+            }
+            graph.set_edge_tags(link_edge(&link), link.optional);
+        }
+        for (id, optional) in node_tags {
+            graph.set_node_tags(id, optional);
+        }
+        graph.mark_placeholders(placeholders);
+        for containment in gfa.containments.iter() {
+            graph.add_containment(containment_record(containment));
+        }
+        graph.header = header;
+        Ok(graph)
+    }
+}
+
+impl TryFrom<GFA2> for HashGraph {
+    type Error = GraphError;
+
+    /// Build an HashGraph from a GFA2 object, validating it as
+    /// [`from_parts`](struct.HashGraph.html#method.from_parts), and
+    /// failing with ```InvalidPathStep``` on a reference of an O-group
+    /// that can't be parsed, keeping the header, the U-groups and the
+    /// tags of the segments and of the edges. A segment without a
+    /// sequence (```*```) gets ```N```s as long as its length, as in
+    /// [`create_graph`](struct.HashGraph.html#method.create_graph)
+    fn try_from(mut gfa2: GFA2) -> Result<Self, Self::Error> {
+        let header = merged_header(gfa2.headers.iter().map(gfa2_header));
+        let placeholders: Vec<(NodeId, usize)> = gfa2
+            .segments
+            .iter()
+            .filter_map(|s| Some((s.id.into(), gfa2_placeholder(&s.sequence, s.slen)?)))
+            .collect();
+        let node_tags =
+            take_segment_tags(gfa2.segments.iter_mut().map(|s| (s.id, &mut s.optional)));
+        let subsets: Vec<(BString, Vec<u64>)> = gfa2
+            .groups_u
+            .iter()
+            .map(|u| (u.id.clone(), u.iter_ids().collect()))
+            .collect();
+        let paths = gfa2
+            .groups_o
+            .iter()
+            .map(|o| {
+                let steps = checked_steps(&o.id, o.iter_checked())?;
+                Ok((o.id.to_vec(), steps, false))
+            })
+            .collect::<Result<Vec<_>, GraphError>>()?;
+        let mut graph = HashGraph::from_parts(
+            gfa2.segments.into_iter().map(|s| {
+                (
+                    NodeId::from(s.id),
+                    Segment2::node_sequence(s.sequence, s.slen).into(),
+                )
+            }),
+            gfa2.edges.iter().map(gfa2_edge),
+            paths,
+        )?;
+        for (id, optional) in node_tags {
+            graph.set_node_tags(id, optional);
+        }
+        for edge in gfa2.edges.into_iter() {
+            graph.set_edge_tags(gfa2_edge(&edge), edge.optional);
+        }
+        graph.mark_placeholders(placeholders);
+        for (name, ids) in subsets {
+            graph.add_subset(name, &ids);
+        }
+        graph.header = header;
+        Ok(graph)
+    }
+}
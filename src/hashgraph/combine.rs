@@ -0,0 +1,218 @@
+use fnv::FnvHashSet;
+
+use crate::{
+    gfa::optional::OptField,
+    handle::{Direction, Edge, Handle, NodeId},
+    handlegraph::*,
+    mutablehandlegraph::*,
+};
+
+use super::{generation::Changes, removal::PathRepair, HashGraph, PathId};
+
+impl HashGraph {
+    /// The body of ```combine_handles```, that counts it as a single change
+    pub(crate) fn combine_nodes(&mut self, handles: &[Handle]) -> Result<Handle, GraphError> {
+        let (first, last) = match (handles.first(), handles.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            // nothing to combine, as a node without a sequence
+            _ => return Err(GraphError::EmptySequence),
+        };
+        if let Some(missing) = handles.iter().find(|h| !self.graph.contains_key(&h.id())) {
+            return Err(GraphError::NodeNotExist(missing.id().to_string()));
+        }
+        if handles.len() == 1 {
+            return Ok(first);
+        }
+        self.check_simple_chain(handles)?;
+        let visiting = self.check_chain_visits(handles)?;
+
+        // the sequence spelled by the chain, without the bases shared by
+        // its inner edges
+        let mut sequence = self.sequence(first);
+        for w in handles.windows(2) {
+            let next = self.sequence(w[1]);
+            let overlap = self.edge_overlap(Edge(w[0], w[1])).min(next.len());
+            sequence.extend_from_slice(&next[overlap..]);
+        }
+
+        // the outer edges, from the left end and the right end of the chain
+        let lefts: Vec<(Handle, Edge)> = self
+            .neighbors(first, Direction::Left)
+            .map(|h| (h, Edge(h, first)))
+            .collect();
+        let rights: Vec<(Handle, Edge)> = self
+            .neighbors(last, Direction::Right)
+            .map(|h| (h, Edge(last, h)))
+            .collect();
+        // the overlaps and the tags of the outer edges, kept by the
+        // edges of the merged node
+        let kept: Vec<(usize, Vec<OptField>)> = lefts
+            .iter()
+            .chain(rights.iter())
+            .map(|(_, edge)| (self.edge_overlap(*edge), self.edge_tags(*edge).to_vec()))
+            .collect();
+
+        let merged = self.append_handle(&sequence)?;
+        // a handle on an end of the chain leaves it (or enters it) from
+        // the same end of the merged node
+        let on_merged = |h: Handle| {
+            if h == first || h == last {
+                merged
+            } else if h == first.flip() || h == last.flip() {
+                merged.flip()
+            } else {
+                h
+            }
+        };
+
+        let mut changes = Changes::TOPOLOGY | Changes::SEQUENCES;
+        let chain: FnvHashSet<NodeId> = handles.iter().map(|h| h.id()).collect();
+        for path_id in visiting {
+            changes = changes | Changes::PATHS;
+            self.combine_steps_of(path_id, handles, merged, &chain);
+        }
+        self.remove_handles_with(
+            &chain.iter().copied().collect::<Vec<_>>(),
+            PathRepair::RemoveSteps,
+        )?;
+
+        let outer = lefts
+            .into_iter()
+            .map(|(h, _)| Edge(on_merged(h), merged))
+            .chain(rights.into_iter().map(|(h, _)| Edge(merged, on_merged(h))));
+        for (edge, (overlap, tags)) in outer.zip(kept) {
+            self.create_edge(edge)?;
+            if overlap > 0 {
+                self.set_edge_overlap(edge, overlap)?;
+            }
+            self.set_edge_tags(edge, tags);
+        }
+        self.record(changes);
+        Ok(merged)
+    }
+
+    /// Check that each handle of the chain only reaches the next one, and
+    /// the next one is only reached by it, so no walk leaves the chain
+    /// halfway
+    fn check_simple_chain(&self, handles: &[Handle]) -> Result<(), GraphError> {
+        let mut seen: FnvHashSet<NodeId> = FnvHashSet::default();
+        for handle in handles {
+            if !seen.insert(handle.id()) {
+                return Err(GraphError::ChainNotSimple(
+                    handle.id().to_string(),
+                    "the node is in the chain more than once".to_string(),
+                ));
+            }
+        }
+        let only = |handle: Handle, dir: Direction, other: Handle| {
+            self.neighbors(handle, dir).all(|h| h == other)
+        };
+        for w in handles.windows(2) {
+            let (this, next) = (w[0], w[1]);
+            if !self.has_edge(this, next) {
+                return Err(GraphError::ChainNotSimple(
+                    this.id().to_string(),
+                    format!("there's no edge to the node {}", next.id()),
+                ));
+            }
+            if !only(this, Direction::Right, next) {
+                return Err(GraphError::ChainNotSimple(
+                    this.id().to_string(),
+                    format!("the node reaches other nodes than {}", next.id()),
+                ));
+            }
+            if !only(next, Direction::Left, this) {
+                return Err(GraphError::ChainNotSimple(
+                    next.id().to_string(),
+                    format!("the node is reached by other nodes than {}", this.id()),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that every path visiting the chain walks through all of it,
+    /// forward or backward, and return the paths to rewrite
+    fn check_chain_visits(&self, handles: &[Handle]) -> Result<Vec<PathId>, GraphError> {
+        let backward: Vec<Handle> = handles.iter().rev().map(|h| h.flip()).collect();
+        let mut visiting: FnvHashSet<PathId> = FnvHashSet::default();
+        for handle in handles {
+            visiting.extend(self.get_node_unchecked(&handle.id()).occurrences.keys());
+        }
+        let mut visiting: Vec<PathId> = visiting.into_iter().collect();
+        visiting.sort_unstable();
+
+        for path_id in visiting.iter() {
+            let path = &self.paths[path_id];
+            let mut ix = 0;
+            while ix < path.nodes.len() {
+                let step = path.nodes[ix];
+                let run = if step == handles[0] {
+                    handles
+                } else if step == backward[0] {
+                    &backward
+                } else if handles.iter().any(|h| h.id() == step.id()) {
+                    return Err(GraphError::ChainNotSimple(
+                        step.id().to_string(),
+                        format!("the path {} enters the chain at the step {}", path.name, ix),
+                    ));
+                } else {
+                    ix += 1;
+                    continue;
+                };
+                if !path
+                    .nodes
+                    .range(ix..)
+                    .copied()
+                    .take(run.len())
+                    .eq(run.iter().copied())
+                {
+                    return Err(GraphError::ChainNotSimple(
+                        step.id().to_string(),
+                        format!(
+                            "the path {} leaves the chain after the step {}",
+                            path.name, ix
+                        ),
+                    ));
+                }
+                ix += run.len();
+            }
+        }
+        Ok(visiting)
+    }
+
+    /// Replace every walk of a path through the chain with a single step on
+    /// the merged node, then index the steps again; the steps on the chain
+    /// are gone, so its nodes don't keep the path
+    fn combine_steps_of(
+        &mut self,
+        path_id: PathId,
+        handles: &[Handle],
+        merged: Handle,
+        chain: &FnvHashSet<NodeId>,
+    ) {
+        let path = self.paths.get_mut(&path_id).unwrap();
+        let mut nodes = Vec::with_capacity(path.nodes.len());
+        let mut ix = 0;
+        while ix < path.nodes.len() {
+            let step = path.nodes[ix];
+            if chain.contains(&step.id()) {
+                // a run checked by check_chain_visits
+                nodes.push(if step == handles[0] {
+                    merged
+                } else {
+                    merged.flip()
+                });
+                ix += handles.len();
+            } else {
+                nodes.push(step);
+                ix += 1;
+            }
+        }
+        path.nodes = nodes.into();
+        for id in chain {
+            self.graph.get_mut(id).unwrap().occurrences.remove(&path_id);
+        }
+        self.index_steps(&path_id);
+    }
+}
@@ -0,0 +1,463 @@
+use bstr::BString;
+use fnv::{FnvHashMap, FnvHashSet};
+use std::{fmt, io::Write};
+
+use crate::{
+    handle::{Direction, Edge, Handle, NodeId},
+    handlegraph::*,
+    util::threads::ThreadConfig,
+};
+
+use super::{HashGraph, Path};
+
+/// A change of a node between two versions of a graph
+/// ```ignore
+/// pub enum NodeChange {
+///     SequenceChanged,
+///     NeighborsChanged { added: Vec<Edge>, removed: Vec<Edge> },
+///     CoverageChanged { old: Vec<BString>, new: Vec<BString> },
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeChange {
+    /// The sequence of the node is different
+    SequenceChanged,
+    /// The edges of the node, as canonical edges sorted, that are only in
+    /// the new graph (```added```) or only in the old one (```removed```)
+    NeighborsChanged {
+        added: Vec<Edge>,
+        removed: Vec<Edge>,
+    },
+    /// The names of the paths going through the node, sorted and
+    /// repeated for each visit
+    CoverageChanged {
+        old: Vec<BString>,
+        new: Vec<BString>,
+    },
+}
+
+/// The changes of a node present in both graphs, in the order of
+/// [`NodeChange`](enum.NodeChange.html)
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeDiff {
+    pub id: NodeId,
+    pub changes: Vec<NodeChange>,
+}
+
+/// The changes between two versions of a graph, node by node, computed by
+/// [`compare_local`](../graph/struct.HashGraph.html#method.compare_local)
+/// ```ignore
+/// pub struct LocalDiffReport {
+///     pub changed: Vec<NodeDiff>,
+///     pub added: Vec<NodeId>,
+///     pub removed: Vec<NodeId>,
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LocalDiffReport {
+    /// The nodes in both graphs with at least a change, sorted by id
+    pub changed: Vec<NodeDiff>,
+    /// The nodes only in the new graph, sorted
+    pub added: Vec<NodeId>,
+    /// The nodes only in the old graph, sorted
+    pub removed: Vec<NodeId>,
+}
+
+/// How many nodes have each kind of change
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LocalDiffSummary {
+    pub sequence_changed: usize,
+    pub neighbors_changed: usize,
+    pub coverage_changed: usize,
+    pub added: usize,
+    pub removed: usize,
+}
+
+impl LocalDiffReport {
+    /// True if the graphs have the same nodes, and none of them changed
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.added.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn summary(&self) -> LocalDiffSummary {
+        let mut summary = LocalDiffSummary {
+            added: self.added.len(),
+            removed: self.removed.len(),
+            ..Default::default()
+        };
+        for change in self.changed.iter().flat_map(|d| d.changes.iter()) {
+            match change {
+                NodeChange::SequenceChanged => summary.sequence_changed += 1,
+                NodeChange::NeighborsChanged { .. } => summary.neighbors_changed += 1,
+                NodeChange::CoverageChanged { .. } => summary.coverage_changed += 1,
+            }
+        }
+        summary
+    }
+}
+
+/// The differences between two graphs, computed by
+/// [`diff`](../graph/struct.HashGraph.html#method.diff): what is only in
+/// the graph compared (```self```) or only in the other one, and what is
+/// in both but differs. The nodes and the edges are sorted, the paths are
+/// named and sorted by name
+/// ```ignore
+/// pub struct GraphDiff {
+///     pub nodes_only_in_self: Vec<NodeId>,
+///     pub nodes_only_in_other: Vec<NodeId>,
+///     pub different_sequences: Vec<NodeId>,
+///     pub edges_only_in_self: Vec<Edge>,
+///     pub edges_only_in_other: Vec<Edge>,
+///     pub paths_only_in_self: Vec<BString>,
+///     pub paths_only_in_other: Vec<BString>,
+///     pub different_paths: Vec<BString>,
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphDiff {
+    pub nodes_only_in_self: Vec<NodeId>,
+    pub nodes_only_in_other: Vec<NodeId>,
+    /// The nodes in both graphs with a different sequence
+    pub different_sequences: Vec<NodeId>,
+    /// The canonical edges only in one of the graphs
+    pub edges_only_in_self: Vec<Edge>,
+    pub edges_only_in_other: Vec<Edge>,
+    pub paths_only_in_self: Vec<BString>,
+    pub paths_only_in_other: Vec<BString>,
+    /// The paths in both graphs with different steps, or circular in
+    /// only one of them
+    pub different_paths: Vec<BString>,
+}
+
+impl GraphDiff {
+    /// True if the graphs are equal
+    pub fn is_empty(&self) -> bool {
+        *self == GraphDiff::default()
+    }
+}
+
+/// A line for each kind of difference found, or a single line if the
+/// graphs are equal
+impl fmt::Display for GraphDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "The graphs are equal");
+        }
+        let lines = [
+            (
+                "Nodes only in the first graph",
+                join(&self.nodes_only_in_self, |id| id.to_string()),
+            ),
+            (
+                "Nodes only in the second graph",
+                join(&self.nodes_only_in_other, |id| id.to_string()),
+            ),
+            (
+                "Nodes with different sequences",
+                join(&self.different_sequences, |id| id.to_string()),
+            ),
+            (
+                "Edges only in the first graph",
+                join(&self.edges_only_in_self, format_edge),
+            ),
+            (
+                "Edges only in the second graph",
+                join(&self.edges_only_in_other, format_edge),
+            ),
+            (
+                "Paths only in the first graph",
+                join(&self.paths_only_in_self, |n| n.to_string()),
+            ),
+            (
+                "Paths only in the second graph",
+                join(&self.paths_only_in_other, |n| n.to_string()),
+            ),
+            (
+                "Paths with different steps",
+                join(&self.different_paths, |n| n.to_string()),
+            ),
+        ];
+        for (what, items) in lines.iter().filter(|(_, items)| !items.is_empty()) {
+            writeln!(f, "{}: {}", what, items)?;
+        }
+        Ok(())
+    }
+}
+
+/// The items of ```ours``` that aren't in ```theirs``` and the other way
+/// round, both sorted
+fn one_sided<T: Copy + Ord + std::hash::Hash>(
+    ours: &FnvHashSet<T>,
+    theirs: &FnvHashSet<T>,
+) -> (Vec<T>, Vec<T>) {
+    let mut only_ours: Vec<T> = ours.difference(theirs).copied().collect();
+    let mut only_theirs: Vec<T> = theirs.difference(ours).copied().collect();
+    only_ours.sort_unstable();
+    only_theirs.sort_unstable();
+    (only_ours, only_theirs)
+}
+
+/// The paths of a graph by name
+fn paths_by_name(graph: &HashGraph) -> FnvHashMap<&BString, &Path> {
+    graph.paths.values().map(|p| (&p.name, p)).collect()
+}
+
+/// Two graphs are equal if they have the same nodes with the same
+/// sequences, the same edges (an edge and its flip on the other strand
+/// are the same edge) and the same paths by name, with the same steps,
+/// regardless of the order they are stored in, as computed by
+/// [`diff`](graph/struct.HashGraph.html#method.diff)
+impl PartialEq for HashGraph {
+    fn eq(&self, other: &HashGraph) -> bool {
+        self.graph.len() == other.graph.len()
+            && self.paths.len() == other.paths.len()
+            && self.diff(other).is_empty()
+    }
+}
+
+/// The header of
+/// [`write_local_diff_tsv`](../graph/struct.HashGraph.html#method.write_local_diff_tsv)
+pub const LOCAL_DIFF_TSV_HEADER: &str =
+    "id\tstatus\told_sequence\tnew_sequence\tadded_edges\tremoved_edges\told_paths\tnew_paths";
+
+fn format_edge(Edge(left, right): &Edge) -> String {
+    let orient = |h: &Handle| if h.is_reverse() { '-' } else { '+' };
+    format!(
+        "{}{},{}{}",
+        left.id(),
+        orient(left),
+        right.id(),
+        orient(right)
+    )
+}
+
+fn join<T>(items: &[T], format: impl Fn(&T) -> String) -> String {
+    items.iter().map(format).collect::<Vec<String>>().join(";")
+}
+
+impl HashGraph {
+    /// The edges of a node, as canonical edges
+    fn canonical_edges_of(&self, id: NodeId) -> FnvHashSet<Edge> {
+        let handle = Handle::pack(id, false);
+        self.neighbors(handle, Direction::Right)
+            .map(|other| Edge::edge_handle(handle, other))
+            .chain(
+                self.neighbors(handle, Direction::Left)
+                    .map(|other| Edge::edge_handle(other, handle)),
+            )
+            .collect()
+    }
+
+    /// The names of the paths going through each node, sorted and
+    /// repeated for each visit
+    fn path_coverage(&self) -> FnvHashMap<NodeId, Vec<BString>> {
+        let mut coverage: FnvHashMap<NodeId, Vec<BString>> = FnvHashMap::default();
+        for path in self.paths.values() {
+            for step in path.nodes.iter() {
+                coverage
+                    .entry(step.id())
+                    .or_default()
+                    .push(path.name.clone());
+            }
+        }
+        for names in coverage.values_mut() {
+            names.sort();
+        }
+        coverage
+    }
+
+    /// Function that compares the graph with ```other``` as a whole,
+    /// listing the nodes, the edges and the paths found in only one of
+    /// them and the nodes and the paths in both that differ; the graphs
+    /// are equal if the diff is empty.\
+    /// The edges are compared as canonical edges and the paths by name,
+    /// so the order the graphs store them in doesn't matter
+    /// # Example
+    /// ```ignore
+    /// let diff = graph.diff(&parsed_again);
+    /// assert!(diff.is_empty(), "{}", diff);
+    /// ```
+    pub fn diff(&self, other: &HashGraph) -> GraphDiff {
+        let ids = |graph: &HashGraph| graph.graph.keys().copied().collect::<FnvHashSet<_>>();
+        let (our_ids, their_ids) = (ids(self), ids(other));
+        let (nodes_only_in_self, nodes_only_in_other) = one_sided(&our_ids, &their_ids);
+        let mut different_sequences: Vec<NodeId> = our_ids
+            .intersection(&their_ids)
+            .filter(|id| {
+                self.node_sequence(self.get_node_unchecked(id))
+                    != other.node_sequence(other.get_node_unchecked(id))
+            })
+            .copied()
+            .collect();
+        different_sequences.sort_unstable();
+
+        let edges = |graph: &HashGraph| {
+            graph
+                .edges()
+                .map(|Edge(l, r)| Edge::edge_handle(l, r))
+                .collect::<FnvHashSet<_>>()
+        };
+        let (edges_only_in_self, edges_only_in_other) = one_sided(&edges(self), &edges(other));
+
+        let (our_paths, their_paths) = (paths_by_name(self), paths_by_name(other));
+        let names_only = |ours: &FnvHashMap<&BString, &Path>,
+                          theirs: &FnvHashMap<&BString, &Path>| {
+            let mut names: Vec<BString> = ours
+                .keys()
+                .filter(|name| !theirs.contains_key(*name))
+                .map(|name| (*name).clone())
+                .collect();
+            names.sort();
+            names
+        };
+        let mut different_paths: Vec<BString> = our_paths
+            .iter()
+            .filter(|(name, ours)| match their_paths.get(*name) {
+                Some(theirs) => {
+                    ours.nodes != theirs.nodes || ours.is_circular != theirs.is_circular
+                }
+                None => false,
+            })
+            .map(|(name, _)| (*name).clone())
+            .collect();
+        different_paths.sort();
+
+        GraphDiff {
+            nodes_only_in_self,
+            nodes_only_in_other,
+            different_sequences,
+            edges_only_in_self,
+            edges_only_in_other,
+            paths_only_in_self: names_only(&our_paths, &their_paths),
+            paths_only_in_other: names_only(&their_paths, &our_paths),
+            different_paths,
+        }
+    }
+
+    /// Function that compares the graph (the old version) with ```other```
+    /// (the new version) node by node, reporting for each node in both
+    /// graphs if its sequence, its edges or the paths going through it
+    /// changed, and listing the nodes found in only one of them.\
+    /// The edges are compared as canonical edges, so an edge stored from
+    /// the other strand isn't a change; the paths are compared by name.\
+    /// The shared nodes are compared in parallel
+    /// # Example
+    /// ```ignore
+    /// let report = old.compare_local(&new);
+    /// println!("{:?}", report.summary());
+    /// ```
+    pub fn compare_local(&self, other: &HashGraph) -> LocalDiffReport {
+        let mut shared: Vec<NodeId> = vec![];
+        let mut removed: Vec<NodeId> = vec![];
+        for id in self.graph.keys() {
+            if other.graph.contains_key(id) {
+                shared.push(*id);
+            } else {
+                removed.push(*id);
+            }
+        }
+        let mut added: Vec<NodeId> = other
+            .graph
+            .keys()
+            .filter(|id| !self.graph.contains_key(id))
+            .copied()
+            .collect();
+        shared.sort();
+        removed.sort();
+        added.sort();
+
+        let (old_coverage, new_coverage) = (self.path_coverage(), other.path_coverage());
+        let no_paths: Vec<BString> = vec![];
+        let changed = ThreadConfig::global()
+            .map(&shared, |id| {
+                let mut changes = vec![];
+                if self.node_sequence(self.get_node_unchecked(id))
+                    != other.node_sequence(other.get_node_unchecked(id))
+                {
+                    changes.push(NodeChange::SequenceChanged);
+                }
+
+                let (old_edges, new_edges) =
+                    (self.canonical_edges_of(*id), other.canonical_edges_of(*id));
+                let mut added: Vec<Edge> = new_edges.difference(&old_edges).copied().collect();
+                let mut removed: Vec<Edge> = old_edges.difference(&new_edges).copied().collect();
+                if !added.is_empty() || !removed.is_empty() {
+                    added.sort();
+                    removed.sort();
+                    changes.push(NodeChange::NeighborsChanged { added, removed });
+                }
+
+                let old = old_coverage.get(id).unwrap_or(&no_paths);
+                let new = new_coverage.get(id).unwrap_or(&no_paths);
+                if old != new {
+                    changes.push(NodeChange::CoverageChanged {
+                        old: old.clone(),
+                        new: new.clone(),
+                    });
+                }
+
+                if changes.is_empty() {
+                    None
+                } else {
+                    Some(NodeDiff { id: *id, changes })
+                }
+            })
+            .into_iter()
+            .flatten()
+            .collect();
+
+        LocalDiffReport {
+            changed,
+            added,
+            removed,
+        }
+    }
+
+    /// Function that writes the [`compare_local`](#method.compare_local)
+    /// report as TSV, with the columns of
+    /// [`LOCAL_DIFF_TSV_HEADER`](../diff/constant.LOCAL_DIFF_TSV_HEADER.html):
+    /// a row for each changed node (status ```changed```), then one for
+    /// each removed and each added node (status ```removed``` and
+    /// ```added```).\
+    /// The fields of a change that didn't happen are empty, the edges
+    /// (as ```1+,2-```) and the path names are separated by ```;```
+    /// # Example
+    /// ```ignore
+    /// old.write_local_diff_tsv(&new, std::io::stdout()).unwrap();
+    /// ```
+    pub fn write_local_diff_tsv<W: Write>(
+        &self,
+        other: &HashGraph,
+        mut out: W,
+    ) -> std::io::Result<()> {
+        let report = self.compare_local(other);
+        writeln!(out, "{}", LOCAL_DIFF_TSV_HEADER)?;
+        for diff in report.changed.iter() {
+            let mut fields: Vec<String> = vec![String::new(); 6];
+            for change in diff.changes.iter() {
+                match change {
+                    NodeChange::SequenceChanged => {
+                        let handle = Handle::pack(diff.id, false);
+                        fields[0] = BString::from(self.sequence(handle)).to_string();
+                        fields[1] = BString::from(other.sequence(handle)).to_string();
+                    }
+                    NodeChange::NeighborsChanged { added, removed } => {
+                        fields[2] = join(added, format_edge);
+                        fields[3] = join(removed, format_edge);
+                    }
+                    NodeChange::CoverageChanged { old, new } => {
+                        fields[4] = join(old, |n| n.to_string());
+                        fields[5] = join(new, |n| n.to_string());
+                    }
+                }
+            }
+            writeln!(out, "{}\tchanged\t{}", diff.id, fields.join("\t"))?;
+        }
+        for (status, ids) in [("removed", &report.removed), ("added", &report.added)].iter() {
+            for id in ids.iter() {
+                writeln!(out, "{}\t{}\t\t\t\t\t\t", id, status)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::{
+    handle::{Direction, Edge, Handle, NodeId},
+    handlegraph::*,
+    mutablehandlegraph::*,
+};
+
+use super::{generation::Changes, HashGraph, PathId};
+
+impl HashGraph {
+    /// Function that duplicates a node so that different paths go through
+    /// different copies of it (unzipping a collapsed repeat).\
+    /// The assignment maps a path to the index of the copy it will traverse,
+    /// where 0 is the original node; the paths not in the assignment keep
+    /// the original node. A path moved to a copy has all its visits of
+    /// the node moved to it.\
+    /// Each copy gets the edges used by its paths, an edge used by more
+    /// copies is duplicated on each of them, while the edges used by no
+    /// path at all stay on the original node.\
+    /// Returns the ids of the new nodes, one for each index in use, ordered
+    /// by index: the indices don't have to be contiguous, the indices
+    /// ```[1, 4]``` create two copies.\
+    /// The graph is left untouched if the node doesn't exist, or a path of
+    /// the assignment doesn't exist or doesn't traverse the node.
+    /// # Example
+    /// ```ignore
+    /// let mut assignment = FnvHashMap::default();
+    /// assignment.insert(path_b, 1);
+    /// // path_b will now go through a new copy of node 3
+    /// let copies = graph.duplicate_handle_for_paths(NodeId::from(3), &assignment).unwrap();
+    /// ```
+    pub fn duplicate_handle_for_paths(
+        &mut self,
+        node: NodeId,
+        assignment: &FnvHashMap<PathId, usize>,
+    ) -> Result<Vec<NodeId>, GraphError> {
+        self.as_one_change(|graph| graph.duplicate_node_for_paths(node, assignment))
+    }
+
+    fn duplicate_node_for_paths(
+        &mut self,
+        node: NodeId,
+        assignment: &FnvHashMap<PathId, usize>,
+    ) -> Result<Vec<NodeId>, GraphError> {
+        let original = self
+            .get_node(&node)
+            .ok_or_else(|| GraphError::NodeNotExist(node.to_string()))?;
+        let sequence = self.node_sequence(original).to_vec();
+
+        // the copy used by each path traversing the node
+        let mut classes: FnvHashMap<PathId, usize> = FnvHashMap::default();
+        for (path_id, path) in self.paths.iter() {
+            if path.nodes.iter().any(|h| h.id() == node) {
+                classes.insert(*path_id, 0);
+            }
+        }
+        for (path_id, copy) in assignment.iter() {
+            match (self.paths.get(path_id), classes.get_mut(path_id)) {
+                (Some(_), Some(class)) => *class = *copy,
+                (Some(path), None) => {
+                    return Err(GraphError::PositionNotFound(
+                        node.to_string(),
+                        path.name.to_string(),
+                    ))
+                }
+                (None, _) => return Err(GraphError::PathNotExist(path_id.to_string())),
+            }
+        }
+
+        // the copies each incident edge is used by
+        let mut used: FnvHashMap<Edge, FnvHashSet<usize>> = FnvHashMap::default();
+        for (path_id, class) in classes.iter() {
+            let path = self.get_path_unchecked(path_id);
+            let len = path.nodes.len();
+            let mut steps: Vec<(usize, usize)> = (1..len).map(|i| (i - 1, i)).collect();
+            if path.is_circular && len > 1 {
+                steps.push((len - 1, 0));
+            }
+            for (prev, next) in steps {
+                let (l, r) = (path.nodes[prev], path.nodes[next]);
+                if l.id() == node || r.id() == node {
+                    used.entry(Edge::edge_handle(l, r))
+                        .or_default()
+                        .insert(*class);
+                }
+            }
+        }
+        let incident: FnvHashSet<Edge> = self
+            .neighbors(Handle::pack(node, false), Direction::Right)
+            .map(|h| Edge::edge_handle(Handle::pack(node, false), h))
+            .chain(
+                self.neighbors(Handle::pack(node, false), Direction::Left)
+                    .map(|h| Edge::edge_handle(h, Handle::pack(node, false))),
+            )
+            .collect();
+
+        // create a copy for each index in use
+        let mut indices: Vec<usize> = classes.values().copied().filter(|c| *c > 0).collect();
+        indices.sort_unstable();
+        indices.dedup();
+        let mut copies: Vec<NodeId> = Vec::with_capacity(indices.len());
+        for _ in indices.iter() {
+            let id = self.max_id + 1;
+            self.create_handle(id, &sequence)?;
+            copies.push(id);
+        }
+        let copy_of = |class: usize| match indices.binary_search(&class) {
+            Ok(ix) => copies[ix],
+            Err(_) => node,
+        };
+
+        // move the steps, keeping the orientation
+        for (path_id, class) in classes.iter().filter(|(_, c)| **c > 0) {
+            let target = copy_of(*class);
+            let path = self.paths.get_mut(path_id).unwrap();
+            let first_key = path.first_key;
+            let mut visits = vec![];
+            for (ix, step) in path.nodes.iter_mut().enumerate() {
+                if step.id() == node {
+                    *step = Handle::pack(target, step.is_reverse());
+                    visits.push(ix.wrapping_add(first_key));
+                }
+            }
+            self.get_node_mut(&node)
+                .unwrap()
+                .occurrences
+                .remove(path_id);
+            let copy = self.get_node_mut(&target).unwrap();
+            copy.occurrences.insert(*path_id, visits);
+            self.record(Changes::PATHS);
+        }
+
+        // rewire the edges
+        let rename = |h: Handle, class: usize| {
+            if h.id() == node {
+                Handle::pack(copy_of(class), h.is_reverse())
+            } else {
+                h
+            }
+        };
+        let mut moved: FnvHashSet<Edge> = FnvHashSet::default();
+        for edge in incident.iter() {
+            let classes = match used.get(edge) {
+                Some(classes) => classes,
+                None => continue,
+            };
+            let Edge(l, r) = *edge;
+            let overlap = self.edge_overlap(*edge);
+            let tags = self.edge_tags(*edge).to_vec();
+            for class in classes.iter().filter(|c| **c > 0) {
+                let (l, r) = (rename(l, *class), rename(r, *class));
+                self.create_edge(Edge(l, r))?;
+                if overlap > 0 {
+                    self.overlaps.insert(Edge::edge_handle(l, r), overlap);
+                }
+                self.set_edge_tags(Edge(l, r), tags.clone());
+            }
+            if !classes.contains(&0) {
+                moved.insert(*edge);
+            }
+        }
+        // no path steps through the moved edges anymore,
+        // so this will not destroy any path
+        self.remove_edges_where(|e| moved.contains(e));
+
+        Ok(copies)
+    }
+}
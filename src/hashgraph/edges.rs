@@ -0,0 +1,253 @@
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::{
+    handle::{Direction, Edge, Handle, NodeId},
+    handlegraph::*,
+    pathgraph::PathHandleGraph,
+};
+
+use super::{generation::Changes, HashGraph, PathId};
+
+impl HashGraph {
+    /// Function that returns true if the edge exists, whatever the end
+    /// it's written from (as ```11-,13+``` and ```13-,11+```), and false
+    /// if one of its nodes doesn't exist, see
+    /// [`Edge::canonical`](../../handle/struct.Edge.html#method.canonical)
+    /// # Example
+    /// ```ignore
+    /// let (l, r) = (Handle::pack(11, true), Handle::pack(13, false));
+    /// graph.create_edge(Edge(l, r))?;
+    /// assert!(graph.has_edge_canonical(Edge(r.flip(), l.flip())));
+    /// ```
+    pub fn has_edge_canonical(&self, edge: Edge) -> bool {
+        let Edge(l, r) = edge.canonical();
+        self.graph.contains_key(&l.id()) && self.graph.contains_key(&r.id()) && self.has_edge(l, r)
+    }
+
+    /// Function that sets the number of bases shared by the end of the
+    /// left handle and the start of the right handle of an existing edge,
+    /// used to trim the sequences of the paths when they're written, see
+    /// [`write_path_fasta_streaming`](#method.write_path_fasta_streaming).\
+    /// The overlap belongs to the edge, whatever the strand it's given
+    /// from, and it's removed together with the edge
+    /// # Example
+    /// ```ignore
+    /// graph.set_edge_overlap(Edge(Handle::pack(1, false), Handle::pack(2, false)), 3)?;
+    /// ```
+    pub fn set_edge_overlap(&mut self, Edge(l, r): Edge, overlap: usize) -> Result<(), GraphError> {
+        if !self.has_edge_canonical(Edge(l, r)) {
+            return Err(GraphError::EdgeNotExist(
+                l.id().to_string(),
+                r.id().to_string(),
+            ));
+        }
+        let edge = Edge::edge_handle(l, r);
+        let old = if overlap == 0 {
+            self.overlaps.remove(&edge)
+        } else {
+            self.overlaps.insert(edge, overlap)
+        };
+        if old.unwrap_or(0) != overlap {
+            self.record(Changes::TOPOLOGY);
+        }
+        Ok(())
+    }
+
+    /// The number of bases shared by the ends of an edge, 0 if it was
+    /// never set
+    #[inline]
+    pub fn edge_overlap(&self, Edge(l, r): Edge) -> usize {
+        self.overlaps
+            .get(&Edge::edge_handle(l, r))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Function that returns the number of bases spelled by a path, as
+    /// [`path_bases_len`](#method.path_bases_len) without counting twice
+    /// the bases shared by consecutive steps: each step after the first
+    /// one loses the overlap of the edge from the previous step (at most
+    /// its length), as the first step does with the edge from the last
+    /// one if the path is circular.\
+    /// The overlaps of the GFA1 links are the bases of their second
+    /// segment covered by the CIGAR, see
+    /// [`CIGAR::query_len`](../../gfa/cigar/struct.CIGAR.html#method.query_len)
+    /// # Example
+    /// ```ignore
+    /// // S 1 ACGTACGT, S 2 ACGTACGT, L 1 + 2 + 5M, P x 1+,2+ *
+    /// assert_eq!(graph.path_bases_len(&path), Some(16));
+    /// assert_eq!(graph.path_bases_len_with_overlaps(&path), Some(11));
+    /// ```
+    pub fn path_bases_len_with_overlaps(&self, path: &PathId) -> Option<usize> {
+        let path = self.paths.get(path)?;
+        let len = |h: &Handle| {
+            self.get_node(&h.id())
+                .map_or(0, |n| self.node_sequence_len(n))
+        };
+        let mut bases: usize = path.nodes.iter().map(len).sum();
+        let closing = match (path.nodes.back(), path.nodes.front()) {
+            (Some(last), Some(first)) if path.is_circular => Some((last, first)),
+            _ => None,
+        };
+        let consecutive = path.nodes.iter().zip(path.nodes.iter().skip(1));
+        for (from, to) in consecutive.chain(closing) {
+            bases -= self.edge_overlap(Edge(*from, *to)).min(len(to));
+        }
+        Some(bases)
+    }
+
+    /// The fields written after the segments of the ```L``` (or ```E```
+    /// for GFA2) line of an edge: its overlap as a CIGAR and, in GFA2,
+    /// where the overlap is on the 2 segments, starting from the end of
+    /// the left handle and from the start of the right one
+    pub(crate) fn overlap_fields(&self, edge: Edge, gfa2: bool) -> String {
+        let overlap = self.edge_overlap(edge);
+        if !gfa2 {
+            return format!("{}M", overlap);
+        }
+        if overlap == 0 {
+            return String::from("0\t0$\t0\t0$\t0M");
+        }
+        let Edge(left, right) = edge;
+        // a position at the end of the segment ends with a $
+        let pos = |value: usize, len: usize| {
+            if value == len {
+                format!("{}$", value)
+            } else {
+                value.to_string()
+            }
+        };
+        let span = |handle: Handle, at_end: bool| {
+            let len = self.node_sequence_len(self.get_node_unchecked(&handle.id()));
+            let overlap = overlap.min(len);
+            if at_end != handle.is_reverse() {
+                (pos(len - overlap, len), pos(len, len))
+            } else {
+                (pos(0, len), pos(overlap, len))
+            }
+        };
+        let (beg1, end1) = span(left, true);
+        let (beg2, end2) = span(right, false);
+        format!("{}\t{}\t{}\t{}\t{}M", beg1, end1, beg2, end2, overlap)
+    }
+
+    /// Function that removes every edge for which the predicate returns true.\
+    /// Every logical edge is evaluated exactly once, in its canonical form
+    /// (the same form returned by [`edges`](../../handlegraph/trait.AllEdges.html)),
+    /// and both adjacency entries of a matching edge are removed in a single pass.\
+    /// Following the same policy of `remove_edge`, every path that steps through
+    /// a removed edge is destroyed.\
+    /// Returns the number of logical edges removed.
+    /// # Example
+    /// ```ignore
+    /// // remove all the edges that connect a node to itself
+    /// let removed = graph.remove_edges_where(|Edge(l, r)| l.id() == r.id());
+    /// ```
+    pub fn remove_edges_where(&mut self, mut pred: impl FnMut(&Edge) -> bool) -> usize {
+        let removed: FnvHashSet<Edge> = self.edges().filter(|edge| pred(edge)).collect();
+        if removed.is_empty() {
+            return 0;
+        }
+        self.as_one_change(|graph| {
+            graph.record(Changes::TOPOLOGY);
+
+            let mut touched: FnvHashSet<NodeId> = FnvHashSet::default();
+            for edge in removed.iter() {
+                graph.forget_edge(edge);
+            }
+            for Edge(l, r) in removed.iter() {
+                touched.insert(l.id());
+                touched.insert(r.id());
+            }
+
+            let touched: Vec<NodeId> = touched.into_iter().collect();
+            let found_before = graph.edges_found_on(&touched);
+            for id in touched.iter() {
+                if let Some(node) = graph.graph.get_mut(id) {
+                    let fwd = Handle::pack(*id, false);
+                    node.right_edges
+                        .retain(|h| !removed.contains(&Edge::edge_handle(fwd, *h)));
+                    node.left_edges
+                        .retain(|h| !removed.contains(&Edge::edge_handle(h.flip(), fwd)));
+                }
+            }
+            graph.edge_count =
+                (graph.edge_count + graph.edges_found_on(&touched)).saturating_sub(found_before);
+
+            let broken: Vec<PathId> = graph
+                .paths
+                .iter()
+                .filter(|(_, path)| {
+                    path.nodes
+                        .iter()
+                        .zip(path.nodes.iter().skip(1))
+                        .any(|(from, to)| removed.contains(&Edge::edge_handle(*from, *to)))
+                })
+                .map(|(id, _)| *id)
+                .collect();
+            let names: FnvHashMap<PathId, Vec<u8>> = broken
+                .iter()
+                .filter_map(|id| Some((*id, graph.paths.get(id)?.name.clone().into())))
+                .collect();
+            for path_id in broken {
+                graph.destroy_path(&path_id);
+                if let Some(name) = names.get(&path_id) {
+                    graph.path_id.remove(name);
+                }
+            }
+        });
+
+        removed.len()
+    }
+
+    /// Function that counts the edges again, in O(n + e), and returns
+    /// their number.\
+    /// The methods of the graph keep ```edge_count``` exact, so it's
+    /// needed only after changing the adjacency lists of the nodes
+    /// directly
+    pub fn recount_edges(&mut self) -> usize {
+        let ids: Vec<NodeId> = self.graph.keys().copied().collect();
+        self.edge_count = self.edges_found_on(&ids);
+        self.edge_count
+    }
+
+    /// The edges that ```edges``` finds on the nodes (a missing one has
+    /// none): each edge is found on one of its 2 nodes, so the edges of
+    /// a node change only with its own adjacency lists
+    pub(crate) fn edges_found_on(&self, ids: &[NodeId]) -> usize {
+        let mut seen: FnvHashSet<NodeId> = FnvHashSet::default();
+        ids.iter()
+            .filter(|id| seen.insert(**id) && self.graph.contains_key(id))
+            .map(|id| {
+                let handle = Handle::pack(*id, false);
+                let left = self.neighbors(handle, Direction::Left);
+                let right = self.neighbors(handle, Direction::Right);
+                HandleEdgesIter::new(handle, left, right).count()
+            })
+            .sum()
+    }
+
+    /// Function that removes all the edges attached to a node.\
+    /// With ```Some(dir)``` only the edges on that side of the forward
+    /// handle of the node are removed, with ```None``` both sides are cleared.\
+    /// Returns the number of logical edges removed.
+    /// # Example
+    /// ```ignore
+    /// // detach node 12 from all its successors
+    /// let removed = graph.remove_edges_of_node(NodeId::from(12), Some(Direction::Right));
+    /// ```
+    pub fn remove_edges_of_node(&mut self, node: NodeId, dir: Option<Direction>) -> usize {
+        let is = |h: &Handle, reverse: bool| h.id() == node && h.is_reverse() == reverse;
+        self.remove_edges_where(|Edge(l, r)| {
+            // an edge leaving the node forward or entering it in reverse
+            // is attached to its right side, and vice versa
+            let right = is(l, false) || is(r, true);
+            let left = is(l, true) || is(r, false);
+            match dir {
+                Some(Direction::Right) => right,
+                Some(Direction::Left) => left,
+                None => right || left,
+            }
+        })
+    }
+}
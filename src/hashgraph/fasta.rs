@@ -0,0 +1,153 @@
+use std::io::{Error, ErrorKind, Write};
+
+use crate::{
+    handle::{Edge, Handle},
+    handlegraph::*,
+};
+
+use super::{HashGraph, PathId};
+
+/// Size of the buffer used to write the sequences
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// Writer of the bases of a FASTA record, wrapping the lines, that
+/// flushes its fixed buffer to the output when it's full
+struct WrappedLines<'a, W: Write> {
+    out: &'a mut W,
+    buffer: [u8; BUFFER_SIZE],
+    used: usize,
+    line_width: usize,
+    column: usize,
+}
+
+impl<'a, W: Write> WrappedLines<'a, W> {
+    fn new(out: &'a mut W, line_width: usize) -> Self {
+        WrappedLines {
+            out,
+            buffer: [0; BUFFER_SIZE],
+            used: 0,
+            line_width,
+            column: 0,
+        }
+    }
+
+    #[inline]
+    fn push_byte(&mut self, byte: u8) -> std::io::Result<()> {
+        if self.used == BUFFER_SIZE {
+            self.out.write_all(&self.buffer)?;
+            self.used = 0;
+        }
+        self.buffer[self.used] = byte;
+        self.used += 1;
+        Ok(())
+    }
+
+    #[inline]
+    fn push(&mut self, base: u8) -> std::io::Result<()> {
+        if self.line_width > 0 && self.column == self.line_width {
+            self.push_byte(b'\n')?;
+            self.column = 0;
+        }
+        self.column += 1;
+        self.push_byte(base)
+    }
+
+    /// Ends the last line of the record and flushes the buffer
+    fn finish(mut self) -> std::io::Result<()> {
+        if self.column > 0 {
+            self.push_byte(b'\n')?;
+        }
+        self.out.write_all(&self.buffer[..self.used])
+    }
+}
+
+impl HashGraph {
+    /// Function that writes the sequence of a path as a FASTA record,
+    /// named after the path, without building the whole sequence: the
+    /// bases are written a node at a time through a small fixed buffer,
+    /// so the memory used doesn't depend on the length of the path.\
+    /// The first bases of a step that overlap the previous step, as set
+    /// by [`set_edge_overlap`](#method.set_edge_overlap), are written
+    /// only once; the reverse steps are reverse complemented while
+    /// they're written. The junction between the last and the first step
+    /// of a circular path isn't trimmed.\
+    /// The lines are wrapped after ```line_width``` bases, or never with
+    /// a width of 0
+    /// # Example
+    /// ```ignore
+    /// let mut out = std::io::BufWriter::new(File::create("chr1.fa")?);
+    /// graph.write_path_fasta_streaming(&path_id, &mut out, 60)?;
+    /// ```
+    pub fn write_path_fasta_streaming<W: Write>(
+        &self,
+        path: &PathId,
+        out: &mut W,
+        line_width: usize,
+    ) -> std::io::Result<()> {
+        let path = self.get_path(path).ok_or_else(|| {
+            Error::new(ErrorKind::NotFound, format!("path {} doesn't exist", path))
+        })?;
+        out.write_all(b">")?;
+        out.write_all(&path.name)?;
+        out.write_all(b"\n")?;
+
+        let mut lines = WrappedLines::new(out, line_width);
+        let mut previous: Option<Handle> = None;
+        for step in path.nodes.iter() {
+            for base in self.step_bases(previous, *step) {
+                lines.push(base)?;
+            }
+            previous = Some(*step);
+        }
+        lines.finish()
+    }
+
+    /// Function that returns the sequence spelled by a path, the same
+    /// written by
+    /// [`write_path_fasta_streaming`](#method.write_path_fasta_streaming),
+    /// or None if the path doesn't exist or visits a missing node
+    /// # Example
+    /// ```ignore
+    /// // P x 1+,2- *, with S 1 ACG and S 2 TTA
+    /// assert_eq!(graph.path_sequence(&0), Some(b"ACGTAA".to_vec()));
+    /// ```
+    pub fn path_sequence(&self, path: &PathId) -> Option<Vec<u8>> {
+        let path = self.get_path(path)?;
+        if !path.nodes.iter().all(|h| self.graph.contains_key(&h.id())) {
+            return None;
+        }
+        let mut sequence = Vec::new();
+        let mut previous: Option<Handle> = None;
+        for step in path.nodes.iter() {
+            sequence.extend(self.step_bases(previous, *step));
+            previous = Some(*step);
+        }
+        Some(sequence)
+    }
+
+    /// The bases of a step, reverse complemented if it's reverse, without
+    /// the first ones that overlap the previous step
+    fn step_bases(&self, previous: Option<Handle>, step: Handle) -> impl Iterator<Item = u8> + '_ {
+        let trimmed = previous.map_or(0, |p| self.edge_overlap(Edge(p, step)));
+        self.sequence_iter(step).skip(trimmed)
+    }
+
+    /// Function that writes every path, sorted by id, with
+    /// [`write_path_fasta_streaming`](#method.write_path_fasta_streaming)
+    /// # Example
+    /// ```ignore
+    /// graph.write_all_paths_fasta_streaming(&mut std::io::stdout(), 80)?;
+    /// ```
+    pub fn write_all_paths_fasta_streaming<W: Write>(
+        &self,
+        out: &mut W,
+        line_width: usize,
+    ) -> std::io::Result<()> {
+        let mut path_ids: Vec<PathId> = self.paths.keys().copied().collect();
+        path_ids.sort();
+        for path_id in path_ids.iter() {
+            self.write_path_fasta_streaming(path_id, out, line_width)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+use fnv::FnvHashSet;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+use crate::{
+    handle::{Direction, Edge, Handle, NodeId},
+    handlegraph::*,
+    util::{dna, threads::ThreadConfig},
+};
+
+use super::HashGraph;
+
+/// Features of a node, computed by
+/// [`node_features`](../graph/struct.HashGraph.html#method.node_features)
+/// ```ignore
+/// pub struct NodeFeatures {
+///     pub id: u64,
+///     pub length: usize,
+///     pub gc_content: f64,
+///     pub left_degree: usize,
+///     pub right_degree: usize,
+///     pub neighbor_lengths: Vec<usize>,
+///     pub coverage: usize,
+///     pub nodes_within_radius: Option<usize>,
+///     pub edges_within_radius: Option<usize>,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeFeatures {
+    pub id: u64,
+    /// Number of bases
+    pub length: usize,
+    /// Fraction of G and C bases (0.0 for an empty sequence)
+    pub gc_content: f64,
+    /// Distinct neighbors on the left of the forward handle
+    pub left_degree: usize,
+    /// Distinct neighbors on the right of the forward handle
+    pub right_degree: usize,
+    /// Lengths of the distinct neighbor nodes, sorted; a node connected
+    /// on both sides or with both orientations is counted once
+    pub neighbor_lengths: Vec<usize>,
+    /// Number of distinct paths going through the node
+    pub coverage: usize,
+    /// Nodes at most ```radius``` edges away, the node included
+    pub nodes_within_radius: Option<usize>,
+    /// Distinct edges between the nodes within the radius
+    pub edges_within_radius: Option<usize>,
+}
+
+/// The header of
+/// [`write_features_csv`](../graph/struct.HashGraph.html#method.write_features_csv),
+/// new columns are always added at the end
+pub const FEATURES_CSV_HEADER: &str = "id,length,gc_content,left_degree,right_degree,\
+neighbor_lengths,coverage,nodes_within_radius,edges_within_radius";
+
+fn optional(value: Option<usize>, none: &str) -> String {
+    value.map_or_else(|| none.to_string(), |v| v.to_string())
+}
+
+impl HashGraph {
+    /// Distinct neighbors of a handle on a side
+    fn distinct_side(&self, handle: Handle, dir: Direction) -> FnvHashSet<Handle> {
+        self.neighbors(handle, dir).collect()
+    }
+
+    /// Nodes reached from a node following at most ```radius``` edges,
+    /// in any direction and orientation, the node included
+    pub(crate) fn nodes_within(&self, node: NodeId, radius: usize) -> FnvHashSet<NodeId> {
+        let mut reached: FnvHashSet<NodeId> = FnvHashSet::default();
+        reached.insert(node);
+        let mut frontier = vec![node];
+        for _ in 0..radius {
+            let mut next = vec![];
+            for id in frontier {
+                let handle = Handle::pack(id, false);
+                for other in self
+                    .neighbors(handle, Direction::Left)
+                    .chain(self.neighbors(handle, Direction::Right))
+                {
+                    if reached.insert(other.id()) {
+                        next.push(other.id());
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        reached
+    }
+
+    fn features_of(&self, id: NodeId, radius: usize) -> NodeFeatures {
+        let node = self.get_node_unchecked(&id);
+        let handle = Handle::pack(id, false);
+        let sequence = self.node_sequence(node);
+        let length = sequence.len();
+
+        let left = self.distinct_side(handle, Direction::Left);
+        let right = self.distinct_side(handle, Direction::Right);
+        let neighbors: FnvHashSet<NodeId> =
+            left.iter().chain(right.iter()).map(|h| h.id()).collect();
+        let mut neighbor_lengths: Vec<usize> = neighbors
+            .iter()
+            .map(|n| self.node_len(Handle::pack(*n, false)))
+            .collect();
+        neighbor_lengths.sort();
+
+        let (nodes_within_radius, edges_within_radius) = if radius == 0 {
+            (None, None)
+        } else {
+            let ball = self.nodes_within(id, radius);
+            let mut edges: FnvHashSet<Edge> = FnvHashSet::default();
+            for n in ball.iter() {
+                let h = Handle::pack(*n, false);
+                for other in self.neighbors(h, Direction::Right) {
+                    if ball.contains(&other.id()) {
+                        edges.insert(Edge::edge_handle(h, other));
+                    }
+                }
+                for other in self.neighbors(h, Direction::Left) {
+                    if ball.contains(&other.id()) {
+                        edges.insert(Edge::edge_handle(other, h));
+                    }
+                }
+            }
+            (Some(ball.len()), Some(edges.len()))
+        };
+
+        NodeFeatures {
+            id: u64::from(id),
+            length,
+            gc_content: dna::gc_content(sequence),
+            left_degree: left.len(),
+            right_degree: right.len(),
+            neighbor_lengths,
+            coverage: node.occurrences.len(),
+            nodes_within_radius,
+            edges_within_radius,
+        }
+    }
+
+    /// Function that computes the features of every node, sorted by id,
+    /// in a single parallel pass.\
+    /// The topology fingerprint (the nodes and edges within ```radius```
+    /// edges from the node) is skipped, and left ```None```, when the
+    /// radius is 0
+    /// # Example
+    /// ```ignore
+    /// for f in graph.node_features(2) {
+    ///     println!("{} {} {:?}", f.id, f.gc_content, f.nodes_within_radius);
+    /// }
+    /// ```
+    pub fn node_features(&self, radius: usize) -> Vec<NodeFeatures> {
+        self.node_features_with_threads(radius, ThreadConfig::global())
+    }
+
+    /// Function that computes the [`node_features`](#method.node_features)
+    /// with the given number of threads, see
+    /// [`ThreadConfig`](../../util/threads/struct.ThreadConfig.html)
+    pub fn node_features_with_threads(
+        &self,
+        radius: usize,
+        threads: ThreadConfig,
+    ) -> Vec<NodeFeatures> {
+        let ids: Vec<NodeId> = self.handles_sorted().map(|h| h.id()).collect();
+        threads.map(&ids, |id| self.features_of(*id, radius))
+    }
+
+    /// Function that writes the [`node_features`](#method.node_features)
+    /// as CSV, with the columns of
+    /// [`FEATURES_CSV_HEADER`](../features/constant.FEATURES_CSV_HEADER.html).\
+    /// The neighbor lengths are separated by ```;``` and the skipped
+    /// fingerprint fields are empty
+    /// # Example
+    /// ```ignore
+    /// graph.write_features_csv(std::io::stdout(), 2).unwrap();
+    /// ```
+    pub fn write_features_csv<W: Write>(&self, mut out: W, radius: usize) -> std::io::Result<()> {
+        writeln!(out, "{}", FEATURES_CSV_HEADER)?;
+        for f in self.node_features(radius) {
+            let lengths: Vec<String> = f.neighbor_lengths.iter().map(|l| l.to_string()).collect();
+            writeln!(
+                out,
+                "{},{},{:.6},{},{},{},{},{},{}",
+                f.id,
+                f.length,
+                f.gc_content,
+                f.left_degree,
+                f.right_degree,
+                lengths.join(";"),
+                f.coverage,
+                optional(f.nodes_within_radius, ""),
+                optional(f.edges_within_radius, ""),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Function that writes the [`node_features`](#method.node_features)
+    /// as JSON lines, a JSON object for each node with the fields of
+    /// [`NodeFeatures`](../features/struct.NodeFeatures.html), where the
+    /// skipped fingerprint fields are ```null```
+    /// # Example
+    /// ```ignore
+    /// graph.write_features_jsonl(std::io::stdout(), 0).unwrap();
+    /// ```
+    pub fn write_features_jsonl<W: Write>(&self, mut out: W, radius: usize) -> std::io::Result<()> {
+        for f in self.node_features(radius) {
+            let lengths: Vec<String> = f.neighbor_lengths.iter().map(|l| l.to_string()).collect();
+            writeln!(
+                out,
+                "{{\"id\":{},\"length\":{},\"gc_content\":{},\"left_degree\":{},\
+                 \"right_degree\":{},\"neighbor_lengths\":[{}],\"coverage\":{},\
+                 \"nodes_within_radius\":{},\"edges_within_radius\":{}}}",
+                f.id,
+                f.length,
+                f.gc_content,
+                f.left_degree,
+                f.right_degree,
+                lengths.join(","),
+                f.coverage,
+                optional(f.nodes_within_radius, "null"),
+                optional(f.edges_within_radius, "null"),
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,295 @@
+use bstr::{BString, ByteSlice};
+use fnv::{FnvHashSet, FnvHasher};
+use std::hash::{Hash, Hasher};
+use std::io::{Error, ErrorKind, Write};
+use std::sync::Arc;
+
+use crate::{
+    gfa::optional::optional_fields,
+    handle::{Edge, Handle, NodeId},
+    handlegraph::*,
+    pathgraph::PathHandleGraph,
+};
+
+use super::HashGraph;
+
+/// The record of the graph built from a line of the parsed file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RawRecord {
+    Segment(NodeId),
+    /// The canonical edge
+    Link(Edge),
+    /// The name of the path
+    Path(BString),
+    /// A line that didn't build a record of its own, like a containment
+    /// or an U-group, on the segments it names: written back as it is
+    /// until one of them is removed
+    Refers(Vec<NodeId>),
+    /// A line that didn't build anything, written back as it is
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct RawLine {
+    /// The line as read, with its line terminator
+    bytes: BString,
+    record: RawRecord,
+    /// The content of the record when it was parsed
+    fingerprint: u64,
+}
+
+/// The lines of a file parsed in fidelity mode, in the order of the file
+#[derive(Debug, Clone)]
+pub(crate) struct RawLines {
+    gfa2: bool,
+    lines: Vec<RawLine>,
+}
+
+/// End the last line written if it had no line terminator (the last
+/// line of the file), before writing another one
+fn start_line<W: Write>(out: &mut W, terminated: &mut bool) -> std::io::Result<()> {
+    if !*terminated {
+        out.write_all(b"\n")?;
+        *terminated = true;
+    }
+    Ok(())
+}
+
+fn orient(handle: &Handle) -> char {
+    if handle.is_reverse() {
+        '-'
+    } else {
+        '+'
+    }
+}
+
+impl HashGraph {
+    /// The content of a record, to find if it changed after the parsing
+    fn fingerprint(&self, record: &RawRecord) -> Option<u64> {
+        let mut hasher = FnvHasher::default();
+        match record {
+            RawRecord::Segment(id) => {
+                let node = self.get_node(id)?;
+                hasher.write(self.node_sequence(node));
+                node.optional.hash(&mut hasher);
+            }
+            RawRecord::Link(edge) => {
+                let Edge(left, right) = *edge;
+                let has_nodes = self.has_node(left.id()) && self.has_node(right.id());
+                if !has_nodes || !self.has_edge(left, right) {
+                    return None;
+                }
+                hasher.write_usize(self.edge_overlap(*edge));
+                self.edge_tags(*edge).hash(&mut hasher);
+            }
+            RawRecord::Refers(ids) => {
+                if !ids.iter().all(|id| self.has_node(*id)) {
+                    return None;
+                }
+            }
+            RawRecord::Path(name) => {
+                let path = self.get_path(&self.name_to_path_handle(name)?)?;
+                hasher.write_u8(path.is_circular as u8);
+                path.nodes.iter().for_each(|h| hasher.write_u64(h.packed()));
+            }
+            RawRecord::Other => (),
+        }
+        Some(hasher.finish())
+    }
+
+    /// Keep the lines of the file the graph was just built from, for
+    /// [`write_gfa_preserving`](#method.write_gfa_preserving); the
+    /// records not in the graph (filtered out or dropped while building
+    /// it) are kept as lines that didn't build anything, referring to
+    /// the segments of the graph they name
+    pub(crate) fn retain_lines(&mut self, gfa2: bool, lines: Vec<(BString, RawRecord)>) {
+        let refers = |ids: Vec<NodeId>| {
+            let ids: Vec<NodeId> = ids.into_iter().filter(|id| self.has_node(*id)).collect();
+            if ids.is_empty() {
+                RawRecord::Other
+            } else {
+                RawRecord::Refers(ids)
+            }
+        };
+        let lines: Vec<(BString, RawRecord)> = lines
+            .into_iter()
+            .map(|(bytes, record)| match record {
+                RawRecord::Link(edge) if !self.has_edge_canonical(edge) => {
+                    let Edge(left, right) = edge;
+                    (bytes, refers(vec![left.id(), right.id()]))
+                }
+                RawRecord::Refers(ids) => (bytes, refers(ids)),
+                record => (bytes, record),
+            })
+            .collect();
+        let lines = lines
+            .into_iter()
+            .map(|(bytes, record)| match self.fingerprint(&record) {
+                Some(fingerprint) => RawLine {
+                    bytes,
+                    record,
+                    fingerprint,
+                },
+                None => RawLine {
+                    bytes,
+                    record: RawRecord::Other,
+                    fingerprint: 0,
+                },
+            })
+            .collect();
+        self.raw_lines = Some(Arc::new(RawLines { gfa2, lines }));
+    }
+
+    /// Number of lines kept by a graph parsed in fidelity mode, see
+    /// [`Parser::fidelity`](../../parser/struct.Parser.html#method.fidelity)
+    pub fn retained_lines(&self) -> Option<usize> {
+        self.raw_lines.as_ref().map(|raw| raw.lines.len())
+    }
+
+    fn write_segment<W: Write>(&self, out: &mut W, gfa2: bool, id: NodeId) -> std::io::Result<()> {
+        let node = self.get_node_unchecked(&id);
+        let sequence = self.node_sequence(node).as_bstr();
+        let tags = optional_fields(&node.optional);
+        let name = self.display_node(id);
+        if gfa2 {
+            writeln!(out, "S\t{}\t{}\t{}{}", name, sequence.len(), sequence, tags)
+        } else {
+            writeln!(out, "S\t{}\t{}{}", name, sequence, tags)
+        }
+    }
+
+    fn write_link<W: Write>(&self, out: &mut W, gfa2: bool, edge: Edge) -> std::io::Result<()> {
+        let Edge(left, right) = edge;
+        let tags = optional_fields(self.edge_tags(edge));
+        if gfa2 {
+            writeln!(
+                out,
+                "E\t*\t{}{}\t{}{}\t{}{}",
+                self.display_node(left.id()),
+                orient(&left),
+                self.display_node(right.id()),
+                orient(&right),
+                self.overlap_fields(edge, true),
+                tags
+            )
+        } else {
+            writeln!(
+                out,
+                "L\t{}\t{}\t{}\t{}\t{}{}",
+                self.display_node(left.id()),
+                orient(&left),
+                self.display_node(right.id()),
+                orient(&right),
+                self.overlap_fields(edge, false),
+                tags
+            )
+        }
+    }
+
+    fn write_path<W: Write>(&self, out: &mut W, gfa2: bool, name: &[u8]) -> std::io::Result<()> {
+        let path = self.get_path_unchecked(&self.name_to_path_handle(name).unwrap());
+        let steps: Vec<String> = path
+            .nodes
+            .iter()
+            .map(|h| format!("{}{}", self.display_node(h.id()), orient(h)))
+            .collect();
+        let tags = optional_fields(&path.optional);
+        if gfa2 {
+            writeln!(out, "O\t{}\t{}{}", name.as_bstr(), steps.join(" "), tags)
+        } else {
+            writeln!(out, "P\t{}\t{}\t*{}", name.as_bstr(), steps.join(","), tags)
+        }
+    }
+
+    /// Function that writes the graph in the format of the file it was
+    /// parsed from in fidelity mode (see
+    /// [`Parser::fidelity`](../../parser/struct.Parser.html#method.fidelity)),
+    /// writing back byte for byte, in their order, the lines of the
+    /// records that didn't change.\
+    /// Only the segments, links (or edges) and paths (or O-groups) that
+    /// changed are written again, in place of their line: a segment whose
+    /// sequence or tags changed, a link whose overlap or tags changed, a
+    /// path whose steps changed. The records removed from the graph are
+    /// left out, and the new ones are written after all the lines of the
+    /// file, as segments, links and paths sorted.
+    /// The lines that didn't build a record are written back: the header
+    /// and the comments always, the containments, the U-groups, the
+    /// fragments, the gaps and the GFA2 edges that aren't dovetails as
+    /// long as the segments they name are in the graph.\
+    /// Fails with ```InvalidInput``` if the graph doesn't keep the lines
+    /// of a file
+    /// # Example
+    /// ```ignore
+    /// let mut graph = Parser::new().fidelity(true).parse_file_to_graph("in.gfa")?;
+    /// graph.modify_handle(5, b"GATTACA")?;
+    /// graph.write_gfa_preserving(std::io::BufWriter::new(File::create("out.gfa")?))?;
+    /// ```
+    pub fn write_gfa_preserving<W: Write>(&self, mut out: W) -> std::io::Result<()> {
+        let raw = self.raw_lines.as_ref().ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "the graph wasn't parsed in fidelity mode",
+            )
+        })?;
+        let gfa2 = raw.gfa2;
+        let mut segments: FnvHashSet<NodeId> = FnvHashSet::default();
+        let mut links: FnvHashSet<Edge> = FnvHashSet::default();
+        let mut paths: FnvHashSet<&[u8]> = FnvHashSet::default();
+        let mut terminated = true;
+
+        for line in raw.lines.iter() {
+            match self.fingerprint(&line.record) {
+                Some(fingerprint) if fingerprint == line.fingerprint => {
+                    out.write_all(&line.bytes)?;
+                    terminated = line.bytes.ends_with(b"\n");
+                }
+                // removed from the graph
+                None => (),
+                Some(_) => {
+                    start_line(&mut out, &mut terminated)?;
+                    match &line.record {
+                        RawRecord::Segment(id) => self.write_segment(&mut out, gfa2, *id)?,
+                        RawRecord::Link(edge) => self.write_link(&mut out, gfa2, *edge)?,
+                        RawRecord::Path(name) => self.write_path(&mut out, gfa2, name)?,
+                        RawRecord::Refers(_) | RawRecord::Other => unreachable!(),
+                    }
+                }
+            }
+            match &line.record {
+                RawRecord::Segment(id) => {
+                    segments.insert(*id);
+                }
+                RawRecord::Link(edge) => {
+                    links.insert(*edge);
+                }
+                RawRecord::Path(name) => {
+                    paths.insert(name.as_slice());
+                }
+                RawRecord::Refers(_) | RawRecord::Other => (),
+            }
+        }
+
+        for handle in self.handles_sorted() {
+            if !segments.contains(&handle.id()) {
+                start_line(&mut out, &mut terminated)?;
+                self.write_segment(&mut out, gfa2, handle.id())?;
+            }
+        }
+        for edge in self.edges_sorted() {
+            if !links.contains(&edge) {
+                start_line(&mut out, &mut terminated)?;
+                self.write_link(&mut out, gfa2, edge)?;
+            }
+        }
+        let mut path_ids: Vec<_> = self.paths.keys().copied().collect();
+        path_ids.sort();
+        for path_id in path_ids {
+            let name = &self.paths[&path_id].name;
+            if !paths.contains(name.as_slice()) {
+                start_line(&mut out, &mut terminated)?;
+                self.write_path(&mut out, gfa2, name)?;
+            }
+        }
+        Ok(())
+    }
+}
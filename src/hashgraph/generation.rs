@@ -0,0 +1,153 @@
+use std::ops::BitOr;
+
+use super::HashGraph;
+
+/// What a mutation changed, to bump the right generation counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Changes(u8);
+
+impl Changes {
+    pub(crate) const NONE: Changes = Changes(0);
+    /// Nodes added or removed, edges or their overlaps changed
+    pub(crate) const TOPOLOGY: Changes = Changes(1);
+    /// The sequence of an existing node changed
+    pub(crate) const SEQUENCES: Changes = Changes(1 << 1);
+    /// Paths created, destroyed or changed
+    pub(crate) const PATHS: Changes = Changes(1 << 2);
+    /// The optional fields of a node or of an edge changed
+    pub(crate) const TAGS: Changes = Changes(1 << 3);
+    pub(crate) const ALL: Changes = Changes(0b1111);
+
+    #[inline]
+    fn contains(self, other: Changes) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    #[inline]
+    fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for Changes {
+    type Output = Changes;
+
+    #[inline]
+    fn bitor(self, other: Changes) -> Changes {
+        Changes(self.0 | other.0)
+    }
+}
+
+/// The generation counters of an HashGraph, and the changes of the
+/// operation in progress, counted once when it ends
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Generations {
+    global: u64,
+    topology: u64,
+    sequence: u64,
+    path: u64,
+    tag: u64,
+    depth: u32,
+    pending: Changes,
+}
+
+impl Generations {
+    fn bump(&mut self, changes: Changes) {
+        if changes.is_empty() {
+            return;
+        }
+        self.global += 1;
+        if changes.contains(Changes::TOPOLOGY) {
+            self.topology += 1;
+        }
+        if changes.contains(Changes::SEQUENCES) {
+            self.sequence += 1;
+        }
+        if changes.contains(Changes::PATHS) {
+            self.path += 1;
+        }
+        if changes.contains(Changes::TAGS) {
+            self.tag += 1;
+        }
+    }
+}
+
+impl HashGraph {
+    /// Function that returns a counter bumped once by every method that
+    /// changes the graph, and never by a method that fails without
+    /// changing it: an index built from the graph is still valid while
+    /// the counter has the same value.\
+    /// The counters only grow, and a clone of the graph starts from the
+    /// values of the original
+    /// # Example
+    /// ```ignore
+    /// let built = graph.generation();
+    /// graph.remove_handle(12)?;
+    /// assert!(graph.generation() > built);
+    /// ```
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generations.global
+    }
+
+    /// Like [`generation`](#method.generation), but bumped only when
+    /// nodes are added or removed, or edges (or their overlaps) change
+    #[inline]
+    pub fn topology_generation(&self) -> u64 {
+        self.generations.topology
+    }
+
+    /// Like [`generation`](#method.generation), but bumped only when the
+    /// sequence of an existing node changes
+    #[inline]
+    pub fn sequence_generation(&self) -> u64 {
+        self.generations.sequence
+    }
+
+    /// Like [`generation`](#method.generation), but bumped only when a
+    /// path is created, destroyed or changes its steps
+    #[inline]
+    pub fn path_generation(&self) -> u64 {
+        self.generations.path
+    }
+
+    /// Like [`generation`](#method.generation), but bumped only when the
+    /// optional fields of a node or of an edge are set or removed
+    #[inline]
+    pub fn tag_generation(&self) -> u64 {
+        self.generations.tag
+    }
+
+    /// Function that bumps all the generation counters, dropping the
+    /// sorted handles and edges.\
+    /// It has to be called after modifying the public fields directly
+    #[inline]
+    pub fn mark_modified(&mut self) {
+        self.record(Changes::ALL);
+    }
+
+    /// Count a change, at the end of the operation in progress if any
+    #[inline]
+    pub(crate) fn record(&mut self, changes: Changes) {
+        let generations = &mut self.generations;
+        if generations.depth > 0 {
+            generations.pending = generations.pending | changes;
+        } else {
+            generations.bump(changes);
+        }
+    }
+
+    /// Run ```f``` as a single operation: the changes made by the methods
+    /// it calls bump each counter once, when it returns
+    pub(crate) fn as_one_change<R>(&mut self, f: impl FnOnce(&mut HashGraph) -> R) -> R {
+        self.generations.depth += 1;
+        let result = f(self);
+        let generations = &mut self.generations;
+        generations.depth -= 1;
+        if generations.depth == 0 {
+            let pending = std::mem::take(&mut generations.pending);
+            generations.bump(pending);
+        }
+        result
+    }
+}
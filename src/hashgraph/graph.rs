@@ -1,6 +1,13 @@
 use fnv::FnvHashMap;
 
-use crate::gfa::{gfa1::GFA, gfa2::GFA2, orientation::Orientation};
+use crate::gfa::{
+    cigar::CIGAR,
+    gfa1::{Containment, Header, Link, GFA},
+    gfa2::{Edge as Gfa2Edge, Header as Gfa2Header, Segment as Gfa2Segment, GFA2},
+    optional::OptField,
+    orientation::Orientation,
+    segment_id::PathParseError,
+};
 use crate::{
     handle::{Edge as GraphEdge, Handle, NodeId},
     handlegraph::*,
@@ -8,12 +15,25 @@ use crate::{
     pathgraph::PathHandleGraph,
 };
 
-use super::{Node, Path, PathId};
+use super::{
+    attach::{gfa1_placeholder, gfa2_placeholder},
+    fidelity::RawLines,
+    generation::Generations,
+    groups::ContainmentRecord,
+    interner::{
+        resolve_sequence, sequence_len, store_sequence, try_resolve_sequence, SequenceInterner,
+    },
+    names::NodeNames,
+    node::SequenceRef,
+    sorted::SortedCache,
+    IdBoundsMismatch, Node, Path, PathId,
+};
+use crate::parser::progress::{BuildProgress, ProgressHook};
+use crate::parser::recovery::RecoveryReport;
 use crate::util::dna;
-use bstr::BString;
-use rayon::prelude::*;
+use bstr::{BString, ByteSlice};
 use std::fmt;
-use std::sync::Mutex;
+use std::sync::Arc;
 
 /// New type
 /// # Example
@@ -24,101 +44,444 @@ use std::sync::Mutex;
 ///     pub graph: FnvHashMap<NodeId, Node>,
 ///     pub path_id: FnvHashMap<Vec<u8>, i64>,
 ///     pub paths: FnvHashMap<i64, Path>,
+///     pub header: Option<Header>,
 /// }
 /// ```
 #[derive(Clone, Debug)]
 pub struct HashGraph {
+    /// The largest and the smallest node id, 0 and ```u64::MAX``` without
+    /// nodes (see [`id_bounds`](#method.id_bounds)). Every method keeps
+    /// them exact: adding a node widens them, removing the node with
+    /// the smallest or the largest id recomputes them in O(n), as
+    /// renaming one of them; after changing the nodes directly they're
+    /// fixed by [`recompute_bounds`](#method.recompute_bounds)
     pub max_id: NodeId,
     pub min_id: NodeId,
     pub graph: FnvHashMap<NodeId, Node>,
     pub path_id: FnvHashMap<Vec<u8>, i64>,
     pub paths: FnvHashMap<i64, Path>,
+    /// The header lines of the file the graph was built from, merged in
+    /// one GFA1 header (with the version of the file, as ```2.0```), and
+    /// written back with its tags by
+    /// [`to_gfa`](../../util/to_file/fn.to_gfa.html)
+    pub header: Option<Header>,
+    /// The nodes of the U-groups of GFA2 by name, see
+    /// [`subset`](#method.subset)
+    pub(crate) subsets: FnvHashMap<BString, Vec<NodeId>>,
+    /// The C lines of GFA1, see
+    /// [`containments_of`](#method.containments_of)
+    pub(crate) containments: Vec<ContainmentRecord>,
+    pub(crate) generations: Generations,
+    pub(crate) sorted: SortedCache,
+    pub(crate) interner: Option<SequenceInterner>,
+    /// Bases shared by the two ends of an edge, by canonical edge
+    pub(crate) overlaps: FnvHashMap<GraphEdge, usize>,
+    /// The optional fields of the edges, by canonical edge, see
+    /// [`edge_tags`](#method.edge_tags)
+    pub(crate) edge_tags: FnvHashMap<GraphEdge, Vec<OptField>>,
+    /// The declared length of the segments written without a sequence
+    /// (0 if it isn't known), see
+    /// [`nodes_missing_sequence`](#method.nodes_missing_sequence)
+    pub(crate) placeholders: FnvHashMap<NodeId, usize>,
+    /// The lines of the file, when it's parsed in fidelity mode
+    pub(crate) raw_lines: Option<Arc<RawLines>>,
+    /// The names of the segments that aren't numbers
+    pub(crate) names: NodeNames,
+    /// The number of edges found by ```edges```, kept by every method
+    /// adding or removing them, see
+    /// [`recount_edges`](#method.recount_edges)
+    pub(crate) edge_count: usize,
+}
+
+/// How an HashGraph stores its data, set when it's created with
+/// [`with_config`](struct.HashGraph.html#method.with_config)
+/// ```ignore
+/// pub struct HashGraphConfig {
+///     pub intern_sequences: bool,
+/// }
+/// ```
+/// * ```intern_sequences``` stores each distinct node sequence once, in a
+///   table of the graph, and the nodes keep its index: for graphs with
+///   many nodes with the same short sequence it saves the allocation of
+///   each copy, while the table keeps two copies of each distinct
+///   sequence. The table is append-only, so a sequence that isn't used
+///   anymore is still kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HashGraphConfig {
+    pub intern_sequences: bool,
+}
+
+/// An inconsistency between the fields of an HashGraph, as after
+/// changing them directly, found by
+/// [`check_consistency`](struct.HashGraph.html#method.check_consistency)
+/// ```ignore
+/// pub enum ConsistencyError {
+///     UnmirroredEdge { node: NodeId, edge: Edge },
+///     MissingStepNode { path_id: PathId, path_name: BString, index: usize, node: NodeId },
+///     DanglingOccurrence { node: NodeId, path_id: PathId, index: usize },
+///     IdBounds(IdBoundsMismatch),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsistencyError {
+    /// An edge kept by the node, that isn't kept by the node on its
+    /// other end (or that node doesn't exist)
+    UnmirroredEdge { node: NodeId, edge: GraphEdge },
+    /// The step (at ```index```) of a path visits a node that doesn't
+    /// exist
+    MissingStepNode {
+        path_id: PathId,
+        path_name: BString,
+        index: usize,
+        node: NodeId,
+    },
+    /// An occurrence of the node that doesn't point at a step of the
+    /// path on the node, or the path doesn't exist
+    DanglingOccurrence {
+        node: NodeId,
+        path_id: PathId,
+        index: usize,
+    },
+    /// ```min_id``` and ```max_id``` don't match the nodes
+    IdBounds(IdBoundsMismatch),
+}
+
+impl ConsistencyError {
+    /// The stable code of the inconsistency, see the
+    /// [`report`](../../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConsistencyError::UnmirroredEdge { .. } => "V009",
+            ConsistencyError::MissingStepNode { .. } => "V010",
+            ConsistencyError::DanglingOccurrence { .. } => "V011",
+            ConsistencyError::IdBounds(mismatch) => mismatch.code(),
+        }
+    }
+}
+
+impl fmt::Display for ConsistencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shown = |h: &Handle| format!("{}{}", h.id(), if h.is_reverse() { "-" } else { "+" });
+        match self {
+            ConsistencyError::UnmirroredEdge {
+                node,
+                edge: GraphEdge(l, r),
+            } => write!(
+                f,
+                "The node {} keeps the edge {} -> {}, but its other end doesn't",
+                node,
+                shown(l),
+                shown(r)
+            ),
+            ConsistencyError::MissingStepNode {
+                path_name,
+                index,
+                node,
+                ..
+            } => write!(
+                f,
+                "The Path ({}) visits the missing node {} at step {}",
+                path_name, node, index
+            ),
+            ConsistencyError::DanglingOccurrence {
+                node,
+                path_id,
+                index,
+            } => write!(
+                f,
+                "The node {} isn't at the step {} of the path {}",
+                node, index, path_id
+            ),
+            ConsistencyError::IdBounds(mismatch) => mismatch.fmt(f),
+        }
+    }
 }
 
 impl Default for HashGraph {
     fn default() -> HashGraph {
         HashGraph {
             max_id: NodeId::from(0),
-            min_id: NodeId::from(std::u64::MAX),
+            min_id: NodeId::from(u64::MAX),
             graph: Default::default(),
             path_id: Default::default(),
             paths: Default::default(),
+            header: None,
+            subsets: Default::default(),
+            containments: vec![],
+            generations: Default::default(),
+            sorted: Default::default(),
+            interner: None,
+            overlaps: Default::default(),
+            edge_tags: Default::default(),
+            placeholders: Default::default(),
+            raw_lines: None,
+            names: Default::default(),
+            edge_count: 0,
         }
     }
 }
 
+/// The most nodes, edges and paths printed by the Display of an
+/// HashGraph, see [`write_full`](struct.HashGraph.html#method.write_full)
+pub const DISPLAY_LIMIT: usize = 1_000;
+
+/// Display prints the nodes sorted by id, the edges sorted and the paths
+/// in the order of their ids, each one spelled by its sequences; a
+/// graph with more than ```DISPLAY_LIMIT``` nodes, edges or paths has
+/// only the first ones of each, followed by how many are left out
 impl fmt::Display for HashGraph {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let nodes = Mutex::new(String::new());
-        // get all the nodes
-        self.handles_par().for_each(|handle| {
-            let node_id: String = handle.id().to_string();
-            let sequence: BString = self.sequence_iter(handle.forward()).collect();
-            nodes
-                .lock()
-                .unwrap()
-                .push_str(&format!("\t\t{}: {}\n", node_id, sequence));
-        });
+        self.write_display(f, Some(DISPLAY_LIMIT))
+    }
+}
 
-        let edges = Mutex::new(String::new());
-        // get all the link (edge) between nodes
-        self.edges_par().for_each(|edge| {
-            let orient = |rev: bool| {
-                if rev {
-                    "-".to_string()
-                } else {
-                    "+".to_string()
-                }
-            };
-            let GraphEdge(left, right) = edge;
-            let from_node: String = left.id().to_string();
-            let to_node: String = right.id().to_string();
-            let left_orient: String = orient(left.is_reverse());
-            let right_orient: String = orient(right.is_reverse());
-
-            edges.lock().unwrap().push_str(&format!(
-                "\t\t{}{} -- {}{}\n",
-                from_node, left_orient, to_node, right_orient
-            ));
-        });
+/// The Display of an HashGraph without a limit
+struct FullDisplay<'a>(&'a HashGraph);
 
-        let mut paths: String = String::new();
-        // get all the path
-        self.paths().for_each(|path_id| {
-            let path = self.paths.get(&path_id).unwrap();
-            //get the id or path name of a path
-            let name = &path.name;
-            let mut first: bool = true;
+impl fmt::Display for FullDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.write_display(f, None)
+    }
+}
 
+impl HashGraph {
+    /// Function that writes the graph as its Display does, with all its
+    /// nodes, edges and paths however many they are
+    /// # Example
+    /// ```ignore
+    /// graph.write_full(std::io::BufWriter::new(File::create("graph.txt")?))?;
+    /// ```
+    pub fn write_full<W: std::io::Write>(&self, mut out: W) -> std::io::Result<()> {
+        write!(out, "{}", FullDisplay(self))
+    }
+
+    fn write_display<W: fmt::Write>(&self, f: &mut W, limit: Option<usize>) -> fmt::Result {
+        let limit = limit.unwrap_or(usize::MAX);
+        let left_out = |f: &mut W, total: usize| {
+            if total > limit {
+                writeln!(f, "\t\t... {} more", total - limit)
+            } else {
+                Ok(())
+            }
+        };
+        let orient = |rev: bool| if rev { "-" } else { "+" };
+
+        writeln!(f, "Graph {{\n\tNodes:")?;
+        for handle in self.handles_sorted().take(limit) {
+            let sequence: BString = self.sequence_iter(handle.forward()).collect();
+            writeln!(f, "\t\t{}: {}", handle.id(), sequence)?;
+        }
+        left_out(f, self.graph.len())?;
+
+        writeln!(f, "\tEdges:")?;
+        for GraphEdge(left, right) in self.edges_sorted().take(limit) {
+            writeln!(
+                f,
+                "\t\t{}{} -- {}{}",
+                left.id(),
+                orient(left.is_reverse()),
+                right.id(),
+                orient(right.is_reverse())
+            )?;
+        }
+        left_out(f, self.edge_count)?;
+
+        writeln!(f, "\tPaths:")?;
+        let mut path_ids: Vec<&PathId> = self.paths().collect();
+        path_ids.sort();
+        for path_id in path_ids.iter().take(limit) {
+            let path = self.paths.get(path_id).unwrap();
+            if path.nodes.is_empty() {
+                writeln!(f)?;
+                continue;
+            }
+            write!(f, "\t\t{}: ", path.name)?;
             for (ix, handle) in path.nodes.iter().enumerate() {
                 let node = self.get_node(&handle.id()).unwrap();
-                if first {
-                    first = false;
-                    paths.push_str(&format!("\t\t{}: ", name));
-                }
                 if ix != 0 {
-                    paths.push_str(&format!(" -> "));
+                    write!(f, " -> ")?;
                 }
                 // print correct reverse and complement sequence to display the correct path
                 if handle.is_reverse() {
-                    let rev_sequence: String =
-                        String::from_utf8(dna::rev_comp(node.sequence.as_slice()))
-                            .expect("Unable to convert from UTF8");
-                    paths.push_str(&format!("{}", rev_sequence));
+                    let rev_sequence = dna::rev_comp_slice(self.node_sequence(node));
+                    write!(f, "{}", rev_sequence.as_bstr())?;
                 } else {
-                    paths.push_str(&format!("{}", node.sequence));
+                    write!(f, "{}", self.node_sequence(node).as_bstr())?;
                 }
             }
-            paths.push_str(&format!("\n"));
-        });
+            writeln!(f)?;
+        }
+        left_out(f, path_ids.len())?;
+        write!(f, "}}")
+    }
+}
 
-        write!(
-            f,
-            "Graph {{\n\tNodes:\n{}\tEdges:\n{}\tPaths:\n{}}}",
-            nodes.into_inner().unwrap(),
-            edges.into_inner().unwrap(),
-            paths
-        )
+/// The edge between the handles of the 2 segment references of a GFA2
+/// edge
+#[inline]
+pub(crate) fn gfa2_edge(e: &Gfa2Edge) -> GraphEdge {
+    GraphEdge(
+        Handle::new(e.sid1, e.sid1_orient),
+        Handle::new(e.sid2, e.sid2_orient),
+    )
+}
+
+/// The edge between the handles of the 2 segments of a GFA1 link
+#[inline]
+pub(crate) fn link_edge(l: &Link) -> GraphEdge {
+    GraphEdge(
+        Handle::new(l.from_segment, l.from_orient),
+        Handle::new(l.to_segment, l.to_orient),
+    )
+}
+
+/// The containment of 2 nodes of a GFA1 C line
+#[inline]
+pub(crate) fn containment_record(c: &Containment) -> ContainmentRecord {
+    ContainmentRecord {
+        container: Handle::new(c.container_name, c.container_orient),
+        contained: Handle::new(c.contained_name, c.contained_orient),
+        pos: c.pos,
+        overlap: c.overlap.clone(),
+    }
+}
+
+/// The bases of the second segment of a GFA1 link covered by its
+/// overlap, 0 if it's unknown or isn't a CIGAR
+#[inline]
+pub(crate) fn link_overlap(l: &Link) -> usize {
+    CIGAR::from_bytes(&l.overlap).map_or(0, |c| c.query_len())
+}
+
+/// The header lines of a GFA object merged in one, None without them
+fn header_of(file: &FileType) -> Option<Header> {
+    match file {
+        FileType::GFA(x) => merged_header(x.headers.iter().cloned()),
+        FileType::GFA2(x) => merged_header(x.headers.iter().map(gfa2_header)),
+    }
+}
+
+/// A GFA2 header as the header of the graph
+#[inline]
+pub(crate) fn gfa2_header(h: &Gfa2Header) -> Header {
+    Header {
+        version: h.version.clone(),
+        tags: h.tags.clone(),
+    }
+}
+
+/// The headers merged in the first one, as
+/// [`Header::merge`](../../gfa/gfa1/struct.Header.html#method.merge) does
+pub(crate) fn merged_header<I: IntoIterator<Item = Header>>(headers: I) -> Option<Header> {
+    headers.into_iter().reduce(|mut first, h| {
+        first.merge(h);
+        first
+    })
+}
+
+/// The graph built from a GFA object by
+/// [`create_graph_report`](struct.HashGraph.html#method.create_graph_report),
+/// with the records left out of it
+/// ```ignore
+/// pub struct BuildReport {
+///     pub graph: HashGraph,
+///     pub missing_segment_edges: Vec<(u64, u64)>,
+///     pub missing_segment_steps: Vec<(BString, usize, u64)>,
+///     pub errors: Vec<GraphError>,
+/// }
+/// ```
+#[derive(Debug)]
+pub struct BuildReport {
+    pub graph: HashGraph,
+    /// The edges (the ids of their segments) with a segment that
+    /// doesn't exist
+    pub missing_segment_edges: Vec<(u64, u64)>,
+    /// The path steps on a segment that doesn't exist: the name of the
+    /// path, the index of the step in its record and the segment
+    pub missing_segment_steps: Vec<(BString, usize, u64)>,
+    /// The other records left out, as the segments declared twice
+    pub errors: Vec<GraphError>,
+}
+
+impl BuildReport {
+    /// Returns true when every record is in the graph
+    pub fn is_complete(&self) -> bool {
+        self.missing_segment_edges.is_empty()
+            && self.missing_segment_steps.is_empty()
+            && self.errors.is_empty()
+    }
+}
+
+/// The segments (their id, name, sequence, tags and declared length if
+/// they're written without a sequence), the edges, the
+/// overlaps of the edges that have one and the paths (their name, steps
+/// and tags) of a GFA object
+type Records = (
+    Vec<(u64, BString, BString, Vec<OptField>, Option<usize>)>,
+    Vec<GraphEdge>,
+    Vec<(GraphEdge, usize, Vec<OptField>)>,
+    Vec<(BString, Vec<PathStepResult>, Vec<OptField>)>,
+    Vec<(BString, Vec<u64>)>,
+    Vec<ContainmentRecord>,
+);
+
+type PathStepResult = Result<(u64, Orientation), PathParseError>;
+
+/// The records of a GFA object, in the order they are added to the graph
+fn records_of(file: FileType) -> Records {
+    match file {
+        FileType::GFA(x) => (
+            x.segments
+                .into_iter()
+                .map(|s| {
+                    let placeholder = gfa1_placeholder(&s.sequence, &s.optional);
+                    (s.name, s.original_name, s.sequence, s.optional, placeholder)
+                })
+                .collect(),
+            x.links.iter().map(link_edge).collect(),
+            x.links
+                .iter()
+                .map(|l| (link_edge(l), link_overlap(l), l.optional.clone()))
+                .filter(|(_, overlap, optional)| *overlap > 0 || !optional.is_empty())
+                .collect(),
+            x.paths
+                .iter()
+                .map(|p| {
+                    let steps = p.iter_checked().collect::<Vec<_>>();
+                    (p.path_name.clone(), steps, p.optional.clone())
+                })
+                .collect(),
+            vec![],
+            x.containments.iter().map(containment_record).collect(),
+        ),
+        FileType::GFA2(x) => (
+            x.segments
+                .into_iter()
+                .map(|s| {
+                    let placeholder = gfa2_placeholder(&s.sequence, s.slen);
+                    let sequence = Gfa2Segment::node_sequence(s.sequence, s.slen);
+                    (s.id, s.original_name, sequence, s.optional, placeholder)
+                })
+                .collect(),
+            x.edges.iter().map(gfa2_edge).collect(),
+            x.edges
+                .iter()
+                .filter(|e| !e.optional.is_empty())
+                .map(|e| (gfa2_edge(e), 0, e.optional.clone()))
+                .collect(),
+            x.groups_o
+                .iter()
+                .map(|o| {
+                    let steps = o.iter_checked().collect::<Vec<_>>();
+                    (o.id.clone(), steps, o.optional.clone())
+                })
+                .collect(),
+            x.groups_u
+                .iter()
+                .map(|u| (u.id.clone(), u.iter_ids().collect()))
+                .collect(),
+            vec![],
+        ),
     }
 }
 
@@ -132,8 +495,93 @@ impl HashGraph {
         Default::default()
     }
 
+    /// Create an empty HashGraph that stores its data as set in ```config```
+    /// # Example
+    /// ```ignore
+    /// let config = HashGraphConfig { intern_sequences: true };
+    /// let graph = HashGraph::with_config(config).create_graph(FileType::GFA(gfa))?;
+    /// ```
+    pub fn with_config(config: HashGraphConfig) -> HashGraph {
+        HashGraph {
+            interner: if config.intern_sequences {
+                Some(SequenceInterner::default())
+            } else {
+                None
+            },
+            ..Default::default()
+        }
+    }
+
+    pub fn config(&self) -> HashGraphConfig {
+        HashGraphConfig {
+            intern_sequences: self.interner.is_some(),
+        }
+    }
+
+    /// The sequence of a node of the graph, the way to read it for both
+    /// the inline and the interned sequences.\
+    /// Panics if the sequence can't be resolved, as an interned sequence
+    /// of a node taken from another graph or a lazy sequence whose file
+    /// changed, see [`try_node_sequence`](#method.try_node_sequence)
+    #[inline]
+    pub fn node_sequence<'a>(&'a self, node: &'a Node) -> &'a [u8] {
+        resolve_sequence(&self.interner, &node.sequence)
+    }
+
+    /// The sequence of a node of the graph like
+    /// [`node_sequence`](#method.node_sequence), returning
+    /// ```GraphError::SequenceUnavailable``` instead of panicking when it
+    /// can't be resolved
+    #[inline]
+    pub fn try_node_sequence<'a>(&'a self, node: &'a Node) -> Result<&'a [u8], GraphError> {
+        try_resolve_sequence(&self.interner, &node.sequence)
+    }
+
+    /// The length of the sequence of a node, that doesn't read the
+    /// sequence of a lazily loaded node
+    #[inline]
+    pub fn node_sequence_len(&self, node: &Node) -> usize {
+        sequence_len(&self.interner, &node.sequence)
+    }
+
+    /// The sequence to store in a node of the graph, interned if the
+    /// graph interns its sequences
+    #[inline]
+    pub(crate) fn make_sequence(&mut self, sequence: &[u8]) -> SequenceRef {
+        store_sequence(&mut self.interner, sequence)
+    }
+
+    /// Keep the optional fields of a segment in its node, if the node
+    /// exists
+    pub(crate) fn set_node_tags(&mut self, id: NodeId, optional: Vec<OptField>) {
+        if let Some(node) = self.graph.get_mut(&id) {
+            node.optional = optional;
+        }
+    }
+
+    /// Keep the optional fields of a path in the path with this name, if
+    /// it exists
+    pub(crate) fn set_path_tags(&mut self, name: &[u8], optional: Vec<OptField>) {
+        let path_id = self.path_id.get(name).copied();
+        if let Some(path) = path_id.and_then(|id| self.paths.get_mut(&id)) {
+            path.optional = optional;
+        }
+    }
+
+    /// Number of distinct sequences stored by an interning graph
+    pub fn interned_sequences(&self) -> Option<usize> {
+        self.interner.as_ref().map(|i| i.len())
+    }
+
     /// Build an HashGraph from a GFA Object\
-    /// The function will iterate only over the segments, edges (links) and ogroups (paths) fields
+    /// The function will iterate only over the segments, edges (links) and ogroups (paths) fields.\
+    /// The records that can't be added are logged as warnings, see
+    /// [`create_graph_with_errors`](#method.create_graph_with_errors),
+    /// except two segment names with the same node id, that fail with
+    /// ```NameCollision``` as the records of the second segment would be
+    /// added to the first one, and a path step that isn't a segment name
+    /// followed by its orientation, that fails with ```InvalidPathStep```
+    /// as the path would miss a node
     ///
     /// [enum]: https://doc.rust-lang.org/std/keyword.enum.html
     /// [gfa]: https://github.com/GFA-spec/GFA-spec/blob/master/GFA1.md
@@ -154,82 +602,312 @@ impl HashGraph {
     /// }
     /// ```
     pub fn create_graph(&mut self, file: FileType) -> Result<HashGraph, GraphError> {
-        match file {
-            FileType::GFA(x) => {
-                x.segments.into_iter().for_each(|s| {
-                    match self.create_handle(s.name, &s.sequence) {
-                        Err(why) => println!("Error {}", why),
-                        _ => (),
-                    }
-                });
-                x.links.into_iter().for_each(|l| {
-                    let left = Handle::new(l.from_segment, l.from_orient);
-                    let right = Handle::new(l.to_segment, l.to_orient);
-                    match self.create_edge(GraphEdge(left, right)) {
-                        Err(why) => println!("Error {}", why),
-                        _ => (),
-                    }
-                });
-                x.paths.into_iter().for_each(|p| {
-                    let path_id = self.create_path_handle(&p.path_name, false);
-                    for (id, orient) in p.iter() {
-                        match self.append_step(&path_id, Handle::new(id, orient)) {
-                            Err(why) => println!("Error: {}", why),
-                            _ => (),
-                        };
-                    }
-                });
-                Ok(self.to_owned())
+        self.create_graph_reporting(file, false, &ProgressHook::default())
+    }
+
+    /// Build an HashGraph from a GFA Object, as
+    /// [`create_graph`](#method.create_graph), calling the hook with the
+    /// records added every ```every``` segments and paths, once after the
+    /// edges (that are added together) and at the end, see
+    /// [`ProgressHook`](../../parser/progress/struct.ProgressHook.html)
+    /// ## Examples
+    /// ```ignore
+    /// let hook = ProgressHook::new(|p: BuildProgress| {
+    ///     eprintln!("{}/{} segments", p.segments, p.total_segments)
+    /// });
+    /// let graph = HashGraph::new().create_graph_with_progress(FileType::GFA(gfa), &hook)?;
+    /// ```
+    pub fn create_graph_with_progress(
+        &mut self,
+        file: FileType,
+        progress: &ProgressHook<BuildProgress>,
+    ) -> Result<HashGraph, GraphError> {
+        self.create_graph_reporting(file, false, progress)
+    }
+
+    /// Like [`create_graph_with_progress`](#method.create_graph_with_progress),
+    /// only logging the path steps that can't be parsed if
+    /// ```skip_invalid_steps```, for the parsers that ignore every error
+    pub(crate) fn create_graph_reporting(
+        &mut self,
+        file: FileType,
+        skip_invalid_steps: bool,
+        progress: &ProgressHook<BuildProgress>,
+    ) -> Result<HashGraph, GraphError> {
+        let mut failure = None;
+        self.keep_header(&file);
+        for why in self.add_records(records_of(file), false, progress) {
+            match why {
+                GraphError::NameCollision(_, _) if failure.is_none() => failure = Some(why),
+                GraphError::InvalidPathStep(_, _) if failure.is_none() && !skip_invalid_steps => {
+                    failure = Some(why)
+                }
+                why => log::warn!("{}", why),
             }
-            FileType::GFA2(x) => {
-                x.segments
-                    .into_iter()
-                    .for_each(|s| match self.create_handle(s.id, &s.sequence) {
-                        Err(why) => println!("Error {}", why),
-                        _ => (),
-                    });
-                x.edges.into_iter().for_each(|e| {
-                    let orient = |rev: &str| match rev {
-                        "43" => Orientation::Forward,
-                        "45" => Orientation::Backward,
-                        _ => panic!("Error retrieving the orientation"),
-                    };
-
-                    let sid1 = e.sid1.to_string();
-                    let len = sid1.len() - 2;
-                    let l = sid1[..len].parse::<u64>().unwrap();
-                    let l_orient = orient(&sid1[len..]);
-
-                    let sid2 = e.sid2.to_string();
-                    let len = sid2.len() - 2;
-                    let r = sid2[..len].parse::<u64>().unwrap();
-                    let r_orient = orient(&sid2[len..]);
-
-                    let left = Handle::new(l, l_orient);
-                    let right = Handle::new(r, r_orient);
-                    match self.create_edge(GraphEdge(left, right)) {
-                        Err(why) => println!("Error {}", why),
-                        _ => (),
+        }
+        match failure {
+            Some(why) => Err(why),
+            None => Ok(self.to_owned()),
+        }
+    }
+
+    /// Build an HashGraph from a GFA Object, as
+    /// [`create_graph`](#method.create_graph), returning the errors of
+    /// the records left out of the graph: the duplicated segments, the
+    /// edges that can't be decoded or reference a missing segment, and
+    /// the path steps referencing a missing segment (the rest of the
+    /// path is kept), as ```MissingStepNode``` with the index of the step
+    /// in its record, and the path steps that can't be parsed, as
+    /// ```InvalidPathStep```
+    /// ## Examples
+    /// ```ignore
+    /// let mut graph = HashGraph::new();
+    /// for why in graph.create_graph_with_errors(FileType::GFA(gfa)) {
+    ///     println!("{}", why);
+    /// }
+    /// ```
+    pub fn create_graph_with_errors(&mut self, file: FileType) -> Vec<GraphError> {
+        self.keep_header(&file);
+        self.add_records(records_of(file), false, &ProgressHook::default())
+    }
+
+    /// Build an HashGraph from a GFA Object, as
+    /// [`create_graph`](#method.create_graph), returning it with the
+    /// records left out: the edges and the path steps referencing a
+    /// missing segment, and the other errors of
+    /// [`create_graph_with_errors`](#method.create_graph_with_errors).\
+    /// If ```strict``` the first record that can't be added stops the
+    /// construction, and its error is returned
+    /// ## Examples
+    /// ```ignore
+    /// let report = HashGraph::new().create_graph_report(FileType::GFA(gfa), false)?;
+    /// for (from, to) in report.missing_segment_edges.iter() {
+    ///     println!("dropped the edge {} -> {}", from, to);
+    /// }
+    /// ```
+    pub fn create_graph_report(
+        &mut self,
+        file: FileType,
+        strict: bool,
+    ) -> Result<BuildReport, GraphError> {
+        self.keep_header(&file);
+        let records = records_of(file);
+        let edges = records.1.clone();
+        let errors = self.add_records(records, strict, &ProgressHook::default());
+        if strict {
+            if let Some(why) = errors.into_iter().next() {
+                return Err(why);
+            }
+            return Ok(BuildReport {
+                graph: self.to_owned(),
+                missing_segment_edges: vec![],
+                missing_segment_steps: vec![],
+                errors: vec![],
+            });
+        }
+
+        let mut report = BuildReport {
+            graph: HashGraph::default(),
+            missing_segment_edges: edges
+                .iter()
+                .filter(|GraphEdge(l, r)| {
+                    !self.graph.contains_key(&l.id()) || !self.graph.contains_key(&r.id())
+                })
+                .map(|GraphEdge(l, r)| (u64::from(l.id()), u64::from(r.id())))
+                .collect(),
+            missing_segment_steps: vec![],
+            errors: vec![],
+        };
+        for why in errors {
+            match why {
+                GraphError::NameCollision(_, _) | GraphError::InvalidPathStep(_, _) => {
+                    return Err(why)
+                }
+                // the edges with a missing segment, already listed
+                GraphError::NodeNotExist(_) => (),
+                GraphError::MissingStepNode(path, node, ix) => {
+                    let node = node.parse::<u64>().unwrap_or_default();
+                    report
+                        .missing_segment_steps
+                        .push((BString::from(path), ix, node))
+                }
+                why => report.errors.push(why),
+            }
+        }
+        report.graph = self.to_owned();
+        Ok(report)
+    }
+
+    /// Keep the header of a GFA object, if it has one
+    fn keep_header(&mut self, file: &FileType) {
+        if let Some(header) = header_of(file) {
+            self.header = Some(header);
+        }
+    }
+
+    /// Add the records of a GFA object, returning the errors of the
+    /// records left out, or only the first one if ```strict```, and
+    /// reporting the records gone through (added or left out)
+    fn add_records(
+        &mut self,
+        (segments, edges, edge_records, paths, subsets, containments): Records,
+        strict: bool,
+        progress: &ProgressHook<BuildProgress>,
+    ) -> Vec<GraphError> {
+        let mut errors = vec![];
+        let mut done = BuildProgress {
+            total_segments: segments.len(),
+            total_edges: edges.len(),
+            total_paths: paths.len(),
+            ..Default::default()
+        };
+        self.graph.reserve(segments.len());
+        for (id, name, sequence, optional, placeholder) in segments {
+            match self.create_named_handle(id.into(), name, &sequence) {
+                Ok(_) => {
+                    self.set_node_tags(id.into(), optional);
+                    self.mark_placeholders(placeholder.map(|len| (id.into(), len)));
+                }
+                Err(why) if strict => return vec![why],
+                Err(why) => errors.push(why),
+            }
+            done.segments += 1;
+            if progress.is_due(done.segments) {
+                progress.report(done);
+            }
+        }
+        if strict {
+            for edge in edges {
+                if let Err(why) = self.create_edge(edge) {
+                    return vec![why];
+                }
+            }
+        } else {
+            errors.extend(self.create_edges_par(edges));
+        }
+        done.edges = done.total_edges;
+        if progress.is_periodic() {
+            progress.report(done);
+        }
+        for (edge, overlap, optional) in edge_records {
+            // an edge left out is already among the errors
+            let _ = self.set_edge_overlap(edge, overlap);
+            self.set_edge_tags(edge, optional);
+        }
+        for (name, steps, optional) in paths {
+            let path_id = self.create_path_handle(&name, false);
+            self.set_path_tags(&name, optional);
+            for (ix, step) in steps.into_iter().enumerate() {
+                let (id, orient) = match step {
+                    Ok(step) => step,
+                    Err(why) => {
+                        let step = why.token().to_string();
+                        errors.push(GraphError::InvalidPathStep(name.to_string(), step));
+                        if strict {
+                            return errors;
+                        }
+                        continue;
                     }
-                });
-                x.groups_o.into_iter().for_each(|o| {
-                    let path_id = self.create_path_handle(&o.id, false);
-                    for (id, orient) in o.iter() {
-                        match self.append_step(&path_id, Handle::new(id, orient)) {
-                            Err(why) => println!("Error: {}", why),
-                            _ => (),
-                        };
+                };
+                match self.append_step(&path_id, Handle::new(id, orient)) {
+                    Ok(_) => (),
+                    // the steps left out before this one don't count
+                    Err(GraphError::MissingStepNode(path, node, _)) => {
+                        errors.push(GraphError::MissingStepNode(path, node, ix))
                     }
-                });
-                Ok(self.to_owned())
+                    Err(why) => errors.push(why),
+                }
+                if strict && !errors.is_empty() {
+                    return errors;
+                }
+            }
+            done.paths += 1;
+            if progress.is_due(done.paths) {
+                progress.report(done);
             }
         }
+        self.add_groups(subsets, containments);
+        progress.report(done);
+        errors
+    }
+
+    /// Build an HashGraph from a GFA Object, like
+    /// [`create_graph`](#method.create_graph), but without failing on
+    /// references to segments that don't exist.\
+    /// The edges referencing a missing segment are dropped, while a path
+    /// step referencing a missing segment (or that can't be parsed) is
+    /// skipped and the rest of the path is kept.\
+    /// Returns a [`RecoveryReport`](../../parser/recovery/struct.RecoveryReport.html)
+    /// with the number of edges and path steps dropped
+    /// ## Examples
+    /// ```ignore
+    /// let mut graph = HashGraph::new();
+    /// let report = graph.create_graph_recover(FileType::GFA(gfa));
+    /// println!("dropped {} edges", report.dropped_edges);
+    /// ```
+    pub fn create_graph_recover(&mut self, file: FileType) -> RecoveryReport {
+        self.create_graph_recovering(file, &ProgressHook::default())
+    }
+
+    /// The body of [`create_graph_recover`](#method.create_graph_recover),
+    /// reporting the progress as
+    /// [`create_graph_with_progress`](#method.create_graph_with_progress):
+    /// the records are added as in ```create_graph```, and the ones left
+    /// out are counted in the report
+    pub(crate) fn create_graph_recovering(
+        &mut self,
+        file: FileType,
+        progress: &ProgressHook<BuildProgress>,
+    ) -> RecoveryReport {
+        let mut report = RecoveryReport::default();
+        self.keep_header(&file);
+        for why in self.add_records(records_of(file), false, progress) {
+            let path = match why {
+                GraphError::NodeNotExist(_) => {
+                    report.dropped_edges += 1;
+                    continue;
+                }
+                GraphError::MissingStepNode(path, _, _) | GraphError::InvalidPathStep(path, _) => {
+                    BString::from(path)
+                }
+                why => {
+                    log::warn!("{}", why);
+                    continue;
+                }
+            };
+            // the errors of a path come together, in the order of the file
+            report.dropped_path_steps += 1;
+            match report.dropped_steps_per_path.last_mut() {
+                Some((last, lost)) if *last == path => *lost += 1,
+                _ => report.dropped_steps_per_path.push((path, 1)),
+            }
+        }
+        report
+    }
+
+    /// Add the subsets and the containments of a GFA object, leaving out
+    /// the nodes that aren't in the graph
+    pub(crate) fn add_groups(
+        &mut self,
+        subsets: Vec<(BString, Vec<u64>)>,
+        containments: Vec<ContainmentRecord>,
+    ) {
+        for (name, ids) in subsets {
+            self.add_subset(name, &ids);
+        }
+        for containment in containments {
+            self.add_containment(containment);
+        }
     }
 
     pub fn print_occurrences(&self) {
         self.handles().for_each(|h| {
             let node = self.get_node(&h.id()).unwrap();
-            println!("{} - {:?}", node.sequence, node.occurrences);
+            println!(
+                "{} - {:?}",
+                self.node_sequence(node).as_bstr(),
+                node.occurrences
+            );
         });
     }
 
@@ -270,4 +948,117 @@ impl HashGraph {
             .get(path_id)
             .unwrap_or_else(|| panic!("Tried to look up nonexistent path:"))
     }
+
+    /// Function that checks that the fields of the graph agree, in O(n)
+    /// over the edges, the steps and the occurrences: every edge is kept
+    /// by both its nodes, every step of a path visits a node, every
+    /// occurrence points at a step on its node and ```min_id``` and
+    /// ```max_id``` bound the nodes.\
+    /// The methods of the graph keep them so, it's needed only after
+    /// changing the public fields directly; the errors are sorted by
+    /// node and by path
+    /// # Example
+    /// ```ignore
+    /// if let Err(errors) = graph.check_consistency() {
+    ///     errors.iter().for_each(|e| println!("{}", e));
+    /// }
+    /// ```
+    pub fn check_consistency(&self) -> Result<(), Vec<ConsistencyError>> {
+        let mut errors: Vec<ConsistencyError> = vec![];
+        let mut ids: Vec<NodeId> = self.graph.keys().copied().collect();
+        ids.sort_unstable();
+        // an edge from a handle is kept on the right of the node of a
+        // forward handle and on the left of a reverse one
+        let keeps = |from: Handle, to: Handle| match self.graph.get(&from.id()) {
+            Some(node) if from.is_reverse() => node.left_edges.contains(&to),
+            Some(node) => node.right_edges.contains(&to),
+            None => false,
+        };
+        for id in ids.iter() {
+            let node = &self.graph[id];
+            let fwd = Handle::pack(*id, false);
+            let sides = [(fwd, &node.right_edges), (fwd.flip(), &node.left_edges)];
+            for (from, edges) in sides.iter() {
+                for to in edges.iter() {
+                    // a reversing self loop is kept once
+                    if *from != to.flip() && !keeps(to.flip(), from.flip()) {
+                        errors.push(ConsistencyError::UnmirroredEdge {
+                            node: *id,
+                            edge: GraphEdge(*from, *to),
+                        });
+                    }
+                }
+            }
+
+            let index_of = |p: &PathId, key: usize| match self.paths.get(p) {
+                Some(path) => path.step_index(key),
+                None => key,
+            };
+            let mut occurrences: Vec<(PathId, usize)> = node
+                .occurrences
+                .iter()
+                .flat_map(|(p, keys)| keys.iter().map(move |key| (*p, index_of(p, *key))))
+                .collect();
+            occurrences.sort_unstable();
+            for (path_id, index) in occurrences {
+                let step = self.paths.get(&path_id).and_then(|p| p.nodes.get(index));
+                if step.map(|h| h.id()) != Some(*id) {
+                    errors.push(ConsistencyError::DanglingOccurrence {
+                        node: *id,
+                        path_id,
+                        index,
+                    });
+                }
+            }
+        }
+
+        let mut path_ids: Vec<PathId> = self.paths.keys().copied().collect();
+        path_ids.sort_unstable();
+        for path_id in path_ids {
+            let path = &self.paths[&path_id];
+            for (index, step) in path.nodes.iter().enumerate() {
+                if !self.graph.contains_key(&step.id()) {
+                    errors.push(ConsistencyError::MissingStepNode {
+                        path_id,
+                        path_name: path.name.clone(),
+                        index,
+                        node: step.id(),
+                    });
+                }
+            }
+        }
+
+        if let Some(mismatch) = self.id_bounds_mismatch() {
+            errors.push(ConsistencyError::IdBounds(mismatch));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Function that renders a
+    /// [`GraphError`](../../handlegraph/error/enum.GraphError.html)
+    /// naming the nodes involved through the graph
+    /// # Examples
+    /// ```ignore
+    /// if let Err(why) = graph.remove_handle(42) {
+    ///     println!("{}", graph.explain_error(&why));
+    /// }
+    /// ```
+    pub fn explain_error(&self, error: &GraphError) -> String {
+        error.explain(self)
+    }
+}
+
+/// The nodes are rendered by the name of their segment, or by their
+/// number if the name is a number
+impl NodeNamer for HashGraph {
+    fn display_node(&self, id: NodeId) -> String {
+        match self.node_name(id) {
+            Some(name) => name.to_string(),
+            None => id.to_string(),
+        }
+    }
 }
@@ -0,0 +1,187 @@
+/// This file provides the groups of nodes of an HashGraph, the subsets
+/// of the U-groups of GFA2 and the containments of GFA1
+use bstr::{BString, ByteSlice};
+use fnv::{FnvHashMap, FnvHashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gfa::cigar::CIGAR,
+    handle::{Handle, NodeId},
+    handlegraph::HandleSequences,
+};
+
+use super::HashGraph;
+
+/// A node contained in another one, from a C line of GFA1
+/// ```ignore
+/// pub struct ContainmentRecord {
+///     pub container: Handle,
+///     pub contained: Handle,
+///     pub pos: usize,
+///     pub overlap: BString,
+/// }
+/// ```
+/// ```pos``` is the first base of the container covered by the
+/// contained node, counted on the forward strand of the container, and
+/// ```overlap``` the CIGAR (or ```*```) as written in the file
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ContainmentRecord {
+    pub container: Handle,
+    pub contained: Handle,
+    pub pos: usize,
+    pub overlap: BString,
+}
+
+impl HashGraph {
+    /// Function that returns the nodes of a subset (an U-group of GFA2),
+    /// in the order of the group, if a subset has this name
+    /// # Example
+    /// ```ignore
+    /// // U 16 1 3 2_to_6
+    /// assert_eq!(graph.subset(b"16"), Some(&[NodeId::from(1), NodeId::from(3)][..]));
+    /// ```
+    pub fn subset(&self, name: &[u8]) -> Option<&[NodeId]> {
+        self.subsets.get(name.as_bstr()).map(|ids| ids.as_slice())
+    }
+
+    /// Function that returns the names of the subsets, sorted
+    pub fn subset_names(&self) -> Vec<&BString> {
+        let mut names: Vec<&BString> = self.subsets.keys().collect();
+        names.sort();
+        names
+    }
+
+    /// Function that returns the containments of a node, the ones where
+    /// it's the container or the contained node, in the order of the file
+    /// # Example
+    /// ```ignore
+    /// // C 1 + 2 + 4 3M
+    /// for c in graph.containments_of(NodeId::from(1)) {
+    ///     println!("{} contains {} from {}", c.container, c.contained, c.pos);
+    /// }
+    /// ```
+    pub fn containments_of(&self, id: NodeId) -> impl Iterator<Item = &ContainmentRecord> {
+        self.containments
+            .iter()
+            .filter(move |c| c.container.id() == id || c.contained.id() == id)
+    }
+
+    /// Function that returns every containment, in the order of the file
+    pub fn containments(&self) -> &[ContainmentRecord] {
+        &self.containments
+    }
+
+    /// Add a subset with the nodes of the graph among ```ids```, the
+    /// references to the edges and to the other groups are left out.\
+    /// A subset found again gets the new nodes after the ones it has,
+    /// and a group without name (```*```) isn't kept
+    pub(crate) fn add_subset(&mut self, name: BString, ids: &[u64]) {
+        if name == "*" {
+            return;
+        }
+        let subset = self.subsets.entry(name).or_default();
+        let mut seen: FnvHashSet<NodeId> = subset.iter().copied().collect();
+        for id in ids.iter().map(|id| NodeId::from(*id)) {
+            if self.graph.contains_key(&id) && seen.insert(id) {
+                subset.push(id);
+            }
+        }
+    }
+
+    /// Add a containment, if both its nodes are in the graph
+    pub(crate) fn add_containment(&mut self, containment: ContainmentRecord) -> bool {
+        let found = |h: Handle| self.graph.contains_key(&h.id());
+        if !found(containment.container) || !found(containment.contained) {
+            return false;
+        }
+        self.containments.push(containment);
+        true
+    }
+
+    /// Remove the nodes from the subsets and the containments with them,
+    /// after they're removed from the graph
+    pub(crate) fn forget_grouped_nodes(&mut self, removed: &FnvHashSet<NodeId>) {
+        for ids in self.subsets.values_mut() {
+            ids.retain(|id| !removed.contains(id));
+        }
+        self.containments.retain(|c| {
+            !removed.contains(&c.container.id()) && !removed.contains(&c.contained.id())
+        });
+    }
+
+    /// Change the containments of the nodes reverse complemented (the
+    /// forward strand of each is now the other one): their handles are
+    /// flipped, and the positions counted again on the container
+    pub(crate) fn flip_grouped_nodes<F: Fn(NodeId) -> bool>(&mut self, flipped: F) {
+        let mut containments = std::mem::take(&mut self.containments);
+        for c in containments.iter_mut() {
+            if flipped(c.container.id()) {
+                let len = self.node_len(c.container);
+                let covered = self.covered_by(c);
+                c.pos = len.saturating_sub(c.pos + covered);
+                c.container = c.container.flip();
+            }
+            if flipped(c.contained.id()) {
+                c.contained = c.contained.flip();
+            }
+        }
+        self.containments = containments;
+    }
+
+    /// Change the containments of a node divided in pieces, given with
+    /// the range of the node each covers: the ones where it's contained
+    /// are dropped, and the ones where it's the container move to the
+    /// piece with all the bases covered, if there's one
+    pub(crate) fn divide_grouped_node(
+        &mut self,
+        id: NodeId,
+        pieces: &[(NodeId, std::ops::Range<usize>)],
+    ) {
+        let containments = std::mem::take(&mut self.containments);
+        for mut c in containments {
+            if c.contained.id() == id {
+                continue;
+            }
+            if c.container.id() == id {
+                let covered = c.pos..c.pos + self.covered_by(&c);
+                let piece = pieces
+                    .iter()
+                    .find(|(_, r)| r.start <= covered.start && covered.end <= r.end);
+                match piece {
+                    Some((piece, range)) => {
+                        c.container = Handle::pack(*piece, c.container.is_reverse());
+                        c.pos -= range.start;
+                    }
+                    None => continue,
+                }
+            }
+            self.containments.push(c);
+        }
+    }
+
+    /// The number of bases of the container covered by a containment,
+    /// from its CIGAR or, without one (as ```*```), the length of the
+    /// contained node
+    fn covered_by(&self, c: &ContainmentRecord) -> usize {
+        match CIGAR::from_bytes(&c.overlap).map(|cigar| cigar.ref_len()) {
+            Ok(covered) if covered > 0 => covered,
+            _ => self.node_len(c.contained),
+        }
+    }
+
+    /// Change the ids of the nodes of the subsets and of the
+    /// containments, as they're renamed
+    pub(crate) fn rename_grouped_nodes(&mut self, mapping: &FnvHashMap<NodeId, NodeId>) {
+        let rename = |id: NodeId| mapping.get(&id).copied().unwrap_or(id);
+        let handle = |h: Handle| Handle::pack(rename(h.id()), h.is_reverse());
+        for ids in self.subsets.values_mut() {
+            for id in ids.iter_mut() {
+                *id = rename(*id);
+            }
+        }
+        for c in self.containments.iter_mut() {
+            c.container = handle(c.container);
+            c.contained = handle(c.contained);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use fnv::FnvHashMap;
+use std::convert::TryFrom;
+
+use super::node::SequenceRef;
+use crate::handlegraph::GraphError;
+
+/// Append-only table of the distinct node sequences of an HashGraph
+/// built with ```intern_sequences```, a sequence is stored once and the
+/// nodes keep its index
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SequenceInterner {
+    ids: FnvHashMap<Box<[u8]>, u32>,
+    sequences: Vec<Box<[u8]>>,
+}
+
+impl SequenceInterner {
+    /// The index of the sequence, adding it to the table if it's new;
+    /// ```None``` when the table is full
+    pub(crate) fn intern(&mut self, sequence: &[u8]) -> Option<u32> {
+        if let Some(id) = self.ids.get(sequence) {
+            return Some(*id);
+        }
+        let id = u32::try_from(self.sequences.len()).ok()?;
+        let sequence: Box<[u8]> = sequence.into();
+        self.ids.insert(sequence.clone(), id);
+        self.sequences.push(sequence);
+        Some(id)
+    }
+
+    #[inline]
+    pub(crate) fn try_get(&self, id: u32) -> Option<&[u8]> {
+        self.sequences.get(id as usize).map(|s| &s[..])
+    }
+
+    /// Number of distinct sequences stored
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.sequences.len()
+    }
+}
+
+/// Store a sequence in the table, if there's one, or inline
+pub(crate) fn store_sequence(
+    interner: &mut Option<SequenceInterner>,
+    sequence: &[u8],
+) -> SequenceRef {
+    match interner.as_mut().and_then(|i| i.intern(sequence)) {
+        Some(id) => SequenceRef::Interned(id),
+        None => SequenceRef::inline(sequence),
+    }
+}
+
+/// The bytes of a sequence, looking up the interned ones in the table
+/// and reading the lazy ones from their store.\
+/// Panics when the sequence can't be resolved, see
+/// [`try_resolve_sequence`](fn.try_resolve_sequence.html)
+#[inline]
+pub(crate) fn resolve_sequence<'a>(
+    interner: &'a Option<SequenceInterner>,
+    sequence: &'a SequenceRef,
+) -> &'a [u8] {
+    match try_resolve_sequence(interner, sequence) {
+        Ok(bytes) => bytes,
+        Err(why) => panic!("{}", why),
+    }
+}
+
+/// Like ```resolve_sequence```, failing on an interned sequence when
+/// there's no table (a node moved from an interning graph to one that
+/// doesn't intern its sequences), on an index out of the table, or on a
+/// lazy sequence that can't be read from its store
+#[inline]
+pub(crate) fn try_resolve_sequence<'a>(
+    interner: &'a Option<SequenceInterner>,
+    sequence: &'a SequenceRef,
+) -> Result<&'a [u8], GraphError> {
+    match (sequence, interner) {
+        (SequenceRef::Inline(seq), _) => Ok(seq.as_slice()),
+        (SequenceRef::Small(bases, len), _) => Ok(&bases[..*len as usize]),
+        (SequenceRef::Interned(id), Some(interner)) => interner.try_get(*id).ok_or_else(|| {
+            GraphError::SequenceUnavailable(format!(
+                "interned sequence {} out of a table of {}",
+                id,
+                interner.len()
+            ))
+        }),
+        (SequenceRef::Interned(id), None) => Err(GraphError::SequenceUnavailable(format!(
+            "interned sequence {} without a table",
+            id
+        ))),
+        (SequenceRef::Lazy(lazy), _) => lazy.try_bytes().map_err(|why| {
+            GraphError::SequenceUnavailable(format!(
+                "can't read the sequence at {} of the lazy store: {}",
+                lazy.offset, why
+            ))
+        }),
+    }
+}
+
+/// The length of a sequence, without reading the lazy ones
+#[inline]
+pub(crate) fn sequence_len(interner: &Option<SequenceInterner>, sequence: &SequenceRef) -> usize {
+    match sequence {
+        SequenceRef::Lazy(lazy) => lazy.len,
+        sequence => resolve_sequence(interner, sequence).len(),
+    }
+}
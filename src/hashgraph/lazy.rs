@@ -0,0 +1,167 @@
+/// This file provides the sequences of the nodes left in their GFA file,
+/// read only when they're needed
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use super::HashGraph;
+use super::SequenceRef;
+
+trait ReadSeek: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+enum Source {
+    Reader(Mutex<Box<dyn ReadSeek>>),
+    Bytes(Box<dyn AsRef<[u8]> + Send + Sync>),
+}
+
+/// The file (or its bytes) holding the sequences of the nodes of a graph
+/// loaded with
+/// [`parse_file_to_graph_lazy`](../../parser/lazy/fn.parse_file_to_graph_lazy.html),
+/// that reads a sequence from its position in the file
+/// # Example
+/// ```ignore
+/// // a memory-mapped file, as the bytes of the store
+/// let mmap = unsafe { memmap::Mmap::map(&File::open("big.gfa")?)? };
+/// let store = LazySequenceStore::from_bytes(mmap);
+/// let graph = Parser::new().parse_file_to_graph_lazy_with("big.gfa", store)?;
+/// ```
+pub struct LazySequenceStore {
+    source: Source,
+}
+
+impl LazySequenceStore {
+    /// Function that opens the file read-only
+    pub fn open<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Ok(LazySequenceStore::from_reader(File::open(path)?))
+    }
+
+    /// Function that reads the sequences from ```reader```, a read at a
+    /// time
+    pub fn from_reader<R: Read + Seek + Send + 'static>(reader: R) -> Self {
+        LazySequenceStore {
+            source: Source::Reader(Mutex::new(Box::new(reader))),
+        }
+    }
+
+    /// Function that reads the sequences from the bytes of the file, as
+    /// a memory map made by the caller
+    pub fn from_bytes<B: AsRef<[u8]> + Send + Sync + 'static>(bytes: B) -> Self {
+        LazySequenceStore {
+            source: Source::Bytes(Box::new(bytes)),
+        }
+    }
+
+    /// Function that reads ```len``` bytes from the position ```offset```
+    pub fn read(&self, offset: u64, len: usize) -> std::io::Result<Vec<u8>> {
+        match &self.source {
+            Source::Reader(reader) => {
+                let mut reader = reader.lock().unwrap_or_else(|e| e.into_inner());
+                reader.seek(SeekFrom::Start(offset))?;
+                let mut bytes = vec![0; len];
+                reader.read_exact(&mut bytes)?;
+                Ok(bytes)
+            }
+            Source::Bytes(bytes) => {
+                let bytes = (**bytes).as_ref();
+                let start = offset as usize;
+                bytes
+                    .get(start..start + len)
+                    .map(|b| b.to_vec())
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::UnexpectedEof,
+                            format!("{} bytes from {} are out of the file", len, offset),
+                        )
+                    })
+            }
+        }
+    }
+}
+
+impl fmt::Debug for LazySequenceStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            Source::Reader(_) => write!(f, "LazySequenceStore(reader)"),
+            Source::Bytes(bytes) => {
+                write!(f, "LazySequenceStore({} bytes)", (**bytes).as_ref().len())
+            }
+        }
+    }
+}
+
+/// The position of the sequence of a node in the file of its
+/// [`LazySequenceStore`](struct.LazySequenceStore.html), with the
+/// sequence once it's read
+/// ```ignore
+/// pub struct LazySequence {
+///     pub offset: u64,
+///     pub len: usize,
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LazySequence {
+    pub offset: u64,
+    pub len: usize,
+    store: Arc<LazySequenceStore>,
+    loaded: OnceLock<Box<[u8]>>,
+}
+
+impl PartialEq for LazySequence {
+    fn eq(&self, other: &Self) -> bool {
+        self.offset == other.offset
+            && self.len == other.len
+            && Arc::ptr_eq(&self.store, &other.store)
+    }
+}
+
+impl Eq for LazySequence {}
+
+impl LazySequence {
+    pub(crate) fn new(store: Arc<LazySequenceStore>, offset: u64, len: usize) -> Self {
+        LazySequence {
+            offset,
+            len,
+            store,
+            loaded: OnceLock::new(),
+        }
+    }
+
+    /// The bytes of the sequence, read from the store the first time.\
+    /// It fails if the store can't be read, as when the file was changed
+    /// after the graph was loaded, and a failed read is tried again the
+    /// next time; the queries of the graph returning the bytes of a
+    /// sequence panic instead
+    pub fn try_bytes(&self) -> std::io::Result<&[u8]> {
+        if let Some(bytes) = self.loaded.get() {
+            return Ok(bytes);
+        }
+        let bytes = self.store.read(self.offset, self.len)?;
+        Ok(self.loaded.get_or_init(|| bytes.into_boxed_slice()))
+    }
+
+    /// Whether the sequence was read
+    pub fn is_loaded(&self) -> bool {
+        self.loaded.get().is_some()
+    }
+}
+
+impl HashGraph {
+    /// Function that drops the sequences read by the nodes of a lazily
+    /// loaded graph, returning how many; they're read again from the
+    /// store when they're needed
+    pub fn release_loaded_sequences(&mut self) -> usize {
+        let mut released = 0;
+        for node in self.graph.values_mut() {
+            if let SequenceRef::Lazy(lazy) = &mut node.sequence {
+                if lazy.loaded.take().is_some() {
+                    released += 1;
+                }
+            }
+        }
+        released
+    }
+}
@@ -0,0 +1,185 @@
+/// This file provides the export of the adjacency of an HashGraph as a
+/// sparse matrix, for the linear algebra libraries
+use std::io::Write;
+
+use crate::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    util::threads::ThreadConfig,
+};
+
+use super::HashGraph;
+
+/// The rows (and columns) of the matrix built by
+/// [`to_csr`](../graph/struct.HashGraph.html#method.to_csr)
+/// ```ignore
+/// pub enum AdjacencyMode {
+///     NodeCollapsed,
+///     OrientedHandles,
+/// }
+/// ```
+/// * ```NodeCollapsed``` has a row for each node, with an entry for each
+///   node adjacent to it on any side and with any orientation, so the
+///   matrix is symmetric
+/// * ```OrientedHandles``` has a row for each handle, the forward one
+///   before the reverse one, with an entry for each handle on its right,
+///   as reported by ```neighbors```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdjacencyMode {
+    NodeCollapsed,
+    OrientedHandles,
+}
+
+/// The adjacency of a graph in the compressed sparse row format, as
+/// read by ```scipy.sparse.csr_matrix((data, indices, indptr))``` or by
+/// ```sprs::CsMat::new```
+/// ```ignore
+/// pub struct CsrAdjacency {
+///     pub indptr: Vec<usize>,
+///     pub indices: Vec<u64>,
+///     pub data: Vec<f32>,
+///     pub row_nodes: Vec<NodeId>,
+///     pub row_handles: Vec<Handle>,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsrAdjacency {
+    /// The entries of row ```i``` are at ```indptr[i]..indptr[i + 1]```
+    pub indptr: Vec<usize>,
+    /// The column of each entry, sorted within a row
+    pub indices: Vec<u64>,
+    /// The value of each entry, always 1.0
+    pub data: Vec<f32>,
+    /// The node of each row, sorted by id (each one twice for
+    /// ```OrientedHandles```)
+    pub row_nodes: Vec<NodeId>,
+    /// The handle of each row, the forward one for ```NodeCollapsed```
+    pub row_handles: Vec<Handle>,
+}
+
+impl CsrAdjacency {
+    /// Number of rows, that is also the number of columns
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.row_handles.len()
+    }
+
+    /// Number of entries
+    #[inline]
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// The row of a handle (of its node for ```NodeCollapsed```), if it's
+    /// in the matrix
+    pub fn row_of(&self, handle: Handle) -> Option<usize> {
+        self.row_handles.binary_search(&handle).ok().or_else(|| {
+            // the rows of a NodeCollapsed matrix are the forward handles
+            self.row_handles.binary_search(&handle.forward()).ok()
+        })
+    }
+
+    /// The columns of the entries of a row
+    pub fn row(&self, row: usize) -> &[u64] {
+        &self.indices[self.indptr[row]..self.indptr[row + 1]]
+    }
+
+    /// Function that writes the matrix in the coordinate format of
+    /// MatrixMarket, as read by ```scipy.io.mmread```, with the rows and
+    /// the columns numbered from 1
+    pub fn write_matrix_market<W: Write>(&self, mut out: W) -> std::io::Result<()> {
+        writeln!(out, "%%MatrixMarket matrix coordinate real general")?;
+        writeln!(out, "% the rows follow the handles sorted by id")?;
+        writeln!(out, "{} {} {}", self.rows(), self.rows(), self.nnz())?;
+        for row in 0..self.rows() {
+            for ix in self.indptr[row]..self.indptr[row + 1] {
+                writeln!(
+                    out,
+                    "{} {} {}",
+                    row + 1,
+                    self.indices[ix] + 1,
+                    self.data[ix]
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl HashGraph {
+    /// Function that builds the adjacency matrix of the graph, with the
+    /// rows in the order of the handles sorted by id, see
+    /// [`AdjacencyMode`](../matrix/enum.AdjacencyMode.html).\
+    /// The columns of the rows are found in a single parallel pass, then
+    /// placed with the prefix sums of their number
+    /// # Example
+    /// ```ignore
+    /// let csr = graph.to_csr(AdjacencyMode::NodeCollapsed);
+    /// println!("{} rows, {} entries", csr.rows(), csr.nnz());
+    /// ```
+    pub fn to_csr(&self, mode: AdjacencyMode) -> CsrAdjacency {
+        let nodes: Vec<NodeId> = self.handles_sorted().map(|h| h.id()).collect();
+        let row_handles: Vec<Handle> = match mode {
+            AdjacencyMode::NodeCollapsed => nodes.iter().map(|n| Handle::pack(*n, false)).collect(),
+            AdjacencyMode::OrientedHandles => nodes
+                .iter()
+                .flat_map(|n| vec![Handle::pack(*n, false), Handle::pack(*n, true)])
+                .collect(),
+        };
+        let column = |handle: Handle| match mode {
+            AdjacencyMode::NodeCollapsed => nodes.binary_search(&handle.id()),
+            AdjacencyMode::OrientedHandles => row_handles.binary_search(&handle),
+        };
+        let columns_of = |handle: &Handle| {
+            let neighbors: Vec<Handle> = match mode {
+                AdjacencyMode::NodeCollapsed => self
+                    .neighbors(*handle, Direction::Left)
+                    .chain(self.neighbors(*handle, Direction::Right))
+                    .collect(),
+                AdjacencyMode::OrientedHandles => {
+                    self.neighbors(*handle, Direction::Right).collect()
+                }
+            };
+            let mut columns: Vec<u64> = neighbors
+                .into_iter()
+                .filter_map(|h| column(h).ok())
+                .map(|c| c as u64)
+                .collect();
+            columns.sort_unstable();
+            columns.dedup();
+            columns
+        };
+        let rows = ThreadConfig::global().map(&row_handles, columns_of);
+
+        let mut indptr = Vec::with_capacity(rows.len() + 1);
+        indptr.push(0);
+        for row in rows.iter() {
+            indptr.push(indptr[indptr.len() - 1] + row.len());
+        }
+        let mut indices = Vec::with_capacity(indptr[rows.len()]);
+        rows.into_iter().for_each(|row| indices.extend(row));
+        CsrAdjacency {
+            data: vec![1.0; indices.len()],
+            indptr,
+            indices,
+            row_nodes: row_handles.iter().map(|h| h.id()).collect(),
+            row_handles,
+        }
+    }
+
+    /// Function that writes the adjacency matrix built by
+    /// [`to_csr`](#method.to_csr) in the MatrixMarket coordinate format,
+    /// to load it without bindings
+    /// # Example
+    /// ```ignore
+    /// let file = std::fs::File::create("graph.mtx")?;
+    /// graph.write_matrix_market(file, AdjacencyMode::OrientedHandles)?;
+    /// ```
+    pub fn write_matrix_market<W: Write>(
+        &self,
+        out: W,
+        mode: AdjacencyMode,
+    ) -> std::io::Result<()> {
+        self.to_csr(mode).write_matrix_market(out)
+    }
+}
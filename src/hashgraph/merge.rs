@@ -0,0 +1,224 @@
+/// This file provides the union of two HashGraphs, as the graphs of the
+/// chromosomes of a genome parsed from separate files
+use bstr::{BString, ByteSlice};
+use fnv::FnvHashSet;
+
+use crate::{
+    handle::{Edge, NodeId},
+    handlegraph::*,
+    mutablehandlegraph::*,
+    pathgraph::PathHandleGraph,
+};
+
+use super::{HashGraph, PathId};
+
+/// The suffix of the paths renamed by
+/// [`merge`](struct.HashGraph.html#method.merge)
+pub const DEFAULT_MERGE_SUFFIX: &str = "_";
+
+/// How the node ids of the graph merged in are read
+/// ```ignore
+/// pub enum MergePolicy {
+///     Disjoint,
+///     SharedIds,
+/// }
+/// ```
+/// * ```Disjoint``` nodes are different from the nodes of the graph,
+///   and get their ids moved after its biggest id
+/// * ```SharedIds``` are the same nodes in both graphs when they have
+///   the same id, and have to spell the same sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    Disjoint,
+    SharedIds,
+}
+
+/// What a merge added to the graph
+/// ```ignore
+/// pub struct MergeReport {
+///     pub id_offset: u64,
+///     pub nodes_added: usize,
+///     pub edges_added: usize,
+///     pub paths_added: usize,
+///     pub renamed_paths: Vec<(BString, BString)>,
+/// }
+/// ```
+/// * ```id_offset``` is added to the ids of the nodes merged in, 0 with
+///   shared ids
+/// * ```renamed_paths``` are the paths whose name was taken, with their
+///   name and the name they got, in the order of their ids
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    pub id_offset: u64,
+    pub nodes_added: usize,
+    pub edges_added: usize,
+    pub paths_added: usize,
+    pub renamed_paths: Vec<(BString, BString)>,
+}
+
+impl HashGraph {
+    /// Function that adds the nodes, the edges and the paths of another
+    /// graph, as [`merge_renaming`](#method.merge_renaming) with the
+    /// [`DEFAULT_MERGE_SUFFIX`](constant.DEFAULT_MERGE_SUFFIX.html)
+    /// # Example
+    /// ```ignore
+    /// let mut graph = parser.parse_file_to_graph("chr1.gfa")?;
+    /// let report = graph.merge(parser.parse_file_to_graph("chr2.gfa")?, MergePolicy::Disjoint)?;
+    /// println!("{} nodes added from {}", report.nodes_added, report.id_offset + 1);
+    /// ```
+    pub fn merge(
+        &mut self,
+        other: HashGraph,
+        id_policy: MergePolicy,
+    ) -> Result<MergeReport, GraphError> {
+        self.merge_renaming(other, id_policy, DEFAULT_MERGE_SUFFIX.as_bytes())
+    }
+
+    /// Function that adds the nodes, the edges and the paths of another
+    /// graph, with their overlaps and tags (the ones of the graph are
+    /// kept for the edges in both), and its subsets and
+    /// containments.\
+    /// With ```Disjoint``` the ids of ```other``` are moved by the
+    /// biggest id of the graph, as
+    /// [`increment_node_ids`](#method.increment_node_ids) does; with
+    /// ```SharedIds``` a node in both graphs keeps the edges of both,
+    /// and the nodes with different sequences are a
+    /// ```SequenceConflict``` with all of them, sorted.\
+    /// A path whose name is taken gets the first free name made of its
+    /// name, ```suffix``` and a number from 1 (as ```x_1```), and a
+    /// segment name taken by another node isn't kept, so the node is
+    /// written with its id. The header of the graph is kept, or
+    /// the one of ```other``` if the graph has none.\
+    /// Nothing changes on an error
+    pub fn merge_renaming(
+        &mut self,
+        mut other: HashGraph,
+        id_policy: MergePolicy,
+        suffix: &[u8],
+    ) -> Result<MergeReport, GraphError> {
+        let mut report = MergeReport::default();
+        match id_policy {
+            MergePolicy::Disjoint => {
+                if !self.graph.is_empty() {
+                    report.id_offset = u64::from(self.max_id);
+                    other.increment_node_ids(report.id_offset)?;
+                }
+            }
+            MergePolicy::SharedIds => self.check_shared_sequences(&other)?,
+        }
+        self.as_one_change(|graph| graph.merge_records(other, suffix, &mut report));
+        Ok(report)
+    }
+
+    /// The shared nodes of ```other``` with a different sequence, as a
+    /// ```SequenceConflict```
+    fn check_shared_sequences(&self, other: &HashGraph) -> Result<(), GraphError> {
+        let mut conflicts: Vec<NodeId> = other
+            .graph
+            .iter()
+            .filter(|(id, node)| {
+                self.graph
+                    .get(id)
+                    .is_some_and(|shared| self.node_sequence(shared) != other.node_sequence(node))
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        conflicts.sort_unstable();
+        Err(GraphError::SequenceConflict(
+            conflicts.iter().map(|id| id.to_string()).collect(),
+        ))
+    }
+
+    /// The body of ```merge_renaming```, after the ids are checked
+    fn merge_records(&mut self, other: HashGraph, suffix: &[u8], report: &mut MergeReport) {
+        let mut ids: Vec<NodeId> = other.graph.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids.iter() {
+            if self.graph.contains_key(id) {
+                continue;
+            }
+            let node = &other.graph[id];
+            // the ids are checked, so the node can be created
+            self.create_handle(*id, other.node_sequence(node)).unwrap();
+            self.set_node_tags(*id, node.optional.clone());
+            self.mark_placeholders(other.placeholders.get(id).map(|len| (*id, *len)));
+            if let Some(name) = other.node_name(*id) {
+                if self.node_id_of_name(name).is_none() {
+                    self.set_node_name(*id, name.to_owned());
+                }
+            }
+            report.nodes_added += 1;
+        }
+
+        let mut edges: Vec<Edge> = other.edges().collect();
+        edges.sort_unstable();
+        for edge in edges {
+            // both the ends are in the graph now
+            if self.create_edge(edge).unwrap() {
+                report.edges_added += 1;
+            }
+            let overlap = other.edge_overlap(edge);
+            if overlap > 0 && self.edge_overlap(edge) == 0 {
+                self.set_edge_overlap(edge, overlap).unwrap();
+            }
+            if self.edge_tags(edge).is_empty() {
+                self.set_edge_tags(edge, other.edge_tags(edge).to_vec());
+            }
+        }
+
+        let mut path_ids: Vec<PathId> = other.paths.keys().copied().collect();
+        path_ids.sort_unstable();
+        for path_id in path_ids {
+            let path = &other.paths[&path_id];
+            let name = self.free_path_name(&path.name, suffix);
+            if name != path.name {
+                report.renamed_paths.push((path.name.clone(), name.clone()));
+            }
+            let new_id = self.create_path_handle(&name, path.is_circular);
+            let new_path = self.paths.get_mut(&new_id).unwrap();
+            new_path.nodes = path.nodes.clone();
+            new_path.optional = path.optional.clone();
+            self.index_steps(&new_id);
+            report.paths_added += 1;
+        }
+
+        let mut names: Vec<&BString> = other.subsets.keys().collect();
+        names.sort();
+        for name in names {
+            let ids: Vec<u64> = other.subsets[name]
+                .iter()
+                .map(|id| u64::from(*id))
+                .collect();
+            self.add_subset(name.clone(), &ids);
+        }
+        let known: FnvHashSet<_> = self.containments.iter().cloned().collect();
+        for containment in other.containments.into_iter() {
+            if !known.contains(&containment) {
+                self.add_containment(containment);
+            }
+        }
+        if self.header.is_none() {
+            self.header = other.header;
+        }
+    }
+
+    /// ```name``` if no path has it, otherwise the first one of
+    /// ```name```, ```suffix``` and a number from 1 that no path has
+    fn free_path_name(&self, name: &BString, suffix: &[u8]) -> BString {
+        if !self.path_id.contains_key(name.as_slice()) {
+            return name.clone();
+        }
+        (1..)
+            .map(|n: usize| {
+                let mut renamed = name.clone();
+                renamed.extend_from_slice(suffix);
+                renamed.extend_from_slice(n.to_string().as_bytes());
+                renamed
+            })
+            .find(|renamed| !self.path_id.contains_key(renamed.as_bytes()))
+            .unwrap()
+    }
+}
@@ -0,0 +1,140 @@
+/// This file provides the names of the segments that aren't numbers,
+/// kept beside the node ids they're converted into
+use bstr::{BStr, BString, ByteSlice};
+use fnv::FnvHashMap;
+
+use crate::{
+    gfa::segment_id::is_numeric_id,
+    handle::{Handle, NodeId},
+    handlegraph::*,
+    mutablehandlegraph::*,
+};
+
+use super::HashGraph;
+
+/// The names of the segments whose name isn't a number, by node and by
+/// name
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NodeNames {
+    names: FnvHashMap<NodeId, BString>,
+    ids: FnvHashMap<BString, NodeId>,
+}
+
+impl NodeNames {
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The names, by node
+    #[inline]
+    pub(crate) fn by_node(&self) -> &FnvHashMap<NodeId, BString> {
+        &self.names
+    }
+}
+
+impl HashGraph {
+    /// Function that returns the name of a segment as written in the
+    /// parsed file, None if the name is the number of the node (or the
+    /// node doesn't exist)
+    /// # Example
+    /// ```ignore
+    /// // S contig_12 ACGT
+    /// let id = graph.node_id_of_name(b"contig_12").unwrap();
+    /// assert_eq!(graph.node_name(id), Some(b"contig_12".as_bstr()));
+    /// ```
+    pub fn node_name(&self, id: NodeId) -> Option<&BStr> {
+        self.names.names.get(&id).map(|n| n.as_bstr())
+    }
+
+    /// Function that returns the node of a segment name, as written in
+    /// the parsed file: a name that is a number is the node id itself,
+    /// unless the node has another name
+    pub fn node_id_of_name(&self, name: &[u8]) -> Option<NodeId> {
+        if let Some(id) = self.names.ids.get(name.as_bstr()) {
+            return Some(*id);
+        }
+        if !is_numeric_id(name) {
+            return None;
+        }
+        let id = NodeId::from(name.to_str().ok()?.parse::<u64>().ok()?);
+        if self.has_node(id) && !self.names.names.contains_key(&id) {
+            Some(id)
+        } else {
+            None
+        }
+    }
+
+    /// Function that creates the node of a segment as
+    /// ```create_handle```, keeping its name if it isn't a number (an
+    /// empty name).\
+    /// A node with the same id and another name is a
+    /// ```NameCollision```, as two names hashed into the same id or a
+    /// number that is the id of a name, so the segments aren't merged
+    pub(crate) fn create_named_handle(
+        &mut self,
+        id: NodeId,
+        name: BString,
+        sequence: &[u8],
+    ) -> Result<Handle, GraphError> {
+        self.check_segment_name(id, &name)?;
+        let handle = self.create_handle(id, sequence)?;
+        self.set_node_name(id, name);
+        Ok(handle)
+    }
+
+    /// Check that a segment name (empty for a number) can take the node
+    /// ```id```, a node with the same name is left to ```create_handle```
+    pub(crate) fn check_segment_name(&self, id: NodeId, name: &[u8]) -> Result<(), GraphError> {
+        if !self.graph.contains_key(&id) {
+            return Ok(());
+        }
+        let existing = self.names.names.get(&id).map(|n| n.as_slice());
+        let existing = existing.unwrap_or_default();
+        if existing == name {
+            return Ok(());
+        }
+        let shown = |name: &[u8]| {
+            if name.is_empty() {
+                id.to_string()
+            } else {
+                name.to_str_lossy().into_owned()
+            }
+        };
+        Err(GraphError::NameCollision(shown(name), shown(existing)))
+    }
+
+    /// Keep the name of a segment, written in the file instead of the
+    /// id; an empty name (a number) isn't kept
+    pub(crate) fn set_node_name(&mut self, id: NodeId, name: BString) {
+        if name.is_empty() || !self.has_node(id) {
+            return;
+        }
+        if let Some(old) = self.names.names.insert(id, name.clone()) {
+            self.names.ids.remove(&old);
+        }
+        self.names.ids.insert(name, id);
+    }
+
+    /// Forget the name of a node that was removed
+    pub(crate) fn forget_node_name(&mut self, id: NodeId) {
+        if let Some(name) = self.names.names.remove(&id) {
+            self.names.ids.remove(&name);
+        }
+    }
+
+    /// Move the names of the nodes renamed as in ```mapping```
+    pub(crate) fn move_node_names(&mut self, mapping: &FnvHashMap<NodeId, NodeId>) {
+        if self.names.is_empty() {
+            return;
+        }
+        let moved: Vec<(NodeId, BString)> = mapping
+            .iter()
+            .filter_map(|(old, new)| Some((*new, self.names.names.remove(old)?)))
+            .collect();
+        for (new, name) in moved {
+            self.names.ids.insert(name.clone(), new);
+            self.names.names.insert(new, name);
+        }
+    }
+}
@@ -1,35 +1,112 @@
 use bstr::BString;
 use fnv::FnvHashMap;
 
-use crate::handle::Handle;
+use crate::{gfa::optional::OptField, handle::Handle};
 
-use super::PathId;
+use super::{lazy::LazySequence, PathId};
 
-/// New type
+/// The most bases of a sequence kept in the node without an allocation,
+/// as many as fit in the space of an inline sequence
+pub const SMALL_SEQUENCE_LEN: usize = 15;
+
+/// The sequence of a node, stored in the node (without an allocation
+/// when it's short) or, for an HashGraph built with
+/// ```intern_sequences```, as an index in the table of the graph, or
+/// left in the file of a lazily loaded graph; use
+/// [`node_sequence`](../graph/struct.HashGraph.html#method.node_sequence)
+/// to get the bytes
+/// ```ignore
+/// pub enum SequenceRef {
+///     Inline(BString),
+///     Small([u8; SMALL_SEQUENCE_LEN], u8),
+///     Interned(u32),
+///     Lazy(Box<LazySequence>),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceRef {
+    Inline(BString),
+    /// At most ```SMALL_SEQUENCE_LEN``` bases and their number
+    Small([u8; SMALL_SEQUENCE_LEN], u8),
+    Interned(u32),
+    Lazy(Box<LazySequence>),
+}
+
+impl SequenceRef {
+    /// A sequence stored in the node, as ```Small``` if it's short
+    /// enough and ```Inline``` otherwise
+    #[inline]
+    pub fn inline(sequence: &[u8]) -> SequenceRef {
+        if sequence.len() <= SMALL_SEQUENCE_LEN {
+            let mut bases = [0; SMALL_SEQUENCE_LEN];
+            bases[..sequence.len()].copy_from_slice(sequence);
+            SequenceRef::Small(bases, sequence.len() as u8)
+        } else {
+            SequenceRef::Inline(sequence.into())
+        }
+    }
+
+    /// The bytes of a sequence stored in the node, None if they're
+    /// kept elsewhere
+    #[inline]
+    pub fn inline_bytes(&self) -> Option<&[u8]> {
+        match self {
+            SequenceRef::Inline(sequence) => Some(sequence.as_slice()),
+            SequenceRef::Small(bases, len) => Some(&bases[..*len as usize]),
+            _ => None,
+        }
+    }
+}
+
+/// New type.\
+/// The sequence, that was a public ```BString``` before the sequences
+/// could be interned or left in the file, is read with
+/// [`node_sequence`](../graph/struct.HashGraph.html#method.node_sequence)
+/// of the graph holding the node, or as stored with
+/// [`sequence_ref`](#method.sequence_ref)
 /// # Example
 /// ```ignore
 /// pub struct Node {
-///     pub sequence: BString,
+///     sequence: SequenceRef,
 ///     pub left_edges: Vec<Handle>,
 ///     pub right_edges: Vec<Handle>,
-///     pub occurrences: FnvHashMap<PathId, usize>,
+///     pub occurrences: FnvHashMap<PathId, Vec<usize>>,
+///     pub optional: Vec<OptField>,
 /// }
 /// ```
 #[derive(Debug, Clone)]
 pub struct Node {
-    pub sequence: BString,
+    pub(crate) sequence: SequenceRef,
     pub left_edges: Vec<Handle>,
     pub right_edges: Vec<Handle>,
-    pub occurrences: FnvHashMap<PathId, usize>,
+    /// The keys of the steps of each path visiting the node, in the order
+    /// of the path; [`Path::step_index`](../path/struct.Path.html#method.step_index)
+    /// gives the index of a step from its key, the same as the key if
+    /// nothing was prepended to the path
+    pub occurrences: FnvHashMap<PathId, Vec<usize>>,
+    /// The optional fields of the segment, written back as they were read
+    pub optional: Vec<OptField>,
 }
 
 impl Node {
     pub fn new(sequence: &[u8]) -> Node {
+        Node::with_sequence(SequenceRef::inline(sequence))
+    }
+
+    pub fn with_sequence(sequence: SequenceRef) -> Node {
         Node {
-            sequence: sequence.into(),
+            sequence,
             left_edges: vec![],
             right_edges: vec![],
             occurrences: FnvHashMap::default(),
+            optional: vec![],
         }
     }
+
+    /// The sequence as it's stored in the node, that only for an inline
+    /// or a small sequence holds the bytes
+    #[inline]
+    pub fn sequence_ref(&self) -> &SequenceRef {
+        &self.sequence
+    }
 }
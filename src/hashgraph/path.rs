@@ -1,17 +1,17 @@
 #![allow(dead_code)]
 
+use crate::{gfa::optional::OptField, handle::Handle, util::dna};
 use bstr::BString;
-use fnv::FnvHashMap;
-
-use crate::handle::{Handle, NodeId};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 // use crate::pathhandlegraph::{PathBase, PathRef, PathRefMut};
 
-use super::Node;
+use super::HashGraph;
 
 pub type PathId = i64;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PathStep {
     Front(i64),
     End(i64),
@@ -42,15 +42,28 @@ impl PathStep {
 ///     pub path_id: PathId,
 ///     pub name: BString,
 ///     pub is_circular: bool,
-///     pub nodes: Vec<Handle>,
+///     pub nodes: VecDeque<Handle>,
+///     pub optional: Vec<OptField>,
 /// }
 /// ```
-#[derive(Debug, Clone)]
+/// The steps are added at both ends without moving the others; the
+/// occurrences of the nodes keep the keys of their steps, that don't
+/// change when a step is prepended, and
+/// [`step_index`](#method.step_index) turns a key into the index of its
+/// step
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Path {
     pub path_id: PathId,
     pub name: BString,
     pub is_circular: bool,
-    pub nodes: Vec<Handle>,
+    pub nodes: VecDeque<Handle>,
+    /// The optional fields of the path (or O-group), written back as
+    /// they were read
+    pub optional: Vec<OptField>,
+    /// The key of the first step, the key of a step is its index plus
+    /// this one (wrapping around)
+    #[serde(default)]
+    pub(crate) first_key: usize,
 }
 
 impl Path {
@@ -59,10 +72,25 @@ impl Path {
             name: name.into(),
             path_id,
             is_circular,
-            nodes: vec![],
+            nodes: VecDeque::new(),
+            optional: vec![],
+            first_key: 0,
         }
     }
 
+    /// The index of the step with the key ```key```, as kept in the
+    /// occurrences of its node
+    #[inline]
+    pub fn step_index(&self, key: usize) -> usize {
+        key.wrapping_sub(self.first_key)
+    }
+
+    /// The key of the step at ```index```
+    #[inline]
+    pub(crate) fn step_key(&self, index: usize) -> usize {
+        index.wrapping_add(self.first_key)
+    }
+
     pub fn len(&self) -> usize {
         self.nodes.len()
     }
@@ -71,10 +99,14 @@ impl Path {
         self.len() == 0
     }
 
-    pub fn bases_len(&self, graph: &FnvHashMap<NodeId, Node>) -> usize {
+    pub fn bases_len(&self, graph: &HashGraph) -> usize {
         self.nodes
             .iter()
-            .filter_map(|handle| graph.get(&handle.id()).map(|n| n.sequence.len()))
+            .filter_map(|handle| {
+                graph
+                    .get_node(&handle.id())
+                    .map(|n| graph.node_sequence_len(n))
+            })
             .sum()
     }
 
@@ -86,11 +118,7 @@ impl Path {
         }
     }
 
-    pub fn position_of_step(
-        &self,
-        graph: &FnvHashMap<NodeId, Node>,
-        step: &PathStep,
-    ) -> Option<usize> {
+    pub fn position_of_step(&self, graph: &HashGraph, step: &PathStep) -> Option<usize> {
         if step.path_id() != self.path_id {
             return None;
         }
@@ -100,24 +128,25 @@ impl Path {
             PathStep::End(_) => Some(self.bases_len(graph)),
             &PathStep::Step(_, step_ix) => {
                 let mut bases = 0;
-                for handle in self.nodes[0..step_ix].iter() {
-                    let node = graph.get(&handle.id())?;
-                    bases += node.sequence.len();
+                for handle in self.nodes.range(..step_ix) {
+                    let node = graph.get_node(&handle.id())?;
+                    bases += graph.node_sequence_len(node);
                 }
                 Some(bases)
             }
         }
     }
 
-    pub fn step_at_position(&self, graph: &FnvHashMap<NodeId, Node>, pos: usize) -> PathStep {
+    pub fn step_at_position(&self, graph: &HashGraph, pos: usize) -> PathStep {
         if pos == 0 {
             return PathStep::Front(self.path_id);
         }
 
         let mut bases = 0;
         for (ix, handle) in self.nodes.iter().enumerate() {
-            let node = graph.get(&handle.id()).unwrap();
-            bases += node.sequence.len();
+            bases += graph
+                .node_sequence(graph.get_node_unchecked(&handle.id()))
+                .len();
             if pos < bases {
                 return PathStep::Step(self.path_id, ix);
             }
@@ -125,4 +154,71 @@ impl Path {
 
         PathStep::End(self.path_id)
     }
+
+    /// The handle of the step on a position (in bases from 0) of the
+    /// path and the offset of the position on it, along its orientation;
+    /// None after the last base or after a step on a missing node
+    pub fn position_to_handle(&self, graph: &HashGraph, pos: usize) -> Option<(Handle, usize)> {
+        let mut start = 0;
+        for handle in self.nodes.iter() {
+            let len = graph.node_sequence_len(graph.get_node(&handle.id())?);
+            if pos < start + len {
+                return Some((*handle, pos - start));
+            }
+            start += len;
+        }
+        None
+    }
+
+    /// The bases spelled by the path from ```start``` to ```end``` (not
+    /// included), with the steps on the reverse strand complemented; None
+    /// if ```end``` is after the end of the path or a step of the range
+    /// is on a missing node.\
+    /// A circular path also has the ranges with ```start``` after
+    /// ```end```, that go through its origin
+    pub fn range_sequence(&self, graph: &HashGraph, start: usize, end: usize) -> Option<Vec<u8>> {
+        if start > end {
+            if !self.is_circular {
+                return None;
+            }
+            let len = self.checked_bases_len(graph)?;
+            if start >= len {
+                return None;
+            }
+            let mut sequence = self.range_sequence(graph, start, len)?;
+            sequence.extend(self.range_sequence(graph, 0, end)?);
+            return Some(sequence);
+        }
+        let mut sequence = Vec::with_capacity(end - start);
+        let mut pos = 0;
+        for handle in self.nodes.iter() {
+            if pos >= end {
+                break;
+            }
+            let bases = graph.node_sequence(graph.get_node(&handle.id())?);
+            let len = bases.len();
+            if pos + len > start {
+                // the part of the step in the range, along its orientation
+                let (from, to) = (start.saturating_sub(pos), std::cmp::min(end - pos, len));
+                if handle.is_reverse() {
+                    sequence.extend(dna::rev_comp_slice(&bases[len - to..len - from]));
+                } else {
+                    sequence.extend_from_slice(&bases[from..to]);
+                }
+            }
+            pos += len;
+        }
+        if pos < end {
+            return None;
+        }
+        Some(sequence)
+    }
+
+    /// The bases of the path, None if a step is on a missing node
+    fn checked_bases_len(&self, graph: &HashGraph) -> Option<usize> {
+        self.nodes
+            .iter()
+            .map(|handle| Some(graph.node_sequence_len(graph.get_node(&handle.id())?)))
+            .sum()
+    }
 }
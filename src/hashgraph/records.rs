@@ -0,0 +1,85 @@
+/// This file provides the nodes of a graph as records borrowing their
+/// sequence, to stream them to the exporters without a lookup for each
+/// of their fields
+use bstr::{BStr, ByteSlice};
+use rayon::prelude::*;
+
+use crate::handle::NodeId;
+
+use super::{HashGraph, Node};
+
+/// A node of the graph, as returned by
+/// [`node_records`](graph/struct.HashGraph.html#method.node_records)
+/// ```ignore
+/// pub struct NodeRecord<'a> {
+///     pub id: NodeId,
+///     pub sequence: &'a BStr,
+///     pub left_degree: usize,
+///     pub right_degree: usize,
+///     pub on_path: bool,
+/// }
+/// ```
+/// * ```sequence``` is borrowed from the graph, in the orientation of
+///   the forward handle
+/// * ```left_degree``` and ```right_degree``` are the edges on the two
+///   sides of the forward handle, as
+///   [`degree`](../handlegraph/trait.HandleNeighbors.html#method.degree)
+/// * ```on_path``` is true if a path goes through the node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeRecord<'a> {
+    pub id: NodeId,
+    pub sequence: &'a BStr,
+    pub left_degree: usize,
+    pub right_degree: usize,
+    pub on_path: bool,
+}
+
+impl<'a> NodeRecord<'a> {
+    pub(crate) fn new(id: NodeId, node: &'a Node, sequence: &'a [u8]) -> Self {
+        NodeRecord {
+            id,
+            sequence: sequence.as_bstr(),
+            left_degree: node.left_edges.len(),
+            right_degree: node.right_edges.len(),
+            on_path: !node.occurrences.is_empty(),
+        }
+    }
+}
+
+impl HashGraph {
+    /// The record of a node, with its sequence as
+    /// [`node_sequence`](#method.node_sequence) reads it
+    fn node_record(&self, id: NodeId) -> NodeRecord<'_> {
+        let node = self.get_node_unchecked(&id);
+        NodeRecord::new(id, node, self.node_sequence(node))
+    }
+
+    /// Function that returns the records of the nodes sorted by id, each
+    /// borrowing the sequence of its node, so nothing is allocated for a
+    /// node.\
+    /// Panics as [`node_sequence`](#method.node_sequence) if a sequence
+    /// can't be resolved
+    /// # Example
+    /// ```ignore
+    /// for record in graph.node_records().filter(|r| !r.on_path) {
+    ///     println!("{}\t{}", record.id, record.sequence);
+    /// }
+    /// ```
+    pub fn node_records(&self) -> impl Iterator<Item = NodeRecord<'_>> + '_ {
+        self.handles_sorted().map(move |h| self.node_record(h.id()))
+    }
+
+    /// Function that returns the records of the nodes like
+    /// [`node_records`](#method.node_records), in parallel; they're
+    /// still sorted by id when they're collected
+    /// # Example
+    /// ```ignore
+    /// let bases: usize = graph.par_node_records().map(|r| r.sequence.len()).sum();
+    /// ```
+    pub fn par_node_records(&self) -> impl IndexedParallelIterator<Item = NodeRecord<'_>> + '_ {
+        let handles = self.sorted_handles();
+        (0..handles.len())
+            .into_par_iter()
+            .map(move |i| self.node_record(handles[i].id()))
+    }
+}
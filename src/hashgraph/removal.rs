@@ -0,0 +1,235 @@
+use fnv::FnvHashSet;
+
+use crate::{
+    handle::{Edge, Handle, NodeId},
+    handlegraph::*,
+};
+
+use super::{generation::Changes, HashGraph, Node, PathId};
+
+/// What [`remove_handle_with`](../graph/struct.HashGraph.html#method.remove_handle_with)
+/// does with the paths that visit the removed node
+/// ```ignore
+/// pub enum PathRepair {
+///     DropPath,
+///     RemoveSteps,
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathRepair {
+    /// Destroy every path visiting the node, as ```remove_handle``` does
+    DropPath,
+    /// Keep the paths, without the steps on the node
+    RemoveSteps,
+}
+
+impl HashGraph {
+    /// Function that removes a node with its tags, its edges with their
+    /// overlaps and tags, and repairs the paths visiting it as told by
+    /// [`PathRepair`](../removal/enum.PathRepair.html).\
+    /// The paths are found in the occurrences of the node, so only the
+    /// neighbors of the node and the paths visiting it are touched.\
+    /// With ```RemoveSteps``` a path that visited only the node is left
+    /// empty, and two steps that were around the node become consecutive
+    /// even if there's no edge between them
+    /// # Example
+    /// ```ignore
+    /// // P1: 1+,2+,3+ becomes P1: 1+,3+
+    /// graph.remove_handle_with(NodeId::from(2), PathRepair::RemoveSteps)?;
+    /// ```
+    pub fn remove_handle_with(
+        &mut self,
+        node_id: NodeId,
+        repair: PathRepair,
+    ) -> Result<bool, GraphError> {
+        self.remove_handles_with(&[node_id], repair)
+    }
+
+    /// Function that removes many nodes as
+    /// [`remove_handle_with`](#method.remove_handle_with), repairing
+    /// each path visiting them once, so removing a lot of nodes from
+    /// long paths doesn't move their steps back once for each node.\
+    /// Nothing changes if a node doesn't exist
+    /// # Example
+    /// ```ignore
+    /// let ids = vec![NodeId::from(2), NodeId::from(5)];
+    /// graph.remove_handles_with(&ids, PathRepair::RemoveSteps)?;
+    /// ```
+    pub fn remove_handles_with(
+        &mut self,
+        ids: &[NodeId],
+        repair: PathRepair,
+    ) -> Result<bool, GraphError> {
+        if let Some(missing) = ids.iter().find(|id| !self.graph.contains_key(id)) {
+            return Err(GraphError::NodeNotExist(missing.to_string()));
+        }
+        let removed: FnvHashSet<NodeId> = ids.iter().copied().collect();
+        // the removed nodes and their neighbors, the only ones whose edges
+        // change
+        let mut ends: Vec<NodeId> = Vec::new();
+        for node_id in removed.iter() {
+            let node = &self.graph[node_id];
+            ends.push(*node_id);
+            ends.extend(
+                node.left_edges
+                    .iter()
+                    .chain(node.right_edges.iter())
+                    .map(|h| h.id()),
+            );
+        }
+        let found_before = self.edges_found_on(&ends);
+        let mut visiting: FnvHashSet<PathId> = FnvHashSet::default();
+        for node_id in removed.iter() {
+            let node: Node = self.graph.remove(node_id).unwrap();
+            self.forget_node_name(*node_id);
+            self.placeholders.remove(node_id);
+            self.detach_removed_node(*node_id, &node);
+            visiting.extend(node.occurrences.keys());
+        }
+        self.edge_count =
+            (self.edge_count + self.edges_found_on(&ends)).saturating_sub(found_before);
+        self.forget_grouped_nodes(&removed);
+
+        let mut changes = Changes::TOPOLOGY;
+        if !visiting.is_empty() {
+            changes = changes | Changes::PATHS;
+        }
+        for path_id in visiting {
+            match repair {
+                PathRepair::DropPath => self.drop_path_without(&path_id, &removed),
+                PathRepair::RemoveSteps => self.remove_steps_of(&path_id, &removed),
+            }
+        }
+
+        if removed.contains(&self.min_id) || removed.contains(&self.max_id) {
+            self.recompute_bounds();
+        }
+        self.record(changes);
+        self.check_bounds();
+        Ok(true)
+    }
+
+    /// Function that removes the tips shorter than ```max_len``` bases,
+    /// the nodes without edges on at least one side (see
+    /// [`tips`](../../handlegraph/analysis/fn.tips.html)), returning how
+    /// many were removed.\
+    /// The tips are found once, so a node that becomes a tip when its
+    /// neighbor is removed is kept; the paths keep their other steps, as
+    /// with ```PathRepair::RemoveSteps```
+    /// # Example
+    /// ```ignore
+    /// let removed = graph.remove_tips(50);
+    /// println!("removed {} tips", removed);
+    /// ```
+    pub fn remove_tips(&mut self, max_len: usize) -> usize {
+        let mut ids: Vec<NodeId> = tips(&*self)
+            .into_iter()
+            .map(|h| h.id())
+            .filter(|id| self.node_sequence_len(self.get_node_unchecked(id)) < max_len)
+            .collect();
+        // an isolated node is a tip with both its orientations
+        ids.dedup();
+        if !ids.is_empty() {
+            self.remove_handles_with(&ids, PathRepair::RemoveSteps)
+                .expect("the tips are nodes of the graph");
+        }
+        ids.len()
+    }
+
+    /// Remove the edges of a node already removed from the graph, with
+    /// their overlaps and tags
+    fn detach_removed_node(&mut self, node_id: NodeId, node: &Node) {
+        // every neighbor may reach the node on both its sides (a node
+        // linked to both the ends of another one), and the self loops
+        // are gone with the node
+        for neighbor in node.left_edges.iter().chain(node.right_edges.iter()) {
+            if let Some(other) = self.graph.get_mut(&neighbor.id()) {
+                other.left_edges.retain(|h| h.id() != node_id);
+                other.right_edges.retain(|h| h.id() != node_id);
+            }
+        }
+        let fwd = Handle::pack(node_id, false);
+        let left = node
+            .left_edges
+            .iter()
+            .map(|h| Edge::edge_handle(h.flip(), fwd));
+        let right = node.right_edges.iter().map(|h| Edge::edge_handle(fwd, *h));
+        for edge in left.chain(right) {
+            self.forget_edge(&edge);
+        }
+    }
+
+    /// Destroy a path visiting nodes already removed from the graph
+    fn drop_path_without(&mut self, path_id: &PathId, removed: &FnvHashSet<NodeId>) {
+        if let Some(path) = self.paths.remove(path_id) {
+            for handle in path.nodes.iter().filter(|h| !removed.contains(&h.id())) {
+                if let Some(node) = self.graph.get_mut(&handle.id()) {
+                    node.occurrences.remove(path_id);
+                }
+            }
+            self.path_id.remove(path.name.as_slice());
+        }
+    }
+
+    /// Remove the steps of a path on the nodes ```removed```, moving back
+    /// the index of the steps after them; the occurrences of
+    /// the removed nodes are left to the caller.\
+    /// As in ```rewrite_segment```, the steps before the first removed
+    /// one keep their keys, or the steps after the last one if they're
+    /// fewer, and only the other steps get new keys
+    pub(crate) fn remove_steps_of(&mut self, path_id: &PathId, removed: &FnvHashSet<NodeId>) {
+        let path = match self.paths.get(path_id) {
+            Some(path) => path,
+            None => return,
+        };
+        let indices: Vec<usize> = path
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, h)| removed.contains(&h.id()))
+            .map(|(ix, _)| ix)
+            .collect();
+        let (first, last) = match (indices.first(), indices.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return,
+        };
+        let len = path.nodes.len();
+        let old_first = path.first_key;
+        let keep_front = len - first <= last + 1;
+        let (moved, first_key) = if keep_front {
+            (first..len, old_first)
+        } else {
+            (0..last + 1, old_first.wrapping_add(indices.len()))
+        };
+        let touched: FnvHashSet<NodeId> = path
+            .nodes
+            .range(moved.clone())
+            .map(|h| h.id())
+            .filter(|id| !removed.contains(id))
+            .collect();
+        // a step keeps its order, moving back by the steps removed before it
+        let rekey = |key: usize| {
+            let ix = key.wrapping_sub(old_first);
+            if moved.contains(&ix) {
+                let shift = indices.partition_point(|removed| *removed < ix);
+                (ix - shift).wrapping_add(first_key)
+            } else {
+                key
+            }
+        };
+        for id in touched.iter() {
+            if let Some(keys) = self
+                .graph
+                .get_mut(id)
+                .and_then(|node| node.occurrences.get_mut(path_id))
+            {
+                for key in keys.iter_mut() {
+                    *key = rekey(*key);
+                }
+            }
+        }
+        let path = self.paths.get_mut(path_id).unwrap();
+        path.nodes.retain(|h| !removed.contains(&h.id()));
+        path.first_key = first_key;
+    }
+}
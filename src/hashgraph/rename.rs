@@ -0,0 +1,207 @@
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::{
+    gfa::optional::OptField,
+    handle::{Edge, Handle, NodeId, MAX_NODE_ID},
+    handlegraph::*,
+};
+
+use super::{generation::Changes, HashGraph, Node};
+
+impl HashGraph {
+    /// Function that changes the id of a node, keeping its sequence, its
+    /// edges and the paths going through it, see
+    /// [`rename_nodes`](#method.rename_nodes)
+    /// # Example
+    /// ```ignore
+    /// graph.rename_node(NodeId::from(3), NodeId::from(1003))?;
+    /// ```
+    pub fn rename_node(&mut self, old: NodeId, new: NodeId) -> Result<(), GraphError> {
+        let mut mapping = FnvHashMap::default();
+        mapping.insert(old, new);
+        self.rename_nodes(&mapping)
+    }
+
+    /// Function that changes the ids of the nodes in ```mapping``` (from
+    /// the old id to the new one) at once, so the ids can be swapped or
+    /// shifted along a chain (as ```1 -> 2, 2 -> 3```).\
+    /// The edges keep their orientation, and so do the path steps and
+    /// the overlaps and the tags of the edges; unlike removing the node
+    /// and adding it again, no path is destroyed.\
+    /// Nothing changes if an old id doesn't exist (```NodeNotExist```),
+    /// or if a new id is used by a node that isn't renamed or by two
+    /// renamed nodes (```IdAlreadyExist```).\
+    /// The id bounds are widened to the new ids, and recomputed in O(n)
+    /// only when the node with the smallest or the largest id is renamed
+    /// # Example
+    /// ```ignore
+    /// let mut swap = FnvHashMap::default();
+    /// swap.insert(NodeId::from(1), NodeId::from(2));
+    /// swap.insert(NodeId::from(2), NodeId::from(1));
+    /// graph.rename_nodes(&swap)?;
+    /// ```
+    pub fn rename_nodes(&mut self, mapping: &FnvHashMap<NodeId, NodeId>) -> Result<(), GraphError> {
+        let mut targets: FnvHashSet<NodeId> = FnvHashSet::default();
+        for (old, new) in mapping.iter() {
+            if !self.graph.contains_key(old) {
+                return Err(GraphError::NodeNotExist(old.to_string()));
+            }
+            let freed = mapping.get(new).is_some_and(|n| n != new);
+            if !targets.insert(*new) || (self.graph.contains_key(new) && new != old && !freed) {
+                return Err(GraphError::IdAlreadyExist(new.to_string()));
+            }
+        }
+        let mapping: FnvHashMap<NodeId, NodeId> = mapping
+            .iter()
+            .filter(|(old, new)| old != new)
+            .map(|(old, new)| (*old, *new))
+            .collect();
+        if mapping.is_empty() {
+            return Ok(());
+        }
+        let rename = |h: Handle| match mapping.get(&h.id()) {
+            Some(new) => Handle::pack(*new, h.is_reverse()),
+            None => h,
+        };
+
+        // take all the nodes out first, so an id can be taken by another
+        // node that's renamed
+        let moved: Vec<(NodeId, Node)> = mapping
+            .iter()
+            .map(|(old, new)| (*new, self.graph.remove(old).unwrap()))
+            .collect();
+        // the renamed nodes and their neighbors, with the new ids
+        let mut touched: FnvHashSet<NodeId> = mapping.values().copied().collect();
+        for (_, node) in moved.iter() {
+            let edges = node.left_edges.iter().chain(node.right_edges.iter());
+            touched.extend(edges.map(|h| rename(*h).id()));
+        }
+        for (new, node) in moved {
+            self.graph.insert(new, node);
+        }
+
+        for id in touched.iter() {
+            if let Some(node) = self.graph.get_mut(id) {
+                for h in node
+                    .left_edges
+                    .iter_mut()
+                    .chain(node.right_edges.iter_mut())
+                {
+                    *h = rename(*h);
+                }
+            }
+        }
+        let mut paths_changed = false;
+        for path in self.paths.values_mut() {
+            for step in path.nodes.iter_mut() {
+                if mapping.contains_key(&step.id()) {
+                    *step = rename(*step);
+                    paths_changed = true;
+                }
+            }
+        }
+        let overlaps: Vec<(Edge, usize)> = self
+            .overlaps
+            .iter()
+            .filter(|(Edge(l, r), _)| {
+                mapping.contains_key(&l.id()) || mapping.contains_key(&r.id())
+            })
+            .map(|(edge, overlap)| (*edge, *overlap))
+            .collect();
+        for (edge, _) in overlaps.iter() {
+            self.overlaps.remove(edge);
+        }
+        for (Edge(l, r), overlap) in overlaps {
+            self.overlaps
+                .insert(Edge::edge_handle(rename(l), rename(r)), overlap);
+        }
+        let tagged: Vec<Edge> = self
+            .edge_tags
+            .keys()
+            .filter(|Edge(l, r)| mapping.contains_key(&l.id()) || mapping.contains_key(&r.id()))
+            .copied()
+            .collect();
+        let tags: Vec<(Edge, Vec<OptField>)> = tagged
+            .into_iter()
+            .filter_map(|edge| Some((edge, self.edge_tags.remove(&edge)?)))
+            .collect();
+        for (Edge(l, r), optional) in tags {
+            self.edge_tags
+                .insert(Edge::edge_handle(rename(l), rename(r)), optional);
+        }
+        self.move_node_names(&mapping);
+        self.move_placeholders(&mapping);
+        self.rename_grouped_nodes(&mapping);
+
+        if mapping
+            .keys()
+            .any(|old| *old == self.max_id || *old == self.min_id)
+        {
+            self.recompute_bounds();
+        } else {
+            for new in mapping.values() {
+                self.max_id = std::cmp::max(self.max_id, *new);
+                self.min_id = std::cmp::min(self.min_id, *new);
+            }
+        }
+
+        if paths_changed {
+            self.record(Changes::TOPOLOGY | Changes::PATHS);
+        } else {
+            self.record(Changes::TOPOLOGY);
+        }
+        self.check_bounds();
+        Ok(())
+    }
+
+    /// Function that renumbers the nodes from 1 to the number of nodes,
+    /// in the order of their old ids, as
+    /// [`rename_nodes`](#method.rename_nodes) does, returning the new id
+    /// of every node (by its old id) to translate the references kept
+    /// outside the graph
+    /// # Example
+    /// ```ignore
+    /// // the nodes 3, 1000 and 5000000
+    /// let mapping = graph.compact_ids();
+    /// assert_eq!(mapping[&NodeId::from(1000)], NodeId::from(2));
+    /// ```
+    pub fn compact_ids(&mut self) -> FnvHashMap<NodeId, NodeId> {
+        let mut ids: Vec<NodeId> = self.graph.keys().copied().collect();
+        ids.sort_unstable();
+        let mapping: FnvHashMap<NodeId, NodeId> = ids
+            .into_iter()
+            .enumerate()
+            .map(|(ix, id)| (id, NodeId::from(ix as u64 + 1)))
+            .collect();
+        // every node is renamed, so no new id is taken by another node
+        self.rename_nodes(&mapping)
+            .expect("the new ids are taken only by renamed nodes");
+        mapping
+    }
+
+    /// Function that adds ```offset``` to the id of every node, as
+    /// [`rename_nodes`](#method.rename_nodes) does, so the ids of the
+    /// graph don't overlap the ones of another graph to merge with it.\
+    /// Nothing changes if a node would get an id above
+    /// [`MAX_NODE_ID`](../../handle/constant.MAX_NODE_ID.html)
+    /// (```IdOverflow```)
+    /// # Example
+    /// ```ignore
+    /// graph.increment_node_ids(other.max_node_id().into())?;
+    /// ```
+    pub fn increment_node_ids(&mut self, offset: u64) -> Result<(), GraphError> {
+        if offset == 0 || self.graph.is_empty() {
+            return Ok(());
+        }
+        let max_id = u64::from(self.max_id).checked_add(offset);
+        if max_id.is_none_or(|id| id > MAX_NODE_ID) {
+            return Err(GraphError::IdOverflow(self.max_id.to_string(), offset));
+        }
+        let mapping: FnvHashMap<NodeId, NodeId> = self
+            .graph
+            .keys()
+            .map(|id| (*id, NodeId::from(u64::from(*id) + offset)))
+            .collect();
+        self.rename_nodes(&mapping)
+    }
+}
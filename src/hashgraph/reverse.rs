@@ -0,0 +1,107 @@
+use crate::{handle::Edge, handlegraph::*, util::dna};
+
+use super::{
+    generation::Changes,
+    interner::{resolve_sequence, store_sequence},
+    HashGraph, PathId, SequenceRef,
+};
+
+impl HashGraph {
+    /// Function that reverses a path: the steps are visited in the opposite
+    /// order and each of them with the opposite orientation, so the path
+    /// spells the reverse complement of its sequence
+    /// # Example
+    /// ```ignore
+    /// // 11+ 12- 13+ becomes 13- 12+ 11-
+    /// graph.reverse_path(&path_id).unwrap();
+    /// ```
+    pub fn reverse_path(&mut self, path_id: &PathId) -> Result<(), GraphError> {
+        let path = self
+            .paths
+            .get_mut(path_id)
+            .ok_or_else(|| GraphError::PathNotExist(path_id.to_string()))?;
+        path.nodes.make_contiguous().reverse();
+        for step in path.nodes.iter_mut() {
+            *step = step.flip();
+        }
+        self.index_steps(path_id);
+        self.record(Changes::PATHS);
+        Ok(())
+    }
+
+    /// Function that replaces the graph with its reverse complement, the
+    /// same graph read from the opposite strand.\
+    /// The sequence of every node is reverse complemented, so what was the
+    /// reverse handle of a node is now the forward one: every edge keeps
+    /// connecting the same sequences (a forward self-loop is still forward
+    /// and a reversing one is still reversing), every path is
+    /// [`reversed`](#method.reverse_path), keeping its circular flag, and
+    /// the containments are counted on the new forward strands.\
+    /// Applying it twice gives back the original graph.
+    /// # Example
+    /// ```ignore
+    /// graph.reverse_complement_in_place();
+    /// ```
+    pub fn reverse_complement_in_place(&mut self) {
+        self.as_one_change(|graph| graph.reverse_complement_all());
+    }
+
+    fn reverse_complement_all(&mut self) {
+        self.record(Changes::ALL);
+        // flipping every node at once, the handles in the adjacency lists
+        // swap side and orientation without touching the other nodes
+        let interner = &mut self.interner;
+        for node in self.graph.values_mut() {
+            // the sequences kept in the node are reversed where they are
+            match &mut node.sequence {
+                SequenceRef::Inline(sequence) => dna::rev_comp_in_place(sequence),
+                SequenceRef::Small(bases, len) => {
+                    dna::rev_comp_in_place(&mut bases[..*len as usize])
+                }
+                stored => {
+                    let sequence = dna::rev_comp_slice(resolve_sequence(interner, stored));
+                    *stored = store_sequence(interner, &sequence);
+                }
+            }
+            std::mem::swap(&mut node.left_edges, &mut node.right_edges);
+            for h in node
+                .left_edges
+                .iter_mut()
+                .chain(node.right_edges.iter_mut())
+            {
+                *h = h.flip();
+            }
+        }
+        // an edge joins the same sequences through the flipped handles
+        self.overlaps = std::mem::take(&mut self.overlaps)
+            .into_iter()
+            .map(|(Edge(l, r), overlap)| (Edge::edge_handle(l.flip(), r.flip()), overlap))
+            .collect();
+        self.edge_tags = std::mem::take(&mut self.edge_tags)
+            .into_iter()
+            .map(|(Edge(l, r), optional)| (Edge::edge_handle(l.flip(), r.flip()), optional))
+            .collect();
+        self.flip_grouped_nodes(|_| true);
+
+        let path_ids: Vec<PathId> = self.paths.keys().copied().collect();
+        for path_id in path_ids {
+            // follow the flipped nodes, then read the path backwards
+            for step in self.paths.get_mut(&path_id).unwrap().nodes.iter_mut() {
+                *step = step.flip();
+            }
+            let _ = self.reverse_path(&path_id);
+        }
+    }
+
+    /// Function that returns the reverse complement of the graph, see
+    /// [`reverse_complement_in_place`](#method.reverse_complement_in_place)
+    /// # Example
+    /// ```ignore
+    /// let opposite = graph.reverse_complement();
+    /// ```
+    pub fn reverse_complement(&self) -> HashGraph {
+        let mut graph = self.clone();
+        graph.reverse_complement_in_place();
+        graph
+    }
+}
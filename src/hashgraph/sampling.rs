@@ -0,0 +1,136 @@
+/// This file provides the sampling of nodes, edges and walks, to estimate
+/// the statistics of a graph without visiting all of it
+use fnv::FnvHashSet;
+
+use crate::{
+    handle::{Direction, Edge, Handle, NodeId},
+    handlegraph::*,
+};
+
+use super::HashGraph;
+
+/// Why a [`random_walk`](../graph/struct.HashGraph.html#method.random_walk)
+/// stopped
+/// ```ignore
+/// pub enum WalkEnd {
+///     Completed,
+///     Tip,
+///     MissingStart,
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalkEnd {
+    /// All the steps were taken
+    Completed,
+    /// The last handle has no neighbor on its right
+    Tip,
+    /// The start handle isn't in the graph, the walk is empty
+    MissingStart,
+}
+
+/// The splitmix64 generator, small and good enough for sampling, so
+/// that the same seed always gives the same samples
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in ```0..bound```
+    fn below(&mut self, bound: usize) -> usize {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+}
+
+/// ```n``` distinct indices in ```0..len```, sorted, with the algorithm of
+/// Floyd: it takes ```n``` random numbers, whatever the length
+fn sample_indices(len: usize, n: usize, seed: u64) -> Vec<usize> {
+    let mut rng = SplitMix64(seed);
+    let mut chosen: FnvHashSet<usize> = FnvHashSet::default();
+    for j in len.saturating_sub(n)..len {
+        let t = rng.below(j + 1);
+        if !chosen.insert(t) {
+            chosen.insert(j);
+        }
+    }
+    let mut indices: Vec<usize> = chosen.into_iter().collect();
+    indices.sort();
+    indices
+}
+
+impl HashGraph {
+    /// Function that returns ```n``` distinct nodes chosen uniformly at
+    /// random, sorted by id, or all the nodes if there are fewer.\
+    /// The nodes are drawn from the
+    /// [`handles_sorted`](#method.handles_sorted), so the sample only
+    /// depends on the seed and on the nodes of the graph, and it takes
+    /// ```n``` random numbers even for a huge graph
+    /// # Example
+    /// ```ignore
+    /// let nodes = graph.sample_nodes(1000, 42);
+    /// ```
+    pub fn sample_nodes(&self, n: usize, seed: u64) -> Vec<NodeId> {
+        let handles = self.sorted_handles();
+        sample_indices(handles.len(), n, seed)
+            .into_iter()
+            .map(|i| handles[i].id())
+            .collect()
+    }
+
+    /// Function that returns ```n``` distinct edges chosen uniformly at
+    /// random, in their canonical form and sorted, or all the edges if
+    /// there are fewer, drawn from the
+    /// [`edges_sorted`](#method.edges_sorted) as in
+    /// [`sample_nodes`](#method.sample_nodes)
+    /// # Example
+    /// ```ignore
+    /// let edges = graph.sample_edges(1000, 42);
+    /// ```
+    pub fn sample_edges(&self, n: usize, seed: u64) -> Vec<Edge> {
+        let edges = self.sorted_edges();
+        sample_indices(edges.len(), n, seed)
+            .into_iter()
+            .map(|i| edges[i])
+            .collect()
+    }
+
+    /// Function that walks the graph from ```start```, following at each
+    /// step one of the distinct neighbors on the right of the current
+    /// handle, chosen uniformly at random, so the orientation of the
+    /// handles is respected.\
+    /// Returns the handles visited, ```start``` included, with the reason
+    /// why the walk stopped: after ```steps``` steps, or earlier at a
+    /// handle without neighbors on the right
+    /// # Example
+    /// ```ignore
+    /// let (walk, end) = graph.random_walk(Handle::pack(1, false), 100, 42);
+    /// if end == WalkEnd::Tip {
+    ///     println!("stopped at {}", walk.last().unwrap().id());
+    /// }
+    /// ```
+    pub fn random_walk(&self, start: Handle, steps: usize, seed: u64) -> (Vec<Handle>, WalkEnd) {
+        if !self.has_node(start.id()) {
+            return (vec![], WalkEnd::MissingStart);
+        }
+        let mut rng = SplitMix64(seed);
+        let mut walk = vec![start];
+        let mut current = start;
+        for _ in 0..steps {
+            // sorted, so the choice doesn't depend on the order of the edges
+            let mut next: Vec<Handle> = self.neighbors(current, Direction::Right).collect();
+            next.sort();
+            next.dedup();
+            if next.is_empty() {
+                return (walk, WalkEnd::Tip);
+            }
+            current = next[rng.below(next.len())];
+            walk.push(current);
+        }
+        (walk, WalkEnd::Completed)
+    }
+}
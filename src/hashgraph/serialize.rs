@@ -0,0 +1,237 @@
+/// This file provides the serde implementation of an HashGraph and its
+/// nodes, that keeps the occurrences of the paths
+use bstr::{BStr, BString, ByteSlice};
+use fnv::FnvHashMap;
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    gfa::{gfa1::Header, optional::OptField},
+    handle::{Edge, Handle, NodeId},
+};
+
+use super::{
+    interner::try_resolve_sequence, ContainmentRecord, HashGraph, HashGraphConfig, Node, Path,
+    PathId, SequenceRef,
+};
+
+/// The fields of a node, with its sequence as bytes
+#[derive(Serialize)]
+#[serde(rename = "Node")]
+struct NodeRef<'a> {
+    sequence: &'a BStr,
+    left_edges: &'a [Handle],
+    right_edges: &'a [Handle],
+    occurrences: &'a FnvHashMap<PathId, Vec<usize>>,
+    optional: &'a [OptField],
+}
+
+impl<'a> NodeRef<'a> {
+    fn new(node: &'a Node, sequence: &'a [u8]) -> Self {
+        NodeRef {
+            sequence: sequence.as_bstr(),
+            left_edges: &node.left_edges,
+            right_edges: &node.right_edges,
+            occurrences: &node.occurrences,
+            optional: &node.optional,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Node")]
+struct NodeFields {
+    sequence: BString,
+    left_edges: Vec<Handle>,
+    right_edges: Vec<Handle>,
+    occurrences: FnvHashMap<PathId, Vec<usize>>,
+    optional: Vec<OptField>,
+}
+
+/// A node is written with its sequence, so only a node with the
+/// sequence inside (not interned or left in the file) can be written
+/// without its graph
+impl Serialize for Node {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.sequence.inline_bytes() {
+            Some(sequence) => NodeRef::new(self, sequence).serialize(serializer),
+            None => Err(ser::Error::custom(
+                "The sequence of the node is kept by its graph",
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = NodeFields::deserialize(deserializer)?;
+        Ok(Node {
+            sequence: SequenceRef::inline(&fields.sequence),
+            left_edges: fields.left_edges,
+            right_edges: fields.right_edges,
+            occurrences: fields.occurrences,
+            optional: fields.optional,
+        })
+    }
+}
+
+/// The nodes of a graph, with the sequences read from the graph
+struct NodesRef<'a>(&'a HashGraph);
+
+impl<'a> Serialize for NodesRef<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let graph = self.0;
+        let mut nodes = Vec::with_capacity(graph.graph.len());
+        for (id, node) in graph.graph.iter() {
+            let sequence = try_resolve_sequence(&graph.interner, &node.sequence)
+                .map_err(ser::Error::custom)?;
+            nodes.push((id, NodeRef::new(node, sequence)));
+        }
+        serializer.collect_map(nodes)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename = "HashGraph")]
+struct GraphRef<'a> {
+    intern_sequences: bool,
+    graph: NodesRef<'a>,
+    path_id: &'a FnvHashMap<Vec<u8>, PathId>,
+    paths: &'a FnvHashMap<PathId, Path>,
+    overlaps: &'a FnvHashMap<Edge, usize>,
+    edge_tags: &'a FnvHashMap<Edge, Vec<OptField>>,
+    placeholders: &'a FnvHashMap<NodeId, usize>,
+    names: &'a FnvHashMap<NodeId, BString>,
+    subsets: &'a FnvHashMap<BString, Vec<NodeId>>,
+    containments: &'a [ContainmentRecord],
+    header: &'a Option<Header>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "HashGraph")]
+struct GraphFields {
+    intern_sequences: bool,
+    graph: FnvHashMap<NodeId, Node>,
+    path_id: FnvHashMap<Vec<u8>, PathId>,
+    paths: FnvHashMap<PathId, Path>,
+    overlaps: FnvHashMap<Edge, usize>,
+    edge_tags: FnvHashMap<Edge, Vec<OptField>>,
+    placeholders: FnvHashMap<NodeId, usize>,
+    names: FnvHashMap<NodeId, BString>,
+    // missing in the graphs written before they were kept
+    #[serde(default)]
+    subsets: FnvHashMap<BString, Vec<NodeId>>,
+    #[serde(default)]
+    containments: Vec<ContainmentRecord>,
+    #[serde(default)]
+    header: Option<Header>,
+}
+
+/// A graph is written with its nodes, its paths (with the occurrences
+/// of the nodes), the overlaps and the tags of the edges, the names of
+/// the segments and the lengths of the ones without a sequence, the
+/// subsets, the containments and the header; the rest (as ```min_id```,
+/// ```max_id``` and the number of edges) is computed again when it's
+/// read back
+impl Serialize for HashGraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GraphRef {
+            intern_sequences: self.interner.is_some(),
+            graph: NodesRef(self),
+            path_id: &self.path_id,
+            paths: &self.paths,
+            overlaps: &self.overlaps,
+            edge_tags: &self.edge_tags,
+            placeholders: &self.placeholders,
+            names: self.names.by_node(),
+            subsets: &self.subsets,
+            containments: &self.containments,
+            header: &self.header,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for HashGraph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = GraphFields::deserialize(deserializer)?;
+        HashGraph::from_fields(fields).map_err(de::Error::custom)
+    }
+}
+
+impl HashGraph {
+    /// The graph read back, checking that the edges and the paths only
+    /// reference its nodes, and the occurrences only their steps
+    fn from_fields(fields: GraphFields) -> Result<HashGraph, String> {
+        let missing = |h: &Handle| !fields.graph.contains_key(&h.id());
+        for (id, node) in fields.graph.iter() {
+            let mut edges = node.left_edges.iter().chain(node.right_edges.iter());
+            if let Some(h) = edges.find(|h| missing(h)) {
+                return Err(format!(
+                    "The node {} has an edge to the missing node {}",
+                    id,
+                    h.id()
+                ));
+            }
+            for (path_id, keys) in node.occurrences.iter() {
+                for key in keys.iter() {
+                    let path = fields.paths.get(path_id);
+                    let ix = path.map_or(*key, |p| p.step_index(*key));
+                    let step = path.and_then(|p| p.nodes.get(ix));
+                    if step.map(|h| h.id()) != Some(*id) {
+                        return Err(format!(
+                            "The node {} isn't at the step {} of the path {}",
+                            id, ix, path_id
+                        ));
+                    }
+                }
+            }
+        }
+        for path in fields.paths.values() {
+            if let Some(h) = path.nodes.iter().find(|h| missing(h)) {
+                return Err(format!(
+                    "The path {} visits the missing node {}",
+                    path.name,
+                    h.id()
+                ));
+            }
+            if fields.path_id.get(path.name.as_slice()) != Some(&path.path_id) {
+                return Err(format!("The path {} has not its id", path.name));
+            }
+        }
+
+        let mut graph = HashGraph::with_config(HashGraphConfig {
+            intern_sequences: fields.intern_sequences,
+        });
+        let mut nodes = fields.graph;
+        // the sequences are read inline, and interned again if the graph
+        // interned them
+        if graph.interner.is_some() {
+            for node in nodes.values_mut() {
+                if let Some(sequence) = node.sequence.inline_bytes() {
+                    let stored = graph.make_sequence(sequence);
+                    node.sequence = stored;
+                }
+            }
+        }
+        graph.graph = nodes;
+        graph.path_id = fields.path_id;
+        graph.paths = fields.paths;
+        graph.overlaps = fields.overlaps;
+        graph.edge_tags = fields.edge_tags;
+        graph.mark_placeholders(fields.placeholders);
+        for (id, name) in fields.names {
+            graph.set_node_name(id, name);
+        }
+        // the groups keep only the nodes of the graph
+        let subsets = fields
+            .subsets
+            .into_iter()
+            .map(|(name, ids)| (name, ids.into_iter().map(u64::from).collect()))
+            .collect();
+        graph.add_groups(subsets, fields.containments);
+        graph.header = fields.header;
+        graph.recompute_bounds();
+        graph.recount_edges();
+        Ok(graph)
+    }
+}
@@ -0,0 +1,191 @@
+/// This file provides the read access to a graph shared between threads:
+/// the read-only traits for an ```&Arc<HashGraph>```, and a graph behind
+/// a lock, read by many tasks and changed now and then
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    handle::{Direction, Edge, Handle, NodeId},
+    handlegraph::*,
+};
+
+use super::HashGraph;
+
+impl<'a> AllHandles for &'a Arc<HashGraph> {
+    type Handles = <&'a HashGraph as AllHandles>::Handles;
+
+    #[inline]
+    fn handles(self) -> Self::Handles {
+        self.as_ref().handles()
+    }
+
+    #[inline]
+    fn node_count(self) -> usize {
+        self.as_ref().node_count()
+    }
+
+    #[inline]
+    fn has_node<I: Into<NodeId>>(self, n_id: I) -> bool {
+        self.as_ref().has_node(n_id)
+    }
+}
+
+impl<'a> AllHandlesPar for &'a Arc<HashGraph> {
+    type HandlesPar = <&'a HashGraph as AllHandlesPar>::HandlesPar;
+
+    fn handles_par(self) -> Self::HandlesPar {
+        self.as_ref().handles_par()
+    }
+}
+
+impl<'a> AllEdges for &'a Arc<HashGraph> {
+    type Edges = <&'a HashGraph as AllEdges>::Edges;
+
+    #[inline]
+    fn edges(self) -> Self::Edges {
+        self.as_ref().edges()
+    }
+
+    #[inline]
+    fn edge_count(self) -> usize {
+        self.as_ref().edge_count()
+    }
+}
+
+impl<'a> AllEdgesPar for &'a Arc<HashGraph> {
+    type EdgesPar = <&'a HashGraph as AllEdgesPar>::EdgesPar;
+
+    fn edges_par(self) -> Self::EdgesPar {
+        self.as_ref().edges_par()
+    }
+}
+
+impl<'a> HandleNeighbors for &'a Arc<HashGraph> {
+    type Neighbors = <&'a HashGraph as HandleNeighbors>::Neighbors;
+
+    #[inline]
+    fn neighbors(self, handle: Handle, dir: Direction) -> Self::Neighbors {
+        self.as_ref().neighbors(handle, dir)
+    }
+
+    #[inline]
+    fn degree(self, handle: Handle, dir: Direction) -> usize {
+        self.as_ref().degree(handle, dir)
+    }
+
+    #[inline]
+    fn has_edge(self, left: Handle, right: Handle) -> bool {
+        self.as_ref().has_edge(left, right)
+    }
+}
+
+impl<'a> HandleNeighborsPar for &'a Arc<HashGraph> {
+    type NeighborsPar = <&'a HashGraph as HandleNeighborsPar>::NeighborsPar;
+
+    fn neighbors_par(self, handle: Handle, dir: Direction) -> Self::NeighborsPar {
+        self.as_ref().neighbors_par(handle, dir)
+    }
+}
+
+impl<'a> HandleSequences for &'a Arc<HashGraph> {
+    type Sequence = <&'a HashGraph as HandleSequences>::Sequence;
+
+    #[inline]
+    fn sequence_iter(self, handle: Handle) -> Self::Sequence {
+        self.as_ref().sequence_iter(handle)
+    }
+
+    fn sequence(self, handle: Handle) -> Vec<u8> {
+        self.as_ref().sequence(handle)
+    }
+
+    #[inline]
+    fn node_len(self, handle: Handle) -> usize {
+        self.as_ref().node_len(handle)
+    }
+}
+
+impl<'a> HandleSequencesPar for &'a Arc<HashGraph> {
+    type SequencePar = <&'a HashGraph as HandleSequencesPar>::SequencePar;
+
+    fn sequence_par_iter(self, handle: Handle) -> Self::SequencePar {
+        self.as_ref().sequence_par_iter(handle)
+    }
+}
+
+impl HandleGraphRef for &Arc<HashGraph> {
+    fn total_length(self) -> usize {
+        self.as_ref().total_length()
+    }
+}
+
+/// An HashGraph behind a read-write lock, that can be cloned to share it
+/// between threads: the readers (as many rayon tasks) go through
+/// [`with_read`](#method.with_read) at the same time, and a writer waits
+/// for them in [`with_write`](#method.with_write).\
+/// The lock isn't poisoned by a closure that panics, the next access
+/// gets the graph as the closure left it
+/// # Example
+/// ```ignore
+/// let shared = SharedHashGraph::new(graph);
+/// let degrees: Vec<usize> = ids
+///     .par_iter()
+///     .map(|id| shared.with_read(|g| g.degree(Handle::pack(*id, false), Direction::Right)))
+///     .collect();
+/// shared.with_write(|g| g.append_handle(b"ACGT"))?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SharedHashGraph {
+    inner: Arc<RwLock<HashGraph>>,
+}
+
+impl From<HashGraph> for SharedHashGraph {
+    fn from(graph: HashGraph) -> Self {
+        SharedHashGraph::new(graph)
+    }
+}
+
+impl SharedHashGraph {
+    pub fn new(graph: HashGraph) -> Self {
+        SharedHashGraph {
+            inner: Arc::new(RwLock::new(graph)),
+        }
+    }
+
+    /// Function that reads the graph in ```f```, at the same time as the
+    /// other readers
+    pub fn with_read<R>(&self, f: impl FnOnce(&HashGraph) -> R) -> R {
+        let graph = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        f(&graph)
+    }
+
+    /// Function that changes the graph in ```f```, once every reader and
+    /// writer before it is done
+    pub fn with_write<R>(&self, f: impl FnOnce(&mut HashGraph) -> R) -> R {
+        let mut graph = self.inner.write().unwrap_or_else(|e| e.into_inner());
+        f(&mut graph)
+    }
+
+    /// The number of nodes of the graph, as it is now
+    pub fn node_count(&self) -> usize {
+        self.with_read(|graph| graph.node_count())
+    }
+
+    /// True if the graph has the node now
+    pub fn has_node<I: Into<NodeId>>(&self, id: I) -> bool {
+        self.with_read(|graph| graph.has_node(id))
+    }
+
+    /// True if the graph has the edge now, written from either of its
+    /// ends
+    pub fn has_edge(&self, edge: Edge) -> bool {
+        self.with_read(|graph| graph.has_edge_canonical(edge))
+    }
+
+    /// The graph, if no other clone of the wrapper shares it
+    pub fn into_inner(self) -> Result<HashGraph, Self> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(lock) => Ok(lock.into_inner().unwrap_or_else(|e| e.into_inner())),
+            Err(inner) => Err(SharedHashGraph { inner }),
+        }
+    }
+}
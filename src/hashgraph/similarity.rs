@@ -0,0 +1,148 @@
+use bstr::BString;
+use std::io::Write;
+
+use crate::{handle::NodeId, util::threads::ThreadConfig};
+
+use super::{HashGraph, PathId};
+
+/// Sorted and deduplicated node ids of a path, paired with the number
+/// of bases of each node
+struct NodeSet {
+    nodes: Vec<NodeId>,
+    lengths: Vec<usize>,
+}
+
+impl NodeSet {
+    fn total(&self, weighted: bool) -> usize {
+        if weighted {
+            self.lengths.iter().sum()
+        } else {
+            self.nodes.len()
+        }
+    }
+
+    /// Size of the intersection between two sets, merging the sorted ids
+    fn shared(&self, other: &NodeSet, weighted: bool) -> usize {
+        let (mut i, mut j, mut shared) = (0, 0, 0);
+        while i < self.nodes.len() && j < other.nodes.len() {
+            match self.nodes[i].cmp(&other.nodes[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    shared += if weighted { self.lengths[i] } else { 1 };
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        shared
+    }
+}
+
+impl HashGraph {
+    fn node_set(&self, path: &PathId) -> NodeSet {
+        let mut nodes: Vec<NodeId> = self
+            .get_path(path)
+            .map(|p| p.nodes.iter().map(|h| h.id()).collect())
+            .unwrap_or_default();
+        nodes.sort();
+        nodes.dedup();
+        let lengths = nodes
+            .iter()
+            .map(|id| self.get_node(id).map_or(0, |n| self.node_sequence_len(n)))
+            .collect();
+        NodeSet { nodes, lengths }
+    }
+
+    /// Function that computes the pairwise similarity of the paths, as the
+    /// Jaccard index of their sets of nodes (a node visited more times by
+    /// the same path is counted once).\
+    /// When ```weighted``` is true each node counts for its number of bases,
+    /// so the index is the number of shared bases over the number of bases
+    /// in the union.\
+    /// The result is a symmetric matrix in the same order of the input,
+    /// where paths without shared nodes give 0.0 and a path compared with
+    /// itself gives 1.0. A path that doesn't exist is treated as empty.
+    /// # Example
+    /// ```ignore
+    /// let matrix = graph.path_similarity(&[0, 1, 2], false);
+    /// println!("{}", matrix[0][1]);
+    /// ```
+    pub fn path_similarity(&self, paths: &[PathId], weighted: bool) -> Vec<Vec<f64>> {
+        self.path_similarity_with_threads(paths, weighted, ThreadConfig::global())
+    }
+
+    /// Function that computes the [`path_similarity`](#method.path_similarity)
+    /// with the given number of threads, see
+    /// [`ThreadConfig`](../../util/threads/struct.ThreadConfig.html)
+    pub fn path_similarity_with_threads(
+        &self,
+        paths: &[PathId],
+        weighted: bool,
+        threads: ThreadConfig,
+    ) -> Vec<Vec<f64>> {
+        let sets: Vec<NodeSet> = threads.map(paths, |p| self.node_set(p));
+        let n = sets.len();
+        let pairs: Vec<(usize, usize)> = (0..n)
+            .flat_map(|i| (i + 1..n).map(move |j| (i, j)))
+            .collect();
+        let values: Vec<f64> = threads.map(&pairs, |(i, j)| {
+            let shared = sets[*i].shared(&sets[*j], weighted);
+            let union = sets[*i].total(weighted) + sets[*j].total(weighted) - shared;
+            if union == 0 {
+                0.0
+            } else {
+                shared as f64 / union as f64
+            }
+        });
+
+        let mut matrix = vec![vec![0.0; n]; n];
+        for (ix, (i, j)) in pairs.into_iter().enumerate() {
+            matrix[i][j] = values[ix];
+            matrix[j][i] = values[ix];
+        }
+        for (i, row) in matrix.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+        matrix
+    }
+
+    /// Function that writes the
+    /// [`path_similarity`](#method.path_similarity) matrix as a
+    /// tab-separated table, labeled with the names of the paths
+    /// # Example
+    /// ```ignore
+    /// graph.write_similarity_tsv(std::io::stdout(), &[0, 1], false).unwrap();
+    /// //      a       b
+    /// // a    1.000000        0.500000
+    /// // b    0.500000        1.000000
+    /// ```
+    pub fn write_similarity_tsv<W: Write>(
+        &self,
+        mut out: W,
+        paths: &[PathId],
+        weighted: bool,
+    ) -> std::io::Result<()> {
+        let names: Vec<BString> = paths
+            .iter()
+            .map(|p| {
+                self.get_path(p)
+                    .map_or_else(|| BString::from(p.to_string()), |p| p.name.clone())
+            })
+            .collect();
+        let matrix = self.path_similarity(paths, weighted);
+
+        for name in names.iter() {
+            write!(out, "\t{}", name)?;
+        }
+        writeln!(out)?;
+        for (name, row) in names.iter().zip(matrix.iter()) {
+            write!(out, "{}", name)?;
+            for value in row {
+                write!(out, "\t{:.6}", value)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    handle::{Edge, Handle},
+    handlegraph::*,
+};
+
+use super::HashGraph;
+
+type Cached<T> = Mutex<Option<(u64, Arc<Vec<T>>)>>;
+
+/// Sorted copies of the handles and edges of an HashGraph, each tagged
+/// with the topology generation of the graph they were built from.\
+/// Cloning a cache gives an empty one, so it will be rebuilt on demand
+#[derive(Debug, Default)]
+pub(crate) struct SortedCache {
+    handles: Cached<Handle>,
+    edges: Cached<Edge>,
+}
+
+impl Clone for SortedCache {
+    fn clone(&self) -> Self {
+        Default::default()
+    }
+}
+
+fn get_or_build<T, F>(cache: &Cached<T>, generation: u64, build: F) -> Arc<Vec<T>>
+where
+    F: FnOnce() -> Vec<T>,
+{
+    let mut cached = cache.lock().unwrap();
+    match cached.as_ref() {
+        Some((gen, items)) if *gen == generation => Arc::clone(items),
+        _ => {
+            let items = Arc::new(build());
+            *cached = Some((generation, Arc::clone(&items)));
+            items
+        }
+    }
+}
+
+impl HashGraph {
+    /// Function that returns the forward handles of the graph sorted by id.\
+    /// Unlike [`handles`](../../handlegraph/trait.AllHandles.html), that
+    /// follows the (unordered) layout of the underlying map for speed,
+    /// the order is always the same for the same graph, so this should be
+    /// used for every output that has to be reproducible.\
+    /// The sorted handles are cached until the next modification made
+    /// through the graph methods; after editing the public fields directly
+    /// call [`mark_modified`](#method.mark_modified).
+    /// # Example
+    /// ```ignore
+    /// for handle in graph.handles_sorted() {
+    ///     println!("{}", handle.id());
+    /// }
+    /// ```
+    pub fn handles_sorted(&self) -> impl Iterator<Item = Handle> + '_ {
+        let handles = self.sorted_handles();
+        (0..handles.len()).map(move |i| handles[i])
+    }
+
+    /// The cached vector behind [`handles_sorted`](#method.handles_sorted)
+    pub(crate) fn sorted_handles(&self) -> Arc<Vec<Handle>> {
+        get_or_build(&self.sorted.handles, self.topology_generation(), || {
+            let mut handles: Vec<Handle> = self.handles().collect();
+            handles.sort();
+            handles
+        })
+    }
+
+    /// Function that returns the edges of the graph in their canonical form,
+    /// sorted.\
+    /// As for [`handles_sorted`](#method.handles_sorted), the order is
+    /// stable and the result is cached until the next modification.
+    /// # Example
+    /// ```ignore
+    /// for Edge(l, r) in graph.edges_sorted() {
+    ///     println!("{} -> {}", l.id(), r.id());
+    /// }
+    /// ```
+    pub fn edges_sorted(&self) -> impl Iterator<Item = Edge> + '_ {
+        let edges = self.sorted_edges();
+        (0..edges.len()).map(move |i| edges[i])
+    }
+
+    /// The cached vector behind [`edges_sorted`](#method.edges_sorted)
+    pub(crate) fn sorted_edges(&self) -> Arc<Vec<Edge>> {
+        get_or_build(&self.sorted.edges, self.topology_generation(), || {
+            let mut edges: Vec<Edge> = self.edges().collect();
+            edges.sort();
+            edges
+        })
+    }
+}
@@ -0,0 +1,174 @@
+use fnv::FnvHashSet;
+
+use crate::{
+    handle::{Direction, Edge, Handle, NodeId},
+    handlegraph::*,
+    mutablehandlegraph::*,
+    pathgraph::PathHandleGraph,
+};
+
+use super::{HashGraph, PathId, SequenceRef};
+
+impl HashGraph {
+    /// Function that returns a copy of the subgraph induced by the nodes:
+    /// the nodes (with their sequences, names and optional fields), the
+    /// edges with both the ends on them (with their overlaps and tags)
+    /// and the fragments of the paths walking through them, the ids
+    /// that aren't nodes of the graph are ignored.\
+    /// A path whose steps are all on the nodes is copied as it is; a path
+    /// that leaves the nodes is split in the runs of consecutive steps on
+    /// them, named ```<name>_0```, ```<name>_1```... in the order of the
+    /// path, or keeps its name if only one run is left, and the runs
+    /// aren't circular. A path that doesn't visit the nodes isn't copied
+    /// # Example
+    /// ```ignore
+    /// // P x 1+,2+,3+,4+ *
+    /// let sub = graph.subgraph_from_nodes(vec![1, 2, 4].into_iter().map(NodeId::from));
+    /// // x_0 is 1+,2+ and x_1 is 4+
+    /// assert!(sub.has_path(b"x_1"));
+    /// ```
+    pub fn subgraph_from_nodes<I: IntoIterator<Item = NodeId>>(&self, nodes: I) -> HashGraph {
+        let nodes: FnvHashSet<NodeId> = nodes
+            .into_iter()
+            .filter(|id| self.graph.contains_key(id))
+            .collect();
+        let mut ids: Vec<NodeId> = nodes.iter().copied().collect();
+        ids.sort_unstable();
+
+        let mut sub = HashGraph::with_config(self.config());
+        for id in ids.iter() {
+            let node = self.get_node_unchecked(id);
+            // an interned sequence is an index in the table of this graph
+            let sequence = match &node.sequence {
+                SequenceRef::Interned(_) => sub.make_sequence(self.node_sequence(node)),
+                stored => stored.clone(),
+            };
+            sub.insert_node(*id, sequence).unwrap();
+            sub.get_node_mut(id).unwrap().optional = node.optional.clone();
+            sub.mark_placeholders(self.placeholders.get(id).map(|len| (*id, *len)));
+            if let Some(name) = self.node_name(*id) {
+                sub.set_node_name(*id, name.to_owned());
+            }
+        }
+
+        for id in ids.iter() {
+            for from in [Handle::pack(*id, false), Handle::pack(*id, true)].iter() {
+                for to in self.neighbors(*from, Direction::Right) {
+                    if !nodes.contains(&to.id()) {
+                        continue;
+                    }
+                    let edge = Edge(*from, to);
+                    // each edge is found from both its ends
+                    if sub.create_edge(edge).unwrap() {
+                        let overlap = self.edge_overlap(edge);
+                        if overlap > 0 {
+                            sub.set_edge_overlap(edge, overlap).unwrap();
+                        }
+                        sub.set_edge_tags(edge, self.edge_tags(edge).to_vec());
+                    }
+                }
+            }
+        }
+
+        let mut path_ids: Vec<PathId> = self.paths.keys().copied().collect();
+        path_ids.sort_unstable();
+        for path_id in path_ids.iter() {
+            self.copy_path_runs(&mut sub, path_id, &nodes);
+        }
+        sub
+    }
+
+    /// Function that returns the subgraph of the nodes at most
+    /// ```radius``` edges away from ```start```, following the edges
+    /// from both its sides, as
+    /// [`subgraph_from_nodes`](#method.subgraph_from_nodes); the graph is
+    /// empty if ```start``` isn't a node
+    /// # Example
+    /// ```ignore
+    /// let around = graph.neighborhood(NodeId::from(42), 2);
+    /// println!("{}", around);
+    /// ```
+    pub fn neighborhood(&self, start: NodeId, radius: usize) -> HashGraph {
+        if !self.graph.contains_key(&start) {
+            return HashGraph::with_config(self.config());
+        }
+        let mut seen: FnvHashSet<NodeId> = FnvHashSet::default();
+        seen.insert(start);
+        let mut frontier = vec![start];
+        for _ in 0..radius {
+            let mut next = vec![];
+            for id in frontier.iter() {
+                let handle = Handle::pack(*id, false);
+                let reached = self
+                    .neighbors(handle, Direction::Left)
+                    .chain(self.neighbors(handle, Direction::Right));
+                for h in reached {
+                    if seen.insert(h.id()) {
+                        next.push(h.id());
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        self.subgraph_from_nodes(seen)
+    }
+
+    /// Copy the runs of consecutive steps of a path that are on the
+    /// nodes, as described by ```subgraph_from_nodes```
+    fn copy_path_runs(&self, sub: &mut HashGraph, path_id: &PathId, nodes: &FnvHashSet<NodeId>) {
+        let path = self.get_path_unchecked(path_id);
+        let inside = |h: &Handle| nodes.contains(&h.id());
+        if !path.nodes.is_empty() && path.nodes.iter().all(inside) {
+            let copy = sub.create_path_handle(&path.name, path.is_circular);
+            for step in path.nodes.iter() {
+                sub.append_step(&copy, *step).unwrap();
+            }
+            return;
+        }
+
+        // a circular path starts after a step that is left out, so a run
+        // isn't cut where the path goes back to its first step
+        let start = match path.nodes.iter().position(|h| !inside(h)) {
+            Some(out) if path.is_circular => out + 1,
+            _ => 0,
+        };
+        let steps = path.nodes.range(start..).chain(path.nodes.range(..start));
+        let mut runs: Vec<Vec<Handle>> = vec![];
+        let mut run: Vec<Handle> = vec![];
+        for step in steps {
+            if inside(step) {
+                run.push(*step);
+            } else if !run.is_empty() {
+                runs.push(std::mem::take(&mut run));
+            }
+        }
+        if !run.is_empty() {
+            runs.push(run);
+        }
+
+        let single = runs.len() == 1;
+        let mut suffix = 0;
+        for run in runs {
+            let name = if single {
+                path.name.to_vec()
+            } else {
+                // a name already taken by another path isn't reused
+                loop {
+                    let mut name = path.name.to_vec();
+                    name.extend_from_slice(format!("_{}", suffix).as_bytes());
+                    suffix += 1;
+                    if !sub.has_path(&name) && !self.has_path(&name) {
+                        break name;
+                    }
+                }
+            };
+            let copy = sub.create_path_handle(&name, false);
+            for step in run {
+                sub.append_step(&copy, step).unwrap();
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::ops::Range;
+
+use crate::{handle::Handle, handlegraph::*, mutablehandlegraph::*, pathgraph::PathHandleGraph};
+
+use super::{HashGraph, PathId};
+
+/// The steps of a path covering a range of bases
+/// ```ignore
+/// pub struct PathSnapshot {
+///     pub path_id: PathId,
+///     pub first_step: usize,
+///     pub steps: Vec<Handle>,
+///     pub start_offset: usize,
+///     pub end_offset: usize,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathSnapshot {
+    pub path_id: PathId,
+    /// Index in the path of the first step
+    pub first_step: usize,
+    /// The steps covering the range, in the order of the path
+    pub steps: Vec<Handle>,
+    /// Bases of the first step before the range begins
+    pub start_offset: usize,
+    /// Bases of the last step that are in the range, so the range ends
+    /// exactly at the end of the last step when it's equal to its length
+    pub end_offset: usize,
+}
+
+impl HashGraph {
+    /// Function that returns the steps of a path covering the bases in
+    /// ```range```, where the first and the last step can be covered only
+    /// in part, as recorded by the offsets of the
+    /// [`PathSnapshot`](../subpath/struct.PathSnapshot.html).\
+    /// The offsets are along the path, so for a reversed step they count
+    /// the bases of the reverse complement of the node.\
+    /// Fails if the path doesn't exist, or the range is empty, inverted or
+    /// goes beyond the end of the path
+    /// # Example
+    /// ```ignore
+    /// // path: ACCTT CCTTGA CTTGATT
+    /// let snapshot = graph.extract_subpath(&path, 3..8).unwrap();
+    /// assert_eq!(snapshot.start_offset, 3);
+    /// assert_eq!(snapshot.end_offset, 3);
+    /// ```
+    pub fn extract_subpath(
+        &self,
+        path_id: &PathId,
+        range: Range<usize>,
+    ) -> Result<PathSnapshot, GraphError> {
+        let path = self
+            .get_path(path_id)
+            .ok_or_else(|| GraphError::PathNotExist(path_id.to_string()))?;
+        let out_of_range = || {
+            GraphError::PositionNotFound(
+                format!("{}..{}", range.start, range.end),
+                path.name.to_string(),
+            )
+        };
+        if range.start >= range.end {
+            return Err(out_of_range());
+        }
+
+        let (mut pos, mut first) = (0, None);
+        for (ix, step) in path.nodes.iter().enumerate() {
+            let len = self.node_len(*step);
+            if first.is_none() && range.start < pos + len {
+                first = Some((ix, range.start - pos));
+            }
+            if let Some((first_step, start_offset)) = first {
+                if range.end <= pos + len {
+                    return Ok(PathSnapshot {
+                        path_id: *path_id,
+                        first_step,
+                        steps: path.nodes.range(first_step..=ix).copied().collect(),
+                        start_offset,
+                        end_offset: range.end - pos,
+                    });
+                }
+            }
+            pos += len;
+        }
+        Err(out_of_range())
+    }
+
+    /// Split a node where a step reaches ```offset``` bases
+    fn split_step(&mut self, step: Handle, offset: usize) -> Result<(), GraphError> {
+        self.divide_handle(step, vec![offset])?;
+        Ok(())
+    }
+
+    /// Function that creates a new path named ```new_name``` spelling
+    /// exactly the bases of the path in ```range```.\
+    /// The first and last nodes covered only in part are split with
+    /// [`divide_handle`](../../mutablehandlegraph/trait.AdditiveHandleGraph.html#tymethod.divide_handle)
+    /// at the boundaries of the range, and every path going through them
+    /// now goes through the pieces; a range starting and ending at the
+    /// boundaries of the nodes doesn't split anything.\
+    /// Returns the id of the new path, or the error of
+    /// [`extract_subpath`](#method.extract_subpath), or ```PathAlreadyExist```
+    /// if there's already a path named ```new_name```, without changing
+    /// the graph
+    /// # Example
+    /// ```ignore
+    /// let sub = graph.extract_subpath_exact(&path, 3..8, b"sub").unwrap();
+    /// assert_eq!(graph.path_bases_len(&sub), Some(5));
+    /// ```
+    pub fn extract_subpath_exact(
+        &mut self,
+        path_id: &PathId,
+        range: Range<usize>,
+        new_name: &[u8],
+    ) -> Result<PathId, GraphError> {
+        self.as_one_change(|graph| graph.split_subpath(path_id, range, new_name))
+    }
+
+    fn split_subpath(
+        &mut self,
+        path_id: &PathId,
+        range: Range<usize>,
+        new_name: &[u8],
+    ) -> Result<PathId, GraphError> {
+        if self.has_path(new_name) {
+            return Err(GraphError::PathAlreadyExist(
+                String::from_utf8_lossy(new_name).into_owned(),
+            ));
+        }
+        let snapshot = self.extract_subpath(path_id, range.clone())?;
+        if snapshot.start_offset > 0 {
+            self.split_step(snapshot.steps[0], snapshot.start_offset)?;
+        }
+
+        // the first split can change the steps
+        let snapshot = self.extract_subpath(path_id, range.clone())?;
+        let last = snapshot.steps[snapshot.steps.len() - 1];
+        if snapshot.end_offset < self.node_len(last) {
+            self.split_step(last, snapshot.end_offset)?;
+        }
+
+        let snapshot = self.extract_subpath(path_id, range)?;
+        let new_path = self.create_path_handle(new_name, false);
+        for step in snapshot.steps {
+            self.append_step(&new_path, step)?;
+        }
+        Ok(new_path)
+    }
+}
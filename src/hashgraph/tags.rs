@@ -0,0 +1,134 @@
+/// This file provides the optional fields of the nodes and of the edges,
+/// as the tags of the segments and of the links read from a GFA file,
+/// or the ones set by an algorithm on the graph
+use crate::{
+    gfa::optional::OptField,
+    handle::{Edge, NodeId},
+    handlegraph::*,
+};
+
+use super::{generation::Changes, HashGraph};
+
+/// Set ```tag``` in the tags, in place of the one with the same name,
+/// returning the tag replaced
+fn put_tag(tags: &mut Vec<OptField>, tag: OptField) -> Option<OptField> {
+    match tags.iter_mut().find(|t| t.tag == tag.tag) {
+        Some(old) => Some(std::mem::replace(old, tag)),
+        None => {
+            tags.push(tag);
+            None
+        }
+    }
+}
+
+/// Remove the tag with this name from the tags
+fn take_tag(tags: &mut Vec<OptField>, tag: &[u8; 2]) -> Option<OptField> {
+    let ix = tags.iter().position(|t| &t.tag == tag)?;
+    Some(tags.remove(ix))
+}
+
+impl HashGraph {
+    /// The optional fields of a node, in the order they were read or
+    /// set, empty if the node doesn't exist
+    #[inline]
+    pub fn node_tags(&self, id: NodeId) -> &[OptField] {
+        self.graph.get(&id).map_or(&[], |node| &node.optional)
+    }
+
+    /// Function that sets an optional field of a node, replacing the one
+    /// with the same tag, and returns the field replaced.\
+    /// The tags of a node are written back with its segment, and
+    /// removed with it
+    /// # Example
+    /// ```ignore
+    /// graph.set_node_tag(NodeId::from(5), OptField::new(b"DP", b'f', b"12.5"))?;
+    /// assert_eq!(graph.node_tags(NodeId::from(5))[0].value, "12.5");
+    /// ```
+    pub fn set_node_tag(
+        &mut self,
+        id: NodeId,
+        tag: OptField,
+    ) -> Result<Option<OptField>, GraphError> {
+        let replaced = match self.graph.get_mut(&id) {
+            Some(node) => put_tag(&mut node.optional, tag),
+            None => return Err(GraphError::NodeNotExist(id.to_string())),
+        };
+        self.record(Changes::TAGS);
+        Ok(replaced)
+    }
+
+    /// Function that removes the optional field of a node with this tag,
+    /// and returns it
+    pub fn remove_node_tag(&mut self, id: NodeId, tag: &[u8; 2]) -> Option<OptField> {
+        let removed = take_tag(&mut self.graph.get_mut(&id)?.optional, tag)?;
+        self.record(Changes::TAGS);
+        Some(removed)
+    }
+
+    /// The optional fields of an edge, whatever the strand it's given
+    /// from, empty if the edge has none or doesn't exist
+    #[inline]
+    pub fn edge_tags(&self, Edge(l, r): Edge) -> &[OptField] {
+        self.edge_tags
+            .get(&Edge::edge_handle(l, r))
+            .map_or(&[], |tags| tags.as_slice())
+    }
+
+    /// Function that sets an optional field of an existing edge,
+    /// replacing the one with the same tag, and returns the field
+    /// replaced.\
+    /// The tags belong to the edge as its overlap does: they're the same
+    /// from both its strands, written back with its link (or GFA2 edge),
+    /// and removed with it
+    /// # Example
+    /// ```ignore
+    /// let (one, two) = (Handle::pack(1, false), Handle::pack(2, false));
+    /// graph.set_edge_tag(Edge(one, two), OptField::new(b"RC", b'i', b"3"))?;
+    /// assert_eq!(graph.edge_tags(Edge(two.flip(), one.flip())).len(), 1);
+    /// ```
+    pub fn set_edge_tag(
+        &mut self,
+        Edge(l, r): Edge,
+        tag: OptField,
+    ) -> Result<Option<OptField>, GraphError> {
+        if !self.has_edge_canonical(Edge(l, r)) {
+            return Err(GraphError::EdgeNotExist(
+                l.id().to_string(),
+                r.id().to_string(),
+            ));
+        }
+        let tags = self.edge_tags.entry(Edge::edge_handle(l, r)).or_default();
+        let replaced = put_tag(tags, tag);
+        self.record(Changes::TAGS);
+        Ok(replaced)
+    }
+
+    /// Function that removes the optional field of an edge with this
+    /// tag, and returns it
+    pub fn remove_edge_tag(&mut self, Edge(l, r): Edge, tag: &[u8; 2]) -> Option<OptField> {
+        let edge = Edge::edge_handle(l, r);
+        let tags = self.edge_tags.get_mut(&edge)?;
+        let removed = take_tag(tags, tag)?;
+        if tags.is_empty() {
+            self.edge_tags.remove(&edge);
+        }
+        self.record(Changes::TAGS);
+        Some(removed)
+    }
+
+    /// Keep the optional fields of a link (or a GFA2 edge) in its edge,
+    /// if the edge exists
+    pub(crate) fn set_edge_tags(&mut self, Edge(l, r): Edge, optional: Vec<OptField>) {
+        if optional.is_empty() || !self.has_edge_canonical(Edge(l, r)) {
+            return;
+        }
+        self.edge_tags.insert(Edge::edge_handle(l, r), optional);
+    }
+
+    /// Drop the overlap and the tags of a canonical edge, returning if
+    /// it had an overlap
+    pub(crate) fn forget_edge(&mut self, edge: &Edge) -> bool {
+        self.edge_tags.remove(edge);
+        self.overlaps.remove(edge).is_some()
+    }
+}
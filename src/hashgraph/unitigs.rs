@@ -0,0 +1,126 @@
+use fnv::FnvHashSet;
+use std::io::Write;
+
+use crate::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+};
+
+use super::HashGraph;
+
+impl HashGraph {
+    /// Distinct neighbors of a handle, as the same edge can be stored more times
+    fn distinct_neighbors(&self, handle: Handle, dir: Direction) -> Vec<Handle> {
+        let mut neighbors: Vec<Handle> = self.neighbors(handle, dir).collect();
+        neighbors.sort();
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// The handle that follows the given one in its chain: the only right
+    /// neighbor of the handle, when the handle is its only left neighbor
+    fn chain_next(&self, handle: Handle) -> Option<Handle> {
+        match self.distinct_neighbors(handle, Direction::Right).as_slice() {
+            [next] if next.id() != handle.id() => {
+                match self.distinct_neighbors(*next, Direction::Left).as_slice() {
+                    [prev] if *prev == handle => Some(*next),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Maximal chains of handles that can be merged in a single node,
+    /// covering every node exactly once, see
+    /// [`simple_paths`](#method.simple_paths)
+    pub(crate) fn chains(&self) -> Vec<Vec<Handle>> {
+        let mut visited: FnvHashSet<NodeId> = FnvHashSet::default();
+        let mut chains = vec![];
+        for start in self.handles_sorted() {
+            if visited.contains(&start.id()) {
+                continue;
+            }
+
+            // go back to the beginning of the chain, or around the cycle
+            let (mut first, mut cycle) = (start, false);
+            let mut seen: FnvHashSet<NodeId> = FnvHashSet::default();
+            seen.insert(start.id());
+            while let Some(prev) = self.chain_next(first.flip()).map(Handle::flip) {
+                if prev == start {
+                    cycle = true;
+                    break;
+                }
+                if !seen.insert(prev.id()) || visited.contains(&prev.id()) {
+                    break;
+                }
+                first = prev;
+            }
+            if cycle {
+                first = start;
+            }
+
+            let mut chain = vec![first];
+            let mut in_chain: FnvHashSet<NodeId> = FnvHashSet::default();
+            in_chain.insert(first.id());
+            let mut last = first;
+            while let Some(next) = self.chain_next(last) {
+                if !in_chain.insert(next.id()) || visited.contains(&next.id()) {
+                    break;
+                }
+                chain.push(next);
+                last = next;
+            }
+
+            if !cycle && chain[chain.len() - 1].flip() < chain[0] {
+                chain = chain.into_iter().rev().map(Handle::flip).collect();
+            }
+            visited.extend(in_chain);
+            chains.push(chain);
+        }
+        chains
+    }
+
+    /// Function that decomposes the graph into its maximal non-branching
+    /// paths (unitigs): chains of handles where each handle has only the
+    /// next one as right neighbor and the next one has only it as left
+    /// neighbor.\
+    /// Every node is in exactly one chain, a node on a branch is a chain
+    /// by itself. Each chain is reported in the direction that starts with
+    /// the smaller handle, comparing its first handle with the flipped
+    /// last one, while an isolated cycle starts from the forward handle of
+    /// its smallest node.\
+    /// The chains are ordered by their smallest node id.
+    /// # Example
+    /// ```ignore
+    /// // 1+ -> 2+ -> 3+ and 1+ -> 4+
+    /// assert_eq!(graph.simple_paths().len(), 3); // [1+], [2+, 3+], [4+]
+    /// ```
+    pub fn simple_paths(&self) -> Vec<Vec<Handle>> {
+        self.chains()
+    }
+
+    /// Function that writes the sequence of each of the
+    /// [`simple_paths`](#method.simple_paths) as a FASTA record, whose
+    /// name is the list of handles of the chain
+    /// # Example
+    /// ```ignore
+    /// graph.write_unitigs_fasta(std::io::stdout()).unwrap();
+    /// // >2+,3+
+    /// // CAT
+    /// ```
+    pub fn write_unitigs_fasta<W: Write>(&self, mut out: W) -> std::io::Result<()> {
+        for chain in self.chains() {
+            let name: Vec<String> = chain
+                .iter()
+                .map(|h| format!("{}{}", h.id(), if h.is_reverse() { "-" } else { "+" }))
+                .collect();
+            writeln!(out, ">{}", name.join(","))?;
+            for handle in chain.iter() {
+                out.write_all(&self.sequence(*handle))?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
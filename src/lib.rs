@@ -1,3 +1,4 @@
+pub mod gaf;
 pub mod gfa;
 pub mod parser;
 
@@ -5,7 +6,17 @@ pub mod handle;
 pub mod handlegraph;
 pub mod hashgraph;
 pub mod mutablehandlegraph;
+pub mod packed;
 pub mod pathgraph;
 pub mod pathhandlegraph;
 
 pub mod util;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(any(test, feature = "testsuite"))]
+pub mod testsuite;
+
+#[cfg(fuzzing)]
+pub mod fuzzing;
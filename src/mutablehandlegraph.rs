@@ -4,7 +4,10 @@ use crate::handlegraph::{error::GraphError, HandleGraph, HandleGraphRef};
 pub trait SubtractiveHandleGraph {
     /// Function that remove a
     /// [`Node`](file:///D:/GitHub/rs-gfahandlegraph/target/doc/gfahandlegraph/hashgraph/node/struct.Node.html)
-    /// and all its occurrencies
+    /// and all its occurrencies.\
+    /// The paths visiting the node are destroyed; an HashGraph can keep
+    /// them without the steps on the node with
+    /// [`remove_handle_with`](file:///D:/GitHub/rs-gfahandlegraph/target/doc/gfahandlegraph/hashgraph/graph/struct.HashGraph.html#method.remove_handle_with)
     /// # Example
     /// ```ignore
     /// // Nodes: 11, 12, 13
@@ -63,6 +66,18 @@ pub trait AdditiveHandleGraph {
         seq: &[u8],
     ) -> Result<Handle, GraphError>;
 
+    /// Function that adds an
+    /// [`Edge`](file:///D:/GitHub/rs-gfahandlegraph/target/doc/gfahandlegraph/handle/struct.Edge.html)
+    /// between 2 existing nodes.\
+    /// Returns ```Ok(false)``` if the edge already exists, and
+    /// ```NodeNotExist``` without changing the graph if one of the nodes
+    /// doesn't exist
+    /// # Example
+    /// ```ignore
+    /// let edge = Edge(Handle::pack(11, false), Handle::pack(12, false));
+    /// assert!(graph.create_edge(edge)?);
+    /// assert!(!graph.create_edge(edge)?);
+    /// ```
     fn create_edge(&mut self, edge: Edge) -> Result<bool, GraphError>;
 }
 
@@ -87,7 +102,10 @@ pub trait ModdableHandleGraph {
     /// given an [`Edge`](file:///D:/GitHub/rs-gfahandlegraph/target/doc/gfahandlegraph/handle/struct.Edge.html),
     /// this function will replace the left, the right or both
     /// [`Handle`](file:///D:/GitHub/rs-gfahandlegraph/target/doc/gfahandlegraph/handle/struct.Handle.html)
-    /// with the provided ones
+    /// with the provided ones.\
+    /// The graph is left untouched, with an ```EdgeNotExist```, if the
+    /// edge isn't in the graph, or with a ```NodeNotExist``` if a new end
+    /// isn't a node of the graph
     /// # Example
     /// ```ignore
     /// let h1 = graph.create_handle(b"1", 1);
@@ -111,14 +129,69 @@ pub trait ModdableHandleGraph {
 /// Trait encapsulating the mutable aspects of a handlegraph
 /// WIP
 pub trait MutableHandleGraph: HandleGraph {
-    fn divide_handle(&mut self, handle: Handle, offsets: Vec<usize>) -> Vec<Handle>;
+    /// Function that divides a node in pieces at the offsets, read on the
+    /// strand of the handle, and returns the pieces in the order of the
+    /// handle: the first one keeps the id of the node.\
+    /// The paths walking through the node walk through all the pieces,
+    /// backwards if they walk through the node backwards.\
+    /// The graph is left untouched, with an ```InvalidOffset```, if the
+    /// offsets aren't increasing or an offset isn't inside the node
+    /// # Example
+    /// ```ignore
+    /// // Nodes: 9 (AAATTTTCTG)
+    /// let pieces = graph.divide_handle(Handle::pack(9, false), vec![3, 7])?;
+    /// // Nodes: 9 (AAA), 16 (TTTT), 17 (CTG)
+    /// ```
+    fn divide_handle(
+        &mut self,
+        handle: Handle,
+        offsets: Vec<usize>,
+    ) -> Result<Vec<Handle>, GraphError>;
 
-    fn split_handle(&mut self, handle: Handle, offset: usize) -> (Handle, Handle) {
-        let handles = self.divide_handle(handle, vec![offset]);
-        (handles[0], handles[1])
+    fn split_handle(
+        &mut self,
+        handle: Handle,
+        offset: usize,
+    ) -> Result<(Handle, Handle), GraphError> {
+        let handles = self.divide_handle(handle, vec![offset])?;
+        Ok((handles[0], handles[1]))
     }
 
-    fn apply_orientation(&mut self, handle: Handle) -> Handle;
+    /// Function that merges a simple chain of handles, each one the only
+    /// neighbor of the next one, into a single node with the sequence
+    /// spelled by the chain (without the overlaps of its inner edges).\
+    /// The new node takes the edges entering the first handle and the
+    /// ones leaving the last handle, and the paths walking through the
+    /// chain step on it instead, backwards if they walk through the
+    /// chain backwards.\
+    /// The graph is left untouched, with a ```ChainNotSimple```, if a
+    /// node of the chain has other neighbors, is in the chain more than
+    /// once, or a path visits only a part of the chain
+    /// # Example
+    /// ```ignore
+    /// // Nodes: 1 (AC), 2 (GT), 3 (TA)
+    /// // Edges: 1+ -> 2+, 2+ -> 3-
+    /// let merged = graph.combine_handles(&[h1, h2, h3.flip()])?;
+    /// // Nodes: 4 (ACGTTA)
+    /// ```
+    fn combine_handles(&mut self, handles: &[Handle]) -> Result<Handle, GraphError>;
+
+    /// Function that makes ```handle``` the forward handle of its node and
+    /// returns it: the node of a reverse handle gets the reverse
+    /// complement of its sequence, and its edges and every step of the
+    /// paths visiting it are flipped, so the paths spell the same
+    /// sequences; a forward handle is returned as it is.\
+    /// The graph is left untouched, with a ```NodeNotExist``` if the node
+    /// doesn't exist, a ```PositionNotFound``` if an occurrence of the
+    /// node isn't a step of its path on the node, or a
+    /// ```SequenceUnavailable``` if the sequence can't be read
+    /// # Example
+    /// ```ignore
+    /// // Nodes: 12 (TCAAGG), x visits 12+ and y visits 12-
+    /// let fwd = graph.apply_orientation(h12.flip())?;
+    /// // Nodes: 12 (CCTTGA), x visits 12- and y visits 12+
+    /// ```
+    fn apply_orientation(&mut self, handle: Handle) -> Result<Handle, GraphError>;
 }
 
 pub trait MutHandleGraphRef: HandleGraphRef {}
@@ -0,0 +1,119 @@
+pub mod graph;
+
+pub use self::graph::PackedGraph;
+
+use rayon::prelude::*;
+
+use crate::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    util::dna,
+};
+
+impl<'a> AllHandles for &'a PackedGraph {
+    type Handles = NodeIdRefHandles<'a, std::slice::Iter<'a, NodeId>>;
+
+    #[inline]
+    fn handles(self) -> Self::Handles {
+        NodeIdRefHandles::new(self.ids.iter())
+    }
+
+    #[inline]
+    fn node_count(self) -> usize {
+        self.ids.len()
+    }
+
+    #[inline]
+    fn has_node<I: Into<NodeId>>(self, n_id: I) -> bool {
+        self.rank(n_id.into()).is_some()
+    }
+}
+
+/// parallel version for AllHandles Iter
+impl<'a> AllHandlesPar for &'a PackedGraph {
+    type HandlesPar = rayon::iter::Map<rayon::slice::Iter<'a, NodeId>, fn(&NodeId) -> Handle>;
+
+    fn handles_par(self) -> Self::HandlesPar {
+        self.ids.par_iter().map(|id| Handle::pack(*id, false))
+    }
+}
+
+impl<'a> AllEdges for &'a PackedGraph {
+    type Edges = EdgesIter<&'a PackedGraph>;
+
+    #[inline]
+    fn edges(self) -> Self::Edges {
+        EdgesIter::new(self)
+    }
+
+    #[inline]
+    fn edge_count(self) -> usize {
+        self.edge_count
+    }
+}
+
+impl<'a> HandleNeighbors for &'a PackedGraph {
+    type Neighbors = NeighborIter<'a, std::slice::Iter<'a, Handle>>;
+
+    #[inline]
+    fn neighbors(self, handle: Handle, dir: Direction) -> Self::Neighbors {
+        let rank = self.rank_unchecked(handle.id());
+        // as for an HashGraph, the sides of a reverse handle are swapped
+        let right = (dir == Direction::Right) != handle.is_reverse();
+        NeighborIter::new(self.side_at(rank, right).iter(), dir == Direction::Left)
+    }
+
+    #[inline]
+    fn degree(self, handle: Handle, dir: Direction) -> usize {
+        let rank = self.rank_unchecked(handle.id());
+        let right = (dir == Direction::Right) != handle.is_reverse();
+        self.side_at(rank, right).len()
+    }
+}
+
+impl<'a> HandleSequences for &'a PackedGraph {
+    type Sequence = SequenceIter<std::iter::Copied<std::slice::Iter<'a, u8>>>;
+
+    #[inline]
+    fn sequence_iter(self, handle: Handle) -> Self::Sequence {
+        let seq = self.sequence_at(self.rank_unchecked(handle.id()));
+        SequenceIter::new(seq.iter().copied(), handle.is_reverse())
+    }
+
+    fn sequence(self, handle: Handle) -> Vec<u8> {
+        let seq = self.sequence_at(self.rank_unchecked(handle.id()));
+        if handle.is_reverse() {
+            dna::rev_comp_slice(seq)
+        } else {
+            seq.into()
+        }
+    }
+
+    #[inline]
+    fn node_len(self, handle: Handle) -> usize {
+        self.sequence_at(self.rank_unchecked(handle.id())).len()
+    }
+}
+
+impl HandleGraph for PackedGraph {
+    /// The smallest id, or ```u64::MAX``` as an empty HashGraph
+    #[inline]
+    fn min_node_id(&self) -> NodeId {
+        self.ids
+            .first()
+            .copied()
+            .unwrap_or_else(|| NodeId::from(u64::MAX))
+    }
+
+    /// The largest id, or 0 as an empty HashGraph
+    #[inline]
+    fn max_node_id(&self) -> NodeId {
+        self.ids.last().copied().unwrap_or_else(|| NodeId::from(0))
+    }
+}
+
+impl HandleGraphRef for &PackedGraph {
+    fn total_length(self) -> usize {
+        self.sequences.len()
+    }
+}
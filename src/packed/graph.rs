@@ -0,0 +1,128 @@
+use crate::{
+    handle::{Handle, NodeId},
+    handlegraph::*,
+    hashgraph::HashGraph,
+};
+
+/// New type.\
+/// A read-only graph that keeps its nodes in a few flat arrays instead
+/// of a map of nodes: the ids sorted, the sequences one after the other
+/// and the neighbors of each side of the nodes one after the other, as
+/// the rows of a CSR matrix, so a node costs a few words besides its
+/// bases and its edges.\
+/// A node is found with a binary search on the ids. The paths aren't
+/// kept, and the graph can't be modified: it's built from an HashGraph
+/// with [`from_hashgraph`](#method.from_hashgraph)
+/// # Example
+/// ```ignore
+/// pub struct PackedGraph {
+///     ids: Vec<NodeId>,
+///     sequences: Vec<u8>,
+///     sequence_offsets: Vec<usize>,
+///     neighbors: Vec<Handle>,
+///     neighbor_offsets: Vec<usize>,
+///     edge_count: usize,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedGraph {
+    pub(crate) ids: Vec<NodeId>,
+    /// The sequence of the node ```i``` (in the order of the ids) is at
+    /// ```sequence_offsets[i]..sequence_offsets[i + 1]```
+    pub(crate) sequences: Vec<u8>,
+    pub(crate) sequence_offsets: Vec<usize>,
+    /// The neighbors of the left side of the node ```i``` are in the row
+    /// ```2 * i```, the ones of its right side in the row ```2 * i + 1```,
+    /// stored as the edges of a Node are
+    pub(crate) neighbors: Vec<Handle>,
+    pub(crate) neighbor_offsets: Vec<usize>,
+    pub(crate) edge_count: usize,
+}
+
+impl Default for PackedGraph {
+    fn default() -> PackedGraph {
+        PackedGraph {
+            ids: vec![],
+            sequences: vec![],
+            sequence_offsets: vec![0],
+            neighbors: vec![],
+            neighbor_offsets: vec![0],
+            edge_count: 0,
+        }
+    }
+}
+
+impl PackedGraph {
+    /// Function that packs the nodes and the edges of an HashGraph, the
+    /// sequences as the graph reads them (interned or lazily loaded ones
+    /// included) and the neighbors in the order of the graph
+    /// # Example
+    /// ```ignore
+    /// let graph = parse_file_to_graph("./tests/big_files/test.gfa")?;
+    /// let packed = PackedGraph::from_hashgraph(&graph);
+    /// drop(graph);
+    /// ```
+    pub fn from_hashgraph(graph: &HashGraph) -> PackedGraph {
+        let mut ids: Vec<NodeId> = graph.graph.keys().copied().collect();
+        ids.sort_unstable();
+        let nodes: Vec<_> = ids.iter().map(|id| graph.get_node_unchecked(id)).collect();
+
+        let bases = nodes.iter().map(|n| graph.node_sequence_len(n)).sum();
+        let ends = nodes
+            .iter()
+            .map(|n| n.left_edges.len() + n.right_edges.len())
+            .sum();
+        let mut packed = PackedGraph {
+            sequences: Vec::with_capacity(bases),
+            sequence_offsets: Vec::with_capacity(ids.len() + 1),
+            neighbors: Vec::with_capacity(ends),
+            neighbor_offsets: Vec::with_capacity(ids.len() * 2 + 1),
+            edge_count: graph.edge_count(),
+            ids: vec![],
+        };
+        packed.sequence_offsets.push(0);
+        packed.neighbor_offsets.push(0);
+        for node in nodes {
+            packed
+                .sequences
+                .extend_from_slice(graph.node_sequence(node));
+            packed.sequence_offsets.push(packed.sequences.len());
+            for side in [&node.left_edges, &node.right_edges].iter() {
+                packed.neighbors.extend_from_slice(side);
+                packed.neighbor_offsets.push(packed.neighbors.len());
+            }
+        }
+        packed.ids = ids;
+        packed
+    }
+
+    /// The position of a node in the arrays
+    #[inline]
+    pub(crate) fn rank(&self, id: NodeId) -> Option<usize> {
+        self.ids.binary_search(&id).ok()
+    }
+
+    /// The position of a node in the arrays, panicking as an HashGraph
+    /// does if the node doesn't exist
+    #[inline]
+    pub(crate) fn rank_unchecked(&self, id: NodeId) -> usize {
+        match self.rank(id) {
+            Some(rank) => rank,
+            None => panic!("The node {} doesn't exist", id),
+        }
+    }
+
+    /// The sequence of the node at ```rank```, in its forward orientation
+    #[inline]
+    pub(crate) fn sequence_at(&self, rank: usize) -> &[u8] {
+        &self.sequences[self.sequence_offsets[rank]..self.sequence_offsets[rank + 1]]
+    }
+
+    /// The neighbors of a side of the node at ```rank```, stored as
+    /// ```left_edges``` and ```right_edges``` of a Node are
+    #[inline]
+    pub(crate) fn side_at(&self, rank: usize, right: bool) -> &[Handle] {
+        let row = rank * 2 + usize::from(right);
+        &self.neighbors[self.neighbor_offsets[row]..self.neighbor_offsets[row + 1]]
+    }
+}
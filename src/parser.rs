@@ -1,61 +1,899 @@
 pub mod error;
+pub mod extension;
+pub mod lazy;
+pub mod multi;
 pub mod parser_gfa1;
 pub mod parser_gfa2;
+pub mod progress;
+pub mod recovery;
+pub(crate) mod validators;
 
 pub use self::error::*;
-pub use self::parser_gfa1::*;
-pub use self::parser_gfa2::*;
+pub use self::extension::CustomLineParser;
+pub use self::lazy::parse_file_to_graph_lazy;
+pub use self::multi::{parse_files_to_graph, MultiFileMode, OverlayWarning};
+// the ParserBuilder and the shared line types (type_header and
+// type_segment) of each format are only in its own module
+pub use self::parser_gfa1::{type_containment, type_link, type_path, GFAParser};
+pub use self::parser_gfa2::{
+    type_edge, type_fragment, type_gap, type_ogroup, type_ugroup, GFA2Parser,
+};
+pub use self::progress::{BuildProgress, ParseProgress, ParseStats, ProgressHook};
+use self::progress::{LineOutcome, ParseCounter};
+pub use self::recovery::{recover_file_to_graph, RecoveryReport};
 
-use crate::gfa::{gfa1::GFA, gfa2::GFA2};
-use crate::hashgraph::HashGraph;
+use crate::gfa::{
+    gfa1::{Line as Line1, GFA},
+    gfa2::{Line as Line2, Segment as Segment2, GFA2},
+    orientation::Orientation,
+    segment_id::PathParseError,
+};
+use crate::handle::{Edge, Handle, NodeId};
+use crate::handlegraph::GraphError;
+use crate::hashgraph::{
+    attach::{gfa1_placeholder, gfa2_placeholder},
+    fidelity::RawRecord,
+    graph::{containment_record, gfa2_edge, gfa2_header, link_edge, link_overlap, FileType},
+    GraphBuilder, HashGraph, PathOrientationAnomaly,
+};
+use crate::packed::PackedGraph;
+use crate::util::{gzip::gunzip, threads::ThreadConfig};
 
-/// Function that given a
-/// [`GFA`](file:///D:/GitHub/rs-gfahandlegraph/target/doc/gfahandlegraph/gfa/gfa1/struct.GFA.html)
-/// or
-/// [`GFA2`](file:///D:/GitHub/rs-gfahandlegraph/target/doc/gfahandlegraph/gfa/gfa2/struct.GFA2.html)
-/// file as input, creates the
-/// corresponding
-/// [`HashGraph`](file:///D:/GitHub/rs-gfahandlegraph/target/doc/gfahandlegraph/hashgraph/graph/struct.HashGraph.html).
-/// # Example
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The first bytes of a gzip file
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The result of parsing a file with the unified
+/// [`Parser`](struct.Parser.html), depending on the format found
 /// ```ignore
-/// match parse_file_to_graph("./tests/gfa2_files/spec_q7.gfa2") {
-///     Ok(g) => g.print_graph(),
-///     Err(why) => println!("Error {}", why),
+/// pub enum ParsedGfa {
+///     GFA(GFA),
+///     GFA2(GFA2),
 /// }
 /// ```
-pub fn parse_file_to_graph<P: AsRef<std::path::Path>>(path: P) -> Result<HashGraph, ParseError> {
-    use crate::hashgraph::graph::FileType;
-    use std::ffi::OsStr;
+pub enum ParsedGfa {
+    GFA(GFA),
+    GFA2(GFA2),
+}
 
-    match path.as_ref().extension().and_then(OsStr::to_str).unwrap() {
-        "gfa2" => {
-            let mut graph = HashGraph::default();
-            let parser = GFA2Parser::default();
-            let gfa2: GFA2 = parser.parse_file(path)?;
+/// The format of the lines of a file or a reader
+/// ```ignore
+/// pub enum GfaFormat {
+///     Gfa1,
+///     Gfa2,
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GfaFormat {
+    Gfa1,
+    Gfa2,
+}
+
+/// The bytes read from the start of a file to find its format, see
+/// [`detect_format`](fn.detect_format.html)
+const SNIFFED_BYTES: u64 = 1 << 20;
 
-            match graph.create_graph(FileType::GFA2(gfa2)) {
-                Ok(g) => Ok(g),
-                Err(why) => Err(ParseError::ConversionGFAToGraph(why.to_string())),
+/// Function that finds the format of the first lines of a file: the
+/// version in the ```VN``` tag of a header, or else the fields of the
+/// first segment (a GFA2 segment has its length before the sequence)./// Returns ```None``` if the lines don't tell the format
+/// # Example
+/// ```ignore
+/// assert_eq!(detect_format(b"S\t1\t4\tACGT\n"), Some(GfaFormat::Gfa2));
+/// assert_eq!(detect_format(b"H\tVN:Z:1.0\n"), Some(GfaFormat::Gfa1));
+/// ```
+pub fn detect_format(bytes: &[u8]) -> Option<GfaFormat> {
+    use bstr::ByteSlice;
+    // the optional tags (as LN:i:4) come after the required fields
+    let is_tag = |f: &&[u8]| f.len() >= 5 && f[2] == b':' && f[4] == b':';
+    for line in ByteSlice::lines(bytes) {
+        let mut fields = line.trim_end().split_str(b"\t");
+        match fields.next() {
+            Some(b"H") => match fields.find_map(|f| f.strip_prefix(b"VN:Z:")) {
+                Some(version) if version.starts_with(b"1") => return Some(GfaFormat::Gfa1),
+                Some(version) if version.starts_with(b"2") => return Some(GfaFormat::Gfa2),
+                _ => (),
+            },
+            Some(b"S") => {
+                let required: Vec<&[u8]> = fields.take_while(|f| !is_tag(f)).collect();
+                return match required.as_slice() {
+                    [_, _] => Some(GfaFormat::Gfa1),
+                    [_, len, _] if !len.is_empty() && len.iter().all(u8::is_ascii_digit) => {
+                        Some(GfaFormat::Gfa2)
+                    }
+                    _ => None,
+                };
             }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// The format of a file from its first lines, as in
+/// [`detect_format`](fn.detect_format.html), without the last line read
+/// if it's cut
+fn detect_file_format(path: &Path) -> Result<Option<GfaFormat>, ParseError> {
+    use bstr::ByteSlice;
+    let mut bytes = vec![];
+    open_file(path)?
+        .take(SNIFFED_BYTES)
+        .read_to_end(&mut bytes)?;
+    let end = match bytes.len() as u64 {
+        SNIFFED_BYTES => bytes.rfind_byte(b'\n').map_or(0, |end| end + 1),
+        _ => bytes.len(),
+    };
+    Ok(detect_format(&bytes[..end]))
+}
+
+/// The lines of a reader up to the first one that can't be read,
+/// included: a reader that fails (as a directory) fails again at every
+/// following line, and its lines would never end
+pub(crate) fn until_read_error<T, I>(lines: I) -> impl Iterator<Item = std::io::Result<T>>
+where
+    I: Iterator<Item = std::io::Result<T>>,
+{
+    let mut failed = false;
+    lines.take_while(move |line| {
+        let keep = !failed;
+        failed |= line.is_err();
+        keep
+    })
+}
+
+/// The name of a file, without the ```.gz``` suffix of a compressed one
+/// (```graph.gfa``` for ```graph.gfa.gz```)
+pub(crate) fn uncompressed_name(path: &Path) -> &Path {
+    let name = match path.extension() {
+        Some(ext) if ext == "gz" => path.file_stem(),
+        _ => path.file_name(),
+    };
+    name.map_or(path, Path::new)
+}
+
+/// The format of a file from its extension, ```.gfa``` or ```.gfa2```
+/// also followed by ```.gz```, or from its first lines with any other
+/// name (or without extension): true for GFA2
+pub(crate) fn is_gfa2_file(path: &Path) -> Result<bool, ParseError> {
+    match uncompressed_name(path).extension().and_then(OsStr::to_str) {
+        Some("gfa2") => Ok(true),
+        Some("gfa") => Ok(false),
+        // a file that can't be read doesn't tell its format either
+        _ => match detect_file_format(path) {
+            Ok(Some(format)) => Ok(format == GfaFormat::Gfa2),
+            _ => Err(ParseError::ExtensionError()),
+        },
+    }
+}
+
+/// Open a file to read its lines: a file that starts with the gzip magic
+/// number (whatever its name) is decompressed in memory, also when it's
+/// made of many members as the ones written by bgzip, so its lines can
+/// still be parsed in parallel
+pub(crate) fn open_file(path: &Path) -> std::io::Result<Box<dyn BufRead + Send>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    // a file that can't be read fails at its first line
+    if let Ok(true) = reader
+        .fill_buf()
+        .map(|bytes| bytes.starts_with(&GZIP_MAGIC))
+    {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        return Ok(Box::new(Cursor::new(gunzip(&bytes)?)));
+    }
+    Ok(Box::new(reader))
+}
+
+/// Check if a file starts with the gzip magic number
+pub(crate) fn is_gzip_file(path: &Path) -> std::io::Result<bool> {
+    let mut magic = [0; 2];
+    let mut file = File::open(path)?;
+    Ok(file.read_exact(&mut magic).is_ok() && magic == GZIP_MAGIC)
+}
+
+/// Leave out the names of the segments, that are then known by their
+/// ids only
+fn drop_names(file: &mut FileType) {
+    match file {
+        FileType::GFA(gfa) => gfa
+            .segments
+            .iter_mut()
+            .for_each(|s| s.original_name.clear()),
+        FileType::GFA2(gfa2) => gfa2
+            .segments
+            .iter_mut()
+            .for_each(|s| s.original_name.clear()),
+    }
+}
+
+/// The error of the first line that failed, for the parsers that parse
+/// the lines in parallel: the lines after it aren't parsed anymore, but
+/// the ones before it still are, as one of them could fail too
+pub(crate) struct FirstFailure {
+    line: AtomicUsize,
+    error: Mutex<Option<ParseError>>,
+}
+
+impl FirstFailure {
+    pub(crate) fn new() -> Self {
+        FirstFailure {
+            line: AtomicUsize::new(usize::MAX),
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Check if the line ```ix``` (from 0) comes before the first line
+    /// that failed, so it has to be parsed
+    #[inline]
+    pub(crate) fn before(&self, ix: usize) -> bool {
+        ix < self.line.load(Ordering::Relaxed)
+    }
+
+    /// Record the error of the line ```ix```, if it comes first
+    pub(crate) fn fail(&self, ix: usize, err: ParseError) {
+        let mut error = self.error.lock().unwrap();
+        if self.before(ix) {
+            self.line.store(ix, Ordering::Relaxed);
+            *error = Some(err.at_line(ix + 1));
+        }
+    }
+
+    pub(crate) fn into_result(self) -> Result<(), ParseError> {
+        match self.error.into_inner().unwrap() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A line read by [`stream_file`](struct.Parser.html#method.stream_file)
+pub(crate) enum StreamedLine {
+    Gfa1(Line1),
+    Gfa2(Line2),
+}
+
+impl ParsedGfa {
+    /// Wrap the parsed object to build an HashGraph from it
+    pub fn into_file_type(self) -> FileType {
+        match self {
+            ParsedGfa::GFA(gfa) => FileType::GFA(gfa),
+            ParsedGfa::GFA2(gfa2) => FileType::GFA2(gfa2),
+        }
+    }
+}
+
+/// Parser for both GFA and GFA2 files, the format is chosen
+/// from the extension of the file (```.gfa``` or ```.gfa2```, also
+/// followed by ```.gz``` for a file compressed with gzip), or from its
+/// first lines with any other name, see
+/// [`detect_format`](fn.detect_format.html).\
+/// The line filters refer to the records used to build the graph:
+/// segments, links (or edges) and paths (or O-groups)
+/// # Example
+/// ```ignore
+/// let graph = Parser::new()
+///     .error_tolerance(ParserTolerance::IgnoreAll)
+///     .on_progress(Box::new(|p| eprintln!("{} lines", p.lines_read)))
+///     .parse_file_to_graph("./tests/gfa2_files/spec_q7.gfa2")
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Parser {
+    pub segments: bool,
+    pub links: bool,
+    pub paths: bool,
+    pub tolerance: ParserTolerance,
+    pub threads: ThreadConfig,
+    pub warn_orientation_anomalies: bool,
+    pub fidelity: bool,
+    pub keep_tags: bool,
+    pub keep_names: bool,
+    pub recover: bool,
+    pub progress: ProgressHook<ParseProgress>,
+    pub build_progress: ProgressHook<BuildProgress>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser {
+            segments: true,
+            links: true,
+            paths: true,
+            tolerance: Default::default(),
+            threads: Default::default(),
+            warn_orientation_anomalies: false,
+            fidelity: false,
+            keep_tags: true,
+            keep_names: true,
+            recover: false,
+            progress: Default::default(),
+            build_progress: Default::default(),
+        }
+    }
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn segments(&mut self, include: bool) -> &mut Self {
+        self.segments = include;
+        self
+    }
+
+    pub fn links(&mut self, include: bool) -> &mut Self {
+        self.links = include;
+        self
+    }
+
+    pub fn paths(&mut self, include: bool) -> &mut Self {
+        self.paths = include;
+        self
+    }
+
+    pub fn error_tolerance(&mut self, tol: ParserTolerance) -> &mut Self {
+        self.tolerance = tol;
+        self
+    }
+
+    /// Number of threads used to parse the file and build the graph, see
+    /// [`ThreadConfig`](../util/threads/struct.ThreadConfig.html)
+    pub fn threads(&mut self, threads: ThreadConfig) -> &mut Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Check the orientation of the path steps while building the graph
+    /// with [`parse_file_to_graph_with_warnings`](#method.parse_file_to_graph_with_warnings),
+    /// see [`path_orientation_anomalies`](../hashgraph/graph/struct.HashGraph.html#method.path_orientation_anomalies)
+    pub fn warn_orientation_anomalies(&mut self, warn: bool) -> &mut Self {
+        self.warn_orientation_anomalies = warn;
+        self
+    }
+
+    /// Keep the lines of the file in the graph built by
+    /// [`parse_file_to_graph`](#method.parse_file_to_graph), to write the
+    /// records that don't change exactly as they were read with
+    /// [`write_gfa_preserving`](../hashgraph/graph/struct.HashGraph.html#method.write_gfa_preserving).\
+    /// The graph keeps the whole text of the file (and the file is read
+    /// twice), so it's meant for editing a file rather than for large
+    /// analyses
+    pub fn fidelity(&mut self, keep: bool) -> &mut Self {
+        self.fidelity = keep;
+        self
+    }
+
+    /// Keep the optional fields of the segments and of the paths in the
+    /// graph (the default), to write them back with the graph, or drop
+    /// them while parsing to save their memory
+    pub fn keep_tags(&mut self, keep: bool) -> &mut Self {
+        self.keep_tags = keep;
+        self
+    }
+
+    /// Keep the names of the segments that aren't numbers (the default),
+    /// to find their nodes with
+    /// [`node_id_of_name`](../hashgraph/graph/struct.HashGraph.html#method.node_id_of_name)
+    /// and write them back, or drop them to save their memory: their
+    /// nodes keep the ids the names are converted into, and are written
+    /// with them
+    pub fn keep_names(&mut self, keep: bool) -> &mut Self {
+        self.keep_names = keep;
+        self
+    }
+
+    /// Call ```callback``` with the progress of the parsing, every
+    /// [`progress_every`](#method.progress_every) lines and at the end,
+    /// as the parser of each format does, see
+    /// [`ProgressHook`](progress/struct.ProgressHook.html)
+    pub fn on_progress(&mut self, callback: Box<dyn Fn(ParseProgress) + Send + Sync>) -> &mut Self {
+        self.progress.set_callback(callback.into());
+        self
+    }
+
+    /// Number of lines between two calls of the
+    /// [`on_progress`](#method.on_progress) callback
+    pub fn progress_every(&mut self, lines: usize) -> &mut Self {
+        self.progress.set_every(lines);
+        self
+    }
+
+    /// Call the hook with the progress of the construction of the graph
+    /// by [`parse_file_to_graph`](#method.parse_file_to_graph) and
+    /// [`parse_reader_to_graph`](#method.parse_reader_to_graph), as
+    /// [`create_graph_with_progress`](../hashgraph/graph/struct.HashGraph.html#method.create_graph_with_progress)
+    /// does
+    pub fn on_build_progress(&mut self, hook: ProgressHook<BuildProgress>) -> &mut Self {
+        self.build_progress = hook;
+        self
+    }
+
+    /// Build the graph in recovery mode, from a file that may be
+    /// corrupted or truncated: the lines that can't be parsed are
+    /// skipped, the edges on a missing segment dropped and the path
+    /// steps on a missing segment left out, and
+    /// [`parse_file_to_graph_with_recovery`](#method.parse_file_to_graph_with_recovery)
+    /// lists them in its report
+    pub fn recover(&mut self, recover: bool) -> &mut Self {
+        self.recover = recover;
+        self
+    }
+
+    fn gfa_parser(&self) -> GFAParser {
+        let mut builder = parser_gfa1::ParserBuilder::all();
+        builder
+            .segments(self.segments)
+            .links(self.links)
+            .paths(self.paths)
+            .error_tolerance(self.tolerance)
+            .keep_tags(self.keep_tags)
+            .threads(self.threads);
+        builder.progress = self.progress.clone();
+        builder.build()
+    }
+
+    fn gfa2_parser(&self) -> GFA2Parser {
+        let mut builder = parser_gfa2::ParserBuilder::all();
+        builder
+            .segments(self.segments)
+            .edges(self.links)
+            .groups_o(self.paths)
+            .keep_tags(self.keep_tags)
+            .threads(self.threads);
+        builder.tolerance = self.tolerance;
+        builder.progress = self.progress.clone();
+        builder.build()
+    }
+
+    /// Function that parses a GFA or GFA2 file
+    /// # Example
+    /// ```ignore
+    /// match Parser::new().parse_file("./tests/gfa1_files/lil.gfa") {
+    ///     Ok(ParsedGfa::GFA(gfa)) => println!("{}", gfa),
+    ///     Ok(ParsedGfa::GFA2(gfa2)) => println!("{}", gfa2),
+    ///     Err(why) => println!("Error {}", why),
+    /// }
+    /// ```
+    pub fn parse_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<ParsedGfa, ParseError> {
+        if is_gfa2_file(path.as_ref())? {
+            Ok(ParsedGfa::GFA2(self.gfa2_parser().parse_file(path)?))
+        } else {
+            Ok(ParsedGfa::GFA(self.gfa_parser().parse_file(path)?))
         }
-        "gfa" => {
+    }
+
+    /// Function that parses a GFA or GFA2 file and creates the
+    /// corresponding
+    /// [`HashGraph`](../hashgraph/graph/struct.HashGraph.html), failing
+    /// on a path step that can't be parsed unless the error tolerance is
+    /// ```IgnoreAll```, when the step is left out
+    /// # Example
+    /// ```ignore
+    /// match Parser::new().parse_file_to_graph("./tests/gfa2_files/spec_q7.gfa2") {
+    ///     Ok(g) => println!("{}", g),
+    ///     Err(why) => println!("Error {}", why),
+    /// }
+    /// ```
+    pub fn parse_file_to_graph<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<HashGraph, ParseError> {
+        let (graph, _) = self.parse_file_to_graph_with_recovery(path)?;
+        Ok(graph)
+    }
+
+    /// Function that parses a GFA or GFA2 file and creates the
+    /// corresponding HashGraph, like
+    /// [`parse_file_to_graph`](#method.parse_file_to_graph), returning
+    /// also what was left out of the graph when
+    /// [`recover`](#method.recover) is set (and a clean report otherwise)
+    /// # Example
+    /// ```ignore
+    /// let (graph, report) = Parser::new()
+    ///     .recover(true)
+    ///     .parse_file_to_graph_with_recovery("./tests/big_files/test.gfa")?;
+    /// println!("{} lines skipped", report.skipped_lines.len());
+    /// ```
+    pub fn parse_file_to_graph_with_recovery<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<(HashGraph, RecoveryReport), ParseError> {
+        let gfa2 = is_gfa2_file(path.as_ref())?;
+        let (mut graph, report) = if self.recover {
+            let (mut file, mut report) = recovery::parse_recovering(self, path.as_ref(), gfa2)?;
+            if !self.keep_names {
+                drop_names(&mut file);
+            }
             let mut graph = HashGraph::default();
-            let parser = GFAParser::default();
-            let gfa: GFA = parser.parse_file(path)?;
+            let hook = &self.build_progress;
+            let built = self
+                .threads
+                .install(|| graph.create_graph_recovering(file, hook));
+            report.dropped_edges = built.dropped_edges;
+            report.dropped_path_steps = built.dropped_path_steps;
+            report.dropped_steps_per_path = built.dropped_steps_per_path;
+            (graph, report)
+        } else {
+            let parsed = self.parse_file(path.as_ref())?;
+            (self.graph_of(parsed)?, RecoveryReport::default())
+        };
+        if self.fidelity {
+            let lines = self.read_raw_lines(open_file(path.as_ref())?, gfa2)?;
+            graph.retain_lines(gfa2, lines);
+        }
+        Ok((graph, report))
+    }
 
-            match graph.create_graph(FileType::GFA(gfa)) {
-                Ok(g) => Ok(g),
-                Err(why) => Err(ParseError::ConversionGFAToGraph(why.to_string())),
+    /// Function that parses the lines read from a reader, in the given
+    /// format, as [`parse_file`](#method.parse_file) does with a file
+    /// # Example
+    /// ```ignore
+    /// let stdin = std::io::BufReader::new(std::io::stdin());
+    /// let parsed = Parser::new().parse_reader(stdin, GfaFormat::Gfa1)?;
+    /// ```
+    pub fn parse_reader<R: BufRead + Send>(
+        &self,
+        reader: R,
+        format: GfaFormat,
+    ) -> Result<ParsedGfa, ParseError> {
+        match format {
+            GfaFormat::Gfa1 => Ok(ParsedGfa::GFA(self.gfa_parser().parse_reader(reader)?)),
+            GfaFormat::Gfa2 => Ok(ParsedGfa::GFA2(self.gfa2_parser().parse_reader(reader)?)),
+        }
+    }
+
+    /// Function that parses the lines read from a reader, in the given
+    /// format, and creates the corresponding HashGraph as
+    /// [`parse_file_to_graph`](#method.parse_file_to_graph); the
+    /// fidelity mode keeps the whole input in memory, as its lines are
+    /// read twice
+    /// # Example
+    /// ```ignore
+    /// let stdin = std::io::BufReader::new(std::io::stdin());
+    /// let graph = Parser::new().parse_reader_to_graph(stdin, GfaFormat::Gfa2)?;
+    /// ```
+    pub fn parse_reader_to_graph<R: BufRead + Send>(
+        &self,
+        mut reader: R,
+        format: GfaFormat,
+    ) -> Result<HashGraph, ParseError> {
+        if !self.fidelity {
+            return self.graph_of(self.parse_reader(reader, format)?);
+        }
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        let gfa2 = format == GfaFormat::Gfa2;
+        let mut graph = self.graph_of(self.parse_reader(Cursor::new(&bytes), format)?)?;
+        let lines = self.read_raw_lines(Cursor::new(&bytes), gfa2)?;
+        graph.retain_lines(gfa2, lines);
+        Ok(graph)
+    }
+
+    /// The graph of a parsed file
+    fn graph_of(&self, parsed: ParsedGfa) -> Result<HashGraph, ParseError> {
+        let mut graph = HashGraph::default();
+        let mut file = parsed.into_file_type();
+        if !self.keep_names {
+            drop_names(&mut file);
+        }
+        // a path step that can't be parsed is left out only if every
+        // error is ignored
+        let skip_invalid_steps = matches!(self.tolerance, ParserTolerance::IgnoreAll);
+        let created = self.threads.install(|| {
+            graph.create_graph_reporting(file, skip_invalid_steps, &self.build_progress)
+        });
+        created.map_err(|why| ParseError::ConversionGFAToGraph(why.to_string()))
+    }
+
+    /// The lines of the file with their line terminator, each with the
+    /// record it builds, for the fidelity mode
+    fn read_raw_lines<R: BufRead>(
+        &self,
+        mut reader: R,
+        gfa2: bool,
+    ) -> Result<Vec<(bstr::BString, RawRecord)>, ParseError> {
+        let (gfa_parser, gfa2_parser) = (self.gfa_parser(), self.gfa2_parser());
+        let mut lines = vec![];
+        loop {
+            let mut bytes = vec![];
+            if reader.read_until(b'\n', &mut bytes)? == 0 {
+                break;
+            }
+            let record = if gfa2 {
+                match gfa2_parser.parse_gfa_line(&bytes) {
+                    Ok(Line2::Segment(s)) => RawRecord::Segment(s.id.into()),
+                    Ok(Line2::Edge(e)) => {
+                        let Edge(left, right) = gfa2_edge(&e);
+                        RawRecord::Link(Edge::edge_handle(left, right))
+                    }
+                    Ok(Line2::GroupO(o)) => RawRecord::Path(o.id),
+                    Ok(Line2::GroupU(u)) => {
+                        RawRecord::Refers(u.iter_ids().map(NodeId::from).collect())
+                    }
+                    Ok(Line2::Fragment(f)) => RawRecord::Refers(vec![f.sid.into()]),
+                    Ok(Line2::Gap(g)) => RawRecord::Refers(vec![g.sid1.into(), g.sid2.into()]),
+                    _ => RawRecord::Other,
+                }
+            } else {
+                match gfa_parser.parse_gfa_line(&bytes) {
+                    Ok(Line1::Segment(s)) => RawRecord::Segment(s.name.into()),
+                    Ok(Line1::Link(l)) => RawRecord::Link(Edge::edge_handle(
+                        Handle::new(l.from_segment, l.from_orient),
+                        Handle::new(l.to_segment, l.to_orient),
+                    )),
+                    Ok(Line1::Path(p)) => RawRecord::Path(p.path_name),
+                    Ok(Line1::Containment(c)) => {
+                        RawRecord::Refers(vec![c.container_name.into(), c.contained_name.into()])
+                    }
+                    _ => RawRecord::Other,
+                }
+            };
+            lines.push((bytes.into(), record));
+        }
+        Ok(lines)
+    }
+
+    /// Function that parses a GFA or GFA2 file and creates the
+    /// corresponding HashGraph, like
+    /// [`parse_file_to_graph`](#method.parse_file_to_graph), returning
+    /// also the path steps with a suspicious orientation when
+    /// [`warn_orientation_anomalies`](#method.warn_orientation_anomalies)
+    /// is set (and no warning otherwise)
+    /// # Example
+    /// ```ignore
+    /// let (graph, warnings) = Parser::new()
+    ///     .warn_orientation_anomalies(true)
+    ///     .parse_file_to_graph_with_warnings("./tests/gfa1_files/lil.gfa")?;
+    /// warnings.iter().for_each(|w| println!("{}", w));
+    /// ```
+    pub fn parse_file_to_graph_with_warnings<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<(HashGraph, Vec<PathOrientationAnomaly>), ParseError> {
+        let graph = self.parse_file_to_graph(path)?;
+        let warnings = if self.warn_orientation_anomalies {
+            graph.path_orientation_anomalies()
+        } else {
+            vec![]
+        };
+        Ok((graph, warnings))
+    }
+
+    /// Function that reads a GFA or GFA2 file line by line, adding each
+    /// record to a [`GraphBuilder`](../hashgraph/builder/struct.GraphBuilder.html)
+    /// as soon as it's parsed.\
+    /// Edges and paths can come before the segments they reference, the
+    /// returned builder has to be finished with
+    /// [`finish_strict`](../hashgraph/builder/struct.GraphBuilder.html#method.finish_strict)
+    /// to fail on the references to segments never declared, or with
+    /// [`finish`](../hashgraph/builder/struct.GraphBuilder.html#method.finish)
+    /// to drop them.\
+    /// Unlike [`parse_file`](#method.parse_file), a line that can't be
+    /// parsed within the error tolerance is returned as an error
+    /// # Example
+    /// ```ignore
+    /// let builder = Parser::new().parse_file_incremental("./tests/gfa2_files/spec_q7.gfa2")?;
+    /// match builder.finish_strict() {
+    ///     Ok(graph) => println!("{}", graph),
+    ///     Err(unresolved) => unresolved.iter().for_each(|u| println!("{}", u)),
+    /// }
+    /// ```
+    pub fn parse_file_incremental<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<GraphBuilder, ParseError> {
+        let mut builder = GraphBuilder::new();
+        self.stream_file(path, |line| match line {
+            StreamedLine::Gfa1(line) => self.insert_gfa_line(&mut builder, line),
+            StreamedLine::Gfa2(line) => self.insert_gfa2_line(&mut builder, line),
+        })?;
+        Ok(builder)
+    }
+
+    /// Parse a GFA or GFA2 file a line at a time, passing each record to
+    /// ```insert``` and failing on the first error outside the error
+    /// tolerance, either of the parser or of ```insert```
+    pub(crate) fn stream_file<P, F>(&self, path: P, mut insert: F) -> ParserResult<()>
+    where
+        P: AsRef<std::path::Path>,
+        F: FnMut(StreamedLine) -> ParserResult<()>,
+    {
+        self.stream_file_at(path, |_, _, line| insert(line))
+    }
+
+    /// Like ```stream_file```, passing also the position of each line in
+    /// the file and its bytes, without the line terminator
+    pub(crate) fn stream_file_at<P, F>(&self, path: P, mut insert: F) -> ParserResult<()>
+    where
+        P: AsRef<std::path::Path>,
+        F: FnMut(u64, &[u8], StreamedLine) -> ParserResult<()>,
+    {
+        use bstr::ByteSlice;
+
+        let gfa2 = is_gfa2_file(path.as_ref())?;
+        let (gfa_parser, gfa2_parser) = (self.gfa_parser(), self.gfa2_parser());
+
+        let mut reader = open_file(path.as_ref())?;
+        let counter = ParseCounter::new(&self.progress);
+        let mut buffer = vec![];
+        let mut offset = 0;
+        let mut number = 0;
+        loop {
+            buffer.clear();
+            let read = reader.read_until(b'\n', &mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            number += 1;
+            let start = offset;
+            offset += read as u64;
+            let line = buffer
+                .strip_suffix(b"\n")
+                .map_or(&buffer[..], |l| l.strip_suffix(b"\r").unwrap_or(l));
+            // the record types left out by the filters aren't parsed
+            let included = if gfa2 {
+                gfa2_parser.includes_line(line)
+            } else {
+                gfa_parser.includes_line(line)
+            };
+            if line.trim().is_empty() || !included {
+                counter.line(line.len(), LineOutcome::Skipped);
+                continue;
+            }
+            let result = if gfa2 {
+                gfa2_parser.parse_gfa_line(line).and_then(|mut l| {
+                    if let (Line2::Segment(s), false) = (&mut l, self.keep_names) {
+                        s.original_name.clear();
+                    }
+                    insert(start, line, StreamedLine::Gfa2(l))
+                })
+            } else {
+                gfa_parser.parse_gfa_line(line).and_then(|mut l| {
+                    if let (Line1::Segment(s), false) = (&mut l, self.keep_names) {
+                        s.original_name.clear();
+                    }
+                    insert(start, line, StreamedLine::Gfa1(l))
+                })
+            };
+            match result {
+                Ok(()) => counter.line(line.len(), LineOutcome::Record(line.trim_start()[0])),
+                Err(err) if err.can_safely_continue(&self.tolerance) => {
+                    counter.line(line.len(), LineOutcome::Ignored)
+                }
+                Err(err) => return Err(err.at_line(number)),
+            }
+        }
+        counter.finish();
+        Ok(())
+    }
+
+    /// The steps of a path, failing with ```InvalidPathStep``` on a step
+    /// that can't be parsed, unless the error tolerance is
+    /// ```IgnoreAll```, when the step is left out
+    fn path_steps<I>(&self, name: &[u8], steps: I) -> ParserResult<Vec<Handle>>
+    where
+        I: Iterator<Item = Result<(u64, Orientation), PathParseError>>,
+    {
+        use bstr::ByteSlice;
+        let mut handles = vec![];
+        for step in steps {
+            match step {
+                Ok((id, orient)) => handles.push(Handle::new(id, orient)),
+                Err(_) if matches!(self.tolerance, ParserTolerance::IgnoreAll) => (),
+                Err(why) => {
+                    let name = name.to_str_lossy().into_owned();
+                    let step = why.token().to_string();
+                    return Err(GraphError::InvalidPathStep(name, step).into());
+                }
+            }
+        }
+        Ok(handles)
+    }
+
+    fn insert_gfa_line(&self, builder: &mut GraphBuilder, line: Line1) -> ParserResult<()> {
+        match line {
+            Line1::Segment(s) if self.segments => {
+                let placeholder = gfa1_placeholder(&s.sequence, &s.optional);
+                builder.add_named_segment(s.name.into(), s.original_name, &s.sequence)?;
+                builder.placeholder_segment(s.name.into(), placeholder);
+                builder.tag_segment(s.name.into(), s.optional)
+            }
+            Line1::Link(l) if self.links => {
+                builder.add_tagged_edge(link_edge(&l), link_overlap(&l), l.optional)
+            }
+            Line1::Path(p) if self.paths => {
+                let steps = self.path_steps(&p.path_name, p.iter_checked())?;
+                builder.add_path(&p.path_name, steps);
+                builder.tag_path(&p.path_name, p.optional)
+            }
+            Line1::Header(h) => builder.add_header(h),
+            Line1::Containment(c) => builder.add_containment(containment_record(&c)),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn insert_gfa2_line(&self, builder: &mut GraphBuilder, line: Line2) -> ParserResult<()> {
+        match line {
+            Line2::Segment(s) if self.segments => {
+                let placeholder = gfa2_placeholder(&s.sequence, s.slen);
+                let sequence = Segment2::node_sequence(s.sequence, s.slen);
+                builder.add_named_segment(s.id.into(), s.original_name, &sequence)?;
+                builder.placeholder_segment(s.id.into(), placeholder);
+                builder.tag_segment(s.id.into(), s.optional)
+            }
+            // as create_graph, the overlaps of the GFA2 edges aren't kept
+            Line2::Edge(e) if self.links => builder.add_tagged_edge(gfa2_edge(&e), 0, e.optional),
+            Line2::GroupO(o) if self.paths => {
+                let steps = self.path_steps(&o.id, o.iter_checked())?;
+                builder.add_path(&o.id, steps);
+                builder.tag_path(&o.id, o.optional)
             }
+            Line2::Header(h) => builder.add_header(gfa2_header(&h)),
+            Line2::GroupU(u) => builder.add_subset(u.id.clone(), u.iter_ids().collect()),
+            _ => (),
         }
-        _ => Err(ParseError::ExtensionError()),
+        Ok(())
     }
 }
 
+/// Function that parses the lines read from a reader, in the given
+/// format, and creates the corresponding
+/// [`HashGraph`](../hashgraph/graph/struct.HashGraph.html), using a
+/// [`Parser`](struct.Parser.html) with the default options
+/// # Example
+/// ```ignore
+/// let bytes = b"S\t1\tACGT\nS\t2\tA\nL\t1\t+\t2\t+\t0M\n";
+/// let graph = parse_reader_to_graph(&bytes[..], GfaFormat::Gfa1)?;
+/// ```
+pub fn parse_reader_to_graph<R: BufRead + Send>(
+    reader: R,
+    format: GfaFormat,
+) -> Result<HashGraph, ParseError> {
+    Parser::new().parse_reader_to_graph(reader, format)
+}
+
+/// Function that given a GFA or GFA2 file as input, creates the
+/// corresponding
+/// [`HashGraph`](../hashgraph/graph/struct.HashGraph.html),
+/// using a [`Parser`](struct.Parser.html) with the default options.
+/// # Example
+/// ```ignore
+/// match parse_file_to_graph("./tests/gfa2_files/spec_q7.gfa2") {
+///     Ok(g) => println!("{}", g),
+///     Err(why) => println!("Error {}", why),
+/// }
+/// ```
+pub fn parse_file_to_graph<P: AsRef<std::path::Path>>(path: P) -> Result<HashGraph, ParseError> {
+    Parser::new().parse_file_to_graph(path)
+}
+
+/// Function that given a GFA or GFA2 file as input, creates the
+/// corresponding
+/// [`PackedGraph`](../packed/graph/struct.PackedGraph.html), without
+/// the paths./// The file is parsed to an HashGraph first, so the memory needed while
+/// parsing is the one of an HashGraph
+/// # Example
+/// ```ignore
+/// let packed = parse_file_to_packed_graph("./tests/big_files/test.gfa")?;
+/// println!("{} nodes", packed.node_count());
+/// ```
+pub fn parse_file_to_packed_graph<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<PackedGraph, ParseError> {
+    let graph = parse_file_to_graph(path)?;
+    Ok(PackedGraph::from_hashgraph(&graph))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::hashgraph::graph::FileType;
     use crate::mutablehandlegraph::SubtractiveHandleGraph;
     use bstr::io::BufReadExt;
     use std::fs::File;
@@ -70,6 +908,36 @@ mod test {
         }
     }
 
+    #[test]
+    fn unified_parser_picks_format() {
+        use crate::handlegraph::AllHandles;
+
+        let parser = Parser::new();
+        match parser.parse_file("./tests/gfa1_files/lil.gfa") {
+            Ok(ParsedGfa::GFA(gfa)) => assert_eq!(gfa.segments.len(), 15),
+            _ => panic!("lil.gfa should be parsed as GFA"),
+        }
+        match parser.parse_file("./tests/gfa2_files/spec_q7.gfa2") {
+            Ok(ParsedGfa::GFA2(gfa2)) => assert_eq!(gfa2.segments.len(), 3),
+            _ => panic!("spec_q7.gfa2 should be parsed as GFA2"),
+        }
+        assert!(matches!(
+            parser.parse_file("./tests/gfa1_files/lil.txt"),
+            Err(ParseError::ExtensionError())
+        ));
+        assert!(matches!(
+            parse_file_to_graph("./tests/gfa1_files/lil"),
+            Err(ParseError::ExtensionError())
+        ));
+
+        let graph = parser
+            .parse_file_to_graph("./tests/gfa1_files/lil.gfa")
+            .unwrap();
+        let wrapped = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+        assert_eq!(graph.node_count(), wrapped.node_count());
+        assert_eq!(graph.to_string(), wrapped.to_string());
+    }
+
     #[test]
     fn ditto_test() {
         match parse_file_to_graph("./tests/gfa2_files/irl.gfa2") {
@@ -119,15 +987,11 @@ mod test {
             "./tests/big_files/CHM13v1Y-GRCh38-HPP58-0.12.gfa",
             "./tests/big_files/GRCh38-20-0.10b.gfa",
         ];
-        for i in 0..3 {
+        for file in FILES.iter() {
             let start = Instant::now();
             let parser = GFAParser::default();
-            let gfa = parser.parse_file(FILES[i].to_string()).unwrap();
-            println!(
-                "Create GFAObject from {}: {:?}",
-                FILES[i].to_string(),
-                start.elapsed()
-            );
+            let gfa = parser.parse_file(file).unwrap();
+            println!("Create GFAObject from {}: {:?}", file, start.elapsed());
             println!(
                 "Nodes: {}\tEdges: {}\tPaths: {}",
                 gfa.segments.len(),
@@ -150,15 +1014,11 @@ mod test {
             "./tests/big_files/CHM13v1Y-GRCh38-HPP58-0.12.gfa2",
             "./tests/big_files/GRCh38-20-0.10b.gfa2",
         ];
-        for i in 0..3 {
+        for file in FILES2.iter() {
             let start = Instant::now();
             let parser = GFA2Parser::default();
-            let gfa = parser.parse_file(FILES2[i].to_string()).unwrap();
-            println!(
-                "Create GFAObject from {}: {:?}",
-                FILES2[i].to_string(),
-                start.elapsed()
-            );
+            let gfa = parser.parse_file(file).unwrap();
+            println!("Create GFAObject from {}: {:?}", file, start.elapsed());
             println!(
                 "Nodes: {}\tEdges: {}\tPaths: {}",
                 gfa.segments.len(),
@@ -196,14 +1056,14 @@ mod test {
             "./tests/big_files/CHM13v1Y-GRCh38-HPP58-0.12.gfa2",
             "./tests/big_files/GRCh38-20-0.10b.gfa2",
         ];
-        for i in 0..6 {
+        for file in FILES.iter() {
             let start = Instant::now();
-            let lines = BufReader::new(File::open(FILES[i].to_string()).unwrap()).byte_lines();
+            let lines = BufReader::new(File::open(file).unwrap()).byte_lines();
             let mut count = 0;
             lines.for_each(|_l| count += 1);
             println!(
                 "Read file {} (has {} lines): {:?}",
-                FILES[i].to_string(),
+                file,
                 count,
                 start.elapsed()
             );
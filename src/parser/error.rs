@@ -20,19 +20,14 @@ pub type ParserResult<T> = Result<T, ParseError>;
 ///     Pedantic,
 /// }
 /// ```
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum ParserTolerance {
     IgnoreAll,
+    #[default]
     Safe,
     Pedantic,
 }
 
-impl Default for ParserTolerance {
-    fn default() -> Self {
-        Self::Safe
-    }
-}
-
 /// Type encapsulating different kinds of GFA fields parsing errors
 /// ```ignore
 /// pub enum ParseFieldError {
@@ -41,11 +36,12 @@ impl Default for ParserTolerance {
 ///     ParseFromStringError,
 ///     OrientationError,
 ///     InvalidField(&'static str),
+///     IdOverflow(String),
 ///     MissingFields,
 ///     Unknown,
 /// }
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParseFieldError {
     /// A segment ID couldn't be parsed as a u64. Can only happen
     /// when parsing into a GFA<u64, T>.
@@ -60,6 +56,9 @@ pub enum ParseFieldError {
     /// A required field was incorrectly formatted. Includes the field
     /// name as defined by the GFA1 spec.
     InvalidField(&'static str),
+    /// A segment ID is too big to be used as a node id. Includes
+    /// the ID as found in the file.
+    IdOverflow(String),
     MissingFields,
     Unknown,
 }
@@ -109,6 +108,9 @@ impl fmt::Display for ParseFieldError {
             PFE::InvalidField(field) => {
                 write!(f, "Failed to parse field `{}`", field)
             }
+            PFE::IdOverflow(id) => {
+                write!(f, "The segment ID {} exceeds the maximum node id", id)
+            }
             PFE::MissingFields => write!(f, "Line is missing required fields"),
             PFE::Unknown => write!(f, "Unknown error when parsing a field"),
         }
@@ -117,6 +119,24 @@ impl fmt::Display for ParseFieldError {
 
 impl error::Error for ParseFieldError {}
 
+impl ParseFieldError {
+    /// The stable code of the error, see the
+    /// [`report`](../../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        use ParseFieldError as PFE;
+        match self {
+            PFE::UintIdError => "F001",
+            PFE::Utf8Error => "F002",
+            PFE::ParseFromStringError => "F003",
+            PFE::OrientationError => "F004",
+            PFE::InvalidField(_) => "F005",
+            PFE::IdOverflow(_) => "F006",
+            PFE::MissingFields => "F007",
+            PFE::Unknown => "F008",
+        }
+    }
+}
+
 /// Type encapsulating different kinds of GFA parsing errors
 /// ```ignore
 /// pub enum ParseError {
@@ -127,7 +147,11 @@ impl error::Error for ParseFieldError {}
 ///     IOError(std::io::Error),
 ///     ExtensionError(),
 ///     ConversionGFAToGraph(String),
+///     TruncatedLine(String),
+///     InvalidArchive(String),
+///     ConflictingSegment(String, String, String),
 ///     Unknown,
+///     InvalidLineAt(usize, ParseFieldError, String),
 /// }
 /// ```
 #[derive(Debug)]
@@ -147,7 +171,19 @@ pub enum ParseError {
     IOError(std::io::Error),
     ExtensionError(),
     ConversionGFAToGraph(String),
+    /// The file ended in the middle of a line. Includes the partial line.
+    TruncatedLine(String),
+    /// A graph archive has a wrong header or it's corrupted.
+    /// Includes what was wrong.
+    InvalidArchive(String),
+    /// A segment declared again, when parsing more files, with another
+    /// sequence. Includes the segment, the file declaring it again and
+    /// the file that declared it first.
+    ConflictingSegment(String, String, String),
     Unknown,
+    /// A line of a file couldn't be parsed. Includes the number of the
+    /// line (from 1), a variant describing the error and the line.
+    InvalidLineAt(usize, ParseFieldError, String),
 }
 
 impl fmt::Display for ParseError {
@@ -165,7 +201,21 @@ impl fmt::Display for ParseError {
             PE::IOError(err) => write!(f, "IO error: {}", err),
             PE::ExtensionError() => write!(f, "Extension not correct!"),
             PE::ConversionGFAToGraph(why) => write!(f, "{}", why),
+            PE::TruncatedLine(line) => {
+                write!(f, "The file ended in the middle of the line {}", line)
+            }
+            PE::InvalidArchive(why) => write!(f, "Invalid graph archive: {}", why),
+            PE::ConflictingSegment(segment, file, first) => write!(
+                f,
+                "Segment {} in {} has a different sequence than in {}",
+                segment, file, first
+            ),
             PE::Unknown => write!(f, "Unknown error when parsing a line"),
+            PE::InvalidLineAt(number, field_err, line) => write!(
+                f,
+                "Failed to parse line {} ({}), error: {}",
+                number, line, field_err
+            ),
         }
     }
 }
@@ -200,11 +250,41 @@ impl ParseError {
         Self::InvalidLine(error, dest)
     }
 
+    /// The error of the line ```number``` of a file, with the number
+    /// when it's an ```InvalidLine```
+    pub(crate) fn at_line(self, number: usize) -> Self {
+        match self {
+            Self::InvalidLine(error, line) => Self::InvalidLineAt(number, error, line),
+            err => err,
+        }
+    }
+
+    /// The stable code of the error, see the
+    /// [`report`](../../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        use ParseError as PE;
+        match self {
+            PE::UnknownLineType => "P001",
+            PE::EmptyLine => "P002",
+            PE::InvalidLine(_, _) => "P003",
+            PE::InvalidField(_) => "P004",
+            PE::IOError(_) => "P005",
+            PE::ExtensionError() => "P006",
+            PE::ConversionGFAToGraph(_) => "P007",
+            PE::TruncatedLine(_) => "P008",
+            PE::InvalidArchive(_) => "P009",
+            PE::ConflictingSegment(_, _, _) => "P010",
+            PE::Unknown => "P011",
+            PE::InvalidLineAt(_, _, _) => "P012",
+        }
+    }
+
     #[inline]
     pub fn can_safely_continue(&self, tol: &ParserTolerance) -> bool {
         use ParserTolerance as Tol;
         match tol {
-            Tol::IgnoreAll => true,
+            // two sequences for a segment can't be ignored
+            Tol::IgnoreAll => !matches!(self, ParseError::ConflictingSegment(..)),
             Tol::Safe => matches!(self, ParseError::EmptyLine | ParseError::UnknownLineType),
             Tol::Pedantic => false,
         }
@@ -0,0 +1,60 @@
+/// This file provides the handling of the lines with a nonstandard
+/// record type, shared by the GFA and GFA2 parsers
+use crate::gfa::extension::{CustomRecord, UnknownRecord};
+use crate::parser::error::*;
+
+use bstr::{BString, ByteSlice};
+
+/// Parser of a custom record type, it gets the whole line and returns
+/// the payload of the record, see
+/// [`CustomRecord::new`](../../gfa/extension/struct.CustomRecord.html#method.new)
+pub type CustomLineParser = fn(&[u8]) -> Result<CustomRecord, ParseFieldError>;
+
+/// A line with a nonstandard record type
+pub(crate) enum ExtensionLine {
+    Unknown(UnknownRecord),
+    Custom(CustomRecord),
+}
+
+/// How the lines with a nonstandard record type are handled
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Extensions {
+    pub(crate) preserve_unknown: bool,
+    pub(crate) custom_lines: Vec<(u8, CustomLineParser)>,
+}
+
+impl Extensions {
+    /// Parse a line the standard parser didn't recognize: a line of a
+    /// registered record type always goes to its parser, any other line
+    /// is kept as unknown if required and the tolerance isn't pedantic,
+    /// otherwise it's still an ```UnknownLineType```
+    pub(crate) fn parse_line(
+        &self,
+        bytes: &[u8],
+        line_number: usize,
+        tolerance: &ParserTolerance,
+    ) -> ParserResult<ExtensionLine> {
+        let line = bytes.trim();
+        let prefix = line
+            .split_str(b"\t")
+            .next()
+            .filter(|p| p.len() == 1)
+            .map(|p| p[0]);
+        if let Some((prefix, parser)) = self.custom_lines.iter().find(|(p, _)| Some(*p) == prefix) {
+            let mut record = parser(line).map_err(|e| ParseError::invalid_line(e, bytes))?;
+            record.prefix = *prefix;
+            record.raw = BString::from(bytes);
+            record.line_number = line_number;
+            return Ok(ExtensionLine::Custom(record));
+        }
+        match tolerance {
+            ParserTolerance::Pedantic => Err(ParseError::UnknownLineType),
+            _ if !self.preserve_unknown || line.is_empty() => Err(ParseError::UnknownLineType),
+            _ => Ok(ExtensionLine::Unknown(UnknownRecord {
+                prefix: line[0],
+                raw: BString::from(bytes),
+                line_number,
+            })),
+        }
+    }
+}
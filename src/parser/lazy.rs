@@ -0,0 +1,151 @@
+/// This file provides the parsing of a graph whose sequences are left in
+/// the file, see [`LazySequenceStore`](../../hashgraph/lazy/struct.LazySequenceStore.html)
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::gfa::{
+    gfa1::Line as Line1,
+    gfa2::{Line as Line2, Segment as Segment2},
+};
+use crate::hashgraph::{
+    attach::{gfa1_placeholder, gfa2_placeholder},
+    GraphBuilder, HashGraph, LazySequence, LazySequenceStore,
+};
+use crate::parser::{error::*, is_gzip_file, Parser, StreamedLine};
+
+/// The position of the field ```field``` (0-based) of a line, if its
+/// bytes are ```expected```
+fn field_offset(line: &[u8], field: usize, expected: &[u8]) -> Option<u64> {
+    let mut start = 0;
+    for (ix, bytes) in line.split(|b| *b == b'\t').enumerate() {
+        if ix == field {
+            return if bytes == expected {
+                Some(start as u64)
+            } else {
+                None
+            };
+        }
+        start += bytes.len() + 1;
+    }
+    None
+}
+
+impl Parser {
+    /// Function that parses a GFA or GFA2 file into an HashGraph that
+    /// keeps only the topology in memory, see
+    /// [`parse_file_to_graph_lazy`](fn.parse_file_to_graph_lazy.html)
+    pub fn parse_file_to_graph_lazy<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<HashGraph, ParseError> {
+        // the sequences can't be read again from a compressed file
+        if is_gzip_file(path.as_ref())? {
+            return self.parse_file_to_graph(path);
+        }
+        let store = LazySequenceStore::open(path.as_ref())?;
+        self.parse_file_to_graph_lazy_with(path, store)
+    }
+
+    /// Like [`parse_file_to_graph_lazy`](#method.parse_file_to_graph_lazy),
+    /// reading the sequences from ```store```, as the bytes of the file
+    /// memory-mapped by the caller. The store must have the same bytes
+    /// of the file parsed
+    /// # Example
+    /// ```ignore
+    /// let store = LazySequenceStore::from_bytes(std::fs::read("graph.gfa")?);
+    /// let graph = Parser::new().parse_file_to_graph_lazy_with("graph.gfa", store)?;
+    /// ```
+    pub fn parse_file_to_graph_lazy_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        store: LazySequenceStore,
+    ) -> Result<HashGraph, ParseError> {
+        let store = Arc::new(store);
+        let mut builder = GraphBuilder::new();
+        self.stream_file_at(path, |offset, bytes, line| match line {
+            StreamedLine::Gfa1(Line1::Segment(s)) if self.segments => {
+                let placeholder = gfa1_placeholder(&s.sequence, &s.optional);
+                match field_offset(bytes, 2, &s.sequence) {
+                    Some(start) => builder.add_lazy_segment(
+                        s.name.into(),
+                        s.original_name,
+                        LazySequence::new(store.clone(), offset + start, s.sequence.len()),
+                    )?,
+                    None => {
+                        builder.add_named_segment(s.name.into(), s.original_name, &s.sequence)?
+                    }
+                }
+                builder.placeholder_segment(s.name.into(), placeholder);
+                builder.tag_segment(s.name.into(), s.optional);
+                Ok(())
+            }
+            StreamedLine::Gfa2(Line2::Segment(s)) if self.segments => {
+                let placeholder = gfa2_placeholder(&s.sequence, s.slen);
+                // the bases of a * sequence aren't in the file
+                let start = if s.sequence == "*" {
+                    None
+                } else {
+                    field_offset(bytes, 3, &s.sequence)
+                };
+                match start {
+                    Some(start) => builder.add_lazy_segment(
+                        s.id.into(),
+                        s.original_name,
+                        LazySequence::new(store.clone(), offset + start, s.sequence.len()),
+                    )?,
+                    None => {
+                        let sequence = Segment2::node_sequence(s.sequence, s.slen);
+                        builder.add_named_segment(s.id.into(), s.original_name, &sequence)?
+                    }
+                }
+                builder.placeholder_segment(s.id.into(), placeholder);
+                builder.tag_segment(s.id.into(), s.optional);
+                Ok(())
+            }
+            StreamedLine::Gfa1(line) => self.insert_gfa_line(&mut builder, line),
+            StreamedLine::Gfa2(line) => self.insert_gfa2_line(&mut builder, line),
+        })?;
+
+        let unresolved = builder.unresolved();
+        match self.tolerance {
+            _ if unresolved.is_empty() => (),
+            ParserTolerance::IgnoreAll => (),
+            _ => return Err(ParseError::ConversionGFAToGraph(unresolved[0].to_string())),
+        }
+        let (graph, _) = builder.finish();
+        Ok(graph)
+    }
+}
+
+/// Function that parses a GFA or GFA2 file (from its extension) into an
+/// [`HashGraph`](../../hashgraph/graph/struct.HashGraph.html) that keeps
+/// the sequences in the file: each node stores where its sequence is in
+/// the file, and its length, and the sequence is read from the file
+/// opened (read-only) by the graph the first time it's needed, as by
+/// ```sequence``` or ```sequence_iter```, then kept by the node until
+/// [`release_loaded_sequences`](../../hashgraph/graph/struct.HashGraph.html#method.release_loaded_sequences).\
+/// A file compressed with gzip is parsed by
+/// [`parse_file_to_graph`](../fn.parse_file_to_graph.html) instead, with
+/// the sequences in memory, as they can't be read again from it.\
+/// ```node_len``` and ```total_length``` never read the file, so the
+/// analyses of the topology (and of the lengths) don't read a sequence.\
+/// Changing the sequence of a node keeps the new one in memory, for that
+/// node only. The file must not change while the graph is used: a
+/// sequence that can't be read anymore panics in every query returning
+/// the bytes of a sequence (```sequence```, ```sequence_iter```,
+/// ```node_sequence```, the writers and the analyses of the sequences),
+/// while [`try_node_sequence`](../../hashgraph/graph/struct.HashGraph.html#method.try_node_sequence)
+/// and [`LazySequence::try_bytes`](../../hashgraph/lazy/struct.LazySequence.html#method.try_bytes)
+/// return the error.\
+/// The edges and the paths are added as by
+/// [`parse_file_incremental`](struct.Parser.html#method.parse_file_incremental),
+/// and a reference to a missing segment is an error unless the error
+/// tolerance is ```IgnoreAll```
+/// # Example
+/// ```ignore
+/// let graph = parse_file_to_graph_lazy("huge.gfa")?;
+/// println!("{} nodes, {} bases", graph.node_count(), graph.total_length());
+/// ```
+pub fn parse_file_to_graph_lazy<P: AsRef<Path>>(path: P) -> Result<HashGraph, ParseError> {
+    Parser::new().parse_file_to_graph_lazy(path)
+}
@@ -0,0 +1,252 @@
+/// This file provides the parsing of a graph split in more files
+use bstr::BString;
+use fnv::FnvHashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::gfa::{
+    gfa1::Line as Line1,
+    gfa2::{Line as Line2, Segment as Segment2},
+};
+use crate::handle::{Edge, Handle, NodeId};
+use crate::hashgraph::{graph::gfa2_edge, GraphBuilder, HashGraph};
+use crate::parser::{error::*, uncompressed_name, Parser, StreamedLine};
+
+/// How the files given to
+/// [`parse_files_to_graph`](fn.parse_files_to_graph.html) are combined
+/// ```ignore
+/// pub enum MultiFileMode {
+///     Union,
+///     BaseWithPathOverlays,
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultiFileMode {
+    /// Every file adds its segments, edges and paths. A segment can be in
+    /// more files only with the same sequence
+    Union,
+    /// The first file gives the segments and the edges, the others only
+    /// add paths on them
+    BaseWithPathOverlays,
+}
+
+/// A record of an overlay file that isn't a path, so it was ignored
+/// ```ignore
+/// pub struct OverlayWarning {
+///     pub file: String,
+///     pub record: String,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverlayWarning {
+    pub file: String,
+    /// The kind of the record, with its id when it has one
+    pub record: String,
+}
+
+impl OverlayWarning {
+    /// The stable code of the diagnostic, see the
+    /// [`report`](../../util/report/index.html) module
+    pub fn code(&self) -> &'static str {
+        "V005"
+    }
+}
+
+impl fmt::Display for OverlayWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: ignored the {}, an overlay file can only add paths",
+            self.file, self.record
+        )
+    }
+}
+
+/// The records used to build the graph, from both formats
+enum Record {
+    Segment(NodeId, BString),
+    Edge(Edge),
+    Path(BString, Vec<Handle>),
+    /// A header, or a record left out by the filters of the parser
+    Skipped,
+    /// A record never used to build the graph, described
+    Other(String),
+}
+
+impl Parser {
+    fn record_of(&self, line: StreamedLine) -> ParserResult<Record> {
+        Ok(match line {
+            StreamedLine::Gfa1(line) => match line {
+                Line1::Segment(s) if self.segments => Record::Segment(s.name.into(), s.sequence),
+                Line1::Link(l) if self.links => Record::Edge(Edge(
+                    Handle::new(l.from_segment, l.from_orient),
+                    Handle::new(l.to_segment, l.to_orient),
+                )),
+                Line1::Path(p) if self.paths => {
+                    let steps = self.path_steps(&p.path_name, p.iter_checked())?;
+                    Record::Path(p.path_name, steps)
+                }
+                Line1::Containment(_) => Record::Other("containment".to_string()),
+                _ => Record::Skipped,
+            },
+            StreamedLine::Gfa2(line) => match line {
+                Line2::Segment(s) if self.segments => {
+                    Record::Segment(s.id.into(), Segment2::node_sequence(s.sequence, s.slen))
+                }
+                Line2::Edge(e) if self.links => Record::Edge(gfa2_edge(&e)),
+                Line2::GroupO(o) if self.paths => {
+                    let steps = self.path_steps(&o.id, o.iter_checked())?;
+                    Record::Path(o.id, steps)
+                }
+                Line2::Fragment(_) => Record::Other("fragment".to_string()),
+                Line2::Gap(_) => Record::Other("gap".to_string()),
+                Line2::GroupU(_) => Record::Other("U-group".to_string()),
+                _ => Record::Skipped,
+            },
+        })
+    }
+
+    /// Function that parses more GFA or GFA2 files (each with its own
+    /// format) into a single
+    /// [`HashGraph`](../../hashgraph/graph/struct.HashGraph.html), like
+    /// [`parse_files_to_graph`](fn.parse_files_to_graph.html), returning
+    /// also the records ignored in the overlay files
+    /// # Example
+    /// ```ignore
+    /// let (graph, warnings) = Parser::new().parse_files_to_graph_with_warnings(
+    ///     &["base.gfa", "sample1.gfa"],
+    ///     MultiFileMode::BaseWithPathOverlays,
+    /// )?;
+    /// warnings.iter().for_each(|w| println!("{}", w));
+    /// ```
+    pub fn parse_files_to_graph_with_warnings<P: AsRef<Path>>(
+        &self,
+        paths: &[P],
+        mode: MultiFileMode,
+    ) -> Result<(HashGraph, Vec<OverlayWarning>), ParseError> {
+        let mut builder = GraphBuilder::new();
+        // the file that declared each segment first
+        let mut declared_in: FnvHashMap<NodeId, usize> = FnvHashMap::default();
+        let mut warnings = vec![];
+        let names: Vec<String> = paths
+            .iter()
+            .map(|p| p.as_ref().display().to_string())
+            .collect();
+
+        for (ix, path) in paths.iter().enumerate() {
+            let overlay = mode == MultiFileMode::BaseWithPathOverlays && ix > 0;
+            let stem = uncompressed_name(path.as_ref())
+                .file_stem()
+                .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+            self.stream_file(path, |line| {
+                match self.record_of(line)? {
+                    Record::Skipped => (),
+                    Record::Path(name, steps) => {
+                        let name = if builder.has_path(&name) {
+                            let renamed = BString::from(format!("{}#{}", stem, name));
+                            if builder.has_path(&renamed) {
+                                return Err(ParseError::ConversionGFAToGraph(format!(
+                                    "Path {} in {} can't be renamed, {} already exists",
+                                    name, names[ix], renamed
+                                )));
+                            }
+                            renamed
+                        } else {
+                            name
+                        };
+                        builder.add_path(&name, steps);
+                    }
+                    Record::Segment(id, _) if overlay => warnings.push(OverlayWarning {
+                        file: names[ix].clone(),
+                        record: format!("segment {}", id),
+                    }),
+                    Record::Edge(Edge(l, r)) if overlay => warnings.push(OverlayWarning {
+                        file: names[ix].clone(),
+                        record: format!(
+                            "edge {}{} {}{}",
+                            l.id(),
+                            if l.is_reverse() { "-" } else { "+" },
+                            r.id(),
+                            if r.is_reverse() { "-" } else { "+" }
+                        ),
+                    }),
+                    Record::Other(record) if overlay => warnings.push(OverlayWarning {
+                        file: names[ix].clone(),
+                        record,
+                    }),
+                    Record::Segment(id, sequence) => match builder.segment_sequence(id) {
+                        Some(known) if known == sequence.as_slice() => (),
+                        Some(_) => {
+                            return Err(ParseError::ConflictingSegment(
+                                id.to_string(),
+                                names[ix].clone(),
+                                names[declared_in[&id]].clone(),
+                            ))
+                        }
+                        None => {
+                            builder.add_segment(id, &sequence)?;
+                            declared_in.insert(id, ix);
+                        }
+                    },
+                    Record::Edge(edge) => builder.add_edge(edge),
+                    Record::Other(_) => (),
+                }
+                Ok(())
+            })?;
+        }
+
+        let unresolved = builder.unresolved();
+        match self.tolerance {
+            _ if unresolved.is_empty() => (),
+            ParserTolerance::IgnoreAll => (),
+            _ => return Err(ParseError::ConversionGFAToGraph(unresolved[0].to_string())),
+        }
+        let (graph, _) = builder.finish();
+        Ok((graph, warnings))
+    }
+
+    /// Function that parses more GFA or GFA2 files into a single
+    /// HashGraph, see [`parse_files_to_graph`](fn.parse_files_to_graph.html)
+    pub fn parse_files_to_graph<P: AsRef<Path>>(
+        &self,
+        paths: &[P],
+        mode: MultiFileMode,
+    ) -> Result<HashGraph, ParseError> {
+        let (graph, _) = self.parse_files_to_graph_with_warnings(paths, mode)?;
+        Ok(graph)
+    }
+}
+
+/// Function that parses more GFA or GFA2 files (each with its own
+/// format, from its extension) into a single
+/// [`HashGraph`](../../hashgraph/graph/struct.HashGraph.html), streaming
+/// the lines of each file into the same
+/// [`GraphBuilder`](../../hashgraph/builder/struct.GraphBuilder.html),
+/// so the records of a file can reference the segments of the others:
+/// * ```Union``` adds the records of every file. A segment declared in
+///   more files must have the same sequence, and it's added once; with
+///   another sequence parsing fails with ```ConflictingSegment```, naming
+///   the segment and both files. The same edge in more files is added once
+/// * ```BaseWithPathOverlays``` takes the segments and the edges only
+///   from the first file, the other files add their paths; their other
+///   records are ignored, and returned by
+///   [`parse_files_to_graph_with_warnings`](struct.Parser.html#method.parse_files_to_graph_with_warnings)
+///
+/// In both modes a path whose name is already taken by a previous path
+/// is renamed as ```<file stem>#<name>```, where the file stem is the
+/// name of its file without the extension (```sample1``` for
+/// ```data/sample1.gfa```).\
+/// An edge or a path referencing a segment that isn't in any file is an
+/// error, unless the error tolerance is ```IgnoreAll```, when it's
+/// dropped as in
+/// [`finish`](../../hashgraph/builder/struct.GraphBuilder.html#method.finish)
+/// # Example
+/// ```ignore
+/// let graph = parse_files_to_graph(&["chr1.gfa", "chr2.gfa"], MultiFileMode::Union)?;
+/// ```
+pub fn parse_files_to_graph<P: AsRef<Path>>(
+    paths: &[P],
+    mode: MultiFileMode,
+) -> Result<HashGraph, ParseError> {
+    Parser::new().parse_files_to_graph(paths, mode)
+}
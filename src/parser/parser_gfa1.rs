@@ -1,15 +1,23 @@
 /// This file provides the function to parse all the fields of a GFA file
-use crate::gfa::{gfa1::*, orientation::Orientation, segment_id::*};
+use crate::gfa::{
+    gfa1::*,
+    optional::{parse_optional, OptField},
+    orientation::Orientation,
+    segment_id::*,
+};
 use crate::parser::error::*;
+use crate::parser::extension::*;
+use crate::parser::progress::{LineOutcome, ParseCounter, ParseProgress, ParseStats, ProgressHook};
+use crate::parser::validators;
+use crate::parser::FirstFailure;
+use crate::util::threads::ThreadConfig;
 
 use bstr::{BStr, BString, ByteSlice};
-use lazy_static::lazy_static;
 use rayon::iter::{ParallelBridge, ParallelIterator};
-use regex::bytes::Regex;
 use std::sync::Mutex;
 
 /// Builder struct for GFAParsers
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone)]
 pub struct ParserBuilder {
     pub headers: bool,
     pub segments: bool,
@@ -17,6 +25,11 @@ pub struct ParserBuilder {
     pub containments: bool,
     pub paths: bool,
     pub tolerance: ParserTolerance,
+    pub preserve_unknown: bool,
+    pub keep_tags: bool,
+    pub custom_lines: Vec<(u8, CustomLineParser)>,
+    pub threads: ThreadConfig,
+    pub progress: ProgressHook<ParseProgress>,
 }
 
 impl ParserBuilder {
@@ -29,6 +42,11 @@ impl ParserBuilder {
             containments: false,
             paths: false,
             tolerance: Default::default(),
+            preserve_unknown: false,
+            keep_tags: true,
+            custom_lines: vec![],
+            threads: Default::default(),
+            progress: Default::default(),
         }
     }
 
@@ -41,6 +59,11 @@ impl ParserBuilder {
             containments: true,
             paths: true,
             tolerance: Default::default(),
+            preserve_unknown: false,
+            keep_tags: true,
+            custom_lines: vec![],
+            threads: Default::default(),
+            progress: Default::default(),
         }
     }
 
@@ -79,6 +102,67 @@ impl ParserBuilder {
         self
     }
 
+    /// Keep the lines with an unknown record type verbatim in the
+    /// ```unknown``` field of the parsed object, instead of dropping them.
+    /// The lines are still rejected with a pedantic tolerance
+    pub fn preserve_unknown_lines(&mut self, preserve: bool) -> &mut Self {
+        self.preserve_unknown = preserve;
+        self
+    }
+
+    /// Keep the optional fields of the lines in their ```optional```
+    /// field (the default), or drop them while parsing to save the
+    /// memory they take on files with a lot of tags
+    pub fn keep_tags(&mut self, keep: bool) -> &mut Self {
+        self.keep_tags = keep;
+        self
+    }
+
+    /// Register a parser for the lines starting with ```prefix```, that
+    /// are kept in the ```custom``` field of the parsed object, whatever
+    /// the tolerance. The standard record types can't be registered
+    /// # Example
+    /// ```ignore
+    /// fn parse_a(line: &[u8]) -> Result<CustomRecord, ParseFieldError> {
+    ///     let fields = line.split_str(b"\t").skip(1).map(BString::from).collect();
+    ///     Ok(CustomRecord::new(fields))
+    /// }
+    /// let mut builder = ParserBuilder::all();
+    /// builder.custom_line(b'A', parse_a);
+    /// ```
+    pub fn custom_line(&mut self, prefix: u8, parser: CustomLineParser) -> &mut Self {
+        self.custom_lines.retain(|(p, _)| *p != prefix);
+        self.custom_lines.push((prefix, parser));
+        self
+    }
+
+    /// Number of threads used to parse a file, see
+    /// [`ThreadConfig`](../../util/threads/struct.ThreadConfig.html)
+    pub fn threads(&mut self, threads: ThreadConfig) -> &mut Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Call ```callback``` with the progress of the parsing, every
+    /// [`progress_every`](#method.progress_every) lines and at the end,
+    /// see [`ProgressHook`](../progress/struct.ProgressHook.html)
+    /// # Example
+    /// ```ignore
+    /// let mut builder = ParserBuilder::all();
+    /// builder.on_progress(Box::new(|p| eprintln!("{} lines", p.lines_read)));
+    /// ```
+    pub fn on_progress(&mut self, callback: Box<dyn Fn(ParseProgress) + Send + Sync>) -> &mut Self {
+        self.progress.set_callback(callback.into());
+        self
+    }
+
+    /// Number of lines between two calls of the
+    /// [`on_progress`](#method.on_progress) callback
+    pub fn progress_every(&mut self, lines: usize) -> &mut Self {
+        self.progress.set_every(lines);
+        self
+    }
+
     pub fn build(self) -> GFAParser {
         GFAParser {
             headers: self.headers,
@@ -87,6 +171,13 @@ impl ParserBuilder {
             containments: self.containments,
             paths: self.paths,
             tolerance: self.tolerance,
+            keep_tags: self.keep_tags,
+            extensions: Extensions {
+                preserve_unknown: self.preserve_unknown,
+                custom_lines: self.custom_lines,
+            },
+            threads: self.threads,
+            progress: self.progress,
         }
     }
 }
@@ -95,9 +186,9 @@ impl ParserBuilder {
 /// # Examples
 /// ```ignore
 /// // create a parser
-/// let parser: GFAParser<bstr::BString, ()> = GFAParser::new();
+/// let parser: GFAParser = GFAParser::new();
 /// // create a gfa object to store the result of the parsing
-/// let gfa: GFA<BString, ()> = parser.parse_file(&"./test/gfa1_files/lil.gfa"). unwrap();
+/// let gfa: GFA = parser.parse_file("./tests/gfa1_files/lil.gfa").unwrap();
 /// ```
 #[derive(Clone)]
 pub struct GFAParser {
@@ -107,6 +198,10 @@ pub struct GFAParser {
     containments: bool,
     paths: bool,
     tolerance: ParserTolerance,
+    keep_tags: bool,
+    extensions: Extensions,
+    threads: ThreadConfig,
+    progress: ProgressHook<ParseProgress>,
 }
 
 impl Default for GFAParser {
@@ -124,8 +219,27 @@ impl GFAParser {
         Default::default()
     }
 
+    /// Check if the parser was built to keep the lines of this record
+    /// type, the lines with an unknown type are left to the extensions
+    #[inline]
+    pub(crate) fn includes_line(&self, bytes: &[u8]) -> bool {
+        let hdr = bytes
+            .trim_start()
+            .split_str(b"\t")
+            .next()
+            .unwrap_or_default();
+        match hdr {
+            b"H" => self.headers,
+            b"S" => self.segments,
+            b"L" => self.links,
+            b"C" => self.containments,
+            b"P" => self.paths,
+            _ => true,
+        }
+    }
+
     #[inline]
-    fn parse_gfa_line(&self, bytes: &[u8]) -> ParserResult<Line> {
+    pub(crate) fn parse_gfa_line(&self, bytes: &[u8]) -> ParserResult<Line> {
         let line: &BStr = bytes.trim().as_ref();
 
         let mut fields = line.split_str(b"\t");
@@ -133,7 +247,7 @@ impl GFAParser {
 
         let invalid_line = |e: ParseFieldError| ParseError::invalid_line(e, bytes);
 
-        let line = match hdr {
+        let mut line = match hdr {
             // most common lines and more important ones
             b"S" => Segment::parse_line(fields).map(Segment::wrap),
             b"L" => Link::parse_line(fields).map(Link::wrap),
@@ -144,6 +258,14 @@ impl GFAParser {
             _ => return Err(ParseError::UnknownLineType),
         }
         .map_err(invalid_line)?;
+        if !self.keep_tags {
+            match &mut line {
+                Line::Segment(x) => x.optional.clear(),
+                Line::Link(x) => x.optional.clear(),
+                Line::Path(x) => x.optional.clear(),
+                _ => (),
+            }
+        }
         Ok(line)
     }
 
@@ -151,6 +273,8 @@ impl GFAParser {
     /// [`GFA`](/gfahandlegraph/gfa/gfa1/struct.GFA.html),
     /// [`ParseError`](../error/enum.ParseError.html)> Object
     ///
+    /// A file compressed with gzip (or bgzip) is decompressed before
+    /// its lines are parsed
     /// # Examples
     /// ```ignore
     /// let parser: GFAParser = GFAParser::new();
@@ -158,23 +282,109 @@ impl GFAParser {
     ///     parser.parse_file(&"./tests/gfa_files/data.gfa").unwrap();
     /// ```
     pub fn parse_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<GFA, ParseError> {
-        use {
-            bstr::io::BufReadExt,
-            std::{fs::File, io::BufReader},
-        };
+        self.parse_reader(crate::parser::open_file(path.as_ref())?)
+    }
+
+    /// Function that parses a file as [`parse_file`](#method.parse_file),
+    /// returning the object with the statistics of its lines
+    /// # Examples
+    /// ```ignore
+    /// let (gfa, stats) = parser.parse_file_with_stats("./tests/gfa_files/data.gfa")?;
+    /// println!("{} segments in {:?}", stats.records_of(b'S'), stats.elapsed);
+    /// ```
+    pub fn parse_file_with_stats<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<(GFA, ParseStats), ParseError> {
+        self.parse_reader_with_stats(crate::parser::open_file(path.as_ref())?)
+    }
+
+    /// Function that parses the lines read from a reader, as
+    /// [`parse_file`](#method.parse_file) does with the lines of a
+    /// file (a reader isn't decompressed)
+    /// # Examples
+    /// ```ignore
+    /// let stdin = std::io::BufReader::new(std::io::stdin());
+    /// let gfa = parser.parse_reader(stdin).unwrap();
+    /// ```
+    pub fn parse_reader<R: std::io::BufRead + Send>(&self, reader: R) -> Result<GFA, ParseError> {
+        self.parse_reader_with_stats(reader).map(|(gfa, _)| gfa)
+    }
 
-        let file = File::open(path.as_ref())?;
-        let lines = BufReader::new(file).byte_lines();
+    /// Function that parses the lines read from a reader as
+    /// [`parse_reader`](#method.parse_reader), returning the object with
+    /// the statistics of its lines
+    pub fn parse_reader_with_stats<R: std::io::BufRead + Send>(
+        &self,
+        reader: R,
+    ) -> Result<(GFA, ParseStats), ParseError> {
+        use bstr::io::BufReadExt;
+
+        let lines = crate::parser::until_read_error(reader.byte_lines());
         let gfa = Mutex::new(GFA::default());
-        lines.par_bridge().for_each(|line| {
-            match self.parse_gfa_line(line.unwrap().as_ref()) {
-                Ok(parsed) => gfa.lock().unwrap().insert_line(parsed),
-                Err(err) if err.can_safely_continue(&self.tolerance) => (),
-                // this line should return the error not panic, but for now it's ok
-                Err(err) => panic!("{}", err),
+        // the lines parsed in parallel are found out of order, so they're
+        // kept with their index and put in the object in the file order
+        let found: Mutex<Vec<(usize, Line)>> = Mutex::new(vec![]);
+        let failure = FirstFailure::new();
+        // the lines of the record types left out aren't even parsed, and
+        // every line is counted without locks
+        let counter = ParseCounter::new(&self.progress);
+        let parse_line = |(ix, line): (usize, std::io::Result<Vec<u8>>)| {
+            if !failure.before(ix) {
+                return;
+            }
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return failure.fail(ix, err.into()),
+            };
+            if !self.includes_line(line.as_ref()) {
+                return counter.line(line.len(), LineOutcome::Skipped);
             }
-        });
-        Ok(gfa.into_inner().unwrap())
+            let parsed = match self.parse_gfa_line(line.as_ref()) {
+                Err(ParseError::UnknownLineType) => self
+                    .extensions
+                    .parse_line(line.as_ref(), ix + 1, &self.tolerance)
+                    .map(|ext| {
+                        let mut gfa = gfa.lock().unwrap();
+                        match ext {
+                            ExtensionLine::Unknown(u) => gfa.unknown.push(u),
+                            ExtensionLine::Custom(c) => gfa.custom.push(c),
+                        }
+                        LineOutcome::Extension
+                    }),
+                parsed => parsed.map(|parsed| {
+                    found.lock().unwrap().push((ix, parsed));
+                    LineOutcome::Record(line.trim_start()[0])
+                }),
+            };
+            match parsed {
+                Ok(outcome) => counter.line(line.len(), outcome),
+                Err(err) if err.can_safely_continue(&self.tolerance) => {
+                    counter.line(line.len(), LineOutcome::Ignored)
+                }
+                Err(err) => failure.fail(ix, err),
+            }
+        };
+        // the file isn't read anymore after a line that failed
+        let lines = lines.enumerate().take_while(|(ix, _)| failure.before(*ix));
+        if self.threads.is_sequential() {
+            lines.for_each(parse_line);
+        } else {
+            self.threads
+                .install(|| lines.par_bridge().for_each(parse_line));
+        }
+        failure.into_result()?;
+        let mut gfa = gfa.into_inner().unwrap();
+        let mut found = found.into_inner().unwrap();
+        found.sort_unstable_by_key(|(ix, _)| *ix);
+        for (_, line) in found {
+            gfa.insert_line(line);
+        }
+        gfa.unknown.sort_by_key(|u| u.line_number);
+        gfa.custom.sort_by_key(|c| c.line_number);
+        let stats = counter.finish();
+        gfa.skipped_lines = stats.skipped_lines;
+        Ok((gfa, stats))
     }
 }
 
@@ -223,31 +433,24 @@ where
     Orientation::parse_error(parsed)
 }
 
+/// function that parses the version and the tags of the header
+/// ```<header> <- {VN:Z:<version>} <tag>*```
 #[inline]
-fn parse_tag(input: &[u8]) -> Option<bool> {
-    lazy_static! {
-        static ref RE_TAG: Regex =
-            Regex::new(r"(?-u)([A-Za-z0-9][A-Za-z0-9]:[ABHJZif]:[ -~]*)*").unwrap();
-    }
-    Some(RE_TAG.is_match(input))
-}
-
-/// function that parses the version of the header tag
-/// ```<header> <- {VN:Z:1.0}  <- (VN:Z:1\.0)?```
-#[inline]
-fn parse_header_tag<I>(input: &mut I) -> ParserFieldResult<BString>
+fn parse_header_tag<I>(input: I) -> (Option<BString>, Vec<OptField>)
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_HEADER: Regex = Regex::new(r"(?-u)(VN:Z:1\.0)?").unwrap();
+    let mut version = None;
+    let mut tags = vec![];
+    for field in input {
+        let field = field.as_ref();
+        match field.strip_prefix(b"VN:Z:") {
+            Some(value) if version.is_none() => version = Some(BString::from(value)),
+            _ => tags.extend(OptField::parse(field)),
+        }
     }
-    let next = next_field(input)?;
-    RE_HEADER
-        .find(next.as_ref())
-        .map(|s| BString::from(s.as_bytes()))
-        .ok_or(ParseFieldError::InvalidField("Version"))
+    (version, tags)
 }
 
 impl Header {
@@ -257,33 +460,27 @@ impl Header {
     }
 
     #[inline]
-    pub fn parse_line<I>(mut input: I) -> ParserFieldResult<Self>
+    pub fn parse_line<I>(input: I) -> ParserFieldResult<Self>
     where
         I: Iterator,
         I::Item: AsRef<[u8]>,
     {
-        let version = parse_header_tag(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
-        }
-        Ok(Header { version })
+        let (version, tags) = parse_header_tag(input);
+        Ok(Header { version, tags })
     }
 }
 
 /// function that parses the overlap tag
 /// ```<overlap> <- * | <CIGAR> <- ([0-9]+[MIDNSHPX=])+```
 #[inline]
-fn parse_overlap<I>(input: &mut I) -> ParserFieldResult<bool>
+fn parse_overlap<I>(input: &mut I) -> ParserFieldResult<BString>
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_OVERLAP: Regex = Regex::new(r"(?-u)\*|([0-9]+[MIDNSHPX=])+").unwrap();
-    }
     let next = next_field(input)?;
-    if RE_OVERLAP.is_match(next.as_ref()) {
-        Ok(true)
+    if validators::has_cigar(next.as_ref()) {
+        Ok(BString::from(next.as_ref()))
     } else {
         Err(ParseFieldError::InvalidField("Overlap"))
     }
@@ -297,13 +494,9 @@ where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_SEQUENCE: Regex = Regex::new(r"(?-u)\*|[A-Za-z=.]+").unwrap();
-    }
     let next = next_field(input)?;
-    RE_SEQUENCE
-        .find(next.as_ref())
-        .map(|s| BString::from(s.as_bytes()))
+    validators::find_gfa1_sequence(next.as_ref())
+        .map(BString::from)
         .ok_or(ParseFieldError::InvalidField("Sequence"))
 }
 
@@ -319,12 +512,17 @@ impl Segment {
         I: Iterator,
         I::Item: AsRef<[u8]>,
     {
-        let name = usize::parse_next(&mut input, IdType::ID())?;
+        let field = input.next().ok_or(ParseFieldError::MissingFields)?;
+        let name = u64::try_parse_id(IdType::ID(), field.as_ref())?;
+        let original_name = segment_name(field.as_ref());
         let sequence = parse_sequence(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
-        }
-        Ok(Segment { name, sequence })
+        let optional = parse_optional(input);
+        Ok(Segment {
+            name,
+            original_name,
+            sequence,
+            optional,
+        })
     }
 }
 
@@ -340,72 +538,38 @@ impl Link {
         I: Iterator,
         I::Item: AsRef<[u8]>,
     {
-        let from_segment = usize::parse_next(&mut input, IdType::ID())?;
+        let from_segment = u64::parse_next(&mut input, IdType::ID())?;
         let from_orient = parse_orientation(&mut input)?;
-        let to_segment = usize::parse_next(&mut input, IdType::ID())?;
+        let to_segment = u64::parse_next(&mut input, IdType::ID())?;
         let to_orient = parse_orientation(&mut input)?;
-        parse_overlap(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
-        }
+        let overlap = parse_overlap(&mut input)?;
+        let optional = parse_optional(input);
         Ok(Link {
             from_segment,
             from_orient,
             to_segment,
             to_orient,
+            overlap,
+            optional,
         })
     }
 }
 
+/// function that parses the position of a containment
+/// ```<pos> <- [0-9]+```
 #[inline]
-fn parse_id<I>(input: &mut I) -> ParserFieldResult<bool>
-where
-    I: Iterator,
-    I::Item: AsRef<[u8]>,
-{
-    lazy_static! {
-        static ref RE_ID: Regex = Regex::new(r"(?-u)[!-~]+").unwrap();
-    }
-    let next = next_field(input)?;
-    if RE_ID.is_match(next.as_ref()) {
-        Ok(true)
-    } else {
-        Err(ParseFieldError::InvalidField("ID"))
-    }
-}
-
-#[inline]
-fn parse_orient<I>(input: &mut I) -> ParserFieldResult<bool>
-where
-    I: Iterator,
-    I::Item: AsRef<[u8]>,
-{
-    lazy_static! {
-        static ref RE_ORIENTATION: Regex = Regex::new(r"(?-u)[+-]").unwrap();
-    }
-    let next = next_field(input)?;
-    if RE_ORIENTATION.is_match(next.as_ref()) {
-        Ok(true)
-    } else {
-        Err(ParseFieldError::InvalidField("Orientation"))
-    }
-}
-
-#[inline]
-fn parse_pos<I>(input: &mut I) -> ParserFieldResult<bool>
+fn parse_pos<I>(input: &mut I) -> ParserFieldResult<usize>
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_POS: Regex = Regex::new(r"(?-u)[0-9]*").unwrap();
-    }
     let next = next_field(input)?;
-    if RE_POS.is_match(next.as_ref()) {
-        Ok(true)
-    } else {
-        Err(ParseFieldError::InvalidField("Position"))
-    }
+    let next = next.as_ref();
+    next.iter()
+        .all(u8::is_ascii_digit)
+        .then(|| next.to_str().ok()?.parse::<usize>().ok())
+        .flatten()
+        .ok_or(ParseFieldError::InvalidField("Pos"))
 }
 
 impl Containment {
@@ -420,35 +584,36 @@ impl Containment {
         I: Iterator,
         I::Item: AsRef<[u8]>,
     {
-        parse_id(&mut input)?;
-        parse_orient(&mut input)?;
-        parse_id(&mut input)?;
-        parse_orient(&mut input)?;
-        parse_pos(&mut input)?;
-        parse_overlap(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
-        }
-
-        Ok(Containment {})
+        let container_name = u64::parse_next(&mut input, IdType::ID())?;
+        let container_orient = parse_orientation(&mut input)?;
+        let contained_name = u64::parse_next(&mut input, IdType::ID())?;
+        let contained_orient = parse_orientation(&mut input)?;
+        let pos = parse_pos(&mut input)?;
+        let overlap = parse_overlap(&mut input)?;
+        let optional = parse_optional(input);
+        Ok(Containment {
+            container_name,
+            container_orient,
+            contained_name,
+            contained_orient,
+            pos,
+            overlap,
+            optional,
+        })
     }
 }
 
 /// function that parses the overlap tag
 /// ```<overlap> <- * | <CIGAR> <- [0-9]+[MIDNSHPX=](,[0-9]+[MIDNSHPX=])*```
 #[inline]
-fn parse_path_overlap<I>(input: &mut I) -> ParserFieldResult<bool>
+fn parse_path_overlap<I>(input: &mut I) -> ParserFieldResult<BString>
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_PATH_OVERLAP: Regex =
-            Regex::new(r"(?-u)\*|[0-9]+[MIDNSHPX=](,[0-9]+[MIDNSHPX=])*").unwrap();
-    }
     let next = next_field(input)?;
-    if RE_PATH_OVERLAP.is_match(next.as_ref()) {
-        Ok(true)
+    if validators::has_cigar(next.as_ref()) {
+        Ok(BString::from(next.as_ref()))
     } else {
         Err(ParseFieldError::InvalidField("Overlap"))
     }
@@ -462,13 +627,9 @@ where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_SEGMENT_NAMES: Regex = Regex::new(r"(?-u)[!-~]+(,[!-~]+)*").unwrap();
-    }
     let next = next_field(input)?;
-    RE_SEGMENT_NAMES
-        .find(next.as_ref())
-        .map(|s| BString::from(s.as_bytes()))
+    validators::find_printable(next.as_ref())
+        .map(BString::from)
         .ok_or(ParseFieldError::InvalidField("Segment names"))
 }
 
@@ -486,14 +647,21 @@ impl Path {
     {
         let path_name = BString::parse_next(&mut input, IdType::ID())?;
         let segment_names = parse_segment_names(&mut input)?;
-
-        parse_path_overlap(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
+        // every step ends with its orientation
+        if segment_names
+            .split_str(b",")
+            .any(|s| !s.ends_with(b"+") && !s.ends_with(b"-"))
+        {
+            return Err(ParseFieldError::OrientationError);
         }
+
+        let overlaps = parse_path_overlap(&mut input)?;
+        let optional = parse_optional(input);
         Ok(Path {
             path_name,
             segment_names,
+            overlaps,
+            optional,
         })
     }
 }
@@ -501,6 +669,7 @@ impl Path {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gfa::extension::CustomRecord;
     use time::Instant;
 
     #[test]
@@ -552,9 +721,7 @@ mod tests {
     #[test]
     fn parse_header() {
         let header = "VN:Z:1.0";
-        let header_ = Header {
-            version: "VN:Z:1.0".into(),
-        };
+        let header_ = Header::new(b"1.0");
         let fields = header.split_terminator('\t');
         match Header::parse_line(fields) {
             Ok(h) => assert_eq!(h, header_),
@@ -565,8 +732,10 @@ mod tests {
     fn can_parse_segment() {
         let segment = "A\tAAAAAAACGT";
         let segment_: Segment = Segment {
-            name: convert_to_usize(b"A").unwrap(),
+            name: name_to_id(b"A").unwrap(),
+            original_name: "A".into(),
             sequence: "AAAAAAACGT".into(),
+            optional: vec![],
         };
 
         let fields = segment.split_terminator('\t');
@@ -584,6 +753,8 @@ mod tests {
             from_orient: Orientation::Backward,
             to_segment: 10,
             to_orient: Orientation::Forward,
+            overlap: "20M".into(),
+            optional: vec![],
         };
         let fields = link.split_terminator('\t');
         match Link::parse_line(fields) {
@@ -595,7 +766,15 @@ mod tests {
     #[test]
     fn can_parse_containments() {
         let containment = "15\t-\t10\t+\t4\t20M";
-        let containment_: Containment = Containment {};
+        let containment_: Containment = Containment {
+            container_name: 15,
+            container_orient: Orientation::Backward,
+            contained_name: 10,
+            contained_orient: Orientation::Forward,
+            pos: 4,
+            overlap: "20M".into(),
+            optional: vec![],
+        };
 
         let fields = containment.split_terminator('\t');
         match Containment::parse_line(fields) {
@@ -610,6 +789,8 @@ mod tests {
         let path_: Path = Path {
             path_name: "14".into(),
             segment_names: "11+,12-,13+".into(),
+            overlaps: "4M,5M".into(),
+            optional: vec![],
         };
 
         let fields = path.split_terminator('\t');
@@ -618,4 +799,66 @@ mod tests {
             Ok(p) => assert_eq!(p, path_),
         }
     }
+
+    const VENDOR_FILE: &str = "H\tVN:Z:1.0\nS\t1\tACGT\nL\t1\t+\t1\t-\t*\n\
+                               A\tutg1\t0\t+\tread1\t0\t4\nX\tnote\n";
+
+    fn vendor_file(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, VENDOR_FILE).unwrap();
+        path
+    }
+
+    fn parse_a(line: &[u8]) -> Result<CustomRecord, ParseFieldError> {
+        let fields: Vec<BString> = line.split_str(b"\t").skip(1).map(BString::from).collect();
+        if fields.len() < 2 {
+            return Err(ParseFieldError::MissingFields);
+        }
+        Ok(CustomRecord::new(fields))
+    }
+
+    #[test]
+    fn preserve_unknown_lines() {
+        let path = vendor_file("gfahandlegraph_vendor_preserve.gfa");
+        let mut builder = ParserBuilder::all();
+        builder.preserve_unknown_lines(true);
+        let gfa = builder.build().parse_file(&path).unwrap();
+        assert_eq!(gfa.unknown.len(), 2);
+        assert_eq!(gfa.unknown[0].prefix, b'A');
+        assert_eq!(gfa.unknown[0].line_number, 4);
+        assert_eq!(gfa.to_string(), VENDOR_FILE);
+
+        // dropped by default
+        let gfa = GFAParser::new().parse_file(&path).unwrap();
+        assert!(gfa.unknown.is_empty());
+        assert_eq!(
+            gfa.to_string(),
+            "H\tVN:Z:1.0\nS\t1\tACGT\nL\t1\t+\t1\t-\t*\n"
+        );
+    }
+
+    #[test]
+    fn custom_line_parser() {
+        let path = vendor_file("gfahandlegraph_vendor_custom.gfa");
+        let mut builder = ParserBuilder::all();
+        builder
+            .pedantic_errors()
+            .preserve_unknown_lines(true)
+            .custom_line(b'A', parse_a);
+        // the X line is still unknown
+        assert!(matches!(
+            builder.clone().build().parse_file(&path),
+            Err(ParseError::UnknownLineType)
+        ));
+
+        builder.custom_line(b'X', |_| Ok(CustomRecord::new(vec![])));
+        let gfa = builder.build().parse_file(&path).unwrap();
+        let a: Vec<&CustomRecord> = gfa.custom_records(b'A').collect();
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].fields[0], "utg1");
+        assert_eq!(a[0].fields.len(), 6);
+        assert_eq!(a[0].line_number, 4);
+        assert!(gfa.unknown.is_empty());
+        assert_eq!(gfa.to_string(), VENDOR_FILE);
+    }
 }
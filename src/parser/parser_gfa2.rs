@@ -1,7 +1,17 @@
 /// This file provides the function to parse all the fields of a GFA2 file
-use crate::gfa::{gfa2::*, segment_id::*};
+use crate::gfa::{
+    gfa2::*,
+    optional::{parse_optional, OptField},
+    orientation::Orientation,
+    segment_id::*,
+};
 use crate::parser::error::ParserTolerance;
 use crate::parser::error::*;
+use crate::parser::extension::*;
+use crate::parser::progress::{LineOutcome, ParseCounter, ParseProgress, ParseStats, ProgressHook};
+use crate::parser::validators;
+use crate::parser::FirstFailure;
+use crate::util::threads::ThreadConfig;
 
 use bstr::{BStr, BString, ByteSlice};
 use lazy_static::lazy_static;
@@ -10,7 +20,7 @@ use regex::bytes::Regex;
 use std::sync::Mutex;
 
 /// Builder struct for GFAParsers
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone)]
 pub struct ParserBuilder {
     pub headers: bool,
     pub segments: bool,
@@ -20,6 +30,11 @@ pub struct ParserBuilder {
     pub groups_o: bool,
     pub groups_u: bool,
     pub tolerance: ParserTolerance,
+    pub preserve_unknown: bool,
+    pub keep_tags: bool,
+    pub custom_lines: Vec<(u8, CustomLineParser)>,
+    pub threads: ThreadConfig,
+    pub progress: ProgressHook<ParseProgress>,
 }
 
 impl ParserBuilder {
@@ -34,6 +49,11 @@ impl ParserBuilder {
             groups_o: false,
             groups_u: false,
             tolerance: Default::default(),
+            preserve_unknown: false,
+            keep_tags: true,
+            custom_lines: vec![],
+            threads: Default::default(),
+            progress: Default::default(),
         }
     }
 
@@ -48,6 +68,11 @@ impl ParserBuilder {
             groups_o: true,
             groups_u: true,
             tolerance: Default::default(),
+            preserve_unknown: false,
+            keep_tags: true,
+            custom_lines: vec![],
+            threads: Default::default(),
+            progress: Default::default(),
         }
     }
 
@@ -81,6 +106,67 @@ impl ParserBuilder {
         self
     }
 
+    /// Keep the lines with an unknown record type verbatim in the
+    /// ```unknown``` field of the parsed object, instead of dropping them.
+    /// The lines are still rejected with a pedantic tolerance
+    pub fn preserve_unknown_lines(&mut self, preserve: bool) -> &mut Self {
+        self.preserve_unknown = preserve;
+        self
+    }
+
+    /// Keep the optional fields of the lines in their ```optional```
+    /// field (the default), or drop them while parsing to save the
+    /// memory they take on files with a lot of tags
+    pub fn keep_tags(&mut self, keep: bool) -> &mut Self {
+        self.keep_tags = keep;
+        self
+    }
+
+    /// Register a parser for the lines starting with ```prefix```, that
+    /// are kept in the ```custom``` field of the parsed object, whatever
+    /// the tolerance. The standard record types can't be registered
+    /// # Example
+    /// ```ignore
+    /// fn parse_a(line: &[u8]) -> Result<CustomRecord, ParseFieldError> {
+    ///     let fields = line.split_str(b"\t").skip(1).map(BString::from).collect();
+    ///     Ok(CustomRecord::new(fields))
+    /// }
+    /// let mut builder = ParserBuilder::all();
+    /// builder.custom_line(b'A', parse_a);
+    /// ```
+    pub fn custom_line(&mut self, prefix: u8, parser: CustomLineParser) -> &mut Self {
+        self.custom_lines.retain(|(p, _)| *p != prefix);
+        self.custom_lines.push((prefix, parser));
+        self
+    }
+
+    /// Number of threads used to parse a file, see
+    /// [`ThreadConfig`](../../util/threads/struct.ThreadConfig.html)
+    pub fn threads(&mut self, threads: ThreadConfig) -> &mut Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Call ```callback``` with the progress of the parsing, every
+    /// [`progress_every`](#method.progress_every) lines and at the end,
+    /// see [`ProgressHook`](../progress/struct.ProgressHook.html)
+    /// # Example
+    /// ```ignore
+    /// let mut builder = ParserBuilder::all();
+    /// builder.on_progress(Box::new(|p| eprintln!("{} lines", p.lines_read)));
+    /// ```
+    pub fn on_progress(&mut self, callback: Box<dyn Fn(ParseProgress) + Send + Sync>) -> &mut Self {
+        self.progress.set_callback(callback.into());
+        self
+    }
+
+    /// Number of lines between two calls of the
+    /// [`on_progress`](#method.on_progress) callback
+    pub fn progress_every(&mut self, lines: usize) -> &mut Self {
+        self.progress.set_every(lines);
+        self
+    }
+
     pub fn build(self) -> GFA2Parser {
         GFA2Parser {
             headers: self.headers,
@@ -91,6 +177,13 @@ impl ParserBuilder {
             groups_o: self.groups_o,
             groups_u: self.groups_u,
             tolerance: self.tolerance,
+            keep_tags: self.keep_tags,
+            extensions: Extensions {
+                preserve_unknown: self.preserve_unknown,
+                custom_lines: self.custom_lines,
+            },
+            threads: self.threads,
+            progress: self.progress,
         }
     }
 }
@@ -105,6 +198,10 @@ pub struct GFA2Parser {
     groups_o: bool,
     groups_u: bool,
     tolerance: ParserTolerance,
+    keep_tags: bool,
+    extensions: Extensions,
+    threads: ThreadConfig,
+    progress: ProgressHook<ParseProgress>,
 }
 
 impl Default for GFA2Parser {
@@ -122,8 +219,29 @@ impl GFA2Parser {
         Default::default()
     }
 
+    /// Check if the parser was built to keep the lines of this record
+    /// type, the lines with an unknown type are left to the extensions
+    #[inline]
+    pub(crate) fn includes_line(&self, bytes: &[u8]) -> bool {
+        let hdr = bytes
+            .trim_start()
+            .split_str(b"\t")
+            .next()
+            .unwrap_or_default();
+        match hdr {
+            b"H" => self.headers,
+            b"S" => self.segments,
+            b"F" => self.fragments,
+            b"E" => self.edges,
+            b"G" => self.gaps,
+            b"O" => self.groups_o,
+            b"U" => self.groups_u,
+            _ => true,
+        }
+    }
+
     #[inline]
-    fn parse_gfa_line(&self, bytes: &[u8]) -> ParserResult<Line> {
+    pub(crate) fn parse_gfa_line(&self, bytes: &[u8]) -> ParserResult<Line> {
         let line: &BStr = bytes.trim().as_ref();
 
         let mut fields = line.split_str(b"\t");
@@ -131,7 +249,7 @@ impl GFA2Parser {
 
         let invalid_line = |e: ParseFieldError| ParseError::invalid_line(e, bytes);
 
-        let line = match hdr {
+        let mut line = match hdr {
             // most common lines and more important ones
             b"S" => Segment::parse_line(fields).map(Segment::wrap),
             b"E" => Edge::parse_line(fields).map(Edge::wrap),
@@ -144,6 +262,23 @@ impl GFA2Parser {
             _ => return Err(ParseError::UnknownLineType),
         }
         .map_err(invalid_line)?;
+        if !self.keep_tags {
+            match &mut line {
+                Line::Segment(x) => x.optional.clear(),
+                Line::Fragment(x) => x.optional.clear(),
+                Line::Edge(x) => x.optional.clear(),
+                Line::Gap(x) => x.optional.clear(),
+                Line::GroupO(x) => x.optional.clear(),
+                Line::GroupU(x) => x.optional.clear(),
+                Line::Header(_) => (),
+            }
+        }
+        // a pedantic parser doesn't accept a segment with a wrong length
+        if let (ParserTolerance::Pedantic, Line::Segment(s)) = (&self.tolerance, &line) {
+            if s.length_mismatch().is_some() {
+                return Err(invalid_line(ParseFieldError::InvalidField("Length")));
+            }
+        }
         Ok(line)
     }
 
@@ -151,6 +286,8 @@ impl GFA2Parser {
     /// [`GFA2`](/gfahandlegraph/gfa/gfa2/struct.GFA2.html),
     /// [`ParseError`](../error/enum.ParseError.html)> Object
     ///
+    /// A file compressed with gzip (or bgzip) is decompressed before
+    /// its lines are parsed
     /// # Examples
     /// ```ignore
     /// let parser: GFA2Parser = GFA2Parser::new();
@@ -158,25 +295,114 @@ impl GFA2Parser {
     ///     parser.parse_file(&"./tests/gfa2_files/data.gfa").unwrap();
     /// ```
     pub fn parse_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<GFA2, ParseError> {
-        use {
-            bstr::io::BufReadExt,
-            std::{fs::File, io::BufReader},
-        };
+        self.parse_reader(crate::parser::open_file(path.as_ref())?)
+    }
 
-        let file = File::open(path.as_ref())?;
-        let lines = BufReader::new(file).byte_lines();
+    /// Function that parses a file as [`parse_file`](#method.parse_file),
+    /// returning the object with the statistics of its lines
+    /// # Examples
+    /// ```ignore
+    /// let (gfa2, stats) = parser.parse_file_with_stats("./tests/gfa_files/data.gfa")?;
+    /// println!("{} segments in {:?}", stats.records_of(b'S'), stats.elapsed);
+    /// ```
+    pub fn parse_file_with_stats<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<(GFA2, ParseStats), ParseError> {
+        self.parse_reader_with_stats(crate::parser::open_file(path.as_ref())?)
+    }
 
-        let gfa2 = Mutex::new(GFA2::default());
+    /// Function that parses the lines read from a reader, as
+    /// [`parse_file`](#method.parse_file) does with the lines of a
+    /// file (a reader isn't decompressed)
+    /// # Examples
+    /// ```ignore
+    /// let stdin = std::io::BufReader::new(std::io::stdin());
+    /// let gfa = parser.parse_reader(stdin).unwrap();
+    /// ```
+    pub fn parse_reader<R: std::io::BufRead + Send>(&self, reader: R) -> Result<GFA2, ParseError> {
+        self.parse_reader_with_stats(reader).map(|(gfa2, _)| gfa2)
+    }
+
+    /// Function that parses the lines read from a reader as
+    /// [`parse_reader`](#method.parse_reader), returning the object with
+    /// the statistics of its lines
+    pub fn parse_reader_with_stats<R: std::io::BufRead + Send>(
+        &self,
+        reader: R,
+    ) -> Result<(GFA2, ParseStats), ParseError> {
+        use bstr::io::BufReadExt;
 
-        lines.par_bridge().for_each(|line| {
-            match self.parse_gfa_line(line.unwrap().as_ref()) {
-                Ok(parsed) => gfa2.lock().unwrap().insert_line(parsed),
-                Err(err) if err.can_safely_continue(&self.tolerance) => (),
-                // this line should return the error not panic, but for now it's ok
-                Err(err) => panic!("{}", err),
+        let lines = crate::parser::until_read_error(reader.byte_lines());
+
+        let gfa2 = Mutex::new(GFA2::default());
+        // the lines parsed in parallel are found out of order, so they're
+        // kept with their index and put in the object in the file order
+        let found: Mutex<Vec<(usize, Line)>> = Mutex::new(vec![]);
+        let failure = FirstFailure::new();
+        // the lines of the record types left out aren't even parsed, and
+        // every line is counted without locks
+        let counter = ParseCounter::new(&self.progress);
+
+        let parse_line = |(ix, line): (usize, std::io::Result<Vec<u8>>)| {
+            if !failure.before(ix) {
+                return;
             }
-        });
-        Ok(gfa2.into_inner().unwrap())
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return failure.fail(ix, err.into()),
+            };
+            if !self.includes_line(line.as_ref()) {
+                return counter.line(line.len(), LineOutcome::Skipped);
+            }
+            let parsed = match self.parse_gfa_line(line.as_ref()) {
+                Err(ParseError::UnknownLineType) => self
+                    .extensions
+                    .parse_line(line.as_ref(), ix + 1, &self.tolerance)
+                    .map(|ext| {
+                        let mut gfa2 = gfa2.lock().unwrap();
+                        match ext {
+                            ExtensionLine::Unknown(u) => gfa2.unknown.push(u),
+                            ExtensionLine::Custom(c) => gfa2.custom.push(c),
+                        }
+                        LineOutcome::Extension
+                    }),
+                parsed => parsed.map(|parsed| {
+                    found.lock().unwrap().push((ix, parsed));
+                    LineOutcome::Record(line.trim_start()[0])
+                }),
+            };
+            match parsed {
+                Ok(outcome) => counter.line(line.len(), outcome),
+                Err(err) if err.can_safely_continue(&self.tolerance) => {
+                    counter.line(line.len(), LineOutcome::Ignored)
+                }
+                Err(err) => failure.fail(ix, err),
+            }
+        };
+        // the file isn't read anymore after a line that failed
+        let lines = lines.enumerate().take_while(|(ix, _)| failure.before(*ix));
+        if self.threads.is_sequential() {
+            lines.for_each(parse_line);
+        } else {
+            self.threads
+                .install(|| lines.par_bridge().for_each(parse_line));
+        }
+        failure.into_result()?;
+        let mut gfa2 = gfa2.into_inner().unwrap();
+        let mut found = found.into_inner().unwrap();
+        found.sort_unstable_by_key(|(ix, _)| *ix);
+        for (_, line) in found {
+            gfa2.insert_line(line);
+        }
+        if let ParserTolerance::Safe = self.tolerance {
+            gfa2.warnings = gfa2.validate().err().unwrap_or_default();
+        }
+        gfa2.unknown.sort_by_key(|u| u.line_number);
+        gfa2.custom.sort_by_key(|c| c.line_number);
+        let stats = counter.finish();
+        gfa2.skipped_lines = stats.skipped_lines;
+        Ok((gfa2, stats))
     }
 }
 
@@ -224,32 +450,25 @@ where
     input.next().ok_or(ParseFieldError::MissingFields)
 }
 
+/// function that parses the version and the tags of the header, the
+/// trace spacing among them
+/// ```<header> <- {VN:Z:<version>} {TS:i:<trace space>} <tag>*```
 #[inline]
-fn parse_tag(input: &[u8]) -> Option<bool> {
-    lazy_static! {
-        static ref RE_TAG: Regex =
-            Regex::new(r"(?-u)([A-Za-z0-9][A-Za-z0-9]:[ABHJZif]:[ -~]*)*").unwrap();
-    }
-    Some(RE_TAG.is_match(input))
-}
-
-/// function that parses the version of the header tag
-/// ```<header> <- {VN:Z:2.0}   {TS:i:<trace space>} <- ((VN:Z:2\.0)?\t?(TS:i:[+-]?[0-9]+)?)?```
-#[inline]
-fn parse_header_tag<I>(input: &mut I) -> ParserFieldResult<BString>
+fn parse_header_tag<I>(input: I) -> (Option<BString>, Vec<OptField>)
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_HEADER: Regex =
-            Regex::new(r"(?-u)((VN:Z:2\.0)?\t?(TS:i:[+-]?[0-9]+)?)?").unwrap();
+    let mut version = None;
+    let mut tags = vec![];
+    for field in input {
+        let field = field.as_ref();
+        match field.strip_prefix(b"VN:Z:") {
+            Some(value) if version.is_none() => version = Some(BString::from(value)),
+            _ => tags.extend(OptField::parse(field)),
+        }
     }
-    let next = next_field(input)?;
-    RE_HEADER
-        .find(next.as_ref())
-        .map(|s| BString::from(s.as_bytes()))
-        .ok_or(ParseFieldError::InvalidField("Version"))
+    (version, tags)
 }
 
 /// function that parses the HEADER field
@@ -261,16 +480,13 @@ impl Header {
     }
 
     #[inline]
-    pub fn parse_line<I>(mut input: I) -> ParserFieldResult<Self>
+    pub fn parse_line<I>(input: I) -> ParserFieldResult<Self>
     where
         I: Iterator,
         I::Item: AsRef<[u8]>,
     {
-        let version = parse_header_tag(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
-        }
-        Ok(Header { version })
+        let (version, tags) = parse_header_tag(input);
+        Ok(Header { version, tags })
     }
 }
 
@@ -282,33 +498,31 @@ where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_SEQUENCE: Regex = Regex::new(r"(?-u)\*|[!-~]+").unwrap();
-    }
     let next = next_field(input)?;
-    RE_SEQUENCE
-        .find(next.as_ref())
-        .map(|s| BString::from(s.as_bytes()))
+    validators::find_gfa2_sequence(next.as_ref())
+        .map(BString::from)
         .ok_or(ParseFieldError::InvalidField("Sequence"))
 }
 
 /// function that parses the slen tag of the segment element
 /// ```<int> <- {-}[0-9]+```
 #[inline]
-fn parse_slen<I>(input: &mut I) -> ParserFieldResult<bool>
+fn parse_slen<I>(input: &mut I) -> ParserFieldResult<u64>
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_LEN: Regex = Regex::new(r"(?-u)\-?[0-9]+").unwrap();
-    }
     let next = next_field(input)?;
-    if RE_LEN.is_match(next.as_ref()) {
-        Ok(true)
-    } else {
-        Err(ParseFieldError::InvalidField("Length"))
+    let invalid = || ParseFieldError::InvalidField("Length");
+    if !validators::has_digit(next.as_ref()) {
+        return Err(invalid());
     }
+    // a negative length isn't the length of a sequence
+    next.as_ref()
+        .to_str()
+        .ok()
+        .and_then(|len| len.parse::<u64>().ok())
+        .ok_or_else(invalid)
 }
 
 /// function that parses the SEGMENT element
@@ -325,30 +539,33 @@ impl Segment {
         I: Iterator,
         I::Item: AsRef<[u8]>,
     {
-        let id = usize::parse_next(&mut input, IdType::ID())?;
-        parse_slen(&mut input)?;
+        let field = input.next().ok_or(ParseFieldError::MissingFields)?;
+        let id = u64::try_parse_id(IdType::ID(), field.as_ref())?;
+        let original_name = segment_name(field.as_ref());
+        let slen = parse_slen(&mut input)?;
         let sequence = parse_sequence(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
-        }
-        Ok(Segment { id, sequence })
+        let optional = parse_optional(input);
+        Ok(Segment {
+            id,
+            original_name,
+            sequence,
+            slen,
+            optional,
+        })
     }
 }
 
 /// function that parses the pos tag of the fragment element
 /// ```<pos> <- {-}[0-9]+{$}```
 #[inline]
-fn parse_pos<I>(input: &mut I) -> ParserFieldResult<bool>
+fn parse_pos<I>(input: &mut I) -> ParserFieldResult<BString>
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_POS: Regex = Regex::new(r"(?-u)\-?[0-9]+\$?").unwrap();
-    }
     let next = next_field(input)?;
-    if RE_POS.is_match(next.as_ref()) {
-        Ok(true)
+    if validators::has_digit(next.as_ref()) {
+        Ok(BString::from(next.as_ref()))
     } else {
         Err(ParseFieldError::InvalidField("Position"))
     }
@@ -357,7 +574,7 @@ where
 /// function that parses the alignment tag
 /// ```<alignment> <- * | <trace> <- {-}[0-9]+(,{-}[0-9]+)* | <CIGAR> <- ([0-9]+[MDIP])+```
 #[inline]
-fn parse_alignment<I>(input: &mut I) -> ParserFieldResult<bool>
+fn parse_alignment<I>(input: &mut I) -> ParserFieldResult<BString>
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
@@ -368,60 +585,44 @@ where
     }
     let next = next_field(input)?;
     if RE_ALIGNMENT.is_match(next.as_ref()) {
-        Ok(true)
+        Ok(BString::from(next.as_ref()))
     } else {
         Err(ParseFieldError::InvalidField("Length"))
     }
 }
 
-#[inline]
-fn parse_id<I>(input: &mut I) -> ParserFieldResult<bool>
-where
-    I: Iterator,
-    I::Item: AsRef<[u8]>,
-{
-    lazy_static! {
-        static ref RE_ID: Regex = Regex::new(r"(?-u)[!-~]+").unwrap();
-    }
-    let next = next_field(input)?;
-    if RE_ID.is_match(next.as_ref()) {
-        Ok(true)
-    } else {
-        Err(ParseFieldError::InvalidField("ID"))
-    }
-}
-
 #[inline]
 fn parse_opt_id<I>(input: &mut I) -> ParserFieldResult<bool>
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_OPTIONAL_ID: Regex = Regex::new(r"(?-u)[!-~]+|\*").unwrap();
-    }
     let next = next_field(input)?;
-    if RE_OPTIONAL_ID.is_match(next.as_ref()) {
+    if validators::has_printable(next.as_ref()) {
         Ok(true)
     } else {
         Err(ParseFieldError::InvalidField("Optional ID"))
     }
 }
 
+/// function that parses the external reference of the fragment element,
+/// a name that isn't a segment followed by its orientation
+/// ```<ref> <- [!-~]+[+-]```
 #[inline]
-fn parse_ref_id<I>(input: &mut I) -> ParserFieldResult<bool>
+fn parse_external_ref<I>(input: &mut I) -> ParserFieldResult<(BString, Orientation)>
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_REFERENCE_ID: Regex = Regex::new(r"(?-u)[!-~]+[+-]").unwrap();
-    }
     let next = next_field(input)?;
-    if RE_REFERENCE_ID.is_match(next.as_ref()) {
-        Ok(true)
-    } else {
-        Err(ParseFieldError::InvalidField("Reference ID"))
+    let next = next.as_ref();
+    if !validators::has_reference_id(next) {
+        return Err(ParseFieldError::InvalidField("Reference ID"));
+    }
+    match next.split_last() {
+        Some((b'+', name)) => Ok((BString::from(name), Orientation::Forward)),
+        Some((b'-', name)) => Ok((BString::from(name), Orientation::Backward)),
+        _ => Err(ParseFieldError::InvalidField("Reference ID")),
     }
 }
 
@@ -439,18 +640,27 @@ impl Fragment {
         I: Iterator,
         I::Item: AsRef<[u8]>,
     {
-        parse_id(&mut input)?;
-        parse_ref_id(&mut input)?;
-        parse_pos(&mut input)?;
-        parse_pos(&mut input)?;
-        parse_pos(&mut input)?;
-        parse_pos(&mut input)?;
-        parse_alignment(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
-        }
-
-        Ok(Fragment {})
+        let field = input.next().ok_or(ParseFieldError::MissingFields)?;
+        let sid = u64::try_parse_id(IdType::ID(), field.as_ref())?;
+        let (external, external_orient) = parse_external_ref(&mut input)?;
+        let sbeg = parse_pos(&mut input)?;
+        let send = parse_pos(&mut input)?;
+        let fbeg = parse_pos(&mut input)?;
+        let fend = parse_pos(&mut input)?;
+        let alignment = parse_alignment(&mut input)?;
+        let optional = parse_optional(input);
+
+        Ok(Fragment {
+            sid,
+            external,
+            external_orient,
+            sbeg,
+            send,
+            fbeg,
+            fend,
+            alignment,
+            optional,
+        })
     }
 }
 
@@ -469,40 +679,62 @@ impl Edge {
         I::Item: AsRef<[u8]>,
     {
         parse_opt_id(&mut input)?;
-        let sid1 = usize::parse_next(&mut input, IdType::REFERENCEID())?;
-        let sid2 = usize::parse_next(&mut input, IdType::REFERENCEID())?;
-        parse_pos(&mut input)?;
-        parse_pos(&mut input)?;
-        parse_pos(&mut input)?;
-        parse_pos(&mut input)?;
-        parse_alignment(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
-        }
-
-        Ok(Edge { sid1, sid2 })
+        let (sid1, sid1_orient) = parse_reference(next_field(&mut input)?.as_ref())?;
+        let (sid2, sid2_orient) = parse_reference(next_field(&mut input)?.as_ref())?;
+        let beg1 = parse_pos(&mut input)?;
+        let end1 = parse_pos(&mut input)?;
+        let beg2 = parse_pos(&mut input)?;
+        let end2 = parse_pos(&mut input)?;
+        let alignment = parse_alignment(&mut input)?;
+        let optional = parse_optional(input);
+
+        Ok(Edge {
+            sid1,
+            sid1_orient,
+            sid2,
+            sid2_orient,
+            beg1,
+            end1,
+            beg2,
+            end2,
+            alignment,
+            optional,
+        })
     }
 }
 
 /// function that parses the (var)int tag of the gap element
 /// ```<int> <- * | {-}[0-9]+```
 #[inline]
-fn parse_var<I>(input: &mut I) -> ParserFieldResult<bool>
+fn parse_var<I>(input: &mut I) -> ParserFieldResult<BString>
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_VAR: Regex = Regex::new(r"(?-u)\*|\-?[0-9]+").unwrap();
-    }
     let next = next_field(input)?;
-    if RE_VAR.is_match(next.as_ref()) {
-        Ok(true)
+    if validators::has_star_or_digit(next.as_ref()) {
+        Ok(BString::from(next.as_ref()))
     } else {
         Err(ParseFieldError::InvalidField("Variance"))
     }
 }
 
+/// function that parses the distance of the gap element
+/// ```<int> <- {-}[0-9]+```
+#[inline]
+fn parse_dist<I>(input: &mut I) -> ParserFieldResult<BString>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    let next = next_field(input)?;
+    if validators::has_digit(next.as_ref()) {
+        Ok(BString::from(next.as_ref()))
+    } else {
+        Err(ParseFieldError::InvalidField("Distance"))
+    }
+}
+
 /// function that parses the GAP element
 /// ```<gap> <- G <gid:opt_id> <sid1:ref> <sid2:ref> <dist:int> (* | <var:int>) <tag>*```
 impl Gap {
@@ -517,16 +749,23 @@ impl Gap {
         I: Iterator,
         I::Item: AsRef<[u8]>,
     {
-        parse_opt_id(&mut input)?;
-        parse_ref_id(&mut input)?;
-        parse_ref_id(&mut input)?;
-        parse_slen(&mut input)?;
-        parse_var(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
-        }
-
-        Ok(Gap {})
+        let id = BString::parse_next(&mut input, IdType::OPTIONALID())?;
+        let (sid1, sid1_orient) = parse_reference(next_field(&mut input)?.as_ref())?;
+        let (sid2, sid2_orient) = parse_reference(next_field(&mut input)?.as_ref())?;
+        let dist = parse_dist(&mut input)?;
+        let var = parse_var(&mut input)?;
+        let optional = parse_optional(input);
+
+        Ok(Gap {
+            id,
+            sid1,
+            sid1_orient,
+            sid2,
+            sid2_orient,
+            dist,
+            var,
+            optional,
+        })
     }
 }
 
@@ -538,30 +777,32 @@ where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_GROUP_REF: Regex = Regex::new(r"(?-u)[!-~]+[+-]([ ][!-~]+[+-])*").unwrap();
-    }
     let next = next_field(input)?;
-    RE_GROUP_REF
-        .find(next.as_ref())
-        .map(|s| BString::from(s.as_bytes()))
+    // every reference ends with its orientation, as the steps of a path,
+    // so a group isn't cut before a reference without one
+    if next
+        .as_ref()
+        .split_str(b" ")
+        .any(|r| !r.ends_with(b"+") && !r.ends_with(b"-"))
+    {
+        return Err(ParseFieldError::OrientationError);
+    }
+    validators::find_group_references(next.as_ref())
+        .map(BString::from)
         .ok_or(ParseFieldError::InvalidField("Reference Group ID"))
 }
 
 /// function that parses the id tag og the o group element
 /// ```<id> <- [!-~]+([ ][!-~]+)*```
 #[inline]
-fn parse_group_id<I>(input: &mut I) -> ParserFieldResult<bool>
+fn parse_group_id<I>(input: &mut I) -> ParserFieldResult<BString>
 where
     I: Iterator,
     I::Item: AsRef<[u8]>,
 {
-    lazy_static! {
-        static ref RE_GROUP_ID: Regex = Regex::new(r"(?-u)[!-~]+([ ][!-~]+)*").unwrap();
-    }
     let next = next_field(input)?;
-    if RE_GROUP_ID.is_match(next.as_ref()) {
-        Ok(true)
+    if validators::has_printable(next.as_ref()) {
+        Ok(BString::from(next.as_ref()))
     } else {
         Err(ParseFieldError::InvalidField("Group ID"))
     }
@@ -583,10 +824,12 @@ impl GroupO {
     {
         let id = BString::parse_next(&mut input, IdType::OPTIONALID())?;
         let var_field = parse_group_ref(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
-        }
-        Ok(GroupO { id, var_field })
+        let optional = parse_optional(input);
+        Ok(GroupO {
+            id,
+            var_field,
+            optional,
+        })
     }
 }
 
@@ -604,18 +847,21 @@ impl GroupU {
         I: Iterator,
         I::Item: AsRef<[u8]>,
     {
-        parse_opt_id(&mut input)?;
-        parse_group_id(&mut input)?;
-        for f in input.into_iter() {
-            parse_tag(f.as_ref());
-        }
-        Ok(GroupU {})
+        let id = BString::parse_next(&mut input, IdType::OPTIONALID())?;
+        let var_field = parse_group_id(&mut input)?;
+        let optional = parse_optional(input);
+        Ok(GroupU {
+            id,
+            var_field,
+            optional,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gfa::optional::OptField;
     use time::Instant;
 
     #[test]
@@ -661,7 +907,11 @@ mod tests {
     fn can_parse_header() {
         let header = "VN:Z:2.0\tHD:Z:20.20\tuR:i:AAAAAAAA";
         let header_ = Header {
-            version: "VN:Z:2.0".into(),
+            version: Some("2.0".into()),
+            tags: vec![
+                OptField::new(b"HD", b'Z', b"20.20"),
+                OptField::new(b"uR", b'i', b"AAAAAAAA"),
+            ],
         };
 
         let fields = header.split_terminator('\t');
@@ -675,8 +925,11 @@ mod tests {
     fn can_parse_segment() {
         let segment = "A\t10\tAAAAAAACGT";
         let segment_ = Segment {
-            id: convert_to_usize(b"A").unwrap(),
+            id: name_to_id(b"A").unwrap(),
+            original_name: "A".into(),
             sequence: "AAAAAAACGT".into(),
+            slen: 10,
+            optional: vec![],
         };
 
         let fields = segment.split_terminator('\t');
@@ -689,7 +942,17 @@ mod tests {
     #[test]
     fn can_parse_fragment() {
         let fragment = "15\tr1-\t10\t10\t20\t20\t*";
-        let fragment_: Fragment = Fragment {};
+        let fragment_: Fragment = Fragment {
+            sid: 15,
+            external: "r1".into(),
+            external_orient: Orientation::Backward,
+            sbeg: "10".into(),
+            send: "10".into(),
+            fbeg: "20".into(),
+            fend: "20".into(),
+            alignment: "*".into(),
+            optional: vec![],
+        };
 
         let fields = fragment.split_terminator('\t');
         match Fragment::parse_line(fields) {
@@ -702,8 +965,16 @@ mod tests {
     fn can_parse_edge() {
         let edge = "*\t2+\t45+\t2531\t2591$\t0\t60\t60M";
         let edge_: Edge = Edge {
-            sid1: convert_to_usize(b"2+").unwrap(),
-            sid2: convert_to_usize(b"45+").unwrap(),
+            sid1: 2,
+            sid1_orient: Orientation::Forward,
+            sid2: 45,
+            sid2_orient: Orientation::Forward,
+            beg1: "2531".into(),
+            end1: "2591$".into(),
+            beg2: "0".into(),
+            end2: "60".into(),
+            alignment: "60M".into(),
+            optional: vec![],
         };
 
         let fields = edge.split_terminator('\t');
@@ -716,7 +987,16 @@ mod tests {
     #[test]
     fn can_parse_gap() {
         let gap = "g1\t7+\t22+\t10\t*";
-        let gap_: Gap = Gap {};
+        let gap_: Gap = Gap {
+            id: "g1".into(),
+            sid1: 7,
+            sid1_orient: Orientation::Forward,
+            sid2: 22,
+            sid2_orient: Orientation::Forward,
+            dist: "10".into(),
+            var: "*".into(),
+            optional: vec![],
+        };
 
         let fields = gap.split_terminator('\t');
         match Gap::parse_line(fields) {
@@ -731,6 +1011,10 @@ mod tests {
         let ogroup_: GroupO = GroupO {
             id: "P1".into(),
             var_field: "36+ 53+ 53_38+ 38_13+ 13+ 14+ 50-".into(),
+            optional: vec![
+                OptField::new(b"AZ", b'i', b"87905"),
+                OptField::new(b"HH", b'f', b"BAR"),
+            ],
         };
 
         let fields = ogroup.split_terminator('\t');
@@ -743,7 +1027,11 @@ mod tests {
     #[test]
     fn can_parse_ugroup() {
         let ugroup = "SG1\t16 24 SG2 51_24 16_24";
-        let ugroup_: GroupU = GroupU {};
+        let ugroup_: GroupU = GroupU {
+            id: "SG1".into(),
+            var_field: "16 24 SG2 51_24 16_24".into(),
+            optional: vec![],
+        };
 
         let fields = ugroup.split_terminator('\t');
         match GroupU::parse_line(fields) {
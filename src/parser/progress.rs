@@ -0,0 +1,283 @@
+/// This file provides the progress reports of the long loads, while a
+/// file is parsed and while a graph is built, and the statistics of a
+/// parsed file
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// The lines between two reports of a hook without an interval
+pub const DEFAULT_PROGRESS_EVERY: usize = 100_000;
+
+/// The progress of a parser, reported while the lines are read
+/// ```ignore
+/// pub struct ParseProgress {
+///     pub lines_read: usize,
+///     pub segments: usize,
+///     pub edges: usize,
+///     pub paths: usize,
+///     pub bytes_read: usize,
+/// }
+/// ```
+/// * ```edges``` are the links of GFA1 and the edges of GFA2
+/// * ```paths``` are the paths of GFA1 and the O-groups of GFA2
+/// * ```bytes_read``` are the bytes of the lines with their line
+///   feeds (also counted for a last line without one), after a
+///   compressed file is decompressed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseProgress {
+    pub lines_read: usize,
+    pub segments: usize,
+    pub edges: usize,
+    pub paths: usize,
+    pub bytes_read: usize,
+}
+
+/// The progress of the construction of an HashGraph, with the number
+/// of records of each kind to add
+/// ```ignore
+/// pub struct BuildProgress {
+///     pub segments: usize,
+///     pub edges: usize,
+///     pub paths: usize,
+///     pub total_segments: usize,
+///     pub total_edges: usize,
+///     pub total_paths: usize,
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuildProgress {
+    pub segments: usize,
+    pub edges: usize,
+    pub paths: usize,
+    pub total_segments: usize,
+    pub total_edges: usize,
+    pub total_paths: usize,
+}
+
+/// A callback called with the progress every ```every``` records (the
+/// lines for a parser, the segments and the paths for a graph, that
+/// reports its edges once, as they are added together), and once more
+/// at the end with the final counts.\
+/// The parallel parsers call it from the thread that reads the line
+/// of the report, so the counts of the lines parsed at the same time
+/// by the other threads can be missing
+/// # Example
+/// ```ignore
+/// let hook = ProgressHook::new(|p: ParseProgress| eprintln!("{} lines", p.lines_read))
+///     .every(1_000_000);
+/// ```
+pub struct ProgressHook<T> {
+    every: Option<usize>,
+    callback: Option<Arc<dyn Fn(T) + Send + Sync>>,
+}
+
+impl<T> ProgressHook<T> {
+    pub fn new<F: Fn(T) + Send + Sync + 'static>(callback: F) -> Self {
+        ProgressHook {
+            every: None,
+            callback: Some(Arc::new(callback)),
+        }
+    }
+
+    /// Report every ```every``` records instead of every
+    /// [`DEFAULT_PROGRESS_EVERY`](constant.DEFAULT_PROGRESS_EVERY.html),
+    /// only at the end if it's 0
+    pub fn every(mut self, every: usize) -> Self {
+        self.every = Some(every);
+        self
+    }
+
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        self.callback.is_some()
+    }
+
+    pub(crate) fn set_every(&mut self, every: usize) {
+        self.every = Some(every);
+    }
+
+    pub(crate) fn set_callback(&mut self, callback: Arc<dyn Fn(T) + Send + Sync>) {
+        self.callback = Some(callback);
+    }
+
+    /// If the hook is called before the end
+    #[inline]
+    pub(crate) fn is_periodic(&self) -> bool {
+        self.is_set() && self.interval() > 0
+    }
+
+    /// If the record ```count``` (from 1) is reported
+    #[inline]
+    pub(crate) fn is_due(&self, count: usize) -> bool {
+        self.is_periodic() && count.is_multiple_of(self.interval())
+    }
+
+    #[inline]
+    fn interval(&self) -> usize {
+        self.every.unwrap_or(DEFAULT_PROGRESS_EVERY)
+    }
+
+    #[inline]
+    pub(crate) fn report(&self, progress: T) {
+        if let Some(callback) = self.callback.as_ref() {
+            callback(progress);
+        }
+    }
+}
+
+impl<T> Default for ProgressHook<T> {
+    fn default() -> Self {
+        ProgressHook {
+            every: None,
+            callback: None,
+        }
+    }
+}
+
+impl<T> Clone for ProgressHook<T> {
+    fn clone(&self) -> Self {
+        ProgressHook {
+            every: self.every,
+            callback: self.callback.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ProgressHook<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressHook")
+            .field("every", &self.every)
+            .field("callback", &self.callback.as_ref().map(|_| "Fn"))
+            .finish()
+    }
+}
+
+/// The statistics of a parsed file, returned by the ```*_with_stats```
+/// functions of the parsers
+/// ```ignore
+/// pub struct ParseStats {
+///     pub lines_read: usize,
+///     pub bytes_read: usize,
+///     pub records: BTreeMap<u8, usize>,
+///     pub extensions: usize,
+///     pub skipped_lines: usize,
+///     pub elapsed: Duration,
+/// }
+/// ```
+/// * ```records``` are the lines parsed of each record type, by its
+///   letter (as ```b'S'```), before the headers are merged
+/// * ```extensions``` are the unknown and custom lines kept
+/// * ```skipped_lines``` are the lines of the record types left out by
+///   the parser
+///
+/// The lines read and not counted are the ones with an error that's
+/// ignored, by the tolerance of the parser
+#[derive(Debug, Clone, Default)]
+pub struct ParseStats {
+    pub lines_read: usize,
+    pub bytes_read: usize,
+    pub records: BTreeMap<u8, usize>,
+    pub extensions: usize,
+    pub skipped_lines: usize,
+    pub elapsed: Duration,
+}
+
+impl ParseStats {
+    /// The lines parsed of a record type
+    /// # Example
+    /// ```ignore
+    /// let (gfa, stats) = GFAParser::new().parse_file_with_stats("./tests/gfa1_files/lil.gfa")?;
+    /// assert_eq!(stats.records_of(b'S'), gfa.segments.len());
+    /// ```
+    #[inline]
+    pub fn records_of(&self, record: u8) -> usize {
+        self.records.get(&record).copied().unwrap_or(0)
+    }
+}
+
+/// What became of a line read by a parser
+pub(crate) enum LineOutcome {
+    Record(u8),
+    Extension,
+    Skipped,
+    Ignored,
+}
+
+/// The counters of a parser, updated from every thread without locks
+pub(crate) struct ParseCounter<'a> {
+    hook: &'a ProgressHook<ParseProgress>,
+    start: Instant,
+    lines: AtomicUsize,
+    bytes: AtomicUsize,
+    records: Vec<AtomicUsize>,
+    extensions: AtomicUsize,
+    skipped: AtomicUsize,
+}
+
+impl<'a> ParseCounter<'a> {
+    pub(crate) fn new(hook: &'a ProgressHook<ParseProgress>) -> Self {
+        ParseCounter {
+            hook,
+            start: Instant::now(),
+            lines: AtomicUsize::new(0),
+            bytes: AtomicUsize::new(0),
+            records: (0..256).map(|_| AtomicUsize::new(0)).collect(),
+            extensions: AtomicUsize::new(0),
+            skipped: AtomicUsize::new(0),
+        }
+    }
+
+    /// Count a line of ```len``` bytes (without its line feed), and
+    /// report the progress if it's due
+    pub(crate) fn line(&self, len: usize, outcome: LineOutcome) {
+        let counter = match outcome {
+            LineOutcome::Record(record) => Some(&self.records[record as usize]),
+            LineOutcome::Extension => Some(&self.extensions),
+            LineOutcome::Skipped => Some(&self.skipped),
+            LineOutcome::Ignored => None,
+        };
+        if let Some(counter) = counter {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        self.bytes.fetch_add(len + 1, Ordering::Relaxed);
+        let lines = self.lines.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.hook.is_due(lines) {
+            self.hook.report(self.progress());
+        }
+    }
+
+    fn records_of(&self, record: u8) -> usize {
+        self.records[record as usize].load(Ordering::Relaxed)
+    }
+
+    fn progress(&self) -> ParseProgress {
+        ParseProgress {
+            lines_read: self.lines.load(Ordering::Relaxed),
+            segments: self.records_of(b'S'),
+            edges: self.records_of(b'L') + self.records_of(b'E'),
+            paths: self.records_of(b'P') + self.records_of(b'O'),
+            bytes_read: self.bytes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The final statistics, reported a last time to the hook
+    pub(crate) fn finish(self) -> ParseStats {
+        self.hook.report(self.progress());
+        ParseStats {
+            lines_read: self.lines.into_inner(),
+            bytes_read: self.bytes.into_inner(),
+            records: self
+                .records
+                .into_iter()
+                .enumerate()
+                .map(|(record, count)| (record as u8, count.into_inner()))
+                .filter(|(_, count)| *count > 0)
+                .collect(),
+            extensions: self.extensions.into_inner(),
+            skipped_lines: self.skipped.into_inner(),
+            elapsed: self.start.elapsed(),
+        }
+    }
+}
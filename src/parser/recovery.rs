@@ -0,0 +1,169 @@
+/// This file provides the recovery mode used to build a graph from a
+/// corrupted or truncated file
+use crate::gfa::{gfa1::GFA, gfa2::GFA2};
+use crate::hashgraph::{graph::FileType, HashGraph};
+use crate::parser::{error::*, Parser};
+
+use bstr::{BString, ByteSlice};
+
+/// Damage report of a graph built in recovery mode
+/// ```ignore
+/// pub struct RecoveryReport {
+///     pub skipped_lines: Vec<(usize, String, ParseError)>,
+///     pub dropped_edges: usize,
+///     pub dropped_path_steps: usize,
+///     pub dropped_steps_per_path: Vec<(BString, usize)>,
+///     pub truncated: bool,
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct RecoveryReport {
+    /// Every line that couldn't be parsed, with its (1-based) line number
+    pub skipped_lines: Vec<(usize, String, ParseError)>,
+    /// Edges referencing a segment that doesn't exist
+    pub dropped_edges: usize,
+    /// Path steps referencing a segment that doesn't exist
+    pub dropped_path_steps: usize,
+    /// Number of steps lost by each path
+    pub dropped_steps_per_path: Vec<(BString, usize)>,
+    /// The file ended in the middle of a line: its last line has no
+    /// newline and can't be parsed
+    pub truncated: bool,
+}
+
+impl RecoveryReport {
+    /// Returns true when nothing was dropped
+    pub fn is_clean(&self) -> bool {
+        self.skipped_lines.is_empty()
+            && self.dropped_edges == 0
+            && self.dropped_path_steps == 0
+            && !self.truncated
+    }
+}
+
+/// Split the file in lines, parsing them sequentially to keep track of
+/// the line numbers. When the file doesn't end with a newline its last
+/// line is kept if it can be parsed, and dropped as truncated otherwise
+fn parse_lines<L, P, I>(bytes: &[u8], mut parse: P, mut insert: I) -> RecoveryReport
+where
+    P: FnMut(&[u8]) -> ParserResult<L>,
+    I: FnMut(L),
+{
+    let mut report = RecoveryReport::default();
+    let unterminated = !bytes.is_empty() && !bytes.ends_with(b"\n");
+    let lines: Vec<&[u8]> = bytes.lines().collect();
+    let last = lines.len();
+    for (ix, line) in lines.into_iter().enumerate() {
+        let number = ix + 1;
+        let text = || line.to_str_lossy().into_owned();
+        if line.trim().is_empty() || line.starts_with(b"#") {
+            continue;
+        }
+        match parse(line) {
+            Ok(parsed) => insert(parsed),
+            Err(_) if unterminated && number == last => {
+                report.truncated = true;
+                report
+                    .skipped_lines
+                    .push((number, text(), ParseError::TruncatedLine(text())));
+            }
+            Err(err) => report.skipped_lines.push((number, text(), err)),
+        }
+    }
+    report
+}
+
+/// The GFA object of a file read in recovery mode, by the parsers of
+/// ```parser```, with every line that can't be parsed skipped and
+/// listed in the report
+pub(crate) fn parse_recovering(
+    parser: &Parser,
+    path: &std::path::Path,
+    gfa2: bool,
+) -> Result<(FileType, RecoveryReport), ParseError> {
+    let bytes = std::fs::read(path)?;
+    if gfa2 {
+        let line_parser = parser.gfa2_parser();
+        let mut gfa2 = GFA2::default();
+        let report = parse_lines(
+            &bytes,
+            |l| line_parser.parse_gfa_line(l),
+            |l| gfa2.insert_line(l),
+        );
+        Ok((FileType::GFA2(gfa2), report))
+    } else {
+        let line_parser = parser.gfa_parser();
+        let mut gfa = GFA::default();
+        let report = parse_lines(
+            &bytes,
+            |l| line_parser.parse_gfa_line(l),
+            |l| gfa.insert_line(l),
+        );
+        Ok((FileType::GFA(gfa), report))
+    }
+}
+
+/// Function that builds the largest consistent
+/// [`HashGraph`](../../hashgraph/graph/struct.HashGraph.html)
+/// from a GFA or GFA2 file that may be corrupted or truncated, as a
+/// [`Parser`](../struct.Parser.html) with
+/// [`recover`](../struct.Parser.html#method.recover) set does.\
+/// Unlike
+/// [`parse_file_to_graph`](../fn.parse_file_to_graph.html)
+/// every line that can't be parsed is skipped, edges referencing
+/// missing segments are dropped, and path steps referencing missing
+/// segments are skipped, keeping the rest of the path.\
+/// Everything that was dropped is listed in the returned
+/// [`RecoveryReport`](struct.RecoveryReport.html)
+/// # Example
+/// ```ignore
+/// let (graph, report) = recover_file_to_graph("./tests/big_files/test.gfa").unwrap();
+/// if report.truncated {
+///     println!("The file was truncated");
+/// }
+/// ```
+pub fn recover_file_to_graph<P: AsRef<std::path::Path>>(
+    path: P,
+) -> Result<(HashGraph, RecoveryReport), ParseError> {
+    Parser::new()
+        .recover(true)
+        .parse_file_to_graph_with_recovery(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_segments(l: &[u8]) -> ParserResult<Vec<u8>> {
+        if l.starts_with(b"S\t") && l.split_str(b"\t").count() == 3 {
+            Ok(l.to_vec())
+        } else {
+            Err(ParseError::UnknownLineType)
+        }
+    }
+
+    #[test]
+    fn parse_lines_truncated() {
+        let mut parsed = vec![];
+        let report = parse_lines(b"S\t1\tA\n\nX\tbad\nS\t2", parse_segments, |l| {
+            parsed.push(l)
+        });
+        assert_eq!(parsed, vec![b"S\t1\tA".to_vec()]);
+        assert!(report.truncated);
+        assert_eq!(report.skipped_lines.len(), 2);
+        assert_eq!(report.skipped_lines[0].0, 3);
+        assert!(matches!(
+            report.skipped_lines[1],
+            (4, _, ParseError::TruncatedLine(_))
+        ));
+    }
+
+    #[test]
+    fn parse_lines_without_trailing_newline() {
+        let mut parsed = vec![];
+        let report = parse_lines(b"S\t1\tA\nS\t2\tAC", parse_segments, |l| parsed.push(l));
+        assert_eq!(parsed, vec![b"S\t1\tA".to_vec(), b"S\t2\tAC".to_vec()]);
+        assert!(!report.truncated);
+        assert!(report.is_clean());
+    }
+}
@@ -0,0 +1,275 @@
+//! This file provides hand-rolled checks of the simplest fields of the
+//! GFA and GFA2 grammars, used instead of the regexes on every line.\
+//! Each function accepts (or finds) exactly what the regex in its
+//! documentation accepts with ```is_match``` (or ```find```), that is
+//! anywhere in the field, as the regexes aren't anchored
+
+#[inline]
+fn is_printable(b: u8) -> bool {
+    (b'!'..=b'~').contains(&b)
+}
+
+#[inline]
+fn is_orientation(b: u8) -> bool {
+    b == b'+' || b == b'-'
+}
+
+/// The first maximal run of printable bytes starting at ```from```
+#[inline]
+fn printable_run(input: &[u8], from: usize) -> Option<(usize, usize)> {
+    let start = from + input[from..].iter().position(|b| is_printable(*b))?;
+    let len = input[start..]
+        .iter()
+        .position(|b| !is_printable(*b))
+        .unwrap_or(input.len() - start);
+    Some((start, start + len))
+}
+
+/// ```[!-~]+``` (or ```[!-~]+|\*```) with ```is_match```
+#[inline]
+pub(crate) fn has_printable(input: &[u8]) -> bool {
+    input.iter().any(|b| is_printable(*b))
+}
+
+/// ```[!-~]+``` (or ```[!-~]+|\*```, or ```[!-~]+(,[!-~]+)*```) with ```find```
+#[inline]
+pub(crate) fn find_printable(input: &[u8]) -> Option<&[u8]> {
+    printable_run(input, 0).map(|(start, end)| &input[start..end])
+}
+
+/// ```-?[0-9]+``` (or ```-?[0-9]+\$?```) with ```is_match```
+#[inline]
+pub(crate) fn has_digit(input: &[u8]) -> bool {
+    input.iter().any(u8::is_ascii_digit)
+}
+
+/// ```\*|-?[0-9]+``` with ```is_match```
+#[inline]
+pub(crate) fn has_star_or_digit(input: &[u8]) -> bool {
+    input.iter().any(|b| *b == b'*' || b.is_ascii_digit())
+}
+
+/// ```\*|([0-9]+[MIDNSHPX=])+``` (or
+/// ```\*|[0-9]+[MIDNSHPX=](,[0-9]+[MIDNSHPX=])*```) with ```is_match```
+#[inline]
+pub(crate) fn has_cigar(input: &[u8]) -> bool {
+    input.contains(&b'*')
+        || input
+            .windows(2)
+            .any(|w| w[0].is_ascii_digit() && b"MIDNSHPX=".contains(&w[1]))
+}
+
+/// ```[!-~]+[+-]``` with ```is_match```
+#[inline]
+pub(crate) fn has_reference_id(input: &[u8]) -> bool {
+    input
+        .windows(2)
+        .any(|w| is_printable(w[0]) && is_orientation(w[1]))
+}
+
+/// The end of ```[!-~]+[+-]``` matched from ```start```, where a run of
+/// printable bytes begins: the greedy match ends with the last
+/// orientation of the run
+#[inline]
+fn reference_end(input: &[u8], start: usize, end: usize) -> Option<usize> {
+    input[start + 1..end]
+        .iter()
+        .rposition(|b| is_orientation(*b))
+        .map(|ix| start + 1 + ix + 1)
+}
+
+/// ```[!-~]+[+-]``` with ```find```
+#[inline]
+pub(crate) fn find_reference_id(input: &[u8]) -> Option<&[u8]> {
+    find_references(input, false)
+}
+
+/// ```[!-~]+[+-]([ ][!-~]+[+-])*``` with ```find```
+#[inline]
+pub(crate) fn find_group_references(input: &[u8]) -> Option<&[u8]> {
+    find_references(input, true)
+}
+
+fn find_references(input: &[u8], repeat: bool) -> Option<&[u8]> {
+    // the match starts at the first run containing an orientation
+    // after its first byte
+    let mut from = 0;
+    let (start, mut end, mut run_end) = loop {
+        let (start, run_end) = printable_run(input, from)?;
+        if let Some(end) = reference_end(input, start, run_end) {
+            break (start, end, run_end);
+        }
+        from = run_end;
+    };
+    // another reference only if this one ends its run
+    while repeat && end == run_end && input.get(end) == Some(&b' ') {
+        let next = end + 1;
+        let next_run_end = match printable_run(input, next) {
+            Some((start, run_end)) if start == next => run_end,
+            _ => break,
+        };
+        match reference_end(input, next, next_run_end) {
+            Some(next_end) => {
+                end = next_end;
+                run_end = next_run_end;
+            }
+            None => break,
+        }
+    }
+    Some(&input[start..end])
+}
+
+/// ```\*|[A-Za-z=.]+``` with ```find```
+#[inline]
+pub(crate) fn find_gfa1_sequence(input: &[u8]) -> Option<&[u8]> {
+    let is_base = |b: &u8| b.is_ascii_alphabetic() || *b == b'=' || *b == b'.';
+    let start = input.iter().position(|b| *b == b'*' || is_base(b))?;
+    if input[start] == b'*' {
+        return Some(&input[start..=start]);
+    }
+    let len = input[start..]
+        .iter()
+        .position(|b| !is_base(b))
+        .unwrap_or(input.len() - start);
+    Some(&input[start..start + len])
+}
+
+/// ```\*|[!-~]+``` with ```find```
+#[inline]
+pub(crate) fn find_gfa2_sequence(input: &[u8]) -> Option<&[u8]> {
+    let (start, end) = printable_run(input, 0)?;
+    if input[start] == b'*' {
+        Some(&input[start..=start])
+    } else {
+        Some(&input[start..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::bytes::Regex;
+
+    /// The bytes at the boundaries of the classes used by the grammars
+    const ALPHABET: &[u8] = b"\x00\t\x20\x21*+-,. $=09AMPXZaz~\x7F\x80\xFF";
+
+    /// Every string of the alphabet up to 3 bytes, and a few longer ones
+    fn corpus() -> Vec<Vec<u8>> {
+        let mut corpus: Vec<Vec<u8>> = vec![vec![]];
+        let mut last: Vec<Vec<u8>> = vec![vec![]];
+        for _ in 0..3 {
+            let next: Vec<Vec<u8>> = last
+                .iter()
+                .flat_map(|s| {
+                    ALPHABET.iter().map(move |b| {
+                        let mut s = s.clone();
+                        s.push(*b);
+                        s
+                    })
+                })
+                .collect();
+            corpus.extend(next.iter().cloned());
+            last = next;
+        }
+        let long: &[&[u8]] = &[
+            b"ACGT",
+            b"12M3I4D",
+            b"12M,3I,4D",
+            b"1-,2+ 3- 4+",
+            b"1- 2+ 3",
+            b"1- 2+ 3-x 4+",
+            b"a+b- c",
+            b"  x+ y-",
+            b"x +y- z+",
+            b"-1,-2,3",
+            b"12$",
+            b"*ACGT",
+            b"AC*GT",
+            b"AC\tGT",
+            b"ACGT\x7F+",
+            b"+- -+ ++",
+            b"11+ 12- 13+",
+        ];
+        corpus.extend(long.iter().map(|s| s.to_vec()));
+
+        // and longer random strings, mostly of the same alphabet
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..5000 {
+            let len = (next() % 12) as usize;
+            let s = (0..len)
+                .map(|_| match next() % 8 {
+                    0 => next() as u8,
+                    _ => ALPHABET[(next() % ALPHABET.len() as u64) as usize],
+                })
+                .collect();
+            corpus.push(s);
+        }
+        corpus
+    }
+
+    fn regex(pattern: &str) -> Regex {
+        Regex::new(&format!("(?-u){}", pattern)).unwrap()
+    }
+
+    fn same_matches(pattern: &str, check: fn(&[u8]) -> bool) {
+        let re = regex(pattern);
+        for s in corpus() {
+            assert_eq!(re.is_match(&s), check(&s), "{} on {:?}", pattern, s);
+        }
+    }
+
+    fn same_finds(pattern: &str, find: fn(&[u8]) -> Option<&[u8]>) {
+        let re = regex(pattern);
+        for s in corpus() {
+            assert_eq!(
+                re.find(&s).map(|m| m.as_bytes()),
+                find(&s),
+                "{} on {:?}",
+                pattern,
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn printable() {
+        same_matches(r"[!-~]+", has_printable);
+        same_matches(r"[!-~]+|\*", has_printable);
+        same_matches(r"[!-~]+([ ][!-~]+)*", has_printable);
+        same_finds(r"[!-~]+", find_printable);
+        same_finds(r"[!-~]+|\*", find_printable);
+        same_finds(r"[!-~]+(,[!-~]+)*", find_printable);
+    }
+
+    #[test]
+    fn numbers() {
+        same_matches(r"\-?[0-9]+", has_digit);
+        same_matches(r"\-?[0-9]+\$?", has_digit);
+        same_matches(r"\*|\-?[0-9]+", has_star_or_digit);
+    }
+
+    #[test]
+    fn cigars() {
+        same_matches(r"\*|([0-9]+[MIDNSHPX=])+", has_cigar);
+        same_matches(r"\*|[0-9]+[MIDNSHPX=](,[0-9]+[MIDNSHPX=])*", has_cigar);
+    }
+
+    #[test]
+    fn references() {
+        same_matches(r"[!-~]+[+-]", has_reference_id);
+        same_finds(r"[!-~]+[+-]", find_reference_id);
+        same_finds(r"[!-~]+[+-]([ ][!-~]+[+-])*", find_group_references);
+    }
+
+    #[test]
+    fn sequences() {
+        same_finds(r"\*|[A-Za-z=.]+", find_gfa1_sequence);
+        same_finds(r"\*|[!-~]+", find_gfa2_sequence);
+    }
+}
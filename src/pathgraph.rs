@@ -65,6 +65,37 @@ pub trait PathHandleGraph {
         pos: usize,
     ) -> Option<Self::StepHandle>;
 
+    /// Get the handle of the step on a position (in bases from 0) of
+    /// the path, and the offset of the position on it along the
+    /// orientation of the step, None if the position is out of the path
+    /// # Example
+    /// ```ignore
+    /// // P x 1+,3- with S 1 CAAATAAG and S 3 GT
+    /// assert_eq!(graph.path_position_to_handle(&x, 9), Some((Handle::pack(3, true), 1)));
+    /// ```
+    fn path_position_to_handle(
+        &self,
+        path_handle: &Self::PathHandle,
+        pos: usize,
+    ) -> Option<(Handle, usize)>;
+
+    /// Get the sequence spelled by the path from the position ```start```
+    /// to ```end``` (not included), as the sequences of its steps along
+    /// their orientation, None if the range is out of the path.\
+    /// The range of a circular path can go through its origin, with
+    /// ```start``` after ```end```
+    /// # Example
+    /// ```ignore
+    /// // P x 1+,3- with S 1 CAAATAAG and S 3 GT
+    /// assert_eq!(graph.path_range_sequence(&x, 6, 10), Some(b"AGAC".to_vec()));
+    /// ```
+    fn path_range_sequence(
+        &self,
+        path_handle: &Self::PathHandle,
+        start: usize,
+        end: usize,
+    ) -> Option<Vec<u8>>;
+
     fn destroy_path(&mut self, path: &Self::PathHandle);
 
     fn next_step(&self, step_handle: &Self::StepHandle) -> Self::StepHandle;
@@ -79,7 +110,11 @@ pub trait PathHandleGraph {
         to_append: Handle,
     ) -> Result<Self::StepHandle, GraphError>;
 
-    fn prepend_step(&mut self, path: &Self::PathHandle, to_prepend: Handle) -> Self::StepHandle;
+    fn prepend_step(
+        &mut self,
+        path: &Self::PathHandle,
+        to_prepend: Handle,
+    ) -> Result<Self::StepHandle, GraphError>;
 
     fn rewrite_segment(
         &mut self,
@@ -103,6 +138,30 @@ pub trait PathHandleGraph {
         path: &'a Self::PathHandle,
     ) -> Box<dyn Iterator<Item = Self::StepHandle> + 'a>;
 
+    /// Returns an iterator over all the steps in a path, each one with
+    /// its handle and the position (in bases) where it starts on the
+    /// path, as ```position_of_step```.\
+    /// The default implementation asks for the position of each step,
+    /// an implementation should walk the path once, summing the lengths
+    /// of the nodes: the steps of a circular path start from its first
+    /// step, so the positions only grow
+    /// # Example
+    /// ```ignore
+    /// for (step, handle, position) in graph.steps_with_positions(&path) {
+    ///     println!("{:?} {} at {}", step, handle.id(), position);
+    /// }
+    /// ```
+    fn steps_with_positions<'a>(
+        &'a self,
+        path: &'a Self::PathHandle,
+    ) -> Box<dyn Iterator<Item = (Self::StepHandle, Handle, usize)> + 'a> {
+        Box::new(self.steps(path).filter_map(move |step| {
+            let handle = self.handle_of_step(&step)?;
+            let position = self.position_of_step(&step)?;
+            Some((step, handle, position))
+        }))
+    }
+
     /// Function that removes a
     /// [`Node`](file:///D:/GitHub/rs-gfahandlegraph/target/doc/gfahandlegraph/hashgraph/node/struct.Node.html)
     /// (and all it's occurrencies) from a
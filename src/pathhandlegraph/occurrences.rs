@@ -8,11 +8,11 @@ pub trait OccurBase: Sized {
     type StepIx: Sized + Copy + Eq;
 }
 
-impl<'a, T: OccurBase> OccurBase for &'a T {
+impl<T: OccurBase> OccurBase for &T {
     type StepIx = T::StepIx;
 }
 
-impl<'a, T: OccurBase> OccurBase for &'a mut T {
+impl<T: OccurBase> OccurBase for &mut T {
     type StepIx = T::StepIx;
 }
 
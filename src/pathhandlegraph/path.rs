@@ -25,7 +25,7 @@ pub trait PathBase: Sized {
     type StepIx: Sized + Copy + Eq;
 }
 
-impl<'a, T> PathBase for &'a T
+impl<T> PathBase for &T
 where
     T: PathBase,
 {
@@ -34,7 +34,7 @@ where
     type StepIx = T::StepIx;
 }
 
-impl<'a, T> PathBase for &'a mut T
+impl<T> PathBase for &mut T
 where
     T: PathBase,
 {
@@ -54,6 +54,7 @@ pub trait PathRef: PathBase {
     fn len(self) -> usize;
 
     #[inline]
+    #[allow(clippy::wrong_self_convention)]
     fn is_empty(self) -> bool {
         self.len() == 0
     }
@@ -0,0 +1,564 @@
+/// This file provides a conformance suite for the implementations of the
+/// handlegraph traits: a few canonical graphs, described by the state
+/// they're expected to have, a builder that creates them on any graph
+/// with the additive traits, and the checks of each trait against the
+/// expected state.\
+/// It's compiled in the tests of the crate and with the ```testsuite```
+/// feature, so another backend can be checked as HashGraph is
+/// # Example
+/// ```ignore
+/// for expected in testsuite::fixtures() {
+///     let mut graph = MyGraph::default();
+///     testsuite::build_graph(&mut graph, &expected).unwrap();
+///     testsuite::check_graph_contracts(&graph, &expected);
+/// }
+/// ```
+use crate::{
+    handle::{Direction, Edge, Handle, NodeId},
+    handlegraph::*,
+    mutablehandlegraph::AdditiveHandleGraph,
+    pathgraph::PathHandleGraph,
+    util::dna,
+};
+
+use bstr::{BString, ByteSlice};
+
+/// A path of an [`ExpectedGraph`](struct.ExpectedGraph.html)
+/// ```ignore
+/// pub struct ExpectedPath {
+///     pub name: BString,
+///     pub circular: bool,
+///     pub steps: Vec<Handle>,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedPath {
+    pub name: BString,
+    pub circular: bool,
+    pub steps: Vec<Handle>,
+}
+
+/// The state a graph is expected to have: its nodes with their forward
+/// sequence, its edges (each once, in any of its two orientations) and
+/// its paths. The neighbors of the handles, the reverse sequences and
+/// the positions in the paths are derived from them
+/// ```ignore
+/// pub struct ExpectedGraph {
+///     pub name: &'static str,
+///     pub nodes: Vec<(NodeId, BString)>,
+///     pub edges: Vec<Edge>,
+///     pub paths: Vec<ExpectedPath>,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedGraph {
+    /// Name of the fixture, in the messages of the failed checks
+    pub name: &'static str,
+    pub nodes: Vec<(NodeId, BString)>,
+    pub edges: Vec<Edge>,
+    pub paths: Vec<ExpectedPath>,
+}
+
+impl ExpectedGraph {
+    /// The sequence of a handle, in its orientation
+    pub fn sequence(&self, handle: Handle) -> Vec<u8> {
+        let (_, seq) = self
+            .nodes
+            .iter()
+            .find(|(id, _)| *id == handle.id())
+            .unwrap_or_else(|| panic!("{}: node {} isn't expected", self.name, handle.id()));
+        if handle.is_reverse() {
+            dna::rev_comp_slice(seq)
+        } else {
+            seq.to_vec()
+        }
+    }
+
+    fn handles(&self) -> Vec<Handle> {
+        let mut handles: Vec<Handle> = self
+            .nodes
+            .iter()
+            .flat_map(|(id, _)| vec![Handle::pack(*id, false), Handle::pack(*id, true)])
+            .collect();
+        handles.sort();
+        handles
+    }
+
+    /// The neighbors of a handle, sorted and without duplicates: an edge
+    /// ```Edge(l, r)``` is also the edge ```Edge(r.flip(), l.flip())```
+    pub fn neighbors(&self, handle: Handle, dir: Direction) -> Vec<Handle> {
+        let mut res: Vec<Handle> = match dir {
+            Direction::Right => self
+                .edges
+                .iter()
+                .flat_map(|Edge(l, r)| {
+                    let mut to = vec![];
+                    if *l == handle {
+                        to.push(*r);
+                    }
+                    if r.flip() == handle {
+                        to.push(l.flip());
+                    }
+                    to
+                })
+                .collect(),
+            Direction::Left => self
+                .neighbors(handle.flip(), Direction::Right)
+                .into_iter()
+                .map(|h| h.flip())
+                .collect(),
+        };
+        res.sort();
+        res.dedup();
+        res
+    }
+
+    /// The visits of the paths to a node, as the name of the path and
+    /// the index of the step
+    pub fn visits(&self, id: NodeId) -> Vec<(BString, usize)> {
+        let mut res: Vec<(BString, usize)> = self
+            .paths
+            .iter()
+            .flat_map(|p| {
+                p.steps
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, h)| h.id() == id)
+                    .map(move |(ix, _)| (p.name.clone(), ix))
+            })
+            .collect();
+        res.sort();
+        res
+    }
+}
+
+/// A handle as written in the GFA files
+fn oriented(handle: &Handle) -> String {
+    format!(
+        "{}{}",
+        handle.id(),
+        if handle.is_reverse() { '-' } else { '+' }
+    )
+}
+
+fn fwd(id: u64) -> Handle {
+    Handle::pack(id, false)
+}
+
+fn rev(id: u64) -> Handle {
+    Handle::pack(id, true)
+}
+
+fn nodes(nodes: &[(u64, &str)]) -> Vec<(NodeId, BString)> {
+    nodes
+        .iter()
+        .map(|(id, seq)| (NodeId::from(*id), BString::from(*seq)))
+        .collect()
+}
+
+fn path(name: &str, circular: bool, steps: &[Handle]) -> ExpectedPath {
+    ExpectedPath {
+        name: BString::from(name),
+        circular,
+        steps: steps.to_vec(),
+    }
+}
+
+/// The canonical graphs of the suite:
+/// * ```empty```, without nodes
+/// * ```reverse_chain```, whose edges join forward and reverse handles
+/// * ```self_loops```, a node with a loop on itself and the two
+///   loops that invert its orientation, on its right and on its left
+/// * ```paths```, with reverse steps, a path visiting a node twice and
+///   a circular path
+pub fn fixtures() -> Vec<ExpectedGraph> {
+    vec![
+        ExpectedGraph {
+            name: "empty",
+            nodes: vec![],
+            edges: vec![],
+            paths: vec![],
+        },
+        ExpectedGraph {
+            name: "reverse_chain",
+            nodes: nodes(&[(1, "ACCG"), (2, "TTGA"), (3, "C"), (5, "GATTACA")]),
+            edges: vec![
+                Edge(fwd(1), rev(2)),
+                Edge(rev(2), fwd(3)),
+                Edge(rev(1), fwd(5)),
+                Edge(fwd(5), rev(3)),
+            ],
+            paths: vec![],
+        },
+        ExpectedGraph {
+            name: "self_loops",
+            nodes: nodes(&[(1, "GATT"), (2, "CA")]),
+            edges: vec![
+                Edge(fwd(1), fwd(1)),
+                Edge(fwd(1), rev(1)),
+                Edge(rev(1), fwd(1)),
+                Edge(fwd(1), fwd(2)),
+                Edge(fwd(2), rev(2)),
+            ],
+            paths: vec![path(
+                "loop",
+                false,
+                &[fwd(1), fwd(1), rev(1), fwd(1), fwd(2)],
+            )],
+        },
+        ExpectedGraph {
+            name: "paths",
+            nodes: nodes(&[(1, "AAC"), (2, "GT"), (3, "TTTA"), (4, "CG")]),
+            edges: vec![
+                Edge(fwd(1), rev(2)),
+                Edge(rev(2), fwd(3)),
+                Edge(fwd(1), fwd(2)),
+                Edge(fwd(2), fwd(1)),
+                Edge(fwd(1), rev(4)),
+                Edge(fwd(2), fwd(3)),
+                Edge(fwd(3), fwd(4)),
+                Edge(fwd(4), fwd(2)),
+            ],
+            paths: vec![
+                path("linear", false, &[fwd(1), rev(2), fwd(3)]),
+                path("repeat", false, &[fwd(1), fwd(2), fwd(1), rev(4)]),
+                path("ring", true, &[fwd(2), fwd(3), fwd(4)]),
+                path("empty", false, &[]),
+            ],
+        },
+    ]
+}
+
+/// Function that creates the nodes, the edges and the paths of
+/// ```expected``` in an empty graph, with the additive traits
+pub fn build_graph<G>(graph: &mut G, expected: &ExpectedGraph) -> Result<(), GraphError>
+where
+    G: AdditiveHandleGraph + PathHandleGraph,
+{
+    for (id, seq) in expected.nodes.iter() {
+        graph.create_handle(*id, seq)?;
+    }
+    for edge in expected.edges.iter() {
+        graph.create_edge(*edge)?;
+    }
+    for p in expected.paths.iter() {
+        let path = graph.create_path_handle(&p.name, p.circular);
+        for step in p.steps.iter() {
+            graph.append_step(&path, *step)?;
+        }
+    }
+    Ok(())
+}
+
+/// Function that checks the handles of the graph: each node once, and
+/// the smallest and the largest id
+pub fn check_handles_contract<'a, G>(graph: &'a G, expected: &ExpectedGraph)
+where
+    G: HandleGraph,
+    &'a G: AllHandles,
+{
+    let name = expected.name;
+    let mut handles: Vec<Handle> = graph.handles().collect();
+    handles.sort();
+    let mut ids: Vec<NodeId> = expected.nodes.iter().map(|(id, _)| *id).collect();
+    ids.sort();
+    assert_eq!(
+        handles,
+        ids.iter()
+            .map(|id| Handle::pack(*id, false))
+            .collect::<Vec<_>>(),
+        "{}: the handles are the forward handles of the nodes",
+        name
+    );
+    assert_eq!(graph.node_count(), ids.len(), "{}: node_count", name);
+    for id in ids.iter() {
+        assert!(graph.has_node(*id), "{}: has_node({})", name, id);
+    }
+    let missing = ids.last().map_or(NodeId::from(1), |id| *id + 1);
+    assert!(!graph.has_node(missing), "{}: has_node({})", name, missing);
+    if let (Some(min), Some(max)) = (ids.first(), ids.last()) {
+        assert_eq!(graph.min_node_id(), *min, "{}: min_node_id", name);
+        assert_eq!(graph.max_node_id(), *max, "{}: max_node_id", name);
+    }
+}
+
+/// Function that checks the sequences of both the handles of each node
+pub fn check_sequences_contract<'a, G>(graph: &'a G, expected: &ExpectedGraph)
+where
+    &'a G: HandleSequences,
+{
+    for handle in expected.handles() {
+        let seq = expected.sequence(handle);
+        let msg = format!("{}: the sequence of {}", expected.name, oriented(&handle));
+        assert_eq!(graph.sequence(handle), seq, "{}", msg);
+        assert_eq!(
+            graph.sequence_iter(handle).collect::<Vec<u8>>(),
+            seq,
+            "{}",
+            msg
+        );
+        assert_eq!(graph.node_len(handle), seq.len(), "{}", msg);
+        assert_eq!(
+            graph.base(handle, seq.len() - 1),
+            seq[seq.len() - 1],
+            "{}",
+            msg
+        );
+        assert_eq!(
+            graph.subsequence(handle, 1, 2),
+            seq[1..seq.len().min(3)].to_vec(),
+            "{}",
+            msg
+        );
+    }
+}
+
+/// Function that checks the neighbors of both the handles of each node,
+/// on both their sides, and the edges of the graph, each reported once
+pub fn check_neighbors_contract<'a, G>(graph: &'a G, expected: &ExpectedGraph)
+where
+    &'a G: HandleNeighbors + AllEdges,
+{
+    let name = expected.name;
+    for handle in expected.handles() {
+        for dir in [Direction::Left, Direction::Right].iter() {
+            let mut neighbors: Vec<Handle> = graph.neighbors(handle, *dir).collect();
+            neighbors.sort();
+            let count = neighbors.len();
+            neighbors.dedup();
+            assert_eq!(
+                count,
+                neighbors.len(),
+                "{}: {} has repeated neighbors",
+                name,
+                oriented(&handle)
+            );
+            assert_eq!(
+                neighbors,
+                expected.neighbors(handle, *dir),
+                "{}: the {:?} neighbors of {}",
+                name,
+                dir,
+                oriented(&handle)
+            );
+            assert_eq!(
+                graph.degree(handle, *dir),
+                count,
+                "{}: the {:?} degree of {}",
+                name,
+                dir,
+                oriented(&handle)
+            );
+        }
+    }
+
+    let canonical = |edges: &mut dyn Iterator<Item = Edge>| {
+        let mut edges: Vec<Edge> = edges.map(|Edge(l, r)| Edge::edge_handle(l, r)).collect();
+        edges.sort();
+        edges
+    };
+    let mut wanted = canonical(&mut expected.edges.iter().copied());
+    wanted.dedup();
+    assert_eq!(
+        canonical(&mut graph.edges()),
+        wanted,
+        "{}: the edges, each once",
+        name
+    );
+    assert_eq!(graph.edge_count(), wanted.len(), "{}: edge_count", name);
+    for Edge(l, r) in expected.edges.iter() {
+        assert!(
+            graph.has_edge(*l, *r),
+            "{}: has_edge({}, {})",
+            name,
+            oriented(l),
+            oriented(r)
+        );
+        assert!(
+            graph.has_edge(r.flip(), l.flip()),
+            "{}: has_edge({}, {})",
+            name,
+            oriented(&r.flip()),
+            oriented(&l.flip())
+        );
+    }
+}
+
+/// Function that checks the paths of the graph: their names, their
+/// steps and the positions of the steps, and the occurrences of the
+/// nodes.\
+/// Each occurrence has to be a step of its path on the node, and each
+/// visit of a path to a node has to be among its occurrences
+pub fn check_paths_contract<G>(graph: &G, expected: &ExpectedGraph)
+where
+    G: PathHandleGraph,
+    for<'a> &'a G: HandleSequences,
+    G::StepHandle: PartialEq,
+{
+    let name = expected.name;
+    assert_eq!(
+        graph.path_count(),
+        expected.paths.len(),
+        "{}: path_count",
+        name
+    );
+    assert!(!graph.has_path(b"not a path"), "{}: has_path", name);
+    for p in expected.paths.iter() {
+        let msg = format!("{}: the path {}", name, p.name);
+        assert!(graph.has_path(&p.name), "{}", msg);
+        let path = graph.name_to_path_handle(&p.name).expect(&msg);
+        assert_eq!(
+            graph.path_handle_to_name(&path).as_bstr(),
+            p.name,
+            "{}",
+            msg
+        );
+        assert_eq!(graph.is_circular(&path), p.circular, "{}", msg);
+        assert_eq!(graph.step_count(&path), p.steps.len(), "{}", msg);
+
+        let steps: Vec<G::StepHandle> = graph.steps(&path).collect();
+        let handles: Vec<Option<Handle>> = steps.iter().map(|s| graph.handle_of_step(s)).collect();
+        assert_eq!(
+            handles,
+            p.steps.iter().map(|h| Some(*h)).collect::<Vec<_>>(),
+            "{}: the steps",
+            msg
+        );
+        let mut position = 0;
+        for (step, handle) in steps.iter().zip(p.steps.iter()) {
+            assert_eq!(graph.position_of_step(step), Some(position), "{}", msg);
+            position += graph.node_len(*handle);
+        }
+        assert_eq!(graph.path_bases_len(&path), Some(position), "{}", msg);
+    }
+
+    for (id, _) in expected.nodes.iter() {
+        let visits = expected.visits(*id);
+        let mut occurrences: Vec<(BString, usize)> = vec![];
+        for step in graph.occurrences(Handle::pack(*id, false)) {
+            let path = graph.path_handle_of_step(&step);
+            let handle = graph.handle_of_step(&step);
+            assert_eq!(
+                handle.map(|h| h.id()),
+                Some(*id),
+                "{}: an occurrence of {} isn't on the node",
+                name,
+                id
+            );
+            let index = graph.steps(&path).position(|s| s == step).unwrap();
+            occurrences.push((graph.path_handle_to_name(&path).into(), index));
+        }
+        occurrences.sort();
+        for occurrence in occurrences.iter() {
+            assert!(
+                visits.contains(occurrence),
+                "{}: {:?} isn't a visit of {}",
+                name,
+                occurrence,
+                id
+            );
+        }
+        for visit in visits.iter() {
+            assert!(
+                occurrences.contains(visit),
+                "{}: the occurrences of {} miss the visit {:?}",
+                name,
+                id,
+                visit
+            );
+        }
+    }
+}
+
+/// Function that runs the checks of the suite that don't need the
+/// paths, for a graph without them
+pub fn check_handle_graph_contracts<G>(graph: &G, expected: &ExpectedGraph)
+where
+    G: HandleGraph,
+    for<'a> &'a G: AllHandles + AllEdges + HandleNeighbors + HandleSequences,
+{
+    check_handles_contract(graph, expected);
+    check_sequences_contract(graph, expected);
+    check_neighbors_contract(graph, expected);
+}
+
+/// Function that runs all the checks of the suite
+pub fn check_graph_contracts<G>(graph: &G, expected: &ExpectedGraph)
+where
+    G: HandleGraph + PathHandleGraph,
+    for<'a> &'a G: AllHandles + AllEdges + HandleNeighbors + HandleSequences,
+    G::StepHandle: PartialEq,
+{
+    check_handle_graph_contracts(graph, expected);
+    check_paths_contract(graph, expected);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hashgraph::HashGraph;
+    use crate::packed::PackedGraph;
+    use crate::parser::parse_file_to_graph;
+
+    /// The fixture as a GFA1 file
+    fn gfa(expected: &ExpectedGraph) -> String {
+        let orient = |h: &Handle| if h.is_reverse() { '-' } else { '+' };
+        let mut res = String::from("H\tVN:Z:1.0\n");
+        for (id, seq) in expected.nodes.iter() {
+            res.push_str(&format!("S\t{}\t{}\n", id, seq));
+        }
+        for Edge(l, r) in expected.edges.iter() {
+            res.push_str(&format!(
+                "L\t{}\t{}\t{}\t{}\t0M\n",
+                l.id(),
+                orient(l),
+                r.id(),
+                orient(r)
+            ));
+        }
+        for p in expected.paths.iter().filter(|p| !p.steps.is_empty()) {
+            let steps: Vec<String> = p
+                .steps
+                .iter()
+                .map(|h| format!("{}{}", h.id(), orient(h)))
+                .collect();
+            res.push_str(&format!("P\t{}\t{}\t*\n", p.name, steps.join(",")));
+        }
+        res
+    }
+
+    #[test]
+    fn hashgraph_conformance() {
+        for expected in fixtures() {
+            let mut graph = HashGraph::new();
+            build_graph(&mut graph, &expected).unwrap();
+            check_graph_contracts(&graph, &expected);
+        }
+    }
+
+    #[test]
+    fn packed_graph_conformance() {
+        for expected in fixtures() {
+            let mut graph = HashGraph::new();
+            build_graph(&mut graph, &expected).unwrap();
+            let packed = PackedGraph::from_hashgraph(&graph);
+            check_handle_graph_contracts(&packed, &expected);
+        }
+    }
+
+    #[test]
+    fn parsed_hashgraph_conformance() {
+        for mut expected in fixtures().into_iter().filter(|e| !e.nodes.is_empty()) {
+            // GFA1 has no circular paths nor empty ones
+            expected
+                .paths
+                .retain(|p| !p.circular && !p.steps.is_empty());
+            let path =
+                std::env::temp_dir().join(format!("gfahandlegraph_suite_{}.gfa", expected.name));
+            std::fs::write(&path, gfa(&expected)).unwrap();
+            let graph = parse_file_to_graph(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            check_graph_contracts(&graph, &expected);
+        }
+    }
+}
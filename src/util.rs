@@ -1,7 +1,23 @@
+pub mod archive;
+mod bincode;
 mod conversion;
+pub mod digest;
 pub mod dna;
+pub mod export;
+pub mod fasta;
+pub mod gzip;
+pub mod metadata;
+pub mod report;
+pub mod threads;
 pub mod to_file;
 
+pub use self::archive::*;
 pub use self::conversion::*;
+pub use self::digest::*;
 pub use self::dna::*;
+pub use self::fasta::*;
+pub use self::gzip::*;
+pub use self::metadata::*;
+pub use self::report::*;
+pub use self::threads::ThreadConfig;
 pub use self::to_file::*;
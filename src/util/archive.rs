@@ -0,0 +1,456 @@
+/// This file provides a binary archive of an HashGraph, to load again
+/// a graph without parsing the GFA file
+use crate::{
+    gfa::{gfa1::Header, optional::OptField},
+    handle::{Edge, Handle, NodeId},
+    hashgraph::{ContainmentRecord, HashGraph},
+    parser::{ParseError, Parser},
+    util::gzip::{gunzip, Compression},
+    util::to_file::save_file,
+};
+
+use std::convert::TryFrom;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const MAGIC: &[u8; 8] = b"GFAHGARC";
+/// Version of the archive layout, an archive with a different
+/// version is rejected (but the version 1, without the overlaps of
+/// the edges, and the version 2, without the names, the tags and the
+/// groups)
+pub const ARCHIVE_VERSION: u32 = 3;
+
+/// Options of [`convert_gfa_to_archive`](fn.convert_gfa_to_archive.html)
+/// ```ignore
+/// pub struct ConvertOptions {
+///     pub parser: Parser,
+///     pub allow_dangling: bool,
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    /// Parser used to read the file (line filters and error tolerance)
+    pub parser: Parser,
+    /// Drop the edges and path steps referencing missing segments,
+    /// reporting them as warnings, instead of failing
+    pub allow_dangling: bool,
+}
+
+/// Summary of a conversion
+/// ```ignore
+/// pub struct ConvertReport {
+///     pub nodes: usize,
+///     pub edges: usize,
+///     pub paths: usize,
+///     pub build_time: Duration,
+///     pub write_time: Duration,
+///     pub warnings: Vec<String>,
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConvertReport {
+    pub nodes: usize,
+    pub edges: usize,
+    pub paths: usize,
+    /// Time spent reading the file and building the graph
+    pub build_time: Duration,
+    /// Time spent writing the archive
+    pub write_time: Duration,
+    /// The references to missing segments that were dropped
+    pub warnings: Vec<String>,
+}
+
+fn write_u64<W: Write>(out: &mut W, value: u64) -> std::io::Result<()> {
+    out.write_all(&value.to_le_bytes())
+}
+
+fn write_bytes<W: Write>(out: &mut W, bytes: &[u8]) -> std::io::Result<()> {
+    write_u64(out, bytes.len() as u64)?;
+    out.write_all(bytes)
+}
+
+fn write_tags<W: Write>(out: &mut W, tags: &[OptField]) -> std::io::Result<()> {
+    write_u64(out, tags.len() as u64)?;
+    for tag in tags.iter() {
+        out.write_all(&tag.tag)?;
+        out.write_all(&[tag.kind])?;
+        write_bytes(out, &tag.value)?;
+    }
+    Ok(())
+}
+
+/// The layout of the archive, after the magic bytes and the version:
+/// * the nodes, sorted by id, as id, sequence, name (empty if it's the
+///   id) and tags
+/// * the distinct edges, sorted, as pairs of handles, their overlap and
+///   their tags
+/// * the paths, sorted by id, as name, circular flag, steps and tags
+/// * the segments without a sequence, sorted by id, as id and declared
+///   length
+/// * the subsets, sorted by name, as name and ids
+/// * the containments, in their order, as the two handles, position and
+///   overlap
+/// * the header, as a flag, then its version (a flag and the bytes) and
+///   its tags
+///
+/// where every number is a little endian u64, every sequence or name
+/// is preceded by its length, and a list of tags by their number, each
+/// as tag, type and value. Returns the number of edges written
+fn write_archive<W: Write>(graph: &HashGraph, out: &mut W) -> std::io::Result<usize> {
+    out.write_all(MAGIC)?;
+    out.write_all(&ARCHIVE_VERSION.to_le_bytes())?;
+
+    write_u64(out, graph.graph.len() as u64)?;
+    for handle in graph.handles_sorted() {
+        let node = graph.get_node_unchecked(&handle.id());
+        write_u64(out, u64::from(handle.id()))?;
+        write_bytes(out, graph.node_sequence(node))?;
+        write_bytes(out, graph.node_name(handle.id()).unwrap_or_default())?;
+        write_tags(out, &node.optional)?;
+    }
+
+    let mut edges: Vec<Edge> = graph.edges_sorted().collect();
+    edges.dedup();
+    write_u64(out, edges.len() as u64)?;
+    for Edge(l, r) in edges.iter() {
+        write_u64(out, l.as_integer())?;
+        write_u64(out, r.as_integer())?;
+        write_u64(out, graph.edge_overlap(Edge(*l, *r)) as u64)?;
+        write_tags(out, graph.edge_tags(Edge(*l, *r)))?;
+    }
+
+    let mut path_ids: Vec<_> = graph.paths.keys().copied().collect();
+    path_ids.sort();
+    write_u64(out, path_ids.len() as u64)?;
+    for path_id in path_ids {
+        let path = graph.get_path_unchecked(&path_id);
+        write_bytes(out, &path.name)?;
+        out.write_all(&[path.is_circular as u8])?;
+        write_u64(out, path.nodes.len() as u64)?;
+        for step in path.nodes.iter() {
+            write_u64(out, step.as_integer())?;
+        }
+        write_tags(out, &path.optional)?;
+    }
+
+    let mut placeholders: Vec<(&NodeId, &usize)> = graph.placeholders.iter().collect();
+    placeholders.sort();
+    write_u64(out, placeholders.len() as u64)?;
+    for (id, declared) in placeholders {
+        write_u64(out, u64::from(*id))?;
+        write_u64(out, *declared as u64)?;
+    }
+
+    let names = graph.subset_names();
+    write_u64(out, names.len() as u64)?;
+    for name in names {
+        let ids = graph.subset(name).unwrap_or_default();
+        write_bytes(out, name)?;
+        write_u64(out, ids.len() as u64)?;
+        for id in ids.iter() {
+            write_u64(out, u64::from(*id))?;
+        }
+    }
+
+    write_u64(out, graph.containments().len() as u64)?;
+    for c in graph.containments().iter() {
+        write_u64(out, c.container.as_integer())?;
+        write_u64(out, c.contained.as_integer())?;
+        write_u64(out, c.pos as u64)?;
+        write_bytes(out, &c.overlap)?;
+    }
+
+    match &graph.header {
+        Some(header) => {
+            out.write_all(&[1])?;
+            match &header.version {
+                Some(version) => {
+                    out.write_all(&[1])?;
+                    write_bytes(out, version)?;
+                }
+                None => out.write_all(&[0])?,
+            }
+            write_tags(out, &header.tags)?;
+        }
+        None => out.write_all(&[0])?,
+    }
+    Ok(edges.len())
+}
+
+/// Function that saves an HashGraph as a binary archive, that can be
+/// loaded again with [`load_archive`](fn.load_archive.html).\
+/// The archive is written to a temporary file next to the destination
+/// and then renamed, so an existing archive is never left half written;
+/// it's compressed with gzip when the path ends with ```.gz```.\
+/// The paths are stored sorted by id, and get back consecutive ids
+/// (in the same order) when loaded. Returns the number of edges written
+/// # Example
+/// ```ignore
+/// save_archive(&graph, "./graph.hga").unwrap();
+/// ```
+pub fn save_archive<P: AsRef<Path>>(graph: &HashGraph, path: P) -> std::io::Result<usize> {
+    save_file(path.as_ref(), Compression::None, |out| {
+        write_archive(graph, out)
+    })
+}
+
+/// Reader over the bytes of an archive
+struct Cursor<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ParseError> {
+        if self.bytes.len() < len {
+            return Err(ParseError::InvalidArchive(
+                "unexpected end of file".to_string(),
+            ));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u64(&mut self) -> Result<u64, ParseError> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn len(&mut self) -> Result<usize, ParseError> {
+        let len = usize::try_from(self.u64()?).unwrap_or(usize::MAX);
+        // every item takes at least a byte, so a bigger length is corrupted
+        if len > self.bytes.len() {
+            return Err(ParseError::InvalidArchive(
+                "length out of bounds".to_string(),
+            ));
+        }
+        Ok(len)
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, ParseError> {
+        let len = self.len()?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn flag(&mut self) -> Result<bool, ParseError> {
+        Ok(self.take(1)?[0] != 0)
+    }
+
+    fn tags(&mut self) -> Result<Vec<OptField>, ParseError> {
+        let count = self.len()?;
+        let mut tags = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut tag = [0; 2];
+            tag.copy_from_slice(self.take(2)?);
+            let kind = self.take(1)?[0];
+            tags.push(OptField::new(&tag, kind, &self.bytes()?));
+        }
+        Ok(tags)
+    }
+}
+
+/// Function that loads an HashGraph saved with
+/// [`save_archive`](fn.save_archive.html) or
+/// [`convert_gfa_to_archive`](fn.convert_gfa_to_archive.html).\
+/// An archive compressed with gzip is decompressed first.\
+/// Fails with ```InvalidArchive``` if the file isn't an archive, it was
+/// written with a different version, or it's corrupted
+/// # Example
+/// ```ignore
+/// let graph = load_archive("./graph.hga").unwrap();
+/// ```
+pub fn load_archive<P: AsRef<Path>>(path: P) -> Result<HashGraph, ParseError> {
+    let mut bytes = std::fs::read(path.as_ref())?;
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        bytes = gunzip(&bytes).map_err(|why| ParseError::InvalidArchive(why.to_string()))?;
+    }
+    let mut cursor = Cursor { bytes: &bytes };
+
+    if cursor.take(MAGIC.len()).ok() != Some(&MAGIC[..]) {
+        return Err(ParseError::InvalidArchive(
+            "not a graph archive".to_string(),
+        ));
+    }
+    let mut version = [0; 4];
+    version.copy_from_slice(cursor.take(4)?);
+    let version = u32::from_le_bytes(version);
+    if version != ARCHIVE_VERSION && version != 1 {
+        return Err(ParseError::InvalidArchive(format!(
+            "version {} is not supported (expected {})",
+            version, ARCHIVE_VERSION
+        )));
+    }
+
+    // the names, the tags and the groups are found from the version 3
+    let described = version > 2;
+    let count = cursor.len()?;
+    let mut nodes = Vec::with_capacity(count);
+    let mut node_names = vec![];
+    let mut node_tags = vec![];
+    for _ in 0..count {
+        let id = NodeId::from(cursor.u64()?);
+        nodes.push((id, cursor.bytes()?));
+        if described {
+            node_names.push((id, cursor.bytes()?));
+            node_tags.push((id, cursor.tags()?));
+        }
+    }
+    let count = cursor.len()?;
+    let mut edges = Vec::with_capacity(count);
+    let mut overlaps = vec![];
+    let mut edge_tags = vec![];
+    for _ in 0..count {
+        let l = Handle::from_integer(cursor.u64()?);
+        let r = Handle::from_integer(cursor.u64()?);
+        edges.push(Edge(l, r));
+        if version > 1 {
+            let overlap = usize::try_from(cursor.u64()?).unwrap_or(usize::MAX);
+            if overlap > 0 {
+                overlaps.push((Edge(l, r), overlap));
+            }
+        }
+        if described {
+            edge_tags.push((Edge(l, r), cursor.tags()?));
+        }
+    }
+    let count = cursor.len()?;
+    let mut paths = Vec::with_capacity(count);
+    let mut path_tags = vec![];
+    for _ in 0..count {
+        let name = cursor.bytes()?;
+        let is_circular = cursor.flag()?;
+        let len = cursor.len()?;
+        let mut steps = Vec::with_capacity(len);
+        for _ in 0..len {
+            steps.push(Handle::from_integer(cursor.u64()?));
+        }
+        if described {
+            path_tags.push((name.clone(), cursor.tags()?));
+        }
+        paths.push((name, steps, is_circular));
+    }
+    let mut placeholders = vec![];
+    let mut subsets = vec![];
+    let mut containments = vec![];
+    let mut header = None;
+    if described {
+        for _ in 0..cursor.len()? {
+            let id = NodeId::from(cursor.u64()?);
+            placeholders.push((id, usize::try_from(cursor.u64()?).unwrap_or(usize::MAX)));
+        }
+        for _ in 0..cursor.len()? {
+            let name = cursor.bytes()?;
+            let len = cursor.len()?;
+            let mut ids = Vec::with_capacity(len);
+            for _ in 0..len {
+                ids.push(cursor.u64()?);
+            }
+            subsets.push((name, ids));
+        }
+        for _ in 0..cursor.len()? {
+            containments.push(ContainmentRecord {
+                container: Handle::from_integer(cursor.u64()?),
+                contained: Handle::from_integer(cursor.u64()?),
+                pos: usize::try_from(cursor.u64()?).unwrap_or(usize::MAX),
+                overlap: cursor.bytes()?.into(),
+            });
+        }
+        if cursor.flag()? {
+            let version = if cursor.flag()? {
+                Some(cursor.bytes()?.into())
+            } else {
+                None
+            };
+            let tags = cursor.tags()?;
+            header = Some(Header { version, tags });
+        }
+    }
+    if !cursor.bytes.is_empty() {
+        return Err(ParseError::InvalidArchive(
+            "unexpected data at the end of file".to_string(),
+        ));
+    }
+
+    let mut graph = HashGraph::from_parts(nodes, edges, paths)
+        .map_err(|why| ParseError::InvalidArchive(why.to_string()))?;
+    for (edge, overlap) in overlaps {
+        graph
+            .set_edge_overlap(edge, overlap)
+            .map_err(|why| ParseError::InvalidArchive(why.to_string()))?;
+    }
+    for (id, name) in node_names {
+        graph.set_node_name(id, name.into());
+    }
+    for (id, tags) in node_tags {
+        graph.set_node_tags(id, tags);
+    }
+    for (edge, tags) in edge_tags {
+        graph.set_edge_tags(edge, tags);
+    }
+    for (name, tags) in path_tags {
+        let path_id = graph.path_id[&name];
+        graph.paths.get_mut(&path_id).unwrap().optional = tags;
+    }
+    graph.mark_placeholders(placeholders);
+    for (name, ids) in subsets {
+        graph.add_subset(name.into(), &ids);
+    }
+    for containment in containments {
+        graph.add_containment(containment);
+    }
+    graph.header = header;
+    Ok(graph)
+}
+
+/// Function that converts a GFA or GFA2 file to a graph archive in a
+/// single pass: the lines are added to the graph as soon as they are
+/// read, using
+/// [`parse_file_incremental`](../../parser/struct.Parser.html#method.parse_file_incremental),
+/// so the GFA objects are never held in memory together with the graph
+/// and the peak memory is about the size of the graph.\
+/// References to missing segments make the conversion fail, unless
+/// ```allow_dangling``` is set; in that case they are dropped and
+/// listed in the warnings of the report
+/// # Example
+/// ```ignore
+/// let report = convert_gfa_to_archive(
+///     "./tests/big_files/test.gfa",
+///     "./test.hga",
+///     &ConvertOptions::default(),
+/// ).unwrap();
+/// println!("{} nodes in {:?}", report.nodes, report.build_time);
+/// ```
+pub fn convert_gfa_to_archive<P: AsRef<Path>, Q: AsRef<Path>>(
+    gfa: P,
+    archive: Q,
+    opts: &ConvertOptions,
+) -> Result<ConvertReport, ParseError> {
+    let start = Instant::now();
+    let builder = opts.parser.parse_file_incremental(gfa)?;
+    let (graph, warnings) = if opts.allow_dangling {
+        let (graph, unresolved) = builder.finish();
+        (graph, unresolved.iter().map(|u| u.to_string()).collect())
+    } else {
+        match builder.finish_strict() {
+            Ok(graph) => (graph, vec![]),
+            Err(unresolved) => {
+                let why: Vec<String> = unresolved.iter().map(|u| u.to_string()).collect();
+                return Err(ParseError::ConversionGFAToGraph(why.join("\n")));
+            }
+        }
+    };
+    let build_time = start.elapsed();
+
+    let start = Instant::now();
+    let edges = save_archive(&graph, archive)?;
+    Ok(ConvertReport {
+        nodes: graph.graph.len(),
+        edges,
+        paths: graph.paths.len(),
+        build_time,
+        write_time: start.elapsed(),
+        warnings,
+    })
+}
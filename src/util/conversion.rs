@@ -1,167 +1,427 @@
+/// This file provides the conversion between the GFA and the GFA2
+/// objects, and from a GFA file to a GFA2 file
+use crate::gfa::{
+    cigar::CIGAR,
+    gfa1::{Containment, Header as Header1, Link, Path, Segment as Segment1, GFA},
+    gfa2::{Edge, GroupO, Header as Header2, Segment as Segment2, GFA2},
+    orientation::Orientation,
+};
+use crate::parser::{GFAParser, ParseError};
+
 use bstr::{BString, ByteSlice};
-use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
-use std::str;
-
-use rayon::iter::{ParallelBridge, ParallelIterator};
-use std::sync::Mutex;
-
-/// Very BASIC converter from
-/// [`GFA`](file:///D:/GitHub/rs-gfahandlegraph/target/doc/gfahandlegraph/gfa/gfa1/struct.GFA.html) format to
-/// [`GFA2`](file:///D:/GitHub/rs-gfahandlegraph/target/doc/gfahandlegraph/gfa/gfa2/struct.GFA2.html) format.\
-/// For now it consider only S-, L- and P- lines,
-/// ignoring all the others.
-pub fn gfa_file_to_gfa2(path: String) -> std::io::Result<()> {
-    let mut file = File::create(format!("{}{}", &path, 2))?;
-    let res = Mutex::new(String::new());
-    let reader = BufReader::new(File::open(&path)?).lines();
-
-    reader.par_bridge().for_each(|line| {
-        let line = line.unwrap();
-        let mut line_split = line.split_whitespace();
-        let prefix = line_split.next().unwrap();
-
-        match prefix {
-            "H" => {
-                let mut tag = line_split.next();
-                let mut opt_fields: Vec<&[u8]> = vec![];
-                let mut version: BString = BString::from("");
-                while tag.is_some() {
-                    if tag.unwrap() == "VN:Z:1.0" {
-                        version = BString::from("VN:Z:2.0");
-                    } else {
-                        opt_fields.push(tag.unwrap().as_bytes());
-                    }
-                    tag = line_split.next();
-                }
-                let mut tag = opt_fields
-                    .into_iter()
-                    .map(|x| BString::from(str::from_utf8(x).unwrap().to_owned() + "\t"))
-                    .collect::<BString>();
-                tag.pop();
-
-                res.lock()
-                    .unwrap()
-                    .push_str(&*format!("H\t{}\t{}\n", version, tag));
-            }
-            "S" => {
-                let id = line_split.next().unwrap().to_string();
-                let sequence = BString::from(line_split.next().unwrap());
-                let len = BString::from(sequence.len().to_string());
-
-                let mut tag = line_split.next();
-                let mut opt_fields: Vec<&[u8]> = vec![];
-                while tag.is_some() {
-                    opt_fields.push(tag.unwrap().as_bytes());
-                    tag = line_split.next();
-                }
-                let mut tag = opt_fields
-                    .into_iter()
-                    .map(|x| BString::from(str::from_utf8(x).unwrap().to_owned() + "\t"))
-                    .collect::<BString>();
-                tag.pop();
-
-                res.lock()
-                    .unwrap()
-                    .push_str(&*format!("S\t{}\t{}\t{}\t{}\n", id, len, sequence, tag));
-            }
-            "L" => {
-                // placeholder value
-                let id = "*".to_string();
-
-                let from_node = line_split.next().unwrap().to_string();
-                let from_node_orient = line_split.next().unwrap().to_string();
-                let to_node = line_split.next().unwrap().to_string();
-                let to_node_orient = line_split.next().unwrap().to_string();
-                let alignment = BString::from(line_split.next().unwrap());
-
-                // placeholder values
-                let mut beg1 = BString::from("0");
-                let mut end1 = BString::from("0$");
-                let mut beg2 = BString::from("0");
-                let mut end2 = BString::from("0$");
-
-                if alignment != "*" {
-                    let len = alignment.len() - 1;
-                    let dist = alignment[..len].to_str().unwrap().parse::<i64>().unwrap();
-
-                    if from_node_orient == "+" && to_node_orient == "+" {
-                        let x = (100 - dist).abs();
-                        beg1 = BString::from(x.to_string());
-                        end1 = BString::from("100$");
-                        end2 = BString::from(dist.to_string());
-                    } else if from_node_orient == "-" && to_node_orient == "-" {
-                        let x = (100 - dist).abs();
-                        end1 = BString::from(dist.to_string());
-                        beg2 = BString::from(x.to_string());
-                        end2 = BString::from("100$");
-                    } else if from_node_orient == "-" && to_node_orient == "+" {
-                        end1 = BString::from(dist.to_string());
-                        end2 = BString::from(dist.to_string());
-                    } else if from_node_orient == "+" && to_node_orient == "-" {
-                        let x = (100 - dist).abs();
-                        beg1 = BString::from(x.to_string());
-                        end1 = BString::from("100$");
-                        beg2 = BString::from(x.to_string());
-                        end2 = BString::from("100$");
-                    }
-                }
-
-                let mut tag = line_split.next();
-                let mut opt_fields: Vec<&[u8]> = vec![];
-                while tag.is_some() {
-                    opt_fields.push(tag.unwrap().as_bytes());
-                    tag = line_split.next();
-                }
-                let mut tag = opt_fields
-                    .into_iter()
-                    .map(|x| BString::from(str::from_utf8(x).unwrap().to_owned() + "\t"))
-                    .collect::<BString>();
-                tag.pop();
-
-                res.lock().unwrap().push_str(&*format!(
-                    "E\t{}\t{}{}\t{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
-                    id,
-                    from_node,
-                    from_node_orient,
-                    to_node,
-                    to_node_orient,
-                    beg1,
-                    end1,
-                    beg2,
-                    end2,
-                    alignment,
-                    tag
-                ));
+use fnv::FnvHashMap;
+use std::{error, fmt};
+
+/// Type encapsulating the records of a
+/// [`GFA2`](../../gfa/gfa2/struct.GFA2.html) object that can't be kept by
+/// a GFA object, see [`gfa2_to_gfa`](fn.gfa2_to_gfa.html)
+/// ```ignore
+/// pub enum ConversionError {
+///     UnsupportedRecord(String),
+///     NotDovetail(String),
+///     UnnamedGroup(usize),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConversionError {
+    /// A record (its line type) that GFA doesn't have, as the
+    /// fragments, the gaps and the unordered groups
+    UnsupportedRecord(String),
+    /// An edge (as ```11+,12-```) that doesn't overlap the ends of its
+    /// segments, so it isn't a link
+    NotDovetail(String),
+    /// An ordered group (its index among the groups) without a name, that
+    /// a path needs
+    UnnamedGroup(usize),
+}
+
+impl ConversionError {
+    /// The stable code of the error, see the
+    /// [`report`](../report/index.html) module
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConversionError::UnsupportedRecord(_) => "C001",
+            ConversionError::NotDovetail(_) => "C002",
+            ConversionError::UnnamedGroup(_) => "C003",
+        }
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::UnsupportedRecord(line) => {
+                write!(f, "The {} lines can't be converted to GFA", line)
             }
-            "P" => {
-                let id = BString::from(line_split.next().unwrap());
-                let seg_ids = line_split.next().unwrap();
-                let var_field = BString::from(str::replace(seg_ids, ",", " "));
-
-                let mut tag = line_split.next();
-                let mut opt_fields: Vec<&[u8]> = vec![];
-                while tag.is_some() {
-                    opt_fields.push(tag.unwrap().as_bytes());
-                    tag = line_split.next();
-                }
-                let mut tag = opt_fields
-                    .into_iter()
-                    .map(|x| BString::from(str::from_utf8(x).unwrap().to_owned() + "\t"))
-                    .collect::<BString>();
-                tag.pop();
-
-                res.lock()
-                    .unwrap()
-                    .push_str(&*format!("P\t{}\t{}\t{}\n", id, var_field, tag));
+            ConversionError::NotDovetail(edge) => write!(
+                f,
+                "The edge {} doesn't overlap the ends of its segments",
+                edge
+            ),
+            ConversionError::UnnamedGroup(ix) => {
+                write!(f, "The group {} has no name for its path", ix)
             }
-            // ignore all the other lines (typically C- and comment-lines)
-            _ => (),
         }
-    });
-    file.write_all(res.into_inner().unwrap().as_bytes())?;
-    file.sync_all()?;
-    Ok(())
+    }
+}
+
+impl error::Error for ConversionError {}
+
+/// The length of a segment, the one of its ```LN``` tag if the sequence
+/// isn't written
+fn segment_len(sequence: &BString, tags: &[crate::gfa::optional::OptField]) -> usize {
+    if sequence != "*" {
+        return sequence.len();
+    }
+    tags.iter()
+        .find(|t| &t.tag == b"LN")
+        .and_then(|t| t.value.to_str().ok()?.parse().ok())
+        .unwrap_or(0)
+}
+
+/// The bases of the 2 segments covered by a CIGAR, None if it isn't one
+fn cigar_lengths(cigar: &[u8]) -> Option<(usize, usize)> {
+    let cigar = CIGAR::from_bytes(cigar).ok()?;
+    Some((cigar.ref_len(), cigar.query_len()))
+}
+
+/// A GFA2 position, with the final ```$``` at the end of the segment
+fn position(value: usize, len: usize) -> BString {
+    if value == len {
+        BString::from(format!("{}$", value))
+    } else {
+        BString::from(value.to_string())
+    }
+}
+
+/// The number of a GFA2 position, and if it's at the end of the segment
+fn parse_position(pos: &[u8]) -> (usize, bool) {
+    let (value, at_end) = match pos.split_last() {
+        Some((b'$', value)) => (value, true),
+        _ => (pos, false),
+    };
+    let value = value.to_str().ok().and_then(|v| v.parse().ok());
+    (value.unwrap_or(0), at_end)
+}
+
+fn orient_sign(orient: Orientation) -> char {
+    match orient {
+        Orientation::Forward => '+',
+        Orientation::Backward => '-',
+    }
+}
+
+/// Function that converts a
+/// [`GFA`](../../gfa/gfa1/struct.GFA.html) object to a
+/// [`GFA2`](../../gfa/gfa2/struct.GFA2.html) object:
+/// * the segments keep their id, name, sequence and tags
+/// * a link becomes an edge whose positions are the bases covered by its
+///   overlap, at the end of the first segment and at the start of the
+///   second one (the other way around if they're reversed); the CIGAR is
+///   the alignment of the edge, an overlap ```*``` is an edge without
+///   bases in common and without alignment
+/// * a containment becomes an edge covering the whole contained segment,
+///   and the bases of the container from its position on, as many as the
+///   overlap covers (the length of the contained segment if it's ```*```)
+/// * a path becomes an ordered group of the same segments, without the
+///   overlaps, as they're already on the edges
+///
+/// The lengths of the segments are their sequences (or their ```LN```
+/// tag), an overlap that isn't a CIGAR is copied as the alignment without
+/// bases in common, and the lines that aren't GFA are copied as they are
+/// # Example
+/// ```ignore
+/// let gfa = GFAParser::new().parse_file("./tests/gfa1_files/lil.gfa")?;
+/// let gfa2 = gfa_to_gfa2(&gfa);
+/// ```
+pub fn gfa_to_gfa2(gfa: &GFA) -> GFA2 {
+    let lens: FnvHashMap<u64, usize> = gfa
+        .segments
+        .iter()
+        .map(|s| (s.name, segment_len(&s.sequence, &s.optional)))
+        .collect();
+
+    let headers = gfa
+        .headers
+        .iter()
+        .map(|h| Header2 {
+            version: h.version.as_ref().map(|_| BString::from("2.0")),
+            tags: h.tags.clone(),
+        })
+        .collect();
+    let segments = gfa
+        .segments
+        .iter()
+        .map(|s| Segment2 {
+            id: s.name,
+            original_name: s.original_name.clone(),
+            sequence: s.sequence.clone(),
+            slen: lens[&s.name] as u64,
+            optional: s.optional.clone(),
+        })
+        .collect();
+    let edges = gfa
+        .links
+        .iter()
+        .map(|l| link_to_edge(l, &lens))
+        .chain(
+            gfa.containments
+                .iter()
+                .map(|c| containment_to_edge(c, &lens)),
+        )
+        .collect();
+    let groups_o = gfa
+        .paths
+        .iter()
+        .map(|p| GroupO {
+            id: p.path_name.clone(),
+            var_field: p.segment_names.replace(",", " ").into(),
+            optional: p.optional.clone(),
+        })
+        .collect();
+
+    GFA2 {
+        headers,
+        segments,
+        edges,
+        groups_o,
+        unknown: gfa.unknown.clone(),
+        custom: gfa.custom.clone(),
+        ..Default::default()
+    }
+}
+
+fn link_to_edge(link: &Link, lens: &FnvHashMap<u64, usize>) -> Edge {
+    let len = |id: u64| lens.get(&id).copied().unwrap_or(0);
+    let (len1, len2) = (len(link.from_segment), len(link.to_segment));
+    let (ov1, ov2) = cigar_lengths(&link.overlap).unwrap_or((0, 0));
+    let (ov1, ov2) = (ov1.min(len1), ov2.min(len2));
+    let (beg1, end1) = match link.from_orient {
+        Orientation::Forward => (len1 - ov1, len1),
+        Orientation::Backward => (0, ov1),
+    };
+    let (beg2, end2) = match link.to_orient {
+        Orientation::Forward => (0, ov2),
+        Orientation::Backward => (len2 - ov2, len2),
+    };
+    let alignment: &[u8] = if link.overlap.is_empty() {
+        b"*"
+    } else {
+        &link.overlap
+    };
+    Edge {
+        optional: link.optional.clone(),
+        ..Edge::new(
+            link.from_segment,
+            link.from_orient,
+            link.to_segment,
+            link.to_orient,
+        )
+        .with_alignment(
+            [
+                &position(beg1, len1),
+                &position(end1, len1),
+                &position(beg2, len2),
+                &position(end2, len2),
+            ],
+            alignment,
+        )
+    }
+}
+
+fn containment_to_edge(containment: &Containment, lens: &FnvHashMap<u64, usize>) -> Edge {
+    let len = |id: u64| lens.get(&id).copied().unwrap_or(0);
+    let (len1, len2) = (
+        len(containment.container_name),
+        len(containment.contained_name),
+    );
+    let covered = cigar_lengths(&containment.overlap).map_or(len2, |(ov1, _)| ov1);
+    let beg1 = containment.pos.min(len1);
+    let end1 = (beg1 + covered).min(len1);
+    let alignment: &[u8] = if containment.overlap.is_empty() {
+        b"*"
+    } else {
+        &containment.overlap
+    };
+    Edge {
+        optional: containment.optional.clone(),
+        ..Edge::new(
+            containment.container_name,
+            containment.container_orient,
+            containment.contained_name,
+            containment.contained_orient,
+        )
+        .with_alignment(
+            [
+                &position(beg1, len1),
+                &position(end1, len1),
+                &position(0, len2),
+                &position(len2, len2),
+            ],
+            alignment,
+        )
+    }
+}
+
+/// Function that converts a
+/// [`GFA2`](../../gfa/gfa2/struct.GFA2.html) object to a
+/// [`GFA`](../../gfa/gfa1/struct.GFA.html) object, the other way around of
+/// [`gfa_to_gfa2`](fn.gfa_to_gfa2.html):
+/// * an edge becomes a link if it overlaps the ends of its segments
+///   (a dovetail), with its alignment if it's a CIGAR, otherwise with a
+///   match as long as the overlap if it's the same on both the segments
+/// * an ordered group becomes a path, with the overlaps ```*```
+///
+/// Fails with the first record that GFA can't keep, see
+/// [`gfa2_to_gfa_with_warnings`](fn.gfa2_to_gfa_with_warnings.html) to
+/// leave them out instead
+/// # Example
+/// ```ignore
+/// let gfa2 = GFA2Parser::new().parse_file("./tests/gfa2_files/spec_q7.gfa2")?;
+/// let gfa = gfa2_to_gfa(&gfa2)?;
+/// ```
+pub fn gfa2_to_gfa(gfa2: &GFA2) -> Result<GFA, ConversionError> {
+    let (gfa, mut warnings) = gfa2_to_gfa_with_warnings(gfa2);
+    if warnings.is_empty() {
+        Ok(gfa)
+    } else {
+        Err(warnings.swap_remove(0))
+    }
+}
+
+/// Function that converts a
+/// [`GFA2`](../../gfa/gfa2/struct.GFA2.html) object to a
+/// [`GFA`](../../gfa/gfa1/struct.GFA.html) object as
+/// [`gfa2_to_gfa`](fn.gfa2_to_gfa.html), leaving out the records that GFA
+/// can't keep and returning them as warnings, one for each type of line
+/// and one for each edge or group left out
+/// # Example
+/// ```ignore
+/// let (gfa, warnings) = gfa2_to_gfa_with_warnings(&gfa2);
+/// for why in warnings {
+///     println!("{}", why);
+/// }
+/// ```
+pub fn gfa2_to_gfa_with_warnings(gfa2: &GFA2) -> (GFA, Vec<ConversionError>) {
+    let mut warnings = vec![];
+    for (line, count) in [
+        ("F", gfa2.fragments.len()),
+        ("G", gfa2.gaps.len()),
+        ("U", gfa2.groups_u.len()),
+    ]
+    .iter()
+    {
+        if *count > 0 {
+            warnings.push(ConversionError::UnsupportedRecord(line.to_string()));
+        }
+    }
+
+    let headers = gfa2
+        .headers
+        .iter()
+        .map(|h| Header1 {
+            version: h.version.as_ref().map(|_| BString::from("1.0")),
+            // the trace spacing is only of the GFA2 alignments
+            tags: h.tags.iter().filter(|t| &t.tag != b"TS").cloned().collect(),
+        })
+        .collect();
+    let segments = gfa2
+        .segments
+        .iter()
+        .map(|s| Segment1 {
+            name: s.id,
+            original_name: s.original_name.clone(),
+            sequence: s.sequence.clone(),
+            optional: s.optional.clone(),
+        })
+        .collect();
+    let mut links = vec![];
+    for edge in gfa2.edges.iter() {
+        match edge_to_link(edge) {
+            Ok(link) => links.push(link),
+            Err(why) => warnings.push(why),
+        }
+    }
+    let mut paths = vec![];
+    for (ix, group) in gfa2.groups_o.iter().enumerate() {
+        if group.id.is_empty() || group.id == "*" {
+            warnings.push(ConversionError::UnnamedGroup(ix));
+            continue;
+        }
+        paths.push(Path {
+            optional: group.optional.clone(),
+            ..Path::new(group.id.clone(), group.var_field.replace(" ", ",").into())
+        });
+    }
+
+    let gfa = GFA {
+        headers,
+        segments,
+        links,
+        paths,
+        unknown: gfa2.unknown.clone(),
+        custom: gfa2.custom.clone(),
+        ..Default::default()
+    };
+    (gfa, warnings)
+}
+
+fn edge_to_link(edge: &Edge) -> Result<Link, ConversionError> {
+    let (beg1, _) = parse_position(&edge.beg1);
+    let (end1, end1_at_end) = parse_position(&edge.end1);
+    let (beg2, _) = parse_position(&edge.beg2);
+    let (end2, end2_at_end) = parse_position(&edge.end2);
+    let (ov1, ov2) = (end1.saturating_sub(beg1), end2.saturating_sub(beg2));
+
+    // the overlap is at the end of a forward segment leaving the edge, and
+    // at the start of a forward segment entering it, while an edge without
+    // bases in common is always a link
+    let first = ov1 == 0
+        || match edge.sid1_orient {
+            Orientation::Forward => end1_at_end,
+            Orientation::Backward => beg1 == 0,
+        };
+    let second = ov2 == 0
+        || match edge.sid2_orient {
+            Orientation::Forward => beg2 == 0,
+            Orientation::Backward => end2_at_end,
+        };
+    if !(first && second) {
+        return Err(ConversionError::NotDovetail(format!(
+            "{}{},{}{}",
+            edge.sid1,
+            orient_sign(edge.sid1_orient),
+            edge.sid2,
+            orient_sign(edge.sid2_orient)
+        )));
+    }
+
+    let overlap = if cigar_lengths(&edge.alignment).is_some() {
+        edge.alignment.clone()
+    } else if ov1 == ov2 && (ov1 > 0 || edge.alignment != "*") {
+        BString::from(format!("{}M", ov1))
+    } else {
+        BString::from("*")
+    };
+    Ok(Link {
+        optional: edge.optional.clone(),
+        ..Link::new(edge.sid1, edge.sid1_orient, edge.sid2, edge.sid2_orient).with_overlap(&overlap)
+    })
+}
+
+/// Converter from a
+/// [`GFA`](../../gfa/gfa1/struct.GFA.html) file to a
+/// [`GFA2`](../../gfa/gfa2/struct.GFA2.html) file, written in the same
+/// path followed by ```2```, as the file is parsed and converted by
+/// [`gfa_to_gfa2`](fn.gfa_to_gfa2.html)
+pub fn gfa_file_to_gfa2(path: String) -> std::io::Result<()> {
+    let gfa = GFAParser::new()
+        .parse_file(&path)
+        .map_err(|why| match why {
+            ParseError::IOError(why) => why,
+            why => std::io::Error::new(std::io::ErrorKind::InvalidData, why.to_string()),
+        })?;
+    std::fs::write(format!("{}{}", &path, 2), gfa_to_gfa2(&gfa).to_string())
 }
 
 #[cfg(test)]
@@ -177,9 +437,9 @@ mod test {
             "./tests/big_files/CHM13v1Y-GRCh38-HPP58-0.12.gfa",
             "./tests/big_files/GRCh38-20-0.10b.gfa",
         ];
-        for i in 0..3 {
+        for file in FILES.iter() {
             let start = Instant::now();
-            let path: String = FILES[i].to_string();
+            let path: String = file.to_string();
             match gfa_file_to_gfa2(path.clone()) {
                 Err(why) => println!("Error: {}", why),
                 _ => println!("Convert file from GFA to GFA2 {:?}", start.elapsed()),
@@ -238,11 +498,10 @@ mod test {
             "./tests/big_files/diatom.gfa",
             "./tests/big_files/test.gfa",
         ];
-        for i in 0..6 {
-            let path = FILES[i].to_string();
-            match gfa_file_to_gfa2(path.clone()) {
-                Err(why) => println!("Error: {}", why),
-                _ => (),
+        for file in FILES.iter() {
+            let path = file.to_string();
+            if let Err(why) = gfa_file_to_gfa2(path.clone()) {
+                println!("Error: {}", why)
             }
         }
     }
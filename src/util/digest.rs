@@ -0,0 +1,275 @@
+//! The canonical digests of the graphs and of the GFA objects, to
+//! fingerprint their content for caching or provenance.
+//!
+//! A digest is computed over a normalized form of the records, so it
+//! doesn't depend on the order they were parsed in (as by the parallel
+//! parsers) nor on the order of the tags of a record:
+//! * the segments (the nodes) sorted by id, with their sequence and
+//!   their tags sorted
+//! * the edges in their canonical orientation, sorted, with their
+//!   overlap: the CIGAR of a GFA1 link as written, the positions of a
+//!   GFA2 edge on each of its segments, the bases of an HashGraph edge,
+//!   and their tags sorted
+//! * the paths (the O-groups) sorted by name, with their steps in order
+//!   and their tags sorted
+//!
+//! The other records, as the headers, the containments or the
+//! fragments, aren't part of the digest. Every field is written with a
+//! fixed width or after its length, in little endian, so the digest is
+//! the same on every machine and doesn't change with the version of the
+//! compiler, unlike [`to_hash`](../../gfa/gfa1/struct.GFA.html#method.to_hash).\
+//! The hash function is a [`DigestHasher`](trait.DigestHasher.html),
+//! the 64 bits FNV-1a by default.
+use bstr::BString;
+use fnv::FnvHasher;
+use std::hash::Hasher;
+
+use crate::gfa::{
+    gfa1::GFA, gfa2::GFA2, optional::OptField, orientation::Orientation, segment_id::PathParseError,
+};
+use crate::handle::{Edge, Handle};
+use crate::hashgraph::{
+    graph::{gfa2_edge, link_edge},
+    HashGraph,
+};
+
+/// A hash function of the canonical digests, fed with the bytes of the
+/// normalized records
+pub trait DigestHasher: Default {
+    fn update(&mut self, bytes: &[u8]);
+
+    fn digest(&self) -> u64;
+}
+
+/// The 64 bits FNV-1a, the hash function of ```canonical_digest```
+#[derive(Default)]
+pub struct Fnv1a64(FnvHasher);
+
+impl DigestHasher for Fnv1a64 {
+    #[inline]
+    fn update(&mut self, bytes: &[u8]) {
+        self.0.write(bytes);
+    }
+
+    #[inline]
+    fn digest(&self) -> u64 {
+        self.0.finish()
+    }
+}
+
+/// The writer of the normalized records into a hasher
+struct Normalized<H>(H);
+
+impl<H: DigestHasher> Normalized<H> {
+    fn kind(&mut self, kind: u8) {
+        self.0.update(&[kind]);
+    }
+
+    fn number(&mut self, n: u64) {
+        self.0.update(&n.to_le_bytes());
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.number(bytes.len() as u64);
+        self.0.update(bytes);
+    }
+
+    fn edge(&mut self, Edge(l, r): Edge) {
+        self.number(l.as_integer());
+        self.number(r.as_integer());
+    }
+
+    /// The tags of a record, sorted as they're written
+    fn tags(&mut self, tags: &[OptField]) {
+        let mut tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+        tags.sort_unstable();
+        self.number(tags.len() as u64);
+        for tag in tags {
+            self.bytes(tag.as_bytes());
+        }
+    }
+
+    /// The steps of a path of a GFA object, the ones that can't be parsed
+    /// as they're written
+    fn steps<I>(&mut self, steps: I)
+    where
+        I: Iterator<Item = Result<(u64, Orientation), PathParseError>>,
+    {
+        for step in steps {
+            match step {
+                Ok((id, orient)) => {
+                    self.kind(b'+');
+                    self.number(Handle::new(id, orient).as_integer());
+                }
+                Err(why) => {
+                    self.kind(b'?');
+                    self.bytes(why.token());
+                }
+            }
+        }
+        self.kind(b'.');
+    }
+
+    fn finish(self) -> u64 {
+        self.0.digest()
+    }
+}
+
+/// The edge in its canonical orientation, and if it was flipped to get it
+fn canonical(edge: Edge) -> (Edge, bool) {
+    let Edge(l, r) = edge;
+    let canonical = Edge::edge_handle(l, r);
+    (canonical, canonical != edge)
+}
+
+impl GFA {
+    /// Function that returns the canonical digest of the object, see the
+    /// [`digest`](../../util/digest/index.html) module
+    /// # Example
+    /// ```ignore
+    /// let gfa = parser.parse_file("./tests/gfa1_files/lil.gfa")?;
+    /// println!("{:016x}", gfa.canonical_digest());
+    /// ```
+    pub fn canonical_digest(&self) -> u64 {
+        self.canonical_digest_with::<Fnv1a64>()
+    }
+
+    /// Function that returns the canonical digest of the object computed
+    /// with the hash function ```H```
+    pub fn canonical_digest_with<H: DigestHasher>(&self) -> u64 {
+        let mut out = Normalized(H::default());
+        let mut segments: Vec<_> = self.segments.iter().collect();
+        segments.sort_by(|a, b| (a.name, &a.sequence).cmp(&(b.name, &b.sequence)));
+        for s in segments {
+            out.kind(b'S');
+            out.number(s.name);
+            out.bytes(&s.sequence);
+            out.tags(&s.optional);
+        }
+        let mut links: Vec<(Edge, &BString, &[OptField])> = self
+            .links
+            .iter()
+            .map(|l| (canonical(link_edge(l)).0, &l.overlap, l.optional.as_slice()))
+            .collect();
+        links.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        for (edge, overlap, tags) in links {
+            out.kind(b'L');
+            out.edge(edge);
+            out.bytes(overlap);
+            out.tags(tags);
+        }
+        let mut paths: Vec<_> = self.paths.iter().collect();
+        paths.sort_by(|a, b| {
+            (&a.path_name, &a.segment_names).cmp(&(&b.path_name, &b.segment_names))
+        });
+        for p in paths {
+            out.kind(b'P');
+            out.bytes(&p.path_name);
+            out.steps(p.iter_checked());
+            out.tags(&p.optional);
+        }
+        out.finish()
+    }
+}
+
+impl GFA2 {
+    /// Function that returns the canonical digest of the object, see the
+    /// [`digest`](../../util/digest/index.html) module
+    pub fn canonical_digest(&self) -> u64 {
+        self.canonical_digest_with::<Fnv1a64>()
+    }
+
+    /// Function that returns the canonical digest of the object computed
+    /// with the hash function ```H```
+    pub fn canonical_digest_with<H: DigestHasher>(&self) -> u64 {
+        let mut out = Normalized(H::default());
+        let mut segments: Vec<_> = self.segments.iter().collect();
+        segments.sort_by(|a, b| (a.id, &a.sequence).cmp(&(b.id, &b.sequence)));
+        for s in segments {
+            out.kind(b'S');
+            out.number(s.id);
+            out.bytes(&s.sequence);
+            out.tags(&s.optional);
+        }
+        // the positions of each segment follow it in the canonical edge
+        let mut edges: Vec<(Edge, [&BString; 4], &[OptField])> = self
+            .edges
+            .iter()
+            .map(|e| {
+                let (edge, flipped) = canonical(gfa2_edge(e));
+                let positions = if flipped {
+                    [&e.beg2, &e.end2, &e.beg1, &e.end1]
+                } else {
+                    [&e.beg1, &e.end1, &e.beg2, &e.end2]
+                };
+                (edge, positions, e.optional.as_slice())
+            })
+            .collect();
+        edges.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        for (edge, positions, tags) in edges {
+            out.kind(b'E');
+            out.edge(edge);
+            for position in positions.iter() {
+                out.bytes(position);
+            }
+            out.tags(tags);
+        }
+        let mut paths: Vec<_> = self.groups_o.iter().collect();
+        paths.sort_by(|a, b| (&a.id, &a.var_field).cmp(&(&b.id, &b.var_field)));
+        for o in paths {
+            out.kind(b'P');
+            out.bytes(&o.id);
+            out.steps(o.iter_checked());
+            out.tags(&o.optional);
+        }
+        out.finish()
+    }
+}
+
+impl HashGraph {
+    /// Function that returns the canonical digest of the graph, see the
+    /// [`digest`](../../util/digest/index.html) module.\
+    /// The paths keep if they're circular, and the edges their overlap
+    /// in bases
+    /// # Example
+    /// ```ignore
+    /// let digest = graph.canonical_digest();
+    /// graph.modify_handle(5, b"A")?;
+    /// assert_ne!(graph.canonical_digest(), digest);
+    /// ```
+    pub fn canonical_digest(&self) -> u64 {
+        self.canonical_digest_with::<Fnv1a64>()
+    }
+
+    /// Function that returns the canonical digest of the graph computed
+    /// with the hash function ```H```
+    pub fn canonical_digest_with<H: DigestHasher>(&self) -> u64 {
+        let mut out = Normalized(H::default());
+        for handle in self.handles_sorted() {
+            let node = self.get_node_unchecked(&handle.id());
+            out.kind(b'S');
+            out.number(u64::from(handle.id()));
+            out.bytes(self.node_sequence(node));
+            out.tags(&node.optional);
+        }
+        for edge in self.edges_sorted() {
+            out.kind(b'L');
+            out.edge(edge);
+            out.number(self.edge_overlap(edge) as u64);
+            out.tags(self.edge_tags(edge));
+        }
+        let mut paths: Vec<_> = self.paths.values().collect();
+        paths.sort_by(|a, b| a.name.cmp(&b.name));
+        for path in paths {
+            out.kind(b'P');
+            out.bytes(&path.name);
+            out.kind(u8::from(path.is_circular));
+            out.number(path.nodes.len() as u64);
+            for step in path.nodes.iter() {
+                out.number(step.as_integer());
+            }
+            out.tags(&path.optional);
+        }
+        out.finish()
+    }
+}
@@ -1,9 +1,14 @@
+use std::convert::TryInto;
+
+/// The complement of an upper case IUPAC nucleotide code, 0 for the
+/// other bytes
 const fn comp_base_impl(base: u8) -> u8 {
     match base {
         b'A' => b'T',
         b'G' => b'C',
         b'C' => b'G',
         b'T' => b'A',
+        b'U' => b'A',
         b'Y' => b'R',
         b'R' => b'Y',
         b'W' => b'W',
@@ -14,7 +19,8 @@ const fn comp_base_impl(base: u8) -> u8 {
         b'V' => b'B',
         b'H' => b'D',
         b'B' => b'V',
-        _ => b'N',
+        b'N' => b'N',
+        _ => 0,
     }
 }
 
@@ -27,8 +33,9 @@ const fn comp_base_table() -> [u8; 256] {
         let offset = 32 * ((i as u8).is_ascii_lowercase() as u8);
         let comp = comp_base_impl((i as u8) - offset);
 
-        if comp == b'N' {
-            table[i] = i as u8;
+        // an unknown byte is any base, in the case of a letter
+        if comp == 0 {
+            table[i] = b'N' + offset;
         } else {
             table[i] = comp + offset;
         }
@@ -42,7 +49,15 @@ const DNA_COMP_TABLE: [u8; 256] = comp_base_table();
 
 /// Retrieves the DNA complement for the provided base using a
 /// lookup-table built at compile time using the `const fn`
-/// `comp_base_table()`.
+/// `comp_base_table()`.\
+/// Every IUPAC nucleotide code (```ACGTURYSWKMBDHVN```) is mapped to its
+/// complement keeping its case, ```U``` to ```A```, and any other byte
+/// to ```N```, or ```n``` for a lower case letter
+/// # Example
+/// ```ignore
+/// assert_eq!(comp_base(b'r'), b'y');
+/// assert_eq!(comp_base(b'*'), b'N');
+/// ```
 #[inline]
 pub const fn comp_base(base: u8) -> u8 {
     DNA_COMP_TABLE[base as usize]
@@ -77,6 +92,172 @@ where
     seq.into_iter().rev().map(|b| comp_base(*b.borrow()))
 }
 
+/// Size of the chunks of the sequence processed at once by the slice
+/// functions, laid out so that the compiler can vectorize the loop on
+/// each chunk
+const CHUNK: usize = 64;
+
+/// The complement of A, C, G or T without the table: A and T differ
+/// by 0x15, C and G by 0x04, and only C and G have the bit 0x02 set
+#[inline]
+fn comp_acgt(base: u8) -> u8 {
+    base ^ (0x15 ^ (((base >> 1) & 1) * 0x11))
+}
+
+/// Complements a full chunk without the table, and without branches so
+/// that the loop is vectorized, returning false (and a wrong complement)
+/// if the chunk has something else than A, C, G and T in upper or lower
+/// case
+#[inline]
+fn comp_acgt_chunk(from: &[u8; CHUNK], to: &mut [u8; CHUNK]) -> bool {
+    let mut other = 0u8;
+    for (b, c) in from.iter().zip(to.iter_mut()) {
+        let lower = *b | 0x20;
+        other |= ((lower != b'a') & (lower != b'c') & (lower != b'g') & (lower != b't')) as u8;
+        *c = comp_acgt(*b);
+    }
+    other == 0
+}
+
+/// Calculates the reverse complement of a sequence, like
+/// [`rev_comp`](fn.rev_comp.html), with [`complement`](fn.complement.html)
+/// and then reversing the result in place, which swaps words rather than
+/// bytes
+#[inline]
+pub fn rev_comp_slice(seq: &[u8]) -> Vec<u8> {
+    let mut out = complement(seq);
+    out.reverse();
+    out
+}
+
+/// Calculates the reverse complement of a sequence in place, without
+/// allocating, with the same result as [`rev_comp`](fn.rev_comp.html)
+/// # Example
+/// ```ignore
+/// let mut seq = b"ACgtN".to_vec();
+/// rev_comp_in_place(&mut seq);
+/// assert_eq!(seq, b"NacGT");
+/// ```
+#[inline]
+pub fn rev_comp_in_place(seq: &mut [u8]) {
+    seq.reverse();
+    let mut buffer = [0; CHUNK];
+    for chunk in seq.chunks_mut(CHUNK) {
+        let done = match (&*chunk).try_into() {
+            Ok(from) => comp_acgt_chunk(from, &mut buffer),
+            Err(_) => false,
+        };
+        if done {
+            chunk.copy_from_slice(&buffer);
+        } else {
+            for b in chunk.iter_mut() {
+                *b = comp_base(*b);
+            }
+        }
+    }
+}
+
+/// Calculates the complement of a sequence, without reversing it, with
+/// the same result as [`comp_base`](fn.comp_base.html) on each base
+#[inline]
+pub fn complement(seq: &[u8]) -> Vec<u8> {
+    let mut out = vec![0; seq.len()];
+    for (from, to) in seq.chunks(CHUNK).zip(out.chunks_mut(CHUNK)) {
+        let done = match (from.try_into(), to.try_into()) {
+            (Ok(from), Ok(to)) => comp_acgt_chunk(from, to),
+            _ => false,
+        };
+        if !done {
+            for (b, c) in from.iter().zip(to.iter_mut()) {
+                *c = comp_base(*b);
+            }
+        }
+    }
+    out
+}
+
+/// The number of each base of a sequence, in upper or lower case, the
+/// other bytes (IUPAC codes included) are counted together
+/// ```ignore
+/// pub struct BaseCounts {
+///     pub a: usize,
+///     pub c: usize,
+///     pub g: usize,
+///     pub t: usize,
+///     pub n: usize,
+///     pub other: usize,
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BaseCounts {
+    pub a: usize,
+    pub c: usize,
+    pub g: usize,
+    pub t: usize,
+    pub n: usize,
+    pub other: usize,
+}
+
+impl BaseCounts {
+    /// The length of the sequence counted
+    pub fn len(&self) -> usize {
+        self.a + self.c + self.g + self.t + self.n + self.other
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn gc(&self) -> usize {
+        self.g + self.c
+    }
+}
+
+/// Counts the bases of a sequence in one pass, a chunk at a time with
+/// small counters that the compiler can vectorize
+/// # Example
+/// ```ignore
+/// let counts = base_counts(b"ACgtN");
+/// assert_eq!((counts.a, counts.g, counts.n), (1, 1, 1));
+/// ```
+#[inline]
+pub fn base_counts(seq: &[u8]) -> BaseCounts {
+    let mut counts = BaseCounts::default();
+    for chunk in seq.chunks(CHUNK) {
+        // a chunk is at most 64 bytes, each counter fits in a byte
+        let (mut a, mut c, mut g, mut t, mut n) = (0u8, 0u8, 0u8, 0u8, 0u8);
+        for b in chunk.iter() {
+            // only the upper and the lower case letter have the same
+            // lower case bit set
+            let lower = *b | 0x20;
+            a += (lower == b'a') as u8;
+            c += (lower == b'c') as u8;
+            g += (lower == b'g') as u8;
+            t += (lower == b't') as u8;
+            n += (lower == b'n') as u8;
+        }
+        let (a, c, g, t, n) = (a as usize, c as usize, g as usize, t as usize, n as usize);
+        counts.a += a;
+        counts.c += c;
+        counts.g += g;
+        counts.t += t;
+        counts.n += n;
+        counts.other += chunk.len() - a - c - g - t - n;
+    }
+    counts
+}
+
+/// The fraction of G and C (in upper or lower case) of a sequence, or 0
+/// for an empty sequence
+#[inline]
+pub fn gc_content(seq: &[u8]) -> f64 {
+    if seq.is_empty() {
+        0.0
+    } else {
+        base_counts(seq).gc() as f64 / seq.len() as f64
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,9 +289,9 @@ mod tests {
         }
     }
 
-    impl Into<u8> for Base {
-        fn into(self) -> u8 {
-            self.0
+    impl From<Base> for u8 {
+        fn from(val: Base) -> Self {
+            val.0
         }
     }
 
@@ -162,6 +343,62 @@ mod tests {
             .quickcheck(is_rev_comp_isomorphic as fn(Vec<Base>) -> bool);
     }
 
+    /// A base among the IUPAC codes that are their own complement's
+    /// complement, in both cases
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Iupac(u8);
+
+    impl Arbitrary for Iupac {
+        fn arbitrary<G: Gen>(g: &mut G) -> Iupac {
+            let codes = b"ACGTRYSWKMBDHVNacgtryswkmbdhvn";
+            Iupac(codes[usize::arbitrary(g) % codes.len()])
+        }
+    }
+
+    fn is_iupac_rev_comp_isomorphic(seq: Vec<Iupac>) -> bool {
+        let seq = seq.into_iter().map(|b| b.0).collect::<Vec<_>>();
+        let mut in_place = rev_comp(&seq);
+        rev_comp_in_place(&mut in_place);
+        rev_comp(rev_comp(seq.clone())) == seq
+            && rev_comp_slice(&rev_comp_slice(&seq)) == seq
+            && in_place == seq
+    }
+
+    #[test]
+    fn iupac_rev_comp_isomorphic() {
+        QuickCheck::new()
+            .tests(10000)
+            .quickcheck(is_iupac_rev_comp_isomorphic as fn(Vec<Iupac>) -> bool);
+    }
+
+    #[test]
+    fn iupac_mixed_case_rev_comp() {
+        assert_eq!(rev_comp(b"AcGtNnRyKm"), b"kMrYnNaCgT");
+        assert_eq!(rev_comp(b"SwBdHv"), b"bDhVwS");
+        assert_eq!(rev_comp_slice(b"ACGTURYSWKMBDHVN"), b"NBDHVKMWSRYAACGT");
+        // U is complemented as a T, the unknown bytes are any base
+        assert_eq!(rev_comp(b"uAX*-x"), b"nNNNTa");
+        for b in 0..=255u8 {
+            let comp = comp_base(b);
+            assert!(b"ACGTRYSWKMBDHVNacgtryswkmbdhvn".contains(&comp), "{}", b);
+            assert_eq!(comp.is_ascii_lowercase(), b.is_ascii_lowercase(), "{}", b);
+        }
+
+        let mut seq = b"ACgtN".to_vec();
+        rev_comp_in_place(&mut seq);
+        assert_eq!(seq, b"NacGT");
+        for seq in every_byte_sequences() {
+            let mut in_place = seq.clone();
+            rev_comp_in_place(&mut in_place);
+            assert_eq!(in_place, rev_comp(&seq));
+        }
+        let acgt: Vec<u8> = b"ACGTacgt".iter().cycle().take(1000).copied().collect();
+        let mut in_place = acgt.clone();
+        rev_comp_in_place(&mut in_place);
+        assert_eq!(in_place, rev_comp_slice(&acgt));
+    }
+
+    #[allow(dead_code)]
     fn arbitrary_bytes_iso(base: u8) -> bool {
         if comp_base_impl(base) != b'N' {
             comp_base(comp_base(base)) == base
@@ -170,10 +407,86 @@ mod tests {
         }
     }
 
+    /// Sequences with every byte at every position of a chunk, of every
+    /// length around the size of the chunks
+    fn every_byte_sequences() -> Vec<Vec<u8>> {
+        let every: Vec<u8> = (0..=255u8).collect();
+        let mut seqs: Vec<Vec<u8>> = (0..=3 * CHUNK)
+            .map(|len| every.iter().cycle().take(len).copied().collect())
+            .collect();
+        for shift in 0..CHUNK {
+            seqs.push(
+                every
+                    .iter()
+                    .cycle()
+                    .skip(shift)
+                    .take(1000)
+                    .copied()
+                    .collect(),
+            );
+        }
+        seqs
+    }
+
+    #[test]
+    fn slice_rev_comp_matches_iterator() {
+        for b in 0..=255u8 {
+            assert_eq!(rev_comp_slice(&[b]), rev_comp([b]));
+            assert_eq!(complement(&[b]), vec![comp_base(b)]);
+        }
+        for seq in every_byte_sequences() {
+            assert_eq!(rev_comp_slice(&seq), rev_comp(&seq));
+            let forward: Vec<u8> = seq.iter().map(|b| comp_base(*b)).collect();
+            assert_eq!(complement(&seq), forward);
+        }
+    }
+
+    #[test]
+    fn acgt_complement_without_table() {
+        for b in b"ACGTacgt".iter() {
+            assert_eq!(comp_acgt(*b), comp_base(*b));
+        }
+        let acgt: Vec<u8> = b"ACGTacgt".iter().cycle().take(1000).copied().collect();
+        assert_eq!(rev_comp_slice(&acgt), rev_comp(&acgt));
+        assert_eq!(
+            complement(&acgt),
+            acgt.iter().map(|b| comp_base(*b)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn chunked_base_counts() {
+        let naive = |seq: &[u8]| {
+            let mut counts = BaseCounts::default();
+            for b in seq.iter() {
+                match b {
+                    b'A' | b'a' => counts.a += 1,
+                    b'C' | b'c' => counts.c += 1,
+                    b'G' | b'g' => counts.g += 1,
+                    b'T' | b't' => counts.t += 1,
+                    b'N' | b'n' => counts.n += 1,
+                    _ => counts.other += 1,
+                }
+            }
+            counts
+        };
+        for b in 0..=255u8 {
+            assert_eq!(base_counts(&[b]), naive(&[b]), "{}", b);
+        }
+        for seq in every_byte_sequences() {
+            let counts = base_counts(&seq);
+            assert_eq!(counts, naive(&seq));
+            assert_eq!(counts.len(), seq.len());
+        }
+        // every byte of a chunk the same, the counters don't overflow
+        assert_eq!(base_counts(&[b'g'; 1000]).g, 1000);
+        assert_eq!(gc_content(b""), 0.0);
+        assert_eq!(gc_content(b"ACgtNNcc"), 0.5);
+    }
+
     #[test]
     fn print_table() {
-        for x in 0..256 {
-            let val = DNA_COMP_TABLE[x];
+        for (x, val) in DNA_COMP_TABLE.iter().enumerate() {
             println!("{:3} -> {:3}", x, val);
         }
     }
@@ -0,0 +1,41 @@
+/// This file provides the tabular export of the nodes of a graph, for
+/// the pipelines that read them a row at a time
+use std::io::{self, Write};
+
+use crate::hashgraph::{HashGraph, NodeRecord};
+
+/// The header of [`nodes_to_tsv`](fn.nodes_to_tsv.html), new columns
+/// are always added at the end
+pub const NODES_TSV_HEADER: &str = "id\tsequence\tleft_degree\tright_degree\ton_path";
+
+/// Function that writes a row for each node of the graph, sorted by id,
+/// after the [`NODES_TSV_HEADER`](constant.NODES_TSV_HEADER.html): the
+/// fields of its [`NodeRecord`](../../hashgraph/struct.NodeRecord.html),
+/// with ```on_path``` as 1 or 0.\
+/// The id is the number of the node, and the sequence its bytes as they
+/// are; the export fails if a sequence can't be read
+/// # Example
+/// ```ignore
+/// let file = std::io::BufWriter::new(File::create("nodes.tsv")?);
+/// nodes_to_tsv(&graph, file)?;
+/// ```
+pub fn nodes_to_tsv<W: Write>(graph: &HashGraph, mut out: W) -> io::Result<()> {
+    writeln!(out, "{}", NODES_TSV_HEADER)?;
+    for h in graph.handles_sorted() {
+        let node = graph.get_node_unchecked(&h.id());
+        let sequence = graph
+            .try_node_sequence(node)
+            .map_err(|why| io::Error::other(why.to_string()))?;
+        let record = NodeRecord::new(h.id(), node, sequence);
+        write!(out, "{}\t", record.id)?;
+        out.write_all(record.sequence)?;
+        writeln!(
+            out,
+            "\t{}\t{}\t{}",
+            record.left_degree,
+            record.right_degree,
+            u8::from(record.on_path)
+        )?;
+    }
+    out.flush()
+}
@@ -0,0 +1,35 @@
+/// This file provides the FASTA export of the sequences spelled by the
+/// paths of a graph
+use std::io::{self, Write};
+
+use crate::{
+    hashgraph::{HashGraph, PathId},
+    pathgraph::PathHandleGraph,
+};
+
+/// Function that writes the sequence of every path of the graph as a
+/// FASTA record, sorted by path id: the header is the name of the path
+/// (its bytes as they are, even if they aren't UTF-8) and the bases are
+/// the ones of
+/// [`path_sequence`](../../hashgraph/graph/struct.HashGraph.html#method.path_sequence),
+/// the reverse steps reverse complemented.\
+/// A circular path is written once, from its first step, and an empty
+/// path is a header without bases; the lines are wrapped after
+/// ```line_width``` bases, or never with a width of 0
+/// # Example
+/// ```ignore
+/// let file = std::io::BufWriter::new(File::create("paths.fa")?);
+/// paths_to_fasta(&graph, file, 60)?;
+/// ```
+pub fn paths_to_fasta<W: Write>(
+    graph: &HashGraph,
+    mut out: W,
+    line_width: usize,
+) -> io::Result<()> {
+    let mut path_ids: Vec<PathId> = graph.paths().copied().collect();
+    path_ids.sort_unstable();
+    for path_id in path_ids.iter() {
+        graph.write_path_fasta_streaming(path_id, &mut out, line_width)?;
+    }
+    out.flush()
+}
@@ -0,0 +1,825 @@
+/// This file provides a gzip encoder and decoder, to write compressed
+/// files (and read them back) without any other dependency
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Compression of the files written by the savers
+/// ```ignore
+/// pub enum Compression {
+///     None,
+///     Gzip(u32),
+/// }
+/// ```
+/// * ```None``` writes the files as they are, but the ones whose path
+///   ends with ```.gz```, that are compressed with the default level
+/// * ```Gzip(level)``` compresses the files with a level from 0 (the
+///   bytes are stored as they are) to 9 (the smallest and the slowest)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip(u32),
+}
+
+impl Compression {
+    /// The level used for the paths ending with ```.gz```
+    pub const DEFAULT_LEVEL: u32 = 6;
+
+    /// The compression of a file written in ```path```
+    pub fn of_path<P: AsRef<Path>>(self, path: P) -> Compression {
+        match self {
+            Compression::None if path.as_ref().extension().is_some_and(|ext| ext == "gz") => {
+                Compression::Gzip(Self::DEFAULT_LEVEL)
+            }
+            compression => compression,
+        }
+    }
+}
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+static CRC_TABLE: [u32; 256] = crc_table();
+
+/// The CRC-32 of the gzip trailer, continuing ```crc```
+fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    let mut c = !crc;
+    for b in bytes {
+        c = CRC_TABLE[((c ^ u32::from(*b)) & 0xff) as usize] ^ (c >> 8);
+    }
+    !c
+}
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// The order of the lengths of the code of the code lengths
+const CLEN_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// The distance a match can reach back
+const WINDOW: usize = 1 << 15;
+/// Number of new bytes compressed in a block
+const BLOCK: usize = 1 << 17;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const HASH_BITS: u32 = 15;
+const END_OF_BLOCK: usize = 256;
+
+fn invalid(why: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid gzip: {}", why))
+}
+
+/// Bits written from the least significant one, as deflate wants
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u32, len: u32) {
+        self.bits |= u64::from(value) << self.count;
+        self.count += len;
+        while self.count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// The huffman codes are written from their most significant bit
+    fn put_code(&mut self, code: u16, len: u8) {
+        let reversed = code.reverse_bits() >> (16 - u32::from(len));
+        self.put(u32::from(reversed), u32::from(len));
+    }
+
+    fn align(&mut self) {
+        if self.count > 0 {
+            self.put(0, 8 - self.count);
+        }
+    }
+}
+
+enum Token {
+    Literal(u8),
+    Match { len: u16, dist: u16 },
+}
+
+fn length_code(len: u16) -> usize {
+    LENGTH_BASE.iter().rposition(|base| *base <= len).unwrap()
+}
+
+fn dist_code(dist: u16) -> usize {
+    DIST_BASE.iter().rposition(|base| *base <= dist).unwrap()
+}
+
+fn hash(bytes: &[u8]) -> usize {
+    let key = u32::from(bytes[0]) | u32::from(bytes[1]) << 8 | u32::from(bytes[2]) << 16;
+    (key.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+/// The matches and the literals of ```data[start..]```, looking back
+/// also in ```data[..start]```, trying at most ```chain``` earlier
+/// positions for each match
+fn tokens(data: &[u8], start: usize, chain: usize) -> Vec<Token> {
+    let end = data.len();
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; end];
+    let insert = |head: &mut [usize], prev: &mut [usize], pos: usize| {
+        if pos + MIN_MATCH <= end {
+            let h = hash(&data[pos..]);
+            prev[pos] = head[h];
+            head[h] = pos;
+        }
+    };
+    for pos in 0..start {
+        insert(&mut head, &mut prev, pos);
+    }
+
+    let mut res = Vec::new();
+    let mut pos = start;
+    while pos < end {
+        let (mut best_len, mut best_dist) = (0, 0);
+        if pos + MIN_MATCH <= end {
+            let max = MAX_MATCH.min(end - pos);
+            let mut candidate = head[hash(&data[pos..])];
+            let mut tries = chain;
+            while candidate != usize::MAX && tries > 0 && pos - candidate <= WINDOW {
+                let len = (0..max)
+                    .find(|k| data[candidate + k] != data[pos + k])
+                    .unwrap_or(max);
+                if len > best_len {
+                    best_len = len;
+                    best_dist = pos - candidate;
+                    if len == max {
+                        break;
+                    }
+                }
+                candidate = prev[candidate];
+                tries -= 1;
+            }
+        }
+        if best_len >= MIN_MATCH {
+            res.push(Token::Match {
+                len: best_len as u16,
+                dist: best_dist as u16,
+            });
+            for p in pos..pos + best_len {
+                insert(&mut head, &mut prev, p);
+            }
+            pos += best_len;
+        } else {
+            res.push(Token::Literal(data[pos]));
+            insert(&mut head, &mut prev, pos);
+            pos += 1;
+        }
+    }
+    res
+}
+
+/// The lengths of the huffman codes of the symbols with these
+/// frequencies
+fn huffman_lengths(freqs: &[u64]) -> Vec<u8> {
+    let mut lengths = vec![0; freqs.len()];
+    let leaves: Vec<usize> = (0..freqs.len()).filter(|s| freqs[*s] > 0).collect();
+    if leaves.len() == 1 {
+        lengths[leaves[0]] = 1;
+        return lengths;
+    }
+    // the parent of each node, the nodes are built after their children
+    let mut parent: Vec<usize> = vec![usize::MAX; leaves.len()];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = leaves
+        .iter()
+        .enumerate()
+        .map(|(node, s)| Reverse((freqs[*s], node)))
+        .collect();
+    while heap.len() > 1 {
+        let Reverse((a, x)) = heap.pop().unwrap();
+        let Reverse((b, y)) = heap.pop().unwrap();
+        let node = parent.len();
+        parent.push(usize::MAX);
+        parent[x] = node;
+        parent[y] = node;
+        heap.push(Reverse((a + b, node)));
+    }
+    let mut depth = vec![0_u8; parent.len()];
+    for node in (0..parent.len().saturating_sub(1)).rev() {
+        depth[node] = depth[parent[node]] + 1;
+    }
+    for (node, s) in leaves.iter().enumerate() {
+        lengths[*s] = depth[node];
+    }
+    lengths
+}
+
+/// The lengths of the huffman codes of the symbols, at most ```limit```
+/// bits long: the frequencies are halved until the codes fit.\
+/// There are always at least two codes, as some decoders reject a
+/// single code
+fn code_lengths(freqs: &[u64], limit: u8) -> Vec<u8> {
+    let mut freqs = freqs.to_vec();
+    for s in 0..2 {
+        if freqs.iter().filter(|f| **f > 0).count() < 2 && freqs[s] == 0 {
+            freqs[s] = 1;
+        }
+    }
+    loop {
+        let lengths = huffman_lengths(&freqs);
+        if lengths.iter().all(|len| *len <= limit) {
+            return lengths;
+        }
+        freqs
+            .iter_mut()
+            .filter(|f| **f > 0)
+            .for_each(|f| *f = f.div_ceil(2));
+    }
+}
+
+/// The canonical codes of the lengths
+fn canonical_codes(lengths: &[u8]) -> Vec<u16> {
+    let mut count = [0_u16; 16];
+    lengths
+        .iter()
+        .filter(|len| **len > 0)
+        .for_each(|len| count[*len as usize] += 1);
+    let mut next = [0_u16; 16];
+    let mut code = 0;
+    for bits in 1..16 {
+        code = (code + count[bits - 1]) << 1;
+        next[bits] = code;
+    }
+    lengths
+        .iter()
+        .map(|len| {
+            if *len == 0 {
+                0
+            } else {
+                let code = next[*len as usize];
+                next[*len as usize] += 1;
+                code
+            }
+        })
+        .collect()
+}
+
+/// The code lengths run length encoded, as symbols with their extra
+/// bits value
+fn run_lengths(lengths: &[u8]) -> Vec<(usize, u32)> {
+    let mut res = Vec::new();
+    let mut ix = 0;
+    while ix < lengths.len() {
+        let len = lengths[ix];
+        let mut run = lengths[ix..].iter().take_while(|l| **l == len).count();
+        ix += run;
+        if len == 0 {
+            while run >= 11 {
+                let r = run.min(138);
+                res.push((18, (r - 11) as u32));
+                run -= r;
+            }
+            if run >= 3 {
+                res.push((17, (run - 3) as u32));
+                run = 0;
+            }
+        } else {
+            res.push((len as usize, 0));
+            run -= 1;
+            while run >= 3 {
+                let r = run.min(6);
+                res.push((16, (r - 3) as u32));
+                run -= r;
+            }
+        }
+        res.extend(std::iter::repeat_n((len as usize, 0), run));
+    }
+    res
+}
+
+/// Write a block with the dynamic huffman codes of its tokens
+fn write_dynamic_block(bits: &mut BitWriter, tokens: &[Token], last: bool) {
+    let mut lit_freqs = vec![0_u64; 286];
+    let mut dist_freqs = vec![0_u64; 30];
+    for token in tokens {
+        match token {
+            Token::Literal(b) => lit_freqs[*b as usize] += 1,
+            Token::Match { len, dist } => {
+                lit_freqs[257 + length_code(*len)] += 1;
+                dist_freqs[dist_code(*dist)] += 1;
+            }
+        }
+    }
+    lit_freqs[END_OF_BLOCK] += 1;
+    let lit_lengths = code_lengths(&lit_freqs, 15);
+    let dist_lengths = code_lengths(&dist_freqs, 15);
+    let (lit_codes, dist_codes) = (
+        canonical_codes(&lit_lengths),
+        canonical_codes(&dist_lengths),
+    );
+
+    let hlit = 257.max(lit_lengths.iter().rposition(|l| *l > 0).unwrap() + 1);
+    let hdist = 1.max(dist_lengths.iter().rposition(|l| *l > 0).unwrap() + 1);
+    let mut lengths = lit_lengths[..hlit].to_vec();
+    lengths.extend_from_slice(&dist_lengths[..hdist]);
+    let runs = run_lengths(&lengths);
+
+    let mut clen_freqs = vec![0_u64; 19];
+    runs.iter().for_each(|(s, _)| clen_freqs[*s] += 1);
+    let clen_lengths = code_lengths(&clen_freqs, 7);
+    let clen_codes = canonical_codes(&clen_lengths);
+    let hclen = 4.max(
+        CLEN_ORDER
+            .iter()
+            .rposition(|s| clen_lengths[*s] > 0)
+            .unwrap()
+            + 1,
+    );
+
+    bits.put(last as u32, 1);
+    bits.put(2, 2);
+    bits.put((hlit - 257) as u32, 5);
+    bits.put((hdist - 1) as u32, 5);
+    bits.put((hclen - 4) as u32, 4);
+    for s in CLEN_ORDER.iter().take(hclen) {
+        bits.put(u32::from(clen_lengths[*s]), 3);
+    }
+    for (s, extra) in runs {
+        bits.put_code(clen_codes[s], clen_lengths[s]);
+        match s {
+            16 => bits.put(extra, 2),
+            17 => bits.put(extra, 3),
+            18 => bits.put(extra, 7),
+            _ => (),
+        }
+    }
+
+    for token in tokens {
+        match token {
+            Token::Literal(b) => bits.put_code(lit_codes[*b as usize], lit_lengths[*b as usize]),
+            Token::Match { len, dist } => {
+                let code = length_code(*len);
+                bits.put_code(lit_codes[257 + code], lit_lengths[257 + code]);
+                bits.put(
+                    u32::from(len - LENGTH_BASE[code]),
+                    u32::from(LENGTH_EXTRA[code]),
+                );
+                let code = dist_code(*dist);
+                bits.put_code(dist_codes[code], dist_lengths[code]);
+                bits.put(
+                    u32::from(dist - DIST_BASE[code]),
+                    u32::from(DIST_EXTRA[code]),
+                );
+            }
+        }
+    }
+    bits.put_code(lit_codes[END_OF_BLOCK], lit_lengths[END_OF_BLOCK]);
+}
+
+/// Write the bytes as they are, in blocks of at most 65535 bytes
+fn write_stored_blocks(bits: &mut BitWriter, bytes: &[u8], last: bool) {
+    let mut chunks: Vec<&[u8]> = bytes.chunks(u16::MAX as usize).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    let count = chunks.len();
+    for (ix, chunk) in chunks.into_iter().enumerate() {
+        bits.put((last && ix + 1 == count) as u32, 1);
+        bits.put(0, 2);
+        bits.align();
+        bits.put(chunk.len() as u32, 16);
+        bits.put(u32::from(!(chunk.len() as u16)), 16);
+        bits.bytes.extend_from_slice(chunk);
+    }
+}
+
+/// A writer that compresses what's written in it with gzip, and writes
+/// it in ```out```.\
+/// The bytes are compressed in blocks, that are written when they're
+/// full, when the encoder is flushed and when it's finished: the file
+/// is complete only after [`finish`](#method.finish), that returns
+/// ```out```
+/// # Example
+/// ```ignore
+/// let mut out = GzEncoder::new(File::create("graph.gfa.gz")?, 6);
+/// graph.write_gfa_preserving(&mut out)?;
+/// out.finish()?.sync_all()?;
+/// ```
+pub struct GzEncoder<W: Write> {
+    out: W,
+    level: u32,
+    /// The last ```WINDOW``` bytes compressed, followed by the ones
+    /// still to compress
+    data: Vec<u8>,
+    /// Where the bytes still to compress start in ```data```
+    start: usize,
+    bits: BitWriter,
+    crc: u32,
+    size: u32,
+}
+
+impl<W: Write> GzEncoder<W> {
+    /// An encoder with a level from 0 to 9, the larger levels are
+    /// taken as 9
+    pub fn new(out: W, level: u32) -> Self {
+        let mut bits = BitWriter::default();
+        // no name and no time, so the same bytes give the same file
+        bits.bytes
+            .extend_from_slice(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 255]);
+        GzEncoder {
+            out,
+            level: level.min(9),
+            data: Vec::new(),
+            start: 0,
+            bits,
+            crc: 0,
+            size: 0,
+        }
+    }
+
+    /// Compress the bytes still to compress in a block, and write the
+    /// whole bytes of the blocks compressed
+    fn write_block(&mut self, last: bool) -> io::Result<()> {
+        if self.level == 0 {
+            write_stored_blocks(&mut self.bits, &self.data[self.start..], last);
+        } else if self.start < self.data.len() || last {
+            let chain = [4, 8, 16, 32, 64, 128, 256, 1024, 4096][self.level as usize - 1];
+            let tokens = tokens(&self.data, self.start, chain);
+            write_dynamic_block(&mut self.bits, &tokens, last);
+        }
+        let keep = self.data.len().saturating_sub(WINDOW);
+        self.data.drain(..keep);
+        self.start = self.data.len();
+        self.out.write_all(&self.bits.bytes)?;
+        self.bits.bytes.clear();
+        Ok(())
+    }
+
+    /// Function that writes the last block and the trailer, returning
+    /// the writer
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_block(true)?;
+        self.bits.align();
+        self.bits.bytes.extend_from_slice(&self.crc.to_le_bytes());
+        self.bits.bytes.extend_from_slice(&self.size.to_le_bytes());
+        self.out.write_all(&self.bits.bytes)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> Write for GzEncoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(BLOCK - (self.data.len() - self.start));
+        self.data.extend_from_slice(&buf[..len]);
+        self.crc = crc32(self.crc, &buf[..len]);
+        self.size = self.size.wrapping_add(len as u32);
+        if self.data.len() - self.start == BLOCK {
+            self.write_block(false)?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.start < self.data.len() {
+            self.write_block(false)?;
+        }
+        self.out.flush()
+    }
+}
+
+/// Bits read from the least significant one
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn get(&mut self, len: u32) -> io::Result<u32> {
+        while self.count < len {
+            let b = *self
+                .bytes
+                .get(self.pos)
+                .ok_or_else(|| invalid("unexpected end of file"))?;
+            self.pos += 1;
+            self.bits |= u32::from(b) << self.count;
+            self.count += 8;
+        }
+        let value = self.bits & ((1_u64 << len) - 1) as u32;
+        self.bits >>= len;
+        self.count -= len;
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() - self.pos < len {
+            return Err(invalid("unexpected end of file"));
+        }
+        self.pos += len;
+        Ok(&self.bytes[self.pos - len..self.pos])
+    }
+}
+
+/// A canonical huffman code, as the number of codes of each length and
+/// the symbols sorted by code
+struct Huffman {
+    count: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> io::Result<Self> {
+        let mut count = [0_u16; 16];
+        lengths.iter().for_each(|len| count[*len as usize] += 1);
+        count[0] = 0;
+        let mut left: i32 = 1;
+        for len in count.iter().skip(1) {
+            left = (left << 1) - i32::from(*len);
+            if left < 0 {
+                return Err(invalid("over-subscribed code"));
+            }
+        }
+        let mut offsets = [0_u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + count[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (s, len) in lengths.iter().enumerate() {
+            if *len > 0 {
+                symbols[offsets[*len as usize] as usize] = s as u16;
+                offsets[*len as usize] += 1;
+            }
+        }
+        Ok(Huffman { count, symbols })
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> io::Result<usize> {
+        let (mut code, mut first, mut index) = (0_i32, 0_i32, 0_i32);
+        for len in 1..16 {
+            code |= bits.get(1)? as i32;
+            let count = i32::from(self.count[len]);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize] as usize);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("unknown code"))
+    }
+}
+
+/// The codes of the blocks with the fixed huffman codes
+fn fixed_codes() -> io::Result<(Huffman, Huffman)> {
+    let mut lengths = [8_u8; 288];
+    lengths[144..256].iter_mut().for_each(|l| *l = 9);
+    lengths[256..280].iter_mut().for_each(|l| *l = 7);
+    Ok((Huffman::new(&lengths)?, Huffman::new(&[5; 30])?))
+}
+
+/// The codes of a block with dynamic huffman codes
+fn dynamic_codes(bits: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let hlit = bits.get(5)? as usize + 257;
+    let hdist = bits.get(5)? as usize + 1;
+    let hclen = bits.get(4)? as usize + 4;
+    if hlit > 286 || hdist > 30 {
+        return Err(invalid("too many codes"));
+    }
+    let mut clen_lengths = [0_u8; 19];
+    for s in CLEN_ORDER.iter().take(hclen) {
+        clen_lengths[*s] = bits.get(3)? as u8;
+    }
+    let clen = Huffman::new(&clen_lengths)?;
+
+    let mut lengths: Vec<u8> = Vec::with_capacity(hlit + hdist);
+    while lengths.len() < hlit + hdist {
+        let (len, run) = match clen.decode(bits)? {
+            16 => match lengths.last() {
+                Some(len) => (*len, 3 + bits.get(2)?),
+                None => return Err(invalid("repeated length without a length")),
+            },
+            17 => (0, 3 + bits.get(3)?),
+            18 => (0, 11 + bits.get(7)?),
+            len => (len as u8, 1),
+        };
+        if lengths.len() + run as usize > hlit + hdist {
+            return Err(invalid("too many lengths"));
+        }
+        lengths.extend(std::iter::repeat_n(len, run as usize));
+    }
+    if lengths[END_OF_BLOCK] == 0 {
+        return Err(invalid("no end of block code"));
+    }
+    Ok((
+        Huffman::new(&lengths[..hlit])?,
+        Huffman::new(&lengths[hlit..])?,
+    ))
+}
+
+/// Decompress the deflate stream of a member at the end of ```out```
+fn inflate(bits: &mut BitReader, out: &mut Vec<u8>) -> io::Result<()> {
+    let member_start = out.len();
+    loop {
+        let last = bits.get(1)? == 1;
+        let (lit, dist) = match bits.get(2)? {
+            0 => {
+                bits.align();
+                let header = bits.take(4)?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                if len != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err(invalid("wrong length of a stored block"));
+                }
+                out.extend_from_slice(bits.take(len as usize)?);
+                if last {
+                    return Ok(());
+                }
+                continue;
+            }
+            1 => fixed_codes()?,
+            2 => dynamic_codes(bits)?,
+            _ => return Err(invalid("unknown block type")),
+        };
+        loop {
+            let s = lit.decode(bits)?;
+            if s < END_OF_BLOCK {
+                out.push(s as u8);
+            } else if s == END_OF_BLOCK {
+                break;
+            } else {
+                let code = s - 257;
+                if code >= LENGTH_BASE.len() {
+                    return Err(invalid("unknown length code"));
+                }
+                let len =
+                    LENGTH_BASE[code] as usize + bits.get(u32::from(LENGTH_EXTRA[code]))? as usize;
+                let code = dist.decode(bits)?;
+                if code >= DIST_BASE.len() {
+                    return Err(invalid("unknown distance code"));
+                }
+                let d = DIST_BASE[code] as usize + bits.get(u32::from(DIST_EXTRA[code]))? as usize;
+                if d > out.len() - member_start {
+                    return Err(invalid("distance too far back"));
+                }
+                let from = out.len() - d;
+                for ix in 0..len {
+                    out.push(out[from + ix]);
+                }
+            }
+        }
+        if last {
+            return Ok(());
+        }
+    }
+}
+
+/// Function that decompresses the bytes of a gzip file, also made of
+/// many members one after the other (as the ones written by bgzip)
+/// # Example
+/// ```ignore
+/// let text = gunzip(&std::fs::read("graph.gfa.gz")?)?;
+/// ```
+pub fn gunzip(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut bits = BitReader {
+        bytes,
+        pos: 0,
+        bits: 0,
+        count: 0,
+    };
+    loop {
+        let header = bits.take(10)?;
+        if header[..3] != [0x1f, 0x8b, 8] {
+            return Err(invalid("not a gzip member"));
+        }
+        let flags = header[3];
+        if flags & 4 != 0 {
+            let len = bits.take(2)?;
+            bits.take(u16::from_le_bytes([len[0], len[1]]) as usize)?;
+        }
+        for flag in [8, 16].iter() {
+            if flags & flag != 0 {
+                while bits.take(1)?[0] != 0 {}
+            }
+        }
+        if flags & 2 != 0 {
+            bits.take(2)?;
+        }
+
+        let member_start = out.len();
+        inflate(&mut bits, &mut out)?;
+        bits.align();
+        let trailer = bits.take(8)?;
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        if crc != crc32(0, &out[member_start..]) || size != (out.len() - member_start) as u32 {
+            return Err(invalid("wrong checksum"));
+        }
+        // some tools pad the file with zeros
+        if bytes[bits.pos..].iter().all(|b| *b == 0) {
+            return Ok(out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn gzip(bytes: &[u8], level: u32) -> Vec<u8> {
+        let mut out = GzEncoder::new(Vec::new(), level);
+        // in small writes, to cross the blocks
+        for chunk in bytes.chunks(1000) {
+            out.write_all(chunk).unwrap();
+        }
+        out.finish().unwrap()
+    }
+
+    #[test]
+    fn gzip_round_trip() {
+        let text = std::fs::read("./tests/gfa1_files/lil.gfa").unwrap();
+        let mut big: Vec<u8> = text.repeat(500);
+        // bytes that don't repeat, with any value
+        let mut x: u32 = 7;
+        big.extend((0..100_000).map(|_| {
+            x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (x >> 16) as u8
+        }));
+        for bytes in [&b""[..], b"A", &text, &big].iter() {
+            for level in [0, 1, 6, 9].iter() {
+                let compressed = gzip(bytes, *level);
+                assert_eq!(&gunzip(&compressed).unwrap(), bytes, "level {}", level);
+                if *level > 0 && bytes.len() > 1000 {
+                    assert!(compressed.len() < bytes.len());
+                }
+            }
+        }
+        // a file with two members
+        let mut members = gzip(&text, 6);
+        members.extend(gzip(b"S\t99\tA\n", 0));
+        let mut expected = text.clone();
+        expected.extend_from_slice(b"S\t99\tA\n");
+        assert_eq!(gunzip(&members).unwrap(), expected);
+    }
+
+    #[test]
+    fn gzip_flush_writes_a_block() {
+        let mut out = GzEncoder::new(Vec::new(), 6);
+        out.write_all(b"H\tVN:Z:1.0\n").unwrap();
+        out.flush().unwrap();
+        out.write_all(b"S\t1\tACGT\n").unwrap();
+        let compressed = out.finish().unwrap();
+        assert_eq!(
+            gunzip(&compressed).unwrap(),
+            b"H\tVN:Z:1.0\nS\t1\tACGT\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn gunzip_rejects_damaged_files() {
+        let mut compressed = gzip(b"S\t1\tACGT\nS\t2\tACGT\n", 6);
+        assert!(gunzip(&compressed[..compressed.len() - 3]).is_err());
+        let len = compressed.len();
+        compressed[len - 8] ^= 1;
+        assert!(gunzip(&compressed).is_err());
+        assert!(gunzip(b"S\t1\tACGT\n").is_err());
+    }
+}
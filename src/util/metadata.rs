@@ -0,0 +1,155 @@
+//! This file provides a small JSON document describing a saved GFA file,
+//! to write next to it (as ```<file>.meta.json```) for the tools and the
+//! people that get the file without the graph.
+//!
+//! [`write_metadata_sidecar`](../../hashgraph/graph/struct.HashGraph.html#method.write_metadata_sidecar)
+//! writes an object with a field for each line:
+//! ```text
+//! {"schema":"gfahandlegraph-metadata/1",
+//! "writer":{"crate":"gfahandlegraph","version":"0.6.1","format":"GFA","deterministic":true,"validated":false,"fidelity":false},
+//! "digest":"fnv1a64:...",
+//! "nodes":15,
+//! "edges":21,
+//! "paths":3,
+//! "total_length":55,
+//! "id_bounds":{"min":1,"max":15},
+//! "samples":{"HG002":2,"HG003":1}}
+//! ```
+//! * ```writer``` is the version of the crate that wrote the file, the
+//!   format (```"GFA"``` or ```"GFA2"```) and the options used: the
+//!   savers always write the records sorted (```deterministic```),
+//!   ```validated``` is the ```validate``` of the
+//!   [`WriterConfig`](../to_file/struct.WriterConfig.html) and
+//!   ```fidelity``` is true for a file written by
+//!   [`write_gfa_preserving`](../../hashgraph/graph/struct.HashGraph.html#method.write_gfa_preserving)
+//! * ```digest``` is the 64 bits FNV-1a hash of all the bytes of the
+//!   file, as 16 hexadecimal digits after ```fnv1a64:```: a file changed
+//!   after the sidecar was written doesn't have the same digest
+//! * ```id_bounds``` is ```null``` for a graph without nodes
+//! * ```samples``` counts the paths of each sample, for the path names
+//!   following PanSN (```sample#haplotype#contig```, the sample is the
+//!   part before the first ```#```); it's ```null``` when no name does
+//!
+//! A new field can be added in the same schema, a field is never removed
+//! or changed without changing the schema.
+use bstr::ByteSlice;
+use fnv::FnvHasher;
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+use std::io::Write;
+
+use crate::{handlegraph::*, hashgraph::HashGraph, util::report::push_json_string};
+
+/// The version of the format written by
+/// [`write_metadata_sidecar`](../../hashgraph/graph/struct.HashGraph.html#method.write_metadata_sidecar)
+pub const METADATA_SCHEMA: &str = "gfahandlegraph-metadata/1";
+
+/// How a GFA file was written, to describe it in its sidecar
+/// ```ignore
+/// pub struct WriteContext {
+///     pub gfa2: bool,
+///     pub validated: bool,
+///     pub fidelity: bool,
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteContext {
+    pub gfa2: bool,
+    /// The file was read back and compared with the graph
+    pub validated: bool,
+    /// The file was written by ```write_gfa_preserving```
+    pub fidelity: bool,
+}
+
+/// The digest written in the sidecar of a file with these bytes
+pub(crate) fn content_digest(bytes: &[u8]) -> String {
+    let mut hasher = FnvHasher::default();
+    hasher.write(bytes);
+    format!("fnv1a64:{:016x}", hasher.finish())
+}
+
+/// The sidecar of the file written as ```ctx```, whose bytes have the
+/// digest ```digest```
+pub(crate) fn metadata_json(graph: &HashGraph, ctx: &WriteContext, digest: &str) -> String {
+    let mut out = String::new();
+    out.push_str("{\"schema\":");
+    push_json_string(&mut out, METADATA_SCHEMA);
+    out.push_str(&format!(
+        ",\n\"writer\":{{\"crate\":\"{}\",\"version\":\"{}\",\"format\":\"{}\",\
+         \"deterministic\":true,\"validated\":{},\"fidelity\":{}}}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        if ctx.gfa2 { "GFA2" } else { "GFA" },
+        ctx.validated,
+        ctx.fidelity
+    ));
+    out.push_str(",\n\"digest\":");
+    push_json_string(&mut out, digest);
+    out.push_str(&format!(",\n\"nodes\":{}", graph.node_count()));
+    out.push_str(&format!(",\n\"edges\":{}", graph.edge_count()));
+    out.push_str(&format!(",\n\"paths\":{}", graph.paths.len()));
+    out.push_str(&format!(",\n\"total_length\":{}", graph.total_length()));
+    match graph.id_bounds() {
+        Some((min, max)) => out.push_str(&format!(
+            ",\n\"id_bounds\":{{\"min\":{},\"max\":{}}}",
+            min, max
+        )),
+        None => out.push_str(",\n\"id_bounds\":null"),
+    }
+
+    // sorted by sample, so the same graph always has the same sidecar
+    let mut samples: BTreeMap<String, usize> = BTreeMap::new();
+    for path in graph.paths.values() {
+        if let Some(end) = path.name.find_byte(b'#') {
+            *samples
+                .entry(String::from_utf8_lossy(&path.name[..end]).into_owned())
+                .or_insert(0) += 1;
+        }
+    }
+    if samples.is_empty() {
+        out.push_str(",\n\"samples\":null");
+    } else {
+        out.push_str(",\n\"samples\":{");
+        for (ix, (sample, paths)) in samples.iter().enumerate() {
+            if ix > 0 {
+                out.push(',');
+            }
+            push_json_string(&mut out, sample);
+            out.push_str(&format!(":{}", paths));
+        }
+        out.push('}');
+    }
+    out.push_str("}\n");
+    out
+}
+
+impl HashGraph {
+    /// Function that writes the sidecar of the GFA file written from the
+    /// graph as described by ```ctx```, the JSON document described in
+    /// the [`metadata`](../../util/metadata/index.html) module.\
+    /// Its digest is computed writing the file again in memory; the
+    /// savers given a
+    /// [`WriterConfig`](../../util/to_file/struct.WriterConfig.html)
+    /// with ```sidecar``` write it next to the file using the bytes
+    /// they wrote
+    /// # Example
+    /// ```ignore
+    /// let ctx = WriteContext::default();
+    /// graph.write_metadata_sidecar(&mut File::create("out.gfa.meta.json")?, &ctx)?;
+    /// ```
+    pub fn write_metadata_sidecar<W: Write>(
+        &self,
+        out: &mut W,
+        ctx: &WriteContext,
+    ) -> std::io::Result<()> {
+        let bytes = if ctx.fidelity {
+            let mut bytes = vec![];
+            self.write_gfa_preserving(&mut bytes)?;
+            bytes
+        } else {
+            super::to_file::format_gfa(self, ctx.gfa2).into_bytes()
+        };
+        let digest = content_digest(&bytes);
+        out.write_all(metadata_json(self, ctx, &digest).as_bytes())
+    }
+}
@@ -0,0 +1,697 @@
+//! This file provides the stable codes of the errors and the diagnostics
+//! of the crate, and a JSON report of them for the tools that have to
+//! tell them apart without reading their messages.
+//!
+//! Every variant has a code made of a letter and three digits, returned
+//! by its ```code``` method:
+//! * ```P```: [`ParseError`](../../parser/error/enum.ParseError.html)
+//! * ```F```: [`ParseFieldError`](../../parser/error/enum.ParseFieldError.html)
+//! * ```G```: [`GraphError`](../../handlegraph/error/enum.GraphError.html)
+//! * ```W```: [`WriteError`](../to_file/enum.WriteError.html)
+//! * ```C```: [`ConversionError`](../enum.ConversionError.html)
+//! * ```A```: [`AttachError`](../../hashgraph/enum.AttachError.html)
+//! * ```V```: the diagnostics found checking a graph or its files:
+//!   ```V001``` [`BrokenAdjacency`](../../hashgraph/struct.BrokenAdjacency.html),
+//!   ```V002``` [`PathOrientationAnomaly`](../../hashgraph/struct.PathOrientationAnomaly.html),
+//!   ```V003``` and ```V004``` the edges and the paths of
+//!   [`UnresolvedReference`](../../hashgraph/builder/enum.UnresolvedReference.html),
+//!   ```V005``` [`OverlayWarning`](../../parser/multi/struct.OverlayWarning.html),
+//!   ```V006``` and ```V007``` the edges and the path steps dropped by a
+//!   [`RecoveryReport`](../../parser/recovery/struct.RecoveryReport.html),
+//!   ```V008``` [`IdBoundsMismatch`](../../hashgraph/struct.IdBoundsMismatch.html),
+//!   ```V009``` to ```V011``` the edges, the steps and the occurrences of
+//!   [`ConsistencyError`](../../hashgraph/graph/enum.ConsistencyError.html),
+//!   ```V012``` the segments of
+//!   [`ValidationError`](../../gfa/gfa2/enum.ValidationError.html),
+//!   ```V013``` [`LengthMismatch`](../../hashgraph/struct.LengthMismatch.html)
+//!
+//! The codes are append-only: a new variant gets the next free code of
+//! its letter, and the code of a removed variant is never used again,
+//! so a code always means the same thing across versions.
+//!
+//! [`to_json_report`](fn.to_json_report.html) writes the items as a
+//! JSON object, with an item for each line:
+//! ```text
+//! {"schema":"gfahandlegraph-report/1","items":[
+//! {"code":"P012","severity":"error","message":"...","line":4},
+//! {"code":"V002","severity":"warning","message":"...","node":"3","path":"x"}
+//! ]}
+//! ```
+//! * ```code``` and ```message``` are strings, ```severity``` is
+//!   ```"error"``` or ```"warning"```
+//! * ```line``` is the (1-based) line of the file, a number
+//! * ```node``` (as ```12```), ```edge``` (as ```1+,2-```) and ```path```
+//!   (its name) identify what the item is about, as strings
+//!
+//! The fields of an item without a value are left out.
+use crate::gfa::gfa2::ValidationError;
+use crate::handle::{Edge, Handle, NodeId};
+use crate::handlegraph::error::GraphError;
+use crate::hashgraph::{
+    AttachError, BrokenAdjacency, ConsistencyError, LengthMismatch, PathOrientationAnomaly,
+    UnresolvedReference,
+};
+use crate::parser::{OverlayWarning, ParseError, ParseFieldError, RecoveryReport};
+use crate::util::{conversion::ConversionError, to_file::WriteError};
+
+/// The version of the format written by
+/// [`to_json_report`](fn.to_json_report.html)
+pub const REPORT_SCHEMA: &str = "gfahandlegraph-report/1";
+
+/// How bad the problem of a [`ReportItem`](struct.ReportItem.html) is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The file or the graph can't be used as it is
+    Error,
+    /// Something was ignored or looks suspicious, but it can be used
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// An error or a diagnostic, with its code, ready to be written by
+/// [`to_json_report`](fn.to_json_report.html)
+/// ```ignore
+/// pub struct ReportItem {
+///     pub code: &'static str,
+///     pub severity: Severity,
+///     pub message: String,
+///     pub line: Option<usize>,
+///     pub node: Option<String>,
+///     pub edge: Option<String>,
+///     pub path: Option<String>,
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportItem {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub line: Option<usize>,
+    pub node: Option<String>,
+    pub edge: Option<String>,
+    pub path: Option<String>,
+}
+
+fn format_handle(h: &Handle) -> String {
+    format!("{}{}", h.id(), if h.is_reverse() { '-' } else { '+' })
+}
+
+impl ReportItem {
+    pub fn new(code: &'static str, severity: Severity, message: String) -> Self {
+        ReportItem {
+            code,
+            severity,
+            message,
+            line: None,
+            node: None,
+            edge: None,
+            path: None,
+        }
+    }
+
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn with_node<T: Into<NodeId>>(mut self, node: T) -> Self {
+        self.node = Some(node.into().to_string());
+        self
+    }
+
+    pub fn with_edge(mut self, Edge(l, r): Edge) -> Self {
+        self.edge = Some(format!("{},{}", format_handle(&l), format_handle(&r)));
+        self
+    }
+
+    pub fn with_path(mut self, name: &[u8]) -> Self {
+        self.path = Some(String::from_utf8_lossy(name).into_owned());
+        self
+    }
+}
+
+impl From<&ParseError> for ReportItem {
+    fn from(err: &ParseError) -> Self {
+        let severity = match err {
+            ParseError::UnknownLineType | ParseError::EmptyLine => Severity::Warning,
+            _ => Severity::Error,
+        };
+        let item = ReportItem::new(err.code(), severity, err.to_string());
+        match err {
+            ParseError::ConflictingSegment(segment, _, _) => ReportItem {
+                node: Some(segment.clone()),
+                ..item
+            },
+            ParseError::InvalidLineAt(number, _, _) => item.with_line(*number),
+            _ => item,
+        }
+    }
+}
+
+impl From<&ParseFieldError> for ReportItem {
+    fn from(err: &ParseFieldError) -> Self {
+        ReportItem::new(err.code(), Severity::Error, err.to_string())
+    }
+}
+
+impl From<&GraphError> for ReportItem {
+    fn from(err: &GraphError) -> Self {
+        let item = ReportItem::new(err.code(), Severity::Error, err.to_string());
+        match err {
+            GraphError::IdAlreadyExist(node)
+            | GraphError::NodeNotExist(node)
+            | GraphError::NameCollision(_, node)
+            | GraphError::ChainNotSimple(node, _)
+            | GraphError::InvalidOffset(node, _) => ReportItem {
+                node: Some(node.clone()),
+                ..item
+            },
+            GraphError::PathNotExist(path)
+            | GraphError::PathAlreadyExist(path)
+            | GraphError::InvalidPathStep(path, _) => ReportItem {
+                path: Some(path.clone()),
+                ..item
+            },
+            GraphError::MissingStepNode(path, node, _) => ReportItem {
+                path: Some(path.clone()),
+                node: Some(node.clone()),
+                ..item
+            },
+            // what the record is about is told by the error that rejected it
+            GraphError::InvalidBatchRecord(_, why) => {
+                let rejected = ReportItem::from(why.as_ref());
+                ReportItem {
+                    node: rejected.node,
+                    path: rejected.path,
+                    ..item
+                }
+            }
+            _ => item,
+        }
+    }
+}
+
+impl From<&WriteError> for ReportItem {
+    fn from(err: &WriteError) -> Self {
+        ReportItem::new(err.code(), Severity::Error, err.to_string())
+    }
+}
+
+impl From<&ConversionError> for ReportItem {
+    fn from(err: &ConversionError) -> Self {
+        ReportItem::new(err.code(), Severity::Error, err.to_string())
+    }
+}
+
+impl From<&AttachError> for ReportItem {
+    fn from(err: &AttachError) -> Self {
+        let item = ReportItem::new(err.code(), Severity::Error, err.to_string());
+        match err {
+            AttachError::InvalidFasta(line) => item.with_line(*line),
+            AttachError::Io(_) | AttachError::DuplicateRecord(_) => item,
+        }
+    }
+}
+
+impl From<&BrokenAdjacency> for ReportItem {
+    fn from(broken: &BrokenAdjacency) -> Self {
+        let message = format!(
+            "Path {} step {}: no edge connects {} to {}",
+            broken.path_name,
+            broken.step,
+            format_handle(&broken.left),
+            format_handle(&broken.right)
+        );
+        ReportItem::new(broken.code(), Severity::Error, message)
+            .with_edge(Edge(broken.left, broken.right))
+            .with_path(&broken.path_name)
+    }
+}
+
+impl From<&PathOrientationAnomaly> for ReportItem {
+    fn from(anomaly: &PathOrientationAnomaly) -> Self {
+        ReportItem::new(anomaly.code(), Severity::Warning, anomaly.to_string())
+            .with_node(anomaly.suggested_fix.id())
+            .with_path(&anomaly.path)
+    }
+}
+
+impl From<&UnresolvedReference> for ReportItem {
+    fn from(reference: &UnresolvedReference) -> Self {
+        let item = ReportItem::new(reference.code(), Severity::Error, reference.to_string());
+        let item = match reference.missing().first() {
+            Some(node) => item.with_node(*node),
+            None => item,
+        };
+        match reference {
+            UnresolvedReference::Edge(edge, _) => item.with_edge(*edge),
+            UnresolvedReference::Path(name, _) => item.with_path(name),
+        }
+    }
+}
+
+impl From<&ConsistencyError> for ReportItem {
+    fn from(error: &ConsistencyError) -> Self {
+        let item = ReportItem::new(error.code(), Severity::Error, error.to_string());
+        match error {
+            ConsistencyError::UnmirroredEdge { node, edge } => {
+                item.with_node(*node).with_edge(*edge)
+            }
+            ConsistencyError::MissingStepNode {
+                path_name, node, ..
+            } => item.with_node(*node).with_path(path_name),
+            ConsistencyError::DanglingOccurrence { node, .. } => item.with_node(*node),
+            ConsistencyError::IdBounds(mismatch) => ReportItem::from(mismatch),
+        }
+    }
+}
+
+impl From<&ValidationError> for ReportItem {
+    fn from(error: &ValidationError) -> Self {
+        let item = ReportItem::new(error.code(), Severity::Error, error.to_string());
+        match error {
+            ValidationError::SegmentLength { id, .. } => item.with_node(*id),
+        }
+    }
+}
+
+impl From<&LengthMismatch> for ReportItem {
+    fn from(mismatch: &LengthMismatch) -> Self {
+        ReportItem::new(mismatch.code(), Severity::Warning, mismatch.to_string())
+            .with_node(mismatch.id)
+    }
+}
+
+impl From<&OverlayWarning> for ReportItem {
+    fn from(warning: &OverlayWarning) -> Self {
+        ReportItem::new(warning.code(), Severity::Warning, warning.to_string())
+    }
+}
+
+impl RecoveryReport {
+    /// Function that returns the damage found as report items: one for
+    /// each skipped line (with its line number), one for the dropped
+    /// edges and one for each path that lost some steps
+    /// # Example
+    /// ```ignore
+    /// let (graph, report) = recover_file_to_graph("./tests/big_files/test.gfa").unwrap();
+    /// println!("{}", to_json_report(&report.report_items()));
+    /// ```
+    pub fn report_items(&self) -> Vec<ReportItem> {
+        let mut items: Vec<ReportItem> = self
+            .skipped_lines
+            .iter()
+            .map(|(line, _, err)| ReportItem::from(err).with_line(*line))
+            .collect();
+        if self.dropped_edges > 0 {
+            items.push(ReportItem::new(
+                "V006",
+                Severity::Warning,
+                format!(
+                    "Dropped {} edges referencing a missing segment",
+                    self.dropped_edges
+                ),
+            ));
+        }
+        for (path, dropped) in self.dropped_steps_per_path.iter() {
+            let message = format!("Path {} lost {} steps on a missing segment", path, dropped);
+            items.push(ReportItem::new("V007", Severity::Warning, message).with_path(path));
+        }
+        items
+    }
+}
+
+pub(crate) fn push_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Function that writes the items as the JSON report described in the
+/// [`report`](index.html) module, in the order they are given
+/// # Example
+/// ```ignore
+/// let items: Vec<ReportItem> = graph
+///     .path_orientation_anomalies()
+///     .iter()
+///     .map(ReportItem::from)
+///     .collect();
+/// println!("{}", to_json_report(&items));
+/// ```
+pub fn to_json_report(items: &[ReportItem]) -> String {
+    let mut out = String::new();
+    out.push_str("{\"schema\":");
+    push_json_string(&mut out, REPORT_SCHEMA);
+    out.push_str(",\"items\":[");
+    for (ix, item) in items.iter().enumerate() {
+        out.push_str(if ix == 0 { "\n" } else { ",\n" });
+        out.push_str("{\"code\":");
+        push_json_string(&mut out, item.code);
+        out.push_str(",\"severity\":");
+        push_json_string(&mut out, item.severity.as_str());
+        out.push_str(",\"message\":");
+        push_json_string(&mut out, &item.message);
+        if let Some(line) = item.line {
+            out.push_str(&format!(",\"line\":{}", line));
+        }
+        let identifiers = [
+            ("node", &item.node),
+            ("edge", &item.edge),
+            ("path", &item.path),
+        ];
+        for (key, value) in identifiers.iter() {
+            if let Some(value) = value {
+                out.push_str(&format!(",\"{}\":", key));
+                push_json_string(&mut out, value);
+            }
+        }
+        out.push('}');
+    }
+    if !items.is_empty() {
+        out.push('\n');
+    }
+    out.push_str("]}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashgraph::IdBoundsMismatch;
+    use bstr::BString;
+    use fnv::FnvHashSet;
+
+    // the matches without a catch-all arm stop compiling when a variant
+    // is added, until it's added to these lists too
+
+    fn parse_errors() -> Vec<ParseError> {
+        let all = vec![
+            ParseError::UnknownLineType,
+            ParseError::EmptyLine,
+            ParseError::InvalidLine(ParseFieldError::MissingFields, "S".to_string()),
+            ParseError::InvalidField(ParseFieldError::MissingFields),
+            ParseError::IOError(std::io::Error::from(std::io::ErrorKind::NotFound)),
+            ParseError::ExtensionError(),
+            ParseError::ConversionGFAToGraph("why".to_string()),
+            ParseError::TruncatedLine("S\t1".to_string()),
+            ParseError::InvalidArchive("why".to_string()),
+            ParseError::ConflictingSegment("1".into(), "b.gfa".into(), "a.gfa".into()),
+            ParseError::Unknown,
+            ParseError::InvalidLineAt(3, ParseFieldError::MissingFields, "S".to_string()),
+        ];
+        for err in all.iter() {
+            match err {
+                ParseError::UnknownLineType
+                | ParseError::EmptyLine
+                | ParseError::InvalidLine(_, _)
+                | ParseError::InvalidField(_)
+                | ParseError::IOError(_)
+                | ParseError::ExtensionError()
+                | ParseError::ConversionGFAToGraph(_)
+                | ParseError::TruncatedLine(_)
+                | ParseError::InvalidArchive(_)
+                | ParseError::ConflictingSegment(_, _, _)
+                | ParseError::Unknown
+                | ParseError::InvalidLineAt(_, _, _) => (),
+            }
+        }
+        all
+    }
+
+    fn field_errors() -> Vec<ParseFieldError> {
+        let all = vec![
+            ParseFieldError::UintIdError,
+            ParseFieldError::Utf8Error,
+            ParseFieldError::ParseFromStringError,
+            ParseFieldError::OrientationError,
+            ParseFieldError::InvalidField("name"),
+            ParseFieldError::IdOverflow("99".to_string()),
+            ParseFieldError::MissingFields,
+            ParseFieldError::Unknown,
+        ];
+        for err in all.iter() {
+            match err {
+                ParseFieldError::UintIdError
+                | ParseFieldError::Utf8Error
+                | ParseFieldError::ParseFromStringError
+                | ParseFieldError::OrientationError
+                | ParseFieldError::InvalidField(_)
+                | ParseFieldError::IdOverflow(_)
+                | ParseFieldError::MissingFields
+                | ParseFieldError::Unknown => (),
+            }
+        }
+        all
+    }
+
+    fn graph_errors() -> Vec<GraphError> {
+        let s = || "1".to_string();
+        let all = vec![
+            GraphError::IdAlreadyExist(s()),
+            GraphError::EmptySequence,
+            GraphError::NodeNotExist(s()),
+            GraphError::EdgeNotExist(s(), s()),
+            GraphError::EdgeAlreadyExist(s(), s()),
+            GraphError::PathNotExist(s()),
+            GraphError::PathAlreadyExist(s()),
+            GraphError::OrientationNotExists(s()),
+            GraphError::PositionNotFound(s(), s()),
+            GraphError::Unknown,
+            GraphError::RepeatedVisit(s(), s()),
+            GraphError::SequenceUnavailable(s()),
+            GraphError::MissingStepNode(s(), s(), 0),
+            GraphError::NameCollision(s(), s()),
+            GraphError::ChainNotSimple(s(), s()),
+            GraphError::InvalidOffset(s(), 0),
+            GraphError::InvalidPathStep(s(), s()),
+            GraphError::IdOverflow(s(), 0),
+            GraphError::SequenceConflict(vec![s()]),
+            GraphError::InvalidBatchRecord(s(), Box::new(GraphError::Unknown)),
+        ];
+        for err in all.iter() {
+            match err {
+                GraphError::IdAlreadyExist(_)
+                | GraphError::EmptySequence
+                | GraphError::NodeNotExist(_)
+                | GraphError::EdgeNotExist(_, _)
+                | GraphError::EdgeAlreadyExist(_, _)
+                | GraphError::PathNotExist(_)
+                | GraphError::PathAlreadyExist(_)
+                | GraphError::OrientationNotExists(_)
+                | GraphError::PositionNotFound(_, _)
+                | GraphError::Unknown
+                | GraphError::RepeatedVisit(_, _)
+                | GraphError::SequenceUnavailable(_)
+                | GraphError::MissingStepNode(_, _, _)
+                | GraphError::NameCollision(_, _)
+                | GraphError::ChainNotSimple(_, _)
+                | GraphError::InvalidOffset(_, _)
+                | GraphError::InvalidPathStep(_, _)
+                | GraphError::IdOverflow(_, _)
+                | GraphError::SequenceConflict(_)
+                | GraphError::InvalidBatchRecord(_, _) => (),
+            }
+        }
+        all
+    }
+
+    fn write_errors() -> Vec<WriteError> {
+        let all = vec![
+            WriteError::Io(std::io::Error::from(std::io::ErrorKind::NotFound)),
+            WriteError::WriteValidationFailed {
+                path: "a.gfa.tmp".to_string(),
+                diff_summary: String::new(),
+            },
+        ];
+        for err in all.iter() {
+            match err {
+                WriteError::Io(_) | WriteError::WriteValidationFailed { .. } => (),
+            }
+        }
+        all
+    }
+
+    fn attach_errors() -> Vec<AttachError> {
+        let all = vec![
+            AttachError::Io(std::io::Error::from(std::io::ErrorKind::NotFound)),
+            AttachError::InvalidFasta(1),
+            AttachError::DuplicateRecord(BString::from("x")),
+        ];
+        for err in all.iter() {
+            match err {
+                AttachError::Io(_)
+                | AttachError::InvalidFasta(_)
+                | AttachError::DuplicateRecord(_) => (),
+            }
+        }
+        all
+    }
+
+    fn conversion_errors() -> Vec<ConversionError> {
+        let all = vec![
+            ConversionError::UnsupportedRecord("F".to_string()),
+            ConversionError::NotDovetail("1+,2-".to_string()),
+            ConversionError::UnnamedGroup(0),
+        ];
+        for err in all.iter() {
+            match err {
+                ConversionError::UnsupportedRecord(_)
+                | ConversionError::NotDovetail(_)
+                | ConversionError::UnnamedGroup(_) => (),
+            }
+        }
+        all
+    }
+
+    fn diagnostics() -> Vec<ReportItem> {
+        let (l, r) = (Handle::pack(1, false), Handle::pack(2, true));
+        let references = [
+            UnresolvedReference::Edge(Edge(l, r), vec![NodeId::from(2)]),
+            UnresolvedReference::Path(BString::from("x"), vec![NodeId::from(2)]),
+        ];
+        for reference in references.iter() {
+            match reference {
+                UnresolvedReference::Edge(_, _) | UnresolvedReference::Path(_, _) => (),
+            }
+        }
+        let mut items = vec![
+            ReportItem::from(&BrokenAdjacency {
+                path_id: 0,
+                path_name: BString::from("x"),
+                step: 0,
+                left: l,
+                right: r,
+            }),
+            ReportItem::from(&PathOrientationAnomaly {
+                path: BString::from("x"),
+                index: 1,
+                suggested_fix: r,
+            }),
+            ReportItem::from(&OverlayWarning {
+                file: "a.gfa".to_string(),
+                record: "segment 1".to_string(),
+            }),
+            ReportItem::from(&IdBoundsMismatch {
+                min_id: NodeId::from(1),
+                max_id: NodeId::from(3),
+                expected: None,
+            }),
+        ];
+        items.extend(references.iter().map(ReportItem::from));
+        let inconsistencies = [
+            ConsistencyError::UnmirroredEdge {
+                node: NodeId::from(1),
+                edge: Edge(l, r),
+            },
+            ConsistencyError::MissingStepNode {
+                path_id: 0,
+                path_name: BString::from("x"),
+                index: 0,
+                node: NodeId::from(2),
+            },
+            ConsistencyError::DanglingOccurrence {
+                node: NodeId::from(1),
+                path_id: 0,
+                index: 1,
+            },
+        ];
+        for error in inconsistencies.iter() {
+            match error {
+                ConsistencyError::UnmirroredEdge { .. }
+                | ConsistencyError::MissingStepNode { .. }
+                | ConsistencyError::DanglingOccurrence { .. } => (),
+                // the code of IdBoundsMismatch
+                ConsistencyError::IdBounds(_) => (),
+            }
+        }
+        items.extend(inconsistencies.iter().map(ReportItem::from));
+        let invalid = ValidationError::SegmentLength {
+            id: 1,
+            slen: 4,
+            sequence_len: 3,
+        };
+        match invalid {
+            ValidationError::SegmentLength { .. } => (),
+        }
+        items.push(ReportItem::from(&invalid));
+        items.push(ReportItem::from(&LengthMismatch {
+            id: NodeId::from(1),
+            declared: 4,
+            found: 3,
+        }));
+        let report = RecoveryReport {
+            dropped_edges: 1,
+            dropped_steps_per_path: vec![(BString::from("x"), 1)],
+            ..Default::default()
+        };
+        items.extend(report.report_items());
+        items
+    }
+
+    #[test]
+    fn unique_codes() {
+        let mut codes: Vec<&'static str> = vec![];
+        codes.extend(parse_errors().iter().map(ParseError::code));
+        codes.extend(field_errors().iter().map(ParseFieldError::code));
+        codes.extend(graph_errors().iter().map(GraphError::code));
+        codes.extend(write_errors().iter().map(WriteError::code));
+        codes.extend(conversion_errors().iter().map(ConversionError::code));
+        codes.extend(attach_errors().iter().map(AttachError::code));
+        codes.extend(diagnostics().iter().map(|item| item.code));
+        assert_eq!(codes.len(), 12 + 8 + 20 + 2 + 3 + 3 + 13);
+
+        let mut seen = FnvHashSet::default();
+        for code in codes {
+            assert!(seen.insert(code), "{} is used twice", code);
+            let bytes = code.as_bytes();
+            assert_eq!(bytes.len(), 4, "{}", code);
+            assert!(b"PFGWCVA".contains(&bytes[0]), "{}", code);
+            assert!(bytes[1..].iter().all(u8::is_ascii_digit), "{}", code);
+        }
+    }
+
+    #[test]
+    fn json_report() {
+        assert_eq!(
+            to_json_report(&[]),
+            "{\"schema\":\"gfahandlegraph-report/1\",\"items\":[]}\n"
+        );
+        let items = vec![
+            ReportItem::new("P003", Severity::Error, "bad \"line\"\t\\\u{1}".to_string())
+                .with_line(4),
+            ReportItem::new("V001", Severity::Warning, "broken".to_string())
+                .with_node(3)
+                .with_edge(Edge(Handle::pack(1, false), Handle::pack(2, true)))
+                .with_path(b"x"),
+        ];
+        assert_eq!(
+            to_json_report(&items),
+            concat!(
+                "{\"schema\":\"gfahandlegraph-report/1\",\"items\":[\n",
+                "{\"code\":\"P003\",\"severity\":\"error\",",
+                "\"message\":\"bad \\\"line\\\"\\t\\\\\\u0001\",\"line\":4},\n",
+                "{\"code\":\"V001\",\"severity\":\"warning\",\"message\":\"broken\",",
+                "\"node\":\"3\",\"edge\":\"1+,2-\",\"path\":\"x\"}\n",
+                "]}\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,88 @@
+/// This file provides the control over the number of threads used by
+/// the parallel sections of the crate
+use rayon::prelude::*;
+
+/// Number of threads used by the parsers and the parallel algorithms
+/// ```ignore
+/// pub struct ThreadConfig {
+///     pub threads: Option<usize>,
+/// }
+/// ```
+/// * ```None``` (or 0) uses the global rayon pool, as before
+/// * ```Some(1)``` takes the sequential code paths, without building a
+///   pool, so the order of the work is deterministic
+/// * ```Some(n)``` runs the parallel sections inside a dedicated pool of
+///   ```n``` threads, built for each call
+///
+/// The order of the parsed records (and so the ids of the paths in the
+/// graph) depends on the order the lines are parsed, so it's stable only
+/// with a single thread.\
+/// The small parallel loops used when adding an edge to an HashGraph
+/// always use the current pool, that is the global one with a single
+/// thread
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThreadConfig {
+    pub threads: Option<usize>,
+}
+
+impl ThreadConfig {
+    /// Use ```threads``` threads
+    pub fn new(threads: usize) -> Self {
+        ThreadConfig {
+            threads: Some(threads),
+        }
+    }
+
+    /// Use a single thread, without any pool
+    pub fn sequential() -> Self {
+        ThreadConfig::new(1)
+    }
+
+    /// Use the global rayon pool
+    pub fn global() -> Self {
+        Default::default()
+    }
+
+    #[inline]
+    pub fn is_sequential(&self) -> bool {
+        self.threads == Some(1)
+    }
+
+    /// Function that runs ```op``` inside the pool of the config, so that
+    /// every parallel iterator used by ```op``` runs on that pool.\
+    /// With a single thread, or with the global pool, ```op``` simply runs
+    /// on the current thread; if the pool can't be built ```op``` runs on
+    /// the global pool
+    /// # Example
+    /// ```ignore
+    /// let matrix = ThreadConfig::new(4).install(|| graph.path_similarity(&ids, false));
+    /// ```
+    pub fn install<OP, R>(&self, op: OP) -> R
+    where
+        OP: FnOnce() -> R + Send,
+        R: Send,
+    {
+        match self.threads {
+            Some(n) if n > 1 => match rayon::ThreadPoolBuilder::new().num_threads(n).build() {
+                Ok(pool) => pool.install(op),
+                Err(_) => op(),
+            },
+            _ => op(),
+        }
+    }
+
+    /// Map the items in parallel, or one after the other with a single
+    /// thread, keeping their order
+    pub(crate) fn map<T, R, F>(&self, items: &[T], f: F) -> Vec<R>
+    where
+        T: Sync,
+        R: Send,
+        F: Fn(&T) -> R + Sync + Send,
+    {
+        if self.is_sequential() {
+            items.iter().map(f).collect()
+        } else {
+            self.install(|| items.par_iter().map(f).collect())
+        }
+    }
+}
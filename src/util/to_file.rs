@@ -1,156 +1,649 @@
-use crate::{handle::Edge, handlegraph::*, hashgraph::HashGraph, pathgraph::PathHandleGraph};
+use crate::{
+    gfa::optional::OptField,
+    handle::{Edge, Handle},
+    handlegraph::*,
+    hashgraph::{graph::FileType, HashGraph, NodeChange},
+    parser::{parser_gfa1, parser_gfa2, ParserTolerance},
+    pathgraph::PathHandleGraph,
+    util::bincode,
+    util::gzip::{gunzip, Compression, GzEncoder},
+    util::metadata::{content_digest, metadata_json, WriteContext},
+};
 
-use bstr::BString;
-use rayon::iter::ParallelIterator;
+use bstr::{BString, ByteSlice};
 use std::fs::File;
-use std::io::Write;
-use std::sync::Mutex;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::{error, fmt};
 
-/// take an HashGraph and create a GFA1 or GFA2 file from it and save that file on a specific
-/// location or on a default one
-pub fn to_gfa(graph: &HashGraph, format: String, path: Option<String>) -> std::io::Result<()> {
+/// How [`to_gfa_with_config`](fn.to_gfa_with_config.html) saves a graph
+/// ```ignore
+/// pub struct WriterConfig {
+///     pub validate: bool,
+///     pub sidecar: bool,
+///     pub compression: Compression,
+/// }
+/// ```
+/// * ```validate``` writes the file next to the destination, parses it
+///   again in ```Pedantic``` tolerance and compares the graph obtained
+///   with the one saved, before renaming the file to the destination:
+///   the file is read back, so the save takes about as long as parsing it
+/// * ```sidecar``` writes also ```<path>.meta.json```, the
+///   [`metadata`](../metadata/index.html) of the file saved, once the
+///   file is in place; it's written to ```<path>.meta.json.tmp``` and
+///   then renamed, so it's never left half written
+/// * ```compression``` compresses the file with gzip, as it's written;
+///   with ```Compression::None``` the file is compressed only when the
+///   path ends with ```.gz```. The sidecar isn't compressed, and its
+///   digest is the one of the uncompressed text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriterConfig {
+    pub validate: bool,
+    pub sidecar: bool,
+    pub compression: Compression,
+}
+
+/// Type encapsulating the errors of
+/// [`to_gfa_with_config`](fn.to_gfa_with_config.html)
+/// ```ignore
+/// pub enum WriteError {
+///     Io(std::io::Error),
+///     WriteValidationFailed { path: String, diff_summary: String },
+/// }
+/// ```
+#[derive(Debug)]
+pub enum WriteError {
+    Io(std::io::Error),
+    /// The file written isn't read back as the graph saved, it's kept in
+    /// ```path``` and the destination isn't touched
+    WriteValidationFailed {
+        path: String,
+        diff_summary: String,
+    },
+}
+
+impl WriteError {
+    /// The stable code of the error, see the
+    /// [`report`](../report/index.html) module
+    pub fn code(&self) -> &'static str {
+        match self {
+            WriteError::Io(_) => "W001",
+            WriteError::WriteValidationFailed { .. } => "W002",
+        }
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::Io(why) => write!(f, "{}", why),
+            WriteError::WriteValidationFailed { path, diff_summary } => write!(
+                f,
+                "The file written in {} isn't the graph saved: {}",
+                path, diff_summary
+            ),
+        }
+    }
+}
+
+impl error::Error for WriteError {}
+
+impl From<std::io::Error> for WriteError {
+    fn from(why: std::io::Error) -> Self {
+        WriteError::Io(why)
+    }
+}
+
+/// Number of differences listed by a ```WriteValidationFailed```
+const DIFF_SUMMARY_LEN: usize = 5;
+
+fn default_path(gfa2: bool) -> String {
+    if gfa2 {
+        String::from("./tests/output_files/default_path/converted_hashgraph.gfa2")
+    } else {
+        String::from("./tests/output_files/default_path/converted_hashgraph.gfa")
+    }
+}
+
+fn is_gfa2(format: &str) -> bool {
     match format.to_uppercase().as_str() {
-        "GFA2" => {
-            let path = path.unwrap_or_else(|| {
-                String::from("./tests/output_files/default_path/converted_hashgraph.gfa2")
-            });
-            let mut file = File::create(&path)?;
-            let res = Mutex::new(String::new());
-            res.lock().unwrap().push_str("H\tVN:Z:2.0\n");
-
-            graph.handles_par().for_each(|h| {
-                let id = usize::from(h.id());
-                let sequence: BString = graph.sequence_iter(h.forward()).collect();
-                let len: BString = BString::from(sequence.len().to_string());
-
-                res.lock()
-                    .unwrap()
-                    .push_str(&*format!("S\t{}\t{}\t{}\n", id, len, sequence));
-            });
-
-            let orient = |rev: bool| {
-                if rev {
-                    "-"
-                } else {
-                    "+"
-                }
-            };
+        "GFA2" => true,
+        "GFA" => false,
+        _ => panic!("Error the format it's not correct!"),
+    }
+}
 
-            graph.edges_par().for_each(|e| {
-                let Edge(left, right) = e;
+fn orient(rev: bool) -> &'static str {
+    if rev {
+        "-"
+    } else {
+        "+"
+    }
+}
 
-                let sid1_id: String = left.id().to_string();
-                let sid1_orient = orient(left.is_reverse());
-                let sid1 = format!("{}{}", sid1_id, sid1_orient);
+/// Function that writes the graph as a GFA1 file in ```out```, through
+/// a ```BufWriter```: the header (with the tags of the header of the
+/// graph), the segments and the links sorted by id, then the paths in
+/// the order they were created and the containments in the order of the
+/// file, so the same graph is always written the same way
+/// # Example
+/// ```ignore
+/// let mut text = vec![];
+/// write_gfa1(&graph, &mut text)?;
+/// write_gfa1(&graph, std::io::stdout().lock())?;
+/// ```
+pub fn write_gfa1<W: Write>(graph: &HashGraph, out: W) -> std::io::Result<()> {
+    write_gfa(graph, out, false)
+}
 
-                let sid2_id: String = right.id().to_string();
-                let sid2_orient = orient(right.is_reverse());
-                let sid2 = format!("{}{}", sid2_id, sid2_orient);
+/// Function that writes the graph as a GFA2 file in ```out```, as
+/// [`write_gfa1`](fn.write_gfa1.html), with the subsets (sorted by
+/// name) as U-groups in place of the containments
+pub fn write_gfa2<W: Write>(graph: &HashGraph, out: W) -> std::io::Result<()> {
+    write_gfa(graph, out, true)
+}
 
-                res.lock().unwrap().push_str(&*format!(
-                    "E\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
-                    "*", sid1, sid2, "0", "0$", "0", "0$", "0M"
-                ));
-            });
+fn write_gfa<W: Write>(graph: &HashGraph, out: W, gfa2: bool) -> std::io::Result<()> {
+    let mut out = BufWriter::new(out);
+    let tags = |out: &mut BufWriter<W>, optional: &[OptField]| -> std::io::Result<()> {
+        for tag in optional {
+            write!(out, "\t{}", tag)?;
+        }
+        Ok(())
+    };
 
-            graph.paths().for_each(|p| {
-                let id: BString = graph.path_handle_to_name(p).into();
-                let mut segment_names: Vec<String> = Vec::new();
+    // the header has the version of the format written, and the trace
+    // spacing only in GFA2
+    out.write_all(if gfa2 { b"H\tVN:Z:2.0" } else { b"H\tVN:Z:1.0" })?;
+    if let Some(header) = &graph.header {
+        for tag in header.tags.iter().filter(|t| gfa2 || &t.tag != b"TS") {
+            write!(out, "\t{}", tag)?;
+        }
+    }
+    out.write_all(b"\n")?;
 
-                graph.steps(p).for_each(|s| {
-                    let handle = graph.handle_of_step(&s).unwrap();
-                    let segment: String = handle.id().to_string();
-                    let orientation = orient(handle.is_reverse());
+    // the segments are written with their name in the parsed file
+    let name = |id| graph.display_node(id);
+    for h in graph.handles_sorted() {
+        let node = graph.get_node_unchecked(&h.id());
+        let sequence = graph
+            .try_node_sequence(node)
+            .map_err(|why| std::io::Error::other(why.to_string()))?;
+        if gfa2 {
+            // a segment without its sequence is written back as it was read
+            let (slen, sequence) = match graph.placeholders.get(&h.id()) {
+                Some(declared) => (*declared, &b"*"[..]),
+                None => (sequence.len(), sequence),
+            };
+            write!(out, "S\t{}\t{}\t", name(h.id()), slen)?;
+            out.write_all(sequence)?;
+        } else {
+            write!(out, "S\t{}\t", name(h.id()))?;
+            out.write_all(sequence)?;
+        }
+        tags(&mut out, &node.optional)?;
+        out.write_all(b"\n")?;
+    }
 
-                    segment_names.push(segment);
-                    segment_names.push(orientation.to_string());
-                    segment_names.push(" ".to_string());
-                });
+    for e in graph.edges_sorted() {
+        let Edge(left, right) = e;
+        let (sid1, sid1_orient) = (name(left.id()), orient(left.is_reverse()));
+        let (sid2, sid2_orient) = (name(right.id()), orient(right.is_reverse()));
+        let overlap = graph.overlap_fields(e, gfa2);
+        if gfa2 {
+            write!(
+                out,
+                "E\t*\t{}{}\t{}{}\t{}",
+                sid1, sid1_orient, sid2, sid2_orient, overlap
+            )?;
+        } else {
+            write!(
+                out,
+                "L\t{}\t{}\t{}\t{}\t{}",
+                sid1, sid1_orient, sid2, sid2_orient, overlap
+            )?;
+        }
+        tags(&mut out, graph.edge_tags(e))?;
+        out.write_all(b"\n")?;
+    }
 
-                let mut segment_names: String = segment_names
-                    .iter()
-                    .fold(String::new(), |acc, str| acc + &str.to_string());
+    let mut paths: Vec<_> = graph.paths().collect();
+    paths.sort();
+    for p in paths {
+        let path = graph.get_path_unchecked(p);
+        out.write_all(if gfa2 { b"O\t" } else { b"P\t" })?;
+        out.write_all(&path.name)?;
+        out.write_all(b"\t")?;
+        // the steps of a GFA1 path are separated by commas, a GFA2 one
+        // by spaces
+        for (i, h) in path.nodes.iter().enumerate() {
+            if i > 0 {
+                out.write_all(if gfa2 { b" " } else { b"," })?;
+            }
+            write!(out, "{}{}", name(h.id()), orient(h.is_reverse()))?;
+        }
+        if !gfa2 {
+            // the overlaps of a GFA1 path aren't known
+            out.write_all(b"\t*")?;
+        }
+        tags(&mut out, &path.optional)?;
+        out.write_all(b"\n")?;
+    }
 
-                // remove the last whitespace " "
-                segment_names.pop();
-                res.lock()
-                    .unwrap()
-                    .push_str(&*format!("O\t{}\t{}\n", id, segment_names));
-            });
-            file.write_all(res.into_inner().unwrap().as_bytes())?;
-            file.sync_all()?;
-            Ok(())
-        }
-        "GFA" => {
-            let path = path.unwrap_or_else(|| {
-                String::from("./tests/output_files/default_path/converted_hashgraph.gfa")
-            });
-            let mut file = File::create(&path)?;
-            let res = Mutex::new(String::new());
-            res.lock().unwrap().push_str("H\tVN:Z:1.0\n");
-
-            graph.handles_par().for_each(|h| {
-                let id = usize::from(h.id());
-                let sequence: BString = graph.sequence_iter(h.forward()).collect();
-
-                res.lock()
-                    .unwrap()
-                    .push_str(&*format!("S\t{}\t{}\n", id, sequence));
-            });
-
-            let orient = |rev: bool| {
-                if rev {
-                    "-"
-                } else {
-                    "+"
+    // GFA1 has no U-groups, and a containment in GFA2 is an edge
+    if gfa2 {
+        // a subset left without nodes isn't a valid group
+        let subsets = graph
+            .subset_names()
+            .into_iter()
+            .filter(|s| graph.subset(s).is_some_and(|ids| !ids.is_empty()));
+        for subset in subsets {
+            out.write_all(b"U\t")?;
+            out.write_all(subset)?;
+            out.write_all(b"\t")?;
+            let ids = graph.subset(subset).unwrap_or_default();
+            for (i, id) in ids.iter().enumerate() {
+                if i > 0 {
+                    out.write_all(b" ")?;
                 }
-            };
+                write!(out, "{}", name(*id))?;
+            }
+            out.write_all(b"\n")?;
+        }
+    } else {
+        for c in graph.containments() {
+            writeln!(
+                out,
+                "C\t{}\t{}\t{}\t{}\t{}\t{}",
+                name(c.container.id()),
+                orient(c.container.is_reverse()),
+                name(c.contained.id()),
+                orient(c.contained.is_reverse()),
+                c.pos,
+                c.overlap
+            )?;
+        }
+    }
+    out.flush()
+}
+
+/// The text of a GFA1 or GFA2 file with the graph, as written by
+/// [`write_gfa1`](fn.write_gfa1.html) and
+/// [`write_gfa2`](fn.write_gfa2.html)
+pub(crate) fn format_gfa(graph: &HashGraph, gfa2: bool) -> String {
+    let mut text = vec![];
+    write_gfa(graph, &mut text, gfa2).unwrap();
+    String::from_utf8_lossy(&text).into_owned()
+}
 
-            graph.edges_par().for_each(|e| {
-                let Edge(left, right) = e;
+/// The writer of a file being saved, that compresses what's written in
+/// it when it's asked
+pub(crate) enum Output<S: Write> {
+    Plain(S),
+    Gzip(GzEncoder<S>),
+}
 
-                let sid1_id: String = left.id().to_string();
-                let sid1_orient = orient(left.is_reverse());
+impl<S: Write> Output<S> {
+    fn finish(self) -> std::io::Result<S> {
+        match self {
+            Output::Plain(out) => Ok(out),
+            Output::Gzip(out) => out.finish(),
+        }
+    }
+}
 
-                let sid2_id: String = right.id().to_string();
-                let sid2_orient = orient(right.is_reverse());
+impl<S: Write> Write for Output<S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Output::Plain(out) => out.write(buf),
+            Output::Gzip(out) => out.write(buf),
+        }
+    }
 
-                res.lock().unwrap().push_str(&*format!(
-                    "L\t{}\t{}\t{}\t{}\t{}\n",
-                    sid1_id, sid1_orient, sid2_id, sid2_orient, "0M"
-                ));
-            });
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Output::Plain(out) => out.flush(),
+            Output::Gzip(out) => out.flush(),
+        }
+    }
+}
 
-            graph.paths().for_each(|p| {
-                let id: BString = graph.path_handle_to_name(p).into();
-                let mut segment_names: Vec<String> = Vec::new();
+/// Create the file ```path``` and write it with ```write```, through
+/// the writer made by ```sink``` and compressed as ```compression```
+/// says; the file is synced once the compression is finished
+fn create_file<S, F, T>(
+    path: &Path,
+    compression: Compression,
+    sink: impl FnOnce(File) -> S,
+    write: F,
+) -> std::io::Result<T>
+where
+    S: Write,
+    F: FnOnce(&mut Output<S>) -> std::io::Result<T>,
+{
+    let file = File::create(path)?;
+    let handle = file.try_clone()?;
+    let mut out = match compression {
+        Compression::None => Output::Plain(sink(file)),
+        Compression::Gzip(level) => Output::Gzip(GzEncoder::new(sink(file), level)),
+    };
+    let res = write(&mut out)?;
+    out.finish()?.flush()?;
+    handle.sync_all()?;
+    Ok(res)
+}
 
-                graph.steps(p).for_each(|s| {
-                    let handle = graph.handle_of_step(&s).unwrap();
-                    let segment: String = handle.id().to_string();
-                    let orientation = orient(handle.is_reverse());
+/// Function that saves the file ```path``` written by ```write```,
+/// compressed as set in ```compression``` or, with
+/// ```Compression::None```, when the path ends with ```.gz```.\
+/// The file is written in ```<path>.tmp``` and renamed to ```path```
+/// once complete, so an existing file is never left half written; the
+/// temporary file is removed when the save fails
+pub(crate) fn save_file<F, T>(path: &Path, compression: Compression, write: F) -> std::io::Result<T>
+where
+    F: FnOnce(&mut Output<BufWriter<File>>) -> std::io::Result<T>,
+{
+    save_file_with(path, compression, BufWriter::new, write)
+}
 
-                    segment_names.push(segment);
-                    segment_names.push(orientation.to_string());
-                    segment_names.push(" ".to_string());
-                });
+fn save_file_with<S, F, T>(
+    path: &Path,
+    compression: Compression,
+    sink: impl FnOnce(File) -> S,
+    write: F,
+) -> std::io::Result<T>
+where
+    S: Write,
+    F: FnOnce(&mut Output<S>) -> std::io::Result<T>,
+{
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = Path::new(&temp);
+    let res = create_file(temp, compression.of_path(path), sink, write)
+        .and_then(|res| std::fs::rename(temp, path).map(|_| res));
+    if res.is_err() {
+        let _ = std::fs::remove_file(temp);
+    }
+    res
+}
 
-                let mut segment_names: String = segment_names
-                    .iter()
-                    .fold(String::new(), |acc, str| acc + &str.to_string());
-                // remove the last whitespace " "
-                segment_names.pop();
+/// take an HashGraph and create a GFA1 or GFA2 file from it and save that file on a specific
+/// location or on a default one.\
+/// The file is compressed with gzip when the path ends with ```.gz```
+pub fn to_gfa(graph: &HashGraph, format: String, path: Option<String>) -> std::io::Result<()> {
+    let gfa2 = is_gfa2(&format);
+    let path = path.unwrap_or_else(|| default_path(gfa2));
+    save_file(Path::new(&path), Compression::None, |out| {
+        write_gfa(graph, out, gfa2)
+    })
+}
 
-                res.lock()
-                    .unwrap()
-                    .push_str(&*format!("P\t{}\t{}\t{}\n", id, segment_names, "0M"));
-            });
-            file.write_all(res.into_inner().unwrap().as_bytes())?;
-            file.sync_all()?;
-            Ok(())
+impl HashGraph {
+    /// Function that saves the graph in ```path``` as bincode, with the
+    /// occurrences of the paths, to load it again with
+    /// [`load_bincode`](#method.load_bincode) without parsing a GFA
+    /// file.\
+    /// As [`to_gfa`](../to_file/fn.to_gfa.html) the file is compressed
+    /// when the path ends with ```.gz```. A lazily loaded graph reads
+    /// all its sequences, and the save fails if one can't be read
+    /// # Example
+    /// ```ignore
+    /// graph.save_bincode("./graph.bin")?;
+    /// let same = HashGraph::load_bincode("./graph.bin")?;
+    /// ```
+    pub fn save_bincode<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        save_file(path.as_ref(), Compression::None, |out| {
+            bincode::to_writer(out, self)
+        })
+    }
+
+    /// Function that loads a graph saved by
+    /// [`save_bincode`](#method.save_bincode), compressed or not.\
+    /// ```min_id```, ```max_id``` and the number of edges are computed
+    /// again, and a file whose edges or paths reference missing nodes is
+    /// ```InvalidData```
+    pub fn load_bincode<P: AsRef<Path>>(path: P) -> Result<HashGraph, std::io::Error> {
+        let mut bytes = std::fs::read(path.as_ref())?;
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            bytes = gunzip(&bytes)?;
         }
-        _ => panic!("Error the format it's not correct!"),
+        bincode::from_slice(&bytes)
+    }
+}
+
+/// Like [`to_gfa`](fn.to_gfa.html), saving the graph as set in
+/// ```config```: with ```validate``` the file is written to
+/// ```<path>.tmp```, read back and compared with the graph, and only
+/// then renamed to ```path```. When the file isn't read back as the
+/// graph, the save fails with ```WriteValidationFailed```, listing the
+/// first differences, and the file is left in ```<path>.tmp```
+/// # Example
+/// ```ignore
+/// let config = WriterConfig {
+///     validate: true,
+///     ..Default::default()
+/// };
+/// match to_gfa_with_config(&graph, "GFA".to_string(), Some("out.gfa".to_string()), config) {
+///     Err(WriteError::WriteValidationFailed { path, diff_summary }) => {
+///         println!("{} is wrong: {}", path, diff_summary)
+///     }
+///     res => res?,
+/// }
+/// ```
+pub fn to_gfa_with_config(
+    graph: &HashGraph,
+    format: String,
+    path: Option<String>,
+    config: WriterConfig,
+) -> Result<(), WriteError> {
+    save_gfa(graph, is_gfa2(&format), path, config, format_gfa)
+}
+
+fn save_gfa(
+    graph: &HashGraph,
+    gfa2: bool,
+    path: Option<String>,
+    config: WriterConfig,
+    format: fn(&HashGraph, bool) -> String,
+) -> Result<(), WriteError> {
+    let path = path.unwrap_or_else(|| default_path(gfa2));
+    let compression = config.compression.of_path(&path);
+    let text = format(graph, gfa2);
+    let write = |out: &mut Output<BufWriter<File>>| out.write_all(text.as_bytes());
+    if !config.validate {
+        save_file(Path::new(&path), compression, write)?;
+        return save_sidecar(graph, gfa2, &path, &text, config);
+    }
+
+    let temp = format!("{}.tmp", path);
+    if let Err(why) = create_file(Path::new(&temp), compression, BufWriter::new, write) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(why.into());
+    }
+
+    let differences = match read_back(&temp, gfa2, compression) {
+        Ok(written) => differences(graph, &written),
+        Err(why) => vec![format!("the file can't be parsed: {}", why)],
+    };
+    if differences.is_empty() {
+        std::fs::rename(&temp, &path)?;
+        save_sidecar(graph, gfa2, &path, &text, config)
+    } else {
+        let mut diff_summary = differences
+            .iter()
+            .take(DIFF_SUMMARY_LEN)
+            .cloned()
+            .collect::<Vec<String>>()
+            .join("; ");
+        if differences.len() > DIFF_SUMMARY_LEN {
+            diff_summary.push_str(&format!(
+                " (and {} more)",
+                differences.len() - DIFF_SUMMARY_LEN
+            ));
+        }
+        Err(WriteError::WriteValidationFailed {
+            path: temp,
+            diff_summary,
+        })
+    }
+}
+
+/// Write the sidecar of the file saved in ```path```, if it's asked
+fn save_sidecar(
+    graph: &HashGraph,
+    gfa2: bool,
+    path: &str,
+    text: &str,
+    config: WriterConfig,
+) -> Result<(), WriteError> {
+    if !config.sidecar {
+        return Ok(());
+    }
+    let ctx = WriteContext {
+        gfa2,
+        validated: config.validate,
+        fidelity: false,
+    };
+    let sidecar = format!("{}.meta.json", path);
+    let temp = format!("{}.tmp", sidecar);
+    let mut file = File::create(&temp)?;
+    file.write_all(metadata_json(graph, &ctx, &content_digest(text.as_bytes())).as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    std::fs::rename(&temp, &sidecar)?;
+    Ok(())
+}
+
+/// Parse a file just written, decompressing it in ```<path>.plain```
+/// when it's compressed
+fn read_back(path: &str, gfa2: bool, compression: Compression) -> Result<HashGraph, String> {
+    if compression == Compression::None {
+        return parse_back(path, gfa2);
     }
+    let text = std::fs::read(path)
+        .and_then(|bytes| gunzip(&bytes))
+        .map_err(|e| e.to_string())?;
+    let plain = format!("{}.plain", path);
+    std::fs::write(&plain, text).map_err(|e| e.to_string())?;
+    let res = parse_back(&plain, gfa2);
+    let _ = std::fs::remove_file(&plain);
+    res
+}
+
+/// Parse a file just written, without tolerating any error in its lines
+fn parse_back(path: &str, gfa2: bool) -> Result<HashGraph, String> {
+    let file = if gfa2 {
+        let mut builder = parser_gfa2::ParserBuilder::all();
+        builder.tolerance = ParserTolerance::Pedantic;
+        FileType::GFA2(
+            builder
+                .build()
+                .parse_file(path)
+                .map_err(|e| e.to_string())?,
+        )
+    } else {
+        let mut builder = parser_gfa1::ParserBuilder::all();
+        builder.error_tolerance(ParserTolerance::Pedantic);
+        FileType::GFA(
+            builder
+                .build()
+                .parse_file(path)
+                .map_err(|e| e.to_string())?,
+        )
+    };
+    // the references to missing segments are then found as differences
+    let mut graph = HashGraph::new();
+    graph.create_graph_recover(file);
+    Ok(graph)
+}
+
+fn format_handles(handles: &[Handle]) -> String {
+    handles
+        .iter()
+        .map(|h| format!("{}{}", h.id(), orient(h.is_reverse())))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// The differences between the graph saved and the one read back, the
+/// nodes sorted by id and then the paths sorted by name
+fn differences(graph: &HashGraph, written: &HashGraph) -> Vec<String> {
+    let report = graph.compare_local(written);
+    let mut differences: Vec<String> = vec![];
+    for diff in report.changed.iter() {
+        for change in diff.changes.iter() {
+            match change {
+                NodeChange::SequenceChanged => {
+                    let handle = Handle::pack(diff.id, false);
+                    differences.push(format!(
+                        "node {} has sequence {} instead of {}",
+                        diff.id,
+                        written.sequence(handle).as_bstr(),
+                        graph.sequence(handle).as_bstr()
+                    ));
+                }
+                NodeChange::NeighborsChanged { added, removed } => {
+                    let edges = |edges: &[Edge]| {
+                        edges
+                            .iter()
+                            .map(|Edge(l, r)| format_handles(&[*l, *r]))
+                            .collect::<Vec<String>>()
+                            .join(" ")
+                    };
+                    differences.push(format!(
+                        "node {} has the edges [{}] more and [{}] less",
+                        diff.id,
+                        edges(added),
+                        edges(removed)
+                    ));
+                }
+                // the paths are compared below
+                NodeChange::CoverageChanged { .. } => (),
+            }
+        }
+    }
+    for id in report.removed.iter() {
+        differences.push(format!("node {} is missing", id));
+    }
+    for id in report.added.iter() {
+        differences.push(format!("node {} isn't in the graph", id));
+    }
+
+    let names = |graph: &HashGraph| {
+        let mut names: Vec<BString> = graph.paths.values().map(|p| p.name.clone()).collect();
+        names.sort();
+        names
+    };
+    for name in names(graph) {
+        let path = graph.get_path_unchecked(&graph.name_to_path_handle(&name).unwrap());
+        match written.name_to_path_handle(&name) {
+            None => differences.push(format!("path {} is missing", name)),
+            Some(id) => {
+                let other = written.get_path_unchecked(&id);
+                if other.nodes != path.nodes {
+                    let first = path
+                        .nodes
+                        .iter()
+                        .zip(other.nodes.iter())
+                        .position(|(a, b)| a != b)
+                        .unwrap_or_else(|| path.nodes.len().min(other.nodes.len()));
+                    differences.push(format!(
+                        "path {} has {} steps instead of {}, different from the step {}",
+                        name,
+                        other.nodes.len(),
+                        path.nodes.len(),
+                        first
+                    ));
+                }
+            }
+        }
+    }
+    for name in names(written) {
+        if graph.name_to_path_handle(&name).is_none() {
+            differences.push(format!("path {} isn't in the graph", name));
+        }
+    }
+    differences
 }
 
 #[cfg(test)]
@@ -164,9 +657,8 @@ mod test {
         match parse_file_to_graph("./tests/gfa1_files/lil.gfa") {
             Ok(g) => {
                 println!("{}", g); //g.print_graph(),
-                match to_gfa(&g, "gfa".to_string(), None) {
-                    Err(why) => println!("Error: {}", why),
-                    _ => (),
+                if let Err(why) = to_gfa(&g, "gfa".to_string(), None) {
+                    println!("Error: {}", why)
                 }
             }
             Err(why) => println!("Error {}", why),
@@ -207,4 +699,143 @@ mod test {
             Err(why) => println!("Error {}", why),
         }
     }
+
+    fn validated() -> WriterConfig {
+        WriterConfig {
+            validate: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validated_save() {
+        let dir = std::env::temp_dir();
+        for (file, format) in [
+            ("./tests/gfa1_files/lil.gfa", "GFA"),
+            ("./tests/gfa2_files/spec_q7.gfa2", "GFA2"),
+        ]
+        .iter()
+        {
+            let graph = parse_file_to_graph(file).unwrap();
+            let path = dir.join(format!(
+                "gfahandlegraph_validated.{}",
+                format.to_lowercase()
+            ));
+            let path = path.to_str().unwrap().to_string();
+            to_gfa_with_config(&graph, format.to_string(), Some(path.clone()), validated())
+                .unwrap();
+            assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+            let written = read_back(&path, *format == "GFA2", Compression::None).unwrap();
+            assert!(differences(&graph, &written).is_empty());
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    /// A file that fails after some bytes
+    struct FailingWriter {
+        out: File,
+        left: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if buf.len() > self.left {
+                return Err(std::io::Error::other("the disk is full"));
+            }
+            self.left -= buf.len();
+            self.out.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.out.flush()
+        }
+    }
+
+    #[test]
+    fn failed_compressed_save_removes_the_temporary_file() {
+        let graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+        let path = std::env::temp_dir().join("gfahandlegraph_failing.gfa.gz");
+        std::fs::write(&path, b"untouched").unwrap();
+        let text = format_gfa(&graph, false);
+
+        // the encoder fails when it writes the last block
+        let failing = |out| FailingWriter { out, left: 20 };
+        let res = save_file_with(&path, Compression::None, failing, |out| {
+            out.write_all(text.as_bytes())
+        });
+        assert_eq!(res.unwrap_err().to_string(), "the disk is full");
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        assert!(!Path::new(&temp).exists());
+        assert_eq!(std::fs::read(&path).unwrap(), b"untouched");
+
+        let res = save_file_with(&path, Compression::None, failing, |out| {
+            out.write_all(b"H\tVN:Z:1.0\n")?;
+            Err::<(), _>(std::io::Error::other("the graph can't be written"))
+        });
+        assert!(res.is_err());
+        assert!(!Path::new(&temp).exists());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn broken_writer_fails_validation() {
+        // loses the first segment and writes the paths with spaces
+        fn broken(graph: &HashGraph, gfa2: bool) -> String {
+            format_gfa(graph, gfa2)
+                .lines()
+                .filter(|line| !line.starts_with("S\t1\t"))
+                .map(|line| {
+                    if line.starts_with('P') {
+                        line.replace(',', " ") + "\n"
+                    } else {
+                        line.to_string() + "\n"
+                    }
+                })
+                .collect()
+        }
+
+        let graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+        let path = std::env::temp_dir().join("gfahandlegraph_broken.gfa");
+        let path = path.to_str().unwrap().to_string();
+        std::fs::write(&path, b"untouched").unwrap();
+
+        // without validation the broken file is saved
+        let unchecked = std::env::temp_dir().join("gfahandlegraph_unchecked.gfa");
+        let unchecked = unchecked.to_str().unwrap().to_string();
+        save_gfa(
+            &graph,
+            false,
+            Some(unchecked.clone()),
+            Default::default(),
+            broken,
+        )
+        .unwrap();
+        std::fs::remove_file(unchecked).unwrap();
+
+        match save_gfa(&graph, false, Some(path.clone()), validated(), broken) {
+            Err(WriteError::WriteValidationFailed {
+                path: temp,
+                diff_summary,
+            }) => {
+                assert_eq!(temp, format!("{}.tmp", path));
+                assert!(std::fs::read_to_string(&temp)
+                    .unwrap()
+                    .starts_with("H\tVN:Z:1.0\nS\t2\t"));
+                assert_eq!(
+                    diff_summary,
+                    "node 2 has the edges [] more and [1+,2+] less; \
+                     node 3 has the edges [] more and [1+,3+] less; \
+                     node 1 is missing; \
+                     path x has 0 steps instead of 10, different from the step 0; \
+                     path y has 0 steps instead of 10, different from the step 0 \
+                     (and 1 more)"
+                );
+                std::fs::remove_file(temp).unwrap();
+            }
+            res => panic!("the validation should fail, not {:?}", res),
+        }
+        assert_eq!(std::fs::read(&path).unwrap(), b"untouched");
+        std::fs::remove_file(path).unwrap();
+    }
 }
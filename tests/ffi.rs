@@ -0,0 +1,161 @@
+// The C ABI is called through its extern declarations, as a C caller
+// would, with a counting allocator to check that every buffer it returns
+// is released by the matching free function
+#![cfg(feature = "ffi")]
+
+extern crate gfahandlegraph;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[repr(C)]
+struct GraphHandle {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    fn gfa_graph_load(path: *const c_char, err: *mut *mut c_char) -> *mut GraphHandle;
+    fn gfa_graph_free(graph: *mut GraphHandle);
+    fn gfa_node_count(graph: *const GraphHandle) -> usize;
+    fn gfa_has_node(graph: *const GraphHandle, node_id: u64) -> bool;
+    fn gfa_sequence(graph: *const GraphHandle, handle: u64, out_len: *mut usize) -> *const u8;
+    fn gfa_neighbors(
+        graph: *const GraphHandle,
+        handle: u64,
+        dir: u8,
+        out_count: *mut usize,
+    ) -> *mut u64;
+    fn gfa_path_count(graph: *const GraphHandle) -> usize;
+    fn gfa_path_name(graph: *const GraphHandle, idx: usize) -> *mut c_char;
+    fn gfa_path_steps(graph: *const GraphHandle, idx: usize, out_count: *mut usize) -> *mut u64;
+    fn gfa_bytes_free(bytes: *const u8, len: usize);
+    fn gfa_handles_free(handles: *mut u64, count: usize);
+    fn gfa_string_free(s: *mut c_char);
+    fn gfa_last_error() -> *mut c_char;
+}
+
+struct CountingAllocator;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(1, Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Take a string returned by the ABI, releasing it
+unsafe fn take_string(s: *mut c_char) -> String {
+    assert!(!s.is_null());
+    let string = CStr::from_ptr(s).to_str().unwrap().to_string();
+    gfa_string_free(s);
+    string
+}
+
+unsafe fn take_handles(handles: *mut u64, count: usize) -> Vec<u64> {
+    assert!(!handles.is_null());
+    let taken = std::slice::from_raw_parts(handles, count).to_vec();
+    gfa_handles_free(handles, count);
+    taken
+}
+
+unsafe fn take_sequence(graph: *const GraphHandle, handle: u64) -> Vec<u8> {
+    let mut len = usize::MAX;
+    let bytes = gfa_sequence(graph, handle, &mut len);
+    assert!(!bytes.is_null());
+    let taken = std::slice::from_raw_parts(bytes, len).to_vec();
+    gfa_bytes_free(bytes, len);
+    taken
+}
+
+/// Every query on lil.gfa and on a missing file, releasing all it gets
+unsafe fn exercise_the_abi() {
+    let lil = CString::new("./tests/gfa1_files/lil.gfa").unwrap();
+    let mut err: *mut c_char = std::ptr::null_mut();
+    let graph = gfa_graph_load(lil.as_ptr(), &mut err);
+    assert!(!graph.is_null());
+    assert!(err.is_null());
+
+    assert_eq!(gfa_node_count(graph), 15);
+    assert!(gfa_has_node(graph, 15));
+    assert!(!gfa_has_node(graph, 16));
+
+    // node 6 forward and reverse
+    assert_eq!(take_sequence(graph, 6 << 1), b"TTG");
+    assert_eq!(take_sequence(graph, 6 << 1 | 1), b"CAA");
+    let mut count = usize::MAX;
+    let right = gfa_neighbors(graph, 6 << 1, 1, &mut count);
+    let mut right = take_handles(right, count);
+    right.sort_unstable();
+    assert_eq!(right, vec![7 << 1, 8 << 1]);
+    let left = gfa_neighbors(graph, 6 << 1 | 1, 1, &mut count);
+    let mut left = take_handles(left, count);
+    left.sort_unstable();
+    assert_eq!(left, vec![4 << 1 | 1, 5 << 1 | 1]);
+
+    assert_eq!(gfa_path_count(graph), 3);
+    let names: Vec<String> = (0..3)
+        .map(|ix| take_string(gfa_path_name(graph, ix)))
+        .collect();
+    assert_eq!(names, vec!["x", "y", "z"]);
+    let steps = gfa_path_steps(graph, 0, &mut count);
+    let steps = take_handles(steps, count);
+    let x: Vec<u64> = [1, 3, 5, 6, 8, 9, 11, 12, 14, 15]
+        .iter()
+        .map(|id| id << 1)
+        .collect();
+    assert_eq!(steps, x);
+
+    // the failed queries return null, and leave their message
+    assert!(gfa_sequence(graph, 16 << 1, &mut count).is_null());
+    assert_eq!(take_string(gfa_last_error()), "node 16 doesn't exist");
+    assert!(gfa_last_error().is_null());
+    assert!(gfa_neighbors(graph, 6 << 1, 2, &mut count).is_null());
+    assert!(take_string(gfa_last_error()).contains("direction 2"));
+    assert!(gfa_path_name(graph, 3).is_null());
+    assert!(take_string(gfa_last_error()).contains("path 3"));
+    assert!(gfa_path_steps(graph, 3, &mut count).is_null());
+    gfa_string_free(gfa_last_error());
+    // without a length the buffer couldn't be released
+    assert!(gfa_sequence(graph, 1 << 1, std::ptr::null_mut()).is_null());
+    assert_eq!(take_string(gfa_last_error()), "out_len is null");
+    assert!(gfa_neighbors(graph, 1 << 1, 1, std::ptr::null_mut()).is_null());
+    assert_eq!(take_string(gfa_last_error()), "out_count is null");
+    assert!(gfa_path_steps(graph, 0, std::ptr::null_mut()).is_null());
+    assert_eq!(take_string(gfa_last_error()), "out_count is null");
+    assert_eq!(gfa_node_count(std::ptr::null()), 0);
+    assert_eq!(take_string(gfa_last_error()), "the graph is null");
+    gfa_graph_free(graph);
+
+    let missing = CString::new("./tests/gfa1_files/missing.gfa").unwrap();
+    let graph = gfa_graph_load(missing.as_ptr(), &mut err);
+    assert!(graph.is_null());
+    assert!(!take_string(err).is_empty());
+    // the message can also be left out
+    assert!(gfa_graph_load(missing.as_ptr(), std::ptr::null_mut()).is_null());
+    assert!(gfa_graph_load(std::ptr::null(), &mut err).is_null());
+    assert_eq!(take_string(err), "the path is null");
+}
+
+#[test]
+fn c_abi_on_lil_gfa() {
+    unsafe {
+        // the first run starts the thread pool and builds the lazy
+        // statics of the parser, that are never released
+        exercise_the_abi();
+        let before = LIVE.load(Ordering::SeqCst);
+        exercise_the_abi();
+        assert_eq!(LIVE.load(Ordering::SeqCst), before);
+    }
+}
@@ -0,0 +1,225 @@
+// The memory used is measured by counting the live bytes allocated, so
+// these tests have their own binary with a counting allocator, and they
+// run one at a time
+use gfahandlegraph::{
+    handle::{Edge, Handle},
+    hashgraph::{HashGraph, HashGraphConfig},
+    mutablehandlegraph::*,
+};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+struct CountingAllocator;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static SERIAL: Mutex<()> = Mutex::new(());
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let live = LIVE.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+        PEAK.fetch_max(live, Ordering::SeqCst);
+        ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const NODES: u64 = 500_000;
+// longer than the sequences kept in the node, that interning can't shrink
+const KMER: usize = 24;
+
+/// A chain of 24-mers over ACGT, drawn from 4096 distinct ones
+fn chopped_graph(config: HashGraphConfig) -> HashGraph {
+    let mut graph = HashGraph::with_config(config);
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    for id in 1..=NODES {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let code = state % 4096;
+        let kmer: Vec<u8> = (0..KMER)
+            .map(|i| b"ACGT"[((code >> (2 * i)) & 3) as usize])
+            .collect();
+        graph.create_handle(id, &kmer).unwrap();
+        if id > 1 {
+            graph
+                .create_edge(Edge(Handle::pack(id - 1, false), Handle::pack(id, false)))
+                .unwrap();
+        }
+    }
+    graph
+}
+
+/// The bytes still allocated by the graph once it's built
+fn graph_bytes(config: HashGraphConfig) -> (usize, HashGraph) {
+    let before = LIVE.load(Ordering::SeqCst);
+    let graph = chopped_graph(config);
+    (LIVE.load(Ordering::SeqCst) - before, graph)
+}
+
+#[test]
+fn interning_saves_the_duplicated_sequences() {
+    let _serial = SERIAL.lock().unwrap();
+    // start the thread pool used when adding the edges, so its
+    // allocations aren't counted in the first graph
+    drop(chopped_graph(HashGraphConfig::default()));
+
+    let (inline, inline_graph) = graph_bytes(HashGraphConfig::default());
+    let (interned, interned_graph) = graph_bytes(HashGraphConfig {
+        intern_sequences: true,
+    });
+    assert_eq!(interned_graph.interned_sequences(), Some(4096));
+    assert_eq!(inline_graph.to_string(), interned_graph.to_string());
+
+    // the nodes keep an index instead of their own copy of the
+    // sequence: at least 3/4 of the sequence bytes are saved, as the
+    // table only keeps two copies of the 4096 distinct sequences
+    let sequence_bytes = NODES as usize * KMER;
+    assert!(
+        interned + sequence_bytes * 3 / 4 <= inline,
+        "interned {} bytes, inline {} bytes",
+        interned,
+        inline
+    );
+}
+
+#[test]
+fn path_fasta_streams_the_sequence() {
+    use gfahandlegraph::pathgraph::PathHandleGraph;
+
+    let _serial = SERIAL.lock().unwrap();
+    // a path of 10k distinct nodes of 100 bases each
+    let mut graph = HashGraph::new();
+    let path = graph.create_path_handle(b"chr", false);
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    for id in 1..=10_000u64 {
+        let sequence: Vec<u8> = (0..100)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                b"ACGT"[(state % 4) as usize]
+            })
+            .collect();
+        graph.create_handle(id, &sequence).unwrap();
+        let step = Handle::pack(id, id % 3 == 0);
+        if id > 1 {
+            let previous = Handle::pack(id - 1, (id - 1) % 3 == 0);
+            graph.create_edge(Edge(previous, step)).unwrap();
+            graph.set_edge_overlap(Edge(previous, step), 7).unwrap();
+        }
+        graph.append_step(&path, step).unwrap();
+    }
+    let total = 10_000 * 100;
+
+    let before = LIVE.load(Ordering::SeqCst);
+    PEAK.store(before, Ordering::SeqCst);
+    let mut sink = std::io::sink();
+    graph
+        .write_path_fasta_streaming(&path, &mut sink, 60)
+        .unwrap();
+    let peak = PEAK.load(Ordering::SeqCst) - before;
+    assert!(peak < total / 100, "peak of {} bytes", peak);
+}
+
+#[test]
+fn packed_graph_is_smaller_than_a_hashgraph() {
+    use gfahandlegraph::{packed::PackedGraph, parser::parse_file_to_graph};
+
+    let _serial = SERIAL.lock().unwrap();
+    // a PackedGraph doesn't keep the paths, so the HashGraph is read
+    // from the medium graph without them
+    let content = std::fs::read_to_string("./tests/big_files/test.gfa").unwrap();
+    let lines: Vec<&str> = content.lines().filter(|l| !l.starts_with('P')).collect();
+    let file = std::env::temp_dir().join("gfahandlegraph_packed_memory.gfa");
+    std::fs::write(&file, lines.join("\n")).unwrap();
+    // the first parse starts the thread pool
+    drop(parse_file_to_graph(&file).unwrap());
+
+    let before = LIVE.load(Ordering::SeqCst);
+    let graph = parse_file_to_graph(&file).unwrap();
+    let hashed = LIVE.load(Ordering::SeqCst) - before;
+    std::fs::remove_file(&file).unwrap();
+
+    let before = LIVE.load(Ordering::SeqCst);
+    let packed = PackedGraph::from_hashgraph(&graph);
+    let packed_bytes = LIVE.load(Ordering::SeqCst) - before;
+    assert!(
+        packed_bytes * 4 <= hashed,
+        "packed {} bytes, HashGraph {} bytes",
+        packed_bytes,
+        hashed
+    );
+    drop(packed);
+}
+
+#[test]
+fn short_sequences_are_kept_in_the_node() {
+    use gfahandlegraph::{
+        hashgraph::{node::SMALL_SEQUENCE_LEN, Node, SequenceRef},
+        parser::parse_file_to_graph,
+    };
+
+    let _serial = SERIAL.lock().unwrap();
+    // the first parse starts the thread pool
+    drop(parse_file_to_graph("./tests/big_files/test.gfa").unwrap());
+    let (bytes, allocations) = (
+        LIVE.load(Ordering::SeqCst),
+        ALLOCATIONS.load(Ordering::SeqCst),
+    );
+    let mut graph = parse_file_to_graph("./tests/big_files/test.gfa").unwrap();
+    let built = LIVE.load(Ordering::SeqCst) - bytes;
+    let built_allocations = ALLOCATIONS.load(Ordering::SeqCst) - allocations;
+
+    let short: Vec<_> = graph
+        .graph
+        .iter()
+        .filter(|(_, node)| matches!(node.sequence_ref(), SequenceRef::Small(..)))
+        .map(|(id, _)| *id)
+        .collect();
+    assert!(short.len() * 10 >= graph.graph.len() * 9);
+    assert!(short
+        .iter()
+        .all(|id| graph.node_sequence(&graph.graph[id]).len() <= SMALL_SEQUENCE_LEN));
+
+    // every short sequence would be an allocation of its own, as they
+    // were before, in a node of the same size
+    let (bytes, allocations) = (
+        LIVE.load(Ordering::SeqCst),
+        ALLOCATIONS.load(Ordering::SeqCst),
+    );
+    let mut bases = 0;
+    for id in short.iter() {
+        let node = graph.graph.get_mut(id).unwrap();
+        let sequence = node.sequence_ref().inline_bytes().unwrap().to_vec();
+        bases += sequence.len();
+        let mut heap = Node::with_sequence(SequenceRef::Inline(sequence.into()));
+        std::mem::swap(&mut heap.left_edges, &mut node.left_edges);
+        std::mem::swap(&mut heap.right_edges, &mut node.right_edges);
+        std::mem::swap(&mut heap.occurrences, &mut node.occurrences);
+        std::mem::swap(&mut heap.optional, &mut node.optional);
+        *node = heap;
+    }
+    let heap = LIVE.load(Ordering::SeqCst) - bytes;
+    let heap_allocations = ALLOCATIONS.load(Ordering::SeqCst) - allocations;
+    assert!(
+        heap_allocations >= short.len() && heap >= bases,
+        "{} allocations and {} bytes saved, of {} allocations and {} bytes",
+        heap_allocations,
+        heap,
+        built_allocations,
+        built
+    );
+}
@@ -125,9 +125,8 @@ fn mod_graph_from_medium_gfa() {
     let mut graph = read_medium_gfa2();
     let random_node = 15_usize;
     let start = Instant::now();
-    match graph.remove_handle(random_node) {
-        Err(why) => println!("Error: {}", why),
-        _ => (),
+    if let Err(why) = graph.remove_handle(random_node) {
+        println!("Error: {}", why)
     };
     println!(
         "remove node {} from graph: {:?}",
@@ -140,9 +139,8 @@ fn mod_graph_from_medium_gfa() {
         Handle::new(2346, Orientation::Forward),
     );
     let start = Instant::now();
-    match graph.remove_edge(edge) {
-        Err(why) => println!("Error: {}", why),
-        _ => (),
+    if let Err(why) = graph.remove_edge(edge) {
+        println!("Error: {}", why)
     };
     println!("remove edge {:?} from graph: {:?}", edge, start.elapsed());
 }
@@ -178,9 +176,8 @@ fn mod_graph_from_big_gfa2() {
     let mut graph = read_big_gfa2();
     let random_node = 11515_usize;
     let start = Instant::now();
-    match graph.remove_handle(random_node) {
-        Err(why) => println!("Error: {}", why),
-        _ => (),
+    if let Err(why) = graph.remove_handle(random_node) {
+        println!("Error: {}", why)
     };
     println!("remove node from graph: {:?}", start.elapsed());
 
@@ -189,9 +186,8 @@ fn mod_graph_from_big_gfa2() {
         Handle::new(115_440_456, Orientation::Backward),
     );
     let start = Instant::now();
-    match graph.remove_edge(edge) {
-        Err(why) => println!("Error: {}", why),
-        _ => (),
+    if let Err(why) = graph.remove_edge(edge) {
+        println!("Error: {}", why)
     };
     println!("remove edge {:?} from graph: {:?}", edge, start.elapsed());
 }
@@ -244,12 +240,12 @@ fn add_path() {
             match graph.create_edge(edge) {
                 Ok(_) => {
                     let path = graph.create_path_handle(b"test_path", false);
-                    let seq_ids = vec![b"11+", b"13-", b"42+"];
+                    let seq_ids = [b"11+", b"13-", b"42+"];
                     for seq in seq_ids.iter() {
                         let last = seq.len() - 1;
                         let seq_id = seq[..last].to_str().unwrap();
 
-                        let sgn: &str = &seq[last..].to_str().unwrap();
+                        let sgn: &str = seq[last..].to_str().unwrap();
                         let orient: Orientation = match sgn {
                             "+" => Orientation::Forward,
                             "-" => Orientation::Backward,
@@ -376,3 +372,7354 @@ fn modify_path() {
         Err(why) => println!("Error {}", why),
     }
 }
+
+fn hub_graph() -> HashGraph {
+    let mut graph = HashGraph::new();
+    for id in 1..=6_u64 {
+        graph.create_handle(id, b"ACGT").unwrap();
+    }
+    let edges = [
+        (1, Orientation::Forward, 3, Orientation::Forward),
+        (2, Orientation::Forward, 3, Orientation::Backward),
+        (3, Orientation::Forward, 4, Orientation::Forward),
+        (5, Orientation::Backward, 3, Orientation::Backward),
+        (4, Orientation::Forward, 6, Orientation::Forward),
+        (1, Orientation::Forward, 2, Orientation::Forward),
+    ];
+    for (l, lo, r, ro) in edges.iter() {
+        graph
+            .create_edge(Edge(Handle::new(*l, *lo), Handle::new(*r, *ro)))
+            .unwrap();
+    }
+    graph
+}
+
+fn sorted_edges(graph: &HashGraph) -> Vec<Edge> {
+    use gfahandlegraph::handlegraph::AllEdges;
+
+    let mut edges: Vec<Edge> = graph.edges().collect();
+    edges.sort();
+    edges
+}
+
+#[test]
+fn remove_edges_where_equals_remove_edge() {
+    use gfahandlegraph::handlegraph::AllEdges;
+
+    let hub: NodeId = 3.into();
+    let touches_hub = |Edge(l, r): &Edge| l.id() == hub || r.id() == hub;
+
+    let mut expected = hub_graph();
+    let to_remove: Vec<Edge> = expected.edges().filter(touches_hub).collect();
+    for edge in to_remove.iter() {
+        expected.remove_edge(*edge).unwrap();
+    }
+
+    let mut graph = hub_graph();
+    let removed = graph.remove_edges_where(touches_hub);
+
+    assert_eq!(removed, 4);
+    assert_eq!(removed, to_remove.len());
+    assert_eq!(sorted_edges(&graph), sorted_edges(&expected));
+    assert_eq!(graph.edges().count(), 2);
+}
+
+#[test]
+fn remove_edges_of_node_by_side() {
+    use gfahandlegraph::handle::Direction;
+
+    let hub: NodeId = 3.into();
+
+    // right side of 3+: 3+ -> 4+, 2+ -> 3- and 5- -> 3- (i.e. 3+ -> 5+)
+    let mut graph = hub_graph();
+    assert_eq!(graph.remove_edges_of_node(hub, Some(Direction::Right)), 3);
+    // left side of 3+: 1+ -> 3+
+    assert_eq!(graph.remove_edges_of_node(hub, Some(Direction::Left)), 1);
+    assert_eq!(graph.remove_edges_of_node(hub, None), 0);
+
+    let mut both = hub_graph();
+    assert_eq!(both.remove_edges_of_node(hub, None), 4);
+    assert_eq!(sorted_edges(&graph), sorted_edges(&both));
+}
+
+#[test]
+fn remove_edges_where_destroys_broken_paths() {
+    use gfahandlegraph::handle::Direction;
+
+    let mut graph = hub_graph();
+    let through = graph.create_path_handle(b"through", false);
+    graph
+        .append_step(&through, Handle::new(1_u64, Orientation::Forward))
+        .unwrap();
+    graph
+        .append_step(&through, Handle::new(3_u64, Orientation::Forward))
+        .unwrap();
+    let around = graph.create_path_handle(b"around", false);
+    graph
+        .append_step(&around, Handle::new(4_u64, Orientation::Forward))
+        .unwrap();
+    graph
+        .append_step(&around, Handle::new(6_u64, Orientation::Forward))
+        .unwrap();
+
+    graph.remove_edges_of_node(3.into(), Some(Direction::Left));
+
+    assert!(!graph.has_path(b"through"));
+    assert!(graph.has_path(b"around"));
+    assert_eq!(graph.path_count(), 1);
+}
+
+#[test]
+fn remove_edges_where_medium_graph() {
+    // the two removals are timed by the edge_removal_benchmark
+    use gfahandlegraph::handlegraph::AllEdges;
+
+    let node: NodeId = 15.into();
+    let touches = |Edge(l, r): &Edge| l.id() == node || r.id() == node;
+
+    let mut expected = read_medium_gfa2();
+    let mut graph = expected.clone();
+
+    let to_remove: Vec<Edge> = expected.edges().filter(touches).collect();
+    for edge in to_remove.iter() {
+        if let Err(why) = expected.remove_edge(*edge) {
+            println!("Error: {}", why)
+        }
+    }
+    let removed = graph.remove_edges_where(touches);
+
+    // the medium graph stores some edges more than once, while the
+    // returned count is the number of distinct logical edges
+    let mut distinct = to_remove;
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(removed, distinct.len());
+    assert_eq!(sorted_edges(&graph), sorted_edges(&expected));
+}
+
+#[test]
+fn explain_error_with_names() {
+    use gfahandlegraph::handlegraph::{GraphError, NodeNamer, NumericNamer};
+
+    struct Contigs;
+    impl NodeNamer for Contigs {
+        fn display_node(&self, id: NodeId) -> String {
+            format!("contig_{}", id)
+        }
+    }
+
+    let mut graph = read_small_gfa2();
+    let error = graph.remove_handle(7).unwrap_err();
+
+    assert_eq!(error.explain(&Contigs), "Cannot find the node: contig_7");
+    assert_eq!(error.explain(&NumericNamer), "Cannot find the node: 7");
+    assert_eq!(graph.explain_error(&error), error.to_string());
+
+    let edge = GraphError::EdgeNotExist("7".to_string(), "8".to_string());
+    assert_eq!(
+        edge.explain(&Contigs),
+        "The Edge (contig_7 -> contig_8) did not exist"
+    );
+    let path = GraphError::PathNotExist("7".to_string());
+    assert_eq!(path.explain(&Contigs), path.to_string());
+}
+
+#[test]
+fn resolve_gaf_against_graph() {
+    use gfahandlegraph::gaf::*;
+    use gfahandlegraph::handlegraph::GraphError;
+
+    let graph = read_small_gfa2();
+    let record =
+        parse_gaf_line(b"read1\t18\t0\t18\t+\t>11<12>13\t18\t0\t18\t18\t18\t60\tNM:i:0").unwrap();
+
+    let handles = resolve_gaf_path(&graph, &record).unwrap();
+    assert_eq!(
+        handles,
+        vec![
+            Handle::new(11_u64, Orientation::Forward),
+            Handle::new(12_u64, Orientation::Backward),
+            Handle::new(13_u64, Orientation::Forward),
+        ]
+    );
+
+    // ACCTT + revcomp(TCAAGG) + CTTGATT
+    let sequence = gaf_path_sequence(&graph, &record).unwrap();
+    assert_eq!(sequence, b"ACCTTCCTTGACTTGATT".to_vec());
+
+    let missing = parse_gaf_line(b"read2\t5\t0\t5\t-\t>11<99\t11\t0\t5\t5\t5\t0").unwrap();
+    match resolve_gaf_path(&graph, &missing) {
+        Err(GraphError::NodeNotExist(node)) => assert_eq!(node, "99"),
+        other => panic!("expected a missing node, got {:?}", other),
+    }
+    assert!(gaf_path_sequence(&graph, &missing).is_err());
+
+    // only the bases from the start to the end of the path are aligned
+    let part = parse_gaf_line(b"read3\t13\t0\t13\t+\t>11<12>13\t18\t2\t15\t13\t13\t60").unwrap();
+    assert_eq!(
+        gaf_path_sequence(&graph, &part).unwrap(),
+        b"CTTCCTTGACTTG".to_vec()
+    );
+    let past = parse_gaf_line(b"read4\t5\t0\t5\t+\t>11\t5\t2\t7\t5\t5\t60").unwrap();
+    match gaf_path_sequence(&graph, &past) {
+        Err(GraphError::PositionNotFound(range, path)) => {
+            assert_eq!((range.as_str(), path.as_str()), ("2-7", ">11"))
+        }
+        other => panic!("expected a range out of the path, got {:?}", other),
+    }
+
+    // the segments are found by the names of the file
+    let file = std::env::temp_dir().join("gfahandlegraph_gaf_names.gfa");
+    std::fs::write(
+        &file,
+        "S\tchr1\tACGT\nS\tchr2\tGGA\nS\t7\tTT\nL\tchr1\t+\tchr2\t-\t*\n",
+    )
+    .unwrap();
+    let named = parse_file_to_graph(&file).unwrap();
+    std::fs::remove_file(&file).unwrap();
+    let record = parse_gaf_line(b"read5\t6\t0\t6\t+\t>chr1<chr2>7\t9\t1\t7\t6\t6\t60").unwrap();
+    let handles = resolve_gaf_path(&named, &record).unwrap();
+    let ids: Vec<_> = [&b"chr1"[..], b"chr2", b"7"]
+        .iter()
+        .map(|name| named.node_id_of_name(name).unwrap())
+        .collect();
+    assert_eq!(handles.iter().map(|h| h.id()).collect::<Vec<_>>(), ids);
+    assert!(handles[1].is_reverse());
+    // ACGT + TCC + TT
+    assert_eq!(
+        gaf_path_sequence(&named, &record).unwrap(),
+        b"CGTTCC".to_vec()
+    );
+}
+
+#[test]
+fn sorted_iteration_is_stable() {
+    use gfahandlegraph::handlegraph::AllHandles;
+
+    let mut graph = read_medium_gfa2();
+    let handles: Vec<Handle> = graph.handles_sorted().collect();
+    let edges: Vec<Edge> = graph.edges_sorted().collect();
+    assert_eq!(handles, graph.handles_sorted().collect::<Vec<_>>());
+    assert_eq!(edges, graph.edges_sorted().collect::<Vec<_>>());
+    assert_eq!(handles.len(), graph.handles().count());
+    assert!(handles.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(edges, sorted_edges(&graph));
+
+    // any modification invalidates the cached order
+    let new_node = graph.append_handle(b"ACGT").unwrap();
+    let updated: Vec<Handle> = graph.handles_sorted().collect();
+    assert_eq!(updated.len(), handles.len() + 1);
+    assert_eq!(updated.last(), Some(&new_node));
+    graph.create_edge(Edge(handles[0], new_node)).unwrap();
+    assert!(graph
+        .edges_sorted()
+        .any(|e| e == Edge::edge_handle(handles[0], new_node)));
+}
+
+#[test]
+fn writer_output_is_deterministic() {
+    use gfahandlegraph::util::to_file::to_gfa;
+
+    let graph = read_small_gfa2();
+    let dir = std::env::temp_dir();
+    let out = |name: &str| {
+        let path = dir.join(name).to_str().unwrap().to_string();
+        to_gfa(&graph, "GFA".to_string(), Some(path.clone())).unwrap();
+        std::fs::read_to_string(path).unwrap()
+    };
+    let first = out("gfahandlegraph_sorted_1.gfa");
+    let second = out("gfahandlegraph_sorted_2.gfa");
+    assert_eq!(first, second);
+    // a fixed expected output also covers different processes
+    assert_eq!(
+        first,
+        "H\tVN:Z:1.0\n\
+         S\t11\tACCTT\n\
+         S\t12\tTCAAGG\n\
+         S\t13\tCTTGATT\n\
+         L\t11\t+\t12\t-\t0M\n\
+         L\t11\t+\t13\t+\t0M\n\
+         L\t12\t-\t13\t+\t0M\n\
+         P\t14\t11+,12-,13+\t*\n"
+    );
+
+    let display = graph.to_string();
+    assert_eq!(display, read_small_gfa2().to_string());
+    assert!(display.contains("\t\t11: ACCTT\n\t\t12: TCAAGG\n\t\t13: CTTGATT\n"));
+}
+
+#[test]
+fn display_is_sorted_and_capped() {
+    use gfahandlegraph::hashgraph::DISPLAY_LIMIT;
+
+    let graph = read_medium_gfa1();
+    let display = format!("{}", graph);
+    assert_eq!(display, format!("{}", graph));
+    let ids: Vec<u64> = display
+        .lines()
+        .skip_while(|l| *l != "\tNodes:")
+        .skip(1)
+        .take_while(|l| *l != "\tEdges:")
+        .filter_map(|l| l.trim().split(':').next()?.parse().ok())
+        .collect();
+    assert!(!ids.is_empty());
+    assert!(ids.windows(2).all(|w| w[0] < w[1]));
+
+    // a graph with many nodes prints only the first ones
+    let n = DISPLAY_LIMIT as u64 + 5;
+    let handles: Vec<Handle> = (1..=n).map(|id| Handle::pack(id, false)).collect();
+    let big = HashGraph::from_parts(
+        (1..=n).map(|id| (NodeId::from(id), b"A".to_vec())),
+        handles.windows(2).map(|w| Edge(w[0], w[1])),
+        vec![],
+    )
+    .unwrap();
+    let display = big.to_string();
+    assert!(display.contains(&format!("\t\t{}: A\n", DISPLAY_LIMIT)));
+    assert!(!display.contains(&format!("\t\t{}: A\n", DISPLAY_LIMIT + 1)));
+    assert!(display.contains("\t\t... 5 more\n\tEdges:"));
+    assert!(display.contains("\t\t... 4 more\n\tPaths:"));
+
+    let mut full = vec![];
+    big.write_full(&mut full).unwrap();
+    let full = String::from_utf8(full).unwrap();
+    assert!(full.contains(&format!("\t\t{}: A\n\tEdges:", n)));
+    assert!(!full.contains("more"));
+    // the two agree on a small graph
+    let graph = read_small_gfa2();
+    let mut small = vec![];
+    graph.write_full(&mut small).unwrap();
+    assert_eq!(String::from_utf8(small).unwrap(), format!("{}", graph));
+}
+
+/// Check that every edge and every path step of the graph
+/// references a node that exists
+fn assert_consistent(graph: &HashGraph) {
+    use gfahandlegraph::handlegraph::AllEdges;
+
+    for Edge(l, r) in graph.edges() {
+        assert!(graph.get_node(&l.id()).is_some(), "missing {:?}", l);
+        assert!(graph.get_node(&r.id()).is_some(), "missing {:?}", r);
+    }
+    for path in graph.paths.values() {
+        for handle in path.nodes.iter() {
+            assert!(graph.get_node(&handle.id()).is_some());
+        }
+    }
+    assert_eq!(graph.edge_count(), graph.edges().count());
+    assert_eq!(graph.check_consistency(), Ok(()));
+}
+
+#[test]
+fn recover_truncated_file() {
+    use gfahandlegraph::handlegraph::AllHandles;
+    use gfahandlegraph::parser::{recover_file_to_graph, ParseError};
+
+    let content = std::fs::read("./tests/big_files/test.gfa").unwrap();
+    let end = content.len() * 8 / 10;
+    // cut the steps of the last path before the orientation of the last one
+    let steps_end = content[..end].iter().rposition(|b| *b == b'\t').unwrap();
+    let cut = &content[..steps_end - 1];
+    assert!(cut.last().unwrap().is_ascii_digit());
+    let path = std::env::temp_dir().join("gfahandlegraph_truncated.gfa");
+    std::fs::write(&path, cut).unwrap();
+
+    // the last line can't be parsed, only the lines before it are intact
+    let intact: Vec<&[u8]> = cut.split(|b| *b == b'\n').collect();
+    let intact = &intact[..intact.len() - 1];
+    let segments = intact.iter().filter(|l| l.starts_with(b"S\t")).count();
+    let paths = intact.iter().filter(|l| l.starts_with(b"P\t")).count();
+
+    let start = Instant::now();
+    let (graph, report) = recover_file_to_graph(&path).unwrap();
+    println!("Recover graph from truncated file: {:?}", start.elapsed());
+
+    assert!(report.truncated);
+    assert_eq!(graph.node_count(), segments);
+    assert_eq!(graph.path_count(), paths);
+    assert_eq!(report.skipped_lines.len(), 1);
+    let (line, _, error) = &report.skipped_lines[0];
+    assert_eq!(*line, intact.len() + 1);
+    assert!(matches!(error, ParseError::TruncatedLine(_)));
+    assert_eq!(report.dropped_edges, 0);
+    assert_eq!(report.dropped_path_steps, 0);
+    assert_consistent(&graph);
+
+    // a last line that can be parsed is kept, even without its newline
+    std::fs::write(&path, &content[..end]).unwrap();
+    let (graph, report) = recover_file_to_graph(&path).unwrap();
+    assert!(!report.truncated);
+    assert!(report.skipped_lines.is_empty());
+    assert_eq!(graph.path_count(), paths + 1);
+}
+
+#[test]
+fn recover_damaged_file() {
+    use bstr::BString;
+    use gfahandlegraph::handlegraph::{AllEdges, AllHandles};
+    use gfahandlegraph::parser::recover_file_to_graph;
+
+    let content = "H\tVN:Z:1.0\n\
+                   S\t1\tACGT\n\
+                   S\t2\tGG\n\
+                   S\t3\n\
+                   S\t4\tTT\n\
+                   L\t1\t+\t2\t+\t0M\n\
+                   L\t2\t+\t3\t-\t0M\n\
+                   L\t2\t+\t4\t+\t0M\n\
+                   P\tp1\t1+,3+,2+,4+\t0M,0M,0M\n\
+                   P\tp2\t1+,2+\t0M\n";
+    let path = std::env::temp_dir().join("gfahandlegraph_damaged.gfa");
+    std::fs::write(&path, content).unwrap();
+
+    let (graph, report) = recover_file_to_graph(&path).unwrap();
+
+    assert!(!report.truncated);
+    assert_eq!(report.skipped_lines.len(), 1);
+    assert_eq!(report.skipped_lines[0].0, 4);
+    assert_eq!(graph.node_count(), 3);
+    assert_eq!(graph.edges().count(), 2);
+    assert_eq!(report.dropped_edges, 1);
+    assert_eq!(report.dropped_path_steps, 1);
+    assert_eq!(
+        report.dropped_steps_per_path,
+        vec![(BString::from("p1"), 1)]
+    );
+    let p1 = graph.name_to_path_handle(b"p1").unwrap();
+    assert_eq!(graph.step_count(&p1), 3);
+    assert_eq!(graph.path_count(), 2);
+    assert_consistent(&graph);
+}
+
+fn path_sequence(graph: &HashGraph, path: i64) -> Vec<u8> {
+    use gfahandlegraph::handlegraph::HandleSequences;
+
+    graph
+        .get_path(&path)
+        .unwrap()
+        .nodes
+        .iter()
+        .flat_map(|h| graph.sequence(*h))
+        .collect()
+}
+
+fn repeat_graph() -> (HashGraph, i64, i64) {
+    let mut graph = HashGraph::new();
+    let sequences: [&[u8]; 5] = [b"AAA", b"CC", b"GATTA", b"TT", b"GG"];
+    for (ix, seq) in sequences.iter().enumerate() {
+        graph.create_handle(ix as u64 + 1, seq).unwrap();
+    }
+    let fwd = |id: u64| Handle::new(id, Orientation::Forward);
+    let rev = |id: u64| Handle::new(id, Orientation::Backward);
+    // 1+ -> 3+ -> 4+ and 5+ -> 3- -> 2+
+    for (l, r) in [
+        (fwd(1), fwd(3)),
+        (fwd(3), fwd(4)),
+        (fwd(5), rev(3)),
+        (rev(3), fwd(2)),
+    ]
+    .iter()
+    {
+        graph.create_edge(Edge(*l, *r)).unwrap();
+    }
+    let a = graph.create_path_handle(b"a", false);
+    for h in [fwd(1), fwd(3), fwd(4)].iter() {
+        graph.append_step(&a, *h).unwrap();
+    }
+    let b = graph.create_path_handle(b"b", false);
+    for h in [fwd(5), rev(3), fwd(2)].iter() {
+        graph.append_step(&b, *h).unwrap();
+    }
+    (graph, a, b)
+}
+
+#[test]
+fn duplicate_handle_for_paths_unzips_repeat() {
+    use fnv::FnvHashMap;
+    use gfahandlegraph::handle::Direction;
+    use gfahandlegraph::handlegraph::{AllEdges, HandleNeighbors};
+
+    let (mut graph, a, b) = repeat_graph();
+    let seq_a = path_sequence(&graph, a);
+    let seq_b = path_sequence(&graph, b);
+
+    let mut assignment = FnvHashMap::default();
+    assignment.insert(b, 1);
+    let copies = graph
+        .duplicate_handle_for_paths(3.into(), &assignment)
+        .unwrap();
+    assert_eq!(copies, vec![NodeId::from(6)]);
+    let copy = copies[0];
+
+    assert_eq!(path_sequence(&graph, a), seq_a);
+    assert_eq!(path_sequence(&graph, b), seq_b);
+    assert_eq!(
+        graph.get_path(&b).unwrap().nodes[1],
+        Handle::new(copy, Orientation::Backward)
+    );
+    assert_eq!(
+        graph.node_sequence(graph.get_node(&copy).unwrap()),
+        b"GATTA"
+    );
+    assert!(graph.get_node(&copy).unwrap().occurrences.contains_key(&b));
+    assert!(!graph
+        .get_node(&3.into())
+        .unwrap()
+        .occurrences
+        .contains_key(&b));
+
+    // the original keeps the edges of path a, the copy the ones of path b
+    assert_eq!(graph.edges().count(), 4);
+    let original = Handle::new(3_u64, Orientation::Forward);
+    let copy_fwd = Handle::new(copy, Orientation::Forward);
+    assert_eq!(graph.degree(original, Direction::Left), 1);
+    assert_eq!(graph.degree(original, Direction::Right), 1);
+    assert_eq!(graph.degree(copy_fwd, Direction::Left), 1);
+    assert_eq!(graph.degree(copy_fwd, Direction::Right), 1);
+    for path in [a, b].iter() {
+        let nodes = &graph.get_path(path).unwrap().nodes;
+        for (from, to) in nodes.iter().zip(nodes.iter().skip(1)) {
+            let edge = Edge::edge_handle(*from, *to);
+            assert!(graph.edges().any(|e| e == edge), "missing {:?}", edge);
+        }
+    }
+    assert_consistent(&graph);
+}
+
+#[test]
+fn duplicate_handle_for_paths_rejects_foreign_path() {
+    use fnv::FnvHashMap;
+    use gfahandlegraph::handlegraph::GraphError;
+
+    let (mut graph, _, b) = repeat_graph();
+    let c = graph.create_path_handle(b"c", false);
+    graph
+        .append_step(&c, Handle::new(1_u64, Orientation::Forward))
+        .unwrap();
+    let before = graph.to_string();
+
+    let mut assignment = FnvHashMap::default();
+    assignment.insert(b, 1);
+    assignment.insert(c, 2);
+    match graph.duplicate_handle_for_paths(3.into(), &assignment) {
+        Err(GraphError::PositionNotFound(node, path)) => {
+            assert_eq!(node, "3");
+            assert_eq!(path, "c");
+        }
+        other => panic!("expected an error, got {:?}", other),
+    }
+    assert_eq!(graph.to_string(), before);
+    assert_eq!(graph.graph.len(), 5);
+}
+
+#[test]
+fn duplicate_handle_for_paths_compacts_indices() {
+    use fnv::FnvHashMap;
+    use gfahandlegraph::handlegraph::AllHandles;
+
+    let (mut graph, a, b) = repeat_graph();
+    let mut assignment = FnvHashMap::default();
+    assignment.insert(a, 3);
+    assignment.insert(b, 7);
+    let copies = graph
+        .duplicate_handle_for_paths(3.into(), &assignment)
+        .unwrap();
+    // one copy for each index in use, none for the indices in between
+    assert_eq!(copies, vec![NodeId::from(6), NodeId::from(7)]);
+    assert_eq!((&graph).node_count(), 7);
+    assert_eq!(graph.get_path(&a).unwrap().nodes[1].id(), copies[0]);
+    assert_eq!(graph.get_path(&b).unwrap().nodes[1].id(), copies[1]);
+    assert_consistent(&graph);
+}
+
+#[test]
+fn duplicate_handle_for_paths_moves_repeated_visits() {
+    use fnv::FnvHashMap;
+    use gfahandlegraph::handlegraph::HandleNeighbors;
+
+    let (mut graph, a, b) = repeat_graph();
+    // a: 1+ -> 3+ -> 4+ -> 3- -> 3+
+    for h in [
+        Handle::new(3_u64, Orientation::Backward),
+        Handle::new(3_u64, Orientation::Forward),
+    ]
+    .iter()
+    {
+        graph.append_step(&a, *h).unwrap();
+    }
+    graph
+        .create_edge(Edge(
+            Handle::new(4_u64, Orientation::Forward),
+            Handle::new(3_u64, Orientation::Backward),
+        ))
+        .unwrap();
+    graph
+        .create_edge(Edge(
+            Handle::new(3_u64, Orientation::Backward),
+            Handle::new(3_u64, Orientation::Forward),
+        ))
+        .unwrap();
+    let sequences = [path_sequence(&graph, a), path_sequence(&graph, b)];
+
+    let mut assignment = FnvHashMap::default();
+    assignment.insert(b, 1);
+    assignment.insert(a, 2);
+    let copies = graph
+        .duplicate_handle_for_paths(3.into(), &assignment)
+        .unwrap();
+    assert_eq!(copies.len(), 2);
+
+    // every visit of a goes through its copy, with its orientation
+    let steps: Vec<Handle> = graph.get_path(&a).unwrap().nodes.iter().copied().collect();
+    assert_eq!(
+        steps,
+        vec![
+            Handle::new(1_u64, Orientation::Forward),
+            Handle::new(copies[1], Orientation::Forward),
+            Handle::new(4_u64, Orientation::Forward),
+            Handle::new(copies[1], Orientation::Backward),
+            Handle::new(copies[1], Orientation::Forward),
+        ]
+    );
+    assert_eq!(graph.get_node(&copies[1]).unwrap().occurrences[&a].len(), 3);
+    assert!(!graph
+        .get_node(&3.into())
+        .unwrap()
+        .occurrences
+        .contains_key(&a));
+    // the loop on the node is now a loop on the copy
+    let copy = Handle::new(copies[1], Orientation::Backward);
+    assert!(graph
+        .neighbors(copy, gfahandlegraph::handle::Direction::Right)
+        .any(|h| h == copy.flip()));
+    assert_eq!(
+        [path_sequence(&graph, a), path_sequence(&graph, b)],
+        sequences
+    );
+    assert_consistent(&graph);
+}
+
+/// lil.gfa with the node 9 divided in a chain of 3 nodes, and the
+/// sequences of its paths
+fn lil_with_chain() -> (HashGraph, Vec<Handle>, Vec<Vec<u8>>) {
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let chain = graph
+        .divide_handle(Handle::pack(9, false), vec![5, 12])
+        .unwrap();
+    let mut path_ids: Vec<i64> = graph.paths.keys().copied().collect();
+    path_ids.sort_unstable();
+    let sequences = path_ids.iter().map(|p| path_sequence(&graph, *p)).collect();
+    (graph, chain, sequences)
+}
+
+#[test]
+fn combine_handles_merges_a_chain() {
+    use gfahandlegraph::handle::Direction;
+    use gfahandlegraph::handlegraph::{HandleNeighbors, HandleSequences};
+
+    let (mut graph, chain, sequences) = lil_with_chain();
+    assert_eq!(chain.len(), 3);
+    let nodes = graph.graph.len();
+
+    let merged = graph.combine_handles(&chain).unwrap();
+    assert_eq!(graph.graph.len(), nodes - 2);
+    assert_eq!(graph.sequence(merged), b"AAATTTTCTGGAGTTCTAT");
+    for id in chain.iter().map(|h| h.id()) {
+        assert!(graph.get_node(&id).is_none());
+    }
+
+    let mut lefts: Vec<u64> = graph
+        .neighbors(merged, Direction::Left)
+        .map(|h| h.unpack_number())
+        .collect();
+    lefts.sort_unstable();
+    assert_eq!(lefts, vec![7, 8]);
+    let mut rights: Vec<u64> = graph
+        .neighbors(merged, Direction::Right)
+        .map(|h| h.unpack_number())
+        .collect();
+    rights.sort_unstable();
+    assert_eq!(rights, vec![10, 11]);
+
+    let mut path_ids: Vec<i64> = graph.paths.keys().copied().collect();
+    path_ids.sort_unstable();
+    for (path, sequence) in path_ids.iter().zip(sequences.iter()) {
+        assert_eq!(&path_sequence(&graph, *path), sequence);
+        let nodes = &graph.get_path(path).unwrap().nodes;
+        let ix = nodes.iter().position(|h| *h == merged).unwrap();
+        assert_eq!(
+            graph.get_node(&merged.id()).unwrap().occurrences[path],
+            vec![ix]
+        );
+    }
+    assert_consistent(&graph);
+}
+
+#[test]
+fn combine_handles_follows_a_reverse_chain() {
+    use gfahandlegraph::handlegraph::{HandleNeighbors, HandleSequences};
+
+    let (mut graph, chain, sequences) = lil_with_chain();
+    // a path walking the chain backwards, from the node 11 to the node 7
+    let w = graph.create_path_handle(b"w", false);
+    let mut steps = vec![Handle::pack(11, true)];
+    steps.extend(chain.iter().rev().map(|h| h.flip()));
+    steps.push(Handle::pack(7, true));
+    for h in steps.iter() {
+        graph.append_step(&w, *h).unwrap();
+    }
+    let backward = path_sequence(&graph, w);
+
+    // the chain given from its reverse strand
+    let reverse: Vec<Handle> = chain.iter().rev().map(|h| h.flip()).collect();
+    let merged = graph.combine_handles(&reverse).unwrap();
+    assert!(!merged.is_reverse());
+    assert_eq!(graph.sequence(merged.flip()), b"AAATTTTCTGGAGTTCTAT");
+
+    let mut path_ids: Vec<i64> = graph.paths.keys().copied().collect();
+    path_ids.sort_unstable();
+    for (path, sequence) in path_ids.iter().zip(sequences.iter()) {
+        assert_eq!(&path_sequence(&graph, *path), sequence);
+        assert!(graph.get_path(path).unwrap().nodes.contains(&merged.flip()));
+    }
+    assert_eq!(path_sequence(&graph, w), backward);
+    assert_eq!(graph.get_path(&w).unwrap().nodes[1], merged);
+    assert!(graph.has_edge(Handle::pack(11, true), merged));
+    assert!(graph.has_edge(merged, Handle::pack(7, true)));
+    assert_consistent(&graph);
+}
+
+#[test]
+fn combine_handles_rejects_branches_and_partial_visits() {
+    use gfahandlegraph::handlegraph::GraphError;
+
+    let (mut graph, chain, _) = lil_with_chain();
+    let before = graph.to_string();
+
+    // the node 1 reaches both the nodes 2 and 3
+    let branch = [Handle::pack(1, false), Handle::pack(2, false)];
+    match graph.combine_handles(&branch) {
+        Err(GraphError::ChainNotSimple(node, _)) => assert_eq!(node, "1"),
+        other => panic!("expected an error, got {:?}", other),
+    }
+    match graph.combine_handles(&[chain[0], chain[1], chain[0]]) {
+        Err(GraphError::ChainNotSimple(node, _)) => assert_eq!(node, chain[0].id().to_string()),
+        other => panic!("expected an error, got {:?}", other),
+    }
+    assert_eq!(graph.to_string(), before);
+
+    // a path stepping only on the middle of the chain
+    let p = graph.create_path_handle(b"p", false);
+    graph.append_step(&p, chain[1]).unwrap();
+    let before = graph.to_string();
+    match graph.combine_handles(&chain) {
+        Err(GraphError::ChainNotSimple(node, why)) => {
+            assert_eq!(node, chain[1].id().to_string());
+            assert!(why.contains("path p"), "{}", why);
+        }
+        other => panic!("expected an error, got {:?}", other),
+    }
+    assert_eq!(graph.to_string(), before);
+}
+
+#[test]
+fn divide_handle_keeps_forward_and_reverse_paths() {
+    use gfahandlegraph::handlegraph::HandleSequences;
+
+    // the node 3 (GATTA) is 3+ in the path a and 3- in the path b
+    let (mut graph, a, b) = repeat_graph();
+    let (seq_a, seq_b) = (path_sequence(&graph, a), path_sequence(&graph, b));
+
+    let (left, right) = graph
+        .split_handle(Handle::new(3_u64, Orientation::Forward), 2)
+        .unwrap();
+    assert_eq!(graph.sequence(left), b"GA");
+    assert_eq!(graph.sequence(right), b"TTA");
+    assert_eq!(path_sequence(&graph, a), seq_a);
+    assert_eq!(path_sequence(&graph, b), seq_b);
+    assert_eq!(
+        graph
+            .get_path(&b)
+            .unwrap()
+            .nodes
+            .range(1..3)
+            .collect::<Vec<_>>(),
+        [&right.flip(), &left.flip()]
+    );
+    assert_consistent(&graph);
+
+    // the offsets of a reverse handle are read on its strand
+    let (mut graph, a, b) = repeat_graph();
+    let reverse = Handle::new(3_u64, Orientation::Backward);
+    let pieces = graph.divide_handle(reverse, vec![1, 3]).unwrap();
+    let spelled: Vec<u8> = pieces.iter().flat_map(|h| graph.sequence(*h)).collect();
+    assert_eq!(spelled, b"TAATC");
+    assert_eq!(graph.sequence(pieces[0]), b"T");
+    assert_eq!(pieces[2], reverse);
+    assert_eq!(path_sequence(&graph, a), seq_a);
+    assert_eq!(path_sequence(&graph, b), seq_b);
+    assert!(graph
+        .get_path(&b)
+        .unwrap()
+        .nodes
+        .range(1..4)
+        .eq(pieces.iter()));
+    for path in [a, b].iter() {
+        let nodes = &graph.get_path(path).unwrap().nodes;
+        for (ix, step) in nodes.iter().enumerate() {
+            assert!(graph.get_node(&step.id()).unwrap().occurrences[path].contains(&ix));
+        }
+    }
+    assert_consistent(&graph);
+}
+
+#[test]
+fn divide_handle_rejects_invalid_offsets() {
+    use gfahandlegraph::handlegraph::GraphError;
+
+    let (mut graph, _, _) = repeat_graph();
+    let before = graph.to_string();
+    let handle = Handle::new(3_u64, Orientation::Forward);
+    for offsets in [vec![0], vec![5], vec![3, 2], vec![2, 2]].iter() {
+        match graph.divide_handle(handle, offsets.clone()) {
+            Err(GraphError::InvalidOffset(node, _)) => assert_eq!(node, "3"),
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+    assert!(matches!(
+        graph.divide_handle(Handle::new(9_u64, Orientation::Forward), vec![1]),
+        Err(GraphError::NodeNotExist(_))
+    ));
+    assert_eq!(graph.to_string(), before);
+    assert_eq!(graph.divide_handle(handle, vec![]).unwrap(), vec![handle]);
+}
+
+fn similarity_graph() -> (HashGraph, Vec<i64>) {
+    let mut graph = HashGraph::new();
+    let sequences: [&[u8]; 5] = [b"AAA", b"CC", b"GATTA", b"TT", b"GG"];
+    for (ix, seq) in sequences.iter().enumerate() {
+        graph.create_handle(ix as u64 + 1, seq).unwrap();
+    }
+    let steps: [(&[u8], &[u64]); 3] = [(b"p", &[1, 2, 3]), (b"q", &[2, 3, 4, 4]), (b"r", &[5])];
+    let mut paths = vec![];
+    for (name, nodes) in steps.iter() {
+        let path = graph.create_path_handle(name, false);
+        for id in nodes.iter() {
+            graph
+                .append_step(&path, Handle::new(*id, Orientation::Forward))
+                .unwrap();
+        }
+        paths.push(path);
+    }
+    (graph, paths)
+}
+
+#[test]
+fn path_similarity_matrix() {
+    let (graph, paths) = similarity_graph();
+
+    // p and q share nodes 2 and 3 over the union {1, 2, 3, 4}
+    let unweighted = graph.path_similarity(&paths, false);
+    assert_eq!(
+        unweighted,
+        vec![
+            vec![1.0, 0.5, 0.0],
+            vec![0.5, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0]
+        ]
+    );
+
+    // shared bases: 2 + 5, union: 3 + 2 + 5 + 2
+    let weighted = graph.path_similarity(&paths, true);
+    assert_eq!(
+        weighted,
+        vec![
+            vec![1.0, 7.0 / 12.0, 0.0],
+            vec![7.0 / 12.0, 1.0, 0.0],
+            vec![0.0, 0.0, 1.0]
+        ]
+    );
+
+    // the order of the matrix follows the input
+    let reversed: Vec<i64> = paths.iter().rev().copied().collect();
+    assert_eq!(graph.path_similarity(&reversed, false)[1][2], 0.5);
+}
+
+#[test]
+fn path_similarity_tsv() {
+    let (graph, paths) = similarity_graph();
+    let mut out: Vec<u8> = vec![];
+    graph.write_similarity_tsv(&mut out, &paths, true).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "\tp\tq\tr\n\
+         p\t1.000000\t0.583333\t0.000000\n\
+         q\t0.583333\t1.000000\t0.000000\n\
+         r\t0.000000\t0.000000\t1.000000\n"
+    );
+}
+
+#[test]
+fn incremental_parse_ignores_declaration_order() {
+    use gfahandlegraph::parser::Parser;
+
+    let in_order = read_small_gfa2();
+    let out_of_order = Parser::new()
+        .parse_file_incremental("./tests/gfa2_files/out_of_order.gfa2")
+        .unwrap()
+        .finish_strict()
+        .unwrap();
+    assert_eq!(in_order.to_string(), out_of_order.to_string());
+    assert_eq!(sorted_edges(&in_order), sorted_edges(&out_of_order));
+    assert_eq!(path_sequence(&out_of_order, 0), b"ACCTTCCTTGACTTGATT");
+
+    let streamed = Parser::new()
+        .parse_file_incremental("./tests/gfa2_files/spec_q7.gfa2")
+        .unwrap()
+        .finish_strict()
+        .unwrap();
+    assert_eq!(in_order.to_string(), streamed.to_string());
+}
+
+#[test]
+fn incremental_builder_reports_dangling_references() {
+    use gfahandlegraph::hashgraph::{GraphBuilder, UnresolvedReference};
+
+    let forward = |id: u64| Handle::new(id, Orientation::Forward);
+    let build = || {
+        let mut builder = GraphBuilder::new();
+        builder.add_edge(Edge(forward(1), forward(2)));
+        builder.add_edge(Edge(forward(2), forward(99)));
+        builder.add_path(b"p", vec![forward(1), forward(99), forward(2)]);
+        builder.add_segment(1, b"A").unwrap();
+        builder.add_segment(2, b"C").unwrap();
+        builder
+    };
+
+    let unresolved = build().finish_strict().unwrap_err();
+    assert_eq!(
+        unresolved,
+        vec![
+            UnresolvedReference::Edge(Edge(forward(2), forward(99)), vec![NodeId::from(99)]),
+            UnresolvedReference::Path("p".into(), vec![NodeId::from(99)]),
+        ]
+    );
+
+    let (graph, unresolved) = build().finish();
+    assert_eq!(unresolved.len(), 2);
+    assert_eq!(sorted_edges(&graph), vec![Edge(forward(1), forward(2))]);
+    assert_eq!(path_sequence(&graph, 0), b"AC");
+    assert_consistent(&graph);
+}
+
+fn assert_covers_once(graph: &HashGraph, chains: &[Vec<Handle>]) {
+    use gfahandlegraph::handlegraph::AllHandles;
+
+    let mut ids: Vec<NodeId> = chains.iter().flatten().map(|h| h.id()).collect();
+    ids.sort();
+    let mut expected: Vec<NodeId> = graph.handles().map(|h| h.id()).collect();
+    expected.sort();
+    assert_eq!(ids, expected);
+}
+
+#[test]
+fn simple_paths_cover_every_node_once() {
+    let graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let chains = graph.simple_paths();
+    // every node of lil.gfa is on a branch
+    assert_eq!(chains.len(), 15);
+    assert_covers_once(&graph, &chains);
+
+    let graph = read_medium_gfa1();
+    assert_covers_once(&graph, &graph.simple_paths());
+}
+
+#[test]
+fn simple_paths_orientation_and_cycles() {
+    let forward = |id: u64| Handle::new(id, Orientation::Forward);
+    let backward = |id: u64| Handle::new(id, Orientation::Backward);
+
+    // 3- -> 2+ -> 1- is the chain 1+ -> 2- -> 3+ read backwards,
+    // 4+ branches to both ends of it
+    let mut graph = HashGraph::new();
+    graph.create_handle(1_u64, b"AC").unwrap();
+    graph.create_handle(2_u64, b"GG").unwrap();
+    graph.create_handle(3_u64, b"TA").unwrap();
+    graph.create_handle(4_u64, b"C").unwrap();
+    graph.create_edge(Edge(backward(3), forward(2))).unwrap();
+    graph.create_edge(Edge(forward(2), backward(1))).unwrap();
+    graph.create_edge(Edge(forward(4), forward(1))).unwrap();
+    graph.create_edge(Edge(forward(4), backward(3))).unwrap();
+    assert_eq!(
+        graph.simple_paths(),
+        vec![vec![forward(1), backward(2), forward(3)], vec![forward(4)]]
+    );
+    let mut fasta = vec![];
+    graph.write_unitigs_fasta(&mut fasta).unwrap();
+    assert_eq!(fasta, b">1+,2-,3+\nACCCTA\n>4+\nC\n".to_vec());
+
+    let mut cycle = HashGraph::new();
+    for id in [3_u64, 1, 4, 2].iter() {
+        cycle.create_handle(*id, b"A").unwrap();
+    }
+    for (l, r) in [(1, 2), (2, 3), (3, 4), (4, 1)].iter() {
+        cycle.create_edge(Edge(forward(*l), forward(*r))).unwrap();
+    }
+    assert_eq!(
+        cycle.simple_paths(),
+        vec![vec![forward(1), forward(2), forward(3), forward(4)]]
+    );
+}
+
+#[test]
+fn from_parts_matches_parsed_graph() {
+    let forward = |id: u64| Handle::new(id, Orientation::Forward);
+    let backward = |id: u64| Handle::new(id, Orientation::Backward);
+
+    let graph = HashGraph::from_parts(
+        vec![
+            (NodeId::from(11), b"ACCTT".to_vec()),
+            (NodeId::from(12), b"TCAAGG".to_vec()),
+            (NodeId::from(13), b"CTTGATT".to_vec()),
+        ],
+        vec![
+            Edge(forward(11), backward(12)),
+            Edge(backward(12), forward(13)),
+            Edge(forward(11), forward(13)),
+        ],
+        vec![(
+            b"14".to_vec(),
+            vec![forward(11), backward(12), forward(13)],
+            false,
+        )],
+    )
+    .unwrap();
+    let parsed = read_small_gfa2();
+    assert_eq!(graph.to_string(), parsed.to_string());
+    assert_eq!(sorted_edges(&graph), sorted_edges(&parsed));
+    assert_eq!(graph.max_id, parsed.max_id);
+    assert_eq!(graph.min_id, parsed.min_id);
+    assert_eq!(
+        graph.get_node(&NodeId::from(13)).unwrap().occurrences,
+        parsed.get_node(&NodeId::from(13)).unwrap().occurrences
+    );
+}
+
+#[test]
+fn from_parts_validation_errors() {
+    use gfahandlegraph::handlegraph::error::GraphError;
+
+    let forward = |id: u64| Handle::new(id, Orientation::Forward);
+    let nodes = || {
+        vec![
+            (NodeId::from(1), b"A".to_vec()),
+            (NodeId::from(2), b"C".to_vec()),
+        ]
+    };
+    let no_edges = || Vec::<Edge>::new();
+    let no_paths = || Vec::<(Vec<u8>, Vec<Handle>, bool)>::new();
+
+    let mut duplicated = nodes();
+    duplicated.push((NodeId::from(1), b"G".to_vec()));
+    match HashGraph::from_parts(duplicated, no_edges(), no_paths()) {
+        Err(GraphError::InvalidBatchRecord(at, why)) => {
+            assert_eq!(at, "node 2");
+            assert!(matches!(*why, GraphError::IdAlreadyExist(id) if id == "1"));
+        }
+        _ => panic!("duplicated node not detected"),
+    }
+
+    let mut empty = nodes();
+    empty.push((NodeId::from(3), vec![]));
+    assert!(matches!(
+        HashGraph::from_parts(empty, no_edges(), no_paths()),
+        Err(GraphError::EmptySequence)
+    ));
+
+    let edges = vec![Edge(forward(1), forward(2)), Edge(forward(2), forward(7))];
+    match HashGraph::from_parts(nodes(), edges, no_paths()) {
+        Err(GraphError::InvalidBatchRecord(at, why)) => {
+            assert_eq!(at, "edge 1");
+            assert!(matches!(*why, GraphError::NodeNotExist(id) if id == "7"));
+        }
+        _ => panic!("dangling edge not detected"),
+    }
+
+    let paths = vec![(
+        b"x".to_vec(),
+        vec![forward(1), forward(2), forward(9)],
+        false,
+    )];
+    match HashGraph::from_parts(nodes(), no_edges(), paths) {
+        Err(GraphError::InvalidBatchRecord(at, why)) => {
+            assert_eq!(at, "path 0, step 2");
+            assert!(matches!(*why, GraphError::NodeNotExist(id) if id == "9"));
+        }
+        _ => panic!("dangling path step not detected"),
+    }
+
+    let paths = vec![
+        (b"x".to_vec(), vec![forward(1)], false),
+        (b"x".to_vec(), vec![forward(2)], true),
+    ];
+    match HashGraph::from_parts(nodes(), no_edges(), paths) {
+        Err(GraphError::InvalidBatchRecord(at, why)) => {
+            assert_eq!(at, "path 1");
+            assert!(matches!(*why, GraphError::PathAlreadyExist(id) if id == "x"));
+        }
+        _ => panic!("duplicated path not detected"),
+    }
+}
+
+#[test]
+fn try_from_gfa_objects() {
+    use gfahandlegraph::hashgraph::graph::FileType;
+    use gfahandlegraph::parser::{GFA2Parser, GFAParser};
+    use std::convert::TryFrom;
+
+    let gfa = GFAParser::new()
+        .parse_file("./tests/gfa1_files/lil.gfa")
+        .unwrap();
+    let parsed = HashGraph::new()
+        .create_graph(FileType::GFA(gfa.clone()))
+        .unwrap();
+    let graph = HashGraph::try_from(gfa).unwrap();
+    assert_eq!(graph.to_string(), parsed.to_string());
+    assert_eq!(sorted_edges(&graph), sorted_edges(&parsed));
+
+    let gfa2 = GFA2Parser::new()
+        .parse_file("./tests/gfa2_files/spec_q7.gfa2")
+        .unwrap();
+    let graph = HashGraph::try_from(gfa2).unwrap();
+    assert_eq!(graph.to_string(), read_small_gfa2().to_string());
+}
+
+#[test]
+fn reverse_complement_is_an_involution() {
+    use gfahandlegraph::util::dna;
+
+    let graph = read_small_gfa2();
+    let reversed = graph.reverse_complement();
+    assert_consistent(&reversed);
+    assert_eq!(
+        path_sequence(&reversed, 0),
+        dna::rev_comp(path_sequence(&graph, 0).as_slice())
+    );
+    assert_eq!(
+        reversed.node_sequence(reversed.get_node(&NodeId::from(11)).unwrap()),
+        b"AAGGT"
+    );
+
+    let mut twice = reversed.clone();
+    twice.reverse_complement_in_place();
+    assert_eq!(twice.to_string(), graph.to_string());
+    assert_eq!(sorted_edges(&twice), sorted_edges(&graph));
+    for id in [11_u64, 12, 13].iter() {
+        let id = NodeId::from(*id);
+        assert_eq!(
+            twice.get_node(&id).unwrap().occurrences,
+            graph.get_node(&id).unwrap().occurrences
+        );
+    }
+}
+
+#[test]
+fn reverse_complement_keeps_self_loops_and_circular_paths() {
+    let forward = |id: u64| Handle::new(id, Orientation::Forward);
+    let backward = |id: u64| Handle::new(id, Orientation::Backward);
+
+    let mut graph = HashGraph::new();
+    graph.create_handle(1_u64, b"AAC").unwrap();
+    graph.create_handle(2_u64, b"GT").unwrap();
+    graph.create_edge(Edge(forward(1), forward(1))).unwrap();
+    graph.create_edge(Edge(forward(2), backward(2))).unwrap();
+    graph.create_edge(Edge(forward(1), forward(2))).unwrap();
+    let path = graph.create_path_handle(b"c", true);
+    graph.append_step(&path, forward(1)).unwrap();
+    graph.append_step(&path, forward(2)).unwrap();
+
+    graph.reverse_complement_in_place();
+    assert_consistent(&graph);
+    assert_eq!(
+        sorted_edges(&graph),
+        vec![
+            Edge(forward(1), forward(1)),
+            // 2+ -> 1+
+            Edge(backward(1), backward(2)),
+            Edge(backward(2), forward(2)),
+        ]
+    );
+    let reversed = graph.get_path(&path).unwrap();
+    assert!(reversed.is_circular);
+    assert_eq!(reversed.nodes, vec![forward(2), forward(1)]);
+    assert_eq!(path_sequence(&graph, path), b"ACGTT");
+}
+
+#[test]
+fn convert_gfa_to_archive_and_load() {
+    use gfahandlegraph::parser::ParseError;
+    use gfahandlegraph::util::{convert_gfa_to_archive, load_archive, ConvertOptions};
+
+    let archive = std::env::temp_dir().join("gfahandlegraph_test_medium.hga");
+    let report = convert_gfa_to_archive(
+        "./tests/big_files/test.gfa",
+        &archive,
+        &ConvertOptions::default(),
+    )
+    .unwrap();
+    assert!(report.warnings.is_empty());
+
+    let loaded = load_archive(&archive).unwrap();
+    let parsed = read_medium_gfa1();
+    assert_eq!(report.nodes, parsed.graph.len());
+    assert_eq!(report.paths, parsed.paths.len());
+    assert_eq!(
+        loaded.handles_sorted().count(),
+        parsed.handles_sorted().count()
+    );
+    let mut edges = sorted_edges(&parsed);
+    edges.dedup();
+    assert_eq!(sorted_edges(&loaded), edges);
+    assert_eq!(report.edges, edges.len());
+    for (name, id) in parsed.path_id.iter() {
+        let expected = &parsed.get_path(id).unwrap().nodes;
+        let path = loaded.get_path(&loaded.path_id[name]).unwrap();
+        assert_eq!(&path.nodes, expected);
+    }
+    assert_consistent(&loaded);
+
+    // a different version in the header
+    let mut bytes = std::fs::read(&archive).unwrap();
+    bytes[8] += 1;
+    std::fs::write(&archive, &bytes).unwrap();
+    assert!(matches!(
+        load_archive(&archive),
+        Err(ParseError::InvalidArchive(_))
+    ));
+    std::fs::remove_file(&archive).unwrap();
+}
+
+fn features_graph() -> HashGraph {
+    let forward = |id: u64| Handle::new(id, Orientation::Forward);
+    let backward = |id: u64| Handle::new(id, Orientation::Backward);
+
+    let mut graph = HashGraph::new();
+    for (id, seq) in [(1_u64, "GGCA"), (2, "AT"), (3, "CCCCC"), (4, "A")].iter() {
+        graph.create_handle(*id, seq.as_bytes()).unwrap();
+    }
+    graph.create_edge(Edge(forward(1), forward(2))).unwrap();
+    graph.create_edge(Edge(forward(2), forward(4))).unwrap();
+    // 3 is connected to 1 with both orientations
+    graph.create_edge(Edge(forward(1), backward(3))).unwrap();
+    graph.create_edge(Edge(forward(3), forward(1))).unwrap();
+    let path = graph.create_path_handle(b"p", false);
+    graph.append_step(&path, forward(1)).unwrap();
+    graph.append_step(&path, forward(2)).unwrap();
+    graph
+}
+
+#[test]
+fn node_features_small_graph() {
+    use gfahandlegraph::hashgraph::NodeFeatures;
+
+    let features = features_graph().node_features(1);
+    assert_eq!(
+        features.iter().map(|f| f.id).collect::<Vec<_>>(),
+        vec![1, 2, 3, 4]
+    );
+    assert_eq!(
+        features[0],
+        NodeFeatures {
+            id: 1,
+            length: 4,
+            gc_content: 0.75,
+            left_degree: 1,
+            right_degree: 2,
+            neighbor_lengths: vec![2, 5],
+            coverage: 1,
+            nodes_within_radius: Some(3),
+            edges_within_radius: Some(3),
+        }
+    );
+    assert_eq!(
+        features[2],
+        NodeFeatures {
+            id: 3,
+            length: 5,
+            gc_content: 1.0,
+            left_degree: 0,
+            right_degree: 2,
+            neighbor_lengths: vec![4],
+            coverage: 0,
+            nodes_within_radius: Some(2),
+            edges_within_radius: Some(2),
+        }
+    );
+    let radius_two = features_graph().node_features(2);
+    assert_eq!(radius_two[0].nodes_within_radius, Some(4));
+    assert_eq!(radius_two[0].edges_within_radius, Some(4));
+}
+
+#[test]
+fn node_features_exporters() {
+    let graph = features_graph();
+    let mut csv = vec![];
+    graph.write_features_csv(&mut csv, 0).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "id,length,gc_content,left_degree,right_degree,neighbor_lengths,coverage,\
+         nodes_within_radius,edges_within_radius\n\
+         1,4,0.750000,1,2,2;5,1,,\n\
+         2,2,0.000000,1,1,1;4,1,,\n\
+         3,5,1.000000,0,2,4,0,,\n\
+         4,1,0.000000,1,0,2,0,,\n"
+    );
+
+    let mut jsonl = vec![];
+    graph.write_features_jsonl(&mut jsonl, 1).unwrap();
+    let jsonl = String::from_utf8(jsonl).unwrap();
+    assert_eq!(jsonl.lines().count(), 4);
+    assert_eq!(
+        jsonl.lines().last().unwrap(),
+        "{\"id\":4,\"length\":1,\"gc_content\":0,\"left_degree\":1,\"right_degree\":0,\
+         \"neighbor_lengths\":[2],\"coverage\":0,\"nodes_within_radius\":2,\
+         \"edges_within_radius\":1}"
+    );
+
+    let graph = read_medium_gfa1();
+    let start = Instant::now();
+    let features = graph.node_features(2);
+    assert_eq!(features.len(), graph.graph.len());
+    assert!(start.elapsed().as_seconds_f64() < 60.0);
+}
+
+/// The entries (from 0) of a MatrixMarket coordinate file, with its size
+fn read_matrix_market(text: &str) -> (usize, usize, Vec<(usize, usize, f32)>) {
+    let mut lines = text.lines().filter(|l| !l.starts_with('%'));
+    let size: Vec<usize> = lines
+        .next()
+        .unwrap()
+        .split(' ')
+        .map(|n| n.parse().unwrap())
+        .collect();
+    let entries = lines
+        .map(|l| {
+            let fields: Vec<&str> = l.split(' ').collect();
+            let index = |i: usize| fields[i].parse::<usize>().unwrap() - 1;
+            (index(0), index(1), fields[2].parse().unwrap())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(entries.len(), size[2]);
+    (size[0], size[1], entries)
+}
+
+#[test]
+fn adjacency_matrix_export() {
+    use gfahandlegraph::handlegraph::AllHandles;
+    use gfahandlegraph::hashgraph::AdjacencyMode;
+
+    let graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let collapsed = graph.to_csr(AdjacencyMode::NodeCollapsed);
+    assert_eq!(collapsed.rows(), 15);
+    assert_eq!(collapsed.indptr.len(), 16);
+    // 20 links between two nodes, on both rows, and the loop on node 5
+    assert_eq!(collapsed.nnz(), 41);
+    assert!(collapsed.data.iter().all(|v| *v == 1.0));
+    for row in 0..collapsed.rows() {
+        assert!(collapsed.row(row).windows(2).all(|w| w[0] < w[1]));
+        for col in collapsed.row(row) {
+            assert!(collapsed.row(*col as usize).contains(&(row as u64)));
+        }
+    }
+    let five = collapsed.row_of(Handle::pack(5, true)).unwrap();
+    assert_eq!(collapsed.row_nodes[five], NodeId::from(5));
+    let neighbors: Vec<u64> = collapsed
+        .row(five)
+        .iter()
+        .map(|c| u64::from(collapsed.row_nodes[*c as usize]))
+        .collect();
+    assert_eq!(neighbors, vec![2, 3, 5, 6]);
+
+    let oriented = graph.to_csr(AdjacencyMode::OrientedHandles);
+    assert_eq!(oriented.rows(), 2 * (&graph).node_count());
+    assert_eq!(oriented.row_handles[0], Handle::pack(1, false));
+    assert_eq!(oriented.row_handles[1], Handle::pack(1, true));
+    // each link from both of its ends
+    assert_eq!(oriented.nnz(), 42);
+    let one = oriented.row_of(Handle::pack(1, false)).unwrap();
+    let right: Vec<Handle> = oriented
+        .row(one)
+        .iter()
+        .map(|c| oriented.row_handles[*c as usize])
+        .collect();
+    assert_eq!(right, vec![Handle::pack(2, false), Handle::pack(3, false)]);
+    let two = oriented.row_of(Handle::pack(2, true)).unwrap();
+    assert_eq!(
+        oriented.row(two),
+        &[oriented.row_of(Handle::pack(1, true)).unwrap() as u64]
+    );
+
+    for (mode, csr) in [
+        (AdjacencyMode::NodeCollapsed, &collapsed),
+        (AdjacencyMode::OrientedHandles, &oriented),
+    ] {
+        let mut out = vec![];
+        graph.write_matrix_market(&mut out, mode).unwrap();
+        let (rows, cols, entries) = read_matrix_market(&String::from_utf8(out).unwrap());
+        assert_eq!((rows, cols), (csr.rows(), csr.rows()));
+        let expected: Vec<(usize, usize, f32)> = (0..csr.rows())
+            .flat_map(|row| csr.row(row).iter().map(move |c| (row, *c as usize, 1.0)))
+            .collect();
+        assert_eq!(entries, expected);
+    }
+}
+
+#[test]
+fn extract_subpath_offsets_and_errors() {
+    let graph = read_small_gfa2();
+    let snapshot = graph.extract_subpath(&0, 3..14).unwrap();
+    assert_eq!(snapshot.first_step, 0);
+    assert_eq!(snapshot.steps.len(), 3);
+    assert_eq!((snapshot.start_offset, snapshot.end_offset), (3, 3));
+
+    let aligned = graph.extract_subpath(&0, 5..11).unwrap();
+    assert_eq!(aligned.first_step, 1);
+    assert_eq!(
+        aligned.steps,
+        vec![Handle::new(12_u64, Orientation::Backward)]
+    );
+    assert_eq!((aligned.start_offset, aligned.end_offset), (0, 6));
+
+    assert!(graph.extract_subpath(&0, 5..5).is_err());
+    #[allow(clippy::reversed_empty_ranges)]
+    let inverted = 9..3;
+    assert!(graph.extract_subpath(&0, inverted).is_err());
+    assert!(graph.extract_subpath(&0, 0..19).is_err());
+    assert!(graph.extract_subpath(&7, 0..1).is_err());
+}
+
+#[test]
+fn extract_subpath_exact_splits_boundary_nodes() {
+    let original = read_small_gfa2();
+    let sequence = path_sequence(&original, 0);
+
+    for range in [3..14, 6..10, 0..18].iter() {
+        let mut graph = read_small_gfa2();
+        let sub = graph
+            .extract_subpath_exact(&0, range.clone(), b"sub")
+            .unwrap();
+        assert_eq!(graph.path_bases_len(&sub), Some(range.len()));
+        assert_eq!(path_sequence(&graph, sub), sequence[range.clone()].to_vec());
+        // the original path goes through the pieces
+        assert_eq!(path_sequence(&graph, 0), sequence);
+        assert_consistent(&graph);
+    }
+
+    let mut graph = read_small_gfa2();
+    let sub = graph.extract_subpath_exact(&0, 5..11, b"sub").unwrap();
+    assert_eq!(graph.graph.len(), 3);
+    assert_eq!(
+        graph.get_path(&sub).unwrap().nodes,
+        vec![Handle::new(12_u64, Orientation::Backward)]
+    );
+    assert!(graph.extract_subpath_exact(&0, 0..3, b"sub").is_err());
+}
+
+#[test]
+fn ids_beyond_u32() {
+    use gfahandlegraph::handlegraph::{AllEdges, AllHandles};
+    use gfahandlegraph::parser::{ParseError, ParseFieldError};
+
+    let big: u64 = u64::from(u32::MAX) + 1;
+    let content = format!(
+        "H\tVN:Z:1.0\nS\t{}\tACG\nS\t2\tTT\nL\t{}\t+\t2\t-\t0M\nP\tx\t{}+,2-\t0M\n",
+        big, big, big
+    );
+    let path = std::env::temp_dir().join("gfahandlegraph_big_ids.gfa");
+    std::fs::write(&path, content).unwrap();
+    let graph = parse_file_to_graph(&path).unwrap();
+    assert!(graph.has_node(big));
+    let path_id = graph.name_to_path_handle(b"x").unwrap();
+    assert_eq!(path_sequence(&graph, path_id), b"ACGAA");
+
+    let content = format!(
+        "H\tVN:Z:2.0\nS\t{}\t3\tACG\nS\t2\t2\tTT\nE\t*\t{}+\t2-\t0\t3$\t0\t2$\t*\n",
+        big, big
+    );
+    let path = std::env::temp_dir().join("gfahandlegraph_big_ids.gfa2");
+    std::fs::write(&path, content).unwrap();
+    let graph = parse_file_to_graph(&path).unwrap();
+    assert!(graph.has_node(big));
+    assert_eq!(graph.edge_count(), 1);
+
+    // the id doesn't fit in a node id
+    let content = "H\tVN:Z:1.0\nS\t99999999999999999999\tACG\n";
+    let path = std::env::temp_dir().join("gfahandlegraph_overflow_ids.gfa");
+    std::fs::write(&path, content).unwrap();
+    match parse_file_to_graph(&path) {
+        Err(ParseError::InvalidLineAt(2, ParseFieldError::IdOverflow(id), _)) => {
+            assert_eq!(id, "99999999999999999999")
+        }
+        other => panic!("expected an overflowing id, got {:?}", other.map(|_| ())),
+    }
+}
+
+fn path_steps(graph: &HashGraph) -> Vec<(Vec<u8>, Vec<Handle>)> {
+    let mut paths: Vec<_> = graph
+        .paths
+        .values()
+        .map(|p| (p.name.to_vec(), p.nodes.iter().copied().collect()))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn parse_with_thread_counts() {
+    use gfahandlegraph::handlegraph::HandleSequences;
+    use gfahandlegraph::parser::Parser;
+    use gfahandlegraph::util::ThreadConfig;
+
+    let parse = |threads| {
+        Parser::new()
+            .threads(threads)
+            .parse_file_to_graph("./tests/big_files/test.gfa")
+            .unwrap()
+    };
+    let sequential = parse(ThreadConfig::sequential());
+    let parallel = parse(ThreadConfig::new(4));
+
+    let nodes = |graph: &HashGraph| -> Vec<(Handle, Vec<u8>)> {
+        graph
+            .handles_sorted()
+            .map(|h| (h, graph.sequence(h)))
+            .collect()
+    };
+    assert_eq!(nodes(&sequential), nodes(&parallel));
+    assert_eq!(sorted_edges(&sequential), sorted_edges(&parallel));
+    assert_eq!(path_steps(&sequential), path_steps(&parallel));
+    assert_eq!(
+        sequential.node_features_with_threads(1, ThreadConfig::sequential()),
+        parallel.node_features_with_threads(1, ThreadConfig::new(4))
+    );
+}
+
+#[test]
+fn thread_config_bounds_the_workers() {
+    use gfahandlegraph::util::ThreadConfig;
+    use rayon::prelude::*;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+
+    let workers = Mutex::new(HashSet::new());
+    let hook = |_: &usize| {
+        workers.lock().unwrap().insert(std::thread::current().id());
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    };
+    let items: Vec<usize> = (0..200).collect();
+    ThreadConfig::new(2).install(|| items.par_iter().for_each(hook));
+    let used = workers.lock().unwrap().len();
+    assert!((1..=2).contains(&used), "{} workers", used);
+
+    // a single thread doesn't leave the current one
+    workers.lock().unwrap().clear();
+    ThreadConfig::sequential().install(|| items.iter().for_each(hook));
+    let used: Vec<_> = workers.lock().unwrap().iter().copied().collect();
+    assert_eq!(used, vec![std::thread::current().id()]);
+}
+
+fn gapped_graph() -> HashGraph {
+    let mut graph = HashGraph::new();
+    graph.create_handle(1, b"ACG").unwrap();
+    graph.create_handle(2, b"TT").unwrap();
+    graph.create_handle(3, b"GCA").unwrap();
+    graph
+        .create_edge(Edge(Handle::pack(1, false), Handle::pack(2, false)))
+        .unwrap();
+    for (name, steps) in [
+        (&b"p"[..], [(1, false), (2, false), (3, true)]),
+        (&b"q"[..], [(3, false), (2, true), (1, true)]),
+    ] {
+        let path = graph.create_path_handle(name, false);
+        for (id, rev) in steps.iter() {
+            graph.append_step(&path, Handle::pack(*id, *rev)).unwrap();
+        }
+    }
+    graph
+}
+
+#[test]
+fn broken_path_adjacencies() {
+    use gfahandlegraph::hashgraph::RepairMode;
+
+    let mut graph = gapped_graph();
+    let broken = graph.broken_path_adjacencies();
+    // q follows 1+ 2+ from the other strand, only its first pair is broken
+    assert_eq!(broken.len(), 2);
+    assert_eq!(broken[0].path_name, "p");
+    assert_eq!(broken[0].step, 1);
+    assert_eq!(
+        (broken[0].left, broken[0].right),
+        (Handle::pack(2, false), Handle::pack(3, true))
+    );
+    assert_eq!(broken[1].path_name, "q");
+    assert_eq!(broken[1].step, 0);
+
+    assert_eq!(
+        graph.repair_path_adjacencies(RepairMode::CreateMissingEdges),
+        2
+    );
+    assert!(graph.broken_path_adjacencies().is_empty());
+    assert_consistent(&graph);
+}
+
+#[test]
+fn split_paths_at_broken_adjacencies() {
+    use gfahandlegraph::hashgraph::RepairMode;
+
+    let mut graph = gapped_graph();
+    let p = graph.name_to_path_handle(b"p").unwrap();
+    let original = path_sequence(&graph, p);
+
+    assert_eq!(graph.repair_path_adjacencies(RepairMode::SplitPaths), 2);
+    assert!(graph.broken_path_adjacencies().is_empty());
+    assert!(!graph.has_path(b"p"));
+    let first = graph.name_to_path_handle(b"p:0").unwrap();
+    let second = graph.name_to_path_handle(b"p:1").unwrap();
+    let mut joined = path_sequence(&graph, first);
+    joined.extend(path_sequence(&graph, second));
+    assert_eq!(joined, original);
+    assert_eq!(graph.path_count(), 4);
+    assert_consistent(&graph);
+}
+
+#[test]
+fn compare_local_versions() {
+    use bstr::BString;
+    use gfahandlegraph::{
+        handlegraph::*,
+        hashgraph::{NodeChange, NodeDiff},
+    };
+
+    let mut old = read_medium_gfa1();
+    assert!(old.compare_local(&old.clone()).is_empty());
+
+    // the same edge stored from the other strand isn't a change
+    let mut new = old.clone();
+    let (a, b) = (Handle::pack(5, false), Handle::pack(20, true));
+    assert!(!old.has_edge(a, b));
+    old.create_edge(Edge(a, b)).unwrap();
+    new.create_edge(Edge(b.flip(), a.flip())).unwrap();
+    assert!(old.compare_local(&new).is_empty());
+
+    new.modify_handle(3, b"ACGT").unwrap();
+    let (left, right) = (Handle::pack(8, false), Handle::pack(11, true));
+    assert!(!new.has_edge(left, right));
+    new.create_edge(Edge(left, right)).unwrap();
+    let path = old.get_path(&0).unwrap();
+    let (name, step) = (path.name.clone(), *path.nodes.back().unwrap());
+    new.remove_step(name.as_ref(), step.id()).unwrap();
+
+    let coverage = |graph: &HashGraph| {
+        let mut names: Vec<BString> = graph
+            .paths
+            .values()
+            .flat_map(|p| {
+                p.nodes
+                    .iter()
+                    .filter(|h| h.id() == step.id())
+                    .map(move |_| p.name.clone())
+            })
+            .collect();
+        names.sort();
+        names
+    };
+    let added = NodeChange::NeighborsChanged {
+        added: vec![Edge::edge_handle(left, right)],
+        removed: vec![],
+    };
+    let mut expected = vec![
+        NodeDiff {
+            id: NodeId::from(3u64),
+            changes: vec![NodeChange::SequenceChanged],
+        },
+        NodeDiff {
+            id: NodeId::from(8u64),
+            changes: vec![added.clone()],
+        },
+        NodeDiff {
+            id: NodeId::from(11u64),
+            changes: vec![added],
+        },
+        NodeDiff {
+            id: step.id(),
+            changes: vec![NodeChange::CoverageChanged {
+                old: coverage(&old),
+                new: coverage(&new),
+            }],
+        },
+    ];
+    expected.sort_by_key(|d| d.id);
+
+    let report = old.compare_local(&new);
+    assert_eq!(report.changed, expected);
+    assert!(report.added.is_empty() && report.removed.is_empty());
+    let summary = report.summary();
+    assert_eq!(
+        (
+            summary.sequence_changed,
+            summary.neighbors_changed,
+            summary.coverage_changed
+        ),
+        (1, 2, 1)
+    );
+}
+
+#[test]
+fn write_local_diff_tsv() {
+    let old = gapped_graph();
+    let mut new = old.clone();
+    new.modify_handle(1, b"AAG").unwrap();
+    new.create_handle(4, b"C").unwrap();
+    new.create_edge(Edge(Handle::pack(3, false), Handle::pack(4, false)))
+        .unwrap();
+    new.remove_step(b"q", 3u64).unwrap();
+
+    let mut tsv = vec![];
+    old.write_local_diff_tsv(&new, &mut tsv).unwrap();
+    assert_eq!(
+        String::from_utf8(tsv).unwrap(),
+        "id\tstatus\told_sequence\tnew_sequence\tadded_edges\tremoved_edges\told_paths\tnew_paths\n\
+         1\tchanged\tACG\tAAG\t\t\t\t\n\
+         3\tchanged\t\t\t3+,4+\t\tp;q\tp\n\
+         4\tadded\t\t\t\t\t\t\n"
+    );
+}
+
+/// The graph of a GFA file, built without and with sequence interning
+fn inline_and_interned(path: &str) -> (HashGraph, HashGraph) {
+    use gfahandlegraph::hashgraph::{graph::FileType, HashGraphConfig};
+    use gfahandlegraph::parser::GFAParser;
+
+    let gfa = GFAParser::new().parse_file(path).unwrap();
+    let inline = HashGraph::new()
+        .create_graph(FileType::GFA(gfa.clone()))
+        .unwrap();
+    let interned = HashGraph::with_config(HashGraphConfig {
+        intern_sequences: true,
+    })
+    .create_graph(FileType::GFA(gfa))
+    .unwrap();
+    (inline, interned)
+}
+
+fn assert_same_graph(a: &HashGraph, b: &HashGraph) {
+    use gfahandlegraph::handlegraph::*;
+
+    assert_eq!(a.to_string(), b.to_string());
+    assert_eq!(sorted_edges(a), sorted_edges(b));
+    let sequences = |g: &HashGraph| {
+        g.handles_sorted()
+            .map(|h| (h, g.sequence(h)))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(sequences(a), sequences(b));
+    assert_eq!(a.total_length(), b.total_length());
+}
+
+#[test]
+fn interned_sequences_match_inline() {
+    use gfahandlegraph::util::{load_archive, save_archive};
+
+    let (inline, interned) = inline_and_interned("./tests/big_files/test.gfa");
+    assert!(!inline.config().intern_sequences);
+    assert!(interned.config().intern_sequences);
+    assert_eq!(inline.interned_sequences(), None);
+    // most of the nodes are single bases
+    assert!(interned.interned_sequences().unwrap() < interned.graph.len() / 4);
+    assert_same_graph(&inline, &interned);
+    assert_consistent(&interned);
+
+    // the archives keep the sequences, not the table
+    let dir = std::env::temp_dir();
+    let (a, b) = (
+        dir.join("gfahandlegraph_test_inline.hga"),
+        dir.join("gfahandlegraph_test_interned.hga"),
+    );
+    save_archive(&inline, &a).unwrap();
+    save_archive(&interned, &b).unwrap();
+    assert_eq!(std::fs::read(&a).unwrap(), std::fs::read(&b).unwrap());
+    let loaded = load_archive(&b).unwrap();
+    assert!(!loaded.config().intern_sequences);
+    assert_eq!(sorted_edges(&loaded), sorted_edges(&inline));
+    std::fs::remove_file(&a).unwrap();
+    std::fs::remove_file(&b).unwrap();
+}
+
+#[test]
+fn mutate_interned_graph() {
+    use gfahandlegraph::handlegraph::HandleSequences;
+
+    let (mut inline, mut interned) = inline_and_interned("./tests/gfa1_files/lil.gfa");
+    let distinct = interned.interned_sequences().unwrap();
+    let mutate = |graph: &mut HashGraph| {
+        graph.modify_handle(2, b"TTG").unwrap();
+        graph.modify_handle(4, b"GATTACA").unwrap();
+        graph
+            .divide_handle(Handle::pack(1, false), vec![3])
+            .unwrap();
+        graph
+            .divide_handle(Handle::pack(9, false), vec![5, 10])
+            .unwrap();
+        graph.apply_orientation(Handle::pack(6, true)).unwrap();
+        graph.reverse_complement_in_place();
+    };
+    mutate(&mut inline);
+    mutate(&mut interned);
+
+    assert_same_graph(&inline, &interned);
+    assert_consistent(&interned);
+    assert_eq!(interned.sequence(Handle::pack(2, false)), b"CAA");
+    // the new sequences are added, the old ones are kept
+    assert!(interned.interned_sequences().unwrap() > distinct);
+}
+
+#[test]
+fn interned_node_moved_to_another_graph() {
+    use gfahandlegraph::handlegraph::GraphError;
+    use gfahandlegraph::hashgraph::SequenceRef;
+
+    let (mut inline, interned) = inline_and_interned("./tests/gfa1_files/lil.gfa");
+    let node = interned.get_node(&NodeId::from(1)).unwrap();
+    assert!(matches!(node.sequence_ref(), SequenceRef::Interned(_)));
+    assert_eq!(interned.try_node_sequence(node).unwrap(), b"CAAATAAG");
+
+    // the table of the sequences stays with the graph it belongs to
+    inline.graph.insert(NodeId::from(1), node.clone());
+    let moved = inline.get_node(&NodeId::from(1)).unwrap();
+    assert!(matches!(
+        inline.try_node_sequence(moved),
+        Err(GraphError::SequenceUnavailable(_))
+    ));
+    let other = inline.get_node(&NodeId::from(2)).unwrap();
+    // a single base is kept in the node without an allocation
+    assert!(matches!(other.sequence_ref(), SequenceRef::Small(_, 1)));
+    assert_eq!(inline.try_node_sequence(other).unwrap(), b"A");
+}
+
+#[test]
+fn parser_line_filters() {
+    use gfahandlegraph::handlegraph::{AllEdges, AllHandles};
+    use gfahandlegraph::parser::{ParsedGfa, Parser};
+
+    let mut parser = Parser::new();
+    parser.links(false).paths(false);
+    match parser.parse_file("./tests/gfa1_files/lil.gfa").unwrap() {
+        ParsedGfa::GFA(gfa) => {
+            assert!(!gfa.segments.is_empty());
+            assert!(gfa.links.is_empty() && gfa.paths.is_empty());
+        }
+        ParsedGfa::GFA2(_) => panic!("lil.gfa is a GFA1 file"),
+    }
+    match parser
+        .parse_file("./tests/gfa2_files/spec_q7.gfa2")
+        .unwrap()
+    {
+        ParsedGfa::GFA2(gfa2) => {
+            assert!(!gfa2.segments.is_empty());
+            assert!(gfa2.edges.is_empty() && gfa2.groups_o.is_empty());
+        }
+        ParsedGfa::GFA(_) => panic!("spec_q7.gfa2 is a GFA2 file"),
+    }
+    let graph = parser
+        .parse_file_to_graph("./tests/gfa1_files/lil.gfa")
+        .unwrap();
+    assert!((&graph).node_count() > 0);
+    assert_eq!((&graph).edge_count(), 0);
+    assert!(graph.paths.is_empty());
+
+    parser.segments(false).links(true).paths(true);
+    match parser.parse_file("./tests/gfa1_files/lil.gfa").unwrap() {
+        ParsedGfa::GFA(gfa) => {
+            assert!(gfa.segments.is_empty());
+            assert!(!gfa.links.is_empty() && !gfa.paths.is_empty());
+        }
+        ParsedGfa::GFA2(_) => panic!("lil.gfa is a GFA1 file"),
+    }
+}
+
+#[test]
+fn path_orientation_anomalies() {
+    use gfahandlegraph::parser::Parser;
+
+    let mut parser = Parser::new();
+    parser.warn_orientation_anomalies(true);
+    let (graph, warnings) = parser
+        .parse_file_to_graph_with_warnings("./tests/gfa1_files/flipped_step.gfa")
+        .unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].path, "flipped");
+    assert_eq!(warnings[0].index, 2);
+    assert_eq!(warnings[0].suggested_fix, Handle::pack(3, false));
+    // the gap with no edge in either orientation is a broken adjacency
+    let broken = graph.broken_path_adjacencies();
+    assert!(broken.iter().any(|b| b.path_name == "gap"));
+
+    let (_, warnings) = parser
+        .parse_file_to_graph_with_warnings("./tests/gfa1_files/lil.gfa")
+        .unwrap();
+    assert!(warnings.is_empty());
+
+    parser.warn_orientation_anomalies(false);
+    let (_, warnings) = parser
+        .parse_file_to_graph_with_warnings("./tests/gfa1_files/flipped_step.gfa")
+        .unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn write_path_fasta_streaming() {
+    let graph = read_small_gfa2();
+    let mut fasta = vec![];
+    graph
+        .write_all_paths_fasta_streaming(&mut fasta, 4)
+        .unwrap();
+    // 11+ 12- 13+, with the middle step reverse complemented
+    let sequence = path_sequence(&graph, 0);
+    let mut expected = b">14\n".to_vec();
+    for line in sequence.chunks(4) {
+        expected.extend_from_slice(line);
+        expected.push(b'\n');
+    }
+    assert_eq!(fasta, expected);
+
+    let mut unwrapped = vec![];
+    graph
+        .write_path_fasta_streaming(&0, &mut unwrapped, 0)
+        .unwrap();
+    assert_eq!(unwrapped, [&b">14\n"[..], &sequence, b"\n"].concat());
+    assert!(graph
+        .write_path_fasta_streaming(&7, &mut unwrapped, 0)
+        .is_err());
+}
+
+#[test]
+fn write_path_fasta_trims_overlaps() {
+    let mut graph = HashGraph::new();
+    graph.create_handle(1, b"AACGT").unwrap();
+    graph.create_handle(2, b"CGTTT").unwrap();
+    graph.create_handle(3, b"GA").unwrap();
+    let edge = Edge(Handle::pack(1, false), Handle::pack(2, false));
+    graph.create_edge(edge).unwrap();
+    assert!(graph
+        .set_edge_overlap(Edge(Handle::pack(1, false), Handle::pack(3, false)), 1)
+        .is_err());
+    // given from the other strand, it's the same edge
+    graph
+        .set_edge_overlap(Edge(Handle::pack(2, true), Handle::pack(1, true)), 3)
+        .unwrap();
+    assert_eq!(graph.edge_overlap(edge), 3);
+
+    for (name, steps) in [
+        (&b"p"[..], [(1, false), (2, false)]),
+        (&b"q"[..], [(2, true), (1, true)]),
+    ] {
+        let path = graph.create_path_handle(name, false);
+        for (id, rev) in steps.iter() {
+            graph.append_step(&path, Handle::pack(*id, *rev)).unwrap();
+        }
+    }
+    let mut fasta = vec![];
+    graph
+        .write_all_paths_fasta_streaming(&mut fasta, 0)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(fasta).unwrap(),
+        ">p\nAACGTTT\n>q\nAAACGTT\n"
+    );
+
+    graph.remove_edge(edge).unwrap();
+    assert_eq!(graph.edge_overlap(edge), 0);
+}
+
+/// 1+ -> 2+ -> 3+ with overlaps 3 and 2, the path p spelling AACGTTTGA
+fn overlapping_chain() -> HashGraph {
+    let mut graph = HashGraph::new();
+    for (id, seq) in [(1, "AACGT"), (2, "CGTTT"), (3, "TTGA")] {
+        graph.create_handle(id, seq.as_bytes()).unwrap();
+    }
+    let path = graph.create_path_handle(b"p", false);
+    for (l, r, overlap) in [(1, 2, 3), (2, 3, 2)] {
+        let edge = Edge(Handle::pack(l, false), Handle::pack(r, false));
+        graph.create_edge(edge).unwrap();
+        graph.set_edge_overlap(edge, overlap).unwrap();
+    }
+    for id in 1..=3 {
+        graph.append_step(&path, Handle::pack(id, false)).unwrap();
+    }
+    graph
+}
+
+fn paths_fasta(graph: &HashGraph) -> String {
+    let mut fasta = vec![];
+    graph
+        .write_all_paths_fasta_streaming(&mut fasta, 0)
+        .unwrap();
+    String::from_utf8(fasta).unwrap()
+}
+
+#[test]
+fn remove_handle_drops_its_overlaps() {
+    let mut graph = overlapping_chain();
+    let fwd = |id: u64| Handle::pack(id, false);
+    graph.remove_handle(1).unwrap();
+    graph.create_handle(1, b"AACGT").unwrap();
+    graph.create_edge(Edge(fwd(1), fwd(2))).unwrap();
+    assert_eq!(graph.edge_overlap(Edge(fwd(1), fwd(2))), 0);
+    assert_eq!(graph.edge_overlap(Edge(fwd(2), fwd(3))), 2);
+}
+
+#[test]
+fn reverse_complement_keeps_overlaps() {
+    let graph = overlapping_chain();
+    assert_eq!(paths_fasta(&graph), ">p\nAACGTTTGA\n");
+    let opposite = graph.reverse_complement();
+    // the end of 1+ is now the end of 1-
+    let rev = |id: u64| Handle::pack(id, true);
+    assert_eq!(opposite.edge_overlap(Edge(rev(1), rev(2))), 3);
+    assert_eq!(opposite.edge_overlap(Edge(rev(2), rev(3))), 2);
+    assert_eq!(paths_fasta(&opposite), ">p\nTCAAACGTT\n");
+    assert_eq!(
+        paths_fasta(&opposite.reverse_complement()),
+        paths_fasta(&graph)
+    );
+}
+
+#[test]
+fn duplicated_edges_keep_their_overlap() {
+    use fnv::FnvHashMap;
+
+    let mut graph = overlapping_chain();
+    let fwd = |id: u64| Handle::pack(id, false);
+    let mut assignment = FnvHashMap::default();
+    assignment.insert(graph.path_id[&b"p"[..]], 1);
+    let copies = graph
+        .duplicate_handle_for_paths(NodeId::from(2), &assignment)
+        .unwrap();
+    let copy = Handle::pack(copies[0], false);
+    assert_eq!(graph.edge_overlap(Edge(fwd(1), copy)), 3);
+    assert_eq!(graph.edge_overlap(Edge(copy, fwd(3))), 2);
+    assert_eq!(paths_fasta(&graph), ">p\nAACGTTTGA\n");
+}
+
+#[test]
+fn archives_keep_the_overlaps() {
+    use gfahandlegraph::util::{load_archive, save_archive};
+
+    let graph = overlapping_chain();
+    let fwd = |id: u64| Handle::pack(id, false);
+    let archive = std::env::temp_dir().join("gfahandlegraph_test_overlaps.hga");
+    save_archive(&graph, &archive).unwrap();
+    let loaded = load_archive(&archive).unwrap();
+    std::fs::remove_file(&archive).unwrap();
+    assert_eq!(loaded.edge_overlap(Edge(fwd(1), fwd(2))), 3);
+    assert_eq!(loaded.edge_overlap(Edge(fwd(2), fwd(3))), 2);
+    assert_eq!(paths_fasta(&loaded), paths_fasta(&graph));
+}
+
+#[test]
+fn sample_graph() {
+    use gfahandlegraph::hashgraph::WalkEnd;
+    use std::collections::HashSet;
+
+    let graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let ids = |ids: &[u64]| ids.iter().map(|id| NodeId::from(*id)).collect::<Vec<_>>();
+    let fwd = |id: u64| Handle::pack(id, false);
+
+    // the same seed gives the same samples
+    assert_eq!(graph.sample_nodes(4, 42), ids(&[3, 4, 6, 9]));
+    assert_eq!(graph.sample_nodes(4, 42), graph.clone().sample_nodes(4, 42));
+    assert_eq!(
+        graph.sample_edges(3, 42),
+        vec![
+            Edge(fwd(2), fwd(5)),
+            Edge(fwd(3), fwd(5)),
+            Edge(fwd(9), fwd(11))
+        ]
+    );
+    assert_eq!(graph.sample_nodes(100, 1).len(), 15);
+    assert_eq!(graph.sample_edges(100, 1).len(), 21);
+
+    // every node is drawn sooner or later
+    let mut seen = HashSet::new();
+    for seed in 0..200 {
+        let sample = graph.sample_nodes(3, seed);
+        assert_eq!(sample.iter().collect::<HashSet<_>>().len(), 3);
+        seen.extend(sample);
+    }
+    assert_eq!(seen.len(), 15);
+
+    let (walk, end) = graph.random_walk(fwd(1), 20, 42);
+    assert_eq!(
+        walk.iter().map(|h| h.id()).collect::<Vec<_>>(),
+        ids(&[1, 3, 4, 6, 7, 9, 11, 12, 14, 15])
+    );
+    assert_eq!(end, WalkEnd::Tip);
+    // the self loop of 5
+    let (walk, end) = graph.random_walk(fwd(5), 3, 7);
+    assert_eq!(walk, vec![fwd(5), fwd(5), fwd(5), fwd(6)]);
+    assert_eq!(end, WalkEnd::Completed);
+
+    // 15 is a tip, 1 reversed too
+    assert_eq!(
+        graph.random_walk(fwd(15), 5, 0),
+        (vec![fwd(15)], WalkEnd::Tip)
+    );
+    let start = Handle::pack(1, true);
+    assert_eq!(graph.random_walk(start, 5, 0), (vec![start], WalkEnd::Tip));
+    assert_eq!(
+        graph.random_walk(fwd(15), 0, 0),
+        (vec![fwd(15)], WalkEnd::Completed)
+    );
+    assert_eq!(
+        graph.random_walk(fwd(99), 5, 0),
+        (vec![], WalkEnd::MissingStart)
+    );
+}
+
+#[test]
+fn write_gfa_preserving() {
+    use gfahandlegraph::handlegraph::HandleNeighbors;
+    use gfahandlegraph::parser::Parser;
+
+    let file = "./tests/big_files/test.gfa";
+    let original = std::fs::read(file).unwrap();
+    let original: Vec<&[u8]> = original.split_inclusive(|b| *b == b'\n').collect();
+
+    let mut graph = Parser::new()
+        .fidelity(true)
+        .parse_file_to_graph(file)
+        .unwrap();
+    assert_eq!(graph.retained_lines(), Some(original.len()));
+    assert_eq!(parse_file_to_graph(file).unwrap().retained_lines(), None);
+    let mut out = vec![];
+    graph.write_gfa_preserving(&mut out).unwrap();
+    assert_eq!(
+        out.split_inclusive(|b| *b == b'\n').collect::<Vec<_>>(),
+        original
+    );
+
+    graph.modify_handle(5, b"GATTACA").unwrap();
+    let new_edge = Edge(Handle::pack(1, false), Handle::pack(5, true));
+    assert!(!graph.has_edge(new_edge.0, new_edge.1));
+    graph.create_edge(new_edge).unwrap();
+    let mut out = vec![];
+    graph.write_gfa_preserving(&mut out).unwrap();
+    let written: Vec<&[u8]> = out.split_inclusive(|b| *b == b'\n').collect();
+
+    // the same lines in the same order, but the segment changed in place;
+    // the last line of the file had no terminator, now it's followed
+    let text = |line: &[u8]| line.strip_suffix(b"\n").unwrap_or(line).to_vec();
+    assert_eq!(written.len(), original.len() + 1);
+    let changed: Vec<usize> = (0..original.len())
+        .filter(|ix| text(written[*ix]) != text(original[*ix]))
+        .collect();
+    assert_eq!(changed.len(), 1);
+    assert!(original[changed[0]].starts_with(b"S\t5\t"));
+    assert_eq!(written[changed[0]], b"S\t5\tGATTACA\n");
+    assert_eq!(written[original.len()], b"L\t1\t+\t5\t-\t0M\n");
+
+    // and the output is read back as the modified graph
+    let path = std::env::temp_dir().join("gfahandlegraph_preserving.gfa");
+    std::fs::write(&path, &out).unwrap();
+    let read_back = parse_file_to_graph(&path).unwrap();
+    assert_eq!(read_back.to_string(), graph.to_string());
+    std::fs::remove_file(path).unwrap();
+
+    // the same for GFA2, where the removed records are left out
+    let file = "./tests/gfa2_files/spec_q7.gfa2";
+    let mut graph = Parser::new()
+        .fidelity(true)
+        .parse_file_to_graph(file)
+        .unwrap();
+    let mut out = vec![];
+    graph.write_gfa_preserving(&mut out).unwrap();
+    assert_eq!(out, std::fs::read(file).unwrap());
+    graph.clear_graph();
+    let mut out = vec![];
+    graph.write_gfa_preserving(&mut out).unwrap();
+    assert_eq!(out, b"H\tVN:Z:2.0    \n");
+}
+
+#[test]
+fn generation_counters() {
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let fwd = |id: u64| Handle::pack(id, false);
+    let counters = |g: &HashGraph| {
+        (
+            g.generation(),
+            g.topology_generation(),
+            g.sequence_generation(),
+            g.path_generation(),
+        )
+    };
+    // how each counter moved since the last check
+    let mut last = counters(&graph);
+    let mut step = |g: &HashGraph| {
+        let now = counters(g);
+        let moved = (
+            now.0 - last.0,
+            now.1 - last.1,
+            now.2 - last.2,
+            now.3 - last.3,
+        );
+        last = now;
+        moved
+    };
+
+    // failed mutations, or ones that don't change anything
+    assert!(graph.remove_handle(999).is_err());
+    assert!(graph.modify_handle(999, b"A").is_err());
+    assert!(graph.remove_edge(Edge(fwd(1), fwd(999))).is_err());
+    graph.modify_handle(1, b"CAAATAAG").unwrap();
+    graph.create_edge(Edge(fwd(1), fwd(2))).unwrap();
+    assert!(graph.reverse_path(&99).is_err());
+    assert_eq!(graph.remove_edges_where(|_| false), 0);
+    assert_eq!(graph.apply_orientation(fwd(1)).unwrap(), fwd(1));
+    assert_eq!(step(&graph), (0, 0, 0, 0));
+
+    graph.modify_handle(1, b"CAAATAAGG").unwrap();
+    assert_eq!(step(&graph), (1, 0, 1, 0));
+
+    graph.create_handle(100, b"ACGT").unwrap();
+    assert_eq!(step(&graph), (1, 1, 0, 0));
+    graph.create_edge(Edge(fwd(15), fwd(100))).unwrap();
+    assert_eq!(step(&graph), (1, 1, 0, 0));
+    graph.set_edge_overlap(Edge(fwd(15), fwd(100)), 2).unwrap();
+    assert_eq!(step(&graph), (1, 1, 0, 0));
+    graph.set_edge_overlap(Edge(fwd(15), fwd(100)), 2).unwrap();
+    assert_eq!(step(&graph), (0, 0, 0, 0));
+    graph
+        .modify_edge(Edge(fwd(15), fwd(100)), Some(fwd(14)), None)
+        .unwrap();
+    assert_eq!(step(&graph), (1, 1, 0, 0));
+
+    let path = graph.create_path_handle(b"w", false);
+    assert_eq!(step(&graph), (1, 0, 0, 1));
+    graph.append_step(&path, fwd(14)).unwrap();
+    assert_eq!(step(&graph), (1, 0, 0, 1));
+    graph.rewrite_path(b"w", vec![fwd(14), fwd(100)]).unwrap();
+    assert_eq!(step(&graph), (1, 0, 0, 1));
+    graph.reverse_path(&path).unwrap();
+    assert_eq!(step(&graph), (1, 0, 0, 1));
+
+    // a single operation made of many changes counts once
+    graph.divide_handle(fwd(9), vec![3, 10]).unwrap();
+    assert_eq!(step(&graph), (1, 1, 1, 1));
+    graph.apply_orientation(fwd(6).flip()).unwrap();
+    assert_eq!(step(&graph), (1, 1, 1, 1));
+    graph.reverse_complement_in_place();
+    assert_eq!(step(&graph), (1, 1, 1, 1));
+    assert_eq!(graph.remove_edges_of_node(NodeId::from(100), None), 1);
+    assert_eq!(step(&graph), (1, 1, 0, 1));
+
+    // the sorted handles follow the topology
+    graph.remove_handle(100).unwrap();
+    assert_eq!(step(&graph), (1, 1, 0, 0));
+    assert!(graph.handles_sorted().all(|h| h.id() != NodeId::from(100)));
+    graph.remove_handle(2).unwrap();
+    assert_eq!(step(&graph), (1, 1, 0, 1));
+
+    // the counters never go back, not even clearing the graph
+    graph.clear_graph();
+    assert_eq!(step(&graph), (1, 1, 1, 1));
+    graph.mark_modified();
+    assert_eq!(step(&graph), (1, 1, 1, 1));
+    assert_eq!(counters(&graph.clone()), counters(&graph));
+}
+
+#[test]
+fn parse_multiple_files() {
+    use gfahandlegraph::parser::{parse_files_to_graph, MultiFileMode, ParseError, Parser};
+
+    let dir = "./tests/gfa1_files/multi/";
+    let files = |names: &[&str]| {
+        names
+            .iter()
+            .map(|n| format!("{}{}", dir, n))
+            .collect::<Vec<_>>()
+    };
+
+    // the overlays add their paths on the topology of the base
+    let base = parse_file_to_graph(format!("{}base.gfa", dir)).unwrap();
+    let (graph, warnings) = Parser::new()
+        .parse_files_to_graph_with_warnings(
+            &files(&["base.gfa", "sample1.gfa", "sample2.gfa"]),
+            MultiFileMode::BaseWithPathOverlays,
+        )
+        .unwrap();
+    assert_eq!(graph.paths.len(), 1 + 2 + 1);
+    assert_eq!(sorted_edges(&graph), sorted_edges(&base));
+    assert_eq!(graph.graph.len(), base.graph.len());
+    let fwd = |id: u64| Handle::pack(id, false);
+    let names: Vec<Vec<u8>> = path_steps(&graph).into_iter().map(|(n, _)| n).collect();
+    assert_eq!(
+        names,
+        vec![
+            b"a".to_vec(),
+            b"b".to_vec(),
+            b"sample1#x".to_vec(),
+            b"x".to_vec()
+        ]
+    );
+    assert!(path_steps(&graph).contains(&(b"x".to_vec(), vec![fwd(1), fwd(2), fwd(4)])));
+    let warnings: Vec<String> = warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        warnings,
+        vec![
+            format!(
+                "{}sample1.gfa: ignored the segment 5, an overlay file can only add paths",
+                dir
+            ),
+            format!(
+                "{}sample2.gfa: ignored the edge 4- 3-, an overlay file can only add paths",
+                dir
+            ),
+        ]
+    );
+
+    // disjoint files give the same graph as the file with all the lines
+    let union =
+        parse_files_to_graph(&files(&["chr1.gfa", "chr2.gfa"]), MultiFileMode::Union).unwrap();
+    let all = parse_file_to_graph(format!("{}chr_all.gfa", dir)).unwrap();
+    assert_same_graph(&union, &all);
+    assert_eq!(path_steps(&union), path_steps(&all));
+
+    // a segment in more files is added once
+    let union = parse_files_to_graph(
+        &files(&["chr1.gfa", "chr1_extra.gfa"]),
+        MultiFileMode::Union,
+    )
+    .unwrap();
+    assert_eq!(union.graph.len(), 3);
+    assert_eq!(sorted_edges(&union).len(), 2);
+    assert_consistent(&union);
+
+    // but only with the same sequence
+    let conflict =
+        parse_files_to_graph(&files(&["chr1.gfa", "conflict.gfa"]), MultiFileMode::Union);
+    match conflict {
+        Err(ParseError::ConflictingSegment(segment, file, first)) => {
+            assert_eq!(segment, "2");
+            assert_eq!(file, format!("{}conflict.gfa", dir));
+            assert_eq!(first, format!("{}chr1.gfa", dir));
+        }
+        _ => panic!("the sequences of segment 2 are different"),
+    }
+}
+
+#[test]
+fn json_report_of_a_damaged_file() {
+    use gfahandlegraph::parser::recover_file_to_graph;
+    use gfahandlegraph::util::report::{to_json_report, ReportItem};
+
+    let (graph, report) = recover_file_to_graph("./tests/gfa1_files/two_issues.gfa").unwrap();
+    let mut items = report.report_items();
+    items.extend(
+        graph
+            .path_orientation_anomalies()
+            .iter()
+            .map(ReportItem::from),
+    );
+    let codes: Vec<&str> = items.iter().map(|item| item.code).collect();
+    assert_eq!(codes, vec!["P003", "V002"]);
+
+    // the same items, whatever their order
+    let sorted_lines = |json: &str| {
+        let mut lines: Vec<String> = json
+            .lines()
+            .map(|l| l.trim_end_matches(',').to_string())
+            .collect();
+        lines.sort();
+        lines
+    };
+    items.reverse();
+    let golden = std::fs::read_to_string("./tests/gfa1_files/two_issues.report.json").unwrap();
+    assert_eq!(sorted_lines(&to_json_report(&items)), sorted_lines(&golden));
+}
+
+/// A graph where node 2 (with the given id) is traversed by two paths,
+/// forward and backwards, and node 1 (with the given id) has a self-loop
+fn renaming_graph(one: u64, two: u64) -> HashGraph {
+    let h = |id: u64, rev: bool| Handle::pack(id, rev);
+    let mut graph = HashGraph::from_parts(
+        vec![
+            (NodeId::from(one), b"ACG".to_vec()),
+            (NodeId::from(two), b"TT".to_vec()),
+            (NodeId::from(3), b"GCA".to_vec()),
+        ],
+        vec![
+            Edge(h(one, false), h(two, false)),
+            Edge(h(two, false), h(3, false)),
+            Edge(h(two, true), h(one, true)),
+            Edge(h(one, false), h(one, true)),
+        ],
+        vec![
+            (
+                b"x".to_vec(),
+                vec![h(one, false), h(two, false), h(3, false)],
+                false,
+            ),
+            (
+                b"y".to_vec(),
+                vec![h(3, true), h(two, true), h(one, true)],
+                false,
+            ),
+        ],
+    )
+    .unwrap();
+    graph
+        .set_edge_overlap(Edge(h(one, false), h(two, false)), 1)
+        .unwrap();
+    graph
+}
+
+#[test]
+fn rename_nodes() {
+    use fnv::FnvHashMap;
+    use gfahandlegraph::handlegraph::GraphError;
+
+    let same = |a: &HashGraph, b: &HashGraph| {
+        assert_same_graph(a, b);
+        assert_eq!(path_steps(a), path_steps(b));
+        assert_eq!((a.min_id, a.max_id), (b.min_id, b.max_id));
+        assert_consistent(a);
+        for Edge(l, r) in sorted_edges(b) {
+            assert_eq!(a.edge_overlap(Edge(l, r)), b.edge_overlap(Edge(l, r)));
+        }
+    };
+
+    let mut graph = renaming_graph(1, 2);
+    let before = graph.path_generation();
+    graph
+        .rename_node(NodeId::from(2), NodeId::from(10))
+        .unwrap();
+    same(&graph, &renaming_graph(1, 10));
+    assert_eq!(graph.path_generation(), before + 1);
+    for path in graph.paths.values() {
+        let ix = path.nodes.iter().position(|h| h.id() == NodeId::from(10));
+        let node = graph.get_node(&NodeId::from(10)).unwrap();
+        assert_eq!(
+            node.occurrences.get(&path.path_id).cloned(),
+            ix.map(|ix| vec![ix])
+        );
+    }
+
+    // the ids can be swapped
+    let mut graph = renaming_graph(1, 2);
+    let mut swap = FnvHashMap::default();
+    swap.insert(NodeId::from(1), NodeId::from(2));
+    swap.insert(NodeId::from(2), NodeId::from(1));
+    graph.rename_nodes(&swap).unwrap();
+    same(&graph, &renaming_graph(2, 1));
+
+    // or shifted along a chain
+    let mut graph = renaming_graph(1, 2);
+    let mut chain = FnvHashMap::default();
+    chain.insert(NodeId::from(1), NodeId::from(2));
+    chain.insert(NodeId::from(2), NodeId::from(7));
+    graph.rename_nodes(&chain).unwrap();
+    same(&graph, &renaming_graph(2, 7));
+
+    // a collision changes nothing
+    let mut graph = renaming_graph(1, 2);
+    let generation = graph.generation();
+    assert!(matches!(
+        graph.rename_node(NodeId::from(1), NodeId::from(3)),
+        Err(GraphError::IdAlreadyExist(_))
+    ));
+    let mut both = FnvHashMap::default();
+    both.insert(NodeId::from(1), NodeId::from(9));
+    both.insert(NodeId::from(2), NodeId::from(9));
+    assert!(graph.rename_nodes(&both).is_err());
+    assert!(matches!(
+        graph.rename_node(NodeId::from(5), NodeId::from(6)),
+        Err(GraphError::NodeNotExist(_))
+    ));
+    same(&graph, &renaming_graph(1, 2));
+    assert_eq!(graph.generation(), generation);
+}
+
+#[test]
+fn id_bounds_after_every_mutator() {
+    use gfahandlegraph::util::report::ReportItem;
+
+    let bounds = |graph: &HashGraph| {
+        assert_eq!(graph.id_bounds_mismatch(), None);
+        graph
+            .id_bounds()
+            .map(|(min, max)| (u64::from(min), u64::from(max)))
+    };
+
+    let mut graph = HashGraph::new();
+    assert_eq!(bounds(&graph), None);
+    for id in [5, 3, 9, 7].iter() {
+        graph.create_handle(*id, b"ACGT").unwrap();
+    }
+    assert_eq!(bounds(&graph), Some((3, 9)));
+
+    // remove the max, the min and one in the middle
+    graph.remove_handle(9).unwrap();
+    assert_eq!(bounds(&graph), Some((3, 7)));
+    graph.remove_handle(3).unwrap();
+    assert_eq!(bounds(&graph), Some((5, 7)));
+    graph.create_handle(6, b"A").unwrap();
+    graph.remove_handle(6).unwrap();
+    assert_eq!(bounds(&graph), Some((5, 7)));
+
+    // a new node goes after the max
+    let appended = graph.append_handle(b"GG").unwrap();
+    assert_eq!(u64::from(appended.id()), 8);
+    graph.create_handle(10, b"C").unwrap();
+    assert_eq!(bounds(&graph), Some((5, 10)));
+
+    // rename to a new max and to a new min, and the max away
+    graph
+        .rename_node(NodeId::from(7), NodeId::from(100))
+        .unwrap();
+    assert_eq!(bounds(&graph), Some((5, 100)));
+    graph.rename_node(NodeId::from(8), NodeId::from(1)).unwrap();
+    assert_eq!(bounds(&graph), Some((1, 100)));
+    graph
+        .rename_node(NodeId::from(100), NodeId::from(20))
+        .unwrap();
+    assert_eq!(bounds(&graph), Some((1, 20)));
+
+    // remove all the nodes, down to an empty graph
+    let mut ids: Vec<NodeId> = graph.graph.keys().copied().collect();
+    ids.sort();
+    for id in ids.into_iter().rev() {
+        graph.remove_handle(id).unwrap();
+        assert_eq!(graph.id_bounds_mismatch(), None);
+    }
+    assert_eq!(bounds(&graph), None);
+    assert_eq!(
+        (u64::from(graph.min_id), u64::from(graph.max_id)),
+        (u64::MAX, 0)
+    );
+
+    // and so do the pieces of a divided node
+    let mut graph = renaming_graph(4, 2);
+    assert_eq!(bounds(&graph), Some((2, 4)));
+    assert!(graph.validate().is_empty());
+    graph
+        .divide_handle(Handle::pack(3, false), vec![1, 2])
+        .unwrap();
+    assert_eq!(bounds(&graph), Some((2, 6)));
+    graph.clear_graph();
+    assert_eq!(bounds(&graph), None);
+
+    // the bounds set directly are reported, until they're recomputed
+    let mut graph = renaming_graph(4, 2);
+    graph.max_id = NodeId::from(3);
+    let items: Vec<ReportItem> = graph.validate();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].code, "V008");
+    assert_eq!(
+        items[0].message,
+        "The id bounds are min 2 and max 3, but the nodes go from 2 to 4"
+    );
+    graph.recompute_bounds();
+    assert_eq!(bounds(&graph), Some((2, 4)));
+    assert!(graph.validate().is_empty());
+
+    graph.graph.clear();
+    assert_eq!(graph.validate()[0].code, "V008");
+    graph.recompute_bounds();
+    assert_eq!(bounds(&graph), None);
+}
+
+#[test]
+fn metadata_sidecar_of_a_saved_gfa() {
+    use gfahandlegraph::handlegraph::{AllEdges, AllHandles, HandleGraphRef};
+    use gfahandlegraph::util::{to_gfa_with_config, WriterConfig};
+
+    let graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let path = std::env::temp_dir().join("gfahandlegraph_sidecar.gfa");
+    let path = path.to_str().unwrap().to_string();
+    let sidecar = format!("{}.meta.json", path);
+    let _ = std::fs::remove_file(&sidecar);
+
+    // without it, no other file is written
+    to_gfa_with_config(
+        &graph,
+        "GFA".to_string(),
+        Some(path.clone()),
+        Default::default(),
+    )
+    .unwrap();
+    assert!(!std::path::Path::new(&sidecar).exists());
+
+    let config = WriterConfig {
+        validate: true,
+        sidecar: true,
+        ..Default::default()
+    };
+    to_gfa_with_config(&graph, "GFA".to_string(), Some(path.clone()), config).unwrap();
+    assert!(!std::path::Path::new(&format!("{}.tmp", sidecar)).exists());
+    let json = std::fs::read_to_string(&sidecar).unwrap();
+    let field = |key: &str| -> String {
+        let start = json.find(&format!("\"{}\":", key)).unwrap() + key.len() + 3;
+        let value = &json[start..];
+        match value.chars().next().unwrap() {
+            '{' => value[..=value.find('}').unwrap()].to_string(),
+            _ => value[..value.find([',', '\n', '}']).unwrap()].to_string(),
+        }
+    };
+
+    for key in [
+        "schema",
+        "writer",
+        "crate",
+        "version",
+        "format",
+        "deterministic",
+        "validated",
+        "fidelity",
+        "digest",
+        "nodes",
+        "edges",
+        "paths",
+        "total_length",
+        "id_bounds",
+        "samples",
+    ]
+    .iter()
+    {
+        assert!(
+            json.contains(&format!("\"{}\":", key)),
+            "{} is missing",
+            key
+        );
+    }
+    assert_eq!(field("schema"), "\"gfahandlegraph-metadata/1\"");
+    assert_eq!(
+        field("version"),
+        format!("\"{}\"", env!("CARGO_PKG_VERSION"))
+    );
+    assert_eq!(field("format"), "\"GFA\"");
+    assert_eq!(field("validated"), "true");
+    assert_eq!(field("nodes"), graph.node_count().to_string());
+    assert_eq!(field("edges"), graph.edge_count().to_string());
+    assert_eq!(field("paths"), graph.paths.len().to_string());
+    assert_eq!(field("total_length"), graph.total_length().to_string());
+    assert_eq!(field("id_bounds"), "{\"min\":1,\"max\":15}");
+    assert_eq!(field("samples"), "null");
+
+    // FNV-1a of the bytes of the file written
+    let digest = std::fs::read(&path)
+        .unwrap()
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    assert_eq!(field("digest"), format!("\"fnv1a64:{:016x}\"", digest));
+
+    // the sidecar written from the graph is the same
+    let mut written = vec![];
+    let ctx = gfahandlegraph::util::WriteContext {
+        validated: true,
+        ..Default::default()
+    };
+    graph.write_metadata_sidecar(&mut written, &ctx).unwrap();
+    assert_eq!(String::from_utf8(written).unwrap(), json);
+
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&sidecar).unwrap();
+
+    // the paths named as PanSN are counted by sample
+    let step = vec![Handle::pack(1, false)];
+    let graph = HashGraph::from_parts(
+        vec![(NodeId::from(1), b"A".to_vec())],
+        vec![],
+        vec![
+            (b"HG002#1#chr1".to_vec(), step.clone(), false),
+            (b"HG002#2#chr1".to_vec(), step.clone(), false),
+            (b"HG003#1#chr1".to_vec(), step.clone(), false),
+            (b"ref".to_vec(), step, false),
+        ],
+    )
+    .unwrap();
+    let mut written = vec![];
+    graph
+        .write_metadata_sidecar(&mut written, &Default::default())
+        .unwrap();
+    let json = String::from_utf8(written).unwrap();
+    assert!(json.contains("\n\"samples\":{\"HG002\":2,\"HG003\":1}}\n"));
+}
+
+#[test]
+fn lazy_sequences_from_the_file() {
+    use gfahandlegraph::handlegraph::{AllHandles, HandleGraphRef, HandleSequences};
+    use gfahandlegraph::hashgraph::LazySequenceStore;
+    use gfahandlegraph::mutablehandlegraph::ModdableHandleGraph;
+    use gfahandlegraph::parser::Parser;
+    use gfahandlegraph::util::to_file::to_gfa;
+    use std::io::{Read, Seek, SeekFrom};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// A file that counts the bytes read from it
+    struct CountingReader {
+        file: std::fs::File,
+        read: Arc<AtomicUsize>,
+    }
+
+    impl Read for CountingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.file.read(buf)?;
+            self.read.fetch_add(read, Ordering::SeqCst);
+            Ok(read)
+        }
+    }
+
+    impl Seek for CountingReader {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.file.seek(pos)
+        }
+    }
+
+    let file = "./tests/big_files/test.gfa";
+    let file_len = std::fs::metadata(file).unwrap().len() as usize;
+    let read = Arc::new(AtomicUsize::new(0));
+    let store = LazySequenceStore::from_reader(CountingReader {
+        file: std::fs::File::open(file).unwrap(),
+        read: read.clone(),
+    });
+    let mut lazy = Parser::new()
+        .parse_file_to_graph_lazy_with(file, store)
+        .unwrap();
+    let mut eager = parse_file_to_graph(file).unwrap();
+
+    assert_eq!(lazy.node_count(), eager.node_count());
+    assert_eq!(lazy.total_length(), eager.total_length());
+    assert_eq!(sorted_edges(&lazy), sorted_edges(&eager));
+    assert_eq!(path_steps(&lazy), path_steps(&eager));
+    assert_eq!(read.load(Ordering::SeqCst), 0);
+
+    // only the sequences fetched are read
+    for id in [1, 53, 1000, 2500, 4058].iter() {
+        for reverse in [false, true].iter() {
+            let handle = Handle::pack(*id, *reverse);
+            assert_eq!(lazy.sequence(handle), eager.sequence(handle));
+        }
+    }
+    let fetched = read.load(Ordering::SeqCst);
+    assert!(
+        fetched > 0 && fetched < file_len / 100,
+        "{} bytes read",
+        fetched
+    );
+
+    // or from the bytes of the file, as a memory map
+    let store = LazySequenceStore::from_bytes(std::fs::read(file).unwrap());
+    let mapped = Parser::new()
+        .parse_file_to_graph_lazy_with(file, store)
+        .unwrap();
+    let handle = Handle::pack(2500, true);
+    assert_eq!(mapped.sequence(handle), eager.sequence(handle));
+
+    lazy.modify_handle(1000, b"GATTACA").unwrap();
+    eager.modify_handle(1000, b"GATTACA").unwrap();
+    let dir = std::env::temp_dir();
+    let saved = |graph: &HashGraph, name: &str| {
+        let path = dir.join(name).to_str().unwrap().to_string();
+        to_gfa(graph, "GFA".to_string(), Some(path.clone())).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        bytes
+    };
+    assert_eq!(
+        saved(&lazy, "gfahandlegraph_lazy.gfa"),
+        saved(&eager, "gfahandlegraph_eager.gfa")
+    );
+
+    // the sequences read are kept until they are released
+    assert_eq!(lazy.release_loaded_sequences(), lazy.node_count() - 1);
+    let before = read.load(Ordering::SeqCst);
+    assert_eq!(lazy.total_length(), eager.total_length());
+    assert_eq!(read.load(Ordering::SeqCst), before);
+}
+
+#[test]
+fn lazy_sequence_read_failure() {
+    use gfahandlegraph::handlegraph::GraphError;
+    use gfahandlegraph::hashgraph::{LazySequenceStore, SequenceRef};
+    use gfahandlegraph::parser::Parser;
+
+    let file = "./tests/big_files/test.gfa";
+    let bytes = std::fs::read(file).unwrap();
+    let eager = parse_file_to_graph(file).unwrap();
+    // the store lost the file after the first segment
+    let cut: usize = bytes
+        .split_inclusive(|b| *b == b'\n')
+        .take(2)
+        .map(<[u8]>::len)
+        .sum();
+    let store = LazySequenceStore::from_bytes(bytes[..cut].to_vec());
+    let lazy = Parser::new()
+        .parse_file_to_graph_lazy_with(file, store)
+        .unwrap();
+
+    let first = lazy.get_node(&NodeId::from(1)).unwrap();
+    assert_eq!(
+        lazy.try_node_sequence(first).unwrap(),
+        eager.node_sequence(eager.get_node(&NodeId::from(1)).unwrap())
+    );
+    let last = lazy.get_node(&NodeId::from(4058)).unwrap();
+    assert!(matches!(
+        lazy.try_node_sequence(last),
+        Err(GraphError::SequenceUnavailable(_))
+    ));
+    match last.sequence_ref() {
+        SequenceRef::Lazy(sequence) => {
+            let err = sequence.try_bytes().unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+            assert!(!sequence.is_loaded());
+        }
+        other => panic!("expected a lazy sequence, got {:?}", other),
+    }
+}
+
+#[test]
+fn adversarial_inputs_never_panic() {
+    use gfahandlegraph::handlegraph::HandleGraphRef;
+    use gfahandlegraph::parser::*;
+    use gfahandlegraph::util::ThreadConfig;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    // every way to parse a file: the result doesn't matter, a panic does
+    let parse_all = |path: &str| {
+        let _ = GFAParser::new().parse_file(path).map(|g| g.to_string());
+        let _ = GFA2Parser::new().parse_file(path).map(|g| g.to_string());
+        let _ = parse_file_to_graph(path).map(|g| g.to_string());
+        let _ = Parser::new()
+            .threads(ThreadConfig::sequential())
+            .fidelity(true)
+            .parse_file_to_graph(path);
+        let _ = Parser::new()
+            .error_tolerance(ParserTolerance::IgnoreAll)
+            .parse_file_to_graph(path);
+        let _ = Parser::new()
+            .parse_file_incremental(path)
+            .map(|b| b.finish());
+        let _ = parse_file_to_graph_lazy(path).map(|g| g.total_length());
+        let _ = recover_file_to_graph(path);
+        let _ = parse_files_to_graph(&[path, path], MultiFileMode::Union);
+    };
+
+    let mut inputs: Vec<(String, Vec<u8>)> = std::fs::read_dir("./tests/adversarial")
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_str().unwrap().to_string();
+            (name, std::fs::read(&path).unwrap())
+        })
+        .collect();
+    inputs.sort();
+    assert!(inputs.len() >= 19);
+    // the huge fields are built here, to keep the corpus small
+    let huge_sequence = format!("S\t1\t{}\nP\tp\t1+\t*\n", "ACGT".repeat(1 << 18));
+    let huge_id = format!(
+        "S\t{}\tA\nL\t1\t+\t{}\t-\t*\n",
+        "9".repeat(100_000),
+        "1".repeat(30)
+    );
+    let huge_path = format!("S\t1\tA\nP\tp\t{}1+\t*\n", "1+,".repeat(100_000));
+    let huge_group = format!("S\t1\t1\tA\nO\tp\t{}1+\n", "1+ ".repeat(100_000));
+    for (name, text) in [
+        ("huge_sequence", huge_sequence),
+        ("huge_id", huge_id),
+        ("huge_path", huge_path),
+        ("huge_group", huge_group),
+    ] {
+        inputs.push((name.to_string(), text.into_bytes()));
+    }
+
+    let dir = std::env::temp_dir();
+    for (name, bytes) in inputs.iter() {
+        // each input as both formats
+        for extension in ["gfa", "gfa2"] {
+            let path = dir.join(format!("gfahandlegraph_adversarial.{}", extension));
+            let path = path.to_str().unwrap();
+            std::fs::write(path, bytes).unwrap();
+            let parsed = catch_unwind(AssertUnwindSafe(|| parse_all(path)));
+            std::fs::remove_file(path).unwrap();
+            assert!(parsed.is_ok(), "{} as .{} panicked", name, extension);
+        }
+    }
+
+    // a directory can be opened, but not read
+    let path = dir.join("gfahandlegraph_adversarial_dir.gfa");
+    std::fs::create_dir_all(&path).unwrap();
+    let parsed = catch_unwind(AssertUnwindSafe(|| parse_all(path.to_str().unwrap())));
+    std::fs::remove_dir(&path).unwrap();
+    assert!(parsed.is_ok(), "a directory panicked");
+}
+
+#[test]
+fn adversarial_regressions() {
+    use gfahandlegraph::handlegraph::{error::GraphError, AllHandles};
+    use gfahandlegraph::hashgraph::graph::FileType;
+    use gfahandlegraph::parser::{ParseError, ParsedGfa, Parser};
+
+    let parsed = |name: &str| parse_file_to_graph(format!("./tests/adversarial/{}", name));
+    // the references without an orientation can't be parsed
+    for name in [
+        "regression_path_step_without_orientation.gfa",
+        "regression_path_empty_step.gfa",
+        "regression_edge_reference_orientation_inside.gfa2",
+    ] {
+        assert!(
+            matches!(parsed(name), Err(ParseError::InvalidLineAt(_, _, _))),
+            "{}",
+            name
+        );
+    }
+    // the orientation isn't part of the id anymore, so "0+" references
+    // the segment 0, that doesn't exist
+    let graph = parsed("regression_edge_reference_lost_id.gfa2").unwrap();
+    assert_eq!(graph.node_count(), 1);
+    assert_eq!(sorted_edges(&graph), vec![]);
+    // the references to a missing segment are left out
+    for (name, steps) in [
+        ("regression_link_to_missing_segment.gfa", None),
+        ("regression_edge_to_missing_segment.gfa2", None),
+        ("regression_path_to_missing_segment.gfa", Some(1)),
+        ("regression_group_to_missing_segment.gfa2", Some(1)),
+    ] {
+        let graph = parsed(name).unwrap();
+        assert_eq!(graph.node_count(), 1, "{}", name);
+        assert_eq!(sorted_edges(&graph), vec![], "{}", name);
+        assert_eq!(graph.paths.values().next().map(|p| p.nodes.len()), steps);
+
+        // and returned, when asked
+        let path = format!("./tests/adversarial/{}", name);
+        let file = match Parser::new().parse_file(&path).unwrap() {
+            ParsedGfa::GFA(gfa) => FileType::GFA(gfa),
+            ParsedGfa::GFA2(gfa2) => FileType::GFA2(gfa2),
+        };
+        let errors = HashGraph::new().create_graph_with_errors(file);
+        assert_eq!(errors.len(), 1, "{}", name);
+        match (&errors[0], steps) {
+            (GraphError::NodeNotExist(id), None) => assert_eq!(id, "2"),
+            (GraphError::MissingStepNode(path, id, ix), Some(_)) => {
+                assert_eq!((path.as_str(), id.as_str(), *ix), ("p", "2", 1))
+            }
+            (other, _) => panic!("{}: unexpected {:?}", name, other),
+        }
+    }
+}
+
+/// The occurrences of every node, sorted by node
+fn all_occurrences(graph: &HashGraph) -> Vec<(NodeId, Vec<(i64, usize)>)> {
+    let mut all: Vec<_> = graph
+        .graph
+        .iter()
+        .map(|(id, node)| {
+            // the keys of the steps as their index in the path
+            let mut occurrences: Vec<_> = node
+                .occurrences
+                .iter()
+                .flat_map(|(p, keys)| {
+                    let path = graph.paths.get(p);
+                    keys.iter()
+                        .map(move |key| (*p, path.map_or(*key, |path| path.step_index(*key))))
+                })
+                .collect();
+            occurrences.sort();
+            (*id, occurrences)
+        })
+        .collect();
+    all.sort();
+    all
+}
+
+#[test]
+fn steps_of_missing_nodes() {
+    use gfahandlegraph::handlegraph::GraphError;
+
+    use gfahandlegraph::hashgraph::PathStep;
+
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let x = graph.path_id[&b"x"[..]];
+    let before = all_occurrences(&graph);
+    let len = graph.get_path(&x).unwrap().nodes.len();
+
+    let missing = Handle::pack(99, false);
+    match graph.append_step(&x, missing) {
+        Err(GraphError::MissingStepNode(path, node, ix)) => {
+            assert_eq!((path.as_str(), node.as_str(), ix), ("x", "99", len))
+        }
+        other => panic!("expected a missing node, got {:?}", other),
+    }
+    match graph.prepend_step(&x, missing) {
+        Err(GraphError::MissingStepNode(path, node, ix)) => {
+            assert_eq!((path.as_str(), node.as_str(), ix), ("x", "99", 0))
+        }
+        other => panic!("expected a missing node, got {:?}", other),
+    }
+    assert!(matches!(
+        graph.append_step(&42, Handle::pack(1, false)),
+        Err(GraphError::PathNotExist(_))
+    ));
+    assert_eq!(graph.get_path(&x).unwrap().nodes.len(), len);
+    assert_eq!(all_occurrences(&graph), before);
+    assert!(graph.validate().is_empty());
+
+    // a valid step moves the others, that keep their keys
+    assert_eq!(
+        graph.prepend_step(&x, Handle::pack(2, false)).unwrap(),
+        PathStep::Step(x, 0)
+    );
+    let path = graph.get_path(&x).unwrap();
+    assert_eq!(path.nodes.len(), len + 1);
+    for (ix, handle) in path.nodes.iter().enumerate() {
+        let node = graph.get_node(&handle.id()).unwrap();
+        let indices: Vec<usize> = node.occurrences[&x]
+            .iter()
+            .map(|key| path.step_index(*key))
+            .collect();
+        assert_eq!(indices, vec![ix]);
+    }
+}
+
+#[test]
+fn create_graph_reports_missing_step_node() {
+    use gfahandlegraph::handlegraph::GraphError;
+    use gfahandlegraph::hashgraph::graph::FileType;
+    use gfahandlegraph::parser::GFAParser;
+
+    let content = "H\tVN:Z:1.0\n\
+                   S\t1\tACGT\n\
+                   S\t2\tGG\n\
+                   S\t3\tTT\n\
+                   L\t1\t+\t2\t+\t0M\n\
+                   L\t2\t+\t3\t+\t0M\n\
+                   P\tchr1\t1+,2+,3+,512+\t*\n";
+    let path = std::env::temp_dir().join("gfahandlegraph_missing_step.gfa");
+    std::fs::write(&path, content).unwrap();
+    let gfa = GFAParser::new().parse_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut graph = HashGraph::new();
+    let errors = graph.create_graph_with_errors(FileType::GFA(gfa));
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        GraphError::MissingStepNode(path, node, 3) if path == "chr1" && node == "512"
+    ));
+    assert_eq!(
+        errors[0].to_string(),
+        "The Path (chr1) references the missing node 512 at step 3"
+    );
+    let chr1 = graph.path_id[&b"chr1"[..]];
+    assert_eq!(graph.get_path(&chr1).unwrap().nodes.len(), 3);
+}
+
+#[test]
+fn compressed_saves() {
+    use gfahandlegraph::util::gzip::{gunzip, Compression};
+    use gfahandlegraph::util::{
+        load_archive, save_archive, to_gfa, to_gfa_with_config, WriterConfig,
+    };
+
+    let graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let dir = std::env::temp_dir();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+    let (plain, gz) = (
+        path("gfahandlegraph_compressed.gfa"),
+        path("gfahandlegraph_compressed.gfa.gz"),
+    );
+    to_gfa(&graph, "GFA".to_string(), Some(plain.clone())).unwrap();
+    to_gfa(&graph, "GFA".to_string(), Some(gz.clone())).unwrap();
+    let text = std::fs::read(&plain).unwrap();
+    let compressed = std::fs::read(&gz).unwrap();
+    assert!(compressed.starts_with(&[0x1f, 0x8b]));
+    assert_eq!(gunzip(&compressed).unwrap(), text);
+    assert!(!std::path::Path::new(&format!("{}.tmp", gz)).exists());
+
+    // the compression asked is used whatever the path, also validating
+    let config = WriterConfig {
+        validate: true,
+        compression: Compression::Gzip(9),
+        ..Default::default()
+    };
+    to_gfa_with_config(&graph, "GFA".to_string(), Some(plain.clone()), config).unwrap();
+    assert_eq!(gunzip(&std::fs::read(&plain).unwrap()).unwrap(), text);
+    for temp in [".tmp", ".tmp.plain"].iter() {
+        assert!(!std::path::Path::new(&format!("{}{}", plain, temp)).exists());
+    }
+    std::fs::remove_file(&gz).unwrap();
+
+    let (archive, archive_gz) = (
+        path("gfahandlegraph_compressed.bin"),
+        path("gfahandlegraph_compressed.bin.gz"),
+    );
+    save_archive(&graph, &archive).unwrap();
+    save_archive(&graph, &archive_gz).unwrap();
+    assert_eq!(
+        gunzip(&std::fs::read(&archive_gz).unwrap()).unwrap(),
+        std::fs::read(&archive).unwrap()
+    );
+    let loaded = load_archive(&archive_gz).unwrap();
+    to_gfa(&loaded, "GFA".to_string(), Some(plain.clone())).unwrap();
+    assert_eq!(std::fs::read(&plain).unwrap(), text);
+    std::fs::remove_file(&plain).unwrap();
+    std::fs::remove_file(&archive).unwrap();
+    std::fs::remove_file(&archive_gz).unwrap();
+}
+
+#[test]
+fn corrupted_lines_are_reported_with_their_number() {
+    use gfahandlegraph::parser::{GFA2Parser, GFAParser, ParseError, ParseFieldError};
+    use gfahandlegraph::util::ThreadConfig;
+
+    // a big file, with two corrupted lines: the first one is reported
+    let mut gfa = String::from("H\tVN:Z:1.0\n");
+    let mut gfa2 = String::from("H\tVN:Z:2.0\n");
+    for id in 1..=5000 {
+        gfa.push_str(&format!("S\t{}\tACGT\n", id));
+        gfa2.push_str(&format!("S\t{}\t4\tACGT\n", id));
+    }
+    gfa.push_str("L\t1\t+\t2\t?\t0M\n");
+    gfa2.push_str("E\t*\t1+\t2\t0\t4$\t0\t4$\t0M\n");
+    for id in 1..5000 {
+        gfa.push_str(&format!("L\t{}\t+\t{}\t+\t0M\n", id, id + 1));
+        gfa2.push_str(&format!("E\t*\t{}+\t{}+\t4$\t4$\t0\t0\t0M\n", id, id + 1));
+    }
+    gfa.push_str("L\t1\t+\t3\t\t0M\n");
+    gfa2.push_str("E\t*\t1+\n");
+
+    let dir = std::env::temp_dir();
+    let (gfa_path, gfa2_path) = (
+        dir.join("gfahandlegraph_corrupted.gfa"),
+        dir.join("gfahandlegraph_corrupted.gfa2"),
+    );
+    std::fs::write(&gfa_path, gfa).unwrap();
+    std::fs::write(&gfa2_path, gfa2).unwrap();
+    for threads in [ThreadConfig::global(), ThreadConfig::sequential()].iter() {
+        let mut builder = gfahandlegraph::parser::parser_gfa1::ParserBuilder::all();
+        builder.threads(*threads);
+        match builder.build().parse_file(&gfa_path) {
+            Err(ParseError::InvalidLineAt(5002, ParseFieldError::OrientationError, line)) => {
+                assert_eq!(line, "L\t1\t+\t2\t?\t0M")
+            }
+            other => panic!("expected the line 5002, got {:?}", other.map(|_| ())),
+        }
+        let mut builder = gfahandlegraph::parser::parser_gfa2::ParserBuilder::all();
+        builder.threads(*threads);
+        match builder.build().parse_file(&gfa2_path) {
+            Err(err @ ParseError::InvalidLineAt(5002, _, _)) => {
+                assert!(err
+                    .to_string()
+                    .starts_with("Failed to parse line 5002 (E\t*\t1+\t2\t"))
+            }
+            other => panic!("expected the line 5002, got {:?}", other.map(|_| ())),
+        }
+    }
+    assert!(GFAParser::new().parse_file(&gfa_path).is_err());
+    assert!(GFA2Parser::new().parse_file(&gfa2_path).is_err());
+    std::fs::remove_file(gfa_path).unwrap();
+    std::fs::remove_file(gfa2_path).unwrap();
+}
+
+fn removal_graph() -> HashGraph {
+    let fwd = |id: u64| Handle::pack(id, false);
+    let mut graph = HashGraph::new();
+    for id in 1..=6 {
+        graph.create_handle(id, b"ACGT").unwrap();
+    }
+    for (l, r) in [(1, 2), (2, 3), (3, 4), (4, 5), (3, 6)].iter() {
+        graph.create_edge(Edge(fwd(*l), fwd(*r))).unwrap();
+    }
+    for (name, steps) in [("a", vec![1, 2, 3, 4, 5]), ("b", vec![3, 6])].iter() {
+        let path = graph.create_path_handle(name.as_bytes(), false);
+        for id in steps.iter() {
+            graph.append_step(&path, fwd(*id)).unwrap();
+        }
+    }
+    graph
+}
+
+fn path_ids(graph: &HashGraph, name: &[u8]) -> Option<Vec<u64>> {
+    let path = graph.name_to_path_handle(name)?;
+    let steps = &graph.paths.get(&path)?.nodes;
+    Some(steps.iter().map(|h| u64::from(h.id())).collect())
+}
+
+/// The occurrences as the paths say they should be, every visit of
+/// each node
+fn expected_occurrences(graph: &HashGraph) -> Vec<(NodeId, Vec<(i64, usize)>)> {
+    use fnv::FnvHashMap;
+
+    let mut expected: FnvHashMap<NodeId, Vec<(i64, usize)>> =
+        graph.graph.keys().map(|id| (*id, vec![])).collect();
+    for (path_id, path) in graph.paths.iter() {
+        for (ix, step) in path.nodes.iter().enumerate() {
+            expected.get_mut(&step.id()).unwrap().push((*path_id, ix));
+        }
+    }
+    let mut all: Vec<_> = expected
+        .into_iter()
+        .map(|(id, mut occurrences)| {
+            occurrences.sort();
+            (id, occurrences)
+        })
+        .collect();
+    all.sort();
+    all
+}
+
+#[test]
+fn remove_handle_keeping_the_paths() {
+    use gfahandlegraph::handlegraph::AllEdges;
+    use gfahandlegraph::hashgraph::PathRepair;
+
+    // in the middle of a path, and in more paths
+    let mut graph = removal_graph();
+    graph
+        .remove_handle_with(NodeId::from(3), PathRepair::RemoveSteps)
+        .unwrap();
+    assert_eq!(path_ids(&graph, b"a"), Some(vec![1, 2, 4, 5]));
+    assert_eq!(path_ids(&graph, b"b"), Some(vec![6]));
+    assert_eq!(all_occurrences(&graph), expected_occurrences(&graph));
+
+    // at the start and at the end
+    graph
+        .remove_handle_with(NodeId::from(1), PathRepair::RemoveSteps)
+        .unwrap();
+    graph
+        .remove_handle_with(NodeId::from(5), PathRepair::RemoveSteps)
+        .unwrap();
+    assert_eq!(path_ids(&graph, b"a"), Some(vec![2, 4]));
+    assert_eq!(all_occurrences(&graph), expected_occurrences(&graph));
+
+    // the only step of a path
+    graph
+        .remove_handle_with(NodeId::from(6), PathRepair::RemoveSteps)
+        .unwrap();
+    assert_eq!(path_ids(&graph, b"b"), Some(vec![]));
+    assert_eq!(graph.edges().count(), 0);
+    assert!(graph
+        .remove_handle_with(NodeId::from(6), PathRepair::RemoveSteps)
+        .is_err());
+}
+
+#[test]
+fn remove_handle_dropping_the_paths() {
+    let mut graph = removal_graph();
+    graph.remove_handle(6).unwrap();
+    assert_eq!(path_ids(&graph, b"a"), Some(vec![1, 2, 3, 4, 5]));
+    assert!(!graph.has_path(b"b"));
+    assert_eq!(all_occurrences(&graph), expected_occurrences(&graph));
+
+    graph.remove_handle(1).unwrap();
+    assert!(!graph.has_path(b"a"));
+    assert_eq!(graph.path_count(), 0);
+    assert!(graph.graph.values().all(|n| n.occurrences.is_empty()));
+    // the name can be used again
+    let path = graph.create_path_handle(b"a", false);
+    graph.append_step(&path, Handle::pack(2, false)).unwrap();
+    assert_eq!(path_ids(&graph, b"a"), Some(vec![2]));
+}
+
+#[test]
+fn remove_handle_clears_the_edges_of_both_sides() {
+    use gfahandlegraph::handlegraph::AllEdges;
+
+    let fwd = |id: u64| Handle::pack(id, false);
+    let mut graph = HashGraph::new();
+    for id in 1..=4 {
+        graph.create_handle(id, b"ACGT").unwrap();
+    }
+    // 1- -> 2+ is on the left of both nodes, 2+ -> 3- on the right of
+    // both, and 4 loops on itself
+    graph.create_edge(Edge(fwd(1).flip(), fwd(2))).unwrap();
+    graph.create_edge(Edge(fwd(2), fwd(3).flip())).unwrap();
+    graph.create_edge(Edge(fwd(2).flip(), fwd(4))).unwrap();
+    graph.create_edge(Edge(fwd(4), fwd(4))).unwrap();
+    graph.create_edge(Edge(fwd(4).flip(), fwd(4))).unwrap();
+
+    graph.remove_handle(2).unwrap();
+    for id in [1, 3].iter() {
+        let node = graph.get_node(&NodeId::from(*id)).unwrap();
+        assert!(node.left_edges.is_empty() && node.right_edges.is_empty());
+    }
+    assert_eq!(graph.edges().count(), 2);
+    graph.remove_handle(4).unwrap();
+    assert_eq!(graph.edges().count(), 0);
+}
+
+#[test]
+fn remove_many_handles_from_medium_graph() {
+    use gfahandlegraph::hashgraph::PathRepair;
+
+    let graph = read_medium_gfa1();
+    let mut ids: Vec<NodeId> = graph.graph.keys().copied().collect();
+    ids.sort();
+    let ids: Vec<NodeId> = ids.into_iter().step_by(4).take(1000).collect();
+    let steps: usize = graph.paths.values().map(|p| p.nodes.len()).sum();
+
+    let mut one_by_one = graph.clone();
+    let start = Instant::now();
+    for id in ids.iter() {
+        one_by_one
+            .remove_handle_with(*id, PathRepair::RemoveSteps)
+            .unwrap();
+    }
+    println!("remove 1000 nodes one by one: {:?}", start.elapsed());
+
+    let mut at_once = graph.clone();
+    let start = Instant::now();
+    at_once
+        .remove_handles_with(&ids, PathRepair::RemoveSteps)
+        .unwrap();
+    let elapsed = start.elapsed();
+    println!("remove 1000 nodes at once: {:?}", elapsed);
+    assert!(elapsed < time::Duration::milliseconds(100));
+
+    for graph in [&one_by_one, &at_once].iter() {
+        assert_eq!(graph.graph.len(), 4058 - 1000);
+        assert_eq!(graph.paths.len(), 7);
+        assert!(graph.paths.values().map(|p| p.nodes.len()).sum::<usize>() < steps);
+        assert_eq!(all_occurrences(graph), expected_occurrences(graph));
+    }
+    assert_eq!(all_occurrences(&one_by_one), all_occurrences(&at_once));
+    let mut all_steps: Vec<_> = at_once.paths.values().map(|p| &p.nodes).collect();
+    all_steps.sort();
+    let mut expected: Vec<_> = one_by_one.paths.values().map(|p| &p.nodes).collect();
+    expected.sort();
+    assert_eq!(all_steps, expected);
+
+    // the paths are dropped once, and a missing node changes nothing
+    let mut dropped = graph.clone();
+    let missing = vec![ids[0], NodeId::from(u64::MAX - 1)];
+    assert!(dropped
+        .remove_handles_with(&missing, PathRepair::DropPath)
+        .is_err());
+    assert_eq!(dropped.graph.len(), 4058);
+    dropped
+        .remove_handles_with(&ids, PathRepair::DropPath)
+        .unwrap();
+    assert_eq!(dropped.paths.len(), 0);
+    assert!(dropped.graph.values().all(|n| n.occurrences.is_empty()));
+}
+
+#[test]
+fn create_edge_between_missing_nodes() {
+    use gfahandlegraph::handlegraph::{error::GraphError, AllEdges};
+
+    let fwd = |id: u64| Handle::pack(id, false);
+    let mut graph = HashGraph::new();
+    graph.create_handle(1, b"ACGT").unwrap();
+    let before = graph.clone();
+
+    // a missing left node, and a missing right node
+    let error = graph.create_edge(Edge(fwd(7), fwd(1))).unwrap_err();
+    assert!(matches!(error, GraphError::NodeNotExist(id) if id == "7"));
+    let error = graph.create_edge(Edge(fwd(1).flip(), fwd(8))).unwrap_err();
+    assert!(matches!(error, GraphError::NodeNotExist(id) if id == "8"));
+    let node = graph.get_node(&NodeId::from(1)).unwrap();
+    assert!(node.left_edges.is_empty() && node.right_edges.is_empty());
+    assert_eq!(graph.edges().count(), 0);
+    assert_eq!(graph.generation(), before.generation());
+}
+
+#[test]
+fn create_edge_reports_duplicates() {
+    use gfahandlegraph::handle::Direction;
+    use gfahandlegraph::handlegraph::{AllEdges, HandleNeighbors};
+
+    let fwd = |id: u64| Handle::pack(id, false);
+    let mut graph = HashGraph::new();
+    graph.create_handle(1, b"ACGT").unwrap();
+    graph.create_handle(2, b"ACGT").unwrap();
+
+    assert_eq!(graph.create_edge(Edge(fwd(1), fwd(2))).ok(), Some(true));
+    let generation = graph.generation();
+    assert_eq!(graph.create_edge(Edge(fwd(1), fwd(2))).ok(), Some(false));
+    // the same edge seen from the other strand
+    assert_eq!(
+        graph.create_edge(Edge(fwd(2).flip(), fwd(1).flip())).ok(),
+        Some(false)
+    );
+    assert_eq!(graph.edges().count(), 1);
+    assert_eq!(graph.generation(), generation);
+    assert_eq!((&graph).degree(fwd(2), Direction::Left), 1);
+
+    // a self loop from the end of a node back into its end
+    assert_eq!(
+        graph.create_edge(Edge(fwd(1), fwd(1).flip())).ok(),
+        Some(true)
+    );
+    assert_eq!(
+        graph.create_edge(Edge(fwd(1), fwd(1).flip())).ok(),
+        Some(false)
+    );
+    let node = graph.get_node(&NodeId::from(1)).unwrap();
+    assert_eq!(node.right_edges, vec![fwd(2), fwd(1).flip()]);
+    assert!(node.left_edges.is_empty());
+    assert!((&graph).has_edge(fwd(1), fwd(1).flip()));
+    assert_eq!(graph.edges().count(), 2);
+}
+
+/// The lines of a file with one of the prefixes, sorted as the parsers
+/// may reorder them
+fn sorted_lines(text: &str, prefixes: &[char]) -> Vec<String> {
+    let mut lines: Vec<String> = text
+        .lines()
+        .filter(|l| l.starts_with(prefixes))
+        .map(|l| l.trim_end().to_string())
+        .collect();
+    lines.sort();
+    lines
+}
+
+#[test]
+fn gfa_objects_keep_overlaps_and_positions() {
+    use gfahandlegraph::parser::{GFA2Parser, GFAParser};
+
+    let file = "./tests/gfa1_files/lil.gfa";
+    let gfa = GFAParser::new().parse_file(file).unwrap();
+    let source = std::fs::read_to_string(file).unwrap();
+    assert_eq!(
+        sorted_lines(&gfa.to_string(), &['L', 'P']),
+        sorted_lines(&source, &['L', 'P'])
+    );
+    let x = gfa.paths.iter().find(|p| p.path_name == "x").unwrap();
+    assert_eq!(x.overlaps_iter().count(), 10);
+    assert_eq!(x.overlaps_iter().last(), Some(&b"11M"[..]));
+
+    let file = "./tests/gfa2_files/spec_q7.gfa2";
+    let gfa2 = GFA2Parser::new().parse_file(file).unwrap();
+    let source = std::fs::read_to_string(file).unwrap();
+    assert_eq!(
+        sorted_lines(&gfa2.to_string(), &['E']),
+        sorted_lines(&source, &['E'])
+    );
+    let edge = gfa2.edges.iter().find(|e| e.alignment == "4M").unwrap();
+    assert_eq!(
+        (&edge.beg1, &edge.end1, &edge.beg2, &edge.end2),
+        (&"1".into(), &"5$".into(), &"2".into(), &"6$".into())
+    );
+}
+
+#[test]
+fn hashgraph_writers_emit_edge_overlaps() {
+    use gfahandlegraph::util::to_file::to_gfa;
+
+    let mut graph = overlapping_chain();
+    // the overlap of 1+ -> 2- is at the end of 1 and at the end of 2
+    let edge = Edge(Handle::pack(1, false), Handle::pack(2, true));
+    graph.create_edge(edge).unwrap();
+    graph.set_edge_overlap(edge, 5).unwrap();
+
+    let dir = std::env::temp_dir();
+    let out = |format: &str, name: &str| {
+        let path = dir.join(name).to_str().unwrap().to_string();
+        to_gfa(&graph, format.to_string(), Some(path.clone())).unwrap();
+        std::fs::read_to_string(path).unwrap()
+    };
+    assert_eq!(
+        sorted_lines(&out("GFA", "gfahandlegraph_overlaps.gfa"), &['L']),
+        vec![
+            "L\t1\t+\t2\t+\t3M",
+            "L\t1\t+\t2\t-\t5M",
+            "L\t2\t+\t3\t+\t2M"
+        ]
+    );
+    assert_eq!(
+        sorted_lines(&out("GFA2", "gfahandlegraph_overlaps.gfa2"), &['E']),
+        vec![
+            "E\t*\t1+\t2+\t2\t5$\t0\t3\t3M",
+            "E\t*\t1+\t2-\t0\t5$\t0\t5$\t5M",
+            "E\t*\t2+\t3+\t3\t5$\t0\t2\t2M"
+        ]
+    );
+}
+
+#[test]
+fn segment_tags_survive_a_round_trip() {
+    use gfahandlegraph::parser::{GFAParser, Parser};
+    use gfahandlegraph::util::to_file::to_gfa;
+
+    let file = "./tests/big_files/A-3105.sort.gfa";
+    let source = std::fs::read_to_string(file).unwrap();
+    let graph = Parser::new().parse_file_to_graph(file).unwrap();
+    let path = std::env::temp_dir()
+        .join("gfahandlegraph_tags.gfa")
+        .to_str()
+        .unwrap()
+        .to_string();
+    to_gfa(&graph, "GFA".to_string(), Some(path.clone())).unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+
+    let segments = sorted_lines(&written, &['S']);
+    assert_eq!(segments.len(), 6880);
+    assert!(segments
+        .iter()
+        .all(|l| l.contains("\tDP:i:") && l.contains("\tRC:i:")));
+    assert_eq!(segments, sorted_lines(&source, &['S']));
+
+    // the tags of the GFA objects are written back by their Display
+    let gfa = GFAParser::new().parse_file(file).unwrap();
+    assert_eq!(
+        sorted_lines(&gfa.to_string(), &['S']),
+        sorted_lines(&source, &['S'])
+    );
+}
+
+#[test]
+fn path_tags_survive_a_round_trip() {
+    use gfahandlegraph::parser::Parser;
+    use gfahandlegraph::util::to_file::to_gfa;
+
+    let dir = std::env::temp_dir();
+    let file = dir.join("gfahandlegraph_path_tags.gfa");
+    std::fs::write(
+        &file,
+        "H\tVN:Z:1.0\nS\t1\tA\tLN:i:1\nS\t2\tC\nL\t1\t+\t2\t+\t0M\nP\tx\t1+,2+\t*\tSN:Z:chr1\tSO:i:10\n",
+    )
+    .unwrap();
+    let graph = Parser::new().parse_file_to_graph(&file).unwrap();
+    let out = dir
+        .join("gfahandlegraph_path_tags_out.gfa")
+        .to_str()
+        .unwrap()
+        .to_string();
+    to_gfa(&graph, "GFA".to_string(), Some(out.clone())).unwrap();
+    let written = std::fs::read_to_string(&out).unwrap();
+    assert_eq!(
+        sorted_lines(&written, &['S', 'P']),
+        vec![
+            "P\tx\t1+,2+\t*\tSN:Z:chr1\tSO:i:10",
+            "S\t1\tA\tLN:i:1",
+            "S\t2\tC"
+        ]
+    );
+
+    to_gfa(&graph, "GFA2".to_string(), Some(out.clone())).unwrap();
+    let written = std::fs::read_to_string(&out).unwrap();
+    assert_eq!(
+        sorted_lines(&written, &['S', 'O']),
+        vec![
+            "O\tx\t1+ 2+\tSN:Z:chr1\tSO:i:10",
+            "S\t1\t1\tA\tLN:i:1",
+            "S\t2\t1\tC"
+        ]
+    );
+}
+
+#[test]
+fn parser_drops_the_tags_if_asked() {
+    use gfahandlegraph::handlegraph::AllHandles;
+    use gfahandlegraph::parser::{parser_gfa1::ParserBuilder, GFAParser, Parser};
+
+    let file = "./tests/big_files/A-3105.sort.gfa";
+    let graph = Parser::new()
+        .keep_tags(false)
+        .parse_file_to_graph(file)
+        .unwrap();
+    assert!(graph
+        .handles()
+        .all(|h| graph.get_node(&h.id()).unwrap().optional.is_empty()));
+
+    let mut builder = ParserBuilder::all();
+    builder.keep_tags(false);
+    let gfa = builder.build().parse_file(file).unwrap();
+    assert!(gfa.segments.iter().all(|s| s.optional.is_empty()));
+    let gfa = GFAParser::new().parse_file(file).unwrap();
+    assert!(gfa.segments.iter().all(|s| s.optional.len() == 2));
+}
+
+#[test]
+fn segment_names_are_kept_and_written_back() {
+    use bstr::ByteSlice;
+    use gfahandlegraph::handlegraph::AllHandles;
+    use gfahandlegraph::util::to_file::to_gfa;
+
+    let dir = std::env::temp_dir();
+    let file = dir.join("gfahandlegraph_names.gfa");
+    std::fs::write(
+        &file,
+        "H\tVN:Z:1.0\nS\ts1\tACG\nS\tcontig_12\tTT\nS\t3\tA\n\
+         L\ts1\t+\tcontig_12\t-\t0M\nL\tcontig_12\t-\t3\t+\t0M\n\
+         P\tp1\ts1+,contig_12-,3+\t*\n",
+    )
+    .unwrap();
+    let graph = parse_file_to_graph(&file).unwrap();
+    assert_eq!(graph.node_count(), 3);
+
+    for name in [&b"s1"[..], b"contig_12"].iter() {
+        let id = graph.node_id_of_name(name).unwrap();
+        assert!(graph.has_node(id));
+        assert_eq!(graph.node_name(id), Some(name.as_bstr()));
+    }
+    // a number is the id itself, and it has no name
+    assert_eq!(graph.node_id_of_name(b"3"), Some(NodeId::from(3)));
+    assert_eq!(graph.node_name(NodeId::from(3)), None);
+    assert_eq!(graph.node_id_of_name(b"contig_13"), None);
+    let contig = graph.node_id_of_name(b"contig_12").unwrap();
+    assert_eq!(graph.node_id_of_name(contig.to_string().as_bytes()), None);
+
+    let out = dir
+        .join("gfahandlegraph_names_out.gfa")
+        .to_str()
+        .unwrap()
+        .to_string();
+    to_gfa(&graph, "GFA".to_string(), Some(out.clone())).unwrap();
+    let written = std::fs::read_to_string(&out).unwrap();
+    assert_eq!(
+        sorted_lines(&written, &['S', 'L', 'P']),
+        vec![
+            "L\t3\t-\tcontig_12\t+\t0M",
+            "L\ts1\t+\tcontig_12\t-\t0M",
+            "P\tp1\ts1+,contig_12-,3+\t*",
+            "S\t3\tA",
+            "S\tcontig_12\tTT",
+            "S\ts1\tACG"
+        ]
+    );
+    // the written file has the same ids when it's parsed again
+    let again = parse_file_to_graph(&out).unwrap();
+    assert_eq!(sorted_edges(&again), sorted_edges(&graph));
+
+    to_gfa(&graph, "GFA2".to_string(), Some(out.clone())).unwrap();
+    let written = std::fs::read_to_string(&out).unwrap();
+    assert_eq!(
+        sorted_lines(&written, &['S', 'O']),
+        vec![
+            "O\tp1\ts1+ contig_12- 3+",
+            "S\t3\t1\tA",
+            "S\tcontig_12\t2\tTT",
+            "S\ts1\t3\tACG"
+        ]
+    );
+    assert_eq!(sorted_lines(&written, &['E']).len(), 2);
+    assert!(written.contains("\t3-\tcontig_12+\t"));
+}
+
+#[test]
+fn long_names_resolve_and_collisions_are_reported() {
+    use bstr::ByteSlice;
+    use gfahandlegraph::gfa::segment_id::hash_name;
+    use gfahandlegraph::handlegraph::{AllEdges, AllHandles, GraphError, HandleNeighbors};
+    use gfahandlegraph::hashgraph::graph::FileType;
+    use gfahandlegraph::parser::{GFA2Parser, GFAParser, ParseError};
+
+    let dir = std::env::temp_dir();
+    let file = dir.join("gfahandlegraph_long_names.gfa");
+    std::fs::write(
+        &file,
+        "H\tVN:Z:1.0\nS\tfirstscaffold\tACG\nS\tsecondscaffold\tTT\n\
+         L\tfirstscaffold\t+\tsecondscaffold\t-\t0M\n\
+         P\tp1\tfirstscaffold+,secondscaffold-\t*\n",
+    )
+    .unwrap();
+    let file2 = dir.join("gfahandlegraph_long_names.gfa2");
+    std::fs::write(
+        &file2,
+        "H\tVN:Z:2.0\nS\tfirstscaffold\t3\tACG\nS\tsecondscaffold\t2\tTT\n\
+         E\t*\tfirstscaffold+\tsecondscaffold-\t0\t3$\t0\t2$\t*\n\
+         O\tp1\tfirstscaffold+ secondscaffold-\n",
+    )
+    .unwrap();
+    let first = NodeId::from(hash_name(b"firstscaffold"));
+    let second = NodeId::from(hash_name(b"secondscaffold"));
+    for file in [&file, &file2].iter() {
+        let graph = parse_file_to_graph(file).unwrap();
+        assert_eq!(graph.node_count(), 2);
+        assert_eq!(graph.node_name(first), Some(b"firstscaffold".as_bstr()));
+        // the links and the path steps reference the same nodes
+        let edge = Edge(Handle::pack(first, false), Handle::pack(second, true));
+        assert_eq!(graph.edges().count(), 1);
+        assert!((&graph).has_edge(edge.0, edge.1));
+        let (_, steps) = &path_steps(&graph)[0];
+        assert_eq!(steps, &vec![edge.0, edge.1]);
+    }
+
+    // a number that is the id of a name isn't merged with its segment
+    let collision = format!(
+        "H\tVN:Z:1.0\nS\tfirstscaffold\tACG\nS\t{}\tTT\n",
+        hash_name(b"firstscaffold")
+    );
+    let file = dir.join("gfahandlegraph_name_collision.gfa");
+    std::fs::write(&file, collision).unwrap();
+    let gfa = GFAParser::new().parse_file(&file).unwrap();
+    let errors = HashGraph::new().create_graph_with_errors(FileType::GFA(gfa));
+    match errors.as_slice() {
+        [GraphError::NameCollision(name, other)] => {
+            assert_eq!(name, &first.to_string());
+            assert_eq!(other, "firstscaffold");
+        }
+        other => panic!("expected a name collision, got {:?}", other),
+    }
+    assert!(matches!(
+        parse_file_to_graph(&file),
+        Err(ParseError::ConversionGFAToGraph(_))
+    ));
+
+    let collision = format!(
+        "H\tVN:Z:2.0\nS\t{}\t2\tTT\nS\tfirstscaffold\t3\tACG\n",
+        hash_name(b"firstscaffold")
+    );
+    let file = dir.join("gfahandlegraph_name_collision.gfa2");
+    std::fs::write(&file, collision).unwrap();
+    let gfa2 = GFA2Parser::new().parse_file(&file).unwrap();
+    let errors = HashGraph::new().create_graph_with_errors(FileType::GFA2(gfa2));
+    assert!(matches!(
+        errors.as_slice(),
+        [GraphError::NameCollision(name, _)] if name == "firstscaffold"
+    ));
+}
+
+#[test]
+fn gfa2_edges_keep_ids_ending_in_43_and_45() {
+    use gfahandlegraph::handlegraph::HandleNeighbors;
+    use gfahandlegraph::parser::GFA2Parser;
+    use gfahandlegraph::util::to_file::to_gfa;
+
+    let dir = std::env::temp_dir();
+    let file = dir.join("gfahandlegraph_edge_ids.gfa2");
+    std::fs::write(
+        &file,
+        "H\tVN:Z:2.0\nS\t43\t1\tA\nS\t45\t1\tC\nS\t12345\t1\tG\nS\t1243\t1\tT\n\
+         E\t*\t12345+\t43-\t0\t1$\t0\t1$\t*\n\
+         E\t*\t1243-\t45+\t0\t1$\t0\t1$\t*\n",
+    )
+    .unwrap();
+
+    let gfa2 = GFA2Parser::new().parse_file(&file).unwrap();
+    assert_eq!(
+        gfa2.edges[0].references(),
+        ((12345, Orientation::Forward), (43, Orientation::Backward))
+    );
+    assert_eq!(
+        gfa2.edges[1].references(),
+        ((1243, Orientation::Backward), (45, Orientation::Forward))
+    );
+    let shown = gfa2.to_string();
+    assert!(shown.contains("E\t*\t12345+\t43-\t"), "{}", shown);
+    assert!(shown.contains("E\t*\t1243-\t45+\t"), "{}", shown);
+
+    let graph = parse_file_to_graph(&file).unwrap();
+    assert!(graph.has_edge(Handle::pack(12345, false), Handle::pack(43, true)));
+    assert!(graph.has_edge(Handle::pack(1243, true), Handle::pack(45, false)));
+
+    let out = dir
+        .join("gfahandlegraph_edge_ids_out.gfa2")
+        .to_str()
+        .unwrap()
+        .to_string();
+    to_gfa(&graph, "GFA2".to_string(), Some(out.clone())).unwrap();
+    let reread = parse_file_to_graph(&out).unwrap();
+    assert_eq!(sorted_edges(&reread), sorted_edges(&graph));
+}
+
+#[test]
+fn gfa_objects_convert_to_gfa2_and_back() {
+    use gfahandlegraph::hashgraph::graph::FileType;
+    use gfahandlegraph::parser::GFAParser;
+    use gfahandlegraph::util::{gfa2_to_gfa, gfa_to_gfa2};
+
+    let gfa = GFAParser::new()
+        .parse_file("./tests/gfa1_files/lil.gfa")
+        .unwrap();
+    let gfa2 = gfa_to_gfa2(&gfa);
+    assert_eq!(gfa2.headers[0].version, Some("2.0".into()));
+    assert_eq!(gfa2.segments.len(), 15);
+    assert_eq!(
+        gfa2.segments[8].to_string(),
+        "S\t9\t19\tAAATTTTCTGGAGTTCTAT"
+    );
+    assert_eq!(gfa2.edges.len(), gfa.links.len());
+    assert!(gfa2.edges[0]
+        .to_string()
+        .starts_with("E\t*\t1+\t2+\t8$\t8$\t0\t0\t0M"));
+    assert_eq!(gfa2.groups_o.len(), 3);
+
+    let back = gfa2_to_gfa(&gfa2).unwrap();
+    assert_eq!(back.headers, gfa.headers);
+    assert_eq!(back.segments, gfa.segments);
+    assert_eq!(back.links, gfa.links);
+    let paths = |gfa: &gfahandlegraph::gfa::GFA| -> Vec<_> {
+        gfa.paths
+            .iter()
+            .map(|p| {
+                (
+                    p.path_name.clone(),
+                    p.segment_names.clone(),
+                    p.optional.clone(),
+                )
+            })
+            .collect()
+    };
+    assert_eq!(paths(&back), paths(&gfa));
+
+    // and so do the graphs built from them
+    let graph = HashGraph::new().create_graph(FileType::GFA(gfa)).unwrap();
+    let converted = HashGraph::new().create_graph(FileType::GFA2(gfa2)).unwrap();
+    assert_eq!(sorted_edges(&converted), sorted_edges(&graph));
+    assert_eq!(path_steps(&converted), path_steps(&graph));
+}
+
+#[test]
+fn gfa2_objects_convert_to_gfa_with_warnings() {
+    use gfahandlegraph::parser::GFA2Parser;
+    use gfahandlegraph::util::{gfa2_to_gfa, gfa2_to_gfa_with_warnings, ConversionError};
+
+    // the edges of the specification overlap the ends of their segments
+    let gfa2 = GFA2Parser::new()
+        .parse_file("./tests/gfa2_files/spec_q7.gfa2")
+        .unwrap();
+    let gfa = gfa2_to_gfa(&gfa2).unwrap();
+    let links: Vec<String> = gfa.links.iter().map(|l| l.to_string()).collect();
+    assert_eq!(
+        links,
+        vec![
+            "L\t11\t+\t12\t-\t4M",
+            "L\t12\t-\t13\t+\t5M",
+            "L\t11\t+\t13\t+\t3M"
+        ]
+    );
+    assert_eq!(gfa.paths[0].segment_names, "11+,12-,13+");
+
+    // a containment, a gap and an unnamed group can't be converted
+    let dir = std::env::temp_dir();
+    let file = dir.join("gfahandlegraph_conversion.gfa2");
+    std::fs::write(
+        &file,
+        "H\tVN:Z:2.0\nS\t1\t8\tACGTACGT\nS\t2\t4\tGTAC\nS\t3\t2\tTT\n\
+         E\t*\t1+\t2+\t2\t6\t0\t4$\t4M\n\
+         E\t*\t1+\t3+\t8$\t8$\t0\t0\t*\n\
+         G\t*\t1+\t3+\t10\t*\n\
+         O\t*\t1+ 3+\n",
+    )
+    .unwrap();
+    let gfa2 = GFA2Parser::new().parse_file(&file).unwrap();
+    assert_eq!(
+        gfa2_to_gfa(&gfa2),
+        Err(ConversionError::UnsupportedRecord("G".to_string()))
+    );
+    let (gfa, warnings) = gfa2_to_gfa_with_warnings(&gfa2);
+    assert_eq!(
+        warnings,
+        vec![
+            ConversionError::UnsupportedRecord("G".to_string()),
+            ConversionError::NotDovetail("1+,2+".to_string()),
+            ConversionError::UnnamedGroup(0),
+        ]
+    );
+    assert_eq!(warnings[1].code(), "C002");
+    let links: Vec<String> = gfa.links.iter().map(|l| l.to_string()).collect();
+    assert_eq!(links, vec!["L\t1\t+\t3\t+\t*"]);
+    assert!(gfa.paths.is_empty());
+}
+
+#[test]
+fn gfa2_display_keeps_fragments_gaps_and_u_groups() {
+    use gfahandlegraph::parser::GFA2Parser;
+
+    let dir = std::env::temp_dir();
+    let file = dir.join("gfahandlegraph_all_records.gfa2");
+    let text = "H\tVN:Z:2.0\n\
+                S\t1\t4\tACGT\n\
+                S\t2\t4\tTTGA\tRC:i:7\n\
+                F\t1\tread1-\t0\t4$\t10\t14\t4M\tID:Z:f1\n\
+                E\t*\t1+\t2+\t2\t4$\t0\t2\t2M\n\
+                G\tg1\t1-\t2+\t-20\t*\n\
+                G\t*\t2+\t1+\t100\t12\n\
+                O\tP1\t1+ 2+\n\
+                U\tSG1\t1 2 P1\tCO:Z:set\n";
+    std::fs::write(&file, text).unwrap();
+
+    let gfa2 = GFA2Parser::new().parse_file(&file).unwrap();
+    assert_eq!(gfa2.fragments.len(), 1);
+    assert_eq!(gfa2.gaps.len(), 2);
+    assert_eq!(gfa2.groups_u.len(), 1);
+    assert_eq!(gfa2.fragments[0].external, "read1");
+    assert_eq!(gfa2.gaps[0].sid1_orient, Orientation::Backward);
+
+    let prefixes = &['H', 'S', 'F', 'E', 'G', 'O', 'U'];
+    let shown = gfa2.to_string();
+    assert_eq!(sorted_lines(&shown, prefixes), sorted_lines(text, prefixes));
+
+    // and the lines written are read back the same
+    std::fs::write(&file, &shown).unwrap();
+    let again = GFA2Parser::new().parse_file(&file).unwrap();
+    assert_eq!(again, gfa2);
+    std::fs::remove_file(&file).unwrap();
+}
+
+#[test]
+fn graph_counts_follow_random_changes() {
+    use gfahandlegraph::handlegraph::{AllEdges, AllHandles, HandleSequences};
+
+    let mut graph = parse_file_to_graph("./tests/big_files/test.gfa").unwrap();
+    let check = |graph: &HashGraph| {
+        assert_eq!(graph.node_count(), graph.handles().count());
+        assert_eq!(graph.edge_count(), graph.edges().count());
+        assert_eq!(graph.path_count(), graph.paths.len());
+    };
+    check(&graph);
+
+    // the same changes on every run
+    let mut state: u64 = 42;
+    let mut next = |bound: usize| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) as usize % bound
+    };
+    for round in 0..60 {
+        let mut ids: Vec<NodeId> = graph.handles().map(|h| h.id()).collect();
+        ids.sort();
+        let handle = |ix: usize, reverse: bool| Handle::pack(ids[ix], reverse);
+        match round % 5 {
+            0 => {
+                let edge = Edge(
+                    handle(next(ids.len()), next(2) == 1),
+                    handle(next(ids.len()), next(2) == 1),
+                );
+                graph.create_edge(edge).unwrap();
+            }
+            1 => {
+                let mut edges: Vec<Edge> = graph.edges().collect();
+                edges.sort();
+                if !edges.is_empty() {
+                    graph.remove_edge(edges[next(edges.len())]).unwrap();
+                }
+            }
+            2 => {
+                let h = handle(next(ids.len()), next(2) == 1);
+                if graph.node_len(h) > 1 {
+                    graph.divide_handle(h, vec![1]).unwrap();
+                }
+            }
+            3 => {
+                graph.remove_handle(ids[next(ids.len())]).unwrap();
+            }
+            _ => {
+                let mut edges: Vec<Edge> = graph.edges().collect();
+                edges.sort();
+                if !edges.is_empty() {
+                    let edge = edges[next(edges.len())];
+                    let right = handle(next(ids.len()), next(2) == 1);
+                    graph.modify_edge(edge, None, Some(right)).unwrap();
+                }
+            }
+        }
+        check(&graph);
+    }
+
+    graph.clear_graph();
+    check(&graph);
+    assert_eq!(graph.edge_count(), 0);
+}
+
+#[test]
+fn bincode_round_trip_keeps_the_graph() {
+    use gfahandlegraph::handlegraph::{AllEdges, AllHandles, HandleSequences};
+    use gfahandlegraph::util::to_file::to_gfa;
+
+    let graph = parse_file_to_graph("./tests/big_files/test.gfa").unwrap();
+    let dir = std::env::temp_dir();
+    let file = dir.join("gfahandlegraph_round_trip.bin");
+
+    let start = std::time::Instant::now();
+    graph.save_bincode(&file).unwrap();
+    let loaded = HashGraph::load_bincode(&file).unwrap();
+    let bincode_time = start.elapsed();
+
+    assert_eq!(loaded.node_count(), graph.node_count());
+    assert_eq!(loaded.edge_count(), graph.edge_count());
+    assert_eq!(loaded.path_count(), graph.path_count());
+    assert_eq!((loaded.min_id, loaded.max_id), (graph.min_id, graph.max_id));
+    assert_eq!(sorted_edges(&loaded), sorted_edges(&graph));
+    let mut ids: Vec<NodeId> = graph.handles().map(|h| h.id()).collect();
+    ids.sort();
+    for id in ids.iter().step_by(97) {
+        let h = Handle::pack(*id, false);
+        assert_eq!(loaded.sequence(h), graph.sequence(h));
+        assert_eq!(
+            loaded.get_node(id).unwrap().occurrences,
+            graph.get_node(id).unwrap().occurrences
+        );
+    }
+    for (name, path_id) in graph.path_id.iter() {
+        let steps = &graph.paths[path_id].nodes;
+        assert_eq!(&loaded.paths[&loaded.path_id[name]].nodes, steps);
+    }
+
+    // the same round trip through the GFA text
+    let text = dir.join("gfahandlegraph_round_trip.gfa");
+    let start = std::time::Instant::now();
+    to_gfa(
+        &graph,
+        "GFA".to_string(),
+        Some(text.to_str().unwrap().to_string()),
+    )
+    .unwrap();
+    let parsed = parse_file_to_graph(&text).unwrap();
+    let gfa_time = start.elapsed();
+    assert_eq!(parsed.node_count(), loaded.node_count());
+    assert!(
+        bincode_time < gfa_time,
+        "bincode {:?}, gfa {:?}",
+        bincode_time,
+        gfa_time
+    );
+
+    // a file that isn't a graph is refused
+    std::fs::write(&file, b"not a graph").unwrap();
+    assert!(HashGraph::load_bincode(&file).is_err());
+    std::fs::remove_file(&file).unwrap();
+    std::fs::remove_file(&text).unwrap();
+}
+
+#[test]
+fn steps_with_positions_follow_the_prefix_sums() {
+    use gfahandlegraph::handlegraph::HandleSequences;
+
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    // a walk with reverse steps, that comes back to its first node
+    let circle = graph.create_path_handle(b"circle", true);
+    for h in [(1, false), (3, true), (9, true), (1, false)].iter() {
+        graph.append_step(&circle, Handle::pack(h.0, h.1)).unwrap();
+    }
+
+    for path_id in graph.paths.keys() {
+        let nodes = &graph.paths[path_id].nodes;
+        let mut prefix = 0;
+        let mut found = 0;
+        for (ix, (step, handle, position)) in graph.steps_with_positions(path_id).enumerate() {
+            assert_eq!(handle, nodes[ix]);
+            assert_eq!(graph.handle_of_step(&step), Some(handle));
+            assert_eq!(position, prefix, "step {} of {}", ix, path_id);
+            assert_eq!(graph.position_of_step(&step), Some(position));
+            prefix += graph.node_len(handle);
+            found += 1;
+        }
+        assert_eq!(found, nodes.len());
+        assert_eq!(graph.path_bases_len(path_id), Some(prefix));
+    }
+    // the second visit of node 1 goes on from the end of the lap
+    let last = graph.steps_with_positions(&circle).last().unwrap();
+    assert_eq!(last.2, 8 + 1 + 19);
+
+    // a long path is walked once
+    let mut long = HashGraph::new();
+    long.create_handle(1, b"ACGT").unwrap();
+    long.create_edge(Edge(Handle::pack(1, false), Handle::pack(1, false)))
+        .unwrap();
+    let path = long.create_path_handle(b"long", false);
+    for ix in 0..100_000 {
+        long.append_step(&path, Handle::pack(1, ix % 2 == 1))
+            .unwrap();
+    }
+    let start = std::time::Instant::now();
+    let positions: Vec<usize> = long.steps_with_positions(&path).map(|s| s.2).collect();
+    assert!(start.elapsed().as_secs() < 2, "{:?}", start.elapsed());
+    assert_eq!(positions.len(), 100_000);
+    assert!(positions.iter().enumerate().all(|(ix, p)| *p == ix * 4));
+}
+
+#[test]
+fn remove_and_modify_step_keep_the_occurrences() {
+    use gfahandlegraph::handlegraph::GraphError;
+    use gfahandlegraph::hashgraph::PathStep;
+
+    // x: 1+,3+,5+,6+,8+,9+,11+,12+,14+,15+
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let x = graph.name_to_path_handle(b"x").unwrap();
+    let in_x = |graph: &HashGraph, id: u64| -> Vec<PathStep> {
+        graph
+            .occurrences(Handle::pack(id, false))
+            .filter(|s| s.path_id() == x)
+            .collect()
+    };
+    let at = |graph: &HashGraph, id: u64| {
+        let step = in_x(graph, id).pop().unwrap();
+        (step.index(), graph.position_of_step(&step))
+    };
+    assert_eq!(at(&graph, 9), (Some(5), Some(14)));
+
+    graph.remove_step(b"x", 5u64).unwrap();
+    assert!(in_x(&graph, 5).is_empty());
+    assert_eq!(at(&graph, 6), (Some(2), Some(9)));
+    assert_eq!(at(&graph, 9), (Some(4), Some(13)));
+    assert_eq!(at(&graph, 15), (Some(8), Some(38)));
+
+    // 8+ becomes 7- (both 1 base long), and 8 leaves the path
+    graph
+        .modify_step(b"x", 8u64, Handle::pack(7, true))
+        .unwrap();
+    assert!(in_x(&graph, 8).is_empty());
+    assert_eq!(at(&graph, 7), (Some(3), Some(12)));
+    assert_eq!(graph.paths[&x].nodes[3], Handle::pack(7, true));
+
+    graph.remove_step(b"x", 11u64).unwrap();
+    assert_eq!(at(&graph, 12), (Some(5), Some(32)));
+    assert_consistent(&graph);
+    for (ix, handle) in graph.paths[&x].nodes.iter().enumerate() {
+        let step = in_x(&graph, handle.id().into()).pop().unwrap();
+        assert_eq!(step.index(), Some(ix));
+    }
+
+    // a node that isn't on the path is an error, and changes nothing
+    let before = graph.paths[&x].nodes.clone();
+    assert!(matches!(
+        graph.remove_step(b"x", 5u64),
+        Err(GraphError::NodeNotExist(_))
+    ));
+    assert!(matches!(
+        graph.modify_step(b"x", 2u64, Handle::pack(4, false)),
+        Err(GraphError::NodeNotExist(_))
+    ));
+    assert!(matches!(
+        graph.modify_step(b"x", 1u64, Handle::pack(99, false)),
+        Err(GraphError::NodeNotExist(_))
+    ));
+    assert!(matches!(
+        graph.remove_step(b"missing", 1u64),
+        Err(GraphError::PathNotExist(_))
+    ));
+    assert_eq!(graph.paths[&x].nodes, before);
+}
+
+#[test]
+fn check_consistency_finds_each_corruption() {
+    use gfahandlegraph::hashgraph::ConsistencyError;
+
+    let lil = || parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    assert_eq!(lil().check_consistency(), Ok(()));
+
+    // the edge 1+ -> 2+ kept only by the node 1
+    let mut graph = lil();
+    let node = graph.get_node_mut(&NodeId::from(2)).unwrap();
+    node.left_edges.retain(|h| *h != Handle::pack(1, true));
+    assert_eq!(
+        graph.check_consistency(),
+        Err(vec![ConsistencyError::UnmirroredEdge {
+            node: NodeId::from(1),
+            edge: Edge(Handle::pack(1, false), Handle::pack(2, false)),
+        }])
+    );
+    // and an edge to a node that doesn't exist, from the left
+    let node = graph.get_node_mut(&NodeId::from(15)).unwrap();
+    node.left_edges.push(Handle::pack(99, false));
+    let errors = graph.check_consistency().unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        errors[1],
+        ConsistencyError::UnmirroredEdge {
+            node: NodeId::from(15),
+            edge: Edge(Handle::pack(15, true), Handle::pack(99, false)),
+        }
+    );
+
+    let mut graph = lil();
+    let x = graph.path_id[b"x".as_ref()];
+    graph
+        .paths
+        .get_mut(&x)
+        .unwrap()
+        .nodes
+        .push_back(Handle::pack(99, false));
+    match graph.check_consistency().unwrap_err().as_slice() {
+        [ConsistencyError::MissingStepNode {
+            path_id,
+            path_name,
+            index,
+            node,
+        }] => {
+            assert_eq!(*path_id, x);
+            assert_eq!(path_name.as_slice(), b"x");
+            assert_eq!((*index, *node), (10, NodeId::from(99)));
+        }
+        errors => panic!("{:?}", errors),
+    }
+
+    // the node 3 is at the step 1 of x, not at the step 2
+    let mut graph = lil();
+    let node = graph.get_node_mut(&NodeId::from(3)).unwrap();
+    node.occurrences.insert(x, vec![2]);
+    node.occurrences.insert(42, vec![0]);
+    assert_eq!(
+        graph.check_consistency(),
+        Err(vec![
+            ConsistencyError::DanglingOccurrence {
+                node: NodeId::from(3),
+                path_id: x,
+                index: 2,
+            },
+            ConsistencyError::DanglingOccurrence {
+                node: NodeId::from(3),
+                path_id: 42,
+                index: 0,
+            },
+        ])
+    );
+
+    let mut graph = lil();
+    graph.max_id = NodeId::from(20);
+    match graph.check_consistency().unwrap_err().as_slice() {
+        [ConsistencyError::IdBounds(mismatch)] => {
+            assert_eq!(mismatch.max_id, NodeId::from(20));
+            assert_eq!(mismatch.expected, Some((NodeId::from(1), NodeId::from(15))));
+        }
+        errors => panic!("{:?}", errors),
+    }
+}
+
+#[test]
+fn paths_to_fasta_writes_the_sequences_of_the_paths() {
+    use bstr::ByteSlice;
+    use gfahandlegraph::util::fasta::paths_to_fasta;
+
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    // 15- 14- 12-, the reverse complements of CCAACTCTCTG, T and ATAT
+    let reverse = graph.create_path_handle(b"r\xff", false);
+    for h in [(15, true), (14, true), (12, true)].iter() {
+        graph.append_step(&reverse, Handle::pack(h.0, h.1)).unwrap();
+    }
+    let circular = graph.create_path_handle(b"c", true);
+    graph
+        .append_step(&circular, Handle::pack(1, false))
+        .unwrap();
+    graph
+        .append_step(&circular, Handle::pack(2, false))
+        .unwrap();
+    let empty = graph.create_path_handle(b"e", false);
+
+    let x = graph.path_id[b"x".as_ref()];
+    let x_sequence = b"CAAATAAGGCTTGGAAATTTTCTGGAGTTCTATTATATTCCAACTCTCTG";
+    assert_eq!(graph.path_sequence(&x), Some(x_sequence.to_vec()));
+    assert_eq!(
+        graph.path_sequence(&reverse),
+        Some(b"CAGAGAGTTGGAATAT".to_vec())
+    );
+    assert_eq!(graph.path_sequence(&circular), Some(b"CAAATAAGA".to_vec()));
+    assert_eq!(graph.path_sequence(&empty), Some(vec![]));
+    assert_eq!(graph.path_sequence(&42), None);
+
+    let mut fasta: Vec<u8> = vec![];
+    paths_to_fasta(&graph, &mut fasta, 8).unwrap();
+    let mut expected: Vec<u8> = vec![];
+    let mut path_ids: Vec<i64> = graph.paths.keys().copied().collect();
+    path_ids.sort_unstable();
+    for path_id in path_ids {
+        expected.push(b'>');
+        expected.extend_from_slice(&graph.paths[&path_id].name);
+        expected.push(b'\n');
+        for line in graph.path_sequence(&path_id).unwrap().chunks(8) {
+            expected.extend_from_slice(line);
+            expected.push(b'\n');
+        }
+    }
+    assert_eq!(fasta.as_bstr(), expected.as_bstr());
+
+    let fasta = fasta.as_bstr();
+    assert!(fasta.contains_str(b">r\xff\nCAGAGAGT\nTGGAATAT\n"));
+    assert!(fasta.contains_str(b">c\nCAAATAAG\nA\n"));
+    // the empty path is the last one
+    assert!(fasta.ends_with(b">e\n"));
+}
+
+#[test]
+fn subgraph_from_nodes_splits_the_paths() {
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let circular = graph.create_path_handle(b"c", true);
+    for id in [1, 2, 3].iter() {
+        graph
+            .append_step(&circular, Handle::pack(*id, false))
+            .unwrap();
+    }
+    let ids = [1, 3, 5, 6, 9, 11, 12];
+    let sub = graph.subgraph_from_nodes(ids.iter().map(|id| NodeId::from(*id)));
+    assert_consistent(&sub);
+    assert_eq!(sub.id_bounds(), Some((NodeId::from(1), NodeId::from(12))));
+    let fwd = |l: u64, r: u64| Edge(Handle::pack(l, false), Handle::pack(r, false));
+    assert_eq!(
+        sorted_edges(&sub),
+        vec![
+            fwd(1, 3),
+            fwd(3, 5),
+            fwd(5, 5),
+            fwd(5, 6),
+            fwd(9, 11),
+            fwd(11, 12)
+        ]
+    );
+
+    let steps = |name: &[u8]| -> Vec<u64> {
+        let path = &sub.paths[&sub.path_id[name]];
+        path.nodes.iter().map(|h| u64::from(h.id())).collect()
+    };
+    // x is 1 3 5 6 8 9 11 12 14 15
+    assert_eq!(steps(b"x_0"), vec![1, 3, 5, 6]);
+    assert_eq!(steps(b"x_1"), vec![9, 11, 12]);
+    assert!(!sub.has_path(b"x") && !sub.has_path(b"x_2"));
+    // the run of the circular path goes back to its first step
+    assert_eq!(steps(b"c"), vec![3, 1]);
+    assert!(!sub.paths[&sub.path_id[b"c".as_ref()]].is_circular);
+}
+
+#[test]
+fn neighborhood_of_a_node_of_the_medium_graph() {
+    use fnv::{FnvHashMap, FnvHashSet};
+    use gfahandlegraph::handlegraph::{AllEdges, HandleSequences};
+
+    let graph = parse_file_to_graph("./tests/big_files/test.gfa").unwrap();
+    let mut path_ids: Vec<i64> = graph.paths.keys().copied().collect();
+    path_ids.sort_unstable();
+    let path = &graph.paths[&path_ids[0]];
+    let start = path.nodes[path.nodes.len() / 2].id();
+
+    // the nodes at most 2 edges away, without following the handles
+    let mut near: FnvHashMap<NodeId, Vec<NodeId>> = FnvHashMap::default();
+    for Edge(l, r) in graph.edges() {
+        near.entry(l.id()).or_default().push(r.id());
+        near.entry(r.id()).or_default().push(l.id());
+    }
+    let mut expected: FnvHashSet<NodeId> = std::iter::once(start).collect();
+    for _ in 0..2 {
+        let reached: Vec<NodeId> = expected
+            .iter()
+            .flat_map(|id| near[id].iter().copied())
+            .collect();
+        expected.extend(reached);
+    }
+
+    let sub = graph.neighborhood(start, 2);
+    assert_consistent(&sub);
+    let nodes: FnvHashSet<NodeId> = sub.graph.keys().copied().collect();
+    assert_eq!(nodes, expected);
+    let edges = graph
+        .edges()
+        .filter(|Edge(l, r)| expected.contains(&l.id()) && expected.contains(&r.id()))
+        .count();
+    assert_eq!(sub.edge_count(), edges);
+    assert_eq!((nodes.len(), edges), (8, 11));
+    assert_eq!(
+        sub.id_bounds(),
+        Some((*nodes.iter().min().unwrap(), *nodes.iter().max().unwrap()))
+    );
+    for id in nodes.iter() {
+        let h = Handle::pack(*id, true);
+        assert_eq!(sub.sequence(h), graph.sequence(h));
+    }
+
+    // the runs of the steps of each path in the neighborhood, the path
+    // of the start is cut at both its ends
+    let mut fragments = 0;
+    for path in graph.paths.values() {
+        let mut runs: Vec<Vec<Handle>> = vec![vec![]];
+        for step in path.nodes.iter() {
+            if expected.contains(&step.id()) {
+                runs.last_mut().unwrap().push(*step);
+            } else if !runs.last().unwrap().is_empty() {
+                runs.push(vec![]);
+            }
+        }
+        runs.retain(|run| !run.is_empty());
+        fragments += runs.len();
+        if let [run] = runs.as_slice() {
+            assert!(run.len() < path.nodes.len());
+            assert_eq!(&sub.paths[&sub.path_id[path.name.as_slice()]].nodes, run);
+        }
+        for (ix, run) in runs.iter().enumerate().filter(|_| runs.len() > 1) {
+            let mut name = path.name.to_vec();
+            name.extend_from_slice(format!("_{}", ix).as_bytes());
+            assert_eq!(&sub.paths[&sub.path_id[&name]].nodes, run);
+        }
+    }
+    let start_path = &sub.paths[&sub.path_id[path.name.as_slice()]];
+    assert!(start_path.nodes.iter().any(|h| h.id() == start));
+    assert_eq!(sub.paths.len(), fragments);
+    assert!(sub.neighborhood(NodeId::from(0), 2).graph.is_empty());
+}
+
+#[test]
+fn parallel_parsing_keeps_the_order_of_the_lines() {
+    use gfahandlegraph::parser::{parser_gfa1, parser_gfa2};
+    use gfahandlegraph::util::ThreadConfig;
+
+    let gfa = |threads| {
+        let mut builder = parser_gfa1::ParserBuilder::all();
+        builder.threads(threads);
+        builder
+            .build()
+            .parse_file("./tests/big_files/test.gfa")
+            .unwrap()
+    };
+    let (first, second) = (gfa(ThreadConfig::new(4)), gfa(ThreadConfig::new(4)));
+    assert!(first == second);
+    assert_eq!(first.to_hash(), second.to_hash());
+    // the same lines of the sequential parser
+    assert_eq!(first.to_hash(), gfa(ThreadConfig::sequential()).to_hash());
+    assert_eq!(first.segments[0].name, 1);
+
+    let gfa2 = |threads| {
+        let mut builder = parser_gfa2::ParserBuilder::all();
+        builder.threads(threads);
+        builder
+            .build()
+            .parse_file("./tests/big_files/test.gfa2")
+            .unwrap()
+    };
+    let (first, second) = (gfa2(ThreadConfig::new(4)), gfa2(ThreadConfig::new(4)));
+    assert!(first == second);
+    assert_eq!(first.to_hash(), second.to_hash());
+    assert_eq!(first.to_hash(), gfa2(ThreadConfig::sequential()).to_hash());
+}
+
+#[test]
+fn gfa2_segment_lengths_follow_the_tolerance() {
+    use gfahandlegraph::gfa::gfa2::ValidationError;
+    use gfahandlegraph::handlegraph::HandleSequences;
+    use gfahandlegraph::hashgraph::graph::FileType;
+    use gfahandlegraph::parser::parser_gfa2::ParserBuilder;
+    use gfahandlegraph::parser::{ParseError, ParseFieldError};
+
+    let builders = || {
+        vec![
+            ParserBuilder::all().ignore_errors(),
+            ParserBuilder::all().ignore_safe_errors(),
+            ParserBuilder::all().pedantic_errors(),
+        ]
+    };
+    for builder in builders() {
+        let gfa2 = builder
+            .build()
+            .parse_file("./tests/gfa2_files/slen_ok.gfa2")
+            .unwrap();
+        assert!(gfa2.validate().is_ok());
+        assert!(gfa2.warnings.is_empty());
+        assert_eq!(gfa2.segments[0].slen, 10);
+    }
+
+    let mismatch = vec![ValidationError::SegmentLength {
+        id: 1,
+        slen: 21,
+        sequence_len: 10,
+    }];
+    let file = "./tests/gfa2_files/slen_mismatch.gfa2";
+    let mut parsed = builders().into_iter().map(|b| b.build().parse_file(file));
+    // the line is kept, silently or with a warning
+    let ignored = parsed.next().unwrap().unwrap();
+    assert!(ignored.warnings.is_empty());
+    assert_eq!(ignored.validate(), Err(mismatch.clone()));
+    let safe = parsed.next().unwrap().unwrap();
+    assert_eq!(safe.warnings, mismatch);
+    assert_eq!(safe.segments.len(), 2);
+    assert_eq!(safe.segments[0].to_string(), "S\t1\t10\tACGTACGTAC");
+    match parsed.next().unwrap() {
+        Err(ParseError::InvalidLineAt(2, ParseFieldError::InvalidField("Length"), line)) => {
+            assert_eq!(line, "S\t1\t21\tACGTACGTAC")
+        }
+        other => panic!("expected the line 2, got {:?}", other.map(|_| ())),
+    }
+
+    // the length of a * segment is its slen
+    let file = "./tests/gfa2_files/slen_star.gfa2";
+    let gfa2 = ParserBuilder::all()
+        .pedantic_errors()
+        .build()
+        .parse_file(file)
+        .unwrap();
+    assert!(gfa2.validate().is_ok());
+    assert_eq!(gfa2.segments[0].length(), 6);
+    assert_eq!(gfa2.segments[0].to_string(), "S\t1\t6\t*");
+    let created = HashGraph::new().create_graph(FileType::GFA2(gfa2)).unwrap();
+    let parsed = parse_file_to_graph(file).unwrap();
+    let lazy = gfahandlegraph::parser::parse_file_to_graph_lazy(file).unwrap();
+    for graph in [created, parsed, lazy].iter() {
+        let star = Handle::pack(1, false);
+        assert_eq!(graph.node_len(star), 6);
+        assert_eq!(graph.sequence(star), b"NNNNNN".to_vec());
+        let p = graph.path_id[b"p".as_ref()];
+        assert_eq!(path_sequence(graph, p), b"NNNNNNACGT".to_vec());
+    }
+}
+
+#[test]
+fn bfs_and_dfs_follow_the_edges_to_the_right() {
+    use gfahandlegraph::handlegraph::traversal::{bfs, dfs};
+
+    let graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let ids = |walk: Vec<Handle>| -> Vec<u64> { walk.iter().map(|h| u64::from(h.id())).collect() };
+
+    let walk: Vec<Handle> = bfs(&graph, Handle::pack(1, false)).collect();
+    assert!(walk.iter().all(|h| !h.is_reverse()));
+    assert_eq!(ids(walk), (1..=15).collect::<Vec<u64>>());
+    let walk: Vec<Handle> = dfs(&graph, Handle::pack(1, false)).collect();
+    assert_eq!(
+        ids(walk),
+        vec![1, 2, 4, 6, 7, 9, 10, 12, 13, 15, 14, 11, 8, 5, 3]
+    );
+
+    // backward from the last node, on the reverse handles, in the order
+    // of the links reaching each node
+    let walk: Vec<Handle> = bfs(&graph, Handle::pack(15, true)).collect();
+    assert!(walk.iter().all(|h| h.is_reverse()));
+    assert_eq!(
+        ids(walk),
+        vec![15, 13, 14, 12, 10, 11, 9, 7, 8, 6, 4, 5, 2, 3, 1]
+    );
+    let walk: Vec<Handle> = dfs(&graph, Handle::pack(9, false)).collect();
+    assert_eq!(ids(walk), vec![9, 10, 12, 13, 15, 14, 11]);
+
+    // a handle and its flip are the same node: 5+ -> 5- comes back to 5
+    let mut graph = graph;
+    graph
+        .create_edge(Edge(Handle::pack(5, false), Handle::pack(5, true)))
+        .unwrap();
+    let walk: Vec<Handle> = bfs(&graph, Handle::pack(5, false)).collect();
+    assert_eq!(walk.iter().filter(|h| h.id() == NodeId::from(5)).count(), 1);
+    let walk: Vec<Handle> = dfs(&graph, Handle::pack(5, false)).collect();
+    assert_eq!(walk.iter().filter(|h| h.id() == NodeId::from(5)).count(), 1);
+
+    assert_eq!(bfs(&graph, Handle::pack(99, false)).count(), 0);
+    assert_eq!(dfs(&graph, Handle::pack(99, false)).count(), 0);
+}
+
+#[test]
+fn connected_components_of_two_graphs() {
+    use gfahandlegraph::handlegraph::traversal::connected_components;
+
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let lil: Vec<NodeId> = (1..=15).map(NodeId::from).collect();
+    assert_eq!(connected_components(&graph), vec![lil.clone()]);
+
+    // 20- -> 21+ and 22+ -> 21-, connected only through the orientations
+    for id in 20..=22 {
+        graph.create_handle(id, b"ACGT").unwrap();
+    }
+    graph
+        .create_edge(Edge(Handle::pack(20, true), Handle::pack(21, false)))
+        .unwrap();
+    graph
+        .create_edge(Edge(Handle::pack(22, false), Handle::pack(21, true)))
+        .unwrap();
+    graph.create_handle(30, b"A").unwrap();
+    let other: Vec<NodeId> = (20..=22).map(NodeId::from).collect();
+    assert_eq!(
+        connected_components(&graph),
+        vec![lil, other, vec![NodeId::from(30)]]
+    );
+    assert!(connected_components(&HashGraph::new()).is_empty());
+}
+
+#[test]
+fn topological_order_of_an_acyclic_graph() {
+    use gfahandlegraph::handlegraph::algorithms::{has_cycle, topological_order};
+
+    // 1+ -> 2+, 1+ -> 3+, 2+ -> 4+, 3+ -> 4+ and 4+ -> 5-
+    let mut graph = HashGraph::new();
+    for id in 1..=5 {
+        graph.create_handle(id, b"ACGT").unwrap();
+    }
+    let edges = [
+        (1, 2, false),
+        (1, 3, false),
+        (2, 4, false),
+        (3, 4, false),
+        (4, 5, true),
+    ];
+    for (l, r, reverse) in edges.iter() {
+        graph
+            .create_edge(Edge(Handle::pack(*l, false), Handle::pack(*r, *reverse)))
+            .unwrap();
+    }
+    let order = topological_order(&graph).unwrap();
+    assert_eq!(
+        order,
+        vec![
+            Handle::pack(1, false),
+            Handle::pack(3, false),
+            Handle::pack(2, false),
+            Handle::pack(4, false),
+            Handle::pack(5, true),
+        ]
+    );
+    let position = |h: Handle| order.iter().position(|o| *o == h);
+    for Edge(l, r) in sorted_edges(&graph) {
+        let (l, r) = match (position(l), position(r)) {
+            (Some(l), Some(r)) => (l, r),
+            _ => (position(r.flip()).unwrap(), position(l.flip()).unwrap()),
+        };
+        assert!(l < r);
+    }
+    assert!(!has_cycle(&graph));
+    assert!(topological_order(&HashGraph::new()).unwrap().is_empty());
+
+    // a long chain is sorted without recursion
+    let mut chain = HashGraph::new();
+    for id in 1..=200_000u64 {
+        chain.create_handle(id, b"A").unwrap();
+        if id > 1 {
+            chain
+                .create_edge(Edge(Handle::pack(id - 1, false), Handle::pack(id, false)))
+                .unwrap();
+        }
+    }
+    let order = topological_order(&chain).unwrap();
+    assert!(order.iter().map(|h| u64::from(h.id())).eq(1..=200_000));
+}
+
+#[test]
+fn topological_order_with_a_reversing_self_loop() {
+    use gfahandlegraph::handlegraph::algorithms::{has_cycle, topological_order};
+
+    // 1+ -> 2+ and 2+ -> 2-, that can't be walked twice
+    let mut graph = HashGraph::new();
+    graph.create_handle(1, b"ACGT").unwrap();
+    graph.create_handle(2, b"ACGT").unwrap();
+    graph
+        .create_edge(Edge(Handle::pack(1, false), Handle::pack(2, false)))
+        .unwrap();
+    graph
+        .create_edge(Edge(Handle::pack(2, false), Handle::pack(2, true)))
+        .unwrap();
+    assert_eq!(
+        topological_order(&graph).unwrap(),
+        vec![Handle::pack(1, false), Handle::pack(2, false)]
+    );
+    assert!(!has_cycle(&graph));
+
+    // 2+ -> 2+ is a cycle
+    graph
+        .create_edge(Edge(Handle::pack(2, false), Handle::pack(2, false)))
+        .unwrap();
+    let error = topological_order(&graph).unwrap_err();
+    assert_eq!(
+        error.edge,
+        Edge(Handle::pack(2, false), Handle::pack(2, false))
+    );
+    assert!(has_cycle(&graph));
+}
+
+#[test]
+fn topological_order_of_a_two_node_cycle() {
+    use gfahandlegraph::handlegraph::algorithms::{has_cycle, topological_order};
+
+    // 3+ -> 1+ -> 2+ -> 1+
+    let mut graph = HashGraph::new();
+    for id in 1..=3 {
+        graph.create_handle(id, b"ACGT").unwrap();
+    }
+    let cycle = [
+        Edge(Handle::pack(1, false), Handle::pack(2, false)),
+        Edge(Handle::pack(2, false), Handle::pack(1, false)),
+    ];
+    for edge in cycle.iter() {
+        graph.create_edge(*edge).unwrap();
+    }
+    graph
+        .create_edge(Edge(Handle::pack(3, false), Handle::pack(1, false)))
+        .unwrap();
+    let error = topological_order(&graph).unwrap_err();
+    assert!(cycle.contains(&error.edge));
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "The graph has a cycle through the edge {}+ -> {}+",
+            error.edge.0.id(),
+            error.edge.1.id()
+        )
+    );
+    assert!(has_cycle(&graph));
+}
+
+#[test]
+fn modify_edge_is_transactional() {
+    use gfahandlegraph::handlegraph::{AllEdges, GraphError, HandleNeighbors};
+
+    let fwd = |id: u64| Handle::pack(id, false);
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let edges = sorted_edges(&graph);
+
+    // 1+ -> 3+ becomes 1+ -> 4+, then 2+ -> 5-
+    graph
+        .modify_edge(Edge(fwd(1), fwd(3)), None, Some(fwd(4)))
+        .unwrap();
+    assert!((&graph).has_edge(fwd(1), fwd(4)));
+    assert!(!(&graph).has_edge(fwd(1), fwd(3)));
+    graph
+        .modify_edge(Edge(fwd(1), fwd(4)), Some(fwd(2)), Some(fwd(5).flip()))
+        .unwrap();
+    assert!((&graph).has_edge(fwd(2), fwd(5).flip()));
+    assert!(!(&graph).has_edge(fwd(1), fwd(4)));
+    assert_eq!((&graph).edge_count(), edges.len());
+    assert_consistent(&graph);
+
+    // a missing new end or a missing edge leaves the graph as it is
+    let edges = sorted_edges(&graph);
+    let missing = graph.modify_edge(Edge(fwd(1), fwd(2)), None, Some(fwd(100)));
+    assert!(matches!(missing, Err(GraphError::NodeNotExist(id)) if id == "100"));
+    let missing = graph.modify_edge(Edge(fwd(1), fwd(6)), None, Some(fwd(7)));
+    assert!(matches!(missing, Err(GraphError::EdgeNotExist(l, r)) if l == "1" && r == "6"));
+    let missing = graph.modify_edge(Edge(fwd(1), fwd(6)), None, None);
+    assert!(matches!(missing, Err(GraphError::EdgeNotExist(..))));
+    let missing = graph.modify_edge(Edge(fwd(100), fwd(6)), None, None);
+    assert!(matches!(missing, Err(GraphError::EdgeNotExist(..))));
+    assert_eq!(sorted_edges(&graph), edges);
+    assert!((&graph).has_edge(fwd(1), fwd(2)));
+    assert_consistent(&graph);
+}
+
+#[test]
+fn write_gfa_streams_the_text_of_to_gfa() {
+    use gfahandlegraph::util::gzip::{gunzip, GzEncoder};
+    use gfahandlegraph::util::to_file::{to_gfa, write_gfa1, write_gfa2};
+
+    let graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let dir = std::env::temp_dir();
+    for format in ["GFA", "GFA2"].iter() {
+        let path = dir.join(format!("gfahandlegraph_streamed.{}", format.to_lowercase()));
+        let path = path.to_str().unwrap().to_string();
+        to_gfa(&graph, format.to_string(), Some(path.clone())).unwrap();
+        let saved = std::fs::read(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let mut text = vec![];
+        if *format == "GFA" {
+            write_gfa1(&graph, &mut text).unwrap();
+        } else {
+            write_gfa2(&graph, &mut text).unwrap();
+        }
+        assert_eq!(text, saved);
+        assert_eq!(
+            text.iter().filter(|b| **b == b'\n').count(),
+            1 + 15 + 21 + 3
+        );
+    }
+
+    // any writer can take the text, as a gzip encoder
+    let mut encoder = GzEncoder::new(vec![], 6);
+    write_gfa1(&graph, &mut encoder).unwrap();
+    let mut text = vec![];
+    write_gfa1(&graph, &mut text).unwrap();
+    assert_eq!(gunzip(&encoder.finish().unwrap()).unwrap(), text);
+}
+
+#[test]
+fn gzipped_files_are_parsed_as_the_plain_ones() {
+    use gfahandlegraph::parser::{
+        parse_file_to_graph_lazy, parser_gfa1::ParserBuilder, ParsedGfa, Parser,
+    };
+    use gfahandlegraph::util::gzip::GzEncoder;
+    use gfahandlegraph::util::threads::ThreadConfig;
+    use gfahandlegraph::util::to_file::{write_gfa1, write_gfa2};
+    use std::io::Write;
+
+    let text = |graph: &HashGraph, gfa2: bool| {
+        let mut text = vec![];
+        if gfa2 {
+            write_gfa2(graph, &mut text).unwrap();
+        } else {
+            write_gfa1(graph, &mut text).unwrap();
+        }
+        text
+    };
+    for (plain, gfa2) in [
+        ("./tests/gfa1_files/lil.gfa", false),
+        ("./tests/gfa2_files/spec_q7.gfa2", true),
+    ]
+    .iter()
+    {
+        let compressed = format!("{}.gz", plain);
+        let expected = text(&parse_file_to_graph(plain).unwrap(), *gfa2);
+        assert_eq!(
+            text(&parse_file_to_graph(&compressed).unwrap(), *gfa2),
+            expected
+        );
+        assert_eq!(
+            text(&parse_file_to_graph_lazy(&compressed).unwrap(), *gfa2),
+            expected
+        );
+        match Parser::new().parse_file(&compressed).unwrap() {
+            ParsedGfa::GFA(_) => assert!(!gfa2),
+            ParsedGfa::GFA2(_) => assert!(gfa2),
+        }
+    }
+
+    // many members, as written by bgzip, in a file named without .gz
+    let lines = std::fs::read("./tests/gfa1_files/lil.gfa").unwrap();
+    let mut members = vec![];
+    for chunk in lines.chunks(100) {
+        let mut encoder = GzEncoder::new(vec![], 6);
+        encoder.write_all(chunk).unwrap();
+        members.extend(encoder.finish().unwrap());
+    }
+    let path = std::env::temp_dir().join("gfahandlegraph_members.gfa");
+    std::fs::write(&path, members).unwrap();
+    let plain = ParserBuilder::all()
+        .build()
+        .parse_file("./tests/gfa1_files/lil.gfa")
+        .unwrap();
+    for threads in [ThreadConfig::sequential(), ThreadConfig::new(4)].iter() {
+        let mut builder = ParserBuilder::all();
+        builder.threads(*threads);
+        let gfa = builder.build().parse_file(&path).unwrap();
+        assert_eq!(gfa.to_hash(), plain.to_hash());
+    }
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn parser_builder_filters_skip_the_lines() {
+    use gfahandlegraph::parser::{parser_gfa1, parser_gfa2, ParserTolerance};
+
+    let full = parser_gfa1::ParserBuilder::all()
+        .build()
+        .parse_file("./tests/big_files/test.gfa")
+        .unwrap();
+    let mut builder = parser_gfa1::ParserBuilder::none();
+    builder.segments(true);
+    let segments = builder
+        .build()
+        .parse_file("./tests/big_files/test.gfa")
+        .unwrap();
+    assert_eq!(segments.segments, full.segments);
+    assert!(segments.headers.is_empty() && segments.links.is_empty() && segments.paths.is_empty());
+    assert_eq!(segments.skipped_lines, 1 + 10639 + 7);
+    assert_eq!(full.skipped_lines, 0);
+
+    let mut builder = parser_gfa2::ParserBuilder::none();
+    builder.groups_o(true);
+    let paths = builder
+        .build()
+        .parse_file("./tests/gfa2_files/spec_q7.gfa2")
+        .unwrap();
+    assert_eq!(paths.groups_o.len(), 1);
+    assert!(paths.segments.is_empty() && paths.edges.is_empty());
+    assert_eq!(paths.skipped_lines, 1 + 3 + 3);
+
+    // a line left out isn't an error, whatever the tolerance
+    let path = std::env::temp_dir().join("gfahandlegraph_filtered.gfa");
+    std::fs::write(&path, "H\tVN:Z:1.0\nS\t1\tACGT\nL\t1\t?\t2\n").unwrap();
+    let mut builder = parser_gfa1::ParserBuilder::all();
+    builder.error_tolerance(ParserTolerance::Pedantic);
+    assert!(builder.build().parse_file(&path).is_err());
+    let mut builder = parser_gfa1::ParserBuilder::all();
+    builder
+        .links(false)
+        .error_tolerance(ParserTolerance::Pedantic);
+    let gfa = builder.build().parse_file(&path).unwrap();
+    assert_eq!((gfa.segments.len(), gfa.skipped_lines), (1, 1));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn parallel_construction_matches_the_edges_added_one_at_a_time() {
+    use gfahandlegraph::handlegraph::{AllEdges, AllHandles, GraphError};
+    use gfahandlegraph::hashgraph::graph::FileType;
+    use gfahandlegraph::parser::parser_gfa1::ParserBuilder;
+
+    let gfa = ParserBuilder::all()
+        .build()
+        .parse_file("./tests/big_files/test.gfa")
+        .unwrap();
+    // more threads than cores, so the edges are added in parallel even
+    // on a single core
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(4)
+        .build()
+        .unwrap();
+    let graph = pool.install(|| {
+        HashGraph::new()
+            .create_graph(FileType::GFA(gfa.clone()))
+            .unwrap()
+    });
+
+    let mut expected = HashGraph::with_capacity(gfa.segments.len());
+    for s in gfa.segments.iter() {
+        expected.create_handle(s.name, &s.sequence).unwrap();
+    }
+    for l in gfa.links.iter() {
+        let left = Handle::new(l.from_segment, l.from_orient);
+        expected
+            .create_edge(Edge(left, Handle::new(l.to_segment, l.to_orient)))
+            .unwrap();
+    }
+    assert_eq!((&graph).node_count(), (&expected).node_count());
+    assert_eq!((&graph).edge_count(), (&expected).edge_count());
+    assert_eq!((&graph).edges().count(), (&expected).edges().count());
+    assert_eq!(graph.paths.len(), gfa.paths.len());
+    for id in (1..=gfa.segments.len() as u64).step_by(97) {
+        let (node, other) = (
+            graph.get_node(&NodeId::from(id)).unwrap(),
+            expected.get_node(&NodeId::from(id)).unwrap(),
+        );
+        assert_eq!(node.left_edges, other.left_edges, "node {}", id);
+        assert_eq!(node.right_edges, other.right_edges, "node {}", id);
+    }
+    assert_consistent(&graph);
+
+    // the same edge twice, from its other end, as a reversing self-loop
+    // and to a missing node
+    let path = std::env::temp_dir().join("gfahandlegraph_parallel.gfa");
+    std::fs::write(
+        &path,
+        "S\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\nL\t2\t-\t1\t-\t0M\nL\t2\t+\t2\t-\t0M\n\
+         L\t1\t+\t9\t+\t0M\nL\t8\t-\t2\t+\t0M\nL\t1\t+\t2\t+\t0M\n",
+    )
+    .unwrap();
+    let gfa = ParserBuilder::all().build().parse_file(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    let mut small = HashGraph::new();
+    let errors = pool.install(|| small.create_graph_with_errors(FileType::GFA(gfa)));
+    let missing: Vec<String> = errors
+        .iter()
+        .map(|why| match why {
+            GraphError::NodeNotExist(id) => id.clone(),
+            why => panic!("unexpected {}", why),
+        })
+        .collect();
+    assert_eq!(missing, vec!["9", "8"]);
+    assert_eq!(
+        sorted_edges(&small),
+        vec![
+            Edge(Handle::pack(1, false), Handle::pack(2, false)),
+            Edge(Handle::pack(2, false), Handle::pack(2, true)),
+        ]
+    );
+    assert_eq!((&small).edge_count(), 2);
+    assert_consistent(&small);
+}
+
+#[test]
+fn malformed_path_steps_are_reported() {
+    use gfahandlegraph::gfa::{
+        gfa1::{Path, Segment, GFA},
+        segment_id::PathParseError,
+    };
+    use gfahandlegraph::handlegraph::GraphError;
+    use gfahandlegraph::hashgraph::graph::FileType;
+    use gfahandlegraph::parser::{Parser, ParserTolerance};
+    use std::convert::TryFrom;
+
+    // a step without orientation and a step on a segment that isn't in
+    // the file
+    let mut gfa = GFA::new();
+    gfa.segments.push(Segment::new(1, b"A"));
+    gfa.segments.push(Segment::new(3, b"C"));
+    gfa.paths.push(Path::new("x".into(), "1+,2,3-,4+".into()));
+    let steps: Vec<_> = gfa.paths[0].iter_checked().collect();
+    assert_eq!(
+        steps,
+        vec![
+            Ok((1, Orientation::Forward)),
+            Err(PathParseError::MissingOrientation("2".into())),
+            Ok((3, Orientation::Backward)),
+            Ok((4, Orientation::Forward)),
+        ]
+    );
+    assert_eq!(gfa.paths[0].iter().count(), 3);
+
+    let errors = HashGraph::new().create_graph_with_errors(FileType::GFA(gfa.clone()));
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(&errors[0], GraphError::InvalidPathStep(p, s) if p == "x" && s == "2"));
+    assert!(matches!(&errors[1], GraphError::MissingStepNode(p, n, 3) if p == "x" && n == "4"));
+    assert_eq!(errors[0].code(), "G017");
+    assert!(matches!(
+        HashGraph::new().create_graph(FileType::GFA(gfa.clone())),
+        Err(GraphError::InvalidPathStep(_, _))
+    ));
+    assert!(matches!(
+        HashGraph::try_from(gfa.clone()),
+        Err(GraphError::InvalidPathStep(_, _))
+    ));
+    let report = HashGraph::new().create_graph_recover(FileType::GFA(gfa));
+    assert_eq!(report.dropped_path_steps, 2);
+
+    // an id too big for a node passes the parser of the line, the path
+    // is rejected unless every error is ignored
+    let path = std::env::temp_dir().join("gfahandlegraph_bad_step.gfa");
+    std::fs::write(
+        &path,
+        "S\t1\tA\nS\t2\tC\nP\tx\t1+,99999999999999999999+,2-\t*\n",
+    )
+    .unwrap();
+    let mut parser = Parser::new();
+    parser.error_tolerance(ParserTolerance::Safe);
+    let why = parser.parse_file_to_graph(&path).unwrap_err();
+    assert!(why.to_string().contains("99999999999999999999+"), "{}", why);
+    parser.error_tolerance(ParserTolerance::IgnoreAll);
+    let graph = parser.parse_file_to_graph(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    let x = graph.name_to_path_handle(b"x").unwrap();
+    assert_eq!(
+        graph.get_path(&x).unwrap().nodes,
+        vec![Handle::pack(1, false), Handle::pack(2, true)]
+    );
+}
+
+#[test]
+fn build_report_lists_the_dangling_records() {
+    use bstr::BString;
+    use gfahandlegraph::handlegraph::{AllEdges, GraphError};
+    use gfahandlegraph::hashgraph::graph::FileType;
+    use gfahandlegraph::parser::parser_gfa1::ParserBuilder;
+
+    let path = std::env::temp_dir().join("gfahandlegraph_dangling.gfa");
+    std::fs::write(
+        &path,
+        "S\t1\tA\nS\t2\tC\nL\t1\t+\t2\t+\t0M\nL\t2\t+\t7\t-\t0M\nL\t1\t-\t2\t+\t0M\n\
+         P\tx\t1+,5+,2+\t*\n",
+    )
+    .unwrap();
+    let gfa = ParserBuilder::all().build().parse_file(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let report = HashGraph::new()
+        .create_graph_report(FileType::GFA(gfa.clone()), false)
+        .unwrap();
+    assert!(!report.is_complete());
+    assert_eq!(report.missing_segment_edges, vec![(2, 7)]);
+    assert_eq!(
+        report.missing_segment_steps,
+        vec![(BString::from("x"), 1, 5)]
+    );
+    assert!(report.errors.is_empty());
+    assert_eq!((&report.graph).edges().count(), 2);
+    let x = report.graph.name_to_path_handle(b"x").unwrap();
+    assert_eq!(
+        report.graph.get_path(&x).unwrap().nodes,
+        vec![Handle::pack(1, false), Handle::pack(2, false)]
+    );
+
+    // the first dangling record stops the construction
+    let mut graph = HashGraph::new();
+    let why = graph
+        .create_graph_report(FileType::GFA(gfa), true)
+        .unwrap_err();
+    assert!(matches!(why, GraphError::NodeNotExist(ref id) if id == "7"));
+    assert_eq!((&graph).edges().count(), 1);
+    assert!(graph.paths.is_empty());
+
+    let lil = ParserBuilder::all()
+        .build()
+        .parse_file("./tests/gfa1_files/lil.gfa")
+        .unwrap();
+    let clean = HashGraph::new()
+        .create_graph_report(FileType::GFA(lil), true)
+        .unwrap();
+    assert!(clean.is_complete());
+}
+
+#[test]
+fn tips_isolated_nodes_and_degrees() {
+    use gfahandlegraph::handlegraph::{degree_histogram, isolated_nodes, tips, AllEdges};
+    use std::collections::BTreeMap;
+
+    // 1+ -> 2+ -> 3+, 2+ -> 4+, and 5 without edges
+    let mut graph = HashGraph::new();
+    for (id, seq) in [
+        (1, "ACGT"),
+        (2, "A"),
+        (3, "AAAAAAAAAA"),
+        (4, "C"),
+        (5, "GG"),
+    ]
+    .iter()
+    {
+        graph.create_handle(*id as u64, seq.as_bytes()).unwrap();
+    }
+    for edge in ["1+,2+", "2+,3+", "2+,4+"].iter() {
+        graph.create_edge(edge.parse::<Edge>().unwrap()).unwrap();
+    }
+    let path = graph.create_path_handle(b"p", false);
+    for step in ["1+", "2+", "4+"].iter() {
+        graph.append_step(&path, step.parse().unwrap()).unwrap();
+    }
+
+    let handles =
+        |text: &[&str]| -> Vec<Handle> { text.iter().map(|h| h.parse().unwrap()).collect() };
+    assert_eq!(tips(&graph), handles(&["1-", "3+", "4+", "5+", "5-"]));
+    assert_eq!(isolated_nodes(&graph), vec![NodeId::from(5u64)]);
+    let expected: BTreeMap<usize, usize> = vec![(0, 1), (1, 3), (3, 1)].into_iter().collect();
+    assert_eq!(degree_histogram(&graph), expected);
+
+    // 3 is long enough to stay
+    assert_eq!(graph.remove_tips(5), 3);
+    assert_eq!(sorted_edges(&graph), vec!["2+,3+".parse::<Edge>().unwrap()]);
+    assert_eq!(graph.get_path(&path).unwrap().nodes, handles(&["2+"]));
+    assert_eq!((&graph).edges().count(), 1);
+    assert_eq!(tips(&graph), handles(&["2-", "3+"]));
+    assert_eq!(graph.remove_tips(0), 0);
+    assert_consistent(&graph);
+
+    let mut lil = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    assert_eq!(tips(&lil), handles(&["1-", "15+"]));
+    assert!(isolated_nodes(&lil).is_empty());
+    let expected: BTreeMap<usize, usize> =
+        vec![(2, 8), (3, 3), (4, 3), (5, 1)].into_iter().collect();
+    assert_eq!(degree_histogram(&lil), expected);
+    // 1 has 8 bases, 15 has 11
+    assert_eq!(lil.remove_tips(9), 1);
+    assert!(!lil.graph.contains_key(&NodeId::from(1u64)));
+    let x = lil.name_to_path_handle(b"x").unwrap();
+    assert_eq!(lil.get_path(&x).unwrap().nodes[0], "3+".parse().unwrap());
+    assert_consistent(&lil);
+}
+
+#[test]
+fn format_sniffed_from_the_content() {
+    use gfahandlegraph::parser::{
+        detect_format, parse_reader_to_graph, GfaFormat, ParseError, ParsedGfa, Parser,
+    };
+
+    let gfa1 = "H\tVN:Z:1.0\nS\t1\tACGT\nS\t2\tTT\nL\t1\t+\t2\t+\t0M\nP\tx\t1+,2+\t*\n";
+    let gfa2 = "S\t1\t4\tACGT\nS\t2\t2\tTT\nE\t*\t1+\t2+\t4\t4$\t0\t0\t0M\n";
+    assert_eq!(detect_format(gfa1.as_bytes()), Some(GfaFormat::Gfa1));
+    assert_eq!(detect_format(gfa2.as_bytes()), Some(GfaFormat::Gfa2));
+    assert_eq!(detect_format(b"H\tVN:Z:2.0\n"), Some(GfaFormat::Gfa2));
+    assert_eq!(detect_format(b"# nothing to tell\n"), None);
+
+    let dir = std::env::temp_dir();
+    let plain = dir.join("gfahandlegraph_sniffed");
+    std::fs::write(&plain, gfa1).unwrap();
+    let graph = parse_file_to_graph(&plain).unwrap();
+    assert_eq!(graph.graph.len(), 2);
+    assert_eq!(sorted_edges(&graph), vec!["1+,2+".parse::<Edge>().unwrap()]);
+    assert!(graph.has_path(b"x"));
+    std::fs::remove_file(&plain).unwrap();
+
+    let wrong = dir.join("gfahandlegraph_sniffed.txt");
+    std::fs::write(&wrong, gfa2).unwrap();
+    match Parser::new().parse_file(&wrong) {
+        Ok(ParsedGfa::GFA2(parsed)) => assert_eq!(parsed.segments.len(), 2),
+        _ => panic!("the content is GFA2"),
+    }
+    let graph = parse_file_to_graph(&wrong).unwrap();
+    assert_eq!(sorted_edges(&graph), vec!["1+,2+".parse::<Edge>().unwrap()]);
+    std::fs::write(&wrong, "# nothing to tell\n").unwrap();
+    assert!(matches!(
+        parse_file_to_graph(&wrong),
+        Err(ParseError::ExtensionError())
+    ));
+    std::fs::remove_file(&wrong).unwrap();
+
+    let graph = parse_reader_to_graph(gfa1.as_bytes(), GfaFormat::Gfa1).unwrap();
+    assert_eq!(graph.graph.len(), 2);
+    assert!(graph.has_path(b"x"));
+    let graph = parse_reader_to_graph(std::io::Cursor::new(gfa2), GfaFormat::Gfa2).unwrap();
+    assert_eq!(sorted_edges(&graph), vec!["1+,2+".parse::<Edge>().unwrap()]);
+    assert_consistent(&graph);
+}
+
+#[test]
+fn occurrences_keep_every_visit_of_a_path() {
+    use gfahandlegraph::hashgraph::PathStep;
+
+    let mut graph = HashGraph::new();
+    for (id, seq) in [(11, "ACGT"), (12, "GG"), (13, "T")].iter() {
+        graph.create_handle(*id as u64, seq.as_bytes()).unwrap();
+    }
+    for edge in ["11+,12+", "12+,11+", "11+,13+"].iter() {
+        graph.create_edge(edge.parse::<Edge>().unwrap()).unwrap();
+    }
+    let p = graph.create_path_handle(b"p", false);
+    for step in ["11+", "12+", "11+", "13+"].iter() {
+        graph.append_step(&p, step.parse().unwrap()).unwrap();
+    }
+    let eleven = NodeId::from(11u64);
+    assert_eq!(graph.get_node(&eleven).unwrap().occurrences[&p], vec![0, 2]);
+    let mut steps: Vec<PathStep> = graph.occurrences(Handle::pack(11, false)).collect();
+    steps.sort_by_key(|s| s.index());
+    assert_eq!(steps, vec![PathStep::Step(p, 0), PathStep::Step(p, 2)]);
+
+    // both the visits go through the pieces
+    let pieces = graph
+        .divide_handle(Handle::pack(11, false), vec![2])
+        .unwrap();
+    let piece = pieces[1];
+    assert_eq!(
+        graph.get_path(&p).unwrap().nodes,
+        vec![
+            pieces[0],
+            piece,
+            "12+".parse().unwrap(),
+            pieces[0],
+            piece,
+            "13+".parse().unwrap()
+        ]
+    );
+    assert_eq!(graph.get_node(&eleven).unwrap().occurrences[&p], vec![0, 3]);
+    assert_eq!(
+        graph.get_node(&piece.id()).unwrap().occurrences[&p],
+        vec![1, 4]
+    );
+    assert_eq!(all_occurrences(&graph), expected_occurrences(&graph));
+
+    // and both are flipped
+    graph.apply_orientation(piece.flip()).unwrap();
+    let nodes = &graph.get_path(&p).unwrap().nodes;
+    assert_eq!((nodes[1], nodes[4]), (piece.flip(), piece.flip()));
+    assert_consistent(&graph);
+
+    graph.destroy_path(&p);
+    assert!(graph.graph.values().all(|n| n.occurrences.is_empty()));
+    assert_consistent(&graph);
+}
+
+#[test]
+fn packed_graph_answers_as_the_hashgraph() {
+    use gfahandlegraph::handle::Direction;
+    use gfahandlegraph::handlegraph::*;
+    use gfahandlegraph::packed::PackedGraph;
+    use gfahandlegraph::parser::parse_file_to_packed_graph;
+
+    let graph = read_medium_gfa1();
+    let packed = parse_file_to_packed_graph("./tests/big_files/test.gfa").unwrap();
+    assert_eq!(packed, PackedGraph::from_hashgraph(&graph));
+
+    let mut handles: Vec<Handle> = (&packed).handles().collect();
+    let mut expected: Vec<Handle> = (&graph).handles().collect();
+    handles.sort();
+    expected.sort();
+    assert_eq!(handles, expected);
+    assert_eq!((&packed).node_count(), (&graph).node_count());
+    assert_eq!(
+        (packed.min_node_id(), packed.max_node_id()),
+        (graph.min_node_id(), graph.max_node_id())
+    );
+    assert_eq!((&packed).edge_count(), (&graph).edge_count());
+    assert_eq!(sorted_edges(&graph), {
+        let mut edges: Vec<Edge> = (&packed).edges().collect();
+        edges.sort();
+        edges
+    });
+    assert_eq!((&packed).total_length(), (&graph).total_length());
+    for handle in expected.iter().flat_map(|h| vec![*h, h.flip()]) {
+        assert_eq!((&packed).sequence(handle), (&graph).sequence(handle));
+        assert_eq!((&packed).node_len(handle), (&graph).node_len(handle));
+        for dir in [Direction::Left, Direction::Right].iter() {
+            assert_eq!(
+                (&packed).neighbors(handle, *dir).collect::<Vec<_>>(),
+                (&graph).neighbors(handle, *dir).collect::<Vec<_>>()
+            );
+            assert_eq!(
+                (&packed).degree(handle, *dir),
+                (&graph).degree(handle, *dir)
+            );
+        }
+    }
+    assert!(!(&packed).has_node(graph.max_id + 1));
+
+    // the generic algorithms give the same answers
+    assert_eq!(topological_order(&packed), topological_order(&graph));
+    assert_eq!(connected_components(&packed), connected_components(&graph));
+    let start = expected[0];
+    assert_eq!(
+        bfs(&packed, start).collect::<Vec<_>>(),
+        bfs(&graph, start).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        dfs(&packed, start).collect::<Vec<_>>(),
+        dfs(&graph, start).collect::<Vec<_>>()
+    );
+    assert_eq!(tips(&packed), tips(&graph));
+    assert_eq!(degree_histogram(&packed), degree_histogram(&graph));
+}
+
+#[test]
+fn graph_diff_lists_the_differences() {
+    use gfahandlegraph::hashgraph::GraphDiff;
+    use gfahandlegraph::mutablehandlegraph::SubtractiveHandleGraph;
+
+    let graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    assert!(graph.diff(&graph).is_empty());
+    assert!(graph == graph.clone());
+    assert_eq!(graph.diff(&graph).to_string(), "The graphs are equal\n");
+
+    // an edge added from the other strand is the same edge
+    let mut flipped = HashGraph::new();
+    let mut straight = HashGraph::new();
+    for g in [&mut flipped, &mut straight].iter_mut() {
+        g.create_handle(1u64, b"ACG").unwrap();
+        g.create_handle(2u64, b"TT").unwrap();
+    }
+    flipped
+        .create_edge("2-,1-".parse::<Edge>().unwrap())
+        .unwrap();
+    straight
+        .create_edge("1+,2+".parse::<Edge>().unwrap())
+        .unwrap();
+    assert!(flipped == straight);
+
+    let mut other = graph.clone();
+    other.modify_handle(3u64, b"GATTACA").unwrap();
+    other.remove_edge("1+,2+".parse::<Edge>().unwrap()).unwrap();
+    other.create_handle(42u64, b"A").unwrap();
+    other
+        .create_edge("15+,42+".parse::<Edge>().unwrap())
+        .unwrap();
+    other.create_path_handle(b"new", false);
+    let x = other.name_to_path_handle(b"x").unwrap();
+    other.append_step(&x, "42+".parse().unwrap()).unwrap();
+    assert!(graph != other);
+
+    let diff = graph.diff(&other);
+    assert_eq!(
+        diff,
+        GraphDiff {
+            nodes_only_in_other: vec![NodeId::from(42u64)],
+            different_sequences: vec![NodeId::from(3u64)],
+            edges_only_in_self: vec!["1+,2+".parse().unwrap()],
+            edges_only_in_other: vec!["15+,42+".parse().unwrap()],
+            // y walks through the removed edge
+            paths_only_in_self: vec!["y".into()],
+            paths_only_in_other: vec!["new".into()],
+            different_paths: vec!["x".into()],
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        diff.to_string(),
+        "Nodes only in the second graph: 42\n\
+         Nodes with different sequences: 3\n\
+         Edges only in the first graph: 1+,2+\n\
+         Edges only in the second graph: 15+,42+\n\
+         Paths only in the first graph: y\n\
+         Paths only in the second graph: new\n\
+         Paths with different steps: x\n"
+    );
+    let back = other.diff(&graph);
+    assert_eq!(back.nodes_only_in_self, diff.nodes_only_in_other);
+    assert_eq!(back.paths_only_in_self, diff.paths_only_in_other);
+    assert_eq!(back.edges_only_in_other, diff.edges_only_in_self);
+}
+
+#[test]
+fn writers_round_trip_to_an_equal_graph() {
+    use gfahandlegraph::parser::{parse_reader_to_graph, GFA2Parser, GFAParser, GfaFormat};
+    use gfahandlegraph::util::to_file::{write_gfa1, write_gfa2};
+
+    let files = [
+        ("./tests/gfa1_files/lil.gfa", GfaFormat::Gfa1),
+        ("./tests/gfa2_files/spec_q7.gfa2", GfaFormat::Gfa2),
+    ];
+    for (file, format) in files.iter() {
+        let graph = parse_file_to_graph(file).unwrap();
+
+        // through the streaming writer
+        let mut text = vec![];
+        match format {
+            GfaFormat::Gfa1 => write_gfa1(&graph, &mut text).unwrap(),
+            GfaFormat::Gfa2 => write_gfa2(&graph, &mut text).unwrap(),
+        }
+        let streamed = parse_reader_to_graph(&text[..], *format).unwrap();
+        assert!(graph == streamed, "{}: {}", file, graph.diff(&streamed));
+
+        // through the GFA objects
+        let text = match format {
+            GfaFormat::Gfa1 => GFAParser::new().parse_file(file).unwrap().to_string(),
+            GfaFormat::Gfa2 => GFA2Parser::new().parse_file(file).unwrap().to_string(),
+        };
+        let object = parse_reader_to_graph(text.as_bytes(), *format).unwrap();
+        assert!(graph == object, "{}: {}", file, graph.diff(&object));
+    }
+}
+
+#[test]
+fn apply_orientation_keeps_the_path_sequences() {
+    use gfahandlegraph::handlegraph::{GraphError, HandleNeighbors, HandleSequences};
+
+    let mut graph = HashGraph::new();
+    for (id, seq) in [(11, "ACCTT"), (12, "TCAAGG"), (13, "CTTGATT")].iter() {
+        graph.create_handle(*id as u64, seq.as_bytes()).unwrap();
+    }
+    for edge in ["11+,12+", "12+,13+", "12+,12-"].iter() {
+        graph.create_edge(edge.parse::<Edge>().unwrap()).unwrap();
+    }
+    // a walks through 12 forward, b backward and c both ways
+    let mut paths = vec![];
+    for (name, steps) in [
+        ("a", "11+ 12+ 13+"),
+        ("b", "13- 12- 11-"),
+        ("c", "11+ 12+ 12- 11-"),
+    ]
+    .iter()
+    {
+        let path = graph.create_path_handle(name.as_bytes(), false);
+        for step in steps.split(' ') {
+            graph.append_step(&path, step.parse().unwrap()).unwrap();
+        }
+        paths.push(path);
+    }
+    let sequences: Vec<Vec<u8>> = paths.iter().map(|p| path_sequence(&graph, *p)).collect();
+
+    let h12: Handle = "12+".parse().unwrap();
+    assert_eq!(graph.apply_orientation(h12.flip()).unwrap(), h12);
+    assert_eq!(graph.sequence(h12), b"CCTTGA");
+    for (path, sequence) in paths.iter().zip(sequences.iter()) {
+        assert_eq!(&path_sequence(&graph, *path), sequence);
+    }
+    let steps = |graph: &HashGraph, path: i64| -> Vec<String> {
+        let nodes = &graph.get_path(&path).unwrap().nodes;
+        nodes.iter().map(|h| h.to_string()).collect()
+    };
+    assert_eq!(steps(&graph, paths[0]), vec!["11+", "12-", "13+"]);
+    assert_eq!(steps(&graph, paths[1]), vec!["13-", "12+", "11-"]);
+    assert_eq!(steps(&graph, paths[2]), vec!["11+", "12-", "12+", "11-"]);
+    assert!((&graph).has_edge("11+".parse().unwrap(), h12.flip()));
+    assert_consistent(&graph);
+
+    // a forward handle is already oriented
+    let generation = graph.generation();
+    assert_eq!(graph.apply_orientation(h12).unwrap(), h12);
+    assert!(matches!(
+        graph.apply_orientation("99-".parse().unwrap()),
+        Err(GraphError::NodeNotExist(_))
+    ));
+    assert!(matches!(
+        graph.apply_orientation("99+".parse().unwrap()),
+        Err(GraphError::NodeNotExist(_))
+    ));
+
+    // an occurrence past the end of its path leaves the graph untouched
+    let node = graph.get_node_mut(&NodeId::from(12u64)).unwrap();
+    node.occurrences.get_mut(&paths[0]).unwrap().push(10);
+    match graph.apply_orientation(h12.flip()) {
+        Err(GraphError::PositionNotFound(node, path)) => {
+            assert_eq!((node.as_str(), path.as_str()), ("12", "a"))
+        }
+        other => panic!("expected a missing position, got {:?}", other),
+    }
+    assert_eq!(graph.sequence(h12), b"CCTTGA");
+    assert_eq!(steps(&graph, paths[1]), vec!["13-", "12+", "11-"]);
+    assert_eq!(graph.generation(), generation);
+}
+
+#[test]
+fn cigar_parsing_and_lengths() {
+    use gfahandlegraph::gfa::cigar::{CigarOp, CIGAR};
+    use gfahandlegraph::parser::ParseFieldError;
+
+    let cigar = CIGAR::from_bytes(b"3M1I2D4=1X2S").unwrap();
+    assert_eq!(cigar.0[0], (3, CigarOp::Match));
+    assert_eq!(cigar.0[2], (2, CigarOp::Del));
+    assert_eq!(cigar.to_string(), "3M1I2D4=1X2S");
+    assert_eq!(cigar.ref_len(), 3 + 2 + 4 + 1);
+    assert_eq!(cigar.query_len(), 3 + 1 + 4 + 1 + 2);
+    assert!(!cigar.is_perfect_match());
+    assert!("5M2=".parse::<CIGAR>().unwrap().is_perfect_match());
+    assert_eq!("10N5H3P".parse::<CIGAR>().unwrap().query_len(), 0);
+
+    let unknown = CIGAR::from_bytes(b"*").unwrap();
+    assert!(unknown.is_empty() && !unknown.is_perfect_match());
+    assert_eq!(unknown.to_string(), "*");
+
+    for invalid in [
+        "",
+        "M",
+        "5",
+        "5M3",
+        "5Q",
+        "5M*",
+        "-5M",
+        "5 M",
+        "4294967296M",
+    ]
+    .iter()
+    {
+        assert!(
+            matches!(
+                CIGAR::from_bytes(invalid.as_bytes()),
+                Err(ParseFieldError::InvalidField("Overlap"))
+            ),
+            "{}",
+            invalid
+        );
+    }
+    assert_eq!(
+        CIGAR::from_bytes(b"4294967295M").unwrap().ref_len(),
+        4294967295
+    );
+}
+
+#[test]
+fn path_bases_len_without_the_overlaps() {
+    use gfahandlegraph::parser::{parse_reader_to_graph, GfaFormat};
+    use std::convert::TryFrom;
+
+    let gfa = "H\tVN:Z:1.0\n\
+               S\t1\tACGTACGTAC\n\
+               S\t2\tGTACGTACGT\n\
+               S\t3\tACG\n\
+               L\t1\t+\t2\t+\t5M\n\
+               L\t2\t+\t3\t+\t5M\n\
+               L\t3\t+\t1\t+\t2M\n\
+               P\tx\t1+,2+,3+\t5M,5M\n\
+               P\ty\t1+,2+\t*\n";
+    let graph = parse_reader_to_graph(gfa.as_bytes(), GfaFormat::Gfa1).unwrap();
+    let fwd = |id: u64| Handle::pack(id, false);
+    assert_eq!(graph.edge_overlap(Edge(fwd(1), fwd(2))), 5);
+    assert_eq!(graph.edge_overlap(Edge(fwd(3), fwd(1))), 2);
+
+    let x = graph.name_to_path_handle(b"x").unwrap();
+    let y = graph.name_to_path_handle(b"y").unwrap();
+    assert_eq!(graph.path_bases_len(&x), Some(23));
+    // the overlap with 3 is longer than it, so all of it is shared
+    assert_eq!(graph.path_bases_len_with_overlaps(&x), Some(23 - 5 - 3));
+    assert_eq!(graph.path_bases_len_with_overlaps(&y), Some(15));
+    assert_eq!(graph.path_bases_len_with_overlaps(&99), None);
+
+    // a circular path also shares the bases from its last step to the first
+    let mut graph = graph;
+    let z = graph.create_path_handle(b"z", true);
+    for id in [1, 2, 3].iter() {
+        graph.append_step(&z, fwd(*id)).unwrap();
+    }
+    assert_eq!(graph.path_bases_len_with_overlaps(&z), Some(23 - 5 - 3 - 2));
+
+    // the graph built from the GFA object has the same overlaps
+    let gfa = gfahandlegraph::parser::GFAParser::new()
+        .parse_reader(gfa.as_bytes())
+        .unwrap();
+    let built = HashGraph::try_from(gfa).unwrap();
+    assert_eq!(built.edge_overlap(Edge(fwd(2), fwd(3))), 5);
+    let x = built.name_to_path_handle(b"x").unwrap();
+    assert_eq!(built.path_bases_len_with_overlaps(&x), Some(15));
+}
+
+#[test]
+fn header_tags_are_kept_and_merged() {
+    use gfahandlegraph::gfa::{gfa1, gfa2, OptField};
+    use gfahandlegraph::parser::{parse_reader_to_graph, GFA2Parser, GFAParser, GfaFormat};
+    use gfahandlegraph::util::gfa2_to_gfa;
+    use gfahandlegraph::util::to_file::{write_gfa1, write_gfa2};
+
+    let text = "H\tVN:Z:2.0\tTS:i:100\nH\tpn:Z:assembler\tTS:i:50\nS\t1\t4\tACGT\n";
+    let gfa2 = GFA2Parser::new().parse_reader(text.as_bytes()).unwrap();
+    let header = gfa2::Header {
+        version: Some("2.0".into()),
+        tags: vec![
+            OptField::new(b"TS", b'i', b"100"),
+            OptField::new(b"pn", b'Z', b"assembler"),
+        ],
+    };
+    // the second line only adds the tags not found yet
+    assert_eq!(gfa2.headers, vec![header.clone()]);
+    assert_eq!(header.to_string(), "H\tVN:Z:2.0\tTS:i:100\tpn:Z:assembler");
+    let reparsed = GFA2Parser::new()
+        .parse_reader(gfa2.to_string().as_bytes())
+        .unwrap();
+    assert_eq!(reparsed.headers, gfa2.headers);
+
+    // the trace spacing is left out of GFA1
+    let gfa = gfa2_to_gfa(&gfa2).unwrap();
+    assert_eq!(gfa.headers[0].to_string(), "H\tVN:Z:1.0\tpn:Z:assembler");
+
+    let graph = parse_reader_to_graph(text.as_bytes(), GfaFormat::Gfa2).unwrap();
+    let kept = graph.header.clone().unwrap();
+    assert_eq!(kept.version, Some("2.0".into()));
+    assert_eq!(kept.tags, header.tags);
+    let mut written = vec![];
+    write_gfa2(&graph, &mut written).unwrap();
+    assert!(written.starts_with(b"H\tVN:Z:2.0\tTS:i:100\tpn:Z:assembler\nS\t1\t4\tACGT\n"));
+    let mut written = vec![];
+    write_gfa1(&graph, &mut written).unwrap();
+    assert!(written.starts_with(b"H\tVN:Z:1.0\tpn:Z:assembler\nS\t1\tACGT\n"));
+
+    let text = "H\tPG:Z:tool\nH\tVN:Z:1.0\nS\t1\tACGT\n";
+    let gfa = GFAParser::new().parse_reader(text.as_bytes()).unwrap();
+    let header = gfa1::Header {
+        version: Some("1.0".into()),
+        tags: vec![OptField::new(b"PG", b'Z', b"tool")],
+    };
+    assert_eq!(gfa.headers, vec![header]);
+    let graph = parse_reader_to_graph(text.as_bytes(), GfaFormat::Gfa1).unwrap();
+    let mut written = vec![];
+    write_gfa1(&graph, &mut written).unwrap();
+    assert!(written.starts_with(b"H\tVN:Z:1.0\tPG:Z:tool\n"));
+
+    // a file without header lines has none
+    let graph = parse_reader_to_graph("S\t1\tACGT\n".as_bytes(), GfaFormat::Gfa1).unwrap();
+    assert!(graph.header.is_none());
+    assert_eq!(
+        gfa1::Header::default().to_string(),
+        "H",
+        "a header without version"
+    );
+}
+
+#[test]
+fn parse_progress_and_stats_of_test_gfa() {
+    use gfahandlegraph::hashgraph::graph::FileType;
+    use gfahandlegraph::parser::{
+        parser_gfa1::ParserBuilder, BuildProgress, ParseProgress, ProgressHook,
+    };
+    use std::sync::{Arc, Mutex};
+
+    let reports: Arc<Mutex<Vec<ParseProgress>>> = Arc::new(Mutex::new(vec![]));
+    let found = reports.clone();
+    let mut builder = ParserBuilder::all();
+    builder
+        .progress_every(1000)
+        .on_progress(Box::new(move |p| found.lock().unwrap().push(p)));
+    let (gfa, stats) = builder
+        .build()
+        .parse_file_with_stats("./tests/big_files/test.gfa")
+        .unwrap();
+
+    let reports = reports.lock().unwrap();
+    assert!(reports.len() > 1);
+    assert_eq!(reports.len(), stats.lines_read / 1000 + 1);
+    let last = reports.last().unwrap();
+    assert_eq!(last.lines_read, stats.lines_read);
+    assert_eq!(last.bytes_read, stats.bytes_read);
+    assert_eq!(last.segments, gfa.segments.len());
+    assert_eq!(last.edges, gfa.links.len());
+    assert_eq!(last.paths, gfa.paths.len());
+    assert!(reports
+        .windows(2)
+        .all(|w| w[0].lines_read < w[1].lines_read));
+
+    assert_eq!(stats.records_of(b'S'), gfa.segments.len());
+    assert_eq!(stats.records_of(b'L'), gfa.links.len());
+    assert_eq!(stats.records_of(b'C'), gfa.containments.len());
+    assert_eq!(stats.records_of(b'P'), gfa.paths.len());
+    let records: usize = stats.records.values().sum();
+    assert_eq!(
+        records + stats.extensions + stats.skipped_lines,
+        stats.lines_read
+    );
+    let file = std::fs::read("./tests/big_files/test.gfa").unwrap();
+    // the last line has no line feed, that's counted anyway
+    assert_eq!(stats.bytes_read, file.len() + 1);
+
+    let built: Arc<Mutex<Vec<BuildProgress>>> = Arc::new(Mutex::new(vec![]));
+    let found = built.clone();
+    let hook = ProgressHook::new(move |p| found.lock().unwrap().push(p)).every(1000);
+    let graph = HashGraph::new()
+        .create_graph_with_progress(FileType::GFA(gfa.clone()), &hook)
+        .unwrap();
+    let built = built.lock().unwrap();
+    let last = built.last().unwrap();
+    assert_eq!(last.segments, gfa.segments.len());
+    assert_eq!(last.segments, last.total_segments);
+    assert_eq!(last.edges, gfa.links.len());
+    assert_eq!(last.paths, gfa.paths.len());
+    assert_eq!(graph.graph.len(), gfa.segments.len());
+    // the segments every 1000, the edges once and the end
+    assert_eq!(built.len(), gfa.segments.len() / 1000 + 2);
+}
+
+#[test]
+fn parse_stats_without_progress_and_in_parallel() {
+    use gfahandlegraph::{
+        parser::parser_gfa2::ParserBuilder as Gfa2ParserBuilder, util::threads::ThreadConfig,
+    };
+
+    let mut builder = Gfa2ParserBuilder::all();
+    builder.threads(ThreadConfig::new(4)).edges(false);
+    let (gfa2, stats) = builder
+        .build()
+        .parse_file_with_stats("./tests/gfa2_files/spec_q7.gfa2")
+        .unwrap();
+    assert!(gfa2.edges.is_empty());
+    assert_eq!(stats.records_of(b'E'), 0);
+    assert_eq!(stats.skipped_lines, gfa2.skipped_lines);
+    assert!(stats.skipped_lines > 0);
+    assert_eq!(stats.records_of(b'S'), gfa2.segments.len());
+    assert_eq!(stats.records_of(b'O'), gfa2.groups_o.len());
+    assert_eq!(stats.records_of(b'U'), gfa2.groups_u.len());
+}
+
+#[test]
+fn subsets_of_the_u_groups() {
+    use bstr::BString;
+    use gfahandlegraph::{
+        parser::{parse_reader_to_graph, GfaFormat},
+        util::to_file::write_gfa2,
+    };
+
+    let mut graph = parse_file_to_graph("./tests/gfa2_files/subsets.gfa2").unwrap();
+    let x = graph.node_id_of_name(b"x").unwrap();
+    let ids = |ids: &[u64]| ids.iter().map(|id| NodeId::from(*id)).collect::<Vec<_>>();
+    // the references to the edges and to the missing segments are left
+    // out, as the groups without name
+    let mut all = ids(&[1, 2, 3]);
+    all.push(x);
+    assert_eq!(graph.subset(b"all"), Some(all.as_slice()));
+    assert_eq!(graph.subset(b"pair"), Some(&[NodeId::from(3), x][..]));
+    assert_eq!(graph.subset(b"missing"), Some(&[][..]));
+    assert_eq!(graph.subset(b"*"), None);
+    assert_eq!(graph.subset(b"other"), None);
+    assert_eq!(
+        graph.subset_names(),
+        vec![
+            &BString::from("all"),
+            &BString::from("missing"),
+            &BString::from("pair")
+        ]
+    );
+    assert!(graph.containments().is_empty());
+
+    let mut text = vec![];
+    write_gfa2(&graph, &mut text).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("U\tall\t1 2 3 x\n"), "{}", text);
+    assert!(text.contains("U\tpair\t3 x\n"));
+    assert!(!text.contains("U\tmissing"));
+    let written = parse_reader_to_graph(text.as_bytes(), GfaFormat::Gfa2).unwrap();
+    assert_eq!(written.subset(b"all"), Some(all.as_slice()));
+    assert_eq!(written.subset(b"pair"), graph.subset(b"pair"));
+
+    // a removed node leaves the subsets, a renamed one is renamed there
+    graph.remove_handle(NodeId::from(3)).unwrap();
+    assert_eq!(graph.subset(b"pair"), Some(&[x][..]));
+    graph
+        .rename_node(NodeId::from(1), NodeId::from(10))
+        .unwrap();
+    let mut all = ids(&[10, 2]);
+    all.push(x);
+    assert_eq!(graph.subset(b"all"), Some(all.as_slice()));
+}
+
+#[test]
+fn containments_of_the_c_lines() {
+    use gfahandlegraph::{
+        parser::{parse_reader_to_graph, GfaFormat},
+        util::to_file::{write_gfa1, write_gfa2},
+    };
+
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/containments.gfa").unwrap();
+    let h = |id: u64, rev: bool| Handle::pack(NodeId::from(id), rev);
+    // the containment of the missing segment 9 is left out
+    assert_eq!(graph.containments().len(), 3);
+    let of_1: Vec<_> = graph.containments_of(NodeId::from(1)).collect();
+    assert_eq!(of_1.len(), 2);
+    assert_eq!(of_1[0].container, h(1, false));
+    assert_eq!(of_1[0].contained, h(2, false));
+    assert_eq!(of_1[0].pos, 4);
+    assert_eq!(of_1[0].overlap, "4M");
+    assert_eq!(of_1[1].container, h(1, true));
+    assert_eq!(of_1[1].contained, h(4, false));
+    assert_eq!(of_1[1].overlap, "*");
+    // a node is found as the container and as the contained node
+    let of_4: Vec<_> = graph.containments_of(NodeId::from(4)).collect();
+    assert_eq!(of_4.len(), 2);
+    assert_eq!(of_4[1].contained, h(4, true));
+    assert_eq!(graph.containments_of(NodeId::from(9)).count(), 0);
+
+    let mut text = vec![];
+    write_gfa1(&graph, &mut text).unwrap();
+    let text = String::from_utf8(text).unwrap();
+    assert!(text.contains("C\t1\t+\t2\t+\t4\t4M\nC\t1\t-\t4\t+\t0\t*\nC\t3\t+\t4\t-\t1\t2M\n"));
+    let written = parse_reader_to_graph(text.as_bytes(), GfaFormat::Gfa1).unwrap();
+    assert_eq!(written.containments(), graph.containments());
+    let mut gfa2 = vec![];
+    write_gfa2(&graph, &mut gfa2).unwrap();
+    assert!(!String::from_utf8(gfa2).unwrap().contains("C\t"));
+
+    // the containments of a removed node are gone
+    graph.remove_handle(NodeId::from(4)).unwrap();
+    assert_eq!(graph.containments().len(), 1);
+    assert_eq!(graph.containments_of(NodeId::from(3)).count(), 0);
+}
+
+#[test]
+fn random_edges_match_a_model() {
+    use gfahandlegraph::handlegraph::{AllEdges, HandleNeighbors};
+    use std::collections::HashSet;
+
+    // a xorshift, so the sequence of operations is always the same
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = |bound: u64| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state % bound
+    };
+    let mut graph = HashGraph::new();
+    for id in 1..=6_u64 {
+        graph.create_handle(id, b"ACGT").unwrap();
+    }
+    let mut model: HashSet<Edge> = HashSet::new();
+    for step in 0..600 {
+        let l = Handle::pack(next(6) + 1, next(2) == 1);
+        let r = Handle::pack(next(6) + 1, next(2) == 1);
+        // an edge is given from either of its strands
+        let edge = if next(2) == 0 {
+            Edge(l, r)
+        } else {
+            Edge(r.flip(), l.flip())
+        };
+        let canonical = Edge::edge_handle(l, r);
+        if next(3) == 0 {
+            graph.remove_edge(edge).unwrap();
+            model.remove(&canonical);
+        } else {
+            let added = graph.create_edge(edge).unwrap();
+            assert_eq!(added, model.insert(canonical), "step {}: {}", step, edge);
+        }
+        let found: Vec<Edge> = graph.edges().collect();
+        let unique: HashSet<Edge> = found.iter().copied().collect();
+        assert_eq!(
+            found.len(),
+            unique.len(),
+            "step {}: a duplicated edge",
+            step
+        );
+        assert_eq!(unique, model, "step {}", step);
+        assert_eq!(graph.edge_count(), model.len());
+        assert!(model
+            .iter()
+            .all(|Edge(l, r)| graph.has_edge(*l, *r) && graph.has_edge(r.flip(), l.flip())));
+        assert_eq!(graph.check_consistency(), Ok(()), "step {}", step);
+    }
+
+    // dividing a node keeps its edges, the self loops too
+    for id in 1..=6_u64 {
+        let handle = Handle::pack(id, next(2) == 1);
+        graph.divide_handle(handle, vec![1, 3]).unwrap();
+        assert_consistent(&graph);
+        let found: Vec<Edge> = graph.edges().collect();
+        let unique: HashSet<Edge> = found.iter().copied().collect();
+        assert_eq!(found.len(), unique.len());
+        assert!(found.iter().all(|Edge(l, r)| graph.has_edge(*l, *r)));
+    }
+}
+
+#[test]
+fn remove_edge_keeps_the_other_orientations() {
+    use gfahandlegraph::handlegraph::{AllEdges, HandleNeighbors};
+
+    let mut graph = HashGraph::new();
+    graph.create_handle(1_u64, b"ACGT").unwrap();
+    graph.create_handle(2_u64, b"GG").unwrap();
+    let (a, b) = (Handle::pack(1_u64, false), Handle::pack(2_u64, false));
+    graph.create_edge(Edge(a, b)).unwrap();
+    graph.create_edge(Edge(a, b.flip())).unwrap();
+    // the same edge from the other strand isn't added again
+    assert!(!graph.create_edge(Edge(b.flip(), a.flip())).unwrap());
+    graph.remove_edge(Edge(a, b.flip())).unwrap();
+    assert!(graph.has_edge(a, b));
+    assert!(!graph.has_edge(a, b.flip()));
+    assert_eq!(graph.edges().collect::<Vec<_>>(), vec![Edge(a, b)]);
+    assert_consistent(&graph);
+}
+
+#[test]
+fn divide_handle_moves_a_reversing_self_loop() {
+    use gfahandlegraph::handlegraph::{AllEdges, HandleNeighbors};
+
+    let mut graph = HashGraph::new();
+    graph.create_handle(1_u64, b"ACGTAC").unwrap();
+    let a = Handle::pack(1_u64, false);
+    // the end of the node back to its end, and its start to its start
+    graph.create_edge(Edge(a, a.flip())).unwrap();
+    graph.create_edge(Edge(a.flip(), a)).unwrap();
+    let pieces = graph.divide_handle(a, vec![2]).unwrap();
+    let last = *pieces.last().unwrap();
+    assert!(graph.has_edge(last, last.flip()));
+    assert!(graph.has_edge(a.flip(), a));
+    assert!(!graph.has_edge(last, a.flip()));
+    assert_eq!(graph.edges().count(), 3);
+    assert_consistent(&graph);
+}
+
+#[test]
+fn compact_ids_after_increment_node_ids() {
+    use gfahandlegraph::handlegraph::{AllEdges, AllHandles, GraphError};
+
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let path_ids: Vec<i64> = graph.paths.keys().copied().collect();
+    assert!(!path_ids.is_empty());
+    let sequences: Vec<Vec<u8>> = path_ids.iter().map(|p| path_sequence(&graph, *p)).collect();
+    let edges = graph.edge_count();
+    let live = |graph: &HashGraph| {
+        assert!(graph
+            .edges()
+            .all(|Edge(l, r)| graph.has_node(l.id()) && graph.has_node(r.id())));
+        assert!(graph
+            .paths
+            .values()
+            .all(|p| p.nodes.iter().all(|h| graph.has_node(h.id()))));
+        assert_consistent(graph);
+    };
+
+    let (min, max) = (u64::from(graph.min_id), u64::from(graph.max_id));
+    graph.increment_node_ids(1_000_000).unwrap();
+    assert_eq!(u64::from(graph.min_id), min + 1_000_000);
+    assert_eq!(u64::from(graph.max_id), max + 1_000_000);
+    assert!(!graph.has_node(NodeId::from(min)));
+    live(&graph);
+    assert_eq!(graph.edge_count(), edges);
+    for (path, sequence) in path_ids.iter().zip(sequences.iter()) {
+        assert_eq!(&path_sequence(&graph, *path), sequence);
+    }
+    // an id above the biggest one fails without changing the graph
+    let too_far = gfahandlegraph::handle::MAX_NODE_ID;
+    assert!(matches!(
+        graph.increment_node_ids(too_far),
+        Err(GraphError::IdOverflow(_, offset)) if offset == too_far
+    ));
+    assert_eq!(u64::from(graph.min_id), min + 1_000_000);
+
+    // the ids are sparse after moving a node far away
+    graph
+        .rename_node(NodeId::from(max + 1_000_000), NodeId::from(5_000_000))
+        .unwrap();
+    let mut old_ids: Vec<NodeId> = graph.graph.keys().copied().collect();
+    old_ids.sort_unstable();
+    let mapping = graph.compact_ids();
+    assert_eq!(mapping.len(), old_ids.len());
+    for (ix, old) in old_ids.iter().enumerate() {
+        assert_eq!(mapping[old], NodeId::from(ix as u64 + 1));
+    }
+    assert_eq!(u64::from(graph.min_id), 1);
+    assert_eq!(u64::from(graph.max_id), old_ids.len() as u64);
+    live(&graph);
+    assert_eq!(graph.edge_count(), edges);
+    for (path, sequence) in path_ids.iter().zip(sequences.iter()) {
+        assert_eq!(&path_sequence(&graph, *path), sequence);
+    }
+    // compacting again changes nothing
+    let again = graph.compact_ids();
+    assert!(again.iter().all(|(old, new)| old == new));
+}
+
+fn toy_graph(ids: &[u64], sequences: &[&[u8]], path: &[u8]) -> HashGraph {
+    let handles: Vec<Handle> = ids.iter().map(|id| Handle::pack(*id, false)).collect();
+    HashGraph::from_parts(
+        ids.iter()
+            .zip(sequences.iter())
+            .map(|(id, s)| (NodeId::from(*id), s.to_vec())),
+        handles.windows(2).map(|w| Edge(w[0], w[1])),
+        vec![(path.to_vec(), handles.clone(), false)],
+    )
+    .unwrap()
+}
+
+#[test]
+fn merge_two_disjoint_graphs() {
+    use gfahandlegraph::handlegraph::{AllEdges, AllHandles};
+    use gfahandlegraph::hashgraph::MergePolicy;
+
+    let mut graph = toy_graph(&[1, 2, 3], &[b"A", b"CC", b"GGG"], b"chr");
+    let other = toy_graph(&[1, 2], &[b"TT", b"AC"], b"chr");
+    let other_sequence = path_sequence(&other, 0);
+    let report = graph.merge(other, MergePolicy::Disjoint).unwrap();
+    assert_eq!(report.id_offset, 3);
+    assert_eq!(
+        (report.nodes_added, report.edges_added, report.paths_added),
+        (2, 1, 1)
+    );
+    assert_eq!(report.renamed_paths, vec![("chr".into(), "chr_1".into())]);
+    assert_eq!(graph.graph.len(), 5);
+    assert_eq!(graph.edge_count(), 3);
+    assert!(graph.has_node(NodeId::from(5)));
+    assert_eq!(path_sequence(&graph, 0), b"ACCGGG".to_vec());
+    let merged = graph.name_to_path_handle(b"chr_1").unwrap();
+    assert_eq!(path_sequence(&graph, merged), other_sequence);
+    assert_consistent(&graph);
+
+    // another suffix, after the first rename
+    let other = toy_graph(&[7], &[b"T"], b"chr");
+    let report = graph
+        .merge_renaming(other, MergePolicy::Disjoint, b".")
+        .unwrap();
+    assert_eq!(report.id_offset, 5);
+    assert_eq!(report.renamed_paths, vec![("chr".into(), "chr.1".into())]);
+    assert!(graph.has_node(NodeId::from(12)));
+    assert_consistent(&graph);
+}
+
+#[test]
+fn merge_graphs_sharing_nodes() {
+    use gfahandlegraph::handlegraph::{AllEdges, HandleNeighbors};
+    use gfahandlegraph::hashgraph::MergePolicy;
+
+    let mut graph = toy_graph(&[1, 2, 3], &[b"A", b"CC", b"GGG"], b"x");
+    // a bubble around the node 2, sharing the nodes 1 and 3
+    let other = toy_graph(&[1, 4, 3], &[b"A", b"T", b"GGG"], b"y");
+    let report = graph.merge(other, MergePolicy::SharedIds).unwrap();
+    assert_eq!(report.id_offset, 0);
+    assert_eq!(
+        (report.nodes_added, report.edges_added, report.paths_added),
+        (1, 2, 1)
+    );
+    assert!(report.renamed_paths.is_empty());
+    assert_eq!(graph.graph.len(), 4);
+    assert_eq!(graph.edge_count(), 4);
+    assert!(graph.has_edge(Handle::pack(1, false), Handle::pack(4, false)));
+    let y = graph.name_to_path_handle(b"y").unwrap();
+    assert_eq!(path_sequence(&graph, y), b"ATGGG".to_vec());
+    assert_consistent(&graph);
+
+    // merging the same graph again adds nothing but its path
+    let again = toy_graph(&[1, 4, 3], &[b"A", b"T", b"GGG"], b"y");
+    let report = graph.merge(again, MergePolicy::SharedIds).unwrap();
+    assert_eq!((report.nodes_added, report.edges_added), (0, 0));
+    assert_eq!(report.renamed_paths, vec![("y".into(), "y_1".into())]);
+    assert_consistent(&graph);
+}
+
+#[test]
+fn merge_with_a_sequence_conflict() {
+    use gfahandlegraph::handlegraph::{AllEdges, GraphError};
+    use gfahandlegraph::hashgraph::MergePolicy;
+
+    let mut graph = toy_graph(&[1, 2, 3], &[b"A", b"CC", b"GGG"], b"x");
+    let other = toy_graph(&[1, 2, 3, 4], &[b"A", b"CG", b"GGC", b"T"], b"y");
+    let generation = graph.generation();
+    match graph.merge(other, MergePolicy::SharedIds) {
+        Err(GraphError::SequenceConflict(nodes)) => {
+            assert_eq!(nodes, vec!["2".to_string(), "3".to_string()])
+        }
+        other => panic!("expected a conflict, found {:?}", other),
+    }
+    assert_eq!(graph.graph.len(), 3);
+    assert_eq!(graph.edge_count(), 2);
+    assert_eq!(graph.paths.len(), 1);
+    assert!(graph.name_to_path_handle(b"y").is_none());
+    assert_eq!(graph.generation(), generation);
+    assert_consistent(&graph);
+}
+
+#[test]
+fn path_range_sequence_of_lil_paths() {
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let x = graph.name_to_path_handle(b"x").unwrap();
+    assert_eq!(
+        graph.path_range_sequence(&x, 6, 11),
+        Some(b"AGGCT".to_vec())
+    );
+    assert_eq!(graph.path_range_sequence(&x, 5, 5), Some(vec![]));
+    assert_eq!(
+        graph.path_range_sequence(&x, 0, 50),
+        Some(path_sequence(&graph, x))
+    );
+    assert_eq!(graph.path_range_sequence(&x, 45, 51), None);
+    // a linear path has no range through its origin
+    assert_eq!(graph.path_range_sequence(&x, 10, 5), None);
+    assert_eq!(
+        graph.path_position_to_handle(&x, 12),
+        Some((Handle::pack(6, false), 2))
+    );
+    assert_eq!(
+        graph.path_position_to_handle(&x, 49),
+        Some((Handle::pack(15, false), 10))
+    );
+    assert_eq!(graph.path_position_to_handle(&x, 50), None);
+
+    // CAA AAATTTTCTGGAGTTCTAT CTTATTTG
+    let r = graph.create_path_handle(b"r", true);
+    for handle in [
+        Handle::pack(6, true),
+        Handle::pack(9, false),
+        Handle::pack(1, true),
+    ] {
+        graph.append_step(&r, handle).unwrap();
+    }
+    assert_eq!(graph.path_range_sequence(&r, 1, 5), Some(b"AAAA".to_vec()));
+    // across the reverse step
+    assert_eq!(
+        graph.path_range_sequence(&r, 20, 25),
+        Some(b"ATCTT".to_vec())
+    );
+    assert_eq!(
+        graph.path_position_to_handle(&r, 23),
+        Some((Handle::pack(1, true), 1))
+    );
+    // through the origin of the circular path
+    assert_eq!(
+        graph.path_range_sequence(&r, 27, 2),
+        Some(b"TTGCA".to_vec())
+    );
+    assert_eq!(graph.path_range_sequence(&r, 30, 2), None);
+    assert_eq!(graph.path_range_sequence(&r, 28, 31), None);
+
+    let full = path_sequence(&graph, r);
+    assert_eq!(full.len(), 30);
+    for start in 0..full.len() {
+        for end in 0..=full.len() {
+            let expected = if start <= end {
+                full[start..end].to_vec()
+            } else {
+                [&full[start..], &full[..end]].concat()
+            };
+            assert_eq!(graph.path_range_sequence(&r, start, end), Some(expected));
+        }
+    }
+}
+
+#[test]
+fn canonical_digest_ignores_the_order_of_the_records() {
+    use gfahandlegraph::parser::{parser_gfa1, parser_gfa2};
+    use gfahandlegraph::util::ThreadConfig;
+    use std::convert::TryFrom;
+
+    let parser = |threads| {
+        let mut builder = parser_gfa1::ParserBuilder::all();
+        builder.threads(threads);
+        builder.build()
+    };
+    let lil = "./tests/gfa1_files/lil.gfa";
+    let sequential = parser(ThreadConfig::sequential()).parse_file(lil).unwrap();
+    let parallel = parser(ThreadConfig::new(4)).parse_file(lil).unwrap();
+    let digest = sequential.canonical_digest();
+    assert_eq!(parallel.canonical_digest(), digest);
+
+    // the same records in the opposite order, with a link flipped
+    let text = std::fs::read_to_string(lil).unwrap();
+    let mut lines: Vec<&str> = text.lines().filter(|l| *l != "L\t1\t+\t2\t+\t0M").collect();
+    lines.reverse();
+    lines.push("L\t2\t-\t1\t-\t0M");
+    let shuffled = parser(ThreadConfig::sequential())
+        .parse_reader(lines.join("\n").as_bytes())
+        .unwrap();
+    assert!(shuffled.to_hash() != sequential.to_hash());
+    assert_eq!(shuffled.canonical_digest(), digest);
+
+    // a single base of a segment changed
+    let changed = text.replace("S\t6\tTTG", "S\t6\tTTC");
+    let changed = parser(ThreadConfig::sequential())
+        .parse_reader(changed.as_bytes())
+        .unwrap();
+    assert_ne!(changed.canonical_digest(), digest);
+
+    // the graphs of both the parsers, and after changing a base
+    let mut graph = HashGraph::try_from(sequential).unwrap();
+    let graph_digest = graph.canonical_digest();
+    assert_eq!(
+        HashGraph::try_from(parallel).unwrap().canonical_digest(),
+        graph_digest
+    );
+    assert_eq!(
+        parse_file_to_graph(lil).unwrap().canonical_digest(),
+        graph_digest
+    );
+    graph.modify_handle(6, b"TTC").unwrap();
+    assert_ne!(graph.canonical_digest(), graph_digest);
+
+    let gfa2_parser = parser_gfa2::ParserBuilder::all().build();
+    let q7 = std::fs::read_to_string("./tests/gfa2_files/spec_q7.gfa2").unwrap();
+    let gfa2 = gfa2_parser.parse_reader(q7.as_bytes()).unwrap();
+    let mut lines: Vec<&str> = q7
+        .lines()
+        .filter(|l| *l != "E\t*\t11+\t12-\t1\t5$\t2\t6$\t4M")
+        .collect();
+    lines.reverse();
+    lines.push("E\t*\t12+\t11-\t2\t6$\t1\t5$\t4M");
+    let reversed = gfa2_parser
+        .parse_reader(lines.join("\n").as_bytes())
+        .unwrap();
+    assert_eq!(reversed.canonical_digest(), gfa2.canonical_digest());
+    let changed = gfa2_parser
+        .parse_reader(q7.replace("ACCTT", "ACCTA").as_bytes())
+        .unwrap();
+    assert_ne!(changed.canonical_digest(), gfa2.canonical_digest());
+}
+
+#[test]
+fn poison_lines_follow_the_tolerance() {
+    use gfahandlegraph::parser::{
+        parser_gfa1, parser_gfa2, ParseError, ParseFieldError, ParserTolerance,
+    };
+    use std::io::{BufReader, Read};
+
+    let gfa1 = |tolerance| {
+        let mut builder = parser_gfa1::ParserBuilder::all();
+        builder.error_tolerance(tolerance);
+        builder.build().parse_file("./tests/gfa1_files/poison.gfa")
+    };
+    let gfa = gfa1(ParserTolerance::IgnoreAll).unwrap();
+    let names: Vec<u64> = gfa.segments.iter().map(|s| s.name).collect();
+    assert_eq!(names.len(), 3, "{:?}", names);
+    assert_eq!(gfa.links.len(), 1);
+    assert_eq!(gfa.paths.len(), 1);
+    assert_eq!(gfa.paths[0].path_name, "q");
+    assert!(gfa.containments.is_empty());
+    // the unknown and the empty lines are skipped, not the first poison one
+    assert!(matches!(
+        gfa1(ParserTolerance::Safe),
+        Err(ParseError::InvalidLineAt(
+            6,
+            ParseFieldError::IdOverflow(_),
+            _
+        ))
+    ));
+    assert!(matches!(
+        gfa1(ParserTolerance::Pedantic),
+        Err(ParseError::UnknownLineType)
+    ));
+
+    // each poison line alone, after the valid lines
+    let text = std::fs::read_to_string("./tests/gfa1_files/poison.gfa").unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    let (head, tail) = (&lines[..2], &lines[lines.len() - 3..]);
+    for (ix, poison) in lines[2..lines.len() - 3].iter().enumerate() {
+        let file = [head, &[poison], tail].concat().join("\n");
+        let parse = |tolerance| {
+            let mut builder = parser_gfa1::ParserBuilder::all();
+            builder.error_tolerance(tolerance);
+            builder.build().parse_reader(file.as_bytes())
+        };
+        let skipped = poison.is_empty() || poison.starts_with('X');
+        let valid_line = poison.starts_with("S\tabc");
+        assert!(parse(ParserTolerance::IgnoreAll).is_ok(), "{}", ix);
+        assert_eq!(
+            parse(ParserTolerance::Safe).is_ok(),
+            skipped || valid_line,
+            "{}",
+            poison
+        );
+        assert_eq!(
+            parse(ParserTolerance::Pedantic).is_ok(),
+            valid_line,
+            "{}",
+            poison
+        );
+    }
+
+    let gfa2 = |builder: parser_gfa2::ParserBuilder| {
+        builder.build().parse_file("./tests/gfa2_files/poison.gfa2")
+    };
+    let all = || parser_gfa2::ParserBuilder::all();
+    let gfa = gfa2(all().ignore_errors()).unwrap();
+    assert_eq!(gfa.segments.len(), 3);
+    assert_eq!(gfa.edges.len(), 1);
+    assert_eq!(gfa.groups_o.len(), 1);
+    assert_eq!(gfa.groups_o[0].id, "h");
+    assert!(matches!(
+        gfa2(all().ignore_safe_errors()),
+        Err(ParseError::InvalidLineAt(
+            6,
+            ParseFieldError::IdOverflow(_),
+            _
+        ))
+    ));
+    assert!(matches!(
+        gfa2(all().pedantic_errors()),
+        Err(ParseError::UnknownLineType)
+    ));
+    // a group with a reference without orientation isn't cut before it
+    let mut builder = all();
+    builder.groups_o(true);
+    assert!(matches!(
+        builder.build().parse_reader(&b"O\tg\t1+ 2 3+\n"[..]),
+        Err(ParseError::InvalidLineAt(
+            1,
+            ParseFieldError::OrientationError,
+            _
+        ))
+    ));
+
+    // a read error in the middle of the file fails whatever the tolerance
+    struct Broken;
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("broken"))
+        }
+    }
+    let reader = BufReader::new(b"S\t1\tA\nS\t2\tC\n".chain(Broken));
+    let mut builder = parser_gfa1::ParserBuilder::all();
+    builder.ignore_errors();
+    assert!(matches!(
+        builder.build().parse_reader(reader),
+        Err(ParseError::IOError(_))
+    ));
+}
+
+#[test]
+fn node_and_edge_tags_follow_the_graph() {
+    use gfahandlegraph::gfa::optional::OptField;
+    use gfahandlegraph::handlegraph::HandleNeighbors;
+    use gfahandlegraph::parser::{parse_reader_to_graph, GfaFormat};
+    use gfahandlegraph::util::to_file::{write_gfa1, write_gfa2};
+
+    let text = "S\t1\tACG\tDP:f:2.5\nS\t2\tTT\nS\t3\tG\n\
+                L\t1\t+\t2\t+\t1M\tRC:i:7\nL\t2\t+\t3\t+\t0M\n";
+    let mut graph = parse_reader_to_graph(text.as_bytes(), GfaFormat::Gfa1).unwrap();
+    let (one, two, three) = (
+        Handle::pack(1, false),
+        Handle::pack(2, false),
+        Handle::pack(3, false),
+    );
+    let rc = OptField::new(b"RC", b'i', b"7");
+    assert_eq!(
+        graph.node_tags(NodeId::from(1)).to_vec(),
+        vec![OptField::new(b"DP", b'f', b"2.5")]
+    );
+    assert_eq!(graph.edge_tags(Edge(one, two)).to_vec(), vec![rc.clone()]);
+    // the same edge from its other strand
+    assert_eq!(
+        graph.edge_tags(Edge(two.flip(), one.flip())).to_vec(),
+        vec![rc]
+    );
+    assert!(graph.edge_tags(Edge(two, three)).is_empty());
+
+    // set, replace and remove
+    let bubble = OptField::new(b"BL", b'Z', b"top");
+    assert_eq!(
+        graph.set_node_tag(NodeId::from(2), bubble.clone()).unwrap(),
+        None
+    );
+    let replaced = graph
+        .set_node_tag(NodeId::from(1), OptField::new(b"DP", b'f', b"3"))
+        .unwrap();
+    assert_eq!(replaced.unwrap().value, "2.5");
+    assert_eq!(graph.node_tags(NodeId::from(1)).len(), 1);
+    assert!(graph.set_node_tag(NodeId::from(9), bubble.clone()).is_err());
+    graph
+        .set_edge_tag(Edge(three.flip(), two.flip()), bubble.clone())
+        .unwrap();
+    assert_eq!(
+        graph.edge_tags(Edge(two, three)).to_vec(),
+        vec![bubble.clone()]
+    );
+    assert!(graph
+        .set_edge_tag(Edge(one, three), bubble.clone())
+        .is_err());
+    assert_eq!(
+        graph.remove_edge_tag(Edge(two, three), b"BL"),
+        Some(bubble.clone())
+    );
+    assert_eq!(graph.remove_edge_tag(Edge(two, three), b"BL"), None);
+    graph
+        .set_edge_tag(Edge(two, three), bubble.clone())
+        .unwrap();
+
+    // written back and read again, in both formats and with bincode
+    let mut gfa1 = vec![];
+    write_gfa1(&graph, &mut gfa1).unwrap();
+    let mut gfa2 = vec![];
+    write_gfa2(&graph, &mut gfa2).unwrap();
+    let file = std::env::temp_dir().join("gfahandlegraph_tags.bin");
+    graph.save_bincode(&file).unwrap();
+    let loaded = [
+        parse_reader_to_graph(&gfa1[..], GfaFormat::Gfa1).unwrap(),
+        parse_reader_to_graph(&gfa2[..], GfaFormat::Gfa2).unwrap(),
+        HashGraph::load_bincode(&file).unwrap(),
+    ];
+    std::fs::remove_file(&file).unwrap();
+    for copy in loaded.iter() {
+        for id in 1..=3 {
+            let id = NodeId::from(id);
+            assert_eq!(copy.node_tags(id), graph.node_tags(id));
+        }
+        for edge in [Edge(one, two), Edge(two, three)].iter() {
+            assert_eq!(copy.edge_tags(*edge), graph.edge_tags(*edge));
+        }
+    }
+
+    // the tags follow the renamed nodes
+    let mut renamed = graph.clone();
+    renamed.increment_node_ids(10).unwrap();
+    let (eleven, twelve) = (Handle::pack(11, false), Handle::pack(12, false));
+    assert_eq!(renamed.edge_tags(Edge(eleven, twelve)).len(), 1);
+    assert_eq!(
+        renamed.node_tags(NodeId::from(12)).to_vec(),
+        vec![bubble.clone()]
+    );
+    assert!(renamed.edge_tags(Edge(one, two)).is_empty());
+
+    // and are gone with their edge or their node
+    graph.remove_edge(Edge(one, two)).unwrap();
+    assert!(graph.edge_tags(Edge(one, two)).is_empty());
+    graph.create_edge(Edge(one, two)).unwrap();
+    assert!(graph.edge_tags(Edge(one, two)).is_empty());
+    graph.remove_handle(NodeId::from(3)).unwrap();
+    assert!(graph.edge_tags(Edge(two, three)).is_empty());
+    assert!(graph.node_tags(NodeId::from(3)).is_empty());
+    assert_eq!(
+        graph
+            .neighbors(two, gfahandlegraph::handle::Direction::Right)
+            .count(),
+        0
+    );
+    assert_consistent(&graph);
+}
+
+#[test]
+fn bulk_edit_adds_the_batch_or_nothing() {
+    use gfahandlegraph::handlegraph::GraphError;
+    use gfahandlegraph::handlegraph::{AllHandles, HandleGraph};
+    use gfahandlegraph::hashgraph::EditReport;
+
+    let mut graph = toy_graph(&[1, 2], &[b"A", b"CC"], b"x");
+    let (two, three, four) = (
+        Handle::pack(2, false),
+        Handle::pack(3, false),
+        Handle::pack(4, true),
+    );
+    // the path and the edges come before the nodes they visit
+    let mut edit = graph.bulk_edit();
+    edit.add_path(b"y", &[two, three, four]);
+    edit.add_edge(Edge(two, three));
+    edit.add_edge(Edge(three, four));
+    edit.add_edge(Edge(four.flip(), three.flip()));
+    edit.add_node(3, b"GGG");
+    edit.add_node(4, b"TA");
+    let report = edit.commit().unwrap();
+    assert_eq!(
+        report,
+        EditReport {
+            nodes_added: 2,
+            edges_added: 2,
+            paths_added: 1,
+        }
+    );
+    assert_eq!(graph.max_node_id(), NodeId::from(4));
+    let y = graph.name_to_path_handle(b"y").unwrap();
+    assert_eq!(path_sequence(&graph, y), b"CCGGGTA".to_vec());
+    assert_consistent(&graph);
+
+    // the same graph through the incremental API
+    let mut incremental = toy_graph(&[1, 2], &[b"A", b"CC"], b"x");
+    incremental.create_handle(3, b"GGG").unwrap();
+    incremental.create_handle(4, b"TA").unwrap();
+    incremental.create_edge(Edge(two, three)).unwrap();
+    incremental.create_edge(Edge(three, four)).unwrap();
+    let path = incremental.create_path_handle(b"y", false);
+    for step in [two, three, four].iter() {
+        incremental.append_step(&path, *step).unwrap();
+    }
+    assert!(graph == incremental, "{}", graph.diff(&incremental));
+
+    // a failing batch leaves the graph untouched
+    let before = graph.clone();
+    let generation = graph.generation();
+    let mut edit = graph.bulk_edit();
+    edit.add_node(5, b"T");
+    edit.add_edge(Edge(Handle::pack(5, false), two));
+    edit.add_node(3, b"A");
+    assert!(matches!(
+        edit.commit(),
+        Err(GraphError::InvalidBatchRecord(at, why))
+            if at == "node 1" && matches!(*why, GraphError::IdAlreadyExist(ref id) if id == "3")
+    ));
+    let mut edit = graph.bulk_edit();
+    edit.add_node(5, b"T");
+    edit.add_path(b"x", &[Handle::pack(5, false)]);
+    assert!(matches!(
+        edit.commit(),
+        Err(GraphError::InvalidBatchRecord(at, why))
+            if at == "path 0" && matches!(*why, GraphError::PathAlreadyExist(ref name) if name == "x")
+    ));
+    let mut edit = graph.bulk_edit();
+    edit.add_edge(Edge(two, Handle::pack(9, false)));
+    assert!(matches!(
+        edit.commit(),
+        Err(GraphError::InvalidBatchRecord(_, why)) if matches!(*why, GraphError::NodeNotExist(_))
+    ));
+    // and so does a batch dropped before its commit
+    graph.bulk_edit().add_node(6, b"A");
+    assert!(graph == before);
+    assert!(!graph.has_node(NodeId::from(5)));
+    assert!(!graph.has_node(NodeId::from(6)));
+    assert_eq!(graph.generation(), generation);
+}
+
+#[test]
+fn attach_sequences_from_fasta_fills_the_star_segments() {
+    use gfahandlegraph::handlegraph::HandleSequences;
+    use gfahandlegraph::hashgraph::{graph::FileType, AttachError, LengthMismatch};
+    use gfahandlegraph::util::to_file::write_gfa2;
+
+    let file = "./tests/gfa2_files/placeholders.gfa2";
+    let graph = parse_file_to_graph(file).unwrap();
+    let ctg2 = graph.node_id_of_name(b"ctg2").unwrap();
+    let (one, three, four) = (NodeId::from(1), NodeId::from(3), NodeId::from(4));
+    let mut waiting = vec![one, ctg2, four];
+    waiting.sort_unstable();
+    assert_eq!(graph.nodes_missing_sequence(), waiting);
+    assert_eq!(graph.sequence(Handle::pack(1, false)), b"NNNN".to_vec());
+    // the placeholders are written back as they were read
+    let mut out = vec![];
+    write_gfa2(&graph, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("S\t1\t4\t*\n"));
+    assert!(out.contains("S\tctg2\t6\t*\n"));
+    assert!(out.contains("S\t3\t3\tACG\n"));
+
+    // the same records, compressed or not
+    for fasta in ["placeholders.fa", "placeholders.fa.gz"].iter() {
+        let mut attached = graph.clone();
+        let generation = attached.generation();
+        let report = attached
+            .attach_sequences_from_fasta(format!("./tests/gfa2_files/{}", fasta))
+            .unwrap();
+        let mut matched = vec![one, ctg2];
+        matched.sort_unstable();
+        assert_eq!(report.matched, matched);
+        assert_eq!(report.missing, vec![four]);
+        assert!(report.length_mismatches.is_empty());
+        assert_eq!(report.ignored_records, vec!["3", "9"]);
+        assert_eq!(attached.nodes_missing_sequence(), vec![four]);
+        assert_eq!(attached.sequence(Handle::pack(1, false)), b"ACGT".to_vec());
+        assert_eq!(
+            attached.sequence(Handle::pack(ctg2, false)),
+            b"ACGTTT".to_vec()
+        );
+        assert_eq!(
+            attached.sequence(Handle::pack(three, false)),
+            b"ACG".to_vec()
+        );
+        let p = attached.path_id[b"p".as_ref()];
+        assert_eq!(path_sequence(&attached, p), b"ACGTACGTTTACG".to_vec());
+        assert!(attached.generation() > generation);
+        assert_consistent(&attached);
+    }
+
+    // a record with another length leaves its node without its sequence
+    let mut attached = graph.clone();
+    let report = attached
+        .attach_sequences_from_fasta("./tests/gfa2_files/placeholders_mismatch.fa")
+        .unwrap();
+    assert_eq!(report.matched, vec![ctg2]);
+    assert_eq!(
+        report.length_mismatches,
+        vec![LengthMismatch {
+            id: one,
+            declared: 4,
+            found: 3
+        }]
+    );
+    assert_eq!(report.missing, vec![four]);
+    assert_eq!(attached.nodes_missing_sequence(), vec![one, four]);
+    assert_eq!(attached.sequence(Handle::pack(1, false)), b"NNNN".to_vec());
+
+    // an invalid file doesn't change the graph
+    let dir = std::env::temp_dir();
+    let invalid = [
+        ("gfahandlegraph_orphan.fa", "ACGT\n>1\nACGT\n"),
+        ("gfahandlegraph_empty.fa", ">1\nACGT\n>ctg2\n"),
+        ("gfahandlegraph_twice.fa", ">1\nACGT\n>1 again\nACGT\n"),
+    ];
+    for (name, text) in invalid.iter() {
+        let path = dir.join(name);
+        std::fs::write(&path, text).unwrap();
+        let mut attached = graph.clone();
+        let result = attached.attach_sequences_from_fasta(&path);
+        std::fs::remove_file(&path).unwrap();
+        match (*name, result) {
+            ("gfahandlegraph_orphan.fa", Err(AttachError::InvalidFasta(1))) => (),
+            ("gfahandlegraph_empty.fa", Err(AttachError::InvalidFasta(3))) => (),
+            ("gfahandlegraph_twice.fa", Err(AttachError::DuplicateRecord(record))) => {
+                assert_eq!(record, "1")
+            }
+            (name, other) => panic!("{}: {:?}", name, other),
+        }
+        assert_eq!(attached.nodes_missing_sequence(), waiting);
+    }
+    let missing = graph
+        .clone()
+        .attach_sequences_from_fasta(dir.join("gfahandlegraph_none.fa"));
+    assert!(matches!(missing, Err(AttachError::Io(_))));
+
+    // the other ways to build the graph keep the placeholders too
+    let gfa2 = gfahandlegraph::parser::parser_gfa2::ParserBuilder::all()
+        .build()
+        .parse_file(file)
+        .unwrap();
+    let created = HashGraph::new().create_graph(FileType::GFA2(gfa2)).unwrap();
+    let lazy = gfahandlegraph::parser::parse_file_to_graph_lazy(file).unwrap();
+    for built in [created, lazy].iter() {
+        assert_eq!(built.nodes_missing_sequence(), waiting);
+    }
+}
+
+#[test]
+fn edges_are_the_same_from_both_their_ends() {
+    use gfahandlegraph::handle::Direction;
+    use gfahandlegraph::handlegraph::{AllEdges, HandleNeighbors};
+
+    let two_nodes = || {
+        let nodes = vec![
+            (NodeId::from(11), b"ACG".to_vec()),
+            (NodeId::from(13), b"TTA".to_vec()),
+        ];
+        HashGraph::from_parts(nodes, vec![], Vec::<(Vec<u8>, Vec<Handle>, bool)>::new()).unwrap()
+    };
+    let orientations = [(false, false), (false, true), (true, false), (true, true)];
+    for (l, r) in orientations.iter() {
+        let (l, r) = (Handle::pack(11, *l), Handle::pack(13, *r));
+        let writings = [Edge(l, r), Edge(r.flip(), l.flip())];
+        for created in writings.iter() {
+            for removed in writings.iter() {
+                let mut graph = two_nodes();
+                assert!(graph.create_edge(*created).unwrap());
+                // created once, whatever the writing
+                for other in writings.iter() {
+                    assert!(!graph.create_edge(*other).unwrap());
+                    assert!(graph.has_edge(other.0, other.1));
+                    assert!(graph.has_edge_canonical(*other));
+                }
+                assert_eq!(graph.edge_count(), 1);
+                assert_eq!(graph.edges().collect::<Vec<_>>(), vec![created.canonical()]);
+                // every other orientation of the nodes is another edge
+                for (a, b) in orientations.iter() {
+                    let other = Edge(Handle::pack(11, *a), Handle::pack(13, *b));
+                    if other.canonical() != created.canonical() {
+                        assert!(!graph.has_edge_canonical(other));
+                    }
+                }
+                assert!(graph.remove_edge(*removed).unwrap());
+                for other in writings.iter() {
+                    assert!(!graph.has_edge(other.0, other.1));
+                    assert!(!graph.has_edge_canonical(*other));
+                }
+                assert_eq!(graph.edge_count(), 0);
+                assert_eq!(graph.degree(Handle::pack(11, false), Direction::Right), 0);
+                assert_eq!(graph.degree(Handle::pack(11, false), Direction::Left), 0);
+                assert_eq!(graph.degree(Handle::pack(13, false), Direction::Right), 0);
+                assert_eq!(graph.degree(Handle::pack(13, false), Direction::Left), 0);
+                assert_consistent(&graph);
+            }
+        }
+    }
+    // a node that doesn't exist has no edges
+    let graph = two_nodes();
+    assert!(!graph.has_edge_canonical(Edge(Handle::pack(11, false), Handle::pack(14, false))));
+}
+
+#[test]
+fn node_records_borrow_the_sequences_and_export_to_tsv() {
+    use bstr::BStr;
+    use gfahandlegraph::hashgraph::NodeRecord;
+    use gfahandlegraph::util::export::{nodes_to_tsv, NODES_TSV_HEADER};
+    use rayon::prelude::*;
+
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let records: Vec<NodeRecord> = graph.node_records().collect();
+    assert_eq!(records.len(), 15);
+    for record in records.iter() {
+        // the sequence is the one stored in the node, not a copy
+        let node = graph.get_node_unchecked(&record.id);
+        let sequence: &BStr = record.sequence;
+        assert!(std::ptr::eq(
+            sequence.as_ptr(),
+            graph.node_sequence(node).as_ptr()
+        ));
+    }
+    let parallel: Vec<NodeRecord> = graph.par_node_records().collect();
+    assert_eq!(parallel, records);
+
+    let mut out = vec![];
+    nodes_to_tsv(&graph, &mut out).unwrap();
+    let expected = [
+        NODES_TSV_HEADER,
+        "1\tCAAATAAG\t0\t2\t1",
+        "2\tA\t1\t2\t1",
+        "3\tG\t1\t2\t1",
+        "4\tT\t2\t1\t1",
+        "5\tC\t3\t2\t1",
+        "6\tTTG\t2\t2\t1",
+        "7\tA\t1\t1\t1",
+        "8\tG\t1\t1\t1",
+        "9\tAAATTTTCTGGAGTTCTAT\t2\t2\t1",
+        "10\tA\t1\t1\t1",
+        "11\tT\t1\t1\t1",
+        "12\tATAT\t2\t2\t1",
+        "13\tA\t1\t1\t1",
+        "14\tT\t1\t1\t1",
+        "15\tCCAACTCTCTG\t2\t0\t1",
+        "",
+    ]
+    .join("\n");
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+    // a node without paths, after removing them all
+    let paths: Vec<_> = graph.paths.keys().copied().collect();
+    for path_id in paths {
+        graph.destroy_path(&path_id);
+    }
+    assert!(graph.node_records().all(|r| !r.on_path));
+}
+
+#[test]
+fn gfa_to_gfa2_computes_the_positions_from_the_segments() {
+    use gfahandlegraph::parser::{GFA2Parser, GFAParser};
+    use gfahandlegraph::util::{gfa_file_to_gfa2, gfa_to_gfa2};
+
+    // every link of lil.gfa is a 0M at the end of its first segment
+    let gfa = GFAParser::new()
+        .parse_file("./tests/gfa1_files/lil.gfa")
+        .unwrap();
+    let gfa2 = gfa_to_gfa2(&gfa);
+    assert_eq!(gfa2.edges.len(), gfa.links.len());
+    for (link, edge) in gfa.links.iter().zip(gfa2.edges.iter()) {
+        let len = gfa.segments[link.from_segment as usize - 1].sequence.len();
+        assert_eq!(
+            edge.to_string(),
+            format!(
+                "E\t*\t{}+\t{}+\t{}$\t{}$\t0\t0\t0M",
+                link.from_segment, link.to_segment, len, len
+            )
+        );
+    }
+
+    // the lengths are 10, 8, 6 (from LN) and 4
+    let dir = std::env::temp_dir();
+    let file = dir.join("gfahandlegraph_conversion.gfa");
+    std::fs::copy("./tests/gfa1_files/conversion.gfa", &file).unwrap();
+    let gfa = GFAParser::new().parse_file(&file).unwrap();
+    let edges: Vec<String> = gfa_to_gfa2(&gfa)
+        .edges
+        .iter()
+        .map(|e| e.to_string())
+        .collect();
+    let expected = vec![
+        // 4M1D3M covers 8 bases of 1 and 7 of 2
+        "E\t*\t1+\t2+\t2\t10$\t0\t7\t4M1D3M",
+        "E\t*\t2-\t3+\t0\t0\t0\t0\t*",
+        // at the start of a reverse first segment, at the end of a
+        // reverse second one
+        "E\t*\t1-\t3-\t0\t2\t4\t6$\t2M",
+        // the containment covers the whole of 4, from the position 3 of 1
+        "E\t*\t1+\t4-\t3\t7\t0\t4$\t4M",
+    ];
+    assert_eq!(edges, expected);
+
+    // the file converter writes the same edges next to the file
+    gfa_file_to_gfa2(file.to_str().unwrap().to_string()).unwrap();
+    let converted = dir.join("gfahandlegraph_conversion.gfa2");
+    let gfa2 = GFA2Parser::new().parse_file(&converted).unwrap();
+    let written: Vec<String> = gfa2.edges.iter().map(|e| e.to_string()).collect();
+    assert_eq!(written, expected);
+    assert_eq!(gfa2.segments[2].to_string(), "S\t3\t6\t*\tLN:i:6");
+    std::fs::remove_file(&file).unwrap();
+    std::fs::remove_file(&converted).unwrap();
+}
+
+#[test]
+fn shared_graph_is_read_by_many_tasks_and_written_by_one() {
+    use gfahandlegraph::handle::Direction;
+    use gfahandlegraph::handlegraph::{
+        AllHandles, HandleGraphRef, HandleNeighbors, HandleSequences,
+    };
+    use gfahandlegraph::hashgraph::SharedHashGraph;
+    use rayon::prelude::*;
+    use std::sync::Arc;
+
+    // a generic reader takes an &Arc like an &HashGraph
+    fn right_degrees<G: HandleGraphRef>(graph: G, ids: &[u64]) -> Vec<usize> {
+        ids.iter()
+            .map(|id| graph.degree(Handle::pack(*id, false), Direction::Right))
+            .collect()
+    }
+
+    let graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let expected = right_degrees(&graph, &(1..=15).collect::<Vec<u64>>());
+    let arc = Arc::new(graph.clone());
+    let degrees: Vec<usize> = (1..=15u64)
+        .into_par_iter()
+        .map(|id| right_degrees(&arc.clone(), &[id])[0])
+        .collect();
+    assert_eq!(degrees, expected);
+    assert_eq!((&arc).total_length(), (&graph).total_length());
+
+    let shared = SharedHashGraph::new(graph);
+    let degrees: Vec<usize> = (1..=15u64)
+        .into_par_iter()
+        .map(|id| {
+            let reader = shared.clone();
+            reader.with_read(|g| g.degree(Handle::pack(id, false), Direction::Right))
+        })
+        .collect();
+    assert_eq!(degrees, expected);
+
+    // the readers run while the main thread waits to add a node
+    let readers = {
+        let shared = shared.clone();
+        std::thread::spawn(move || {
+            (0..100)
+                .into_par_iter()
+                .map(|_| shared.with_read(|g| g.node_count()))
+                .filter(|count| *count == 15 || *count == 16)
+                .count()
+        })
+    };
+    let added = shared
+        .with_write(|g| g.create_handle(16u64, b"ACGT"))
+        .unwrap();
+    assert_eq!(readers.join().unwrap(), 100);
+    assert_eq!(added, Handle::pack(16, false));
+    assert_eq!(shared.node_count(), 16);
+    assert!(shared.has_node(16u64));
+    assert!(!shared.has_edge(Edge(Handle::pack(15, false), added)));
+
+    // the graph comes back once nobody else shares it
+    let other = shared.clone();
+    let shared = shared.into_inner().unwrap_err();
+    drop(other);
+    let graph = shared.into_inner().unwrap();
+    assert_eq!(graph.sequence(added), b"ACGT".to_vec());
+}
+
+#[test]
+fn path_steps_keep_their_positions_after_mixed_edits() {
+    use gfahandlegraph::handlegraph::HandleSequences;
+    use gfahandlegraph::hashgraph::PathStep;
+
+    let mut graph = toy_graph(&[1, 2, 3, 4], &[b"A", b"CC", b"GGG", b"TTTT"], b"x");
+    let x = graph.path_id[b"x".as_ref()];
+    let h = |id: u64| Handle::pack(id, false);
+    let check = |graph: &HashGraph, expected: &[u64]| {
+        let path = graph.get_path(&x).unwrap();
+        let ids: Vec<u64> = path.nodes.iter().map(|h| u64::from(h.id())).collect();
+        assert_eq!(ids, expected);
+        let mut start = 0;
+        for (ix, handle) in path.nodes.iter().enumerate() {
+            let step = PathStep::Step(x, ix);
+            assert_eq!(graph.position_of_step(&step), Some(start), "step {}", ix);
+            let found = graph.step_at_position(&x, start + graph.node_len(*handle) - 1);
+            assert_eq!(found, Some(step), "end of the step {}", ix);
+            start += graph.node_len(*handle);
+        }
+        for id in 1..=4 {
+            let mut visits: Vec<usize> = graph
+                .occurrences(h(id))
+                .map(|step| step.index().unwrap())
+                .collect();
+            visits.sort_unstable();
+            let on_path: Vec<usize> = (0..ids.len()).filter(|ix| ids[*ix] == id).collect();
+            assert_eq!(visits, on_path, "visits of {}", id);
+        }
+        assert_consistent(graph);
+    };
+
+    graph.prepend_step(&x, h(4)).unwrap();
+    graph.prepend_step(&x, h(2)).unwrap();
+    graph.append_step(&x, h(1)).unwrap();
+    check(&graph, &[2, 4, 1, 2, 3, 4, 1]);
+
+    // a longer segment near the end moves the steps after it, a shorter
+    // one near the front the steps before it
+    graph.rewrite_segment(
+        &PathStep::Step(x, 5),
+        &PathStep::Step(x, 5),
+        vec![h(3), h(3)],
+    );
+    check(&graph, &[2, 4, 1, 2, 3, 3, 3, 1]);
+    graph.rewrite_segment(&PathStep::Step(x, 1), &PathStep::Step(x, 2), vec![h(1)]);
+    check(&graph, &[2, 1, 2, 3, 3, 3, 1]);
+    graph.rewrite_segment(
+        &PathStep::Step(x, 3),
+        &PathStep::Step(x, 4),
+        vec![h(4), h(2)],
+    );
+    check(&graph, &[2, 1, 2, 4, 2, 3, 1]);
+
+    graph.prepend_step(&x, h(3)).unwrap();
+    graph.remove_step(b"x", 1u64).unwrap();
+    check(&graph, &[3, 2, 2, 4, 2, 3]);
+    graph.prepend_step(&x, h(1)).unwrap();
+    graph.rewrite_segment(&PathStep::Front(x), &PathStep::Step(x, 1), vec![]);
+    check(&graph, &[2, 2, 4, 2, 3]);
+    graph.append_step(&x, h(4)).unwrap();
+    check(&graph, &[2, 2, 4, 2, 3, 4]);
+
+    // removing or changing steps keeps the keys of the steps before the
+    // first one removed, or after the last one if they're fewer
+    let keys = |graph: &HashGraph, id: u64| {
+        graph
+            .get_node(&NodeId::from(id))
+            .and_then(|node| node.occurrences.get(&x).cloned())
+    };
+    graph.append_step(&x, h(1)).unwrap();
+    let before = keys(&graph, 2);
+    graph.remove_step(b"x", 3u64).unwrap();
+    check(&graph, &[2, 2, 4, 2, 4, 1]);
+    assert_eq!(keys(&graph, 2), before);
+    graph.prepend_step(&x, h(3)).unwrap();
+    let before: Vec<_> = [1, 2, 4].iter().map(|id| keys(&graph, *id)).collect();
+    graph.remove_step(b"x", 3u64).unwrap();
+    check(&graph, &[2, 2, 4, 2, 4, 1]);
+    let after: Vec<_> = [1, 2, 4].iter().map(|id| keys(&graph, *id)).collect();
+    assert_eq!(after, before);
+    graph.remove_step(b"x", 2u64).unwrap();
+    check(&graph, &[4, 4, 1]);
+    let before = keys(&graph, 1);
+    graph.modify_step(b"x", 4u64, h(3)).unwrap();
+    check(&graph, &[3, 3, 1]);
+    assert_eq!(keys(&graph, 1), before);
+    assert_eq!(keys(&graph, 4), None);
+}
+
+#[test]
+fn write_gfa_preserving_regenerates_retagged_records() {
+    use gfahandlegraph::gfa::OptField;
+    use gfahandlegraph::parser::Parser;
+
+    let preserved = |graph: &HashGraph| {
+        let mut out = vec![];
+        graph.write_gfa_preserving(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let fwd = |id: u64| Handle::pack(id, false);
+    let mut graph = Parser::new()
+        .fidelity(true)
+        .parse_file_to_graph("./tests/gfa1_files/lil.gfa")
+        .unwrap();
+    let original = preserved(&graph);
+    graph
+        .set_node_tag(NodeId::from(1), OptField::new(b"DP", b'f', b"12.5"))
+        .unwrap();
+    graph
+        .set_edge_tag(Edge(fwd(1), fwd(2)), OptField::new(b"RC", b'i', b"3"))
+        .unwrap();
+    graph.set_edge_overlap(Edge(fwd(1), fwd(3)), 1).unwrap();
+    let written = preserved(&graph);
+    let changed: Vec<(&str, &str)> = original
+        .lines()
+        .zip(written.lines())
+        .filter(|(before, after)| before != after)
+        .collect();
+    assert_eq!(
+        changed,
+        vec![
+            ("S\t1\tCAAATAAG", "S\t1\tCAAATAAG\tDP:f:12.5"),
+            ("L\t1\t+\t2\t+\t0M", "L\t1\t+\t2\t+\t0M\tRC:i:3"),
+            ("L\t1\t+\t3\t+\t0M", "L\t1\t+\t3\t+\t1M"),
+        ]
+    );
+    assert_eq!(written.lines().count(), original.lines().count());
+
+    // the containments of a removed segment are left out
+    let mut graph = Parser::new()
+        .fidelity(true)
+        .parse_file_to_graph("./tests/gfa1_files/containments.gfa")
+        .unwrap();
+    assert!(graph.remove_handle(NodeId::from(4)).unwrap());
+    let containments: Vec<String> = preserved(&graph)
+        .lines()
+        .filter(|line| line.starts_with('C'))
+        .map(String::from)
+        .collect();
+    assert_eq!(
+        containments,
+        vec!["C\t1\t+\t2\t+\t4\t4M", "C\t1\t+\t9\t+\t3\t*"]
+    );
+}
+
+#[test]
+fn archives_keep_the_names_the_tags_and_the_groups() {
+    use gfahandlegraph::util::{load_archive, save_archive};
+
+    let round_trip = |graph: &HashGraph, name: &str| {
+        let archive = std::env::temp_dir().join(name);
+        save_archive(graph, &archive).unwrap();
+        let loaded = load_archive(&archive).unwrap();
+        std::fs::remove_file(&archive).unwrap();
+        assert!(graph.diff(&loaded).is_empty(), "{}", graph.diff(&loaded));
+        assert_eq!(loaded.canonical_digest(), graph.canonical_digest());
+        assert_eq!(loaded.header, graph.header);
+        assert_eq!(loaded.containments(), graph.containments());
+        assert_eq!(loaded.subset_names(), graph.subset_names());
+        for name in graph.subset_names() {
+            assert_eq!(loaded.subset(name), graph.subset(name));
+        }
+        assert_eq!(
+            loaded.nodes_missing_sequence(),
+            graph.nodes_missing_sequence()
+        );
+        for handle in graph.handles_sorted() {
+            let id = handle.id();
+            assert_eq!(loaded.node_name(id), graph.node_name(id));
+            assert_eq!(loaded.node_tags(id), graph.node_tags(id));
+        }
+        for edge in graph.edges_sorted() {
+            assert_eq!(loaded.edge_tags(edge), graph.edge_tags(edge));
+        }
+        for path in graph.paths.values() {
+            let same = &loaded.paths[&loaded.path_id[&path.name.to_vec()]];
+            assert_eq!(same.optional, path.optional);
+        }
+        loaded
+    };
+
+    let file = std::env::temp_dir().join("gfahandlegraph_described.gfa");
+    std::fs::write(
+        &file,
+        "H\tVN:Z:1.0\tPG:Z:test\n\
+         S\tchrA\tACGT\tDP:f:1.5\n\
+         S\t2\tGGT\n\
+         S\t3\tTT\n\
+         L\tchrA\t+\t2\t+\t1M\tRC:i:4\n\
+         C\tchrA\t+\t3\t-\t1\t2M\n\
+         P\tp\tchrA+,2+\t1M\tNM:i:0\n",
+    )
+    .unwrap();
+    let graph = parse_file_to_graph(&file).unwrap();
+    std::fs::remove_file(&file).unwrap();
+    let loaded = round_trip(&graph, "gfahandlegraph_described.hga");
+    let chr_a = loaded.node_id_of_name(b"chrA").unwrap();
+    assert_eq!(loaded.node_tags(chr_a).len(), 1);
+    let link = Edge(Handle::pack(chr_a, false), Handle::pack(2, false));
+    assert_eq!(loaded.edge_tags(link).len(), 1);
+    assert_eq!(loaded.containments().len(), 1);
+    assert!(loaded.header.is_some());
+
+    round_trip(
+        &parse_file_to_graph("./tests/gfa2_files/subsets.gfa2").unwrap(),
+        "gfahandlegraph_subsets.hga",
+    );
+    round_trip(
+        &parse_file_to_graph("./tests/gfa2_files/placeholders.gfa2").unwrap(),
+        "gfahandlegraph_placeholders.hga",
+    );
+}
+
+#[test]
+fn incremental_parsing_builds_the_same_graph() {
+    use gfahandlegraph::parser::Parser;
+
+    let same_graph = |path: &str| {
+        let (built, unresolved) = Parser::new().parse_file_incremental(path).unwrap().finish();
+        let graph = parse_file_to_graph(path).unwrap();
+        assert!(unresolved.is_empty());
+        assert!(graph.diff(&built).is_empty(), "{}", graph.diff(&built));
+        assert_eq!(built.header, graph.header);
+        assert_eq!(built.containments(), graph.containments());
+        assert_eq!(built.subset_names(), graph.subset_names());
+        for name in graph.subset_names() {
+            assert_eq!(built.subset(name), graph.subset(name));
+        }
+        for edge in graph.edges_sorted() {
+            assert_eq!(built.edge_overlap(edge), graph.edge_overlap(edge));
+            assert_eq!(built.edge_tags(edge), graph.edge_tags(edge));
+        }
+        graph
+    };
+
+    let graph = same_graph("./tests/gfa1_files/containments.gfa");
+    assert_eq!(graph.containments().len(), 3);
+    assert!(graph.header.is_some());
+
+    let graph = same_graph("./tests/gfa1_files/conversion.gfa");
+    assert!(graph.edges_sorted().any(|e| graph.edge_overlap(e) > 0));
+
+    let graph = same_graph("./tests/gfa2_files/subsets.gfa2");
+    assert!(!graph.subset_names().is_empty());
+}
+
+#[test]
+fn containments_follow_the_reversed_and_divided_nodes() {
+    use gfahandlegraph::handlegraph::HandleSequences;
+    use gfahandlegraph::hashgraph::ContainmentRecord;
+    use gfahandlegraph::mutablehandlegraph::MutableHandleGraph;
+    use gfahandlegraph::util::dna::rev_comp_slice;
+
+    // the bases of the container covered, read on its forward strand:
+    // the overlaps of the file are matches or *
+    let covered = |graph: &HashGraph, c: &ContainmentRecord| {
+        let sequence = graph.sequence(c.container.forward());
+        let len = c.overlap.to_string().trim_end_matches('M').parse();
+        let len = len.unwrap_or_else(|_| graph.node_len(c.contained));
+        sequence[c.pos..c.pos + len].to_vec()
+    };
+
+    let graph = parse_file_to_graph("./tests/gfa1_files/containments.gfa").unwrap();
+    let opposite = graph.reverse_complement();
+    assert_eq!(opposite.containments().len(), graph.containments().len());
+    for (before, after) in graph.containments().iter().zip(opposite.containments()) {
+        assert_eq!(after.container, before.container.flip());
+        assert_eq!(after.contained, before.contained.flip());
+        assert_eq!(after.overlap, before.overlap);
+        assert_eq!(
+            covered(&opposite, after),
+            rev_comp_slice(&covered(&graph, before))
+        );
+    }
+    assert_eq!(
+        opposite.reverse_complement().containments(),
+        graph.containments()
+    );
+
+    // only the containments of the node reversed change
+    let mut oriented = graph.clone();
+    oriented
+        .apply_orientation(Handle::new(1_u64, Orientation::Backward))
+        .unwrap();
+    for (before, after) in graph.containments().iter().zip(oriented.containments()) {
+        assert_eq!(after.contained, before.contained);
+        if before.container.id() == NodeId::from(1) {
+            assert_eq!(after.container, before.container.flip());
+            assert_eq!(
+                covered(&oriented, after),
+                rev_comp_slice(&covered(&graph, before))
+            );
+        } else {
+            assert_eq!(after, before);
+        }
+    }
+
+    // C 1 + 2 + 4 4M and C 1 - 4 + 0 * move to the pieces of 1 they're
+    // on, C 3 + 4 - 1 2M stays, and a containment across 2 pieces is
+    // dropped
+    let mut divided = graph.clone();
+    let pieces = divided
+        .divide_handle(Handle::pack(1, false), vec![2, 8])
+        .unwrap();
+    let containers: Vec<(Handle, usize)> = divided
+        .containments()
+        .iter()
+        .map(|c| (c.container, c.pos))
+        .collect();
+    assert_eq!(
+        containers,
+        vec![
+            (pieces[1], 2),
+            (pieces[0].flip(), 0),
+            (Handle::pack(3, false), 1)
+        ]
+    );
+    for (before, after) in [0, 1, 2]
+        .iter()
+        .map(|ix| &graph.containments()[*ix])
+        .zip(divided.containments())
+    {
+        assert_eq!(covered(&divided, after), covered(&graph, before));
+    }
+    let mut across = graph.clone();
+    across
+        .divide_handle(Handle::pack(1, false), vec![6])
+        .unwrap();
+    assert_eq!(across.containments().len(), 2);
+    // a divided node isn't contained anymore
+    across
+        .divide_handle(Handle::pack(4, false), vec![1])
+        .unwrap();
+    assert!(across.containments().is_empty());
+}
+
+#[test]
+fn bincode_keeps_the_groups_and_the_header() {
+    let dir = std::env::temp_dir();
+    for (path, name) in [
+        (
+            "./tests/gfa1_files/containments.gfa",
+            "gfahandlegraph_containments.bin",
+        ),
+        (
+            "./tests/gfa2_files/subsets.gfa2",
+            "gfahandlegraph_subsets.bin",
+        ),
+    ]
+    .iter()
+    {
+        let graph = parse_file_to_graph(path).unwrap();
+        let file = dir.join(name);
+        graph.save_bincode(&file).unwrap();
+        let loaded = HashGraph::load_bincode(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(loaded.header, graph.header);
+        assert_eq!(loaded.containments(), graph.containments());
+        assert_eq!(loaded.subset_names(), graph.subset_names());
+        for name in graph.subset_names() {
+            assert_eq!(loaded.subset(name), graph.subset(name));
+        }
+    }
+}
+
+#[test]
+fn parser_recovers_with_a_flag() {
+    use gfahandlegraph::parser::{recover_file_to_graph, Parser};
+
+    let content = "S\t1\tACGT\n\
+                   S\t2\n\
+                   S\t3\tTT\n\
+                   L\t1\t+\t2\t+\t0M\n\
+                   L\t1\t+\t3\t+\t0M\n\
+                   P\tp\t1+,2+,3+\t0M,0M\n";
+    let path = std::env::temp_dir().join("gfahandlegraph_recover_flag.gfa");
+    std::fs::write(&path, content).unwrap();
+
+    let (graph, report) = Parser::new()
+        .recover(true)
+        .parse_file_to_graph_with_recovery(&path)
+        .unwrap();
+    let (expected, expected_report) = recover_file_to_graph(&path).unwrap();
+    assert!(graph.diff(&expected).is_empty());
+    assert_eq!(report.skipped_lines.len(), 1);
+    assert_eq!(report.skipped_lines[0].0, 2);
+    assert_eq!(report.dropped_edges, 1);
+    assert_eq!(report.dropped_path_steps, 1);
+    assert_eq!(
+        report.dropped_steps_per_path,
+        expected_report.dropped_steps_per_path
+    );
+    assert_consistent(&graph);
+
+    // the other options still apply, and without the flag the report is
+    // clean
+    let mut preserved = vec![];
+    Parser::new()
+        .recover(true)
+        .fidelity(true)
+        .parse_file_to_graph(&path)
+        .unwrap()
+        .write_gfa_preserving(&mut preserved)
+        .unwrap();
+    assert!(preserved.starts_with(b"S\t1\tACGT\n"));
+    if let Ok((_, report)) = Parser::new().parse_file_to_graph_with_recovery(&path) {
+        assert!(report.is_clean());
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn parser_reports_progress_and_drops_names() {
+    use gfahandlegraph::handlegraph::{AllEdges, AllHandles};
+    use gfahandlegraph::parser::{BuildProgress, ParseProgress, Parser, ProgressHook};
+    use std::sync::{Arc, Mutex};
+
+    let content = "H\tVN:Z:1.0\n\
+                   S\tfirst\tACGT\n\
+                   S\tsecond\tGG\n\
+                   S\t3\tTT\n\
+                   L\tfirst\t+\tsecond\t+\t0M\n\
+                   P\tp\tfirst+,second+,3+\t0M,0M\n";
+    let path = std::env::temp_dir().join("gfahandlegraph_parser_facade.gfa");
+    std::fs::write(&path, content).unwrap();
+
+    let parsed: Arc<Mutex<Vec<ParseProgress>>> = Arc::new(Mutex::new(vec![]));
+    let built: Arc<Mutex<Vec<BuildProgress>>> = Arc::new(Mutex::new(vec![]));
+    let (found, added) = (parsed.clone(), built.clone());
+    let graph = Parser::new()
+        .progress_every(2)
+        .on_progress(Box::new(move |p| found.lock().unwrap().push(p)))
+        .on_build_progress(ProgressHook::new(move |p| added.lock().unwrap().push(p)))
+        .parse_file_to_graph(&path)
+        .unwrap();
+    let last = *parsed.lock().unwrap().last().unwrap();
+    assert_eq!(last.lines_read, 6);
+    assert_eq!((last.segments, last.edges, last.paths), (3, 1, 1));
+    assert_eq!(parsed.lock().unwrap().len(), 6 / 2 + 1);
+    let last = *built.lock().unwrap().last().unwrap();
+    assert_eq!((last.segments, last.edges, last.paths), (3, 1, 1));
+    assert!(graph.node_id_of_name(b"first").is_some());
+
+    // the incremental parser reports the lines it reads one by one
+    parsed.lock().unwrap().clear();
+    let found = parsed.clone();
+    let (incremental, _) = Parser::new()
+        .on_progress(Box::new(move |p| found.lock().unwrap().push(p)))
+        .parse_file_incremental(&path)
+        .unwrap()
+        .finish();
+    assert_eq!(parsed.lock().unwrap().last().unwrap().lines_read, 6);
+    assert!(incremental.diff(&graph).is_empty());
+
+    // without the names the nodes are known by their ids only, with
+    // every parser
+    let unnamed = Parser::new()
+        .keep_names(false)
+        .parse_file_to_graph(&path)
+        .unwrap();
+    let (streamed, _) = Parser::new()
+        .keep_names(false)
+        .parse_file_incremental(&path)
+        .unwrap()
+        .finish();
+    let recovered = Parser::new()
+        .keep_names(false)
+        .recover(true)
+        .parse_file_to_graph(&path)
+        .unwrap();
+    for graph in [&unnamed, &streamed, &recovered].iter() {
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 1);
+        assert!(graph.node_id_of_name(b"first").is_none());
+        assert!(graph.node_name(NodeId::from(3)).is_none());
+        assert_eq!(graph.node_id_of_name(b"3"), Some(NodeId::from(3)));
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn retagging_moves_the_generation() {
+    use gfahandlegraph::gfa::optional::OptField;
+
+    let mut graph = parse_file_to_graph("./tests/gfa1_files/lil.gfa").unwrap();
+    let edge = Edge(Handle::pack(1, false), Handle::pack(2, false));
+    let dp = OptField::new(b"DP", b'f', b"2.5");
+    let rc = OptField::new(b"RC", b'i', b"3");
+    let counters = |g: &HashGraph| (g.generation(), g.tag_generation(), g.topology_generation());
+    let start = counters(&graph);
+
+    graph.set_node_tag(NodeId::from(1), dp.clone()).unwrap();
+    graph.set_edge_tag(edge, rc).unwrap();
+    assert_eq!(graph.remove_node_tag(NodeId::from(1), b"DP"), Some(dp));
+    assert!(graph.remove_edge_tag(edge, b"RC").is_some());
+    let retagged = counters(&graph);
+    assert_eq!(retagged, (start.0 + 4, start.1 + 4, start.2));
+
+    // what fails, or removes nothing, doesn't change the graph
+    let missing = OptField::new(b"DP", b'f', b"1");
+    assert!(graph
+        .set_node_tag(NodeId::from(100), missing.clone())
+        .is_err());
+    let absent = Edge(Handle::pack(2, false), Handle::pack(1, false));
+    assert!(graph.set_edge_tag(absent, missing).is_err());
+    assert_eq!(graph.remove_node_tag(NodeId::from(1), b"DP"), None);
+    assert_eq!(graph.remove_edge_tag(edge, b"RC"), None);
+    assert_eq!(counters(&graph), retagged);
+}
+
+#[test]
+fn explain_error_names_the_nodes_of_a_batch() {
+    use gfahandlegraph::handlegraph::GraphError;
+    use gfahandlegraph::parser::{parse_reader_to_graph, GfaFormat};
+    use gfahandlegraph::util::report::ReportItem;
+
+    let text = "S\tfirst\tACGT\nS\t2\tGG\n";
+    let mut graph = parse_reader_to_graph(text.as_bytes(), GfaFormat::Gfa1).unwrap();
+    let first = graph.node_id_of_name(b"first").unwrap();
+
+    let mut edit = graph.bulk_edit();
+    edit.add_node(9, b"A");
+    edit.add_node(first, b"T");
+    let why = edit.commit().unwrap_err();
+    match &why {
+        GraphError::InvalidBatchRecord(at, rejected) => {
+            assert_eq!(at, "node 1");
+            assert!(
+                matches!(**rejected, GraphError::IdAlreadyExist(ref id) if *id == first.to_string())
+            );
+        }
+        _ => panic!("duplicated node not detected"),
+    }
+    assert_eq!(
+        graph.explain_error(&why),
+        "The Id provided (first) already exists (node 1)"
+    );
+    assert_eq!(
+        why.to_string(),
+        format!("The Id provided ({}) already exists (node 1)", first)
+    );
+    // the report tells the node apart from where the record is
+    let item = ReportItem::from(&why);
+    assert_eq!(item.code, "G020");
+    assert_eq!(item.node, Some(first.to_string()));
+
+    let mut edit = graph.bulk_edit();
+    edit.add_path(b"x", &[Handle::pack(2, false), Handle::pack(7, false)]);
+    let why = edit.commit().unwrap_err();
+    assert_eq!(
+        graph.explain_error(&why),
+        "Cannot find the node: 7 (path 0, step 1)"
+    );
+}